@@ -5,29 +5,41 @@ use std::ops::{AddAssign, Sub};
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    ensure, ensure_ne, to_binary, BankMsg, Binary, Coin, CosmosMsg, Deps, DepsMut, DistributionMsg,
-    Empty, Env, MessageInfo, Order, Response, StakingMsg, StdResult,
+    ensure, ensure_ne, from_binary, to_binary, Addr, BankMsg, Binary, BlockInfo, Coin, CosmosMsg,
+    Deps, DepsMut, DistributionMsg, Empty, Env, IbcMsg, MessageInfo, Order, Reply, Response,
+    StakingMsg, StdError, StdResult, Storage, Uint128, WasmMsg,
 };
-use cw1::CanExecuteResponse;
+use cw1::{CanExecuteResponse, ExecuteMode};
 use cw1_whitelist::{
     contract::{
-        execute_freeze, execute_update_admins, instantiate as whitelist_instantiate,
-        query_admin_list,
+        dispatch_msgs, execute_freeze, execute_unfreeze, execute_update_admins,
+        instantiate as whitelist_instantiate, query_admin_list, reply as whitelist_reply,
     },
     msg::InstantiateMsg,
     state::ADMIN_LIST,
 };
 use cw2::{get_contract_version, set_contract_version};
+use cw20::Cw20ExecuteMsg;
 use cw_storage_plus::Bound;
-use cw_utils::Expiration;
+use cw_utils::{Duration, Expiration, NativeBalance, Scheduled};
 use semver::Version;
 
 use crate::error::ContractError;
 use crate::msg::{
-    AllAllowancesResponse, AllPermissionsResponse, AllowanceInfo, ExecuteMsg, PermissionsInfo,
-    QueryMsg,
+    AllAllowancesResponse, AllAllowedMessagesResponse, AllCw20AllowancesResponse,
+    AllPermissionsResponse, AllSubkeysResponse, AllowanceInfo, AllowanceTopUpInfo,
+    AllowanceTopUpResponse, AllowedMessagesResponse, Cw20AllowanceInfo, ExecuteMsg,
+    HistoryRetentionResponse, IbcChannelLimitInfo, IbcChannelLimitsResponse, PermissionsInfo,
+    QueryMsg, RecurringAllowanceInfo, RecurringAllowanceResponse, SpendHistoryResponse,
+    SpendRecordInfo, SubkeyFilter, SubkeyInfo,
+};
+use crate::state::{
+    Allowance, AllowanceTopUp, Cw20Allowance, Deduction, MessageType, PendingDebit, Permissions,
+    RecurringAllowance, SpendHistoryBounds, SpendRecord, ALLOWANCES, ALLOWANCE_TOP_UPS,
+    ALLOWED_MESSAGES, CW20_ALLOWANCES, DEFAULT_HISTORY_RETENTION, HISTORY_RETENTION,
+    IBC_CHANNEL_LIMITS, LEGACY_PERMISSIONS, PENDING_DEBITS, PERMISSIONS, RECURRING_ALLOWANCES,
+    SPEND_HISTORY, SPEND_HISTORY_BOUNDS,
 };
-use crate::state::{Allowance, Permissions, ALLOWANCES, PERMISSIONS};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:cw1-subkeys";
@@ -55,8 +67,9 @@ pub fn execute(
     msg: ExecuteMsg<Empty>,
 ) -> Result<Response<Empty>, ContractError> {
     match msg {
-        ExecuteMsg::Execute { msgs } => execute_execute(deps, env, info, msgs),
-        ExecuteMsg::Freeze {} => Ok(execute_freeze(deps, env, info)?),
+        ExecuteMsg::Execute { msgs, mode } => execute_execute(deps, env, info, msgs, mode),
+        ExecuteMsg::Freeze { until } => Ok(execute_freeze(deps, env, info, until)?),
+        ExecuteMsg::Unfreeze {} => Ok(execute_unfreeze(deps, env, info)?),
         ExecuteMsg::UpdateAdmins { admins } => Ok(execute_update_admins(deps, env, info, admins)?),
         ExecuteMsg::IncreaseAllowance {
             spender,
@@ -68,10 +81,53 @@ pub fn execute(
             amount,
             expires,
         } => execute_decrease_allowance(deps, env, info, spender, amount, expires),
+        ExecuteMsg::IncreaseCw20Allowance {
+            spender,
+            token,
+            amount,
+            expires,
+        } => execute_increase_cw20_allowance(deps, env, info, spender, token, amount, expires),
+        ExecuteMsg::DecreaseCw20Allowance {
+            spender,
+            token,
+            amount,
+            expires,
+        } => execute_decrease_cw20_allowance(deps, env, info, spender, token, amount, expires),
+        ExecuteMsg::SetRecurringAllowance {
+            spender,
+            amount,
+            period,
+        } => execute_set_recurring_allowance(deps, env, info, spender, amount, period),
+        ExecuteMsg::RemoveRecurringAllowance { spender } => {
+            execute_remove_recurring_allowance(deps, env, info, spender)
+        }
+        ExecuteMsg::SetIbcChannelLimit {
+            spender,
+            channel_id,
+            limit,
+        } => execute_set_ibc_channel_limit(deps, env, info, spender, channel_id, limit),
+        ExecuteMsg::SetAllowanceTopUp {
+            spender,
+            amount,
+            period,
+            cap,
+        } => execute_set_allowance_top_up(deps, env, info, spender, amount, period, cap),
+        ExecuteMsg::RemoveAllowanceTopUp { spender } => {
+            execute_remove_allowance_top_up(deps, env, info, spender)
+        }
         ExecuteMsg::SetPermissions {
             spender,
             permissions,
         } => execute_set_permissions(deps, env, info, spender, permissions),
+        ExecuteMsg::SetAllowedMessages { spender, messages } => {
+            execute_set_allowed_messages(deps, env, info, spender, messages)
+        }
+        ExecuteMsg::SetHistoryRetention { retention } => {
+            execute_set_history_retention(deps, env, info, retention)
+        }
+        ExecuteMsg::PruneSpendHistory { spender, limit } => {
+            execute_prune_spend_history(deps, env, info, spender, limit)
+        }
     }
 }
 
@@ -80,6 +136,7 @@ pub fn execute_execute<T>(
     env: Env,
     info: MessageInfo,
     msgs: Vec<CosmosMsg<T>>,
+    mode: ExecuteMode,
 ) -> Result<Response<T>, ContractError>
 where
     T: Clone + fmt::Debug + PartialEq + JsonSchema,
@@ -88,7 +145,24 @@ where
 
     // Not an admin - need to check for permissions
     if !cfg.is_admin(info.sender.as_ref()) {
-        for msg in &msgs {
+        apply_top_up(deps.storage, &info.sender, &env.block)?;
+        let allowed = ALLOWED_MESSAGES
+            .may_load(deps.storage, &info.sender)?
+            .unwrap_or_default();
+        let mut spends = vec![];
+        for (index, msg) in msgs.iter().enumerate() {
+            let mut debit = PendingDebit {
+                spender: info.sender.clone(),
+                funds: None,
+                cw20: None,
+                ibc_channel: None,
+                spend: spend_of(msg, &env.block),
+            };
+            // default-deny: only message types on the allowlist may be relayed
+            ensure!(
+                allowed.iter().any(|entry| entry.covers(msg)),
+                ContractError::MessageTypeRejected {}
+            );
             match msg {
                 CosmosMsg::Staking(staking_msg) => {
                     let perm = PERMISSIONS.may_load(deps.storage, &info.sender)?;
@@ -104,30 +178,292 @@ where
                     to_address: _,
                     amount,
                 }) => {
-                    ALLOWANCES.update::<_, ContractError>(deps.storage, &info.sender, |allow| {
-                        let mut allowance = allow.ok_or(ContractError::NoAllowance {})?;
-                        ensure!(
-                            !allowance.expires.is_expired(&env.block),
-                            ContractError::NoAllowance {}
-                        );
-
-                        // Decrease allowance
-                        allowance.balance = allowance.balance.sub(amount.clone())?;
-                        Ok(allowance)
-                    })?;
+                    debit.funds = Some(deduct_allowance(
+                        deps.storage,
+                        &info.sender,
+                        &env.block,
+                        amount,
+                    )?);
+                }
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr,
+                    msg,
+                    funds,
+                }) => {
+                    if !funds.is_empty() {
+                        debit.funds = Some(deduct_allowance(
+                            deps.storage,
+                            &info.sender,
+                            &env.block,
+                            funds,
+                        )?);
+                    }
+                    if let Some(amount) = cw20_spent(msg) {
+                        let token = deps.api.addr_validate(contract_addr)?;
+                        let key = (&info.sender, &token);
+                        let allowance = CW20_ALLOWANCES
+                            .may_load(deps.storage, key)?
+                            .filter(|allow| !allow.expires.is_expired(&env.block))
+                            .ok_or(ContractError::NoAllowance {})?;
+                        let allowance = Cw20Allowance {
+                            amount: allowance
+                                .amount
+                                .checked_sub(amount)
+                                .map_err(StdError::from)?,
+                            ..allowance
+                        };
+                        CW20_ALLOWANCES.save(deps.storage, key, &allowance)?;
+                        debit.cw20 = Some((token, amount));
+                    }
+                }
+                CosmosMsg::Ibc(IbcMsg::Transfer {
+                    channel_id, amount, ..
+                }) => {
+                    let perm = PERMISSIONS.may_load(deps.storage, &info.sender)?;
+                    let perm = perm.ok_or(ContractError::NotAllowed {})?;
+                    ensure!(perm.ibc_transfer, ContractError::IbcTransferPerm {});
+                    let key = (&info.sender, channel_id.as_str());
+                    if let Some(remaining) = IBC_CHANNEL_LIMITS.may_load(deps.storage, key)? {
+                        let remaining = remaining.sub(amount.clone()).map_err(|_| {
+                            ContractError::IbcChannelLimitExceeded {
+                                channel: channel_id.clone(),
+                            }
+                        })?;
+                        IBC_CHANNEL_LIMITS.save(deps.storage, key, &remaining)?;
+                        debit.ibc_channel = Some((channel_id.clone(), amount.clone()));
+                    }
+                    debit.funds = Some(deduct_allowance(
+                        deps.storage,
+                        &info.sender,
+                        &env.block,
+                        std::slice::from_ref(amount),
+                    )?);
                 }
                 _ => {
                     return Err(ContractError::MessageTypeRejected {});
                 }
             }
+            // a best-effort message is only recorded if it succeeds, and gives back what it took
+            // otherwise, see `reply`
+            match mode {
+                ExecuteMode::Atomic => spends.push(debit.spend),
+                ExecuteMode::BestEffort => {
+                    PENDING_DEBITS.save(deps.storage, index as u64, &debit)?
+                }
+            }
         }
+        record_spends(deps.storage, &info.sender, spends)?;
     }
     // Relay messages
     let res = Response::new()
-        .add_messages(msgs)
         .add_attribute("action", "execute")
         .add_attribute("owner", info.sender);
-    Ok(res)
+    Ok(dispatch_msgs(res, msgs, mode))
+}
+
+/// Adds the top-ups due to the allowance of the subkey
+fn apply_top_up(storage: &mut dyn Storage, spender: &Addr, block: &BlockInfo) -> StdResult<()> {
+    if let Some(mut top_up) = ALLOWANCE_TOP_UPS.may_load(storage, spender)? {
+        let mut allowance = ALLOWANCES.may_load(storage, spender)?.unwrap_or_default();
+        if top_up.apply(&mut allowance, block)? {
+            ALLOWANCES.save(storage, spender, &allowance)?;
+            ALLOWANCE_TOP_UPS.save(storage, spender, &top_up)?;
+        }
+    }
+    Ok(())
+}
+
+/// The unexpired allowance of the subkey, including the top-ups due which are not applied yet
+fn current_allowance(
+    storage: &dyn Storage,
+    spender: &Addr,
+    block: &BlockInfo,
+) -> StdResult<Option<Allowance>> {
+    let mut allowance = ALLOWANCES.may_load(storage, spender)?;
+    if let Some(mut top_up) = ALLOWANCE_TOP_UPS.may_load(storage, spender)? {
+        let mut topped_up = allowance.clone().unwrap_or_default();
+        if top_up.apply(&mut topped_up, block)? {
+            allowance = Some(topped_up);
+        }
+    }
+    Ok(allowance.filter(|allow| !allow.expires.is_expired(block)))
+}
+
+/// The spend record of a relayed message: whom it targets, and the funds leaving the proxy with it
+fn spend_of<T>(msg: &CosmosMsg<T>, block: &BlockInfo) -> SpendRecord {
+    let (target, funds) = match msg {
+        CosmosMsg::Bank(BankMsg::Send { to_address, amount }) => {
+            (to_address.clone(), amount.clone())
+        }
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr,
+            funds,
+            ..
+        }) => (contract_addr.clone(), funds.clone()),
+        CosmosMsg::Ibc(IbcMsg::Transfer {
+            channel_id, amount, ..
+        }) => (channel_id.clone(), vec![amount.clone()]),
+        CosmosMsg::Staking(StakingMsg::Delegate { validator, amount }) => {
+            (validator.clone(), vec![amount.clone()])
+        }
+        CosmosMsg::Staking(StakingMsg::Undelegate { validator, .. }) => (validator.clone(), vec![]),
+        CosmosMsg::Staking(StakingMsg::Redelegate { dst_validator, .. }) => {
+            (dst_validator.clone(), vec![])
+        }
+        CosmosMsg::Distribution(DistributionMsg::SetWithdrawAddress { address }) => {
+            (address.clone(), vec![])
+        }
+        CosmosMsg::Distribution(DistributionMsg::WithdrawDelegatorReward { validator }) => {
+            (validator.clone(), vec![])
+        }
+        _ => (String::new(), vec![]),
+    };
+    SpendRecord {
+        time: block.time,
+        target,
+        funds,
+    }
+}
+
+/// Number of old records pruned when a new one is added, so lowering the retention does not make
+/// a single execution prune the whole excess
+const PRUNE_ON_RECORD: u64 = 2;
+
+/// Appends the spends of messages relayed by a subkey to its spend history
+fn record_spends(
+    storage: &mut dyn Storage,
+    spender: &Addr,
+    spends: Vec<SpendRecord>,
+) -> StdResult<()> {
+    let retention = HISTORY_RETENTION
+        .may_load(storage)?
+        .unwrap_or(DEFAULT_HISTORY_RETENTION);
+    if retention == 0 || spends.is_empty() {
+        return Ok(());
+    }
+
+    let mut bounds = SPEND_HISTORY_BOUNDS
+        .may_load(storage, spender)?
+        .unwrap_or_default();
+    let count = spends.len() as u64;
+    for record in spends {
+        SPEND_HISTORY.save(storage, (spender, bounds.next), &record)?;
+        bounds.next += 1;
+    }
+    prune_history(
+        storage,
+        spender,
+        &mut bounds,
+        retention,
+        PRUNE_ON_RECORD * count,
+    );
+    SPEND_HISTORY_BOUNDS.save(storage, spender, &bounds)
+}
+
+/// Removes up to `limit` of the oldest records over the retention, returning how many were removed
+fn prune_history(
+    storage: &mut dyn Storage,
+    spender: &Addr,
+    bounds: &mut SpendHistoryBounds,
+    retention: u32,
+    limit: u64,
+) -> u64 {
+    let excess = (bounds.next - bounds.oldest).saturating_sub(retention.into());
+    let pruned = excess.min(limit);
+    for id in bounds.oldest..bounds.oldest + pruned {
+        SPEND_HISTORY.remove(storage, (spender, id));
+    }
+    bounds.oldest += pruned;
+    pruned
+}
+
+/// Takes `amount` from the allowances of the subkey, returning which one covered it
+fn deduct_allowance(
+    storage: &mut dyn Storage,
+    spender: &Addr,
+    block: &BlockInfo,
+    amount: &[Coin],
+) -> Result<Deduction, ContractError> {
+    // the recurring allowance is spent first, as it is refilled every period
+    let mut recurring_err = None;
+    if let Some(recurring) = RECURRING_ALLOWANCES.may_load(storage, spender)? {
+        let mut recurring = recurring.current(block)?;
+        match recurring.remaining.clone().sub(amount.to_vec()) {
+            Ok(remaining) => {
+                recurring.remaining = remaining;
+                RECURRING_ALLOWANCES.save(storage, spender, &recurring)?;
+                return Ok(Deduction::Recurring(amount.to_vec()));
+            }
+            Err(err) => recurring_err = Some(err),
+        }
+    }
+
+    let allowance = ALLOWANCES
+        .may_load(storage, spender)?
+        .filter(|allow| !allow.expires.is_expired(block));
+    match (allowance, recurring_err) {
+        (Some(mut allowance), _) => {
+            // Decrease allowance
+            allowance.balance = allowance.balance.sub(amount.to_vec())?;
+            ALLOWANCES.save(storage, spender, &allowance)?;
+            Ok(Deduction::OneShot(amount.to_vec()))
+        }
+        // without a one-shot allowance, report what the recurring one lacks
+        (None, Some(err)) => Err(err.into()),
+        (None, None) => Err(ContractError::NoAllowance {}),
+    }
+}
+
+/// Gives back to the allowances and channel limits of the subkey what a failed message took from
+/// them, unless they were removed since
+fn refund_debit(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    debit: PendingDebit,
+) -> StdResult<()> {
+    let spender = &debit.spender;
+    match debit.funds {
+        Some(Deduction::Recurring(amount)) => {
+            if let Some(recurring) = RECURRING_ALLOWANCES.may_load(storage, spender)? {
+                let mut recurring = recurring.current(block)?;
+                recurring.remaining += NativeBalance(amount);
+                RECURRING_ALLOWANCES.save(storage, spender, &recurring)?;
+            }
+        }
+        Some(Deduction::OneShot(amount)) => {
+            if let Some(mut allowance) = ALLOWANCES.may_load(storage, spender)? {
+                allowance.balance += NativeBalance(amount);
+                ALLOWANCES.save(storage, spender, &allowance)?;
+            }
+        }
+        None => {}
+    }
+    if let Some((token, amount)) = debit.cw20 {
+        let key = (spender, &token);
+        if let Some(mut allowance) = CW20_ALLOWANCES.may_load(storage, key)? {
+            allowance.amount += amount;
+            CW20_ALLOWANCES.save(storage, key, &allowance)?;
+        }
+    }
+    if let Some((channel, amount)) = debit.ibc_channel {
+        let key = (spender, channel.as_str());
+        if let Some(mut remaining) = IBC_CHANNEL_LIMITS.may_load(storage, key)? {
+            remaining += amount;
+            IBC_CHANNEL_LIMITS.save(storage, key, &remaining)?;
+        }
+    }
+    Ok(())
+}
+
+/// The amount of cw20 tokens a wasm execute message transfers out of the proxy, if it is a cw20
+/// `Transfer` or `Send`
+fn cw20_spent(msg: &Binary) -> Option<Uint128> {
+    match from_binary(msg).ok()? {
+        Cw20ExecuteMsg::Transfer { amount, .. } | Cw20ExecuteMsg::Send { amount, .. } => {
+            Some(amount)
+        }
+        _ => None,
+    }
 }
 
 pub fn check_staking_permissions(
@@ -155,7 +491,10 @@ pub fn check_distribution_permissions(
 ) -> Result<(), ContractError> {
     match distribution_msg {
         DistributionMsg::SetWithdrawAddress { .. } => {
-            ensure!(permissions.withdraw, ContractError::WithdrawAddrPerm {});
+            ensure!(
+                permissions.set_withdraw_address,
+                ContractError::WithdrawAddrPerm {}
+            );
         }
         DistributionMsg::WithdrawDelegatorReward { .. } => {
             ensure!(permissions.withdraw, ContractError::WithdrawPerm {});
@@ -272,12 +611,15 @@ where
     Ok(res)
 }
 
-pub fn execute_set_permissions<T>(
+#[allow(clippy::too_many_arguments)]
+pub fn execute_increase_cw20_allowance<T>(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     spender: String,
-    perm: Permissions,
+    token: String,
+    amount: Uint128,
+    expires: Option<Expiration>,
 ) -> Result<Response<T>, ContractError>
 where
     T: Clone + fmt::Debug + PartialEq + JsonSchema,
@@ -291,118 +633,778 @@ where
         spender_addr,
         ContractError::CannotSetOwnAccount {}
     );
-    PERMISSIONS.save(deps.storage, &spender_addr, &perm)?;
+    let token_addr = deps.api.addr_validate(&token)?;
+
+    CW20_ALLOWANCES.update::<_, ContractError>(
+        deps.storage,
+        (&spender_addr, &token_addr),
+        |allow| {
+            let prev_expires = allow
+                .as_ref()
+                .map(|allow| allow.expires)
+                .unwrap_or_default();
+
+            let mut allowance = allow
+                .filter(|allow| !allow.expires.is_expired(&env.block))
+                .unwrap_or_default();
+
+            if let Some(exp) = expires {
+                if exp.is_expired(&env.block) {
+                    return Err(ContractError::SettingExpiredAllowance(exp));
+                }
+
+                allowance.expires = exp;
+            } else if prev_expires.is_expired(&env.block) {
+                return Err(ContractError::SettingExpiredAllowance(prev_expires));
+            }
+
+            allowance.amount += amount;
+            Ok(allowance)
+        },
+    )?;
 
     let res = Response::new()
-        .add_attribute("action", "set_permissions")
+        .add_attribute("action", "increase_cw20_allowance")
         .add_attribute("owner", info.sender)
         .add_attribute("spender", spender)
-        .add_attribute("permissions", perm.to_string());
+        .add_attribute("token", token)
+        .add_attribute("amount", amount);
     Ok(res)
 }
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::AdminList {} => to_binary(&query_admin_list(deps)?),
-        QueryMsg::Allowance { spender } => to_binary(&query_allowance(deps, env, spender)?),
-        QueryMsg::Permissions { spender } => to_binary(&query_permissions(deps, spender)?),
-        QueryMsg::CanExecute { sender, msg } => {
-            to_binary(&query_can_execute(deps, env, sender, msg)?)
-        }
-        QueryMsg::AllAllowances { start_after, limit } => {
-            to_binary(&query_all_allowances(deps, env, start_after, limit)?)
-        }
-        QueryMsg::AllPermissions { start_after, limit } => {
-            to_binary(&query_all_permissions(deps, start_after, limit)?)
+#[allow(clippy::too_many_arguments)]
+pub fn execute_decrease_cw20_allowance<T>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    spender: String,
+    token: String,
+    amount: Uint128,
+    expires: Option<Expiration>,
+) -> Result<Response<T>, ContractError>
+where
+    T: Clone + fmt::Debug + PartialEq + JsonSchema,
+{
+    let cfg = ADMIN_LIST.load(deps.storage)?;
+    ensure!(cfg.is_admin(&info.sender), ContractError::Unauthorized {});
+
+    let spender_addr = deps.api.addr_validate(&spender)?;
+    ensure_ne!(
+        info.sender,
+        spender_addr,
+        ContractError::CannotSetOwnAccount {}
+    );
+    let token_addr = deps.api.addr_validate(&token)?;
+    let key = (&spender_addr, &token_addr);
+
+    let allowance = CW20_ALLOWANCES.update::<_, ContractError>(deps.storage, key, |allow| {
+        let mut allowance = allow
+            .filter(|allow| !allow.expires.is_expired(&env.block))
+            .ok_or(ContractError::NoAllowance {})?;
+
+        if let Some(exp) = expires {
+            if exp.is_expired(&env.block) {
+                return Err(ContractError::SettingExpiredAllowance(exp));
+            }
+
+            allowance.expires = exp;
         }
-    }
-}
 
-// if the subkey has no allowance, return an empty struct (not an error)
-pub fn query_allowance(deps: Deps, env: Env, spender: String) -> StdResult<Allowance> {
-    // we can use unchecked here as it is a query - bad value means a miss, we never write it
-    let spender = deps.api.addr_validate(&spender)?;
-    let allow = ALLOWANCES
-        .may_load(deps.storage, &spender)?
-        .filter(|allow| !allow.expires.is_expired(&env.block))
-        .unwrap_or_default();
+        allowance.amount = allowance.amount.saturating_sub(amount);
+        Ok(allowance)
+    })?;
 
-    Ok(allow)
-}
+    if allowance.amount.is_zero() {
+        CW20_ALLOWANCES.remove(deps.storage, key);
+    }
 
-// if the subkey has no permissions, return an empty struct (not an error)
-pub fn query_permissions(deps: Deps, spender: String) -> StdResult<Permissions> {
-    let spender = deps.api.addr_validate(&spender)?;
-    let permissions = PERMISSIONS
-        .may_load(deps.storage, &spender)?
-        .unwrap_or_default();
-    Ok(permissions)
+    let res = Response::new()
+        .add_attribute("action", "decrease_cw20_allowance")
+        .add_attribute("owner", info.sender)
+        .add_attribute("spender", spender)
+        .add_attribute("token", token)
+        .add_attribute("amount", amount);
+    Ok(res)
 }
 
-fn query_can_execute(
-    deps: Deps,
+pub fn execute_set_recurring_allowance<T>(
+    deps: DepsMut,
     env: Env,
-    sender: String,
-    msg: CosmosMsg,
-) -> StdResult<CanExecuteResponse> {
-    Ok(CanExecuteResponse {
-        can_execute: can_execute(deps, env, sender, msg)?,
-    })
-}
-
-// this can just return booleans and the query_can_execute wrapper creates the struct once, not on every path
-fn can_execute(deps: Deps, env: Env, sender: String, msg: CosmosMsg) -> StdResult<bool> {
+    info: MessageInfo,
+    spender: String,
+    amount: Vec<Coin>,
+    period: Duration,
+) -> Result<Response<T>, ContractError>
+where
+    T: Clone + fmt::Debug + PartialEq + JsonSchema,
+{
     let cfg = ADMIN_LIST.load(deps.storage)?;
-    if cfg.is_admin(&sender) {
-        return Ok(true);
-    }
+    ensure!(cfg.is_admin(&info.sender), ContractError::Unauthorized {});
 
-    let sender = deps.api.addr_validate(&sender)?;
-    match msg {
-        CosmosMsg::Bank(BankMsg::Send { amount, .. }) => {
-            // now we check if there is enough allowance for this message
-            let allowance = ALLOWANCES.may_load(deps.storage, &sender)?;
-            match allowance {
-                // if there is an allowance, we subtract the requested amount to ensure it is covered (error on underflow)
-                Some(allow) => {
-                    Ok(!allow.expires.is_expired(&env.block) && allow.balance.sub(amount).is_ok())
-                }
-                None => Ok(false),
-            }
-        }
-        CosmosMsg::Staking(staking_msg) => {
-            let perm_opt = PERMISSIONS.may_load(deps.storage, &sender)?;
-            match perm_opt {
-                Some(permission) => Ok(check_staking_permissions(&staking_msg, permission).is_ok()),
-                None => Ok(false),
-            }
-        }
-        CosmosMsg::Distribution(distribution_msg) => {
-            let perm_opt = PERMISSIONS.may_load(deps.storage, &sender)?;
-            match perm_opt {
-                Some(permission) => {
-                    Ok(check_distribution_permissions(&distribution_msg, permission).is_ok())
-                }
-                None => Ok(false),
-            }
-        }
-        _ => Ok(false),
-    }
+    let spender_addr = deps.api.addr_validate(&spender)?;
+    ensure_ne!(
+        info.sender,
+        spender_addr,
+        ContractError::CannotSetOwnAccount {}
+    );
+
+    let mut amount = NativeBalance(amount);
+    amount.normalize();
+    ensure!(
+        !amount.is_empty() && period != Duration::Height(0) && period != Duration::Time(0),
+        ContractError::InvalidRecurringAllowance {}
+    );
+    let recurring = RecurringAllowance::new(amount, period, &env.block);
+    RECURRING_ALLOWANCES.save(deps.storage, &spender_addr, &recurring)?;
+
+    let res = Response::new()
+        .add_attribute("action", "set_recurring_allowance")
+        .add_attribute("owner", info.sender)
+        .add_attribute("spender", spender)
+        .add_attribute("period", period.to_string());
+    Ok(res)
 }
 
-const MAX_LIMIT: u32 = 30;
-const DEFAULT_LIMIT: u32 = 10;
+pub fn execute_remove_recurring_allowance<T>(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    spender: String,
+) -> Result<Response<T>, ContractError>
+where
+    T: Clone + fmt::Debug + PartialEq + JsonSchema,
+{
+    let cfg = ADMIN_LIST.load(deps.storage)?;
+    ensure!(cfg.is_admin(&info.sender), ContractError::Unauthorized {});
 
-fn calc_limit(request: Option<u32>) -> usize {
-    request.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize
+    let spender_addr = deps.api.addr_validate(&spender)?;
+    RECURRING_ALLOWANCES.remove(deps.storage, &spender_addr);
+
+    let res = Response::new()
+        .add_attribute("action", "remove_recurring_allowance")
+        .add_attribute("owner", info.sender)
+        .add_attribute("spender", spender);
+    Ok(res)
 }
 
-// return a list of all allowances here
-pub fn query_all_allowances(
-    deps: Deps,
+pub fn execute_set_allowance_top_up<T>(
+    deps: DepsMut,
     env: Env,
-    start_after: Option<String>,
+    info: MessageInfo,
+    spender: String,
+    amount: Vec<Coin>,
+    period: Duration,
+    cap: Vec<Coin>,
+) -> Result<Response<T>, ContractError>
+where
+    T: Clone + fmt::Debug + PartialEq + JsonSchema,
+{
+    let cfg = ADMIN_LIST.load(deps.storage)?;
+    ensure!(cfg.is_admin(&info.sender), ContractError::Unauthorized {});
+
+    let spender_addr = deps.api.addr_validate(&spender)?;
+    ensure_ne!(
+        info.sender,
+        spender_addr,
+        ContractError::CannotSetOwnAccount {}
+    );
+
+    let mut amount = NativeBalance(amount);
+    amount.normalize();
+    let mut cap = NativeBalance(cap);
+    cap.normalize();
+    ensure!(
+        !amount.is_empty()
+            && period != Duration::Height(0)
+            && period != Duration::Time(0)
+            && amount.0.iter().all(|coin| cap.has(coin)),
+        ContractError::InvalidAllowanceTopUp {}
+    );
+    let top_up = AllowanceTopUp::new(amount, period, cap, &env.block)?;
+    ALLOWANCE_TOP_UPS.save(deps.storage, &spender_addr, &top_up)?;
+
+    let res = Response::new()
+        .add_attribute("action", "set_allowance_top_up")
+        .add_attribute("owner", info.sender)
+        .add_attribute("spender", spender)
+        .add_attribute("period", period.to_string());
+    Ok(res)
+}
+
+pub fn execute_remove_allowance_top_up<T>(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    spender: String,
+) -> Result<Response<T>, ContractError>
+where
+    T: Clone + fmt::Debug + PartialEq + JsonSchema,
+{
+    let cfg = ADMIN_LIST.load(deps.storage)?;
+    ensure!(cfg.is_admin(&info.sender), ContractError::Unauthorized {});
+
+    let spender_addr = deps.api.addr_validate(&spender)?;
+    ALLOWANCE_TOP_UPS.remove(deps.storage, &spender_addr);
+
+    let res = Response::new()
+        .add_attribute("action", "remove_allowance_top_up")
+        .add_attribute("owner", info.sender)
+        .add_attribute("spender", spender);
+    Ok(res)
+}
+
+pub fn execute_set_ibc_channel_limit<T>(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    spender: String,
+    channel_id: String,
+    limit: Option<Vec<Coin>>,
+) -> Result<Response<T>, ContractError>
+where
+    T: Clone + fmt::Debug + PartialEq + JsonSchema,
+{
+    let cfg = ADMIN_LIST.load(deps.storage)?;
+    ensure!(cfg.is_admin(&info.sender), ContractError::Unauthorized {});
+
+    let spender_addr = deps.api.addr_validate(&spender)?;
+    ensure_ne!(
+        info.sender,
+        spender_addr,
+        ContractError::CannotSetOwnAccount {}
+    );
+
+    let key = (&spender_addr, channel_id.as_str());
+    match limit {
+        Some(limit) => {
+            let mut limit = NativeBalance(limit);
+            limit.normalize();
+            IBC_CHANNEL_LIMITS.save(deps.storage, key, &limit)?;
+        }
+        None => IBC_CHANNEL_LIMITS.remove(deps.storage, key),
+    }
+
+    let res = Response::new()
+        .add_attribute("action", "set_ibc_channel_limit")
+        .add_attribute("owner", info.sender)
+        .add_attribute("spender", spender)
+        .add_attribute("channel_id", channel_id);
+    Ok(res)
+}
+
+pub fn execute_set_history_retention<T>(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    retention: u32,
+) -> Result<Response<T>, ContractError>
+where
+    T: Clone + fmt::Debug + PartialEq + JsonSchema,
+{
+    let cfg = ADMIN_LIST.load(deps.storage)?;
+    ensure!(cfg.is_admin(&info.sender), ContractError::Unauthorized {});
+
+    HISTORY_RETENTION.save(deps.storage, &retention)?;
+
+    let res = Response::new()
+        .add_attribute("action", "set_history_retention")
+        .add_attribute("owner", info.sender)
+        .add_attribute("retention", retention.to_string());
+    Ok(res)
+}
+
+pub fn execute_prune_spend_history<T>(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    spender: String,
+    limit: Option<u32>,
+) -> Result<Response<T>, ContractError>
+where
+    T: Clone + fmt::Debug + PartialEq + JsonSchema,
+{
+    let spender_addr = deps.api.addr_validate(&spender)?;
+    let retention = HISTORY_RETENTION
+        .may_load(deps.storage)?
+        .unwrap_or(DEFAULT_HISTORY_RETENTION);
+
+    let mut bounds = SPEND_HISTORY_BOUNDS
+        .may_load(deps.storage, &spender_addr)?
+        .unwrap_or_default();
+    let limit = calc_limit(limit) as u64;
+    let pruned = prune_history(deps.storage, &spender_addr, &mut bounds, retention, limit);
+    if pruned > 0 {
+        SPEND_HISTORY_BOUNDS.save(deps.storage, &spender_addr, &bounds)?;
+    }
+
+    let res = Response::new()
+        .add_attribute("action", "prune_spend_history")
+        .add_attribute("spender", spender)
+        .add_attribute("pruned", pruned.to_string());
+    Ok(res)
+}
+
+pub fn execute_set_permissions<T>(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    spender: String,
+    perm: Permissions,
+) -> Result<Response<T>, ContractError>
+where
+    T: Clone + fmt::Debug + PartialEq + JsonSchema,
+{
+    let cfg = ADMIN_LIST.load(deps.storage)?;
+    ensure!(cfg.is_admin(&info.sender), ContractError::Unauthorized {});
+
+    let spender_addr = deps.api.addr_validate(&spender)?;
+    ensure_ne!(
+        info.sender,
+        spender_addr,
+        ContractError::CannotSetOwnAccount {}
+    );
+    PERMISSIONS.save(deps.storage, &spender_addr, &perm)?;
+
+    let res = Response::new()
+        .add_attribute("action", "set_permissions")
+        .add_attribute("owner", info.sender)
+        .add_attribute("spender", spender)
+        .add_attribute("permissions", perm.to_string());
+    Ok(res)
+}
+
+pub fn execute_set_allowed_messages<T>(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    spender: String,
+    messages: Vec<MessageType>,
+) -> Result<Response<T>, ContractError>
+where
+    T: Clone + fmt::Debug + PartialEq + JsonSchema,
+{
+    let cfg = ADMIN_LIST.load(deps.storage)?;
+    ensure!(cfg.is_admin(&info.sender), ContractError::Unauthorized {});
+
+    let spender_addr = deps.api.addr_validate(&spender)?;
+    ensure_ne!(
+        info.sender,
+        spender_addr,
+        ContractError::CannotSetOwnAccount {}
+    );
+
+    let messages = messages
+        .into_iter()
+        .map(|entry| match entry {
+            MessageType::WasmExecute { contract, methods } => {
+                ensure!(
+                    methods.iter().all(|methods| !methods.is_empty()),
+                    ContractError::EmptyMethods {}
+                );
+                Ok(MessageType::WasmExecute {
+                    contract: deps.api.addr_validate(&contract)?.into(),
+                    methods,
+                })
+            }
+            entry => Ok(entry),
+        })
+        .collect::<Result<Vec<_>, ContractError>>()?;
+    // an empty list is stored rather than removed, so migration does not fill it again
+    ALLOWED_MESSAGES.save(deps.storage, &spender_addr, &messages)?;
+
+    let res = Response::new()
+        .add_attribute("action", "set_allowed_messages")
+        .add_attribute("owner", info.sender)
+        .add_attribute("spender", spender);
+    Ok(res)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, env: Env, reply: Reply) -> Result<Response, ContractError> {
+    if let Some(debit) = PENDING_DEBITS.may_load(deps.storage, reply.id)? {
+        PENDING_DEBITS.remove(deps.storage, reply.id);
+        if reply.result.is_ok() {
+            record_spends(deps.storage, &debit.spender, vec![debit.spend])?;
+        } else {
+            refund_debit(deps.storage, &env.block, debit)?;
+        }
+    }
+    Ok(whitelist_reply(deps, env, reply)?)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::AdminList {} => to_binary(&query_admin_list(deps, env)?),
+        QueryMsg::Allowance { spender } => to_binary(&query_allowance(deps, env, spender)?),
+        QueryMsg::Cw20Allowance { spender, token } => {
+            to_binary(&query_cw20_allowance(deps, env, spender, token)?)
+        }
+        QueryMsg::AllCw20Allowances {
+            spender,
+            start_after,
+            limit,
+        } => to_binary(&query_all_cw20_allowances(
+            deps,
+            env,
+            spender,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::SpendHistory {
+            spender,
+            start_after,
+            limit,
+        } => to_binary(&query_spend_history(deps, spender, start_after, limit)?),
+        QueryMsg::HistoryRetention {} => to_binary(&query_history_retention(deps)?),
+        QueryMsg::IbcChannelLimits {
+            spender,
+            start_after,
+            limit,
+        } => to_binary(&query_ibc_channel_limits(
+            deps,
+            spender,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::AllowanceTopUp { spender } => {
+            to_binary(&query_allowance_top_up(deps, env, spender)?)
+        }
+        QueryMsg::RecurringAllowance { spender } => {
+            to_binary(&query_recurring_allowance(deps, env, spender)?)
+        }
+        QueryMsg::Permissions { spender } => to_binary(&query_permissions(deps, spender)?),
+        QueryMsg::AllowedMessages { spender } => to_binary(&query_allowed_messages(deps, spender)?),
+        QueryMsg::AllAllowedMessages { start_after, limit } => {
+            to_binary(&query_all_allowed_messages(deps, start_after, limit)?)
+        }
+        QueryMsg::CanExecute { sender, msg } => {
+            to_binary(&query_can_execute(deps, env, sender, msg)?)
+        }
+        QueryMsg::AllAllowances { start_after, limit } => {
+            to_binary(&query_all_allowances(deps, env, start_after, limit)?)
+        }
+        QueryMsg::AllPermissions { start_after, limit } => {
+            to_binary(&query_all_permissions(deps, start_after, limit)?)
+        }
+        QueryMsg::AllSubkeys {
+            filter,
+            start_after,
+            limit,
+        } => to_binary(&query_all_subkeys(deps, env, filter, start_after, limit)?),
+    }
+}
+
+// if the subkey has no allowance, return an empty struct (not an error)
+pub fn query_allowance(deps: Deps, env: Env, spender: String) -> StdResult<Allowance> {
+    // we can use unchecked here as it is a query - bad value means a miss, we never write it
+    let spender = deps.api.addr_validate(&spender)?;
+    let allow = current_allowance(deps.storage, &spender, &env.block)?.unwrap_or_default();
+
+    Ok(allow)
+}
+
+// if the subkey has no allowance on the token, return a zero one (not an error)
+pub fn query_cw20_allowance(
+    deps: Deps,
+    env: Env,
+    spender: String,
+    token: String,
+) -> StdResult<Cw20AllowanceInfo> {
+    let spender_addr = deps.api.addr_validate(&spender)?;
+    let token_addr = deps.api.addr_validate(&token)?;
+    let allow = CW20_ALLOWANCES
+        .may_load(deps.storage, (&spender_addr, &token_addr))?
+        .filter(|allow| !allow.expires.is_expired(&env.block))
+        .unwrap_or_default();
+
+    Ok(Cw20AllowanceInfo {
+        spender,
+        token,
+        amount: allow.amount,
+        expires: allow.expires,
+    })
+}
+
+// return a list of the cw20 allowances of a subkey here
+pub fn query_all_cw20_allowances(
+    deps: Deps,
+    env: Env,
+    spender: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<AllCw20AllowancesResponse> {
+    let limit = calc_limit(limit);
+    let spender_addr = deps.api.addr_validate(&spender)?;
+    let start = start_after.map(|s| Bound::ExclusiveRaw(s.into()));
+
+    let allowances = CW20_ALLOWANCES
+        .prefix(&spender_addr)
+        .range(deps.storage, start, None, Order::Ascending)
+        .filter(|item| {
+            if let Ok((_, allow)) = item {
+                !allow.expires.is_expired(&env.block)
+            } else {
+                true
+            }
+        })
+        .take(limit)
+        .map(|item| {
+            item.map(|(token, allow)| Cw20AllowanceInfo {
+                spender: spender.clone(),
+                token: token.into(),
+                amount: allow.amount,
+                expires: allow.expires,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(AllCw20AllowancesResponse { allowances })
+}
+
+// return the spend history of a subkey here, oldest first
+pub fn query_spend_history(
+    deps: Deps,
+    spender: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<SpendHistoryResponse> {
+    let limit = calc_limit(limit);
+    let spender_addr = deps.api.addr_validate(&spender)?;
+    let start = start_after.map(Bound::exclusive);
+
+    let records = SPEND_HISTORY
+        .prefix(&spender_addr)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            item.map(|(id, record)| SpendRecordInfo {
+                id,
+                time: record.time,
+                target: record.target,
+                funds: record.funds,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(SpendHistoryResponse { spender, records })
+}
+
+pub fn query_history_retention(deps: Deps) -> StdResult<HistoryRetentionResponse> {
+    let retention = HISTORY_RETENTION
+        .may_load(deps.storage)?
+        .unwrap_or(DEFAULT_HISTORY_RETENTION);
+    Ok(HistoryRetentionResponse { retention })
+}
+
+// return what is left of the IBC channel limits of a subkey here
+pub fn query_ibc_channel_limits(
+    deps: Deps,
+    spender: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<IbcChannelLimitsResponse> {
+    let limit = calc_limit(limit);
+    let spender_addr = deps.api.addr_validate(&spender)?;
+    let start = start_after.map(|s| Bound::ExclusiveRaw(s.into()));
+
+    let limits = IBC_CHANNEL_LIMITS
+        .prefix(&spender_addr)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            item.map(|(channel_id, remaining)| IbcChannelLimitInfo {
+                channel_id,
+                remaining,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(IbcChannelLimitsResponse { spender, limits })
+}
+
+// the next top-up is the one due after the current block, even if earlier ones are not applied yet
+pub fn query_allowance_top_up(
+    deps: Deps,
+    env: Env,
+    spender: String,
+) -> StdResult<AllowanceTopUpResponse> {
+    let spender_addr = deps.api.addr_validate(&spender)?;
+    let top_up = ALLOWANCE_TOP_UPS
+        .may_load(deps.storage, &spender_addr)?
+        .map(|mut top_up| -> StdResult<_> {
+            // top-ups which are due are only applied on execution
+            top_up.apply(&mut Allowance::default(), &env.block)?;
+            Ok(AllowanceTopUpInfo {
+                next_at: match top_up.next {
+                    Scheduled::AtHeight(height) => Expiration::AtHeight(height),
+                    Scheduled::AtTime(time) => Expiration::AtTime(time),
+                },
+                amount: top_up.amount,
+                period: top_up.period,
+                cap: top_up.cap,
+            })
+        })
+        .transpose()?;
+    Ok(AllowanceTopUpResponse { spender, top_up })
+}
+
+// the remaining balance is that of the current period, even if nothing was spent in it yet
+pub fn query_recurring_allowance(
+    deps: Deps,
+    env: Env,
+    spender: String,
+) -> StdResult<RecurringAllowanceResponse> {
+    let spender_addr = deps.api.addr_validate(&spender)?;
+    let recurring = RECURRING_ALLOWANCES
+        .may_load(deps.storage, &spender_addr)?
+        .map(|recurring| -> StdResult<_> {
+            let recurring = recurring.current(&env.block)?;
+            Ok(RecurringAllowanceInfo {
+                resets_at: recurring.resets_at()?,
+                amount: recurring.amount,
+                period: recurring.period,
+                remaining: recurring.remaining,
+            })
+        })
+        .transpose()?;
+    Ok(RecurringAllowanceResponse { spender, recurring })
+}
+
+// if the subkey has no permissions, return an empty struct (not an error)
+pub fn query_permissions(deps: Deps, spender: String) -> StdResult<Permissions> {
+    let spender = deps.api.addr_validate(&spender)?;
+    let permissions = PERMISSIONS
+        .may_load(deps.storage, &spender)?
+        .unwrap_or_default();
+    Ok(permissions)
+}
+
+// if the subkey has no allowlist, return an empty list (not an error)
+pub fn query_allowed_messages(deps: Deps, spender: String) -> StdResult<AllowedMessagesResponse> {
+    let spender_addr = deps.api.addr_validate(&spender)?;
+    let messages = ALLOWED_MESSAGES
+        .may_load(deps.storage, &spender_addr)?
+        .unwrap_or_default();
+    Ok(AllowedMessagesResponse { spender, messages })
+}
+
+fn query_can_execute(
+    deps: Deps,
+    env: Env,
+    sender: String,
+    msg: CosmosMsg,
+) -> StdResult<CanExecuteResponse> {
+    Ok(CanExecuteResponse {
+        can_execute: can_execute(deps, env, sender, msg)?,
+    })
+}
+
+// this can just return booleans and the query_can_execute wrapper creates the struct once, not on every path
+fn can_execute(deps: Deps, env: Env, sender: String, msg: CosmosMsg) -> StdResult<bool> {
+    let cfg = ADMIN_LIST.load(deps.storage)?;
+    if cfg.is_admin(&sender) {
+        return Ok(true);
+    }
+
+    let sender = deps.api.addr_validate(&sender)?;
+    let allowed = ALLOWED_MESSAGES
+        .may_load(deps.storage, &sender)?
+        .unwrap_or_default();
+    if !allowed.iter().any(|entry| entry.covers(&msg)) {
+        return Ok(false);
+    }
+
+    match msg {
+        CosmosMsg::Bank(BankMsg::Send { amount, .. }) => {
+            allowance_covers(deps, &env.block, &sender, amount)
+        }
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr,
+            msg,
+            funds,
+        }) => {
+            let cw20_covered = match cw20_spent(&msg) {
+                Some(amount) => {
+                    let token = deps.api.addr_validate(&contract_addr)?;
+                    match CW20_ALLOWANCES.may_load(deps.storage, (&sender, &token))? {
+                        Some(allow) => {
+                            !allow.expires.is_expired(&env.block) && allow.amount >= amount
+                        }
+                        None => false,
+                    }
+                }
+                None => true,
+            };
+            Ok(cw20_covered
+                && (funds.is_empty() || allowance_covers(deps, &env.block, &sender, funds)?))
+        }
+        CosmosMsg::Staking(staking_msg) => {
+            let perm_opt = PERMISSIONS.may_load(deps.storage, &sender)?;
+            match perm_opt {
+                Some(permission) => Ok(check_staking_permissions(&staking_msg, permission).is_ok()),
+                None => Ok(false),
+            }
+        }
+        CosmosMsg::Distribution(distribution_msg) => {
+            let perm_opt = PERMISSIONS.may_load(deps.storage, &sender)?;
+            match perm_opt {
+                Some(permission) => {
+                    Ok(check_distribution_permissions(&distribution_msg, permission).is_ok())
+                }
+                None => Ok(false),
+            }
+        }
+        CosmosMsg::Ibc(IbcMsg::Transfer {
+            channel_id, amount, ..
+        }) => {
+            let perm = PERMISSIONS.may_load(deps.storage, &sender)?;
+            if !matches!(perm, Some(perm) if perm.ibc_transfer) {
+                return Ok(false);
+            }
+            let limit = IBC_CHANNEL_LIMITS.may_load(deps.storage, (&sender, &channel_id))?;
+            if let Some(remaining) = limit {
+                if remaining.sub(amount.clone()).is_err() {
+                    return Ok(false);
+                }
+            }
+            allowance_covers(deps, &env.block, &sender, vec![amount])
+        }
+        _ => Ok(false),
+    }
+}
+
+fn allowance_covers(
+    deps: Deps,
+    block: &BlockInfo,
+    sender: &Addr,
+    amount: Vec<Coin>,
+) -> StdResult<bool> {
+    if let Some(recurring) = RECURRING_ALLOWANCES.may_load(deps.storage, sender)? {
+        let recurring = recurring.current(block)?;
+        if recurring.remaining.sub(amount.clone()).is_ok() {
+            return Ok(true);
+        }
+    }
+
+    // now we check if there is enough allowance for this message
+    let allowance = current_allowance(deps.storage, sender, block)?;
+    match allowance {
+        // if there is an allowance, we subtract the requested amount to ensure it is covered (error on underflow)
+        Some(allow) => Ok(allow.balance.sub(amount).is_ok()),
+        None => Ok(false),
+    }
+}
+
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+fn calc_limit(request: Option<u32>) -> usize {
+    request.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize
+}
+
+// return a list of all allowances here
+pub fn query_all_allowances(
+    deps: Deps,
+    env: Env,
+    start_after: Option<String>,
     limit: Option<u32>,
 ) -> StdResult<AllAllowancesResponse> {
     let limit = calc_limit(limit);
@@ -452,6 +1454,120 @@ pub fn query_all_permissions(
     Ok(AllPermissionsResponse { permissions })
 }
 
+// return every subkey with an allowance or permissions matching the filter here
+pub fn query_all_subkeys(
+    deps: Deps,
+    env: Env,
+    filter: SubkeyFilter,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<AllSubkeysResponse> {
+    let limit = calc_limit(limit);
+    let start = || {
+        start_after
+            .as_ref()
+            .map(|s| Bound::ExclusiveRaw(s.as_bytes().to_vec()))
+    };
+
+    // subkeys may have an allowance, permissions or both, so walk both maps in order
+    let mut allowances = ALLOWANCES
+        .keys(deps.storage, start(), None, Order::Ascending)
+        .peekable();
+    let mut permissions = PERMISSIONS
+        .keys(deps.storage, start(), None, Order::Ascending)
+        .peekable();
+    let spenders = std::iter::from_fn(|| match (allowances.peek(), permissions.peek()) {
+        (None, None) => None,
+        (Some(Ok(allowed)), Some(Ok(permitted))) => match allowed.cmp(permitted) {
+            std::cmp::Ordering::Less => allowances.next(),
+            std::cmp::Ordering::Greater => permissions.next(),
+            std::cmp::Ordering::Equal => {
+                permissions.next();
+                allowances.next()
+            }
+        },
+        (Some(_), None) | (Some(Err(_)), _) => allowances.next(),
+        _ => permissions.next(),
+    });
+
+    let horizon = filter.expires_within.map(|within| {
+        let mut block = env.block.clone();
+        match within {
+            Duration::Height(height) => block.height += height,
+            Duration::Time(time) => block.time = block.time.plus_seconds(time),
+        }
+        block
+    });
+
+    let mut subkeys = vec![];
+    for spender in spenders {
+        let spender = spender?;
+        let allowance = ALLOWANCES
+            .may_load(deps.storage, &spender)?
+            .filter(|allow| !allow.expires.is_expired(&env.block));
+        let perms = PERMISSIONS.may_load(deps.storage, &spender)?;
+        // only an expired allowance is left, same as if there was none
+        if allowance.is_none() && perms.is_none() {
+            continue;
+        }
+        let allowance = allowance.unwrap_or_default();
+        let perms = perms.unwrap_or_default();
+
+        if let Some(denom) = &filter.denom {
+            let balance = &allowance.balance.0;
+            if !balance
+                .iter()
+                .any(|c| &c.denom == denom && !c.amount.is_zero())
+            {
+                continue;
+            }
+        }
+        if let Some(horizon) = &horizon {
+            if allowance.balance.is_empty() || !allowance.expires.is_expired(horizon) {
+                continue;
+            }
+        }
+        if let Some(required) = &filter.permissions {
+            if !perms.includes(required) {
+                continue;
+            }
+        }
+
+        subkeys.push(SubkeyInfo {
+            spender: spender.into(),
+            balance: allowance.balance,
+            expires: allowance.expires,
+            permissions: perms,
+        });
+        if subkeys.len() == limit {
+            break;
+        }
+    }
+    Ok(AllSubkeysResponse { subkeys })
+}
+
+// return the allowlists of all subkeys here
+pub fn query_all_allowed_messages(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<AllAllowedMessagesResponse> {
+    let limit = calc_limit(limit);
+    let start = start_after.map(|s| Bound::ExclusiveRaw(s.into()));
+
+    let allowlists = ALLOWED_MESSAGES
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            item.map(|(addr, messages)| AllowedMessagesResponse {
+                spender: addr.into(),
+                messages,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(AllAllowedMessagesResponse { allowlists })
+}
+
 // Migrate contract if version is lower than current version
 #[entry_point]
 pub fn migrate(deps: DepsMut, _env: Env, _msg: Empty) -> Result<Response, ContractError> {
@@ -465,21 +1581,89 @@ pub fn migrate(deps: DepsMut, _env: Env, _msg: Empty) -> Result<Response, Contra
         // should occur here
     }
 
+    migrate_withdraw_address_permissions(deps.storage)?;
+    migrate_allowed_messages(deps.storage)?;
+
     Ok(Response::new())
 }
 
+/// Subkeys set up before `set_withdraw_address` was split from `withdraw` keep being allowed to
+/// set the withdraw address if they could withdraw. Permissions which already have the flag are
+/// left untouched, so this is safe to run on every migration.
+fn migrate_withdraw_address_permissions(storage: &mut dyn Storage) -> StdResult<()> {
+    let legacy = LEGACY_PERMISSIONS
+        .range(storage, None, None, Order::Ascending)
+        .filter(|item| match item {
+            Ok((_, perm)) => perm.set_withdraw_address.is_none(),
+            Err(_) => true,
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    for (spender, perm) in legacy {
+        let perm = Permissions {
+            delegate: perm.delegate,
+            redelegate: perm.redelegate,
+            undelegate: perm.undelegate,
+            withdraw: perm.withdraw,
+            set_withdraw_address: perm.withdraw,
+            ibc_transfer: false,
+        };
+        PERMISSIONS.save(storage, &spender, &perm)?;
+    }
+    Ok(())
+}
+
+/// Subkeys set up before message allowlists existed get an allowlist matching what they could
+/// do before: bank sends if they have an allowance, staking and distribution messages if any of
+/// the corresponding permission flags is set. Subkeys which already have an allowlist are left
+/// untouched, so this is safe to run on every migration.
+fn migrate_allowed_messages(storage: &mut dyn Storage) -> StdResult<()> {
+    let mut allowlists: std::collections::BTreeMap<Addr, Vec<MessageType>> = Default::default();
+
+    let allowances = ALLOWANCES
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for spender in allowances {
+        allowlists
+            .entry(spender)
+            .or_default()
+            .push(MessageType::BankSend);
+    }
+
+    let permissions = PERMISSIONS
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (spender, perm) in permissions {
+        let entry = allowlists.entry(spender).or_default();
+        if perm.delegate || perm.redelegate || perm.undelegate {
+            entry.push(MessageType::Staking);
+        }
+        if perm.withdraw || perm.set_withdraw_address {
+            entry.push(MessageType::Distribution);
+        }
+    }
+
+    for (spender, messages) in allowlists {
+        if !ALLOWED_MESSAGES.has(storage, &spender) {
+            ALLOWED_MESSAGES.save(storage, &spender, &messages)?;
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use cosmwasm_std::testing::{
         mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage,
     };
-    use cosmwasm_std::{coin, coins, OwnedDeps, StakingMsg, SubMsg, Timestamp};
+    use cosmwasm_std::{
+        coin, coins, OwnedDeps, StakingMsg, SubMsg, SubMsgResponse, SubMsgResult, Timestamp,
+    };
 
     use cw1_whitelist::msg::AdminListResponse;
     use cw2::{get_contract_version, ContractVersion};
     use cw_utils::NativeBalance;
 
-    use crate::state::Permissions;
+    use crate::state::{LegacyPermissions, Permissions};
 
     use std::collections::HashMap;
 
@@ -504,12 +1688,16 @@ mod tests {
         redelegate: true,
         undelegate: true,
         withdraw: true,
+        set_withdraw_address: true,
+        ibc_transfer: true,
     };
     const NO_PERMS: Permissions = Permissions {
         delegate: false,
         redelegate: false,
         undelegate: false,
         withdraw: false,
+        set_withdraw_address: false,
+        ibc_transfer: false,
     };
 
     // Expiration constant working properly with default `mock_env`
@@ -567,6 +1755,16 @@ mod tests {
             self
         }
 
+        /// Overrides the allowlist derived from allowances and permissions
+        fn with_allowed_messages(
+            mut self,
+            spender: &'static str,
+            messages: Vec<MessageType>,
+        ) -> Self {
+            self.spenders.entry(spender).or_default().allowed_messages = Some(messages);
+            self
+        }
+
         fn with_admin(mut self, admin: &'static str) -> Self {
             self.admins.push(admin);
             self
@@ -578,6 +1776,7 @@ mod tests {
         allowances: Vec<Coin>,
         allowances_expire: Option<Expiration>,
         permissions: Option<Permissions>,
+        allowed_messages: Option<Vec<MessageType>>,
     }
 
     /// Test suite helper unifying test initialization, keeping access to created data
@@ -603,6 +1802,8 @@ mod tests {
             let instantiate_msg = InstantiateMsg {
                 admins,
                 mutable: true,
+                policy: None,
+                threshold: None,
             };
             let owner = mock_info(OWNER, &[]);
 
@@ -619,8 +1820,27 @@ mod tests {
                     allowances,
                     allowances_expire: expires,
                     permissions,
+                    allowed_messages,
                 } = spender;
 
+                // unless given explicitly, allow the message types the spender is configured for
+                let allowed_messages = allowed_messages.unwrap_or_else(|| {
+                    let mut messages = vec![];
+                    if !allowances.is_empty() {
+                        messages.push(MessageType::BankSend);
+                    }
+                    if permissions.is_some() {
+                        messages.push(MessageType::Staking);
+                        messages.push(MessageType::Distribution);
+                    }
+                    messages
+                });
+                let msg = ExecuteMsg::SetAllowedMessages {
+                    spender: name.to_owned(),
+                    messages: allowed_messages,
+                };
+                execute(deps.as_mut().branch(), mock_env(), owner.clone(), msg).unwrap();
+
                 for amount in allowances {
                     let msg = ExecuteMsg::IncreaseAllowance {
                         spender: name.to_owned(),
@@ -878,7 +2098,9 @@ mod tests {
 
             // Verify
             assert_eq!(
-                query_admin_list(deps.as_ref()).unwrap().canonical(),
+                query_admin_list(deps.as_ref(), mock_env())
+                    .unwrap()
+                    .canonical(),
                 AdminListResponse {
                     admins: vec![OWNER.to_owned(), ADMIN1.to_owned()],
                     mutable: true,
@@ -908,7 +2130,9 @@ mod tests {
             assert_eq!(rsp.data, None);
 
             assert_eq!(
-                query_admin_list(deps.as_ref()).unwrap().canonical(),
+                query_admin_list(deps.as_ref(), mock_env())
+                    .unwrap()
+                    .canonical(),
                 AdminListResponse {
                     admins: vec![OWNER.to_owned(), ADMIN1.to_owned(), ADMIN2.to_owned()],
                     mutable: true,
@@ -937,7 +2161,9 @@ mod tests {
             assert_eq!(rsp.data, None);
 
             assert_eq!(
-                query_admin_list(deps.as_ref()).unwrap().canonical(),
+                query_admin_list(deps.as_ref(), mock_env())
+                    .unwrap()
+                    .canonical(),
                 AdminListResponse {
                     admins: vec![OWNER.to_owned(), ADMIN1.to_owned(), ADMIN2.to_owned()],
                     mutable: true,
@@ -962,7 +2188,9 @@ mod tests {
             .unwrap_err();
 
             assert_eq!(
-                query_admin_list(deps.as_ref()).unwrap().canonical(),
+                query_admin_list(deps.as_ref(), mock_env())
+                    .unwrap()
+                    .canonical(),
                 AdminListResponse {
                     admins: vec![OWNER.to_owned()],
                     mutable: true,
@@ -999,7 +2227,9 @@ mod tests {
             .unwrap_err();
 
             assert_eq!(
-                query_admin_list(deps.as_ref()).unwrap().canonical(),
+                query_admin_list(deps.as_ref(), mock_env())
+                    .unwrap()
+                    .canonical(),
                 AdminListResponse {
                     admins: vec![ADMIN1.to_owned()],
                     mutable: true,
@@ -1695,7 +2925,10 @@ mod tests {
                 deps.as_mut(),
                 mock_env(),
                 info,
-                ExecuteMsg::Execute { msgs: msgs.clone() },
+                ExecuteMsg::Execute {
+                    msgs: msgs.clone(),
+                    mode: ExecuteMode::Atomic,
+                },
             )
             .unwrap();
 
@@ -1721,6 +2954,45 @@ mod tests {
             );
         }
 
+        #[test]
+        fn best_effort_failure_refunded() {
+            let Suite { mut deps, .. } = SuiteConfig::new()
+                .with_allowance(SPENDER1, coin(10, TOKEN1))
+                .init();
+
+            let send = |amount| -> CosmosMsg {
+                BankMsg::Send {
+                    to_address: SPENDER2.to_owned(),
+                    amount: coins(amount, TOKEN1),
+                }
+                .into()
+            };
+            let msg = ExecuteMsg::Execute {
+                msgs: vec![send(3), send(4)],
+                mode: ExecuteMode::BestEffort,
+            };
+            execute(deps.as_mut(), mock_env(), mock_info(SPENDER1, &[]), msg).unwrap();
+            let allowance = |deps: Deps| {
+                query_allowance(deps, mock_env(), SPENDER1.to_owned())
+                    .unwrap()
+                    .canonical()
+                    .balance
+            };
+            assert_eq!(allowance(deps.as_ref()), NativeBalance(coins(3, TOKEN1)));
+
+            // the first send goes through, the second one fails and is given back
+            let result = |id, result| Reply { id, result };
+            let ok = SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: None,
+            });
+            reply(deps.as_mut(), mock_env(), result(0, ok)).unwrap();
+            let err = SubMsgResult::Err("insufficient funds".to_owned());
+            reply(deps.as_mut(), mock_env(), result(1, err)).unwrap();
+            assert_eq!(allowance(deps.as_ref()), NativeBalance(coins(7, TOKEN1)));
+            assert!(PENDING_DEBITS.is_empty(deps.as_ref().storage));
+        }
+
         #[test]
         fn without_allowance() {
             let Suite { mut deps, .. } = Suite::init();
@@ -1737,7 +3009,10 @@ mod tests {
                 deps.as_mut(),
                 mock_env(),
                 info,
-                ExecuteMsg::Execute { msgs },
+                ExecuteMsg::Execute {
+                    msgs,
+                    mode: ExecuteMode::Atomic,
+                },
             )
             .unwrap_err();
 
@@ -1767,7 +3042,10 @@ mod tests {
                 deps.as_mut(),
                 mock_env(),
                 info,
-                ExecuteMsg::Execute { msgs },
+                ExecuteMsg::Execute {
+                    msgs,
+                    mode: ExecuteMode::Atomic,
+                },
             )
             .unwrap_err();
 
@@ -1804,7 +3082,10 @@ mod tests {
                 deps.as_mut(),
                 mock_env(),
                 info,
-                ExecuteMsg::Execute { msgs },
+                ExecuteMsg::Execute {
+                    msgs,
+                    mode: ExecuteMode::Atomic,
+                },
             )
             .unwrap_err();
 
@@ -1834,7 +3115,10 @@ mod tests {
                 deps.as_mut(),
                 mock_env(),
                 info,
-                ExecuteMsg::Execute { msgs },
+                ExecuteMsg::Execute {
+                    msgs,
+                    mode: ExecuteMode::Atomic,
+                },
             )
             .unwrap_err();
 
@@ -1862,7 +3146,10 @@ mod tests {
                 deps.as_mut(),
                 mock_env(),
                 info,
-                ExecuteMsg::Execute { msgs: msgs.clone() },
+                ExecuteMsg::Execute {
+                    msgs: msgs.clone(),
+                    mode: ExecuteMode::Atomic,
+                },
             )
             .unwrap();
 
@@ -1897,7 +3184,10 @@ mod tests {
                 deps.as_mut(),
                 mock_env(),
                 info,
-                ExecuteMsg::Execute { msgs: msgs.clone() },
+                ExecuteMsg::Execute {
+                    msgs: msgs.clone(),
+                    mode: ExecuteMode::Atomic,
+                },
             )
             .unwrap();
 
@@ -1921,7 +3211,10 @@ mod tests {
                 deps.as_mut(),
                 mock_env(),
                 info,
-                ExecuteMsg::Execute { msgs },
+                ExecuteMsg::Execute {
+                    msgs,
+                    mode: ExecuteMode::Atomic,
+                },
             )
             .unwrap_err();
         }
@@ -1965,7 +3258,10 @@ mod tests {
                     deps.as_mut(),
                     mock_env(),
                     mock_info(SPENDER1, &[]),
-                    ExecuteMsg::Execute { msgs: msgs.clone() },
+                    ExecuteMsg::Execute {
+                        msgs: msgs.clone(),
+                        mode: ExecuteMode::Atomic,
+                    },
                 )
                 .unwrap();
 
@@ -2011,7 +3307,10 @@ mod tests {
                     deps.as_mut(),
                     mock_env(),
                     mock_info(ADMIN1, &[]),
-                    ExecuteMsg::Execute { msgs: msgs.clone() },
+                    ExecuteMsg::Execute {
+                        msgs: msgs.clone(),
+                        mode: ExecuteMode::Atomic,
+                    },
                 )
                 .unwrap();
 
@@ -2051,16 +3350,92 @@ mod tests {
                 .into(),
             ];
 
-            for msg in msgs {
-                let msgs = vec![msg];
+            for msg in msgs {
+                let msgs = vec![msg];
+                execute(
+                    deps.as_mut(),
+                    mock_env(),
+                    mock_info(SPENDER1, &[]),
+                    ExecuteMsg::Execute {
+                        msgs,
+                        mode: ExecuteMode::Atomic,
+                    },
+                )
+                .unwrap_err();
+            }
+        }
+
+        #[test]
+        fn withdraw_without_setting_withdraw_address() {
+            let compound = Permissions {
+                delegate: true,
+                withdraw: true,
+                ..NO_PERMS
+            };
+            let Suite { mut deps, .. } = SuiteConfig::new()
+                .with_permissions(SPENDER1, compound)
+                .with_allowed_messages(
+                    SPENDER1,
+                    vec![MessageType::Staking, MessageType::Distribution],
+                )
+                .init();
+
+            let execute_one = |deps: DepsMut, msg: CosmosMsg| {
                 execute(
-                    deps.as_mut(),
+                    deps,
                     mock_env(),
                     mock_info(SPENDER1, &[]),
-                    ExecuteMsg::Execute { msgs },
+                    ExecuteMsg::Execute {
+                        msgs: vec![msg],
+                        mode: ExecuteMode::Atomic,
+                    },
                 )
-                .unwrap_err();
-            }
+            };
+            let withdraw = DistributionMsg::WithdrawDelegatorReward {
+                validator: "validator1".to_owned(),
+            };
+            let delegate = StakingMsg::Delegate {
+                validator: "validator1".to_owned(),
+                amount: coin(10, TOKEN1),
+            };
+            let undelegate = StakingMsg::Undelegate {
+                validator: "validator1".to_owned(),
+                amount: coin(10, TOKEN1),
+            };
+            let set_withdraw_address = DistributionMsg::SetWithdrawAddress {
+                address: SPENDER1.to_owned(),
+            };
+
+            // rewards can be compounded
+            execute_one(deps.as_mut(), withdraw.into()).unwrap();
+            execute_one(deps.as_mut(), delegate.into()).unwrap();
+            // but not unbonded or redirected
+            let err = execute_one(deps.as_mut(), undelegate.into()).unwrap_err();
+            assert_eq!(err, ContractError::UnDelegatePerm {});
+            let err = execute_one(deps.as_mut(), set_withdraw_address.clone().into()).unwrap_err();
+            assert_eq!(err, ContractError::WithdrawAddrPerm {});
+
+            // permissions stored before the split keep allowing both
+            let legacy = LegacyPermissions {
+                delegate: false,
+                redelegate: false,
+                undelegate: false,
+                withdraw: true,
+                set_withdraw_address: None,
+            };
+            LEGACY_PERMISSIONS
+                .save(deps.as_mut().storage, &Addr::unchecked(SPENDER1), &legacy)
+                .unwrap();
+            migrate(deps.as_mut(), mock_env(), Empty {}).unwrap();
+            assert_eq!(
+                query_permissions(deps.as_ref(), SPENDER1.to_owned()).unwrap(),
+                Permissions {
+                    withdraw: true,
+                    set_withdraw_address: true,
+                    ..NO_PERMS
+                }
+            );
+            execute_one(deps.as_mut(), set_withdraw_address.into()).unwrap();
         }
     }
 
@@ -2160,72 +3535,609 @@ mod tests {
                 .into(),
             ];
 
-            for msg in msgs {
-                let resp =
-                    query_can_execute(deps.as_ref(), mock_env(), SPENDER1.to_owned(), msg.clone())
-                        .unwrap();
+            for msg in msgs {
+                let resp =
+                    query_can_execute(deps.as_ref(), mock_env(), SPENDER1.to_owned(), msg.clone())
+                        .unwrap();
+
+                assert_eq!(
+                    resp,
+                    CanExecuteResponse { can_execute: false },
+                    "Original message: {:#?}",
+                    msg
+                );
+            }
+        }
+
+        #[test]
+        fn custom() {
+            let Suite { deps, .. } = SuiteConfig::new()
+                .with_permissions(SPENDER1, ALL_PERMS)
+                .init();
+
+            let msg: CosmosMsg = CosmosMsg::Custom(Empty {});
+
+            let resp =
+                query_can_execute(deps.as_ref(), mock_env(), SPENDER1.to_owned(), msg).unwrap();
+
+            assert_eq!(resp, CanExecuteResponse { can_execute: false });
+        }
+
+        #[test]
+        fn admin() {
+            let Suite { deps, .. } = SuiteConfig::new().with_admin(ADMIN1).init();
+
+            let msgs = vec![
+                BankMsg::Send {
+                    to_address: SPENDER2.to_owned(),
+                    amount: coins(5, TOKEN1),
+                }
+                .into(),
+                StakingMsg::Delegate {
+                    validator: SPENDER2.to_owned(),
+                    amount: coin(8, TOKEN),
+                }
+                .into(),
+                DistributionMsg::WithdrawDelegatorReward {
+                    validator: SPENDER2.to_owned(),
+                }
+                .into(),
+                CosmosMsg::Custom(Empty {}),
+            ];
+
+            for msg in msgs {
+                let resp =
+                    query_can_execute(deps.as_ref(), mock_env(), ADMIN1.to_owned(), msg.clone())
+                        .unwrap();
+
+                assert_eq!(
+                    resp,
+                    CanExecuteResponse { can_execute: true },
+                    "Original message: {:#?}",
+                    msg
+                );
+            }
+        }
+    }
+
+    mod allowed_messages {
+        use super::*;
+
+        const CONTRACT: &str = "contract";
+
+        fn wasm_execute(contract: &str, funds: Vec<Coin>) -> CosmosMsg {
+            WasmMsg::Execute {
+                contract_addr: contract.to_owned(),
+                msg: Binary::default(),
+                funds,
+            }
+            .into()
+        }
+
+        #[test]
+        fn set_and_query() {
+            let Suite { mut deps, owner } = Suite::init();
+
+            let messages = vec![
+                MessageType::BankSend,
+                MessageType::WasmExecute {
+                    contract: CONTRACT.to_owned(),
+                    methods: None,
+                },
+            ];
+            let msg = ExecuteMsg::SetAllowedMessages {
+                spender: SPENDER1.to_owned(),
+                messages: messages.clone(),
+            };
+
+            // only admins can set the allowlist
+            let err = execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(SPENDER2, &[]),
+                msg.clone(),
+            )
+            .unwrap_err();
+            assert_eq!(err, ContractError::Unauthorized {});
+
+            execute(deps.as_mut(), mock_env(), owner, msg).unwrap();
+            assert_eq!(
+                query_allowed_messages(deps.as_ref(), SPENDER1.to_owned()).unwrap(),
+                AllowedMessagesResponse {
+                    spender: SPENDER1.to_owned(),
+                    messages,
+                }
+            );
+            assert_eq!(
+                query_allowed_messages(deps.as_ref(), SPENDER2.to_owned())
+                    .unwrap()
+                    .messages,
+                vec![]
+            );
+        }
+
+        #[test]
+        fn allowance_without_allowlist_rejected() {
+            let Suite { mut deps, .. } = SuiteConfig::new()
+                .with_allowance(SPENDER1, coin(10, TOKEN1))
+                .with_allowed_messages(SPENDER1, vec![])
+                .init();
+
+            let msg: CosmosMsg = BankMsg::Send {
+                to_address: SPENDER2.to_owned(),
+                amount: coins(6, TOKEN1),
+            }
+            .into();
+
+            let resp =
+                query_can_execute(deps.as_ref(), mock_env(), SPENDER1.to_owned(), msg.clone())
+                    .unwrap();
+            assert_eq!(resp, CanExecuteResponse { can_execute: false });
+
+            let err = execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(SPENDER1, &[]),
+                ExecuteMsg::Execute {
+                    msgs: vec![msg],
+                    mode: ExecuteMode::Atomic,
+                },
+            )
+            .unwrap_err();
+            assert_eq!(err, ContractError::MessageTypeRejected {});
+        }
+
+        #[test]
+        fn wasm_execute_on_allowed_contract() {
+            let Suite { mut deps, .. } = SuiteConfig::new()
+                .with_allowance(SPENDER1, coin(10, TOKEN1))
+                .with_allowed_messages(
+                    SPENDER1,
+                    vec![MessageType::WasmExecute {
+                        contract: CONTRACT.to_owned(),
+                        methods: None,
+                    }],
+                )
+                .init();
+
+            // other contracts are rejected
+            let err = execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(SPENDER1, &[]),
+                ExecuteMsg::Execute {
+                    msgs: vec![wasm_execute("other", vec![])],
+                    mode: ExecuteMode::Atomic,
+                },
+            )
+            .unwrap_err();
+            assert_eq!(err, ContractError::MessageTypeRejected {});
+
+            // funds are limited by the allowance
+            let resp = query_can_execute(
+                deps.as_ref(),
+                mock_env(),
+                SPENDER1.to_owned(),
+                wasm_execute(CONTRACT, coins(11, TOKEN1)),
+            )
+            .unwrap();
+            assert_eq!(resp, CanExecuteResponse { can_execute: false });
+
+            let msgs = vec![wasm_execute(CONTRACT, coins(6, TOKEN1))];
+            let rsp = execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(SPENDER1, &[]),
+                ExecuteMsg::Execute {
+                    msgs: msgs.clone(),
+                    mode: ExecuteMode::Atomic,
+                },
+            )
+            .unwrap();
+            assert_eq!(
+                rsp.messages,
+                msgs.into_iter().map(SubMsg::new).collect::<Vec<_>>()
+            );
+            assert_eq!(
+                query_allowance(deps.as_ref(), mock_env(), SPENDER1.to_owned())
+                    .unwrap()
+                    .balance,
+                NativeBalance(vec![coin(4, TOKEN1)])
+            );
+        }
+
+        #[test]
+        fn wasm_execute_allowed_methods() {
+            let Suite { mut deps, owner } = Suite::init();
+            let call = |method: &str| -> CosmosMsg {
+                WasmMsg::Execute {
+                    contract_addr: CONTRACT.to_owned(),
+                    msg: Binary::from(format!(r#"{{"{}":{{"amount":"1"}}}}"#, method).as_bytes()),
+                    funds: vec![],
+                }
+                .into()
+            };
+            let can_execute = |deps: Deps, msg| {
+                query_can_execute(deps, mock_env(), SPENDER1.to_owned(), msg)
+                    .unwrap()
+                    .can_execute
+            };
+
+            let allowlist = |methods| ExecuteMsg::SetAllowedMessages {
+                spender: SPENDER1.to_owned(),
+                messages: vec![MessageType::WasmExecute {
+                    contract: CONTRACT.to_owned(),
+                    methods,
+                }],
+            };
+            let err = execute(
+                deps.as_mut(),
+                mock_env(),
+                owner.clone(),
+                allowlist(Some(vec![])),
+            )
+            .unwrap_err();
+            assert_eq!(err, ContractError::EmptyMethods {});
+            let methods = Some(vec!["stake".to_owned(), "claim".to_owned()]);
+            execute(deps.as_mut(), mock_env(), owner, allowlist(methods.clone())).unwrap();
+
+            // only the listed methods may be called, on the listed contract
+            assert!(can_execute(deps.as_ref(), call("stake")));
+            assert!(can_execute(deps.as_ref(), call("claim")));
+            assert!(!can_execute(deps.as_ref(), call("transfer")));
+            assert!(!can_execute(deps.as_ref(), wasm_execute(CONTRACT, vec![])));
+            let err = execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(SPENDER1, &[]),
+                ExecuteMsg::Execute {
+                    msgs: vec![call("transfer")],
+                    mode: ExecuteMode::Atomic,
+                },
+            )
+            .unwrap_err();
+            assert_eq!(err, ContractError::MessageTypeRejected {});
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(SPENDER1, &[]),
+                ExecuteMsg::Execute {
+                    msgs: vec![call("stake")],
+                    mode: ExecuteMode::Atomic,
+                },
+            )
+            .unwrap();
+
+            // allowlists of all subkeys are listed
+            assert_eq!(
+                query_all_allowed_messages(deps.as_ref(), None, None).unwrap(),
+                AllAllowedMessagesResponse {
+                    allowlists: vec![AllowedMessagesResponse {
+                        spender: SPENDER1.to_owned(),
+                        messages: vec![MessageType::WasmExecute {
+                            contract: CONTRACT.to_owned(),
+                            methods,
+                        }],
+                    }],
+                }
+            );
+        }
+
+        #[test]
+        fn migration_derives_allowlist() {
+            let Suite { mut deps, .. } = SuiteConfig::new()
+                .with_allowance(SPENDER1, coin(10, TOKEN1))
+                .with_permissions(SPENDER2, ALL_PERMS)
+                .with_permissions(SPENDER3, NO_PERMS)
+                .with_allowed_messages(SPENDER4, vec![])
+                .init();
+
+            // simulate state from before allowlists existed
+            for spender in [SPENDER1, SPENDER2, SPENDER3] {
+                ALLOWED_MESSAGES.remove(deps.as_mut().storage, &Addr::unchecked(spender));
+            }
+
+            migrate(deps.as_mut(), mock_env(), Empty {}).unwrap();
+
+            let allowed = |spender: &str| {
+                query_allowed_messages(deps.as_ref(), spender.to_owned())
+                    .unwrap()
+                    .messages
+            };
+            assert_eq!(allowed(SPENDER1), vec![MessageType::BankSend]);
+            assert_eq!(
+                allowed(SPENDER2),
+                vec![MessageType::Staking, MessageType::Distribution]
+            );
+            assert_eq!(allowed(SPENDER3), vec![]);
+            assert_eq!(allowed(SPENDER4), vec![]);
+        }
+    }
+
+    // tests permissions and allowances are independent features and does not affect each other
+    mod cw20_allowance {
+        use super::*;
+
+        const CW20: &str = "cw20token";
+
+        fn transfer(amount: u128) -> CosmosMsg {
+            let msg = Cw20ExecuteMsg::Transfer {
+                recipient: SPENDER2.to_owned(),
+                amount: Uint128::new(amount),
+            };
+            WasmMsg::Execute {
+                contract_addr: CW20.to_owned(),
+                msg: to_binary(&msg).unwrap(),
+                funds: vec![],
+            }
+            .into()
+        }
+
+        #[test]
+        fn transfers_deducted() {
+            let Suite { mut deps, owner } = SuiteConfig::new()
+                .with_allowed_messages(
+                    SPENDER1,
+                    vec![MessageType::WasmExecute {
+                        contract: CW20.to_owned(),
+                        methods: None,
+                    }],
+                )
+                .init();
+            let spend = |deps: DepsMut, amount| {
+                let msgs = vec![transfer(amount)];
+                execute(
+                    deps,
+                    mock_env(),
+                    mock_info(SPENDER1, &[]),
+                    ExecuteMsg::Execute {
+                        msgs,
+                        mode: ExecuteMode::Atomic,
+                    },
+                )
+            };
+
+            // transfers need an allowance on the token
+            let err = spend(deps.as_mut(), 1).unwrap_err();
+            assert_eq!(err, ContractError::NoAllowance {});
+            let msg = ExecuteMsg::IncreaseCw20Allowance {
+                spender: SPENDER1.to_owned(),
+                token: CW20.to_owned(),
+                amount: Uint128::new(10),
+                expires: None,
+            };
+            execute(deps.as_mut(), mock_env(), owner.clone(), msg).unwrap();
+
+            let can_execute = |deps: Deps, amount| {
+                query_can_execute(deps, mock_env(), SPENDER1.to_owned(), transfer(amount))
+                    .unwrap()
+                    .can_execute
+            };
+            assert!(can_execute(deps.as_ref(), 10));
+            assert!(!can_execute(deps.as_ref(), 11));
+            spend(deps.as_mut(), 6).unwrap();
+            spend(deps.as_mut(), 6).unwrap_err();
+            assert_eq!(
+                query_cw20_allowance(
+                    deps.as_ref(),
+                    mock_env(),
+                    SPENDER1.to_owned(),
+                    CW20.to_owned()
+                )
+                .unwrap(),
+                Cw20AllowanceInfo {
+                    spender: SPENDER1.to_owned(),
+                    token: CW20.to_owned(),
+                    amount: Uint128::new(4),
+                    expires: Expiration::Never {},
+                }
+            );
+
+            // decreasing it to zero removes it
+            let msg = ExecuteMsg::DecreaseCw20Allowance {
+                spender: SPENDER1.to_owned(),
+                token: CW20.to_owned(),
+                amount: Uint128::new(5),
+                expires: None,
+            };
+            execute(deps.as_mut(), mock_env(), owner, msg).unwrap();
+            assert_eq!(
+                query_all_cw20_allowances(
+                    deps.as_ref(),
+                    mock_env(),
+                    SPENDER1.to_owned(),
+                    None,
+                    None
+                )
+                .unwrap(),
+                AllCw20AllowancesResponse { allowances: vec![] }
+            );
+        }
+
+        #[test]
+        fn expired_allowance() {
+            let Suite { mut deps, owner } = SuiteConfig::new()
+                .with_allowed_messages(
+                    SPENDER1,
+                    vec![MessageType::WasmExecute {
+                        contract: CW20.to_owned(),
+                        methods: None,
+                    }],
+                )
+                .init();
+
+            let msg = ExecuteMsg::IncreaseCw20Allowance {
+                spender: SPENDER1.to_owned(),
+                token: CW20.to_owned(),
+                amount: Uint128::new(10),
+                expires: Some(NON_EXPIRED_HEIGHT),
+            };
+            execute(deps.as_mut(), mock_env(), owner, msg).unwrap();
+            let allowances = query_all_cw20_allowances(
+                deps.as_ref(),
+                mock_env(),
+                SPENDER1.to_owned(),
+                None,
+                None,
+            )
+            .unwrap()
+            .allowances;
+            assert_eq!(allowances.len(), 1);
+
+            let mut env = mock_env();
+            env.block.height = 22_222;
+            let msgs = vec![transfer(1)];
+            let err = execute(
+                deps.as_mut(),
+                env.clone(),
+                mock_info(SPENDER1, &[]),
+                ExecuteMsg::Execute {
+                    msgs,
+                    mode: ExecuteMode::Atomic,
+                },
+            )
+            .unwrap_err();
+            assert_eq!(err, ContractError::NoAllowance {});
+            let allowance =
+                query_cw20_allowance(deps.as_ref(), env, SPENDER1.to_owned(), CW20.to_owned())
+                    .unwrap();
+            assert_eq!(allowance.amount, Uint128::zero());
+        }
+    }
 
-                assert_eq!(
-                    resp,
-                    CanExecuteResponse { can_execute: false },
-                    "Original message: {:#?}",
-                    msg
-                );
+    mod recurring_allowance {
+        use super::*;
+
+        fn send(deps: DepsMut, env: Env, amount: u128) -> Result<Response, ContractError> {
+            let msgs = vec![BankMsg::Send {
+                to_address: SPENDER2.to_owned(),
+                amount: coins(amount, TOKEN1),
             }
+            .into()];
+            execute(
+                deps,
+                env,
+                mock_info(SPENDER1, &[]),
+                ExecuteMsg::Execute {
+                    msgs,
+                    mode: ExecuteMode::Atomic,
+                },
+            )
         }
 
         #[test]
-        fn custom() {
-            let Suite { deps, .. } = SuiteConfig::new()
-                .with_permissions(SPENDER1, ALL_PERMS)
+        fn resets_every_period() {
+            let Suite { mut deps, owner } = SuiteConfig::new()
+                .with_allowed_messages(SPENDER1, vec![MessageType::BankSend])
                 .init();
+            let mut env = mock_env();
+            let start = env.block.height;
 
-            let msg: CosmosMsg = CosmosMsg::Custom(Empty {});
+            let msg = ExecuteMsg::SetRecurringAllowance {
+                spender: SPENDER1.to_owned(),
+                amount: coins(10, TOKEN1),
+                period: Duration::Height(100),
+            };
+            let err = execute(
+                deps.as_mut(),
+                env.clone(),
+                mock_info(SPENDER2, &[]),
+                msg.clone(),
+            )
+            .unwrap_err();
+            assert_eq!(err, ContractError::Unauthorized {});
+            execute(deps.as_mut(), env.clone(), owner, msg).unwrap();
 
-            let resp =
-                query_can_execute(deps.as_ref(), mock_env(), SPENDER1.to_owned(), msg).unwrap();
+            // the allowance is spent within the period
+            send(deps.as_mut(), env.clone(), 6).unwrap();
+            send(deps.as_mut(), env.clone(), 6).unwrap_err();
+            let msg = BankMsg::Send {
+                to_address: SPENDER2.to_owned(),
+                amount: coins(6, TOKEN1),
+            };
+            assert!(
+                !query_can_execute(deps.as_ref(), env.clone(), SPENDER1.to_owned(), msg.into())
+                    .unwrap()
+                    .can_execute
+            );
+            assert_eq!(
+                query_recurring_allowance(deps.as_ref(), env.clone(), SPENDER1.to_owned()).unwrap(),
+                RecurringAllowanceResponse {
+                    spender: SPENDER1.to_owned(),
+                    recurring: Some(RecurringAllowanceInfo {
+                        amount: NativeBalance(coins(10, TOKEN1)),
+                        period: Duration::Height(100),
+                        remaining: NativeBalance(coins(4, TOKEN1)),
+                        resets_at: Expiration::AtHeight(start + 100),
+                    }),
+                }
+            );
 
-            assert_eq!(resp, CanExecuteResponse { can_execute: false });
+            // and reset in later periods, which stay aligned on the first one
+            env.block.height += 250;
+            let recurring =
+                query_recurring_allowance(deps.as_ref(), env.clone(), SPENDER1.to_owned())
+                    .unwrap()
+                    .recurring
+                    .unwrap();
+            assert_eq!(recurring.remaining, NativeBalance(coins(10, TOKEN1)));
+            assert_eq!(recurring.resets_at, Expiration::AtHeight(start + 300));
+            send(deps.as_mut(), env.clone(), 10).unwrap();
+            send(deps.as_mut(), env, 1).unwrap_err();
         }
 
         #[test]
-        fn admin() {
-            let Suite { deps, .. } = SuiteConfig::new().with_admin(ADMIN1).init();
+        fn spent_before_one_shot_allowance() {
+            let Suite { mut deps, owner } = SuiteConfig::new()
+                .with_allowance(SPENDER1, coin(5, TOKEN1))
+                .init();
+            let env = mock_env();
 
-            let msgs = vec![
-                BankMsg::Send {
-                    to_address: SPENDER2.to_owned(),
-                    amount: coins(5, TOKEN1),
-                }
-                .into(),
-                StakingMsg::Delegate {
-                    validator: SPENDER2.to_owned(),
-                    amount: coin(8, TOKEN),
-                }
-                .into(),
-                DistributionMsg::WithdrawDelegatorReward {
-                    validator: SPENDER2.to_owned(),
-                }
-                .into(),
-                CosmosMsg::Custom(Empty {}),
-            ];
+            let msg = ExecuteMsg::SetRecurringAllowance {
+                spender: SPENDER1.to_owned(),
+                amount: coins(10, TOKEN1),
+                period: Duration::Time(30 * 24 * 60 * 60),
+            };
+            execute(deps.as_mut(), env.clone(), owner.clone(), msg).unwrap();
 
-            for msg in msgs {
-                let resp =
-                    query_can_execute(deps.as_ref(), mock_env(), ADMIN1.to_owned(), msg.clone())
-                        .unwrap();
+            // what the recurring allowance cannot cover comes out of the one-shot allowance
+            send(deps.as_mut(), env.clone(), 8).unwrap();
+            send(deps.as_mut(), env.clone(), 5).unwrap();
+            let allowance = query_allowance(deps.as_ref(), env.clone(), SPENDER1.to_owned())
+                .unwrap()
+                .canonical();
+            assert!(allowance.balance.is_empty());
+            let recurring =
+                query_recurring_allowance(deps.as_ref(), env.clone(), SPENDER1.to_owned())
+                    .unwrap()
+                    .recurring
+                    .unwrap();
+            assert_eq!(recurring.remaining, NativeBalance(coins(2, TOKEN1)));
 
-                assert_eq!(
-                    resp,
-                    CanExecuteResponse { can_execute: true },
-                    "Original message: {:#?}",
-                    msg
-                );
-            }
+            // removing it leaves nothing to spend
+            let msg = ExecuteMsg::RemoveRecurringAllowance {
+                spender: SPENDER1.to_owned(),
+            };
+            execute(deps.as_mut(), env.clone(), owner.clone(), msg).unwrap();
+            assert_eq!(
+                query_recurring_allowance(deps.as_ref(), env.clone(), SPENDER1.to_owned())
+                    .unwrap()
+                    .recurring,
+                None
+            );
+            send(deps.as_mut(), env.clone(), 1).unwrap_err();
+
+            // nor can empty ones be set
+            let msg = ExecuteMsg::SetRecurringAllowance {
+                spender: SPENDER1.to_owned(),
+                amount: coins(10, TOKEN1),
+                period: Duration::Height(0),
+            };
+            let err = execute(deps.as_mut(), env, owner, msg).unwrap_err();
+            assert_eq!(err, ContractError::InvalidRecurringAllowance {});
         }
     }
 
-    // tests permissions and allowances are independent features and does not affect each other
     #[test]
     fn permissions_allowances_independent() {
         let mut deps = mock_dependencies();
@@ -2249,6 +4161,8 @@ mod tests {
             redelegate: false,
             undelegate: false,
             withdraw: true,
+            set_withdraw_address: false,
+            ibc_transfer: false,
         };
 
         let info = mock_info(owner, &[]);
@@ -2256,6 +4170,8 @@ mod tests {
         let instantiate_msg = InstantiateMsg {
             admins,
             mutable: true,
+            policy: None,
+            threshold: None,
         };
         instantiate(deps.as_mut(), mock_env(), info.clone(), instantiate_msg).unwrap();
 
@@ -2297,4 +4213,470 @@ mod tests {
         let res_allow = query_allowance(deps.as_ref(), mock_env(), spender2.to_string()).unwrap();
         assert_eq!(allow, res_allow);
     }
+
+    mod all_subkeys {
+        use super::*;
+
+        #[test]
+        fn filtered() {
+            let delegate_only = Permissions {
+                delegate: true,
+                ..NO_PERMS
+            };
+            let expires_soon = Expiration::AtHeight(mock_env().block.height + 50);
+
+            let Suite { deps, .. } = SuiteConfig::new()
+                .with_allowance(SPENDER1, coin(10, TOKEN1))
+                .with_permissions(SPENDER1, ALL_PERMS)
+                .with_allowance(SPENDER2, coin(20, TOKEN2))
+                .expire_allowances(SPENDER2, expires_soon)
+                .with_permissions(SPENDER3, delegate_only)
+                // expired, and without permissions - should not occur in result
+                .with_allowance(SPENDER4, coin(30, TOKEN1))
+                .expire_allowances(SPENDER4, EXPIRED_HEIGHT)
+                .init();
+
+            let spender1 = SubkeyInfo {
+                spender: SPENDER1.to_owned(),
+                balance: NativeBalance(vec![coin(10, TOKEN1)]),
+                expires: Expiration::Never {},
+                permissions: ALL_PERMS,
+            };
+            let spender2 = SubkeyInfo {
+                spender: SPENDER2.to_owned(),
+                balance: NativeBalance(vec![coin(20, TOKEN2)]),
+                expires: expires_soon,
+                permissions: NO_PERMS,
+            };
+            let spender3 = SubkeyInfo {
+                spender: SPENDER3.to_owned(),
+                balance: NativeBalance(vec![]),
+                expires: Expiration::Never {},
+                permissions: delegate_only,
+            };
+
+            let query = |filter: SubkeyFilter, start_after: Option<&str>, limit| {
+                query_all_subkeys(
+                    deps.as_ref(),
+                    mock_env(),
+                    filter,
+                    start_after.map(str::to_owned),
+                    limit,
+                )
+                .unwrap()
+                .subkeys
+            };
+
+            // allowances and permissions are combined, across pages
+            assert_eq!(
+                query(SubkeyFilter::default(), None, Some(2)),
+                vec![spender1.clone(), spender2.clone()]
+            );
+            assert_eq!(
+                query(SubkeyFilter::default(), Some(SPENDER2), Some(2)),
+                vec![spender3.clone()]
+            );
+
+            let by_denom = SubkeyFilter {
+                denom: Some(TOKEN1.to_owned()),
+                ..SubkeyFilter::default()
+            };
+            assert_eq!(query(by_denom, None, None), vec![spender1.clone()]);
+
+            let by_expiration = SubkeyFilter {
+                expires_within: Some(Duration::Height(100)),
+                ..SubkeyFilter::default()
+            };
+            assert_eq!(query(by_expiration, None, None), vec![spender2]);
+            let by_expiration = SubkeyFilter {
+                expires_within: Some(Duration::Height(10)),
+                ..SubkeyFilter::default()
+            };
+            assert_eq!(query(by_expiration, None, None), vec![]);
+
+            let by_permissions = SubkeyFilter {
+                permissions: Some(delegate_only),
+                ..SubkeyFilter::default()
+            };
+            assert_eq!(query(by_permissions, None, Some(1)), vec![spender1.clone()]);
+            let by_permissions = SubkeyFilter {
+                permissions: Some(delegate_only),
+                ..SubkeyFilter::default()
+            };
+            assert_eq!(query(by_permissions, Some(SPENDER1), None), vec![spender3]);
+
+            let combined = SubkeyFilter {
+                denom: Some(TOKEN1.to_owned()),
+                permissions: Some(ALL_PERMS),
+                ..SubkeyFilter::default()
+            };
+            assert_eq!(query(combined, None, None), vec![spender1]);
+        }
+    }
+
+    mod allowance_top_up {
+        use super::*;
+
+        fn send(deps: DepsMut, env: Env, amount: u128) -> Result<Response, ContractError> {
+            let msgs = vec![BankMsg::Send {
+                to_address: SPENDER2.to_owned(),
+                amount: coins(amount, TOKEN1),
+            }
+            .into()];
+            execute(
+                deps,
+                env,
+                mock_info(SPENDER1, &[]),
+                ExecuteMsg::Execute {
+                    msgs,
+                    mode: ExecuteMode::Atomic,
+                },
+            )
+        }
+
+        #[test]
+        fn topped_up_every_period_up_to_cap() {
+            let Suite { mut deps, owner } = SuiteConfig::new()
+                .with_allowance(SPENDER1, coin(5, TOKEN1))
+                .with_allowed_messages(SPENDER1, vec![MessageType::BankSend])
+                .init();
+            let mut env = mock_env();
+            let start = env.block.height;
+
+            // the cap has to cover the amount
+            let msg = ExecuteMsg::SetAllowanceTopUp {
+                spender: SPENDER1.to_owned(),
+                amount: coins(10, TOKEN1),
+                period: Duration::Height(100),
+                cap: coins(5, TOKEN1),
+            };
+            let err = execute(deps.as_mut(), env.clone(), owner.clone(), msg).unwrap_err();
+            assert_eq!(err, ContractError::InvalidAllowanceTopUp {});
+
+            let msg = ExecuteMsg::SetAllowanceTopUp {
+                spender: SPENDER1.to_owned(),
+                amount: coins(10, TOKEN1),
+                period: Duration::Height(100),
+                cap: coins(25, TOKEN1),
+            };
+            let err = execute(
+                deps.as_mut(),
+                env.clone(),
+                mock_info(SPENDER2, &[]),
+                msg.clone(),
+            )
+            .unwrap_err();
+            assert_eq!(err, ContractError::Unauthorized {});
+            execute(deps.as_mut(), env.clone(), owner, msg).unwrap();
+
+            // nothing is added before the first period ends
+            send(deps.as_mut(), env.clone(), 6).unwrap_err();
+            assert_eq!(
+                query_allowance_top_up(deps.as_ref(), env.clone(), SPENDER1.to_owned()).unwrap(),
+                AllowanceTopUpResponse {
+                    spender: SPENDER1.to_owned(),
+                    top_up: Some(AllowanceTopUpInfo {
+                        amount: NativeBalance(coins(10, TOKEN1)),
+                        period: Duration::Height(100),
+                        cap: NativeBalance(coins(25, TOKEN1)),
+                        next_at: Expiration::AtHeight(start + 100),
+                    }),
+                }
+            );
+
+            // top-ups due pile up, but not over the cap
+            env.block.height += 350;
+            let allowance =
+                query_allowance(deps.as_ref(), env.clone(), SPENDER1.to_owned()).unwrap();
+            assert_eq!(allowance.balance, NativeBalance(coins(25, TOKEN1)));
+            let next_at = query_allowance_top_up(deps.as_ref(), env.clone(), SPENDER1.to_owned())
+                .unwrap()
+                .top_up
+                .unwrap()
+                .next_at;
+            assert_eq!(next_at, Expiration::AtHeight(start + 400));
+
+            // and are applied when the subkey executes
+            send(deps.as_mut(), env.clone(), 20).unwrap();
+            send(deps.as_mut(), env.clone(), 6).unwrap_err();
+            env.block.height += 50;
+            send(deps.as_mut(), env.clone(), 15).unwrap();
+            let allowance =
+                query_allowance(deps.as_ref(), env.clone(), SPENDER1.to_owned()).unwrap();
+            assert_eq!(allowance.balance, NativeBalance(vec![]));
+
+            // until removed
+            let msg = ExecuteMsg::RemoveAllowanceTopUp {
+                spender: SPENDER1.to_owned(),
+            };
+            execute(deps.as_mut(), env.clone(), mock_info(OWNER, &[]), msg).unwrap();
+            env.block.height += 100;
+            send(deps.as_mut(), env.clone(), 1).unwrap_err();
+            assert_eq!(
+                query_allowance_top_up(deps.as_ref(), env, SPENDER1.to_owned())
+                    .unwrap()
+                    .top_up,
+                None
+            );
+        }
+    }
+
+    mod ibc_transfer {
+        use super::*;
+        use cosmwasm_std::IbcTimeout;
+
+        fn transfer(channel: &str, amount: u128) -> CosmosMsg {
+            IbcMsg::Transfer {
+                channel_id: channel.to_owned(),
+                to_address: "remote".to_owned(),
+                amount: coin(amount, TOKEN1),
+                timeout: IbcTimeout::with_timestamp(Timestamp::from_seconds(1_000_000_000)),
+            }
+            .into()
+        }
+
+        fn send(deps: DepsMut, spender: &str, msg: CosmosMsg) -> Result<Response, ContractError> {
+            execute(
+                deps,
+                mock_env(),
+                mock_info(spender, &[]),
+                ExecuteMsg::Execute {
+                    msgs: vec![msg],
+                    mode: ExecuteMode::Atomic,
+                },
+            )
+        }
+
+        #[test]
+        fn limited_by_permission_allowance_and_channel() {
+            let bridge = Permissions {
+                ibc_transfer: true,
+                ..NO_PERMS
+            };
+            let Suite { mut deps, owner } = SuiteConfig::new()
+                .with_allowance(SPENDER1, coin(100, TOKEN1))
+                .with_permissions(SPENDER1, bridge)
+                .with_allowed_messages(SPENDER1, vec![MessageType::IbcTransfer])
+                .with_allowance(SPENDER2, coin(100, TOKEN1))
+                .with_permissions(SPENDER2, NO_PERMS)
+                .with_allowed_messages(SPENDER2, vec![MessageType::IbcTransfer])
+                .init();
+
+            // the permission is needed on top of the allowance
+            let err = send(deps.as_mut(), SPENDER2, transfer("channel-0", 10)).unwrap_err();
+            assert_eq!(err, ContractError::IbcTransferPerm {});
+
+            let msg = ExecuteMsg::SetIbcChannelLimit {
+                spender: SPENDER1.to_owned(),
+                channel_id: "channel-0".to_owned(),
+                limit: Some(coins(30, TOKEN1)),
+            };
+            let err = execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(SPENDER2, &[]),
+                msg.clone(),
+            )
+            .unwrap_err();
+            assert_eq!(err, ContractError::Unauthorized {});
+            execute(deps.as_mut(), mock_env(), owner, msg).unwrap();
+
+            // transfers over a limited channel are deducted from its limit
+            send(deps.as_mut(), SPENDER1, transfer("channel-0", 20)).unwrap();
+            let err = send(deps.as_mut(), SPENDER1, transfer("channel-0", 20)).unwrap_err();
+            assert_eq!(
+                err,
+                ContractError::IbcChannelLimitExceeded {
+                    channel: "channel-0".to_owned()
+                }
+            );
+            let can_execute = |deps: Deps, msg| {
+                query_can_execute(deps, mock_env(), SPENDER1.to_owned(), msg)
+                    .unwrap()
+                    .can_execute
+            };
+            assert!(!can_execute(deps.as_ref(), transfer("channel-0", 20)));
+            assert!(can_execute(deps.as_ref(), transfer("channel-0", 10)));
+            assert_eq!(
+                query_ibc_channel_limits(deps.as_ref(), SPENDER1.to_owned(), None, None).unwrap(),
+                IbcChannelLimitsResponse {
+                    spender: SPENDER1.to_owned(),
+                    limits: vec![IbcChannelLimitInfo {
+                        channel_id: "channel-0".to_owned(),
+                        remaining: NativeBalance(coins(10, TOKEN1)),
+                    }],
+                }
+            );
+
+            // and every transfer from the allowance
+            send(deps.as_mut(), SPENDER1, transfer("channel-1", 70)).unwrap();
+            assert!(!can_execute(deps.as_ref(), transfer("channel-1", 20)));
+            send(deps.as_mut(), SPENDER1, transfer("channel-1", 20)).unwrap_err();
+            send(deps.as_mut(), SPENDER1, transfer("channel-0", 10)).unwrap();
+            let allowance =
+                query_allowance(deps.as_ref(), mock_env(), SPENDER1.to_owned()).unwrap();
+            assert_eq!(allowance.balance, NativeBalance(vec![]));
+        }
+
+        #[test]
+        fn best_effort_failure_not_recorded_nor_counted() {
+            let bridge = Permissions {
+                ibc_transfer: true,
+                ..NO_PERMS
+            };
+            let Suite { mut deps, owner } = SuiteConfig::new()
+                .with_allowance(SPENDER1, coin(100, TOKEN1))
+                .with_permissions(SPENDER1, bridge)
+                .with_allowed_messages(SPENDER1, vec![MessageType::IbcTransfer])
+                .init();
+            let msg = ExecuteMsg::SetIbcChannelLimit {
+                spender: SPENDER1.to_owned(),
+                channel_id: "channel-0".to_owned(),
+                limit: Some(coins(50, TOKEN1)),
+            };
+            execute(deps.as_mut(), mock_env(), owner, msg).unwrap();
+
+            let msg = ExecuteMsg::Execute {
+                msgs: vec![transfer("channel-0", 20), transfer("channel-0", 30)],
+                mode: ExecuteMode::BestEffort,
+            };
+            execute(deps.as_mut(), mock_env(), mock_info(SPENDER1, &[]), msg).unwrap();
+            let history = |deps: Deps| {
+                query_spend_history(deps, SPENDER1.to_owned(), None, None)
+                    .unwrap()
+                    .records
+            };
+            assert!(history(deps.as_ref()).is_empty());
+
+            // the first transfer is sent, the second one fails
+            let ok = SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: None,
+            });
+            reply(deps.as_mut(), mock_env(), Reply { id: 0, result: ok }).unwrap();
+            let err = SubMsgResult::Err("channel closed".to_owned());
+            reply(deps.as_mut(), mock_env(), Reply { id: 1, result: err }).unwrap();
+
+            // only the first one is recorded, and taken from the allowance and channel limit
+            let records = history(deps.as_ref());
+            assert_eq!(records.len(), 1);
+            assert_eq!(records[0].funds, coins(20, TOKEN1));
+            let allowance =
+                query_allowance(deps.as_ref(), mock_env(), SPENDER1.to_owned()).unwrap();
+            assert_eq!(allowance.balance, NativeBalance(coins(80, TOKEN1)));
+            let limits =
+                query_ibc_channel_limits(deps.as_ref(), SPENDER1.to_owned(), None, None).unwrap();
+            assert_eq!(limits.limits[0].remaining, NativeBalance(coins(30, TOKEN1)));
+        }
+    }
+
+    mod spend_history {
+        use super::*;
+
+        fn send(deps: DepsMut, sender: MessageInfo, recipients: &[&str]) {
+            let msgs = recipients
+                .iter()
+                .map(|to| {
+                    BankMsg::Send {
+                        to_address: (*to).to_owned(),
+                        amount: coins(10, TOKEN1),
+                    }
+                    .into()
+                })
+                .collect();
+            let msg = ExecuteMsg::Execute {
+                msgs,
+                mode: ExecuteMode::Atomic,
+            };
+            execute(deps, mock_env(), sender, msg).unwrap();
+        }
+
+        fn history(deps: Deps, start_after: Option<u64>, limit: Option<u32>) -> Vec<u64> {
+            query_spend_history(deps, SPENDER1.to_owned(), start_after, limit)
+                .unwrap()
+                .records
+                .into_iter()
+                .map(|record| record.id)
+                .collect()
+        }
+
+        #[test]
+        fn recorded_paginated_and_pruned() {
+            let Suite { mut deps, owner } = SuiteConfig::new()
+                .with_allowance(SPENDER1, coin(1000, TOKEN1))
+                .with_allowed_messages(SPENDER1, vec![MessageType::BankSend])
+                .init();
+            assert_eq!(
+                query_history_retention(deps.as_ref()).unwrap().retention,
+                DEFAULT_HISTORY_RETENTION
+            );
+
+            // only messages relayed by subkeys are recorded
+            send(deps.as_mut(), owner.clone(), &["admin-target"]);
+            send(
+                deps.as_mut(),
+                mock_info(SPENDER1, &[]),
+                &["alice", "bob", "carl"],
+            );
+
+            let records = query_spend_history(deps.as_ref(), SPENDER1.to_owned(), None, None)
+                .unwrap()
+                .records;
+            assert_eq!(
+                records[1],
+                SpendRecordInfo {
+                    id: 1,
+                    time: mock_env().block.time,
+                    target: "bob".to_owned(),
+                    funds: coins(10, TOKEN1),
+                }
+            );
+            assert_eq!(history(deps.as_ref(), None, None), vec![0, 1, 2]);
+            assert_eq!(history(deps.as_ref(), Some(0), Some(1)), vec![1]);
+            assert!(
+                query_spend_history(deps.as_ref(), OWNER.to_owned(), None, None)
+                    .unwrap()
+                    .records
+                    .is_empty()
+            );
+
+            // only admins can change the retention
+            let msg = ExecuteMsg::SetHistoryRetention { retention: 1 };
+            let err = execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(SPENDER1, &[]),
+                msg.clone(),
+            )
+            .unwrap_err();
+            assert_eq!(err, ContractError::Unauthorized {});
+            execute(deps.as_mut(), mock_env(), owner, msg).unwrap();
+
+            // anyone can prune records over the retention
+            let msg = ExecuteMsg::PruneSpendHistory {
+                spender: SPENDER1.to_owned(),
+                limit: Some(1),
+            };
+            execute(deps.as_mut(), mock_env(), mock_info(SPENDER2, &[]), msg).unwrap();
+            assert_eq!(history(deps.as_ref(), None, None), vec![1, 2]);
+
+            // new records prune the oldest ones
+            send(deps.as_mut(), mock_info(SPENDER1, &[]), &["dave"]);
+            assert_eq!(history(deps.as_ref(), None, None), vec![3]);
+        }
+
+        #[test]
+        fn disabled_with_zero_retention() {
+            let Suite { mut deps, owner } = SuiteConfig::new()
+                .with_allowance(SPENDER1, coin(1000, TOKEN1))
+                .with_allowed_messages(SPENDER1, vec![MessageType::BankSend])
+                .init();
+
+            let msg = ExecuteMsg::SetHistoryRetention { retention: 0 };
+            execute(deps.as_mut(), mock_env(), owner, msg).unwrap();
+
+            send(deps.as_mut(), mock_info(SPENDER1, &[]), &["alice"]);
+            assert!(history(deps.as_ref(), None, None).is_empty());
+        }
+    }
 }
@@ -19,6 +19,15 @@ pub enum ContractError {
     #[error("No allowance for this account")]
     NoAllowance {},
 
+    #[error("Recurring allowances need a non-zero amount and period")]
+    InvalidRecurringAllowance {},
+
+    #[error("Allowance top-ups need a non-zero amount and period, and a cap covering the amount")]
+    InvalidAllowanceTopUp {},
+
+    #[error("Method allowlists cannot be empty")]
+    EmptyMethods {},
+
     #[error("Message type rejected")]
     MessageTypeRejected {},
 
@@ -37,6 +46,12 @@ pub enum ContractError {
     #[error("Set withdraw address is not allowed")]
     WithdrawAddrPerm {},
 
+    #[error("IBC transfer is not allowed")]
+    IbcTransferPerm {},
+
+    #[error("IBC transfer exceeds the limit of channel {channel}")]
+    IbcChannelLimitExceeded { channel: String },
+
     #[error("Unsupported message")]
     UnsupportedMessage {},
 
@@ -52,6 +67,11 @@ impl From<cw1_whitelist::ContractError> for ContractError {
         match err {
             cw1_whitelist::ContractError::Std(error) => ContractError::Std(error),
             cw1_whitelist::ContractError::Unauthorized {} => ContractError::Unauthorized {},
+            cw1_whitelist::ContractError::PolicyViolation {} => {
+                ContractError::MessageTypeRejected {}
+            }
+            // session keys and thresholds are not used by subkeys
+            err => ContractError::Std(StdError::generic_err(err.to_string())),
         }
     }
 }
@@ -3,10 +3,11 @@ use schemars::JsonSchema;
 use std::fmt;
 
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Coin, CosmosMsg, Empty};
-use cw_utils::{Expiration, NativeBalance};
+use cosmwasm_std::{Coin, CosmosMsg, Empty, Timestamp, Uint128};
+use cw1::ExecuteMode;
+use cw_utils::{Duration, Expiration, NativeBalance};
 
-use crate::state::Permissions;
+use crate::state::{MessageType, Permissions};
 
 #[cw_serde]
 pub enum ExecuteMsg<T = Empty>
@@ -16,9 +17,21 @@ where
     /// Execute requests the contract to re-dispatch all these messages with the
     /// contract's address as sender. Every implementation has it's own logic to
     /// determine in
-    Execute { msgs: Vec<CosmosMsg<T>> },
+    Execute {
+        msgs: Vec<CosmosMsg<T>>,
+        /// Whether the messages are executed atomically (default), or independently of each other.
+        /// Allowances spent by messages which fail are not given back.
+        #[serde(default)]
+        mode: ExecuteMode,
+    },
     /// Freeze will make a mutable contract immutable, must be called by an admin
-    Freeze {},
+    Freeze {
+        #[serde(default)]
+        until: Option<Expiration>,
+    },
+    /// Unfreeze votes to make a frozen contract mutable again, must be called by an admin. The
+    /// freeze is lifted once at least two thirds of the admins voted for it
+    Unfreeze {},
     /// UpdateAdmins will change the admin set of the contract, must be called by an existing admin,
     /// and only works if the contract is mutable
     UpdateAdmins { admins: Vec<String> },
@@ -36,11 +49,68 @@ where
         expires: Option<Expiration>,
     },
 
+    /// Add a cw20 allowance on the given token contract to a given subkey (subkey must not be
+    /// admin). Cw20 `Transfer` and `Send` messages to the token are deducted from it
+    IncreaseCw20Allowance {
+        spender: String,
+        token: String,
+        amount: Uint128,
+        expires: Option<Expiration>,
+    },
+    /// Decreases a cw20 allowance for a given subkey (subkey must not be admin)
+    DecreaseCw20Allowance {
+        spender: String,
+        token: String,
+        amount: Uint128,
+        expires: Option<Expiration>,
+    },
+    /// Sets a recurring allowance for a given subkey (subkey must not be admin), letting it
+    /// spend up to `amount` every `period`, on top of its one-shot allowance. Replaces any
+    /// previous recurring allowance, starting a new period.
+    SetRecurringAllowance {
+        spender: String,
+        amount: Vec<Coin>,
+        period: Duration,
+    },
+    /// Removes the recurring allowance of a given subkey
+    RemoveRecurringAllowance { spender: String },
+    /// Limits what a given subkey (subkey must not be admin) may send in total over an IBC
+    /// channel, on top of its allowance. Replaces any previous limit on the channel, and None
+    /// removes it.
+    SetIbcChannelLimit {
+        spender: String,
+        channel_id: String,
+        limit: Option<Vec<Coin>>,
+    },
+    /// Tops up the allowance of a given subkey (subkey must not be admin) by `amount` every
+    /// `period`, as long as it stays under `cap`. Replaces any previous top-up, the first one
+    /// being due a period from now.
+    SetAllowanceTopUp {
+        spender: String,
+        amount: Vec<Coin>,
+        period: Duration,
+        cap: Vec<Coin>,
+    },
+    /// Stops topping up the allowance of a given subkey
+    RemoveAllowanceTopUp { spender: String },
+
     // Setups up permissions for a given subkey.
     SetPermissions {
         spender: String,
         permissions: Permissions,
     },
+    /// Replaces the allowlist of message types the subkey may execute.
+    /// Any message type not on the list is rejected.
+    SetAllowedMessages {
+        spender: String,
+        messages: Vec<MessageType>,
+    },
+    /// Sets how many records of spend history are kept per subkey, must be called by an admin.
+    /// Older records are pruned as new ones are added, or with `PruneSpendHistory`.
+    SetHistoryRetention { retention: u32 },
+    /// Removes up to `limit` records of the spend history of `spender` over the retention. Can be
+    /// called by anyone.
+    PruneSpendHistory { spender: String, limit: Option<u32> },
 }
 
 #[cw_serde]
@@ -55,9 +125,52 @@ where
     /// Get the current allowance for the given subkey (how much it can spend)
     #[returns(crate::state::Allowance)]
     Allowance { spender: String },
+    /// Get the current cw20 allowance of the given subkey on the given token contract
+    #[returns(Cw20AllowanceInfo)]
+    Cw20Allowance { spender: String, token: String },
+    /// Gets all cw20 allowances of the given subkey
+    #[returns(AllCw20AllowancesResponse)]
+    AllCw20Allowances {
+        spender: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Gets what is left of the IBC channel limits of the given subkey
+    #[returns(IbcChannelLimitsResponse)]
+    IbcChannelLimits {
+        spender: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Gets the messages relayed by the given subkey, oldest first
+    #[returns(SpendHistoryResponse)]
+    SpendHistory {
+        spender: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Shows how many records of spend history are kept per subkey
+    #[returns(HistoryRetentionResponse)]
+    HistoryRetention {},
+    /// Get the scheduled top-ups of the allowance of the given subkey
+    #[returns(AllowanceTopUpResponse)]
+    AllowanceTopUp { spender: String },
+    /// Get the recurring allowance of the given subkey, and what is left of it in the current
+    /// period
+    #[returns(RecurringAllowanceResponse)]
+    RecurringAllowance { spender: String },
     /// Get the current permissions for the given subkey (how much it can spend)
     #[returns(PermissionsInfo)]
     Permissions { spender: String },
+    /// Get the message types the given subkey is allowed to execute
+    #[returns(AllowedMessagesResponse)]
+    AllowedMessages { spender: String },
+    /// Gets the message types every subkey is allowed to execute
+    #[returns(AllAllowedMessagesResponse)]
+    AllAllowedMessages {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
     /// Checks permissions of the caller on this proxy.
     /// If CanExecute returns true then a call to `Execute` with the same message,
     /// before any further state changes, should also succeed.
@@ -75,6 +188,27 @@ where
         start_after: Option<String>,
         limit: Option<u32>,
     },
+    /// Gets all subkeys having an allowance or permissions, with both of them, optionally
+    /// filtered. `limit` applies to the subkeys matching the filter.
+    #[returns(AllSubkeysResponse)]
+    AllSubkeys {
+        #[serde(default)]
+        filter: SubkeyFilter,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+}
+
+/// Restricts the subkeys returned by `QueryMsg::AllSubkeys`. Every given condition has to be met.
+#[cw_serde]
+#[derive(Default)]
+pub struct SubkeyFilter {
+    /// Only subkeys with a non-empty allowance in this denom
+    pub denom: Option<String>,
+    /// Only subkeys whose allowance expires within this duration from now
+    pub expires_within: Option<Duration>,
+    /// Only subkeys having at least every permission set here
+    pub permissions: Option<Permissions>,
 }
 
 #[cw_serde]
@@ -144,6 +278,86 @@ impl AllowanceInfo {
     }
 }
 
+#[cw_serde]
+pub struct Cw20AllowanceInfo {
+    pub spender: String,
+    pub token: String,
+    pub amount: Uint128,
+    pub expires: Expiration,
+}
+
+#[cw_serde]
+pub struct AllCw20AllowancesResponse {
+    pub allowances: Vec<Cw20AllowanceInfo>,
+}
+
+#[cw_serde]
+pub struct RecurringAllowanceResponse {
+    pub spender: String,
+    /// None if the subkey has no recurring allowance
+    pub recurring: Option<RecurringAllowanceInfo>,
+}
+
+#[cw_serde]
+pub struct RecurringAllowanceInfo {
+    /// What can be spent every period
+    pub amount: NativeBalance,
+    pub period: Duration,
+    /// What is left to spend in the current period
+    pub remaining: NativeBalance,
+    /// When the current period ends, and the remaining balance is reset to `amount`
+    pub resets_at: Expiration,
+}
+
+#[cw_serde]
+pub struct SpendRecordInfo {
+    pub id: u64,
+    pub time: Timestamp,
+    pub target: String,
+    pub funds: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct SpendHistoryResponse {
+    pub spender: String,
+    pub records: Vec<SpendRecordInfo>,
+}
+
+#[cw_serde]
+pub struct HistoryRetentionResponse {
+    pub retention: u32,
+}
+
+#[cw_serde]
+pub struct IbcChannelLimitInfo {
+    pub channel_id: String,
+    /// What the subkey may still send over the channel
+    pub remaining: NativeBalance,
+}
+
+#[cw_serde]
+pub struct IbcChannelLimitsResponse {
+    pub spender: String,
+    pub limits: Vec<IbcChannelLimitInfo>,
+}
+
+#[cw_serde]
+pub struct AllowanceTopUpResponse {
+    pub spender: String,
+    /// None if the allowance of the subkey is not topped up
+    pub top_up: Option<AllowanceTopUpInfo>,
+}
+
+#[cw_serde]
+pub struct AllowanceTopUpInfo {
+    /// What is added to the allowance every period
+    pub amount: NativeBalance,
+    pub period: Duration,
+    pub cap: NativeBalance,
+    /// When the next top-up is due
+    pub next_at: Expiration,
+}
+
 #[cw_serde]
 pub struct PermissionsInfo {
     pub spender: String,
@@ -184,7 +398,32 @@ impl PermissionsInfo {
     }
 }
 
+#[cw_serde]
+pub struct AllowedMessagesResponse {
+    pub spender: String,
+    pub messages: Vec<MessageType>,
+}
+
+#[cw_serde]
+pub struct AllAllowedMessagesResponse {
+    pub allowlists: Vec<AllowedMessagesResponse>,
+}
+
 #[cw_serde]
 pub struct AllPermissionsResponse {
     pub permissions: Vec<PermissionsInfo>,
 }
+
+#[cw_serde]
+pub struct SubkeyInfo {
+    pub spender: String,
+    /// Empty if the subkey has no allowance, or it has expired
+    pub balance: NativeBalance,
+    pub expires: Expiration,
+    pub permissions: Permissions,
+}
+
+#[cw_serde]
+pub struct AllSubkeysResponse {
+    pub subkeys: Vec<SubkeyInfo>,
+}
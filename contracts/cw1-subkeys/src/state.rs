@@ -1,10 +1,14 @@
 use schemars::JsonSchema;
-use serde::{Deserialize, Serialize};
+use serde::de::{IgnoredAny, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::fmt;
 
-use cosmwasm_std::Addr;
-use cw_storage_plus::Map;
-use cw_utils::{Expiration, NativeBalance};
+use cosmwasm_std::{
+    from_slice, Addr, BankMsg, Binary, BlockInfo, Coin, CosmosMsg, IbcMsg, StdResult, Timestamp,
+    Uint128, WasmMsg,
+};
+use cw_storage_plus::{Item, Map};
+use cw_utils::{Duration, Expiration, NativeBalance, Scheduled};
 
 // Permissions struct defines users message execution permissions.
 // Could have implemented permissions for each cosmos module(StakingPermissions, GovPermissions etc...)
@@ -15,19 +19,133 @@ pub struct Permissions {
     pub delegate: bool,
     pub redelegate: bool,
     pub undelegate: bool,
+    /// Withdrawing rewards, to the withdraw address
     pub withdraw: bool,
+    /// Changing the address rewards are withdrawn to, so a subkey compounding rewards cannot
+    /// redirect them
+    #[serde(default)]
+    pub set_withdraw_address: bool,
+    /// Sending tokens over IBC, limited by the allowance and the channel limits of the subkey
+    #[serde(default)]
+    pub ibc_transfer: bool,
+}
+
+impl Permissions {
+    /// Checks if every permission granted by `other` is granted by these permissions as well
+    pub fn includes(&self, other: &Permissions) -> bool {
+        (self.delegate || !other.delegate)
+            && (self.redelegate || !other.redelegate)
+            && (self.undelegate || !other.undelegate)
+            && (self.withdraw || !other.withdraw)
+            && (self.set_withdraw_address || !other.set_withdraw_address)
+            && (self.ibc_transfer || !other.ibc_transfer)
+    }
 }
 
 impl fmt::Display for Permissions {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
-            "staking: {{ delegate: {}, redelegate: {}, undelegate: {}, withdraw: {} }}",
-            self.delegate, self.redelegate, self.undelegate, self.withdraw
+            "staking: {{ delegate: {}, redelegate: {}, undelegate: {}, withdraw: {}, set_withdraw_address: {} }}, ibc: {{ transfer: {} }}",
+            self.delegate,
+            self.redelegate,
+            self.undelegate,
+            self.withdraw,
+            self.set_withdraw_address,
+            self.ibc_transfer
         )
     }
 }
 
+/// MessageType is an entry of a subkey's allowlist. Any message which is not covered by the
+/// allowlist is rejected, even if the subkey has enough allowance or permissions for it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageType {
+    /// `BankMsg::Send`, limited by the subkey's allowance
+    BankSend,
+    /// `StakingMsg`, limited by the subkey's permissions
+    Staking,
+    /// `DistributionMsg`, limited by the subkey's permissions
+    Distribution,
+    /// `IbcMsg::Transfer`, limited by the subkey's permissions, allowance and channel limits
+    IbcTransfer,
+    /// `WasmMsg::Execute` on the given contract. Funds sent along are deducted from the allowance.
+    /// If `methods` are given, only messages calling one of them (the only key of the JSON
+    /// message, eg. `transfer` for `{"transfer": {...}}`) are allowed.
+    WasmExecute {
+        contract: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        methods: Option<Vec<String>>,
+    },
+}
+
+impl MessageType {
+    /// Checks if the given message is covered by this entry
+    pub fn covers<T>(&self, msg: &CosmosMsg<T>) -> bool {
+        match (self, msg) {
+            (MessageType::BankSend, CosmosMsg::Bank(BankMsg::Send { .. })) => true,
+            (MessageType::Staking, CosmosMsg::Staking(_)) => true,
+            (MessageType::Distribution, CosmosMsg::Distribution(_)) => true,
+            (MessageType::IbcTransfer, CosmosMsg::Ibc(IbcMsg::Transfer { .. })) => true,
+            (
+                MessageType::WasmExecute { contract, methods },
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr, msg, ..
+                }),
+            ) => {
+                contract == contract_addr
+                    && match methods {
+                        Some(methods) => match execute_method(msg) {
+                            Some(method) => methods.contains(&method),
+                            None => false,
+                        },
+                        None => true,
+                    }
+            }
+            _ => false,
+        }
+    }
+}
+
+/// The method called by a wasm execute message, if it is a JSON object with a single key
+fn execute_method(msg: &Binary) -> Option<String> {
+    let ObjectKeys(keys) = from_slice(msg).ok()?;
+    match <[String; 1]>::try_from(keys) {
+        Ok([method]) => Some(method),
+        Err(_) => None,
+    }
+}
+
+/// The keys of a JSON object. It is read as a struct, as the contract JSON parser does not
+/// support maps.
+struct ObjectKeys(Vec<String>);
+
+impl<'de> Deserialize<'de> for ObjectKeys {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct KeysVisitor;
+
+        impl<'de> Visitor<'de> for KeysVisitor {
+            type Value = ObjectKeys;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a JSON object")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<ObjectKeys, A::Error> {
+                let mut keys = vec![];
+                // keys can only be read as borrowed strings
+                while let Some((key, IgnoredAny)) = map.next_entry::<&str, IgnoredAny>()? {
+                    keys.push(key.to_owned());
+                }
+                Ok(ObjectKeys(keys))
+            }
+        }
+
+        deserializer.deserialize_struct("ObjectKeys", &[], KeysVisitor)
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
 pub struct Allowance {
     pub balance: NativeBalance,
@@ -68,5 +186,214 @@ impl Allowance {
     }
 }
 
+/// RecurringAllowance lets a subkey spend up to `amount` per `period`. The window starts when the
+/// allowance is set, and the remaining balance is reset to `amount` every period after that.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RecurringAllowance {
+    pub amount: NativeBalance,
+    pub period: Duration,
+    /// Start of the current window
+    pub window_start: Scheduled,
+    /// What is left to spend in the current window
+    pub remaining: NativeBalance,
+}
+
+impl RecurringAllowance {
+    pub fn new(amount: NativeBalance, period: Duration, block: &BlockInfo) -> Self {
+        let window_start = match period {
+            Duration::Height(_) => Scheduled::AtHeight(block.height),
+            Duration::Time(_) => Scheduled::AtTime(block.time),
+        };
+        RecurringAllowance {
+            remaining: amount.clone(),
+            amount,
+            period,
+            window_start,
+        }
+    }
+
+    /// Moves to the window of `block` if the current one is over, resetting the remaining balance
+    pub fn current(mut self, block: &BlockInfo) -> StdResult<Self> {
+        if !(self.window_start + self.period)?.is_triggered(block) {
+            return Ok(self);
+        }
+        // skip the windows in which nothing was spent, so they stay aligned on the first one
+        let elapsed = match (self.window_start, self.period) {
+            (Scheduled::AtHeight(start), Duration::Height(period)) => {
+                (block.height - start) / period
+            }
+            (Scheduled::AtTime(start), Duration::Time(period)) => {
+                (block.time.seconds() - start.seconds()) / period
+            }
+            _ => 1,
+        };
+        self.window_start = (self.window_start + self.period * elapsed)?;
+        self.remaining = self.amount.clone();
+        Ok(self)
+    }
+
+    /// When the current window ends
+    pub fn resets_at(&self) -> StdResult<Expiration> {
+        Ok(match (self.window_start + self.period)? {
+            Scheduled::AtHeight(height) => Expiration::AtHeight(height),
+            Scheduled::AtTime(time) => Expiration::AtTime(time),
+        })
+    }
+}
+
+/// AllowanceTopUp adds `amount` to the allowance of a subkey every `period`, up to `cap`. It is
+/// applied lazily, the next time the subkey executes something.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AllowanceTopUp {
+    pub amount: NativeBalance,
+    pub period: Duration,
+    /// The allowance is never topped up above it. It covers every denom of `amount`.
+    pub cap: NativeBalance,
+    /// When the next top-up is due
+    pub next: Scheduled,
+}
+
+impl AllowanceTopUp {
+    pub fn new(
+        amount: NativeBalance,
+        period: Duration,
+        cap: NativeBalance,
+        block: &BlockInfo,
+    ) -> StdResult<Self> {
+        let start = match period {
+            Duration::Height(_) => Scheduled::AtHeight(block.height),
+            Duration::Time(_) => Scheduled::AtTime(block.time),
+        };
+        Ok(AllowanceTopUp {
+            amount,
+            period,
+            cap,
+            next: (start + period)?,
+        })
+    }
+
+    /// Adds the top-ups due at `block` to the allowance, returning whether any was due. An expired
+    /// allowance is replaced by a new one, which never expires.
+    pub fn apply(&mut self, allowance: &mut Allowance, block: &BlockInfo) -> StdResult<bool> {
+        if !self.next.is_triggered(block) {
+            return Ok(false);
+        }
+        let due = 1 + match (self.next, self.period) {
+            (Scheduled::AtHeight(next), Duration::Height(period)) => (block.height - next) / period,
+            (Scheduled::AtTime(next), Duration::Time(period)) => {
+                (block.time.seconds() - next.seconds()) / period
+            }
+            _ => 0,
+        };
+        self.next = (self.next + self.period * due)?;
+
+        if allowance.expires.is_expired(block) {
+            *allowance = Allowance::default();
+        }
+        for coin in &self.amount.0 {
+            let cap = self
+                .cap
+                .0
+                .iter()
+                .find(|cap| cap.denom == coin.denom)
+                .map(|cap| cap.amount)
+                .unwrap_or_default();
+            let current = allowance
+                .balance
+                .0
+                .iter()
+                .find(|allowed| allowed.denom == coin.denom)
+                .map(|allowed| allowed.amount)
+                .unwrap_or_default();
+            let topped_up = current
+                .saturating_add(coin.amount.saturating_mul(Uint128::from(due)))
+                .min(cap);
+            if topped_up > current {
+                allowance.balance += Coin {
+                    denom: coin.denom.clone(),
+                    amount: topped_up - current,
+                };
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// SpendRecord is an entry of the spend history of a subkey, kept for audits
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SpendRecord {
+    pub time: Timestamp,
+    /// Recipient of the message: the address, contract, validator or IBC channel it targets
+    pub target: String,
+    /// Funds leaving the proxy with the message
+    pub funds: Vec<Coin>,
+}
+
+/// Position of the spend history of a subkey. Records `oldest..next` are kept.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema, Default)]
+pub struct SpendHistoryBounds {
+    pub oldest: u64,
+    pub next: u64,
+}
+
+/// Cw20Allowance is what a subkey may transfer of a cw20 token held by the proxy
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct Cw20Allowance {
+    pub amount: Uint128,
+    pub expires: Expiration,
+}
+
+/// The allowance the funds of a message relayed by a subkey were taken from
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Deduction {
+    Recurring(Vec<Coin>),
+    OneShot(Vec<Coin>),
+}
+
+/// What a message relayed by a subkey in best-effort mode took from its allowances and channel
+/// limits, given back if the message fails
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PendingDebit {
+    pub spender: Addr,
+    pub funds: Option<Deduction>,
+    /// The cw20 token transferred, and the amount
+    pub cw20: Option<(Addr, Uint128)>,
+    /// The limited IBC channel transferred over, and the amount
+    pub ibc_channel: Option<(String, Coin)>,
+    /// Added to the spend history if the message succeeds
+    pub spend: SpendRecord,
+}
+
+/// Permissions as stored before `set_withdraw_address` was split from `withdraw`, which allowed
+/// both. Only used for migration.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct LegacyPermissions {
+    pub delegate: bool,
+    pub redelegate: bool,
+    pub undelegate: bool,
+    pub withdraw: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub set_withdraw_address: Option<bool>,
+}
+
 pub const PERMISSIONS: Map<&Addr, Permissions> = Map::new("permissions");
+pub const LEGACY_PERMISSIONS: Map<&Addr, LegacyPermissions> = Map::new("permissions");
 pub const ALLOWANCES: Map<&Addr, Allowance> = Map::new("allowances");
+/// Cw20 allowances by spender and token contract
+pub const CW20_ALLOWANCES: Map<(&Addr, &Addr), Cw20Allowance> = Map::new("cw20_allowances");
+/// What is left of the limits on IBC transfers by spender and channel. Transfers over channels
+/// without a limit are only restricted by the allowance.
+pub const IBC_CHANNEL_LIMITS: Map<(&Addr, &str), NativeBalance> = Map::new("ibc_channel_limits");
+pub const RECURRING_ALLOWANCES: Map<&Addr, RecurringAllowance> = Map::new("recurring_allowances");
+pub const ALLOWANCE_TOP_UPS: Map<&Addr, AllowanceTopUp> = Map::new("allowance_top_ups");
+/// Spend history by spender and record id
+pub const SPEND_HISTORY: Map<(&Addr, u64), SpendRecord> = Map::new("spend_history");
+pub const SPEND_HISTORY_BOUNDS: Map<&Addr, SpendHistoryBounds> = Map::new("spend_history_bounds");
+/// How many records of spend history are kept per subkey, `DEFAULT_HISTORY_RETENTION` if missing
+pub const HISTORY_RETENTION: Item<u32> = Item::new("history_retention");
+pub const DEFAULT_HISTORY_RETENTION: u32 = 100;
+/// Allowlist of message types per subkey. Missing entry means nothing is allowed.
+pub const ALLOWED_MESSAGES: Map<&Addr, Vec<MessageType>> = Map::new("allowed_messages");
+/// Debits of the best-effort messages waiting for their reply, by reply id
+pub const PENDING_DEBITS: Map<u64, PendingDebit> = Map::new("pending_debits");
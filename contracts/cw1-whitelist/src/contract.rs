@@ -1,19 +1,30 @@
 use schemars::JsonSchema;
+use serde::{de::DeserializeOwned, Serialize};
 use std::fmt;
 
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    to_binary, Addr, Api, Binary, CosmosMsg, Deps, DepsMut, Empty, Env, MessageInfo, Response,
-    StdResult,
+    ensure, to_binary, Addr, Api, Binary, BlockInfo, CosmosMsg, Deps, DepsMut, Empty, Env, Event,
+    MessageInfo, Order, Reply, Response, StdError, StdResult, Storage, SubMsg, SubMsgResult,
 };
 
-use cw1::CanExecuteResponse;
+use cw1::{CanExecuteResponse, ExecuteMode};
 use cw2::set_contract_version;
+use cw_storage_plus::Bound;
+use cw_utils::Expiration;
 
 use crate::error::ContractError;
-use crate::msg::{AdminListResponse, ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::state::{AdminList, ADMIN_LIST};
+use crate::msg::{
+    AdminListResponse, ExecuteMsg, FreezeResponse, InstantiateMsg, ListPendingExecutionsResponse,
+    ListSessionKeysResponse, PendingExecutionResponse, PolicyResponse, QueryMsg, SessionKeyInfo,
+    SessionKeyResponse, ThresholdResponse,
+};
+use crate::state::{
+    admin_policy, load_admin_list, pending_executions, AdminList, PendingExecution, Policy,
+    SessionKey, ADMIN_LIST, ADMIN_POLICIES, FROZEN_UNTIL, GLOBAL_POLICY, PENDING_COUNT,
+    SESSION_KEYS, THRESHOLD, UNFREEZE_VOTES,
+};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:cw1-whitelist";
@@ -32,6 +43,17 @@ pub fn instantiate(
         mutable: msg.mutable,
     };
     ADMIN_LIST.save(deps.storage, &cfg)?;
+    if let Some(policy) = msg.policy {
+        GLOBAL_POLICY.save(deps.storage, &policy)?;
+    }
+    if let Some(threshold) = msg.threshold {
+        if !cfg.is_valid_threshold(threshold) {
+            return Err(StdError::generic_err(
+                ContractError::InvalidThreshold {}.to_string(),
+            ));
+        }
+        THRESHOLD.save(deps.storage, &threshold)?;
+    }
     Ok(Response::default())
 }
 
@@ -49,59 +71,263 @@ pub fn execute(
     msg: ExecuteMsg<Empty>,
 ) -> Result<Response<Empty>, ContractError> {
     match msg {
-        ExecuteMsg::Execute { msgs } => execute_execute(deps, env, info, msgs),
-        ExecuteMsg::Freeze {} => execute_freeze(deps, env, info),
+        ExecuteMsg::Execute { msgs, mode } => execute_execute(deps, env, info, msgs, mode),
+        ExecuteMsg::Approve { id } => execute_approve(deps, env, info, id),
+        ExecuteMsg::CancelPending { id } => execute_cancel_pending::<Empty>(deps, env, info, id),
+        ExecuteMsg::SetThreshold { threshold } => execute_set_threshold(deps, env, info, threshold),
+        ExecuteMsg::Freeze { until } => execute_freeze(deps, env, info, until),
+        ExecuteMsg::Unfreeze {} => execute_unfreeze(deps, env, info),
         ExecuteMsg::UpdateAdmins { admins } => execute_update_admins(deps, env, info, admins),
+        ExecuteMsg::SetPolicy { admin, policy } => {
+            execute_set_policy(deps, env, info, admin, policy)
+        }
+        ExecuteMsg::GrantSession {
+            key,
+            expires,
+            policy,
+        } => execute_grant_session(deps, env, info, key, expires, policy),
+        ExecuteMsg::RevokeSession { key } => execute_revoke_session(deps, env, info, key),
+        ExecuteMsg::PruneSessions { limit } => execute_prune_sessions(deps, env, info, limit),
     }
 }
 
 pub fn execute_execute<T>(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msgs: Vec<CosmosMsg<T>>,
+    mode: ExecuteMode,
 ) -> Result<Response<T>, ContractError>
 where
-    T: Clone + fmt::Debug + PartialEq + JsonSchema,
+    T: Clone + fmt::Debug + PartialEq + JsonSchema + Serialize + DeserializeOwned,
 {
-    if !can_execute(deps.as_ref(), info.sender.as_ref())? {
-        Err(ContractError::Unauthorized {})
-    } else {
+    let admin = check_execute(deps.storage, &env.block, &info.sender, &msgs)?;
+
+    // with a threshold, messages wait for the approval of other admins
+    let threshold = THRESHOLD.may_load(deps.storage)?.unwrap_or(1);
+    if threshold > 1 {
+        let id = PENDING_COUNT.may_load(deps.storage)?.unwrap_or_default() + 1;
+        PENDING_COUNT.save(deps.storage, &id)?;
+        let pending = PendingExecution {
+            proposer: admin.clone(),
+            msgs,
+            mode,
+            approvals: vec![admin],
+        };
+        pending_executions().save(deps.storage, id, &pending)?;
+
         let res = Response::new()
-            .add_messages(msgs)
-            .add_attribute("action", "execute");
-        Ok(res)
+            .add_attribute("action", "propose_execution")
+            .add_attribute("id", id.to_string());
+        return Ok(res);
     }
+
+    let res = Response::new().add_attribute("action", "execute");
+    Ok(dispatch_msgs(res, msgs, mode))
 }
 
-pub fn execute_freeze(
+/// Adds the messages to the response, as plain messages if the execution is atomic, or as
+/// submessages reporting their outcome to `reply` otherwise. Replies are identified by the
+/// position of the message.
+pub fn dispatch_msgs<T>(
+    res: Response<T>,
+    msgs: Vec<CosmosMsg<T>>,
+    mode: ExecuteMode,
+) -> Response<T> {
+    match mode {
+        ExecuteMode::Atomic => res.add_messages(msgs),
+        ExecuteMode::BestEffort => res.add_submessages(
+            msgs.into_iter()
+                .enumerate()
+                .map(|(index, msg)| SubMsg::reply_always(msg, index as u64)),
+        ),
+    }
+}
+
+/// Reports the outcome of messages executed in `ExecuteMode::BestEffort`
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(_deps: DepsMut, _env: Env, reply: Reply) -> Result<Response, ContractError> {
+    let event = Event::new("execute_result").add_attribute("index", reply.id.to_string());
+    let event = match reply.result {
+        SubMsgResult::Ok(_) => event.add_attribute("status", "success"),
+        SubMsgResult::Err(err) => event
+            .add_attribute("status", "failure")
+            .add_attribute("error", err),
+    };
+    Ok(Response::new().add_event(event))
+}
+
+pub fn execute_approve<T>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response<T>, ContractError>
+where
+    T: Clone + fmt::Debug + PartialEq + JsonSchema + Serialize + DeserializeOwned,
+{
+    let mut pending = pending_executions::<T>().load(deps.storage, id)?;
+    // approvers must be allowed to execute the messages themselves
+    let admin = check_execute(deps.storage, &env.block, &info.sender, &pending.msgs)?;
+    if pending.approvals.contains(&admin) {
+        return Err(ContractError::AlreadyApproved {});
+    }
+    pending.approvals.push(admin);
+
+    let cfg = ADMIN_LIST.load(deps.storage)?;
+    let threshold = THRESHOLD.may_load(deps.storage)?.unwrap_or(1);
+    let res = Response::new()
+        .add_attribute("action", "approve_execution")
+        .add_attribute("id", id.to_string());
+    if pending.approvals(&cfg) >= threshold {
+        pending_executions::<T>().remove(deps.storage, id);
+        let res = res.add_attribute("status", "executed");
+        Ok(dispatch_msgs(res, pending.msgs, pending.mode))
+    } else {
+        pending_executions().save(deps.storage, id, &pending)?;
+        Ok(res.add_attribute("status", "pending"))
+    }
+}
+
+pub fn execute_cancel_pending<T>(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError>
+where
+    T: Clone + fmt::Debug + PartialEq + JsonSchema + Serialize + DeserializeOwned,
+{
+    let pending = pending_executions::<T>().load(deps.storage, id)?;
+    ensure!(
+        pending.proposer == info.sender,
+        ContractError::Unauthorized {}
+    );
+    pending_executions::<T>().remove(deps.storage, id);
+
+    let res = Response::new()
+        .add_attribute("action", "cancel_pending")
+        .add_attribute("id", id.to_string());
+    Ok(res)
+}
+
+pub fn execute_set_threshold(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    threshold: Option<u32>,
 ) -> Result<Response, ContractError> {
-    let mut cfg = ADMIN_LIST.load(deps.storage)?;
+    let cfg = load_admin_list(deps.storage, &env.block)?;
+    if !cfg.can_modify(info.sender.as_ref()) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    match threshold {
+        Some(threshold) => {
+            ensure!(
+                cfg.is_valid_threshold(threshold),
+                ContractError::InvalidThreshold {}
+            );
+            THRESHOLD.save(deps.storage, &threshold)?;
+        }
+        None => THRESHOLD.remove(deps.storage),
+    }
+
+    let res = Response::new()
+        .add_attribute("action", "set_threshold")
+        .add_attribute(
+            "threshold",
+            threshold.map_or_else(|| "none".to_owned(), |threshold| threshold.to_string()),
+        );
+    Ok(res)
+}
+
+pub fn execute_freeze(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    until: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    let mut cfg = load_admin_list(deps.storage, &env.block)?;
     if !cfg.can_modify(info.sender.as_ref()) {
         Err(ContractError::Unauthorized {})
     } else {
+        let mut res = Response::new().add_attribute("action", "freeze");
+        match until.filter(|until| *until != Expiration::Never {}) {
+            Some(until) => {
+                ensure!(
+                    !until.is_expired(&env.block),
+                    ContractError::InvalidFreezeExpiry {}
+                );
+                FROZEN_UNTIL.save(deps.storage, &until)?;
+                res = res.add_attribute("until", until.to_string());
+            }
+            None => FROZEN_UNTIL.remove(deps.storage),
+        }
+
         cfg.mutable = false;
         ADMIN_LIST.save(deps.storage, &cfg)?;
-
-        let res = Response::new().add_attribute("action", "freeze");
+        UNFREEZE_VOTES.remove(deps.storage);
         Ok(res)
     }
 }
 
+pub fn execute_unfreeze(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let mut cfg = load_admin_list(deps.storage, &env.block)?;
+    ensure!(
+        cfg.is_admin(info.sender.as_ref()),
+        ContractError::Unauthorized {}
+    );
+    ensure!(!cfg.mutable, ContractError::NotFrozen {});
+
+    let mut votes = UNFREEZE_VOTES.may_load(deps.storage)?.unwrap_or_default();
+    ensure!(
+        !votes.contains(&info.sender),
+        ContractError::AlreadyApproved {}
+    );
+    votes.push(info.sender);
+
+    // votes of former admins do not count
+    let count = votes
+        .iter()
+        .filter(|voter| cfg.is_admin(voter.as_ref()))
+        .count();
+    let res = Response::new()
+        .add_attribute("action", "unfreeze")
+        .add_attribute("votes", count.to_string());
+    if count * 3 >= cfg.admins.len() * 2 {
+        cfg.mutable = true;
+        ADMIN_LIST.save(deps.storage, &cfg)?;
+        FROZEN_UNTIL.remove(deps.storage);
+        UNFREEZE_VOTES.remove(deps.storage);
+        Ok(res.add_attribute("status", "unfrozen"))
+    } else {
+        UNFREEZE_VOTES.save(deps.storage, &votes)?;
+        Ok(res.add_attribute("status", "pending"))
+    }
+}
+
 pub fn execute_update_admins(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     admins: Vec<String>,
 ) -> Result<Response, ContractError> {
-    let mut cfg = ADMIN_LIST.load(deps.storage)?;
+    let mut cfg = load_admin_list(deps.storage, &env.block)?;
     if !cfg.can_modify(info.sender.as_ref()) {
         Err(ContractError::Unauthorized {})
     } else {
         cfg.admins = map_validate(deps.api, &admins)?;
+        if let Some(threshold) = THRESHOLD.may_load(deps.storage)? {
+            ensure!(
+                cfg.is_valid_threshold(threshold),
+                ContractError::InvalidThreshold {}
+            );
+        }
         ADMIN_LIST.save(deps.storage, &cfg)?;
 
         let res = Response::new().add_attribute("action", "update_admins");
@@ -109,22 +335,188 @@ pub fn execute_update_admins(
     }
 }
 
-fn can_execute(deps: Deps, sender: &str) -> StdResult<bool> {
+pub fn execute_set_policy(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    admin: Option<String>,
+    policy: Option<Policy>,
+) -> Result<Response, ContractError> {
+    let cfg = load_admin_list(deps.storage, &env.block)?;
+    if !cfg.can_modify(info.sender.as_ref()) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let admin = admin
+        .map(|admin| deps.api.addr_validate(&admin))
+        .transpose()?;
+    match (&admin, policy) {
+        (Some(admin), Some(policy)) => ADMIN_POLICIES.save(deps.storage, admin, &policy)?,
+        (Some(admin), None) => ADMIN_POLICIES.remove(deps.storage, admin),
+        (None, Some(policy)) => GLOBAL_POLICY.save(deps.storage, &policy)?,
+        (None, None) => GLOBAL_POLICY.remove(deps.storage),
+    }
+
+    let res = Response::new()
+        .add_attribute("action", "set_policy")
+        .add_attribute(
+            "admin",
+            admin.map_or_else(|| "global".to_owned(), String::from),
+        );
+    Ok(res)
+}
+
+pub fn execute_grant_session(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    key: String,
+    expires: Expiration,
+    policy: Option<Policy>,
+) -> Result<Response, ContractError> {
+    let cfg = ADMIN_LIST.load(deps.storage)?;
+    ensure!(
+        cfg.is_admin(info.sender.as_ref()),
+        ContractError::Unauthorized {}
+    );
+    ensure!(
+        expires != Expiration::Never {} && !expires.is_expired(&env.block),
+        ContractError::InvalidSessionExpiry {}
+    );
+
+    let key_addr = deps.api.addr_validate(&key)?;
+    let session = SessionKey {
+        granter: info.sender.clone(),
+        expires,
+        policy,
+    };
+    SESSION_KEYS.save(deps.storage, &key_addr, &session)?;
+
+    let res = Response::new()
+        .add_attribute("action", "grant_session")
+        .add_attribute("granter", info.sender)
+        .add_attribute("key", key)
+        .add_attribute("expires", expires.to_string());
+    Ok(res)
+}
+
+pub fn execute_revoke_session(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    key: String,
+) -> Result<Response, ContractError> {
     let cfg = ADMIN_LIST.load(deps.storage)?;
-    let can = cfg.is_admin(&sender);
-    Ok(can)
+    ensure!(
+        cfg.is_admin(info.sender.as_ref()),
+        ContractError::Unauthorized {}
+    );
+
+    let key_addr = deps.api.addr_validate(&key)?;
+    SESSION_KEYS.remove(deps.storage, &key_addr);
+
+    let res = Response::new()
+        .add_attribute("action", "revoke_session")
+        .add_attribute("key", key);
+    Ok(res)
+}
+
+pub fn execute_prune_sessions(
+    deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let limit = calc_limit(limit);
+    let expired = SESSION_KEYS
+        .range(deps.storage, None, None, Order::Ascending)
+        .filter(|item| {
+            item.as_ref()
+                .map_or(true, |(_, session)| session.expires.is_expired(&env.block))
+        })
+        .take(limit)
+        .map(|item| item.map(|(key, _)| key))
+        .collect::<StdResult<Vec<_>>>()?;
+    for key in &expired {
+        SESSION_KEYS.remove(deps.storage, key);
+    }
+
+    let res = Response::new()
+        .add_attribute("action", "prune_sessions")
+        .add_attribute("pruned", expired.len().to_string());
+    Ok(res)
+}
+
+/// Checks that `sender` may relay `msgs`: admins within their policy, and session keys within
+/// their own and the one of the admin who granted them, as long as they are still an admin.
+/// Returns the admin on behalf of whom they are relayed
+fn check_execute<T>(
+    storage: &dyn Storage,
+    block: &BlockInfo,
+    sender: &Addr,
+    msgs: &[CosmosMsg<T>],
+) -> Result<Addr, ContractError> {
+    let cfg = ADMIN_LIST.load(storage)?;
+    let admin = if cfg.is_admin(sender) {
+        sender.clone()
+    } else {
+        // expired sessions are rejected even before they are pruned
+        let session = SESSION_KEYS
+            .may_load(storage, sender)?
+            .filter(|session| !session.expires.is_expired(block) && cfg.is_admin(&session.granter))
+            .ok_or(ContractError::Unauthorized {})?;
+        if let Some(policy) = session.policy {
+            ensure!(
+                msgs.iter().all(|msg| policy.allows(msg)),
+                ContractError::PolicyViolation {}
+            );
+        }
+        session.granter
+    };
+
+    if let Some(policy) = admin_policy(storage, &admin)? {
+        ensure!(
+            msgs.iter().all(|msg| policy.allows(msg)),
+            ContractError::PolicyViolation {}
+        );
+    }
+    Ok(admin)
+}
+
+fn can_execute<T>(deps: Deps, env: &Env, sender: &str, msg: CosmosMsg<T>) -> StdResult<bool> {
+    let sender = Addr::unchecked(sender);
+    match check_execute(deps.storage, &env.block, &sender, &[msg]) {
+        Ok(_) => Ok(true),
+        Err(ContractError::Std(err)) => Err(err),
+        Err(_) => Ok(false),
+    }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
-        QueryMsg::AdminList {} => to_binary(&query_admin_list(deps)?),
-        QueryMsg::CanExecute { sender, msg } => to_binary(&query_can_execute(deps, sender, msg)?),
+        QueryMsg::AdminList {} => to_binary(&query_admin_list(deps, env)?),
+        QueryMsg::Freeze {} => to_binary(&query_freeze(deps, env)?),
+        QueryMsg::CanExecute { sender, msg } => {
+            to_binary(&query_can_execute(deps, env, sender, msg)?)
+        }
+        QueryMsg::Policy { admin } => to_binary(&query_policy(deps, admin)?),
+        QueryMsg::Threshold {} => to_binary(&ThresholdResponse {
+            threshold: THRESHOLD.may_load(deps.storage)?,
+        }),
+        QueryMsg::PendingExecution { id } => to_binary(&query_pending_execution(deps, id)?),
+        QueryMsg::ListPendingExecutions { start_after, limit } => {
+            to_binary(&query_list_pending_executions(deps, start_after, limit)?)
+        }
+        QueryMsg::SessionKey { key } => to_binary(&query_session_key(deps, key)?),
+        QueryMsg::ListSessionKeys { start_after, limit } => {
+            to_binary(&query_list_session_keys(deps, start_after, limit)?)
+        }
     }
 }
 
-pub fn query_admin_list(deps: Deps) -> StdResult<AdminListResponse> {
-    let cfg = ADMIN_LIST.load(deps.storage)?;
+pub fn query_admin_list(deps: Deps, env: Env) -> StdResult<AdminListResponse> {
+    let cfg = load_admin_list(deps.storage, &env.block)?;
     Ok(AdminListResponse {
         admins: cfg.admins.into_iter().map(|a| a.into()).collect(),
         mutable: cfg.mutable,
@@ -133,19 +525,117 @@ pub fn query_admin_list(deps: Deps) -> StdResult<AdminListResponse> {
 
 pub fn query_can_execute(
     deps: Deps,
+    env: Env,
     sender: String,
-    _msg: CosmosMsg,
+    msg: CosmosMsg,
 ) -> StdResult<CanExecuteResponse> {
     Ok(CanExecuteResponse {
-        can_execute: can_execute(deps, &sender)?,
+        can_execute: can_execute(deps, &env, &sender, msg)?,
     })
 }
 
+pub fn query_policy(deps: Deps, admin: Option<String>) -> StdResult<PolicyResponse> {
+    let policy = match admin {
+        Some(admin) => admin_policy(deps.storage, &deps.api.addr_validate(&admin)?)?,
+        None => GLOBAL_POLICY.may_load(deps.storage)?,
+    };
+    Ok(PolicyResponse { policy })
+}
+
+pub fn query_freeze(deps: Deps, env: Env) -> StdResult<FreezeResponse> {
+    let cfg = load_admin_list(deps.storage, &env.block)?;
+    if cfg.mutable {
+        return Ok(FreezeResponse {
+            frozen: false,
+            until: None,
+            unfreeze_votes: vec![],
+        });
+    }
+    let votes = UNFREEZE_VOTES.may_load(deps.storage)?.unwrap_or_default();
+    Ok(FreezeResponse {
+        frozen: true,
+        until: FROZEN_UNTIL.may_load(deps.storage)?,
+        unfreeze_votes: votes.into_iter().map(String::from).collect(),
+    })
+}
+
+fn pending_info(id: u64, pending: PendingExecution) -> PendingExecutionResponse {
+    PendingExecutionResponse {
+        id,
+        proposer: pending.proposer.into(),
+        msgs: pending.msgs,
+        mode: pending.mode,
+        approvals: pending.approvals.into_iter().map(String::from).collect(),
+    }
+}
+
+pub fn query_pending_execution(deps: Deps, id: u64) -> StdResult<PendingExecutionResponse> {
+    let pending = pending_executions().load(deps.storage, id)?;
+    Ok(pending_info(id, pending))
+}
+
+pub fn query_list_pending_executions(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ListPendingExecutionsResponse> {
+    let limit = calc_limit(limit);
+    let start = start_after.map(Bound::exclusive);
+
+    let executions = pending_executions()
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(id, pending)| pending_info(id, pending)))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(ListPendingExecutionsResponse { executions })
+}
+
+fn session_info(key: Addr, session: SessionKey) -> SessionKeyInfo {
+    SessionKeyInfo {
+        key: key.into(),
+        granter: session.granter.into(),
+        expires: session.expires,
+        policy: session.policy,
+    }
+}
+
+pub fn query_session_key(deps: Deps, key: String) -> StdResult<SessionKeyResponse> {
+    let key = deps.api.addr_validate(&key)?;
+    let session = SESSION_KEYS
+        .may_load(deps.storage, &key)?
+        .map(|session| session_info(key, session));
+    Ok(SessionKeyResponse { session })
+}
+
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+fn calc_limit(request: Option<u32>) -> usize {
+    request.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize
+}
+
+pub fn query_list_session_keys(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<ListSessionKeysResponse> {
+    let limit = calc_limit(limit);
+    let start = start_after.map(|s| Bound::ExclusiveRaw(s.into()));
+
+    let sessions = SESSION_KEYS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(key, session)| session_info(key, session)))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(ListSessionKeysResponse { sessions })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::state::MessageKind;
     use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-    use cosmwasm_std::{coin, coins, BankMsg, StakingMsg, SubMsg, WasmMsg};
+    use cosmwasm_std::{coin, coins, BankMsg, DistributionMsg, StakingMsg, SubMsg, WasmMsg};
 
     #[test]
     fn instantiate_and_modify_config() {
@@ -161,6 +651,8 @@ mod tests {
         let instantiate_msg = InstantiateMsg {
             admins: vec![alice.to_string(), bob.to_string(), carl.to_string()],
             mutable: true,
+            policy: None,
+            threshold: None,
         };
         let info = mock_info(anyone, &[]);
         instantiate(deps.as_mut(), mock_env(), info, instantiate_msg).unwrap();
@@ -170,7 +662,10 @@ mod tests {
             admins: vec![alice.to_string(), bob.to_string(), carl.to_string()],
             mutable: true,
         };
-        assert_eq!(query_admin_list(deps.as_ref()).unwrap(), expected);
+        assert_eq!(
+            query_admin_list(deps.as_ref(), mock_env()).unwrap(),
+            expected
+        );
 
         // anyone cannot modify the contract
         let msg = ExecuteMsg::UpdateAdmins {
@@ -192,21 +687,39 @@ mod tests {
             admins: vec![alice.to_string(), bob.to_string()],
             mutable: true,
         };
-        assert_eq!(query_admin_list(deps.as_ref()).unwrap(), expected);
+        assert_eq!(
+            query_admin_list(deps.as_ref(), mock_env()).unwrap(),
+            expected
+        );
 
         // carl cannot freeze it
         let info = mock_info(carl, &[]);
-        let err = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Freeze {}).unwrap_err();
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::Freeze { until: None },
+        )
+        .unwrap_err();
         assert_eq!(err, ContractError::Unauthorized {});
 
         // but bob can
         let info = mock_info(bob, &[]);
-        execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Freeze {}).unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::Freeze { until: None },
+        )
+        .unwrap();
         let expected = AdminListResponse {
             admins: vec![alice.to_string(), bob.to_string()],
             mutable: false,
         };
-        assert_eq!(query_admin_list(deps.as_ref()).unwrap(), expected);
+        assert_eq!(
+            query_admin_list(deps.as_ref(), mock_env()).unwrap(),
+            expected
+        );
 
         // and now alice cannot change it again
         let msg = ExecuteMsg::UpdateAdmins {
@@ -229,11 +742,13 @@ mod tests {
         let instantiate_msg = InstantiateMsg {
             admins: vec![alice.to_string(), carl.to_string()],
             mutable: false,
+            policy: None,
+            threshold: None,
         };
         let info = mock_info(bob, &[]);
         instantiate(deps.as_mut(), mock_env(), info, instantiate_msg).unwrap();
 
-        let freeze: ExecuteMsg<Empty> = ExecuteMsg::Freeze {};
+        let freeze: ExecuteMsg<Empty> = ExecuteMsg::Freeze { until: None };
         let msgs = vec![
             BankMsg::Send {
                 to_address: bob.to_string(),
@@ -249,7 +764,10 @@ mod tests {
         ];
 
         // make some nice message
-        let execute_msg = ExecuteMsg::Execute { msgs: msgs.clone() };
+        let execute_msg = ExecuteMsg::Execute {
+            msgs: msgs.clone(),
+            mode: ExecuteMode::Atomic,
+        };
 
         // bob cannot execute them
         let info = mock_info(bob, &[]);
@@ -279,6 +797,8 @@ mod tests {
         let instantiate_msg = InstantiateMsg {
             admins: vec![alice.to_string(), bob.to_string()],
             mutable: false,
+            policy: None,
+            threshold: None,
         };
         let info = mock_info(anyone, &[]);
         instantiate(deps.as_mut(), mock_env(), info, instantiate_msg).unwrap();
@@ -294,19 +814,487 @@ mod tests {
         });
 
         // owner can send
-        let res = query_can_execute(deps.as_ref(), alice.to_string(), send_msg.clone()).unwrap();
+        let res = query_can_execute(
+            deps.as_ref(),
+            mock_env(),
+            alice.to_string(),
+            send_msg.clone(),
+        )
+        .unwrap();
         assert!(res.can_execute);
 
         // owner can stake
-        let res = query_can_execute(deps.as_ref(), bob.to_string(), staking_msg.clone()).unwrap();
+        let res = query_can_execute(
+            deps.as_ref(),
+            mock_env(),
+            bob.to_string(),
+            staking_msg.clone(),
+        )
+        .unwrap();
         assert!(res.can_execute);
 
         // anyone cannot send
-        let res = query_can_execute(deps.as_ref(), anyone.to_string(), send_msg).unwrap();
+        let res =
+            query_can_execute(deps.as_ref(), mock_env(), anyone.to_string(), send_msg).unwrap();
         assert!(!res.can_execute);
 
         // anyone cannot stake
-        let res = query_can_execute(deps.as_ref(), anyone.to_string(), staking_msg).unwrap();
+        let res =
+            query_can_execute(deps.as_ref(), mock_env(), anyone.to_string(), staking_msg).unwrap();
+        assert!(!res.can_execute);
+    }
+
+    #[test]
+    fn policies_restrict_execution() {
+        let mut deps = mock_dependencies();
+
+        let alice = "alice";
+        let bob = "bob";
+
+        // by default, admins may only send up to 100 ujuno
+        let instantiate_msg = InstantiateMsg {
+            admins: vec![alice.to_string(), bob.to_string()],
+            mutable: true,
+            policy: Some(Policy {
+                allowed: vec![MessageKind::BankSend],
+                send_limit: Some(coins(100, "ujuno")),
+            }),
+            threshold: None,
+        };
+        let info = mock_info(alice, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, instantiate_msg).unwrap();
+
+        let send = |amount| -> CosmosMsg {
+            BankMsg::Send {
+                to_address: bob.to_string(),
+                amount: coins(amount, "ujuno"),
+            }
+            .into()
+        };
+        let delegate: CosmosMsg = StakingMsg::Delegate {
+            validator: bob.to_string(),
+            amount: coin(100, "ujuno"),
+        }
+        .into();
+
+        let res =
+            query_can_execute(deps.as_ref(), mock_env(), alice.to_string(), send(100)).unwrap();
+        assert!(res.can_execute);
+        let res =
+            query_can_execute(deps.as_ref(), mock_env(), alice.to_string(), send(101)).unwrap();
+        assert!(!res.can_execute);
+        let msg = ExecuteMsg::Execute {
+            msgs: vec![send(100), delegate.clone()],
+            mode: ExecuteMode::Atomic,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(alice, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::PolicyViolation {});
+
+        // bob is given a policy allowing staking, but no sends
+        let policy = Policy {
+            allowed: vec![MessageKind::Staking],
+            send_limit: None,
+        };
+        let msg = ExecuteMsg::SetPolicy {
+            admin: Some(bob.to_string()),
+            policy: Some(policy.clone()),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(alice, &[]), msg).unwrap();
+        assert_eq!(
+            query_policy(deps.as_ref(), Some(bob.to_string())).unwrap(),
+            PolicyResponse {
+                policy: Some(policy)
+            }
+        );
+        let msg = ExecuteMsg::Execute {
+            msgs: vec![delegate.clone()],
+            mode: ExecuteMode::Atomic,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(bob, &[]), msg).unwrap();
+        let res = query_can_execute(deps.as_ref(), mock_env(), bob.to_string(), send(1)).unwrap();
+        assert!(!res.can_execute);
+
+        // lifting the global policy lets alice relay anything
+        let msg = ExecuteMsg::SetPolicy {
+            admin: None,
+            policy: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(bob, &[]), msg).unwrap();
+        assert_eq!(
+            query_policy(deps.as_ref(), Some(alice.to_string())).unwrap(),
+            PolicyResponse { policy: None }
+        );
+        let res =
+            query_can_execute(deps.as_ref(), mock_env(), alice.to_string(), delegate).unwrap();
+        assert!(res.can_execute);
+
+        // but policies cannot be changed once frozen
+        let info = mock_info(alice, &[]);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::Freeze { until: None },
+        )
+        .unwrap();
+        let msg = ExecuteMsg::SetPolicy {
+            admin: Some(bob.to_string()),
+            policy: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+    }
+
+    #[test]
+    fn session_keys_expire() {
+        let mut deps = mock_dependencies();
+
+        let alice = "alice";
+        let bot = "bot";
+
+        let instantiate_msg = InstantiateMsg {
+            admins: vec![alice.to_string()],
+            mutable: true,
+            policy: None,
+            threshold: None,
+        };
+        let info = mock_info(alice, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, instantiate_msg).unwrap();
+
+        let env = mock_env();
+        let grant = |expires, policy| ExecuteMsg::GrantSession {
+            key: bot.to_string(),
+            expires,
+            policy,
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(alice, &[]),
+            grant(Expiration::Never {}, None),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidSessionExpiry {});
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(bot, &[]),
+            grant(Expiration::AtHeight(env.block.height + 10), None),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        // alice lets a bot withdraw rewards for a while
+        let policy = Policy {
+            allowed: vec![MessageKind::Distribution],
+            send_limit: None,
+        };
+        let expires = Expiration::AtHeight(env.block.height + 10);
+        let msg = grant(expires, Some(policy.clone()));
+        execute(deps.as_mut(), env.clone(), mock_info(alice, &[]), msg).unwrap();
+        assert_eq!(
+            query_session_key(deps.as_ref(), bot.to_string()).unwrap(),
+            SessionKeyResponse {
+                session: Some(SessionKeyInfo {
+                    key: bot.to_string(),
+                    granter: alice.to_string(),
+                    expires,
+                    policy: Some(policy),
+                })
+            }
+        );
+
+        let withdraw: CosmosMsg = DistributionMsg::WithdrawDelegatorReward {
+            validator: "validator".to_string(),
+        }
+        .into();
+        let send: CosmosMsg = BankMsg::Send {
+            to_address: bot.to_string(),
+            amount: coins(1, "ujuno"),
+        }
+        .into();
+        let msg = ExecuteMsg::Execute {
+            msgs: vec![withdraw.clone()],
+            mode: ExecuteMode::Atomic,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info(bot, &[]), msg).unwrap();
+        let msg = ExecuteMsg::Execute {
+            msgs: vec![send],
+            mode: ExecuteMode::Atomic,
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info(bot, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::PolicyViolation {});
+
+        // until the session expires
+        let mut later = env.clone();
+        later.block.height += 10;
+        let res =
+            query_can_execute(deps.as_ref(), later.clone(), bot.to_string(), withdraw).unwrap();
         assert!(!res.can_execute);
+
+        // after which it can be pruned by anyone
+        let msg = ExecuteMsg::PruneSessions { limit: None };
+        execute(deps.as_mut(), env, mock_info(bot, &[]), msg.clone()).unwrap();
+        assert_eq!(
+            query_list_session_keys(deps.as_ref(), None, None)
+                .unwrap()
+                .sessions
+                .len(),
+            1
+        );
+        let res = execute(deps.as_mut(), later, mock_info(bot, &[]), msg).unwrap();
+        assert_eq!(res.attributes[1], ("pruned", "1"));
+        assert_eq!(
+            query_list_session_keys(deps.as_ref(), None, None).unwrap(),
+            ListSessionKeysResponse { sessions: vec![] }
+        );
+    }
+
+    #[test]
+    fn threshold_of_admins_approve_execution() {
+        let mut deps = mock_dependencies();
+
+        let alice = "alice";
+        let bob = "bob";
+        let carl = "carl";
+
+        let mut instantiate_msg = InstantiateMsg {
+            admins: vec![alice.to_string(), bob.to_string(), carl.to_string()],
+            mutable: true,
+            policy: None,
+            threshold: Some(4),
+        };
+        let info = mock_info(alice, &[]);
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            instantiate_msg.clone(),
+        )
+        .unwrap_err();
+        instantiate_msg.threshold = Some(2);
+        instantiate(deps.as_mut(), mock_env(), info, instantiate_msg).unwrap();
+
+        // messages are not executed right away
+        let msgs: Vec<CosmosMsg> = vec![BankMsg::Send {
+            to_address: bob.to_string(),
+            amount: coins(100, "ujuno"),
+        }
+        .into()];
+        let msg = ExecuteMsg::Execute {
+            msgs: msgs.clone(),
+            mode: ExecuteMode::Atomic,
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info(alice, &[]), msg).unwrap();
+        assert_eq!(res.messages, vec![]);
+        assert_eq!(
+            res.attributes,
+            [("action", "propose_execution"), ("id", "1")]
+        );
+        assert_eq!(
+            query_pending_execution(deps.as_ref(), 1).unwrap(),
+            PendingExecutionResponse {
+                id: 1,
+                proposer: alice.to_string(),
+                msgs: msgs.clone(),
+                mode: ExecuteMode::Atomic,
+                approvals: vec![alice.to_string()],
+            }
+        );
+
+        // admins approve once, and only them
+        let approve = ExecuteMsg::Approve { id: 1 };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(alice, &[]),
+            approve.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::AlreadyApproved {});
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            approve.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        // the last approval needed executes them
+        let res = execute(deps.as_mut(), mock_env(), mock_info(carl, &[]), approve).unwrap();
+        assert_eq!(
+            res.messages,
+            msgs.clone()
+                .into_iter()
+                .map(SubMsg::new)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            query_list_pending_executions(deps.as_ref(), None, None).unwrap(),
+            ListPendingExecutionsResponse { executions: vec![] }
+        );
+
+        // proposers can cancel their pending executions
+        let msg = ExecuteMsg::Execute {
+            msgs,
+            mode: ExecuteMode::Atomic,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(bob, &[]), msg).unwrap();
+        let cancel = ExecuteMsg::CancelPending { id: 2 };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(alice, &[]),
+            cancel.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), mock_env(), mock_info(bob, &[]), cancel).unwrap();
+        query_pending_execution(deps.as_ref(), 2).unwrap_err();
+
+        // and the threshold cannot exceed the number of admins
+        let msg = ExecuteMsg::UpdateAdmins {
+            admins: vec![alice.to_string()],
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(alice, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidThreshold {});
+        let msg = ExecuteMsg::SetThreshold { threshold: None };
+        execute(deps.as_mut(), mock_env(), mock_info(alice, &[]), msg).unwrap();
+        let msg = ExecuteMsg::UpdateAdmins {
+            admins: vec![alice.to_string()],
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(alice, &[]), msg).unwrap();
+    }
+
+    #[test]
+    fn timed_freeze_and_unfreeze() {
+        let mut deps = mock_dependencies();
+
+        let alice = "alice";
+        let bob = "bob";
+        let carl = "carl";
+
+        let instantiate_msg = InstantiateMsg {
+            admins: vec![alice.to_string(), bob.to_string(), carl.to_string()],
+            mutable: true,
+            policy: None,
+            threshold: None,
+        };
+        let info = mock_info(alice, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, instantiate_msg).unwrap();
+
+        // a timed freeze must end in the future
+        let mut env = mock_env();
+        let until = Expiration::AtHeight(env.block.height + 100);
+        let msg = ExecuteMsg::Freeze {
+            until: Some(Expiration::AtHeight(env.block.height)),
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info(alice, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidFreezeExpiry {});
+        let msg = ExecuteMsg::Freeze { until: Some(until) };
+        execute(deps.as_mut(), env.clone(), mock_info(alice, &[]), msg).unwrap();
+        assert_eq!(
+            query_freeze(deps.as_ref(), env.clone()).unwrap(),
+            FreezeResponse {
+                frozen: true,
+                until: Some(until),
+                unfreeze_votes: vec![],
+            }
+        );
+
+        let update = ExecuteMsg::UpdateAdmins {
+            admins: vec![alice.to_string(), bob.to_string()],
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(alice, &[]),
+            update.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        // the contract thaws on its own
+        env.block.height += 100;
+        assert!(
+            query_admin_list(deps.as_ref(), env.clone())
+                .unwrap()
+                .mutable
+        );
+        execute(deps.as_mut(), env.clone(), mock_info(alice, &[]), update).unwrap();
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(alice, &[]),
+            ExecuteMsg::Unfreeze {},
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::NotFrozen {});
+
+        // an indefinite freeze needs the votes of two thirds of the admins to be lifted
+        let msg = ExecuteMsg::Freeze { until: None };
+        execute(deps.as_mut(), env.clone(), mock_info(bob, &[]), msg).unwrap();
+        env.block.height += 1000;
+        assert!(
+            !query_admin_list(deps.as_ref(), env.clone())
+                .unwrap()
+                .mutable
+        );
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(carl, &[]),
+            ExecuteMsg::Unfreeze {},
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(alice, &[]),
+            ExecuteMsg::Unfreeze {},
+        )
+        .unwrap();
+        assert_eq!(
+            res.attributes,
+            [
+                ("action", "unfreeze"),
+                ("votes", "1"),
+                ("status", "pending")
+            ]
+        );
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(alice, &[]),
+            ExecuteMsg::Unfreeze {},
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::AlreadyApproved {});
+        assert_eq!(
+            query_freeze(deps.as_ref(), env.clone())
+                .unwrap()
+                .unfreeze_votes,
+            vec![alice.to_string()]
+        );
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(bob, &[]),
+            ExecuteMsg::Unfreeze {},
+        )
+        .unwrap();
+        assert_eq!(
+            res.attributes,
+            [
+                ("action", "unfreeze"),
+                ("votes", "2"),
+                ("status", "unfrozen")
+            ]
+        );
+        assert!(query_admin_list(deps.as_ref(), env).unwrap().mutable);
     }
 }
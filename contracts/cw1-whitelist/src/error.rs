@@ -8,4 +8,22 @@ pub enum ContractError {
 
     #[error("Unauthorized")]
     Unauthorized {},
+
+    #[error("Message rejected by the policy of the sender")]
+    PolicyViolation {},
+
+    #[error("Session keys must expire, and not already have")]
+    InvalidSessionExpiry {},
+
+    #[error("A timed freeze cannot end in the past")]
+    InvalidFreezeExpiry {},
+
+    #[error("Contract is not frozen")]
+    NotFrozen {},
+
+    #[error("Threshold must be between 1 and the number of admins")]
+    InvalidThreshold {},
+
+    #[error("Execution already approved by this admin")]
+    AlreadyApproved {},
 }
@@ -1,8 +1,11 @@
 use crate::msg::{AdminListResponse, ExecuteMsg, InstantiateMsg, QueryMsg};
 use anyhow::{anyhow, Result};
 use assert_matches::assert_matches;
-use cosmwasm_std::{to_binary, Addr, CosmosMsg, Empty, QueryRequest, StdError, WasmMsg, WasmQuery};
-use cw1::Cw1Contract;
+use cosmwasm_std::{
+    coin, coins, to_binary, Addr, BankMsg, CosmosMsg, Empty, QueryRequest, StdError, WasmMsg,
+    WasmQuery,
+};
+use cw1::{Cw1Contract, ExecuteMode};
 use cw_multi_test::{App, AppResponse, Contract, ContractWrapper, Executor};
 use derivative::Derivative;
 use serde::{de::DeserializeOwned, Serialize};
@@ -16,7 +19,8 @@ fn contract_cw1() -> Box<dyn Contract<Empty>> {
         crate::contract::execute,
         crate::contract::instantiate,
         crate::contract::query,
-    );
+    )
+    .with_reply(crate::contract::reply);
     Box::new(contract)
 }
 
@@ -47,7 +51,12 @@ impl Suite {
             .instantiate_contract(
                 self.cw1_id,
                 Addr::unchecked(self.owner.clone()),
-                &InstantiateMsg { admins, mutable },
+                &InstantiateMsg {
+                    admins,
+                    mutable,
+                    policy: None,
+                    threshold: None,
+                },
                 &[],
                 "Whitelist",
                 None,
@@ -71,6 +80,7 @@ impl Suite {
                 msg: to_binary(&msg)?,
                 funds: vec![],
             })],
+            mode: ExecuteMode::Atomic,
         };
         self.app
             .execute_contract(
@@ -102,7 +112,7 @@ fn proxy_freeze_message() {
         suite.instantiate_cw1_contract(vec![first_contract.addr().to_string()], true);
     assert_ne!(second_contract, first_contract);
 
-    let freeze_msg: ExecuteMsg = ExecuteMsg::Freeze {};
+    let freeze_msg: ExecuteMsg = ExecuteMsg::Freeze { until: None };
     assert_matches!(
         suite.execute(first_contract.addr(), &second_contract.addr(), freeze_msg),
         Ok(_)
@@ -118,3 +128,63 @@ fn proxy_freeze_message() {
             }) if !mutable
     );
 }
+
+#[test]
+fn best_effort_execution() {
+    let mut suite = Suite::init().unwrap();
+
+    let contract = suite.instantiate_cw1_contract(vec![suite.owner.clone()], true);
+    suite
+        .app
+        .init_modules(|router, _, storage| {
+            router
+                .bank
+                .init_balance(storage, &contract.addr(), coins(100, "ujuno"))
+        })
+        .unwrap();
+
+    // there is only enough to pay the first one
+    let payout = |recipient: &str| -> CosmosMsg {
+        BankMsg::Send {
+            to_address: recipient.to_owned(),
+            amount: coins(60, "ujuno"),
+        }
+        .into()
+    };
+    let execute: ExecuteMsg = ExecuteMsg::Execute {
+        msgs: vec![payout("alice"), payout("bob")],
+        mode: ExecuteMode::Atomic,
+    };
+    let owner = Addr::unchecked(suite.owner.clone());
+    suite
+        .app
+        .execute_contract(owner.clone(), contract.addr(), &execute, &[])
+        .unwrap_err();
+
+    let execute: ExecuteMsg = ExecuteMsg::Execute {
+        msgs: vec![payout("alice"), payout("bob")],
+        mode: ExecuteMode::BestEffort,
+    };
+    let res = suite
+        .app
+        .execute_contract(owner, contract.addr(), &execute, &[])
+        .unwrap();
+
+    let results: Vec<_> = res
+        .events
+        .iter()
+        .filter(|event| event.ty == "wasm-execute_result")
+        .map(|event| {
+            (
+                event.attributes[1].value.as_str(),
+                event.attributes[2].value.as_str(),
+            )
+        })
+        .collect();
+    assert_eq!(results, [("0", "success"), ("1", "failure")]);
+
+    let balance = |addr: &str| suite.app.wrap().query_balance(addr, "ujuno").unwrap();
+    assert_eq!(balance("alice"), coin(60, "ujuno"));
+    assert_eq!(balance("bob"), coin(0, "ujuno"));
+    assert_eq!(balance(contract.addr().as_str()), coin(40, "ujuno"));
+}
@@ -4,11 +4,21 @@ use std::fmt;
 
 use cosmwasm_schema::{cw_serde, QueryResponses};
 use cosmwasm_std::{CosmosMsg, Empty};
+use cw1::ExecuteMode;
+use cw_utils::Expiration;
+
+use crate::state::Policy;
 
 #[cw_serde]
 pub struct InstantiateMsg {
     pub admins: Vec<String>,
     pub mutable: bool,
+    /// The policy of admins without their own
+    #[serde(default)]
+    pub policy: Option<Policy>,
+    /// If set, messages are only executed once this many admins approved them
+    #[serde(default)]
+    pub threshold: Option<u32>,
 }
 
 #[cw_serde]
@@ -19,12 +29,52 @@ where
     /// Execute requests the contract to re-dispatch all these messages with the
     /// contract's address as sender. Every implementation has it's own logic to
     /// determine in
-    Execute { msgs: Vec<CosmosMsg<T>> },
-    /// Freeze will make a mutable contract immutable, must be called by an admin
-    Freeze {},
+    Execute {
+        msgs: Vec<CosmosMsg<T>>,
+        /// Whether the messages are executed atomically (default), or independently of each other
+        #[serde(default)]
+        mode: ExecuteMode,
+    },
+    /// Approve a pending execution, must be called by an admin. The messages are executed with
+    /// the last approval needed
+    Approve { id: u64 },
+    /// Cancel a pending execution, must be called by the admin who proposed it
+    CancelPending { id: u64 },
+    /// SetThreshold sets how many admins must approve messages before they are executed, or
+    /// executes them right away if None. Must be called by an existing admin, and only works if
+    /// the contract is mutable
+    SetThreshold { threshold: Option<u32> },
+    /// Freeze will make a mutable contract immutable, must be called by an admin. It stays frozen
+    /// until `until` if given, or indefinitely
+    Freeze {
+        #[serde(default)]
+        until: Option<Expiration>,
+    },
+    /// Unfreeze votes to make a frozen contract mutable again, must be called by an admin. The
+    /// freeze is lifted once at least two thirds of the admins voted for it
+    Unfreeze {},
     /// UpdateAdmins will change the admin set of the contract, must be called by an existing admin,
     /// and only works if the contract is mutable
     UpdateAdmins { admins: Vec<String> },
+    /// SetPolicy sets the policy of `admin`, or the global one if `admin` is None, restricting
+    /// the messages they may execute. A None policy removes it. Must be called by an existing
+    /// admin, and only works if the contract is mutable
+    SetPolicy {
+        admin: Option<String>,
+        policy: Option<Policy>,
+    },
+    /// GrantSession authorizes `key` to execute messages on behalf of the sender, an admin, until
+    /// `expires`. They are limited by the policy of the admin, and `policy` if given. Replaces any
+    /// previous session of the key
+    GrantSession {
+        key: String,
+        expires: Expiration,
+        policy: Option<Policy>,
+    },
+    /// RevokeSession removes the session of `key`, must be called by an admin
+    RevokeSession { key: String },
+    /// PruneSessions removes up to `limit` expired session keys, may be called by anyone
+    PruneSessions { limit: Option<u32> },
 }
 
 #[cw_serde]
@@ -41,6 +91,43 @@ where
     /// before any further state changes, should also succeed.
     #[returns(cw1::CanExecuteResponse)]
     CanExecute { sender: String, msg: CosmosMsg<T> },
+    /// Shows the policy applying to `admin` (their own, or the global one), or the global policy
+    /// if `admin` is None
+    #[returns(PolicyResponse)]
+    Policy { admin: Option<String> },
+    /// Shows how many admins must approve messages, if any
+    #[returns(ThresholdResponse)]
+    Threshold {},
+    /// Shows a pending execution, with the admins who approved it
+    #[returns(PendingExecutionResponse)]
+    PendingExecution { id: u64 },
+    /// Lists all pending executions
+    #[returns(ListPendingExecutionsResponse)]
+    ListPendingExecutions {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Shows whether the contract is frozen, until when, and the votes to lift the freeze
+    #[returns(FreezeResponse)]
+    Freeze {},
+    /// Shows the session of `key`, if any, even if it expired
+    #[returns(SessionKeyResponse)]
+    SessionKey { key: String },
+    /// Lists all session keys, including expired ones not pruned yet
+    #[returns(ListSessionKeysResponse)]
+    ListSessionKeys {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+}
+
+#[cw_serde]
+pub struct FreezeResponse {
+    pub frozen: bool,
+    /// When the freeze ends, None if it does not
+    pub until: Option<Expiration>,
+    /// Admins who voted to lift the freeze
+    pub unfreeze_votes: Vec<String>,
 }
 
 #[cw_serde]
@@ -49,6 +136,52 @@ pub struct AdminListResponse {
     pub mutable: bool,
 }
 
+#[cw_serde]
+pub struct PolicyResponse {
+    /// None if any message may be relayed
+    pub policy: Option<Policy>,
+}
+
+#[cw_serde]
+pub struct ThresholdResponse {
+    /// None if messages are executed right away
+    pub threshold: Option<u32>,
+}
+
+#[cw_serde]
+pub struct PendingExecutionResponse<T = Empty> {
+    pub id: u64,
+    pub proposer: String,
+    pub msgs: Vec<CosmosMsg<T>>,
+    pub mode: ExecuteMode,
+    /// The admins who approved it so far, including the proposer
+    pub approvals: Vec<String>,
+}
+
+#[cw_serde]
+pub struct ListPendingExecutionsResponse<T = Empty> {
+    pub executions: Vec<PendingExecutionResponse<T>>,
+}
+
+#[cw_serde]
+pub struct SessionKeyInfo {
+    pub key: String,
+    /// The admin who authorized the key
+    pub granter: String,
+    pub expires: Expiration,
+    pub policy: Option<Policy>,
+}
+
+#[cw_serde]
+pub struct SessionKeyResponse {
+    pub session: Option<SessionKeyInfo>,
+}
+
+#[cw_serde]
+pub struct ListSessionKeysResponse {
+    pub sessions: Vec<SessionKeyInfo>,
+}
+
 #[cfg(any(test, feature = "test-utils"))]
 impl AdminListResponse {
     /// Utility function forconverting message to its canonical form, so two messages with
@@ -1,8 +1,10 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::Addr;
-use cw_storage_plus::Item;
+use cosmwasm_std::{Addr, BankMsg, BlockInfo, Coin, CosmosMsg, Empty, StdResult, Storage, WasmMsg};
+use cw1::ExecuteMode;
+use cw_storage_plus::{Item, Map};
+use cw_utils::Expiration;
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, Eq, JsonSchema, Debug, Default)]
 pub struct AdminList {
@@ -21,14 +23,153 @@ impl AdminList {
     pub fn can_modify(&self, addr: &str) -> bool {
         self.mutable && self.is_admin(addr)
     }
+
+    /// returns true if `threshold` admins can be found to approve executions
+    pub fn is_valid_threshold(&self, threshold: u32) -> bool {
+        threshold >= 1 && threshold as usize <= self.admins.len()
+    }
+}
+
+/// MessageKind is a category of `CosmosMsg` a policy may allow
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageKind {
+    BankSend,
+    BankBurn,
+    Staking,
+    Distribution,
+    WasmExecute,
+    WasmInstantiate,
+    WasmMigrate,
+    /// `WasmMsg::UpdateAdmin` and `WasmMsg::ClearAdmin`
+    WasmAdmin,
+    Custom,
+    /// Any other message, eg. stargate, ibc or gov ones
+    Other,
+}
+
+impl MessageKind {
+    pub fn of<T>(msg: &CosmosMsg<T>) -> Self {
+        match msg {
+            CosmosMsg::Bank(BankMsg::Send { .. }) => MessageKind::BankSend,
+            CosmosMsg::Bank(BankMsg::Burn { .. }) => MessageKind::BankBurn,
+            CosmosMsg::Staking(_) => MessageKind::Staking,
+            CosmosMsg::Distribution(_) => MessageKind::Distribution,
+            CosmosMsg::Wasm(WasmMsg::Execute { .. }) => MessageKind::WasmExecute,
+            CosmosMsg::Wasm(WasmMsg::Instantiate { .. }) => MessageKind::WasmInstantiate,
+            CosmosMsg::Wasm(WasmMsg::Migrate { .. }) => MessageKind::WasmMigrate,
+            CosmosMsg::Wasm(WasmMsg::UpdateAdmin { .. } | WasmMsg::ClearAdmin { .. }) => {
+                MessageKind::WasmAdmin
+            }
+            CosmosMsg::Custom(_) => MessageKind::Custom,
+            _ => MessageKind::Other,
+        }
+    }
+}
+
+/// Policy restricts the messages admins may relay
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Policy {
+    /// The kinds of messages that may be relayed. Any other is rejected
+    pub allowed: Vec<MessageKind>,
+    /// If set, the most a single `BankMsg::Send` may send of each denom. Denoms not listed
+    /// cannot be sent
+    pub send_limit: Option<Vec<Coin>>,
+}
+
+impl Policy {
+    /// returns true if the message may be relayed under this policy
+    pub fn allows<T>(&self, msg: &CosmosMsg<T>) -> bool {
+        if !self.allowed.contains(&MessageKind::of(msg)) {
+            return false;
+        }
+        match (msg, &self.send_limit) {
+            (CosmosMsg::Bank(BankMsg::Send { amount, .. }), Some(limit)) => {
+                amount.iter().all(|coin| {
+                    limit
+                        .iter()
+                        .any(|max| max.denom == coin.denom && coin.amount <= max.amount)
+                })
+            }
+            _ => true,
+        }
+    }
+}
+
+/// Loads the admin list, which is mutable again once a timed freeze is over
+pub fn load_admin_list(storage: &dyn Storage, block: &BlockInfo) -> StdResult<AdminList> {
+    let mut cfg = ADMIN_LIST.load(storage)?;
+    if !cfg.mutable {
+        cfg.mutable = match FROZEN_UNTIL.may_load(storage)? {
+            Some(until) => until.is_expired(block),
+            None => false,
+        };
+    }
+    Ok(cfg)
+}
+
+/// The policy applying to an admin: their own if they have one, the global one otherwise.
+/// Admins with no policy may relay any message.
+pub fn admin_policy(storage: &dyn Storage, admin: &Addr) -> StdResult<Option<Policy>> {
+    match ADMIN_POLICIES.may_load(storage, admin)? {
+        Some(policy) => Ok(Some(policy)),
+        None => GLOBAL_POLICY.may_load(storage),
+    }
+}
+
+/// SessionKey is a temporary key an admin authorized to execute messages on their behalf,
+/// within their own policy and the one of the session, if any
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SessionKey {
+    pub granter: Addr,
+    pub expires: Expiration,
+    pub policy: Option<Policy>,
+}
+
+/// PendingExecution holds messages waiting for the approval of enough admins to be executed
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingExecution<T = Empty> {
+    pub proposer: Addr,
+    pub msgs: Vec<CosmosMsg<T>>,
+    #[serde(default)]
+    pub mode: ExecuteMode,
+    /// Admins who approved the execution, including the proposer
+    pub approvals: Vec<Addr>,
+}
+
+impl<T> PendingExecution<T> {
+    /// The approvals of those who are still admins
+    pub fn approvals(&self, cfg: &AdminList) -> u32 {
+        self.approvals
+            .iter()
+            .filter(|admin| cfg.is_admin(admin))
+            .count() as u32
+    }
+}
+
+/// The pending executions, by id
+pub const fn pending_executions<'a, T>() -> Map<'a, u64, PendingExecution<T>> {
+    Map::new("pending_executions")
 }
 
 pub const ADMIN_LIST: Item<AdminList> = Item::new("admin_list");
+/// The number of admins who must approve messages before they are executed, if any
+/// End of a timed freeze. Missing if the contract is mutable, or frozen indefinitely.
+pub const FROZEN_UNTIL: Item<Expiration> = Item::new("frozen_until");
+/// Admins who voted to lift the current freeze
+pub const UNFREEZE_VOTES: Item<Vec<Addr>> = Item::new("unfreeze_votes");
+pub const THRESHOLD: Item<u32> = Item::new("threshold");
+pub const PENDING_COUNT: Item<u64> = Item::new("pending_count");
+pub const GLOBAL_POLICY: Item<Policy> = Item::new("global_policy");
+pub const ADMIN_POLICIES: Map<&Addr, Policy> = Map::new("admin_policies");
+pub const SESSION_KEYS: Map<&Addr, SessionKey> = Map::new("session_keys");
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use cosmwasm_std::{coin, coins};
+
     #[test]
     fn is_admin() {
         let admins: Vec<_> = vec!["bob", "paul", "john"]
@@ -66,4 +207,39 @@ mod tests {
         assert!(!config.can_modify(alice.as_ref()));
         assert!(!config.can_modify(bob.as_ref()));
     }
+
+    #[test]
+    fn policy_allows() {
+        let send = |amount| -> CosmosMsg {
+            BankMsg::Send {
+                to_address: "bob".to_owned(),
+                amount,
+            }
+            .into()
+        };
+        let migrate: CosmosMsg = WasmMsg::Migrate {
+            contract_addr: "contract".to_owned(),
+            new_code_id: 2,
+            msg: Default::default(),
+        }
+        .into();
+
+        // only allowed kinds of messages pass
+        let policy = Policy {
+            allowed: vec![MessageKind::BankSend],
+            send_limit: None,
+        };
+        assert!(policy.allows(&send(coins(1_000_000, "ujuno"))));
+        assert!(!policy.allows(&migrate));
+
+        // and sends up to the limit, of listed denoms
+        let policy = Policy {
+            allowed: vec![MessageKind::BankSend, MessageKind::WasmMigrate],
+            send_limit: Some(coins(100, "ujuno")),
+        };
+        assert!(policy.allows(&send(coins(100, "ujuno"))));
+        assert!(!policy.allows(&send(coins(101, "ujuno"))));
+        assert!(!policy.allows(&send(vec![coin(1, "ujuno"), coin(1, "uatom")])));
+        assert!(policy.allows(&migrate));
+    }
 }
@@ -0,0 +1,309 @@
+use cosmwasm_std::{
+    Addr, BlockInfo, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Storage, Uint128,
+};
+use cw_utils::Duration;
+
+use crate::error::ContractError;
+use crate::msg::{AllowancePeriod, AllowancePeriodResponse};
+use crate::state::{AllowanceWindow, ALLOWANCES, ALLOWANCE_PERIODS, ALLOWANCE_WINDOWS};
+
+/// Returns the window of the allowance containing the given block. A new one, with nothing spent
+/// yet, is started if the last window is over.
+fn current_window(
+    storage: &dyn Storage,
+    owner: &Addr,
+    spender: &Addr,
+    block: &BlockInfo,
+    period: &AllowancePeriod,
+) -> StdResult<AllowanceWindow> {
+    match ALLOWANCE_WINDOWS.may_load(storage, (owner, spender))? {
+        Some(window) if !window.ends.is_expired(block) => Ok(window),
+        _ => Ok(AllowanceWindow {
+            ends: period.period.after(block),
+            spent: Uint128::zero(),
+        }),
+    }
+}
+
+/// Accounts for `amount` spent by `spender` in the current window, failing if this exceeds the
+/// limit per period. Returns the window to save once the allowance itself was deducted, if the
+/// allowance has such a limit.
+pub fn spend_in_period(
+    storage: &dyn Storage,
+    owner: &Addr,
+    spender: &Addr,
+    block: &BlockInfo,
+    amount: Uint128,
+) -> Result<Option<AllowanceWindow>, ContractError> {
+    let period = match ALLOWANCE_PERIODS.may_load(storage, (owner, spender))? {
+        Some(period) => period,
+        None => return Ok(None),
+    };
+
+    let mut window = current_window(storage, owner, spender, block, &period)?;
+    let remaining = period.max_amount.saturating_sub(window.spent);
+    if amount > remaining {
+        return Err(ContractError::AllowancePeriodExceeded { remaining });
+    }
+    window.spent += amount;
+    Ok(Some(window))
+}
+
+/// Forgets the limit per period of an allowance that was removed
+pub fn remove_allowance_period(storage: &mut dyn Storage, owner: &Addr, spender: &Addr) {
+    ALLOWANCE_PERIODS.remove(storage, (owner, spender));
+    ALLOWANCE_WINDOWS.remove(storage, (owner, spender));
+}
+
+/// Limits how much of its allowance `spender` can use per period, or removes the limit (with
+/// `None`). The current window is dropped, so the next spend starts a new one.
+pub fn execute_set_allowance_period(
+    deps: DepsMut,
+    info: MessageInfo,
+    spender: String,
+    period: Option<AllowancePeriod>,
+) -> Result<Response, ContractError> {
+    let spender_addr = deps.api.addr_validate(&spender)?;
+    if spender_addr == info.sender {
+        return Err(ContractError::CannotSetOwnAccount {});
+    }
+
+    let key = (&info.sender, &spender_addr);
+    ALLOWANCE_WINDOWS.remove(deps.storage, key);
+    let res = Response::new()
+        .add_attribute("action", "set_allowance_period")
+        .add_attribute("owner", &info.sender)
+        .add_attribute("spender", spender);
+    let res = match period {
+        Some(period) => {
+            if period.period == Duration::Height(0) || period.period == Duration::Time(0) {
+                return Err(ContractError::InvalidAllowancePeriod {});
+            }
+            ALLOWANCE_PERIODS.save(deps.storage, key, &period)?;
+            res.add_attribute("max_amount", period.max_amount)
+                .add_attribute("period", period.period.to_string())
+        }
+        None => {
+            ALLOWANCE_PERIODS.remove(deps.storage, key);
+            res.add_attribute("max_amount", "None")
+        }
+    };
+    Ok(res)
+}
+
+pub fn query_allowance_period(
+    deps: Deps,
+    env: Env,
+    owner: String,
+    spender: String,
+) -> StdResult<AllowancePeriodResponse> {
+    let owner_addr = deps.api.addr_validate(&owner)?;
+    let spender_addr = deps.api.addr_validate(&spender)?;
+    let key = (&owner_addr, &spender_addr);
+    let period = match ALLOWANCE_PERIODS.may_load(deps.storage, key)? {
+        Some(period) => period,
+        None => {
+            return Ok(AllowancePeriodResponse {
+                period: None,
+                remaining: None,
+                window_ends: None,
+            })
+        }
+    };
+
+    // a window only really starts with its first spend
+    let window = ALLOWANCE_WINDOWS
+        .may_load(deps.storage, key)?
+        .filter(|window| !window.ends.is_expired(&env.block));
+    let spent = window.as_ref().map(|w| w.spent).unwrap_or_default();
+    // the allowance itself may be lower than what is left in the period
+    let allowance = match ALLOWANCES.may_load(deps.storage, key)? {
+        Some(allowance) if !allowance.expires.is_expired(&env.block) => allowance.allowance,
+        _ => Uint128::zero(),
+    };
+    Ok(AllowancePeriodResponse {
+        remaining: Some(period.max_amount.saturating_sub(spent).min(allowance)),
+        window_ends: window.map(|w| w.ends),
+        period: Some(period),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::from_binary;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cw20::{Cw20Coin, Expiration};
+
+    use crate::contract::{execute, instantiate, query, query_balance};
+    use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+
+    const DAY: u64 = 24 * 60 * 60;
+
+    fn do_instantiate(deps: DepsMut) {
+        let msg = InstantiateMsg {
+            name: "Project".to_string(),
+            symbol: "PROJ".to_string(),
+            decimals: 6,
+            initial_balances: vec![Cw20Coin {
+                address: "owner".to_string(),
+                amount: Uint128::new(1000),
+            }],
+            mint: None,
+            marketing: None,
+            snapshots: false,
+            freezer: None,
+            pauser: None,
+        };
+        instantiate(deps, mock_env(), mock_info("creator", &[]), msg).unwrap();
+    }
+
+    fn env_at(days: u64) -> Env {
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(days * DAY);
+        env
+    }
+
+    fn pull(amount: u128) -> ExecuteMsg {
+        ExecuteMsg::TransferFrom {
+            owner: "owner".to_string(),
+            recipient: "spender".to_string(),
+            amount: Uint128::new(amount),
+        }
+    }
+
+    fn query_period(deps: Deps, env: Env) -> AllowancePeriodResponse {
+        let msg = QueryMsg::AllowancePeriod {
+            owner: "owner".to_string(),
+            spender: "spender".to_string(),
+        };
+        from_binary(&query(deps, env, msg).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn spending_is_limited_per_period() {
+        let mut deps = mock_dependencies();
+        do_instantiate(deps.as_mut());
+
+        let allow = ExecuteMsg::IncreaseAllowance {
+            spender: "spender".to_string(),
+            amount: Uint128::new(500),
+            expires: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), allow).unwrap();
+
+        // at most 100 a month
+        let period = AllowancePeriod {
+            max_amount: Uint128::new(100),
+            period: Duration::Time(30 * DAY),
+        };
+        let msg = ExecuteMsg::SetAllowancePeriod {
+            spender: "spender".to_string(),
+            period: Some(period.clone()),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+        assert_eq!(
+            query_period(deps.as_ref(), mock_env()),
+            AllowancePeriodResponse {
+                period: Some(period),
+                remaining: Some(Uint128::new(100)),
+                window_ends: None,
+            }
+        );
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("spender", &[]),
+            pull(60),
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            env_at(10),
+            mock_info("spender", &[]),
+            pull(50),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::AllowancePeriodExceeded {
+                remaining: Uint128::new(40)
+            }
+        );
+        let res = query_period(deps.as_ref(), env_at(10));
+        assert_eq!(res.remaining, Some(Uint128::new(40)));
+        assert_eq!(
+            res.window_ends,
+            Some(Expiration::AtTime(
+                mock_env().block.time.plus_seconds(30 * DAY)
+            ))
+        );
+
+        // the limit resets with the next window
+        execute(
+            deps.as_mut(),
+            env_at(30),
+            mock_info("spender", &[]),
+            pull(100),
+        )
+        .unwrap();
+        assert_eq!(
+            query_balance(deps.as_ref(), "spender".to_string())
+                .unwrap()
+                .balance,
+            Uint128::new(160)
+        );
+
+        // and never lets more than the allowance through
+        let res = query_period(deps.as_ref(), env_at(60));
+        assert_eq!(res.remaining, Some(Uint128::new(100)));
+        let decrease = ExecuteMsg::DecreaseAllowance {
+            spender: "spender".to_string(),
+            amount: Uint128::new(300),
+            expires: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), decrease).unwrap();
+        let res = query_period(deps.as_ref(), env_at(60));
+        assert_eq!(res.remaining, Some(Uint128::new(40)));
+    }
+
+    #[test]
+    fn allowance_period_is_removed_with_the_allowance() {
+        let mut deps = mock_dependencies();
+        do_instantiate(deps.as_mut());
+
+        let msg = ExecuteMsg::SetAllowancePeriod {
+            spender: "spender".to_string(),
+            period: Some(AllowancePeriod {
+                max_amount: Uint128::new(100),
+                period: Duration::Height(0),
+            }),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidAllowancePeriod {});
+
+        let msg = ExecuteMsg::SetAllowancePeriod {
+            spender: "spender".to_string(),
+            period: Some(AllowancePeriod {
+                max_amount: Uint128::new(100),
+                period: Duration::Height(10),
+            }),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+        let allow = ExecuteMsg::IncreaseAllowance {
+            spender: "spender".to_string(),
+            amount: Uint128::new(500),
+            expires: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), allow).unwrap();
+        let decrease = ExecuteMsg::DecreaseAllowance {
+            spender: "spender".to_string(),
+            amount: Uint128::new(500),
+            expires: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), decrease).unwrap();
+        assert_eq!(query_period(deps.as_ref(), mock_env()).period, None);
+    }
+}
@@ -1,11 +1,22 @@
 use cosmwasm_std::{
-    attr, Addr, Binary, BlockInfo, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult,
-    Storage, Uint128,
+    attr, Addr, Binary, BlockInfo, ContractInfoResponse, Deps, DepsMut, Env, Event, MessageInfo,
+    Response, StdError, StdResult, Storage, SubMsg, Uint128, WasmQuery,
 };
-use cw20::{AllowanceResponse, Cw20ReceiveMsg, Expiration};
+use cw20::{AllowanceResponse, Cw20AllowanceSpentMsg, Cw20ReceiveMsg, Expiration};
 
+use crate::allowance_period::{remove_allowance_period, spend_in_period};
+use crate::burn::{is_burner, record_burn};
 use crate::error::ContractError;
-use crate::state::{ALLOWANCES, ALLOWANCES_SPENDER, BALANCES, TOKEN_INFO};
+use crate::fee::{collect_transfer_fee, fee_attributes, split_burn_tax, split_transfer_fee};
+use crate::freeze::ensure_not_frozen;
+use crate::hooks::transfer_hook_msgs;
+use crate::snapshots::{record_total_supply, update_balance};
+use crate::state::{ALLOWANCES, ALLOWANCES_SPENDER, ALLOWANCE_WINDOWS, PAUSE, TOKEN_INFO};
+use crate::vesting::ensure_unlocked;
+use crate::wallet_limits::ensure_within_wallet_limits;
+
+/// Reply id of the allowance spent notifications, whose failures are ignored
+pub const ALLOWANCE_SPENT_REPLY_ID: u64 = 1;
 
 pub fn execute_increase_allowance(
     deps: DepsMut,
@@ -19,6 +30,7 @@ pub fn execute_increase_allowance(
     if spender_addr == info.sender {
         return Err(ContractError::CannotSetOwnAccount {});
     }
+    ensure_not_frozen(deps.storage, &info.sender)?;
 
     let update_fn = |allow: Option<AllowanceResponse>| -> Result<_, _> {
         let mut val = allow.unwrap_or_default();
@@ -81,6 +93,7 @@ pub fn execute_decrease_allowance(
     } else {
         ALLOWANCES.remove(deps.storage, key);
         ALLOWANCES_SPENDER.remove(deps.storage, reverse(key));
+        remove_allowance_period(deps.storage, key.0, key.1);
     }
 
     let res = Response::new().add_attributes(vec![
@@ -100,6 +113,7 @@ pub fn deduct_allowance(
     block: &BlockInfo,
     amount: Uint128,
 ) -> Result<AllowanceResponse, ContractError> {
+    let window = spend_in_period(storage, owner, spender, block, amount)?;
     let update_fn = |current: Option<AllowanceResponse>| -> _ {
         match current {
             Some(mut a) => {
@@ -118,7 +132,50 @@ pub fn deduct_allowance(
         }
     };
     ALLOWANCES.update(storage, (owner, spender), update_fn)?;
-    ALLOWANCES_SPENDER.update(storage, (spender, owner), update_fn)
+    let remaining = ALLOWANCES_SPENDER.update(storage, (spender, owner), update_fn)?;
+    if let Some(window) = window {
+        ALLOWANCE_WINDOWS.save(storage, (owner, spender), &window)?;
+    }
+    Ok(remaining)
+}
+
+/// Builds the `allowance_spent` event, along with a notification for the owner if it is a contract.
+/// The notification is sent as a submessage replying on error, so it cannot make the spend fail.
+pub fn allowance_spent_notification(
+    deps: Deps,
+    owner: &Addr,
+    spender: &Addr,
+    amount: Uint128,
+    remaining: AllowanceResponse,
+) -> StdResult<(Event, Option<SubMsg>)> {
+    let event = Event::new("allowance_spent").add_attributes(vec![
+        attr("owner", owner),
+        attr("spender", spender),
+        attr("amount", amount),
+        attr("remaining", remaining.allowance),
+    ]);
+
+    let owner_is_contract = deps
+        .querier
+        .query::<ContractInfoResponse>(
+            &WasmQuery::ContractInfo {
+                contract_addr: owner.to_string(),
+            }
+            .into(),
+        )
+        .is_ok();
+    let notification = if owner_is_contract {
+        let msg = Cw20AllowanceSpentMsg {
+            spender: spender.to_string(),
+            amount,
+            remaining,
+        }
+        .into_cosmos_msg(owner)?;
+        Some(SubMsg::reply_on_error(msg, ALLOWANCE_SPENT_REPLY_ID))
+    } else {
+        None
+    };
+    Ok((event, notification))
 }
 
 pub fn execute_transfer_from(
@@ -131,31 +188,48 @@ pub fn execute_transfer_from(
 ) -> Result<Response, ContractError> {
     let rcpt_addr = deps.api.addr_validate(&recipient)?;
     let owner_addr = deps.api.addr_validate(&owner)?;
+    ensure_not_frozen(deps.storage, &owner_addr)?;
+    ensure_unlocked(deps.storage, &env.block, &owner_addr, amount)?;
+    PAUSE.assert_not_paused(deps.storage, &env.block)?;
+    let (net, fee) = split_transfer_fee(deps.storage, &owner_addr, &rcpt_addr, amount)?;
+    ensure_within_wallet_limits(deps.storage, Some(&owner_addr), &rcpt_addr, amount, net)?;
 
     // deduct allowance before doing anything else have enough allowance
-    deduct_allowance(deps.storage, &owner_addr, &info.sender, &env.block, amount)?;
+    let remaining = deduct_allowance(deps.storage, &owner_addr, &info.sender, &env.block, amount)?;
+    let (event, notification) =
+        allowance_spent_notification(deps.as_ref(), &owner_addr, &info.sender, amount, remaining)?;
 
-    BALANCES.update(
+    update_balance(
         deps.storage,
+        env.block.height,
         &owner_addr,
         |balance: Option<Uint128>| -> StdResult<_> {
             Ok(balance.unwrap_or_default().checked_sub(amount)?)
         },
     )?;
-    BALANCES.update(
+    update_balance(
         deps.storage,
+        env.block.height,
         &rcpt_addr,
-        |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + amount) },
+        |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + net) },
     )?;
 
-    let res = Response::new().add_attributes(vec![
+    let mut hooks = transfer_hook_msgs(deps.storage, &owner_addr, Some(&rcpt_addr), net)?;
+    hooks.extend(collect_transfer_fee(
+        deps.storage,
+        env.block.height,
+        &owner_addr,
+        fee.as_ref(),
+    )?);
+    let res = Response::new().add_submessages(hooks).add_attributes(vec![
         attr("action", "transfer_from"),
         attr("from", owner),
         attr("to", recipient),
         attr("by", info.sender),
         attr("amount", amount),
     ]);
-    Ok(res)
+    let res = res.add_attributes(fee_attributes(net, fee.as_ref()));
+    Ok(res.add_event(event).add_submessages(notification))
 }
 
 pub fn execute_burn_from(
@@ -167,31 +241,62 @@ pub fn execute_burn_from(
     amount: Uint128,
 ) -> Result<Response, ContractError> {
     let owner_addr = deps.api.addr_validate(&owner)?;
-
-    // deduct allowance before doing anything else have enough allowance
-    deduct_allowance(deps.storage, &owner_addr, &info.sender, &env.block, amount)?;
+    ensure_not_frozen(deps.storage, &owner_addr)?;
+    ensure_unlocked(deps.storage, &env.block, &owner_addr, amount)?;
+    PAUSE.assert_not_paused(deps.storage, &env.block)?;
+
+    // the burner needs no allowance, otherwise deduct it before doing anything else
+    let forced = is_burner(deps.storage, &info.sender)?;
+    let (event, notification) = if forced {
+        (None, None)
+    } else {
+        let remaining =
+            deduct_allowance(deps.storage, &owner_addr, &info.sender, &env.block, amount)?;
+        let (event, notification) = allowance_spent_notification(
+            deps.as_ref(),
+            &owner_addr,
+            &info.sender,
+            amount,
+            remaining,
+        )?;
+        (Some(event), notification)
+    };
 
     // lower balance
-    BALANCES.update(
+    update_balance(
         deps.storage,
+        env.block.height,
         &owner_addr,
         |balance: Option<Uint128>| -> StdResult<_> {
             Ok(balance.unwrap_or_default().checked_sub(amount)?)
         },
     )?;
+    // the tax goes to the treasury, only the rest is destroyed
+    let (burned, tax) = split_burn_tax(deps.storage, &owner_addr, amount)?;
+    let mut hooks =
+        collect_transfer_fee(deps.storage, env.block.height, &owner_addr, tax.as_ref())?;
     // reduce total_supply
-    TOKEN_INFO.update(deps.storage, |mut meta| -> StdResult<_> {
-        meta.total_supply = meta.total_supply.checked_sub(amount)?;
+    let token_info = TOKEN_INFO.update(deps.storage, |mut meta| -> StdResult<_> {
+        meta.total_supply = meta.total_supply.checked_sub(burned)?;
         Ok(meta)
     })?;
+    record_total_supply(deps.storage, env.block.height, token_info.total_supply)?;
+    record_burn(deps.storage, &owner_addr, burned)?;
 
-    let res = Response::new().add_attributes(vec![
-        attr("action", "burn_from"),
+    hooks.extend(transfer_hook_msgs(deps.storage, &owner_addr, None, burned)?);
+    let action = if forced {
+        "forced_burn_from"
+    } else {
+        "burn_from"
+    };
+    let res = Response::new().add_submessages(hooks).add_attributes(vec![
+        attr("action", action),
         attr("from", owner),
         attr("by", info.sender),
         attr("amount", amount),
     ]);
-    Ok(res)
+    let res = res.add_attributes(fee_attributes(burned, tax.as_ref()));
+    Ok(res.add_events(event).add_submessages(notification))
 }
 
 pub fn execute_send_from(
@@ -205,24 +310,40 @@ pub fn execute_send_from(
 ) -> Result<Response, ContractError> {
     let rcpt_addr = deps.api.addr_validate(&contract)?;
     let owner_addr = deps.api.addr_validate(&owner)?;
+    ensure_not_frozen(deps.storage, &owner_addr)?;
+    ensure_unlocked(deps.storage, &env.block, &owner_addr, amount)?;
+    PAUSE.assert_not_paused(deps.storage, &env.block)?;
+    let (net, fee) = split_transfer_fee(deps.storage, &owner_addr, &rcpt_addr, amount)?;
+    ensure_within_wallet_limits(deps.storage, Some(&owner_addr), &rcpt_addr, amount, net)?;
 
     // deduct allowance before doing anything else have enough allowance
-    deduct_allowance(deps.storage, &owner_addr, &info.sender, &env.block, amount)?;
+    let remaining = deduct_allowance(deps.storage, &owner_addr, &info.sender, &env.block, amount)?;
+    let (event, notification) =
+        allowance_spent_notification(deps.as_ref(), &owner_addr, &info.sender, amount, remaining)?;
 
     // move the tokens to the contract
-    BALANCES.update(
+    update_balance(
         deps.storage,
+        env.block.height,
         &owner_addr,
         |balance: Option<Uint128>| -> StdResult<_> {
             Ok(balance.unwrap_or_default().checked_sub(amount)?)
         },
     )?;
-    BALANCES.update(
+    update_balance(
         deps.storage,
+        env.block.height,
         &rcpt_addr,
-        |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + amount) },
+        |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + net) },
     )?;
 
+    let mut hooks = transfer_hook_msgs(deps.storage, &owner_addr, Some(&rcpt_addr), net)?;
+    hooks.extend(collect_transfer_fee(
+        deps.storage,
+        env.block.height,
+        &owner_addr,
+        fee.as_ref(),
+    )?);
     let attrs = vec![
         attr("action", "send_from"),
         attr("from", &owner),
@@ -230,16 +351,22 @@ pub fn execute_send_from(
         attr("by", &info.sender),
         attr("amount", amount),
     ];
+    let attrs = [attrs, fee_attributes(net, fee.as_ref())].concat();
 
     // create a send message
     let msg = Cw20ReceiveMsg {
         sender: info.sender.into(),
-        amount,
+        amount: net,
         msg,
     }
     .into_cosmos_msg(contract)?;
 
-    let res = Response::new().add_message(msg).add_attributes(attrs);
+    let res = Response::new()
+        .add_message(msg)
+        .add_attributes(attrs)
+        .add_event(event)
+        .add_submessages(notification)
+        .add_submessages(hooks);
     Ok(res)
 }
 
@@ -257,7 +384,9 @@ mod tests {
     use super::*;
 
     use cosmwasm_std::testing::{mock_dependencies_with_balance, mock_env, mock_info};
-    use cosmwasm_std::{coins, CosmosMsg, SubMsg, Timestamp, WasmMsg};
+    use cosmwasm_std::{
+        coins, to_binary, ContractResult, CosmosMsg, SystemError, SystemResult, Timestamp, WasmMsg,
+    };
     use cw20::{Cw20Coin, TokenInfoResponse};
 
     use crate::contract::{execute, instantiate, query_balance, query_token_info};
@@ -283,6 +412,9 @@ mod tests {
             }],
             mint: None,
             marketing: None,
+            snapshots: false,
+            freezer: None,
+            pauser: None,
         };
         let info = mock_info("creator", &[]);
         let env = mock_env();
@@ -760,6 +892,89 @@ mod tests {
         assert_eq!(err, ContractError::Expired {});
     }
 
+    #[test]
+    fn allowance_spent_notifies_owner() {
+        let mut deps = mock_dependencies_with_balance(&[]);
+        let owner = String::from("addr0001");
+        let spender = String::from("addr0002");
+        let rcpt = String::from("addr0003");
+
+        do_instantiate(deps.as_mut(), &owner, Uint128::new(999999));
+
+        let msg = ExecuteMsg::IncreaseAllowance {
+            spender: spender.clone(),
+            amount: Uint128::new(77777),
+            expires: None,
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(owner.as_ref(), &[]),
+            msg,
+        )
+        .unwrap();
+
+        // owner is not a contract, only the event is emitted
+        let msg = ExecuteMsg::TransferFrom {
+            owner: owner.clone(),
+            recipient: rcpt.clone(),
+            amount: Uint128::new(44444),
+        };
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(spender.as_ref(), &[]),
+            msg,
+        )
+        .unwrap();
+        assert!(res.messages.is_empty());
+        assert_eq!(
+            res.events,
+            vec![Event::new("allowance_spent").add_attributes(vec![
+                attr("owner", &owner),
+                attr("spender", &spender),
+                attr("amount", "44444"),
+                attr("remaining", "33333"),
+            ])]
+        );
+
+        // owner is a contract, it gets notified as well
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::ContractInfo { .. } => SystemResult::Ok(ContractResult::Ok(
+                to_binary(&ContractInfoResponse::new(1, "creator")).unwrap(),
+            )),
+            _ => SystemResult::Err(SystemError::Unknown {}),
+        });
+        let msg = ExecuteMsg::BurnFrom {
+            owner: owner.clone(),
+            amount: Uint128::new(33),
+        };
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(spender.as_ref(), &[]),
+            msg,
+        )
+        .unwrap();
+        let notification = Cw20AllowanceSpentMsg {
+            spender,
+            amount: Uint128::new(33),
+            remaining: AllowanceResponse {
+                allowance: Uint128::new(33300),
+                expires: Expiration::Never {},
+            },
+        }
+        .into_cosmos_msg(owner)
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::reply_on_error(
+                notification,
+                ALLOWANCE_SPENT_REPLY_ID
+            )]
+        );
+    }
+
     #[test]
     fn no_past_expiration() {
         let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
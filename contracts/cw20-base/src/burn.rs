@@ -0,0 +1,264 @@
+use cosmwasm_std::{Addr, Deps, DepsMut, MessageInfo, Response, StdResult, Storage, Uint128};
+
+use crate::error::ContractError;
+use crate::hooks::assert_minter;
+use crate::msg::{BurnedByResponse, BurnerResponse, TotalBurnedResponse};
+use crate::state::{BURNED_BY, BURNER, TOTAL_BURNED};
+
+/// Adds `amount` burned from `addr` to the burn history. Must be called on every burn
+pub fn record_burn(storage: &mut dyn Storage, addr: &Addr, amount: Uint128) -> StdResult<()> {
+    let total = TOTAL_BURNED.may_load(storage)?.unwrap_or_default();
+    TOTAL_BURNED.save(storage, &total.checked_add(amount)?)?;
+    BURNED_BY.update(storage, addr, |burned| -> StdResult<_> {
+        Ok(burned.unwrap_or_default().checked_add(amount)?)
+    })?;
+    Ok(())
+}
+
+/// Whether the address can burn from any account without an allowance
+pub fn is_burner(storage: &dyn Storage, addr: &Addr) -> StdResult<bool> {
+    Ok(BURNER.may_load(storage)?.as_ref() == Some(addr))
+}
+
+/// Sets the burner, or removes the role (with `None`). Must be called by the minter.
+pub fn execute_update_burner(
+    deps: DepsMut,
+    info: MessageInfo,
+    new_burner: Option<String>,
+) -> Result<Response, ContractError> {
+    assert_minter(deps.storage, &info.sender)?;
+
+    let burner = new_burner
+        .as_deref()
+        .map(|addr| deps.api.addr_validate(addr))
+        .transpose()?;
+    match &burner {
+        Some(burner) => BURNER.save(deps.storage, burner)?,
+        None => BURNER.remove(deps.storage),
+    }
+
+    let res = Response::new()
+        .add_attribute("action", "update_burner")
+        .add_attribute(
+            "new_burner",
+            burner
+                .map(Addr::into_string)
+                .unwrap_or_else(|| "None".to_string()),
+        );
+    Ok(res)
+}
+
+pub fn query_burner(deps: Deps) -> StdResult<BurnerResponse> {
+    let burner = BURNER.may_load(deps.storage)?.map(Addr::into_string);
+    Ok(BurnerResponse { burner })
+}
+
+pub fn query_total_burned(deps: Deps) -> StdResult<TotalBurnedResponse> {
+    let total_burned = TOTAL_BURNED.may_load(deps.storage)?.unwrap_or_default();
+    Ok(TotalBurnedResponse { total_burned })
+}
+
+pub fn query_burned_by(deps: Deps, address: String) -> StdResult<BurnedByResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let burned = BURNED_BY
+        .may_load(deps.storage, &address)?
+        .unwrap_or_default();
+    Ok(BurnedByResponse { burned })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{attr, from_binary};
+    use cw20::{Cw20Coin, MinterResponse};
+
+    use crate::contract::{execute, instantiate, query, query_token_info};
+    use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+
+    fn total_burned(deps: Deps) -> Uint128 {
+        let res: TotalBurnedResponse =
+            from_binary(&query(deps, mock_env(), QueryMsg::TotalBurned {}).unwrap()).unwrap();
+        res.total_burned
+    }
+
+    fn burned_by(deps: Deps, address: &str) -> Uint128 {
+        let msg = QueryMsg::BurnedBy {
+            address: address.to_string(),
+        };
+        let res: BurnedByResponse = from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+        res.burned
+    }
+
+    #[test]
+    fn burns_are_accounted() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            name: "Deflationary".to_string(),
+            symbol: "DEFL".to_string(),
+            decimals: 6,
+            initial_balances: vec![
+                Cw20Coin {
+                    address: "alice".to_string(),
+                    amount: Uint128::new(1000),
+                },
+                Cw20Coin {
+                    address: "bob".to_string(),
+                    amount: Uint128::new(1000),
+                },
+            ],
+            mint: None,
+            marketing: None,
+            snapshots: false,
+            freezer: None,
+            pauser: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        assert_eq!(total_burned(deps.as_ref()), Uint128::zero());
+        assert_eq!(burned_by(deps.as_ref(), "alice"), Uint128::zero());
+
+        // the memo is reported with the burn
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[]),
+            ExecuteMsg::Burn {
+                amount: Uint128::new(100),
+                memo: Some("buyback #1".to_string()),
+            },
+        )
+        .unwrap();
+        assert_eq!(res.attributes.last(), Some(&attr("memo", "buyback #1")));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[]),
+            ExecuteMsg::Burn {
+                amount: Uint128::new(50),
+                memo: None,
+            },
+        )
+        .unwrap();
+
+        // burning from an allowance is accounted to the owner
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bob", &[]),
+            ExecuteMsg::IncreaseAllowance {
+                spender: "alice".to_string(),
+                amount: Uint128::new(300),
+                expires: None,
+            },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[]),
+            ExecuteMsg::BurnFrom {
+                owner: "bob".to_string(),
+                amount: Uint128::new(300),
+            },
+        )
+        .unwrap();
+
+        assert_eq!(burned_by(deps.as_ref(), "alice"), Uint128::new(150));
+        assert_eq!(burned_by(deps.as_ref(), "bob"), Uint128::new(300));
+        assert_eq!(total_burned(deps.as_ref()), Uint128::new(450));
+        assert_eq!(
+            query_token_info(deps.as_ref()).unwrap().total_supply,
+            Uint128::new(1550)
+        );
+    }
+
+    #[test]
+    fn burner_burns_without_allowance() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            name: "Bridged".to_string(),
+            symbol: "BRG".to_string(),
+            decimals: 6,
+            initial_balances: vec![Cw20Coin {
+                address: "alice".to_string(),
+                amount: Uint128::new(1000),
+            }],
+            mint: Some(MinterResponse {
+                minter: "minter".to_string(),
+                cap: None,
+            }),
+            marketing: None,
+            snapshots: false,
+            freezer: None,
+            pauser: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let burn_from = ExecuteMsg::BurnFrom {
+            owner: "alice".to_string(),
+            amount: Uint128::new(400),
+        };
+        // disabled by default
+        let burner = |deps: Deps| -> Option<String> {
+            let res: BurnerResponse =
+                from_binary(&query(deps, mock_env(), QueryMsg::Burner {}).unwrap()).unwrap();
+            res.burner
+        };
+        assert_eq!(burner(deps.as_ref()), None);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bridge", &[]),
+            burn_from.clone(),
+        )
+        .unwrap_err();
+
+        // only the minter sets the burner
+        let update = ExecuteMsg::UpdateBurner {
+            new_burner: Some("bridge".to_string()),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bridge", &[]),
+            update.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), mock_env(), mock_info("minter", &[]), update).unwrap();
+        assert_eq!(burner(deps.as_ref()), Some("bridge".to_string()));
+
+        // the burner needs no allowance, and its burns are clearly reported
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bridge", &[]),
+            burn_from.clone(),
+        )
+        .unwrap();
+        assert_eq!(res.attributes[0], attr("action", "forced_burn_from"));
+        assert!(res.events.is_empty());
+        assert_eq!(burned_by(deps.as_ref(), "alice"), Uint128::new(400));
+        assert_eq!(
+            query_token_info(deps.as_ref()).unwrap().total_supply,
+            Uint128::new(600)
+        );
+
+        // until the role is removed
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("minter", &[]),
+            ExecuteMsg::UpdateBurner { new_burner: None },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bridge", &[]),
+            burn_from,
+        )
+        .unwrap_err();
+    }
+}
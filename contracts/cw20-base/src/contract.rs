@@ -2,32 +2,89 @@
 use cosmwasm_std::entry_point;
 use cosmwasm_std::Order::Ascending;
 use cosmwasm_std::{
-    to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult, Uint128,
+    from_binary, to_binary, Addr, Binary, BlockInfo, Deps, DepsMut, Env, MessageInfo, Reply,
+    Response, StdError, StdResult, Storage, SubMsg, Uint128,
 };
 
 use cw2::set_contract_version;
 use cw20::{
-    BalanceResponse, Cw20Coin, Cw20ReceiveMsg, DownloadLogoResponse, EmbeddedLogo, Logo, LogoInfo,
-    MarketingInfoResponse, MinterResponse, TokenInfoResponse,
+    BalanceResponse, Cw20Coin, Cw20ReceiveAck, Cw20ReceiveMsg, DownloadLogoResponse, EmbeddedLogo,
+    Expiration, Logo, LogoInfo, MarketingInfoResponse, TokenInfoResponse,
 };
-use cw_utils::ensure_from_older_version;
+use cw_utils::{ensure_from_older_version, parse_reply_execute_data};
 
+use crate::allowance_period::{execute_set_allowance_period, query_allowance_period};
 use crate::allowances::{
     execute_burn_from, execute_decrease_allowance, execute_increase_allowance, execute_send_from,
-    execute_transfer_from, query_allowance,
+    execute_transfer_from, query_allowance, ALLOWANCE_SPENT_REPLY_ID,
+};
+use crate::burn::{
+    execute_update_burner, query_burned_by, query_burner, query_total_burned, record_burn,
+};
+use crate::enumerable::{
+    query_all_accounts, query_all_accounts_with_balances, query_owner_allowances,
+    query_spender_allowances,
 };
-use crate::enumerable::{query_all_accounts, query_owner_allowances, query_spender_allowances};
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+use crate::escrow::{
+    execute_claim_transfer, execute_reclaim_transfer, execute_transfer_pending,
+    query_pending_transfers_by_recipient, query_pending_transfers_by_sender,
+};
+use crate::fee::{
+    collect_transfer_fee, execute_set_fee_exempt, execute_update_burn_tax,
+    execute_update_transfer_fee, fee_attributes, query_burn_tax, query_fee_exempt,
+    query_transfer_fee, split_batch_transfer_fees, split_burn_tax, split_transfer_fee,
+};
+use crate::freeze::{
+    ensure_not_frozen, execute_freeze, execute_unfreeze, execute_update_freezer, query_freezer,
+    query_frozen_accounts,
+};
+use crate::holders::{
+    execute_continue_holders_backfill, init_holders, query_num_holders, query_top_holders,
+};
+use crate::hooks::{
+    assert_minter, execute_add_hook, execute_remove_hook, execute_update_mint_callback,
+    mint_callback_msg, query_mint_callback, transfer_hook_msgs, TRANSFER_HOOK_REPLY_ID,
+};
+use crate::import::{
+    execute_finish_import, execute_import_balances, query_import_status, start_import,
+};
+use crate::mint_limit::{
+    apply_mint_rate_limit, execute_update_mint_rate_limit, query_mint_rate_limit,
+};
+use crate::mint_schedule::{execute_mint_vested, execute_set_mint_schedule, query_mint_schedule};
+use crate::minters::{charge_minter, execute_remove_minter, execute_set_minter, query_minters};
+use crate::msg::{
+    CurrentMinterResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, PendingMarketingResponse,
+    QueryMsg,
+};
+use crate::permit::{execute_permit, execute_permit_transfer_from, query_nonce};
+use crate::rebase::{execute_rebase, execute_update_rebaser, load_balance, query_rebase_info};
+use crate::recover::execute_recover_stuck;
+use crate::rescale::{
+    ensure_not_rescaling, execute_continue_rescale, query_rescale_status, start_rescale,
+};
+use crate::snapshots::{
+    query_balance_at, query_total_supply_at, record_total_supply, update_balance,
+};
 use crate::state::{
-    MinterData, TokenInfo, ALLOWANCES, ALLOWANCES_SPENDER, BALANCES, LOGO, MARKETING_INFO,
-    TOKEN_INFO,
+    MinterData, TokenInfo, ALLOWANCES, ALLOWANCES_SPENDER, FREEZER, HOOKS, LOGO, MARKETING_INFO,
+    MINTERS, PAUSE, PAUSER, PENDING_MARKETING, PENDING_MINTER, SNAPSHOTS, TOKEN_INFO,
+};
+use crate::vesting::{ensure_unlocked, execute_set_vesting_lock, query_vesting_lock};
+use crate::votes::{execute_delegate, query_delegate, query_votes, query_votes_at};
+use crate::wallet_limits::{
+    ensure_batch_within_wallet_limits, ensure_within_wallet_limits, execute_set_limit_exempt,
+    execute_update_wallet_limits, query_limit_exempt, query_wallet_limits,
 };
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:cw20-base";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// Reply id of the receive messages of `SendAcked`, which must be acknowledged
+pub const RECEIVE_ACK_REPLY_ID: u64 = 3;
+
 const LOGO_SIZE_CAP: usize = 5 * 1024;
 
 /// Checks if data starts with XML preamble
@@ -94,15 +151,25 @@ fn verify_logo(logo: &Logo) -> Result<(), ContractError> {
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     mut deps: DepsMut,
-    _env: Env,
+    env: Env,
     _info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
     // check valid token info
     msg.validate()?;
+    SNAPSHOTS.save(deps.storage, &msg.snapshots)?;
+    if let Some(freezer) = &msg.freezer {
+        FREEZER.save(deps.storage, &deps.api.addr_validate(freezer)?)?;
+    }
+    let pauser = msg
+        .pauser
+        .as_ref()
+        .map(|pauser| deps.api.addr_validate(pauser))
+        .transpose()?;
+    PAUSER.set(deps.branch(), pauser)?;
     // create initial accounts
-    let total_supply = create_accounts(&mut deps, &msg.initial_balances)?;
+    let total_supply = create_accounts(&mut deps, &msg.initial_balances, env.block.height)?;
 
     if let Some(limit) = msg.get_cap() {
         if total_supply > limit {
@@ -127,6 +194,7 @@ pub fn instantiate(
         mint,
     };
     TOKEN_INFO.save(deps.storage, &data)?;
+    record_total_supply(deps.storage, env.block.height, total_supply)?;
 
     if let Some(marketing) = msg.marketing {
         let logo = if let Some(logo) = marketing.logo {
@@ -159,13 +227,16 @@ pub fn instantiate(
 pub fn create_accounts(
     deps: &mut DepsMut,
     accounts: &[Cw20Coin],
+    height: u64,
 ) -> Result<Uint128, ContractError> {
     validate_accounts(accounts)?;
 
     let mut total_supply = Uint128::zero();
     for row in accounts {
         let address = deps.api.addr_validate(&row.address)?;
-        BALANCES.save(deps.storage, &address, &row.amount)?;
+        update_balance(deps.storage, height, &address, |_| -> StdResult<_> {
+            Ok(row.amount)
+        })?;
         total_supply += row.amount;
     }
 
@@ -191,16 +262,30 @@ pub fn execute(
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
+    // nothing can move while balances are in different units
+    if !matches!(msg, ExecuteMsg::ContinueRescale {}) {
+        ensure_not_rescaling(deps.storage)?;
+    }
     match msg {
         ExecuteMsg::Transfer { recipient, amount } => {
             execute_transfer(deps, env, info, recipient, amount)
         }
-        ExecuteMsg::Burn { amount } => execute_burn(deps, env, info, amount),
+        ExecuteMsg::Burn { amount, memo } => execute_burn(deps, env, info, amount, memo),
         ExecuteMsg::Send {
             contract,
             amount,
             msg,
         } => execute_send(deps, env, info, contract, amount, msg),
+        ExecuteMsg::SendAcked {
+            contract,
+            amount,
+            msg,
+        } => execute_send_acked(deps, env, info, contract, amount, msg),
+        ExecuteMsg::SendOrTransfer {
+            contract,
+            amount,
+            msg,
+        } => execute_send_or_transfer(deps, env, info, contract, amount, msg),
         ExecuteMsg::Mint { recipient, amount } => execute_mint(deps, env, info, recipient, amount),
         ExecuteMsg::IncreaseAllowance {
             spender,
@@ -212,12 +297,37 @@ pub fn execute(
             amount,
             expires,
         } => execute_decrease_allowance(deps, env, info, spender, amount, expires),
+        ExecuteMsg::SetAllowancePeriod { spender, period } => {
+            execute_set_allowance_period(deps, info, spender, period)
+        }
         ExecuteMsg::TransferFrom {
             owner,
             recipient,
             amount,
         } => execute_transfer_from(deps, env, info, owner, recipient, amount),
         ExecuteMsg::BurnFrom { owner, amount } => execute_burn_from(deps, env, info, owner, amount),
+        ExecuteMsg::Permit {
+            owner,
+            spender,
+            amount,
+            nonce,
+            deadline,
+            pubkey,
+            signature,
+        } => execute_permit(
+            deps, env, owner, spender, amount, nonce, deadline, pubkey, signature,
+        ),
+        ExecuteMsg::PermitTransferFrom {
+            owner,
+            recipient,
+            amount,
+            nonce,
+            deadline,
+            pubkey,
+            signature,
+        } => execute_permit_transfer_from(
+            deps, env, info, owner, recipient, amount, nonce, deadline, pubkey, signature,
+        ),
         ExecuteMsg::SendFrom {
             owner,
             contract,
@@ -229,16 +339,93 @@ pub fn execute(
             description,
             marketing,
         } => execute_update_marketing(deps, env, info, project, description, marketing),
+        ExecuteMsg::AcceptMarketing {} => execute_accept_marketing(deps, info),
+        ExecuteMsg::AcceptMinter {} => execute_accept_minter(deps, info),
+        ExecuteMsg::RenounceMinter {} => execute_renounce_minter(deps, info),
         ExecuteMsg::UploadLogo(logo) => execute_upload_logo(deps, env, info, logo),
         ExecuteMsg::UpdateMinter { new_minter } => {
             execute_update_minter(deps, env, info, new_minter)
         }
+        ExecuteMsg::AddHook { addr } => execute_add_hook(deps, info, addr),
+        ExecuteMsg::RemoveHook { addr } => execute_remove_hook(deps, info, addr),
+        ExecuteMsg::UpdateMintCallback { enabled } => {
+            execute_update_mint_callback(deps, info, enabled)
+        }
+        ExecuteMsg::TransferBatch { recipients } => {
+            execute_transfer_batch(deps, env, info, recipients)
+        }
+        ExecuteMsg::SendBatch { contracts } => execute_send_batch(deps, env, info, contracts),
+        ExecuteMsg::UpdateMintRateLimit { rate_limit } => {
+            execute_update_mint_rate_limit(deps, info, rate_limit)
+        }
+        ExecuteMsg::TransferPending {
+            recipient,
+            amount,
+            expires,
+        } => execute_transfer_pending(deps, env, info, recipient, amount, expires),
+        ExecuteMsg::ClaimTransfer { id } => execute_claim_transfer(deps, env, info, id),
+        ExecuteMsg::ReclaimTransfer { id } => execute_reclaim_transfer(deps, env, info, id),
+        ExecuteMsg::UpdateRebaser { new_rebaser } => {
+            execute_update_rebaser(deps, info, new_rebaser)
+        }
+        ExecuteMsg::Rebase { index } => execute_rebase(deps, info, index),
+        ExecuteMsg::UpdateBurner { new_burner } => execute_update_burner(deps, info, new_burner),
+        ExecuteMsg::SetMintSchedule { schedule } => {
+            execute_set_mint_schedule(deps, env, info, schedule)
+        }
+        ExecuteMsg::SetVestingLock { address, lock } => {
+            execute_set_vesting_lock(deps, info, address, lock)
+        }
+        ExecuteMsg::MintVested {} => execute_mint_vested(deps, env),
+        ExecuteMsg::SetMinter { address, cap } => execute_set_minter(deps, info, address, cap),
+        ExecuteMsg::RemoveMinter { address } => execute_remove_minter(deps, info, address),
+        ExecuteMsg::Freeze { address } => execute_freeze(deps, info, address),
+        ExecuteMsg::Unfreeze { address } => execute_unfreeze(deps, info, address),
+        ExecuteMsg::UpdateFreezer { new_freezer } => {
+            execute_update_freezer(deps, info, new_freezer)
+        }
+        ExecuteMsg::Pause { until } => Ok(PAUSE.execute_pause(
+            &PAUSER,
+            deps,
+            &env.block,
+            info,
+            until.unwrap_or(Expiration::Never {}),
+        )?),
+        ExecuteMsg::Unpause {} => Ok(PAUSE.execute_unpause(&PAUSER, deps, info)?),
+        ExecuteMsg::UpdatePauser { new_pauser } => {
+            let new_pauser = new_pauser
+                .map(|pauser| deps.api.addr_validate(&pauser))
+                .transpose()?;
+            Ok(PAUSER.execute_update_admin(deps, info, new_pauser)?)
+        }
+        ExecuteMsg::UpdateTransferFee { fee } => execute_update_transfer_fee(deps, info, fee),
+        ExecuteMsg::UpdateBurnTax { tax } => execute_update_burn_tax(deps, info, tax),
+        ExecuteMsg::SetFeeExempt { address, exempt } => {
+            execute_set_fee_exempt(deps, info, address, exempt)
+        }
+        ExecuteMsg::UpdateWalletLimits { limits } => {
+            execute_update_wallet_limits(deps, info, limits)
+        }
+        ExecuteMsg::SetLimitExempt { address, exempt } => {
+            execute_set_limit_exempt(deps, info, address, exempt)
+        }
+        ExecuteMsg::Delegate { delegatee } => execute_delegate(deps, env, info, delegatee),
+        ExecuteMsg::RecoverStuck { asset, recipient } => {
+            execute_recover_stuck(deps, env, info, asset, recipient)
+        }
+        ExecuteMsg::ImportBalances {
+            balances,
+            allowances,
+        } => execute_import_balances(deps, env, info, balances, allowances),
+        ExecuteMsg::FinishImport {} => execute_finish_import(deps, info),
+        ExecuteMsg::ContinueRescale {} => execute_continue_rescale(deps, env),
+        ExecuteMsg::ContinueHoldersBackfill {} => execute_continue_holders_backfill(deps),
     }
 }
 
 pub fn execute_transfer(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     recipient: String,
     amount: Uint128,
@@ -246,64 +433,103 @@ pub fn execute_transfer(
     if amount == Uint128::zero() {
         return Err(ContractError::InvalidZeroAmount {});
     }
+    PAUSE.assert_not_paused(deps.storage, &env.block)?;
+    ensure_not_frozen(deps.storage, &info.sender)?;
+    ensure_unlocked(deps.storage, &env.block, &info.sender, amount)?;
 
     let rcpt_addr = deps.api.addr_validate(&recipient)?;
+    let (net, fee) = split_transfer_fee(deps.storage, &info.sender, &rcpt_addr, amount)?;
+    ensure_within_wallet_limits(deps.storage, Some(&info.sender), &rcpt_addr, amount, net)?;
 
-    BALANCES.update(
+    update_balance(
         deps.storage,
+        env.block.height,
         &info.sender,
         |balance: Option<Uint128>| -> StdResult<_> {
             Ok(balance.unwrap_or_default().checked_sub(amount)?)
         },
     )?;
-    BALANCES.update(
+    update_balance(
         deps.storage,
+        env.block.height,
         &rcpt_addr,
-        |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + amount) },
+        |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + net) },
     )?;
 
+    let mut hooks = transfer_hook_msgs(deps.storage, &info.sender, Some(&rcpt_addr), net)?;
+    hooks.extend(collect_transfer_fee(
+        deps.storage,
+        env.block.height,
+        &info.sender,
+        fee.as_ref(),
+    )?);
     let res = Response::new()
+        .add_submessages(hooks)
         .add_attribute("action", "transfer")
         .add_attribute("from", info.sender)
         .add_attribute("to", recipient)
-        .add_attribute("amount", amount);
+        .add_attribute("amount", amount)
+        .add_attributes(fee_attributes(net, fee.as_ref()));
     Ok(res)
 }
 
 pub fn execute_burn(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     amount: Uint128,
+    memo: Option<String>,
 ) -> Result<Response, ContractError> {
     if amount == Uint128::zero() {
         return Err(ContractError::InvalidZeroAmount {});
     }
+    PAUSE.assert_not_paused(deps.storage, &env.block)?;
+    ensure_not_frozen(deps.storage, &info.sender)?;
+    ensure_unlocked(deps.storage, &env.block, &info.sender, amount)?;
 
     // lower balance
-    BALANCES.update(
+    update_balance(
         deps.storage,
+        env.block.height,
         &info.sender,
         |balance: Option<Uint128>| -> StdResult<_> {
             Ok(balance.unwrap_or_default().checked_sub(amount)?)
         },
     )?;
+    // the tax goes to the treasury, only the rest is destroyed
+    let (burned, tax) = split_burn_tax(deps.storage, &info.sender, amount)?;
+    let mut hooks =
+        collect_transfer_fee(deps.storage, env.block.height, &info.sender, tax.as_ref())?;
     // reduce total_supply
-    TOKEN_INFO.update(deps.storage, |mut info| -> StdResult<_> {
-        info.total_supply = info.total_supply.checked_sub(amount)?;
+    let token_info = TOKEN_INFO.update(deps.storage, |mut info| -> StdResult<_> {
+        info.total_supply = info.total_supply.checked_sub(burned)?;
         Ok(info)
     })?;
+    record_total_supply(deps.storage, env.block.height, token_info.total_supply)?;
+    record_burn(deps.storage, &info.sender, burned)?;
 
+    hooks.extend(transfer_hook_msgs(
+        deps.storage,
+        &info.sender,
+        None,
+        burned,
+    )?);
     let res = Response::new()
+        .add_submessages(hooks)
         .add_attribute("action", "burn")
         .add_attribute("from", info.sender)
-        .add_attribute("amount", amount);
+        .add_attribute("amount", amount)
+        .add_attributes(fee_attributes(burned, tax.as_ref()));
+    let res = match memo {
+        Some(memo) => res.add_attribute("memo", memo),
+        None => res,
+    };
     Ok(res)
 }
 
 pub fn execute_mint(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     recipient: String,
     amount: Uint128,
@@ -311,18 +537,20 @@ pub fn execute_mint(
     if amount == Uint128::zero() {
         return Err(ContractError::InvalidZeroAmount {});
     }
+    PAUSE.assert_not_paused(deps.storage, &env.block)?;
 
     let mut config = TOKEN_INFO
         .may_load(deps.storage)?
         .ok_or(ContractError::Unauthorized {})?;
 
-    if config
+    // minting is disabled for everyone once the minter is removed
+    let is_minter = config
         .mint
         .as_ref()
         .ok_or(ContractError::Unauthorized {})?
         .minter
-        != info.sender
-    {
+        == info.sender;
+    if !is_minter && !MINTERS.has(deps.storage, &info.sender) {
         return Err(ContractError::Unauthorized {});
     }
 
@@ -333,17 +561,27 @@ pub fn execute_mint(
             return Err(ContractError::CannotExceedCap {});
         }
     }
+    let rcpt_addr = deps.api.addr_validate(&recipient)?;
+    ensure_within_wallet_limits(deps.storage, None, &rcpt_addr, amount, amount)?;
+    // additional minters are also held to their own cap
+    if !is_minter {
+        charge_minter(deps.storage, &info.sender, amount)?;
+    }
+    apply_mint_rate_limit(deps.storage, &env.block, amount)?;
     TOKEN_INFO.save(deps.storage, &config)?;
+    record_total_supply(deps.storage, env.block.height, config.total_supply)?;
 
     // add amount to recipient balance
-    let rcpt_addr = deps.api.addr_validate(&recipient)?;
-    BALANCES.update(
+    update_balance(
         deps.storage,
+        env.block.height,
         &rcpt_addr,
         |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + amount) },
     )?;
 
+    let callback = mint_callback_msg(deps.storage, &info.sender, &rcpt_addr, amount)?;
     let res = Response::new()
+        .add_messages(callback)
         .add_attribute("action", "mint")
         .add_attribute("to", recipient)
         .add_attribute("amount", amount);
@@ -352,86 +590,327 @@ pub fn execute_mint(
 
 pub fn execute_send(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
+    info: MessageInfo,
+    contract: String,
+    amount: Uint128,
+    msg: Binary,
+) -> Result<Response, ContractError> {
+    send(deps, env, info, contract, amount, msg, false)
+}
+
+/// Like `execute_send`, but the receive message replies on success, so the send can be reverted
+/// unless the receiving contract accepts the tokens with a `Cw20ReceiveAck`
+pub fn execute_send_acked(
+    deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     contract: String,
     amount: Uint128,
     msg: Binary,
+) -> Result<Response, ContractError> {
+    send(deps, env, info, contract, amount, msg, true)
+}
+
+fn send(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    contract: String,
+    amount: Uint128,
+    msg: Binary,
+    acked: bool,
 ) -> Result<Response, ContractError> {
     if amount == Uint128::zero() {
         return Err(ContractError::InvalidZeroAmount {});
     }
+    PAUSE.assert_not_paused(deps.storage, &env.block)?;
+    ensure_not_frozen(deps.storage, &info.sender)?;
+    ensure_unlocked(deps.storage, &env.block, &info.sender, amount)?;
 
     let rcpt_addr = deps.api.addr_validate(&contract)?;
+    let (net, fee) = split_transfer_fee(deps.storage, &info.sender, &rcpt_addr, amount)?;
+    ensure_within_wallet_limits(deps.storage, Some(&info.sender), &rcpt_addr, amount, net)?;
 
     // move the tokens to the contract
-    BALANCES.update(
+    update_balance(
         deps.storage,
+        env.block.height,
         &info.sender,
         |balance: Option<Uint128>| -> StdResult<_> {
             Ok(balance.unwrap_or_default().checked_sub(amount)?)
         },
     )?;
-    BALANCES.update(
+    update_balance(
         deps.storage,
+        env.block.height,
         &rcpt_addr,
-        |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + amount) },
+        |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + net) },
     )?;
 
+    let mut hooks = transfer_hook_msgs(deps.storage, &info.sender, Some(&rcpt_addr), net)?;
+    hooks.extend(collect_transfer_fee(
+        deps.storage,
+        env.block.height,
+        &info.sender,
+        fee.as_ref(),
+    )?);
     let res = Response::new()
         .add_attribute("action", "send")
         .add_attribute("from", &info.sender)
         .add_attribute("to", &contract)
         .add_attribute("amount", amount)
-        .add_message(
-            Cw20ReceiveMsg {
-                sender: info.sender.into(),
-                amount,
-                msg,
-            }
-            .into_cosmos_msg(contract)?,
-        );
+        .add_attributes(fee_attributes(net, fee.as_ref()));
+    let receive = Cw20ReceiveMsg {
+        sender: info.sender.into(),
+        amount: net,
+        msg,
+    }
+    .into_cosmos_msg(contract)?;
+    let res = if acked {
+        res.add_attribute("ack", "required")
+            .add_submessage(SubMsg::reply_on_success(receive, RECEIVE_ACK_REPLY_ID))
+    } else {
+        res.add_message(receive)
+    };
+    Ok(res.add_submessages(hooks))
+}
+
+/// Checks the `Cw20ReceiveAck` returned by the receiving contract of a `SendAcked`. Failing
+/// here reverts the whole send.
+fn handle_receive_ack(reply: Reply) -> Result<Response, ContractError> {
+    let data = parse_reply_execute_data(reply)
+        .map_err(|_| ContractError::MissingReceiveAck {})?
+        .data
+        .ok_or(ContractError::MissingReceiveAck {})?;
+    let ack: Cw20ReceiveAck =
+        from_binary(&data).map_err(|_| ContractError::MissingReceiveAck {})?;
+    match ack {
+        Cw20ReceiveAck::Accepted { payload } => {
+            let res = Response::new().add_attribute("ack", "accepted");
+            Ok(match payload {
+                Some(payload) => res.set_data(payload),
+                None => res,
+            })
+        }
+        Cw20ReceiveAck::Rejected { reason } => Err(ContractError::ReceiveRejected { reason }),
+    }
+}
+
+/// Sends to `contract` if it is a contract, or transfers to it otherwise, as the receive
+/// message would fail on a plain account. The "path" attribute tells which one was taken.
+pub fn execute_send_or_transfer(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    contract: String,
+    amount: Uint128,
+    msg: Binary,
+) -> Result<Response, ContractError> {
+    let is_contract = deps.querier.query_wasm_contract_info(&contract).is_ok();
+    if is_contract {
+        let res = execute_send(deps, env, info, contract, amount, msg)?;
+        Ok(res.add_attribute("path", "send"))
+    } else {
+        let res = execute_transfer(deps, env, info, contract, amount)?;
+        Ok(res.add_attribute("path", "transfer"))
+    }
+}
+
+pub fn execute_transfer_batch(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipients: Vec<(String, Uint128)>,
+) -> Result<Response, ContractError> {
+    PAUSE.assert_not_paused(deps.storage, &env.block)?;
+
+    let recipients_count = recipients.len();
+    let credits = recipients
+        .into_iter()
+        .map(|(recipient, amount)| Ok((deps.api.addr_validate(&recipient)?, amount)))
+        .collect::<StdResult<Vec<_>>>()?;
+    let amounts = credits.clone();
+    let (credits, fee) = split_batch_transfer_fees(deps.storage, &info.sender, credits)?;
+    ensure_batch_within_wallet_limits(deps.storage, &info.sender, &amounts, &credits)?;
+    let total = transfer_many(deps.storage, &env.block, &info.sender, &credits)?;
+
+    let mut hooks = vec![];
+    for (rcpt_addr, amount) in &credits {
+        hooks.extend(transfer_hook_msgs(
+            deps.storage,
+            &info.sender,
+            Some(rcpt_addr),
+            *amount,
+        )?);
+    }
+    let res = Response::new()
+        .add_submessages(hooks)
+        .add_attribute("action", "transfer_batch")
+        .add_attribute("from", info.sender)
+        .add_attribute("recipients", recipients_count.to_string())
+        .add_attribute("amount", total);
+    let res = if fee.is_zero() {
+        res
+    } else {
+        res.add_attribute("net_amount", total - fee)
+            .add_attribute("fee", fee)
+    };
     Ok(res)
 }
 
-pub fn execute_update_minter(
+pub fn execute_send_batch(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
-    new_minter: Option<String>,
+    contracts: Vec<(String, Uint128, Binary)>,
 ) -> Result<Response, ContractError> {
-    let mut config = TOKEN_INFO
-        .may_load(deps.storage)?
-        .ok_or(ContractError::Unauthorized {})?;
+    PAUSE.assert_not_paused(deps.storage, &env.block)?;
+
+    let recipients_count = contracts.len();
+    let credits = contracts
+        .iter()
+        .map(|(contract, amount, _)| Ok((deps.api.addr_validate(contract)?, *amount)))
+        .collect::<StdResult<Vec<_>>>()?;
+    let amounts = credits.clone();
+    let (credits, fee) = split_batch_transfer_fees(deps.storage, &info.sender, credits)?;
+    ensure_batch_within_wallet_limits(deps.storage, &info.sender, &amounts, &credits)?;
+    let total = transfer_many(deps.storage, &env.block, &info.sender, &credits)?;
+
+    let mut res = Response::new();
+    for ((rcpt_addr, amount), (_, _, msg)) in credits.iter().zip(contracts) {
+        let receive = Cw20ReceiveMsg {
+            sender: info.sender.to_string(),
+            amount: *amount,
+            msg,
+        };
+        res = res.add_message(receive.into_cosmos_msg(rcpt_addr)?);
+    }
+    for (rcpt_addr, amount) in &credits {
+        res = res.add_submessages(transfer_hook_msgs(
+            deps.storage,
+            &info.sender,
+            Some(rcpt_addr),
+            *amount,
+        )?);
+    }
+    let res = res
+        .add_attribute("action", "send_batch")
+        .add_attribute("from", info.sender)
+        .add_attribute("recipients", recipients_count.to_string())
+        .add_attribute("amount", total);
+    let res = if fee.is_zero() {
+        res
+    } else {
+        res.add_attribute("net_amount", total - fee)
+            .add_attribute("fee", fee)
+    };
+    Ok(res)
+}
 
-    let mint = config.mint.as_ref().ok_or(ContractError::Unauthorized {})?;
-    if mint.minter != info.sender {
-        return Err(ContractError::Unauthorized {});
+/// Debits the sum of all credits from the sender once, then credits every recipient.
+/// Returns the total amount moved.
+fn transfer_many(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    sender: &Addr,
+    credits: &[(Addr, Uint128)],
+) -> Result<Uint128, ContractError> {
+    if credits.is_empty() {
+        return Err(ContractError::EmptyBatch {});
+    }
+    ensure_not_frozen(storage, sender)?;
+    let mut total = Uint128::zero();
+    for (_, amount) in credits {
+        if amount.is_zero() {
+            return Err(ContractError::InvalidZeroAmount {});
+        }
+        total = total.checked_add(*amount).map_err(StdError::overflow)?;
     }
+    ensure_unlocked(storage, block, sender, total)?;
 
-    let minter_data = new_minter
-        .map(|new_minter| deps.api.addr_validate(&new_minter))
-        .transpose()?
-        .map(|minter| MinterData {
-            minter,
-            cap: mint.cap,
-        });
+    update_balance(
+        storage,
+        block.height,
+        sender,
+        |balance: Option<Uint128>| -> StdResult<_> {
+            Ok(balance.unwrap_or_default().checked_sub(total)?)
+        },
+    )?;
+    for (rcpt_addr, amount) in credits {
+        update_balance(
+            storage,
+            block.height,
+            rcpt_addr,
+            |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + amount) },
+        )?;
+    }
+    Ok(total)
+}
 
-    config.mint = minter_data;
+/// Proposes a new minter, who only takes over once it calls `AcceptMinter`. Proposing None
+/// withdraws the pending proposal.
+pub fn execute_update_minter(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    new_minter: Option<String>,
+) -> Result<Response, ContractError> {
+    assert_minter(deps.storage, &info.sender)?;
 
-    TOKEN_INFO.save(deps.storage, &config)?;
+    let pending_minter = new_minter
+        .map(|new_minter| deps.api.addr_validate(&new_minter))
+        .transpose()?;
+    match &pending_minter {
+        Some(pending) => PENDING_MINTER.save(deps.storage, pending)?,
+        None => PENDING_MINTER.remove(deps.storage),
+    }
 
     Ok(Response::default()
         .add_attribute("action", "update_minter")
         .add_attribute(
-            "new_minter",
-            config
-                .mint
-                .map(|m| m.minter.into_string())
+            "pending_minter",
+            pending_minter
+                .map(Addr::into_string)
                 .unwrap_or_else(|| "None".to_string()),
         ))
 }
 
+/// If proposed as the new minter with `UpdateMinter`, takes the role over, keeping the cap
+pub fn execute_accept_minter(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    match PENDING_MINTER.may_load(deps.storage)? {
+        Some(pending) if pending == info.sender => (),
+        _ => return Err(ContractError::Unauthorized {}),
+    }
+    let mut config = TOKEN_INFO.load(deps.storage)?;
+    let mint = config.mint.as_mut().ok_or(ContractError::Unauthorized {})?;
+
+    mint.minter = info.sender.clone();
+    TOKEN_INFO.save(deps.storage, &config)?;
+    PENDING_MINTER.remove(deps.storage);
+
+    Ok(Response::new()
+        .add_attribute("action", "accept_minter")
+        .add_attribute("new_minter", info.sender))
+}
+
+/// Removes the minter, along with any pending proposal. Nobody can mint anymore, forever.
+pub fn execute_renounce_minter(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    assert_minter(deps.storage, &info.sender)?;
+
+    let mut config = TOKEN_INFO.load(deps.storage)?;
+    config.mint = None;
+    TOKEN_INFO.save(deps.storage, &config)?;
+    PENDING_MINTER.remove(deps.storage);
+
+    Ok(Response::new()
+        .add_attribute("action", "renounce_minter")
+        .add_attribute("minter", info.sender))
+}
+
 pub fn execute_update_marketing(
     deps: DepsMut,
     _env: Env,
@@ -465,11 +944,27 @@ pub fn execute_update_marketing(
         None => (),
     }
 
+    // a new marketing account has to accept the role, so it cannot be handed to a wrong address
+    let mut pending = PENDING_MARKETING.may_load(deps.storage)?;
     match marketing {
-        Some(empty) if empty.trim().is_empty() => marketing_info.marketing = None,
-        Some(marketing) => marketing_info.marketing = Some(deps.api.addr_validate(&marketing)?),
+        Some(empty) if empty.trim().is_empty() => {
+            marketing_info.marketing = None;
+            pending = None;
+        }
+        Some(marketing) => {
+            let marketing = deps.api.addr_validate(&marketing)?;
+            pending = if marketing == info.sender {
+                None
+            } else {
+                Some(marketing)
+            };
+        }
         None => (),
     }
+    match &pending {
+        Some(pending) => PENDING_MARKETING.save(deps.storage, pending)?,
+        None => PENDING_MARKETING.remove(deps.storage),
+    }
 
     if marketing_info.project.is_none()
         && marketing_info.description.is_none()
@@ -481,7 +976,32 @@ pub fn execute_update_marketing(
         MARKETING_INFO.save(deps.storage, &marketing_info)?;
     }
 
-    let res = Response::new().add_attribute("action", "update_marketing");
+    let mut res = Response::new().add_attribute("action", "update_marketing");
+    if let Some(pending) = pending {
+        res = res.add_attribute("pending_marketing", pending);
+    }
+    Ok(res)
+}
+
+pub fn execute_accept_marketing(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    match PENDING_MARKETING.may_load(deps.storage)? {
+        Some(pending) if pending == info.sender => (),
+        _ => return Err(ContractError::Unauthorized {}),
+    }
+    let mut marketing_info = MARKETING_INFO
+        .may_load(deps.storage)?
+        .ok_or(ContractError::Unauthorized {})?;
+
+    marketing_info.marketing = Some(info.sender.clone());
+    MARKETING_INFO.save(deps.storage, &marketing_info)?;
+    PENDING_MARKETING.remove(deps.storage);
+
+    let res = Response::new()
+        .add_attribute("action", "accept_marketing")
+        .add_attribute("marketing", info.sender);
     Ok(res)
 }
 
@@ -521,14 +1041,24 @@ pub fn execute_upload_logo(
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Balance { address } => to_binary(&query_balance(deps, address)?),
         QueryMsg::TokenInfo {} => to_binary(&query_token_info(deps)?),
         QueryMsg::Minter {} => to_binary(&query_minter(deps)?),
+        QueryMsg::MintRateLimit {} => to_binary(&query_mint_rate_limit(deps, env)?),
+        QueryMsg::MintSchedule {} => to_binary(&query_mint_schedule(deps, env)?),
+        QueryMsg::VestingLock { address } => to_binary(&query_vesting_lock(deps, env, address)?),
+        QueryMsg::Minters { start_after, limit } => {
+            to_binary(&query_minters(deps, start_after, limit)?)
+        }
         QueryMsg::Allowance { owner, spender } => {
             to_binary(&query_allowance(deps, owner, spender)?)
         }
+        QueryMsg::AllowancePeriod { owner, spender } => {
+            to_binary(&query_allowance_period(deps, env, owner, spender)?)
+        }
+        QueryMsg::Nonce { owner } => to_binary(&query_nonce(deps, owner)?),
         QueryMsg::AllAllowances {
             owner,
             start_after,
@@ -547,16 +1077,72 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::AllAccounts { start_after, limit } => {
             to_binary(&query_all_accounts(deps, start_after, limit)?)
         }
+        QueryMsg::AllAccountsWithBalances { start_after, limit } => {
+            to_binary(&query_all_accounts_with_balances(deps, start_after, limit)?)
+        }
         QueryMsg::MarketingInfo {} => to_binary(&query_marketing_info(deps)?),
         QueryMsg::DownloadLogo {} => to_binary(&query_download_logo(deps)?),
+        QueryMsg::PendingMarketing {} => to_binary(&query_pending_marketing(deps)?),
+        QueryMsg::Freezer {} => to_binary(&query_freezer(deps)?),
+        QueryMsg::FrozenAccounts { start_after, limit } => {
+            to_binary(&query_frozen_accounts(deps, start_after, limit)?)
+        }
+        QueryMsg::Paused {} => to_binary(&PAUSE.query_paused(deps, &env.block)?),
+        QueryMsg::Pauser {} => to_binary(&PAUSER.query_admin(deps)?),
+        QueryMsg::TransferFee {} => to_binary(&query_transfer_fee(deps)?),
+        QueryMsg::BurnTax {} => to_binary(&query_burn_tax(deps)?),
+        QueryMsg::FeeExempt { start_after, limit } => {
+            to_binary(&query_fee_exempt(deps, start_after, limit)?)
+        }
+        QueryMsg::WalletLimits {} => to_binary(&query_wallet_limits(deps)?),
+        QueryMsg::LimitExempt { start_after, limit } => {
+            to_binary(&query_limit_exempt(deps, start_after, limit)?)
+        }
+        QueryMsg::PendingTransfersBySender {
+            sender,
+            start_after,
+            limit,
+        } => to_binary(&query_pending_transfers_by_sender(
+            deps,
+            sender,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::PendingTransfersByRecipient {
+            recipient,
+            start_after,
+            limit,
+        } => to_binary(&query_pending_transfers_by_recipient(
+            deps,
+            recipient,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::NumHolders {} => to_binary(&query_num_holders(deps)?),
+        QueryMsg::TopHolders { start_after, limit } => {
+            to_binary(&query_top_holders(deps, start_after, limit)?)
+        }
+        QueryMsg::RebaseInfo {} => to_binary(&query_rebase_info(deps)?),
+        QueryMsg::Burner {} => to_binary(&query_burner(deps)?),
+        QueryMsg::TotalBurned {} => to_binary(&query_total_burned(deps)?),
+        QueryMsg::BurnedBy { address } => to_binary(&query_burned_by(deps, address)?),
+        QueryMsg::ImportStatus {} => to_binary(&query_import_status(deps)?),
+        QueryMsg::RescaleStatus {} => to_binary(&query_rescale_status(deps)?),
+        QueryMsg::Delegate { address } => to_binary(&query_delegate(deps, address)?),
+        QueryMsg::Votes { address } => to_binary(&query_votes(deps, address)?),
+        QueryMsg::VotesAt { address, height } => to_binary(&query_votes_at(deps, address, height)?),
+        QueryMsg::Hooks {} => to_binary(&HOOKS.query_hooks(deps)?),
+        QueryMsg::MintCallback {} => to_binary(&query_mint_callback(deps)?),
+        QueryMsg::BalanceAt { address, height } => {
+            to_binary(&query_balance_at(deps, address, height)?)
+        }
+        QueryMsg::TotalSupplyAt { height } => to_binary(&query_total_supply_at(deps, height)?),
     }
 }
 
 pub fn query_balance(deps: Deps, address: String) -> StdResult<BalanceResponse> {
     let address = deps.api.addr_validate(&address)?;
-    let balance = BALANCES
-        .may_load(deps.storage, &address)?
-        .unwrap_or_default();
+    let balance = load_balance(deps.storage, &address)?;
     Ok(BalanceResponse { balance })
 }
 
@@ -571,18 +1157,28 @@ pub fn query_token_info(deps: Deps) -> StdResult<TokenInfoResponse> {
     Ok(res)
 }
 
-pub fn query_minter(deps: Deps) -> StdResult<Option<MinterResponse>> {
+pub fn query_minter(deps: Deps) -> StdResult<Option<CurrentMinterResponse>> {
     let meta = TOKEN_INFO.load(deps.storage)?;
     let minter = match meta.mint {
-        Some(m) => Some(MinterResponse {
+        Some(m) => Some(CurrentMinterResponse {
             minter: m.minter.into(),
             cap: m.cap,
+            pending_minter: PENDING_MINTER
+                .may_load(deps.storage)?
+                .map(Addr::into_string),
         }),
         None => None,
     };
     Ok(minter)
 }
 
+pub fn query_pending_marketing(deps: Deps) -> StdResult<PendingMarketingResponse> {
+    let pending_marketing = PENDING_MARKETING
+        .may_load(deps.storage)?
+        .map(Addr::into_string);
+    Ok(PendingMarketingResponse { pending_marketing })
+}
+
 pub fn query_marketing_info(deps: Deps) -> StdResult<MarketingInfoResponse> {
     Ok(MARKETING_INFO.may_load(deps.storage)?.unwrap_or_default())
 }
@@ -603,7 +1199,7 @@ pub fn query_download_logo(deps: Deps) -> StdResult<DownloadLogoResponse> {
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+pub fn migrate(mut deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
     let original_version =
         ensure_from_older_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
@@ -616,7 +1212,30 @@ pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, C
             ALLOWANCES_SPENDER.save(deps.storage, (&spender, &owner), &allowance)?;
         }
     }
-    Ok(Response::default())
+
+    let mut res = match msg.import {
+        Some(config) => start_import(deps.branch(), config)?,
+        None => Response::default(),
+    };
+    // count and index the holders of tokens deployed before they were tracked, in batches
+    if init_holders(deps.storage)? {
+        res = res.add_attribute("holders_backfill", "started");
+    }
+    if let Some(decimals) = msg.decimals {
+        res = res.add_attributes(start_rescale(deps, decimals)?.attributes);
+    }
+    Ok(res)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(_deps: DepsMut, _env: Env, reply: Reply) -> Result<Response, ContractError> {
+    match reply.id {
+        // owner notifications are fire-and-forget, a failing owner contract must not block spending
+        // as are transfer hooks, a failing hook contract must not block token movements
+        ALLOWANCE_SPENT_REPLY_ID | TRANSFER_HOOK_REPLY_ID => Ok(Response::new()),
+        RECEIVE_ACK_REPLY_ID => handle_receive_ack(reply),
+        id => Err(ContractError::UnknownReplyId { id }),
+    }
 }
 
 #[cfg(test)]
@@ -624,10 +1243,15 @@ mod tests {
     use cosmwasm_std::testing::{
         mock_dependencies, mock_dependencies_with_balance, mock_env, mock_info,
     };
-    use cosmwasm_std::{coins, from_binary, Addr, CosmosMsg, StdError, SubMsg, WasmMsg};
+    use cosmwasm_std::{
+        attr, coins, from_binary, Addr, ContractInfoResponse, ContractResult, CosmosMsg, StdError,
+        SubMsg, SubMsgResponse, SubMsgResult, SystemError, SystemResult, WasmMsg, WasmQuery,
+    };
+    use cw20::{Cw20MintCallbackMsg, Cw20TransferHookMsg, MinterResponse};
+    use cw_controllers::{AdminError, PauseError, PausedResponse};
 
     use super::*;
-    use crate::msg::InstantiateMarketingInfo;
+    use crate::msg::{InstantiateMarketingInfo, MintCallbackResponse};
 
     fn get_balance<T: Into<String>>(deps: Deps, address: T) -> Uint128 {
         query_balance(deps, address.into()).unwrap().balance
@@ -674,6 +1298,9 @@ mod tests {
             }],
             mint: mint.clone(),
             marketing: None,
+            snapshots: false,
+            freezer: None,
+            pauser: None,
         };
         let info = mock_info("creator", &[]);
         let env = mock_env();
@@ -691,7 +1318,12 @@ mod tests {
             }
         );
         assert_eq!(get_balance(deps.as_ref(), addr), amount);
-        assert_eq!(query_minter(deps.as_ref()).unwrap(), mint,);
+        let expected = mint.map(|m| CurrentMinterResponse {
+            minter: m.minter,
+            cap: m.cap,
+            pending_minter: None,
+        });
+        assert_eq!(query_minter(deps.as_ref()).unwrap(), expected);
         meta
     }
 
@@ -714,6 +1346,9 @@ mod tests {
                 }],
                 mint: None,
                 marketing: None,
+                snapshots: false,
+                freezer: None,
+                pauser: None,
             };
             let info = mock_info("creator", &[]);
             let env = mock_env();
@@ -754,6 +1389,9 @@ mod tests {
                     cap: Some(limit),
                 }),
                 marketing: None,
+                snapshots: false,
+                freezer: None,
+                pauser: None,
             };
             let info = mock_info("creator", &[]);
             let env = mock_env();
@@ -775,9 +1413,10 @@ mod tests {
             );
             assert_eq!(
                 query_minter(deps.as_ref()).unwrap(),
-                Some(MinterResponse {
+                Some(CurrentMinterResponse {
                     minter,
                     cap: Some(limit),
+                    pending_minter: None,
                 }),
             );
         }
@@ -801,6 +1440,9 @@ mod tests {
                     cap: Some(limit),
                 }),
                 marketing: None,
+                snapshots: false,
+                freezer: None,
+                pauser: None,
             };
             let info = mock_info("creator", &[]);
             let env = mock_env();
@@ -829,6 +1471,9 @@ mod tests {
                         marketing: Some("marketing".to_owned()),
                         logo: Some(Logo::Url("url".to_owned())),
                     }),
+                    snapshots: false,
+                    freezer: None,
+                    pauser: None,
                 };
 
                 let info = mock_info("creator", &[]);
@@ -869,6 +1514,9 @@ mod tests {
                         marketing: Some("m".to_owned()),
                         logo: Some(Logo::Url("url".to_owned())),
                     }),
+                    snapshots: false,
+                    freezer: None,
+                    pauser: None,
                 };
 
                 let info = mock_info("creator", &[]);
@@ -975,6 +1623,33 @@ mod tests {
         let env = mock_env();
         let res = execute(deps.as_mut(), env.clone(), info, msg);
         assert!(res.is_ok());
+
+        // the new minter is only proposed until it accepts
+        assert_eq!(
+            query_minter(deps.as_ref()).unwrap(),
+            Some(CurrentMinterResponse {
+                minter: minter.clone(),
+                cap,
+                pending_minter: Some(new_minter.to_string()),
+            })
+        );
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(&minter, &[]),
+            ExecuteMsg::AcceptMinter {},
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(new_minter, &[]),
+            ExecuteMsg::AcceptMinter {},
+        )
+        .unwrap();
+
+        // without a pending minter, the response matches the cw20 spec
         let query_minter_msg = QueryMsg::Minter {};
         let res = query(deps.as_ref(), env, query_minter_msg);
         let mint: MinterResponse = from_binary(&res.unwrap()).unwrap();
@@ -985,7 +1660,7 @@ mod tests {
     }
 
     #[test]
-    fn others_cannot_update_minter() {
+    fn proposed_minter_can_be_withdrawn() {
         let mut deps = mock_dependencies();
         let minter = String::from("minter");
         do_instantiate_with_minter(
@@ -997,30 +1672,71 @@ mod tests {
         );
 
         let msg = ExecuteMsg::UpdateMinter {
-            new_minter: Some("new_minter".to_string()),
+            new_minter: Some("typo".to_string()),
         };
+        execute(deps.as_mut(), mock_env(), mock_info(&minter, &[]), msg).unwrap();
+        let msg = ExecuteMsg::UpdateMinter { new_minter: None };
+        execute(deps.as_mut(), mock_env(), mock_info(&minter, &[]), msg).unwrap();
 
-        let info = mock_info("not the minter", &[]);
-        let env = mock_env();
-        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        // the minter is kept, and the typo cannot take over
+        let mint = query_minter(deps.as_ref()).unwrap().unwrap();
+        assert_eq!(mint.minter, minter);
+        assert_eq!(mint.pending_minter, None);
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("typo", &[]),
+            ExecuteMsg::AcceptMinter {},
+        )
+        .unwrap_err();
         assert_eq!(err, ContractError::Unauthorized {});
     }
 
     #[test]
-    fn unset_minter() {
+    fn others_cannot_update_minter() {
         let mut deps = mock_dependencies();
         let minter = String::from("minter");
-        let cap = None;
         do_instantiate_with_minter(
             deps.as_mut(),
             &String::from("genesis"),
             Uint128::new(1234),
             &minter,
-            cap,
+            None,
         );
 
-        let msg = ExecuteMsg::UpdateMinter { new_minter: None };
+        let msg = ExecuteMsg::UpdateMinter {
+            new_minter: Some("new_minter".to_string()),
+        };
 
+        let info = mock_info("not the minter", &[]);
+        let env = mock_env();
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+    }
+
+    #[test]
+    fn unset_minter() {
+        let mut deps = mock_dependencies();
+        let minter = String::from("minter");
+        let cap = None;
+        do_instantiate_with_minter(
+            deps.as_mut(),
+            &String::from("genesis"),
+            Uint128::new(1234),
+            &minter,
+            cap,
+        );
+
+        let msg = ExecuteMsg::RenounceMinter {};
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
         let info = mock_info(&minter, &[]);
         let env = mock_env();
         let res = execute(deps.as_mut(), env.clone(), info, msg);
@@ -1085,6 +1801,9 @@ mod tests {
             ],
             mint: None,
             marketing: None,
+            snapshots: false,
+            freezer: None,
+            pauser: None,
         };
         let err =
             instantiate(deps.as_mut(), env.clone(), info.clone(), instantiate_msg).unwrap_err();
@@ -1107,6 +1826,9 @@ mod tests {
             ],
             mint: None,
             marketing: None,
+            snapshots: false,
+            freezer: None,
+            pauser: None,
         };
         let res = instantiate(deps.as_mut(), env, info, instantiate_msg).unwrap();
         assert_eq!(0, res.messages.len());
@@ -1235,6 +1957,7 @@ mod tests {
         let env = mock_env();
         let msg = ExecuteMsg::Burn {
             amount: Uint128::zero(),
+            memo: None,
         };
         let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
         assert_eq!(err, ContractError::InvalidZeroAmount {});
@@ -1246,7 +1969,10 @@ mod tests {
         // cannot burn more than we have
         let info = mock_info(addr1.as_ref(), &[]);
         let env = mock_env();
-        let msg = ExecuteMsg::Burn { amount: too_much };
+        let msg = ExecuteMsg::Burn {
+            amount: too_much,
+            memo: None,
+        };
         let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
         assert!(matches!(err, ContractError::Std(StdError::Overflow { .. })));
         assert_eq!(
@@ -1257,7 +1983,10 @@ mod tests {
         // valid burn reduces total supply
         let info = mock_info(addr1.as_ref(), &[]);
         let env = mock_env();
-        let msg = ExecuteMsg::Burn { amount: burn };
+        let msg = ExecuteMsg::Burn {
+            amount: burn,
+            memo: None,
+        };
         let res = execute(deps.as_mut(), env, info, msg).unwrap();
         assert_eq!(res.messages.len(), 0);
 
@@ -1343,6 +2072,532 @@ mod tests {
         );
     }
 
+    #[test]
+    fn send_or_transfer_falls_back_to_transfer() {
+        let mut deps = mock_dependencies();
+        let owner = String::from("addr0001");
+        let amount = Uint128::new(5000);
+        do_instantiate(deps.as_mut(), &owner, amount);
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::ContractInfo { contract_addr } if contract_addr == "contract" => {
+                SystemResult::Ok(ContractResult::Ok(
+                    to_binary(&ContractInfoResponse::new(1, "creator")).unwrap(),
+                ))
+            }
+            _ => SystemResult::Err(SystemError::NoSuchContract {
+                addr: "unknown".to_string(),
+            }),
+        });
+        let send_msg = Binary::from(r#"{"some":123}"#.as_bytes());
+        let send_or_transfer = |contract: &str| ExecuteMsg::SendOrTransfer {
+            contract: contract.to_string(),
+            amount: Uint128::new(1000),
+            msg: send_msg.clone(),
+        };
+
+        // a contract gets the receive message
+        let info = mock_info(owner.as_ref(), &[]);
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            send_or_transfer("contract"),
+        )
+        .unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert!(res.attributes.contains(&attr("action", "send")));
+        assert!(res.attributes.contains(&attr("path", "send")));
+
+        // a plain account is transferred to instead
+        let info = mock_info(owner.as_ref(), &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, send_or_transfer("account")).unwrap();
+        assert!(res.messages.is_empty());
+        assert!(res.attributes.contains(&attr("action", "transfer")));
+        assert!(res.attributes.contains(&attr("path", "transfer")));
+
+        assert_eq!(get_balance(deps.as_ref(), owner), Uint128::new(3000));
+        assert_eq!(get_balance(deps.as_ref(), "contract"), Uint128::new(1000));
+        assert_eq!(get_balance(deps.as_ref(), "account"), Uint128::new(1000));
+    }
+
+    #[test]
+    fn send_acked_requires_acceptance() {
+        let mut deps = mock_dependencies();
+        let owner = String::from("addr0001");
+        do_instantiate(deps.as_mut(), &owner, Uint128::new(5000));
+
+        let send_msg = Binary::from(r#"{"some":123}"#.as_bytes());
+        let msg = ExecuteMsg::SendAcked {
+            contract: "contract".to_string(),
+            amount: Uint128::new(1000),
+            msg: send_msg.clone(),
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info(&owner, &[]), msg).unwrap();
+        let receive = Cw20ReceiveMsg {
+            sender: owner,
+            amount: Uint128::new(1000),
+            msg: send_msg,
+        }
+        .into_cosmos_msg("contract")
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::reply_on_success(receive, RECEIVE_ACK_REPLY_ID)]
+        );
+        assert!(res.attributes.contains(&attr("ack", "required")));
+
+        // the reply holds the response data of the receiver, wrapped in a MsgExecuteContractResponse
+        let mut reply_with = |data: Option<Binary>| {
+            let data = data.map(|data| {
+                let mut encoded = vec![0x0a, data.len() as u8];
+                encoded.extend_from_slice(data.as_slice());
+                Binary::from(encoded)
+            });
+            let msg = Reply {
+                id: RECEIVE_ACK_REPLY_ID,
+                result: SubMsgResult::Ok(SubMsgResponse {
+                    events: vec![],
+                    data,
+                }),
+            };
+            reply(deps.as_mut(), mock_env(), msg)
+        };
+
+        // the payload of an accepting ack is passed on
+        let payload = Binary::from(b"receipt".as_slice());
+        let ack = Cw20ReceiveAck::accept(Some(payload.clone()));
+        let res = reply_with(Some(ack.into_binary().unwrap())).unwrap();
+        assert_eq!(res.data, Some(payload));
+
+        // a rejecting, unknown or missing ack reverts the send
+        let ack = Cw20ReceiveAck::reject("unsupported token");
+        let err = reply_with(Some(ack.into_binary().unwrap())).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::ReceiveRejected {
+                reason: "unsupported token".to_string()
+            }
+        );
+        let err = reply_with(Some(Binary::from(b"{}".as_slice()))).unwrap_err();
+        assert_eq!(err, ContractError::MissingReceiveAck {});
+        let err = reply_with(None).unwrap_err();
+        assert_eq!(err, ContractError::MissingReceiveAck {});
+    }
+
+    #[test]
+    fn transfer_batch() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        let addr1 = String::from("addr0001");
+        let addr2 = String::from("addr0002");
+        let addr3 = String::from("addr0003");
+        let amount1 = Uint128::from(12340000u128);
+
+        do_instantiate(deps.as_mut(), &addr1, amount1);
+
+        // cannot send an empty batch
+        let info = mock_info(addr1.as_ref(), &[]);
+        let msg = ExecuteMsg::TransferBatch { recipients: vec![] };
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::EmptyBatch {});
+
+        // cannot transfer nothing to anyone in the batch
+        let info = mock_info(addr1.as_ref(), &[]);
+        let msg = ExecuteMsg::TransferBatch {
+            recipients: vec![
+                (addr2.clone(), Uint128::new(100)),
+                (addr3.clone(), Uint128::zero()),
+            ],
+        };
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidZeroAmount {});
+
+        // cannot transfer more than we have in total
+        let info = mock_info(addr1.as_ref(), &[]);
+        let msg = ExecuteMsg::TransferBatch {
+            recipients: vec![(addr2.clone(), amount1), (addr3.clone(), Uint128::new(1))],
+        };
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::Std(StdError::Overflow { .. })));
+        assert_eq!(get_balance(deps.as_ref(), &addr2), Uint128::zero());
+
+        // valid batch credits everyone, including repeated recipients
+        let info = mock_info(addr1.as_ref(), &[]);
+        let msg = ExecuteMsg::TransferBatch {
+            recipients: vec![
+                (addr2.clone(), Uint128::new(100)),
+                (addr3.clone(), Uint128::new(200)),
+                (addr2.clone(), Uint128::new(300)),
+            ],
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(res.messages.len(), 0);
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "transfer_batch"),
+                attr("from", &addr1),
+                attr("recipients", "3"),
+                attr("amount", "600"),
+            ]
+        );
+
+        assert_eq!(
+            get_balance(deps.as_ref(), addr1),
+            amount1.checked_sub(Uint128::new(600)).unwrap()
+        );
+        assert_eq!(get_balance(deps.as_ref(), addr2), Uint128::new(400));
+        assert_eq!(get_balance(deps.as_ref(), addr3), Uint128::new(200));
+        assert_eq!(
+            query_token_info(deps.as_ref()).unwrap().total_supply,
+            amount1
+        );
+    }
+
+    #[test]
+    fn send_batch() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        let addr1 = String::from("addr0001");
+        let contract1 = String::from("contract1");
+        let contract2 = String::from("contract2");
+        let amount1 = Uint128::from(12340000u128);
+        let msg1 = Binary::from(r#"{"some":123}"#.as_bytes());
+        let msg2 = Binary::from(r#"{"other":456}"#.as_bytes());
+
+        do_instantiate(deps.as_mut(), &addr1, amount1);
+
+        let info = mock_info(addr1.as_ref(), &[]);
+        let msg = ExecuteMsg::SendBatch {
+            contracts: vec![
+                (contract1.clone(), Uint128::new(100), msg1.clone()),
+                (contract2.clone(), Uint128::new(200), msg2.clone()),
+            ],
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // every contract is notified with its own message
+        let expected: Vec<_> = [(&contract1, 100u128, msg1), (&contract2, 200u128, msg2)]
+            .into_iter()
+            .map(|(contract, amount, msg)| {
+                let receive = Cw20ReceiveMsg {
+                    sender: addr1.clone(),
+                    amount: Uint128::new(amount),
+                    msg,
+                };
+                SubMsg::new(receive.into_cosmos_msg(contract).unwrap())
+            })
+            .collect();
+        assert_eq!(res.messages, expected);
+
+        assert_eq!(
+            get_balance(deps.as_ref(), addr1),
+            amount1.checked_sub(Uint128::new(300)).unwrap()
+        );
+        assert_eq!(get_balance(deps.as_ref(), contract1), Uint128::new(100));
+        assert_eq!(get_balance(deps.as_ref(), contract2), Uint128::new(200));
+    }
+
+    #[test]
+    fn mint_callback() {
+        let mut deps = mock_dependencies();
+        let minter = String::from("controller");
+        let amount = Uint128::new(1000);
+        do_instantiate_with_minter(deps.as_mut(), "addr0001", amount, &minter, None);
+        let enable = ExecuteMsg::UpdateMintCallback { enabled: true };
+        let mint = ExecuteMsg::Mint {
+            recipient: "addr0002".to_string(),
+            amount,
+        };
+
+        // only a minter that is a contract can request callbacks
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("addr0001", &[]),
+            enable.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(&minter, &[]),
+            enable.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::MintCallbackNotContract {});
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::ContractInfo { .. } => SystemResult::Ok(ContractResult::Ok(
+                to_binary(&ContractInfoResponse::new(1, "creator")).unwrap(),
+            )),
+            _ => SystemResult::Err(SystemError::Unknown {}),
+        });
+        execute(deps.as_mut(), mock_env(), mock_info(&minter, &[]), enable).unwrap();
+        let res: MintCallbackResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::MintCallback {}).unwrap())
+                .unwrap();
+        assert!(res.enabled);
+
+        // every mint calls the minter back
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(&minter, &[]),
+            mint.clone(),
+        )
+        .unwrap();
+        let callback = Cw20MintCallbackMsg {
+            recipient: "addr0002".to_string(),
+            amount,
+        };
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(callback.into_cosmos_msg(&minter).unwrap())]
+        );
+
+        // until it is disabled
+        let msg = ExecuteMsg::UpdateMintCallback { enabled: false };
+        execute(deps.as_mut(), mock_env(), mock_info(&minter, &[]), msg).unwrap();
+        let res = execute(deps.as_mut(), mock_env(), mock_info(&minter, &[]), mint).unwrap();
+        assert!(res.messages.is_empty());
+        let res: MintCallbackResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::MintCallback {}).unwrap())
+                .unwrap();
+        assert!(!res.enabled);
+    }
+
+    #[test]
+    fn transfer_hooks() {
+        let mut deps = mock_dependencies();
+        let addr1 = String::from("addr0001");
+        let addr2 = String::from("addr0002");
+        let minter = String::from("minter");
+        let hook = String::from("hook");
+        let amount1 = Uint128::from(12340000u128);
+        let transfer = Uint128::from(76543u128);
+
+        do_instantiate_with_minter(deps.as_mut(), &addr1, amount1, &minter, None);
+
+        // only the minter can register hooks
+        let msg = ExecuteMsg::AddHook { addr: hook.clone() };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(&addr1, &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(&minter, &[]),
+            msg.clone(),
+        )
+        .unwrap();
+
+        // cannot register the same hook twice
+        let err = execute(deps.as_mut(), mock_env(), mock_info(&minter, &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::Hook(_)));
+
+        let hooks: cw_controllers::HooksResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Hooks {}).unwrap()).unwrap();
+        assert_eq!(hooks.hooks, vec![hook.clone()]);
+
+        // transfers notify the hook
+        let msg = ExecuteMsg::Transfer {
+            recipient: addr2.clone(),
+            amount: transfer,
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info(&addr1, &[]), msg).unwrap();
+        let expected = Cw20TransferHookMsg {
+            sender: addr1.clone(),
+            recipient: Some(addr2.clone()),
+            amount: transfer,
+        };
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::reply_on_error(
+                expected.into_cosmos_msg(&hook).unwrap(),
+                TRANSFER_HOOK_REPLY_ID
+            )]
+        );
+
+        // burns notify the hook without a recipient
+        let msg = ExecuteMsg::Burn {
+            amount: transfer,
+            memo: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info(&addr2, &[]), msg).unwrap();
+        let expected = Cw20TransferHookMsg {
+            sender: addr2.clone(),
+            recipient: None,
+            amount: transfer,
+        };
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::reply_on_error(
+                expected.into_cosmos_msg(&hook).unwrap(),
+                TRANSFER_HOOK_REPLY_ID
+            )]
+        );
+
+        // a failing hook is ignored
+        let reply_msg = Reply {
+            id: TRANSFER_HOOK_REPLY_ID,
+            result: SubMsgResult::Err("hook failed".to_string()),
+        };
+        reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+
+        // removed hooks are no longer called
+        let msg = ExecuteMsg::RemoveHook { addr: hook };
+        execute(deps.as_mut(), mock_env(), mock_info(&minter, &[]), msg).unwrap();
+        let msg = ExecuteMsg::Transfer {
+            recipient: addr2,
+            amount: transfer,
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info(&addr1, &[]), msg).unwrap();
+        assert_eq!(res.messages.len(), 0);
+    }
+
+    #[test]
+    fn pause_halts_token_movements() {
+        let mut deps = mock_dependencies();
+        let owner = String::from("owner");
+        let minter = String::from("minter");
+        let pauser = String::from("pauser");
+        let instantiate_msg = InstantiateMsg {
+            name: "Pausable".to_string(),
+            symbol: "PAUSE".to_string(),
+            decimals: 6,
+            initial_balances: vec![Cw20Coin {
+                address: owner.clone(),
+                amount: Uint128::new(1000),
+            }],
+            mint: Some(MinterResponse {
+                minter: minter.clone(),
+                cap: None,
+            }),
+            marketing: None,
+            snapshots: false,
+            freezer: None,
+            pauser: Some(pauser.clone()),
+        };
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            instantiate_msg,
+        )
+        .unwrap();
+
+        // only the pauser can pause
+        let pause = ExecuteMsg::Pause {
+            until: Some(Expiration::AtHeight(mock_env().block.height + 100)),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(&owner, &[]),
+            pause.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Pause(PauseError::Admin(AdminError::NotAdmin {}))
+        );
+        execute(deps.as_mut(), mock_env(), mock_info(&pauser, &[]), pause).unwrap();
+        let paused: PausedResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Paused {}).unwrap()).unwrap();
+        assert!(paused.paused);
+
+        let paused_err = ContractError::Pause(PauseError::Paused {});
+        let transfer = ExecuteMsg::Transfer {
+            recipient: minter.clone(),
+            amount: Uint128::new(10),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(&owner, &[]),
+            transfer.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, paused_err);
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(&owner, &[]),
+            ExecuteMsg::Burn {
+                amount: Uint128::new(10),
+                memo: None,
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err, paused_err);
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(&minter, &[]),
+            ExecuteMsg::Mint {
+                recipient: owner.clone(),
+                amount: Uint128::new(10),
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err, paused_err);
+
+        // other messages and queries keep working
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(&owner, &[]),
+            ExecuteMsg::IncreaseAllowance {
+                spender: minter.clone(),
+                amount: Uint128::new(10),
+                expires: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(get_balance(deps.as_ref(), &owner), Uint128::new(1000));
+
+        // the pause expires by itself
+        let mut env = mock_env();
+        env.block.height += 100;
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(&owner, &[]),
+            transfer.clone(),
+        )
+        .unwrap();
+
+        // or is lifted by the pauser
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(&pauser, &[]),
+            ExecuteMsg::Pause { until: None },
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(&owner, &[]),
+            transfer.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, paused_err);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(&pauser, &[]),
+            ExecuteMsg::Unpause {},
+        )
+        .unwrap();
+        execute(deps.as_mut(), env, mock_info(&owner, &[]), transfer).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), &minter), Uint128::new(20));
+    }
+
     mod migration {
         use super::*;
 
@@ -1380,6 +2635,9 @@ mod tests {
                         }],
                         mint: None,
                         marketing: None,
+                        snapshots: false,
+                        freezer: None,
+                        pauser: None,
                     },
                     &[],
                     "TOKEN",
@@ -1422,7 +2680,11 @@ mod tests {
                 CosmosMsg::Wasm(WasmMsg::Migrate {
                     contract_addr: cw20_addr.to_string(),
                     new_code_id: cw20_id,
-                    msg: to_binary(&MigrateMsg {}).unwrap(),
+                    msg: to_binary(&MigrateMsg {
+                        import: None,
+                        decimals: None,
+                    })
+                    .unwrap(),
                 }),
             )
             .unwrap();
@@ -1481,6 +2743,9 @@ mod tests {
                     marketing: Some("marketing".to_owned()),
                     logo: Some(Logo::Url("url".to_owned())),
                 }),
+                snapshots: false,
+                freezer: None,
+                pauser: None,
             };
 
             let info = mock_info("creator", &[]);
@@ -1535,6 +2800,9 @@ mod tests {
                     marketing: Some("creator".to_owned()),
                     logo: Some(Logo::Url("url".to_owned())),
                 }),
+                snapshots: false,
+                freezer: None,
+                pauser: None,
             };
 
             let info = mock_info("creator", &[]);
@@ -1588,6 +2856,9 @@ mod tests {
                     marketing: Some("creator".to_owned()),
                     logo: Some(Logo::Url("url".to_owned())),
                 }),
+                snapshots: false,
+                freezer: None,
+                pauser: None,
             };
 
             let info = mock_info("creator", &[]);
@@ -1641,6 +2912,9 @@ mod tests {
                     marketing: Some("creator".to_owned()),
                     logo: Some(Logo::Url("url".to_owned())),
                 }),
+                snapshots: false,
+                freezer: None,
+                pauser: None,
             };
 
             let info = mock_info("creator", &[]);
@@ -1694,6 +2968,9 @@ mod tests {
                     marketing: Some("creator".to_owned()),
                     logo: Some(Logo::Url("url".to_owned())),
                 }),
+                snapshots: false,
+                freezer: None,
+                pauser: None,
             };
 
             let info = mock_info("creator", &[]);
@@ -1747,6 +3024,9 @@ mod tests {
                     marketing: Some("creator".to_owned()),
                     logo: Some(Logo::Url("url".to_owned())),
                 }),
+                snapshots: false,
+                freezer: None,
+                pauser: None,
             };
 
             let info = mock_info("creator", &[]);
@@ -1767,6 +3047,39 @@ mod tests {
 
             assert_eq!(res.messages, vec![]);
 
+            // the new marketing account is only proposed until it accepts
+            assert_eq!(
+                query_marketing_info(deps.as_ref()).unwrap().marketing,
+                Some(Addr::unchecked("creator"))
+            );
+            assert_eq!(
+                query_pending_marketing(deps.as_ref())
+                    .unwrap()
+                    .pending_marketing,
+                Some("marketing".to_owned())
+            );
+            let err = execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("other", &[]),
+                ExecuteMsg::AcceptMarketing {},
+            )
+            .unwrap_err();
+            assert_eq!(err, ContractError::Unauthorized {});
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("marketing", &[]),
+                ExecuteMsg::AcceptMarketing {},
+            )
+            .unwrap();
+            assert_eq!(
+                query_pending_marketing(deps.as_ref())
+                    .unwrap()
+                    .pending_marketing,
+                None
+            );
+
             assert_eq!(
                 query_marketing_info(deps.as_ref()).unwrap(),
                 MarketingInfoResponse {
@@ -1800,6 +3113,9 @@ mod tests {
                     marketing: Some("creator".to_owned()),
                     logo: Some(Logo::Url("url".to_owned())),
                 }),
+                snapshots: false,
+                freezer: None,
+                pauser: None,
             };
 
             let info = mock_info("creator", &[]);
@@ -1842,6 +3158,74 @@ mod tests {
             );
         }
 
+        #[test]
+        fn replace_marketing_proposal() {
+            let mut deps = mock_dependencies();
+            let instantiate_msg = InstantiateMsg {
+                name: "Cash Token".to_string(),
+                symbol: "CASH".to_string(),
+                decimals: 9,
+                initial_balances: vec![],
+                mint: None,
+                marketing: Some(InstantiateMarketingInfo {
+                    project: Some("Project".to_owned()),
+                    description: Some("Description".to_owned()),
+                    marketing: Some("creator".to_owned()),
+                    logo: None,
+                }),
+                snapshots: false,
+                freezer: None,
+                pauser: None,
+            };
+            let info = mock_info("creator", &[]);
+            instantiate(deps.as_mut(), mock_env(), info.clone(), instantiate_msg).unwrap();
+
+            let propose = |marketing: &str| ExecuteMsg::UpdateMarketing {
+                project: None,
+                description: None,
+                marketing: Some(marketing.to_owned()),
+            };
+            let accept = |deps: DepsMut, sender: &str| {
+                execute(
+                    deps,
+                    mock_env(),
+                    mock_info(sender, &[]),
+                    ExecuteMsg::AcceptMarketing {},
+                )
+            };
+
+            // a wrong proposal can be replaced
+            let res = execute(deps.as_mut(), mock_env(), info.clone(), propose("typo")).unwrap();
+            assert_eq!(res.attributes[1], attr("pending_marketing", "typo"));
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                info.clone(),
+                propose("marketing"),
+            )
+            .unwrap();
+            let err = accept(deps.as_mut(), "typo").unwrap_err();
+            assert_eq!(err, ContractError::Unauthorized {});
+
+            // or cancelled by proposing the current account
+            execute(deps.as_mut(), mock_env(), info.clone(), propose("creator")).unwrap();
+            let err = accept(deps.as_mut(), "marketing").unwrap_err();
+            assert_eq!(err, ContractError::Unauthorized {});
+
+            // clearing the marketing account drops the proposal too
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                info.clone(),
+                propose("marketing"),
+            )
+            .unwrap();
+            execute(deps.as_mut(), mock_env(), info, propose("")).unwrap();
+            let err = accept(deps.as_mut(), "marketing").unwrap_err();
+            assert_eq!(err, ContractError::Unauthorized {});
+            assert_eq!(query_marketing_info(deps.as_ref()).unwrap().marketing, None);
+        }
+
         #[test]
         fn clear_marketing() {
             let mut deps = mock_dependencies();
@@ -1857,6 +3241,9 @@ mod tests {
                     marketing: Some("creator".to_owned()),
                     logo: Some(Logo::Url("url".to_owned())),
                 }),
+                snapshots: false,
+                freezer: None,
+                pauser: None,
             };
 
             let info = mock_info("creator", &[]);
@@ -1910,6 +3297,9 @@ mod tests {
                     marketing: Some("creator".to_owned()),
                     logo: Some(Logo::Url("url".to_owned())),
                 }),
+                snapshots: false,
+                freezer: None,
+                pauser: None,
             };
 
             let info = mock_info("creator", &[]);
@@ -1959,6 +3349,9 @@ mod tests {
                     marketing: Some("creator".to_owned()),
                     logo: Some(Logo::Url("url".to_owned())),
                 }),
+                snapshots: false,
+                freezer: None,
+                pauser: None,
             };
 
             let info = mock_info("creator", &[]);
@@ -2009,6 +3402,9 @@ mod tests {
                     marketing: Some("creator".to_owned()),
                     logo: Some(Logo::Url("url".to_owned())),
                 }),
+                snapshots: false,
+                freezer: None,
+                pauser: None,
             };
 
             let info = mock_info("creator", &[]);
@@ -2060,6 +3456,9 @@ mod tests {
                     marketing: Some("creator".to_owned()),
                     logo: Some(Logo::Url("url".to_owned())),
                 }),
+                snapshots: false,
+                freezer: None,
+                pauser: None,
             };
 
             let info = mock_info("creator", &[]);
@@ -2110,6 +3509,9 @@ mod tests {
                     marketing: Some("creator".to_owned()),
                     logo: Some(Logo::Url("url".to_owned())),
                 }),
+                snapshots: false,
+                freezer: None,
+                pauser: None,
             };
 
             let info = mock_info("creator", &[]);
@@ -2167,6 +3569,9 @@ mod tests {
                     marketing: Some("creator".to_owned()),
                     logo: Some(Logo::Url("url".to_owned())),
                 }),
+                snapshots: false,
+                freezer: None,
+                pauser: None,
             };
 
             let info = mock_info("creator", &[]);
@@ -2217,6 +3622,9 @@ mod tests {
                     marketing: Some("creator".to_owned()),
                     logo: Some(Logo::Url("url".to_owned())),
                 }),
+                snapshots: false,
+                freezer: None,
+                pauser: None,
             };
 
             let info = mock_info("creator", &[]);
@@ -4,6 +4,8 @@ use cw20::{
     SpenderAllowanceInfo,
 };
 
+use crate::msg::{AccountBalance, AllAccountsWithBalancesResponse};
+use crate::rebase::{rebase_index, to_amount};
 use crate::state::{ALLOWANCES, ALLOWANCES_SPENDER, BALANCES};
 use cw_storage_plus::Bound;
 
@@ -78,13 +80,39 @@ pub fn query_all_accounts(
     Ok(AllAccountsResponse { accounts })
 }
 
+pub fn query_all_accounts_with_balances(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<AllAccountsWithBalancesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|s| Bound::ExclusiveRaw(s.into()));
+
+    let index = rebase_index(deps.storage)?;
+    let accounts = BALANCES
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            item.map(|(address, balance)| AccountBalance {
+                address: address.into(),
+                balance: match index {
+                    Some(index) => to_amount(balance, index),
+                    None => balance,
+                },
+            })
+        })
+        .collect::<StdResult<_>>()?;
+
+    Ok(AllAccountsWithBalancesResponse { accounts })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     use cosmwasm_std::testing::{mock_dependencies_with_balance, mock_env, mock_info};
-    use cosmwasm_std::{coins, from_binary, DepsMut, Uint128};
-    use cw20::{Cw20Coin, Expiration, TokenInfoResponse};
+    use cosmwasm_std::{coins, from_binary, to_binary, DepsMut, Uint128};
+    use cw20::{Cw20Coin, Cw20QueryMsg, Expiration, TokenInfoResponse};
 
     use crate::contract::{execute, instantiate, query, query_token_info};
     use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
@@ -101,6 +129,9 @@ mod tests {
             }],
             mint: None,
             marketing: None,
+            snapshots: false,
+            freezer: None,
+            pauser: None,
         };
         let info = mock_info("creator", &[]);
         let env = mock_env();
@@ -220,7 +251,7 @@ mod tests {
             limit: None,
         };
         let allowances: AllSpenderAllowancesResponse =
-            from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
         assert_eq!(allowances.allowances.len(), 2);
 
         // one is owner1 (order of CanonicalAddr uncorrelated with String)
@@ -230,7 +261,7 @@ mod tests {
             limit: Some(1),
         };
         let allowances: AllSpenderAllowancesResponse =
-            from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
         assert_eq!(allowances.allowances.len(), 1);
         let allow = &allowances.allowances[0];
         assert_eq!(&allow.owner, &owner1);
@@ -244,7 +275,7 @@ mod tests {
             limit: Some(10000),
         };
         let allowances: AllSpenderAllowancesResponse =
-            from_binary(&query(deps.as_ref(), env, msg).unwrap()).unwrap();
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
         assert_eq!(allowances.allowances.len(), 1);
         let allow = &allowances.allowances[0];
         assert_eq!(&allow.owner, &owner2);
@@ -252,6 +283,65 @@ mod tests {
         assert_eq!(&allow.allowance, &allow2);
     }
 
+    #[test]
+    fn spender_index_follows_allowances() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        let owner = String::from("owner");
+        let spender = String::from("spender");
+        do_instantiate(deps.as_mut(), &owner, Uint128::new(1000));
+
+        // cw20 spec messages are understood as is
+        let spec_query = |deps: Deps| -> AllSpenderAllowancesResponse {
+            let msg = to_binary(&Cw20QueryMsg::AllSpenderAllowances {
+                spender: spender.clone(),
+                start_after: None,
+                limit: None,
+            })
+            .unwrap();
+            let msg: QueryMsg = from_binary(&msg).unwrap();
+            from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap()
+        };
+
+        let owner_info = mock_info(owner.as_ref(), &[]);
+        let msg = ExecuteMsg::IncreaseAllowance {
+            spender: spender.clone(),
+            amount: Uint128::new(500),
+            expires: None,
+        };
+        execute(deps.as_mut(), mock_env(), owner_info.clone(), msg).unwrap();
+
+        // spending lowers the indexed allowance
+        let msg = ExecuteMsg::TransferFrom {
+            owner: owner.clone(),
+            recipient: spender.clone(),
+            amount: Uint128::new(200),
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(spender.as_ref(), &[]),
+            msg,
+        )
+        .unwrap();
+        assert_eq!(
+            spec_query(deps.as_ref()).allowances,
+            vec![SpenderAllowanceInfo {
+                owner: owner.clone(),
+                allowance: Uint128::new(300),
+                expires: Expiration::Never {},
+            }]
+        );
+
+        // and removing the allowance drops it from the index
+        let msg = ExecuteMsg::DecreaseAllowance {
+            spender: spender.clone(),
+            amount: Uint128::new(300),
+            expires: None,
+        };
+        execute(deps.as_mut(), mock_env(), owner_info, msg).unwrap();
+        assert_eq!(spec_query(deps.as_ref()).allowances, vec![]);
+    }
+
     #[test]
     fn query_all_accounts_works() {
         let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
@@ -315,5 +405,29 @@ mod tests {
             query_all_accounts(deps.as_ref(), Some(accounts.accounts[0].clone()), Some(777))
                 .unwrap();
         assert_eq!(accounts.accounts, expected_order[3..].to_vec());
+
+        // the same accounts come with their balances
+        let expected: Vec<_> = expected_order
+            .iter()
+            .zip([444444, 12340000 - 999999, 333333, 222222])
+            .map(|(address, balance)| AccountBalance {
+                address: address.clone(),
+                balance: Uint128::new(balance),
+            })
+            .collect();
+        let msg = QueryMsg::AllAccountsWithBalances {
+            start_after: None,
+            limit: Some(3),
+        };
+        let res: AllAccountsWithBalancesResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(res.accounts, expected[..3].to_vec());
+        let msg = QueryMsg::AllAccountsWithBalances {
+            start_after: Some(res.accounts[2].address.clone()),
+            limit: None,
+        };
+        let res: AllAccountsWithBalancesResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(res.accounts, expected[3..].to_vec());
     }
 }
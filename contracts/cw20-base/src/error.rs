@@ -1,4 +1,5 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{StdError, Uint128};
+use cw_controllers::{AdminError, HookError, PauseError, PermitError};
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
@@ -6,6 +7,18 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("{0}")]
+    Hook(#[from] HookError),
+
+    #[error("{0}")]
+    Admin(#[from] AdminError),
+
+    #[error("{0}")]
+    Pause(#[from] PauseError),
+
+    #[error("{0}")]
+    Permit(#[from] PermitError),
+
     #[error("Unauthorized")]
     Unauthorized {},
 
@@ -24,6 +37,95 @@ pub enum ContractError {
     #[error("Minting cannot exceed the cap")]
     CannotExceedCap {},
 
+    #[error("Transfer fee must be below 10000 bps")]
+    InvalidTransferFee {},
+
+    #[error("Burn tax cannot exceed 10000 bps")]
+    InvalidBurnTax {},
+
+    #[error("Wallet limits cannot be zero")]
+    InvalidWalletLimits {},
+
+    #[error("Account {address} cannot hold more than {max_balance}")]
+    MaxBalanceExceeded {
+        address: String,
+        max_balance: Uint128,
+    },
+
+    #[error("Transfers cannot move more than {max_transfer}")]
+    MaxTransferExceeded { max_transfer: Uint128 },
+
+    #[error("Account {address} is frozen")]
+    AccountFrozen { address: String },
+
+    #[error("Address is not a minter")]
+    NotMinter {},
+
+    #[error(
+        "Minting exceeds the rate limit, only {remaining} can be minted in the current window"
+    )]
+    MintRateLimitExceeded { remaining: Uint128 },
+
+    #[error("Mint rate limit window cannot be empty")]
+    InvalidMintWindow {},
+
+    #[error("Spending exceeds the allowance period limit, only {remaining} can be spent in the current period")]
+    AllowancePeriodExceeded { remaining: Uint128 },
+
+    #[error("Allowance period cannot be empty")]
+    InvalidAllowancePeriod {},
+
+    #[error("No legacy import in progress")]
+    NoImportInProgress {},
+
+    #[error("Import batch cannot contain more than {max} entries")]
+    ImportBatchTooLarge { max: usize },
+
+    #[error("Imported total supply {actual} does not match the expected {expected}")]
+    ImportChecksumMismatch { expected: Uint128, actual: Uint128 },
+
+    #[error("Token is halted until its balances are rescaled to the new decimals")]
+    RescaleInProgress {},
+
+    #[error("No rescale in progress")]
+    NoRescaleInProgress {},
+
+    #[error("No holders backfill in progress")]
+    NoHoldersBackfillInProgress {},
+
+    #[error("Decimals must change and be at most 18")]
+    InvalidRescale {},
+
+    #[error("Cannot recover the tokens managed by this contract")]
+    CannotRecoverOwnToken {},
+
+    #[error("Nothing to recover")]
+    NothingToRecover {},
+
+    #[error("Mint schedule must have a non-empty period, amount and number of periods")]
+    InvalidMintSchedule {},
+
+    #[error("No vested tokens to mint")]
+    NothingVested {},
+
+    #[error("Rebase index must be greater than zero")]
+    InvalidRebaseIndex {},
+
+    #[error("Cannot rebase the supply of a token with balance snapshots")]
+    RebaseWithSnapshots {},
+
+    #[error("Votes cannot be delegated with a rebasing supply")]
+    RebaseWithVotes {},
+
+    #[error("Pending transfer {id} not found")]
+    PendingTransferNotFound { id: u64 },
+
+    #[error("Pending transfer {id} expired and can only be reclaimed by its sender")]
+    PendingTransferExpired { id: u64 },
+
+    #[error("Pending transfer {id} can only be reclaimed once it expired")]
+    PendingTransferNotExpired { id: u64 },
+
     #[error("Logo binary data exceeds 5KB limit")]
     LogoTooBig {},
 
@@ -33,9 +135,30 @@ pub enum ContractError {
     #[error("Invalid png header")]
     InvalidPngHeader {},
 
+    #[error("Only a minter that is a contract can request mint callbacks")]
+    MintCallbackNotContract {},
+
+    #[error("Vesting lock must have a non-zero amount and end after it starts")]
+    InvalidVestingLock {},
+
+    #[error("Account {address} cannot move tokens below the {locked} still locked")]
+    TokensLocked { address: String, locked: Uint128 },
+
     #[error("Invalid expiration value")]
     InvalidExpiration {},
 
     #[error("Duplicate initial balance addresses")]
     DuplicateInitialBalanceAddresses {},
+
+    #[error("Batch must contain at least one transfer")]
+    EmptyBatch {},
+
+    #[error("Got a submessage reply with unknown id: {id}")]
+    UnknownReplyId { id: u64 },
+
+    #[error("Receiving contract did not acknowledge the tokens")]
+    MissingReceiveAck {},
+
+    #[error("Receiving contract rejected the tokens: {reason}")]
+    ReceiveRejected { reason: String },
 }
@@ -0,0 +1,403 @@
+use cosmwasm_std::{
+    Addr, Deps, DepsMut, Empty, Env, MessageInfo, Order, Response, StdResult, Storage, Uint128,
+};
+use cw20::Expiration;
+use cw_storage_plus::{Bound, Map};
+
+use crate::error::ContractError;
+use crate::fee::{collect_transfer_fee, fee_attributes, split_transfer_fee};
+use crate::freeze::ensure_not_frozen;
+use crate::hooks::transfer_hook_msgs;
+use crate::msg::{PendingTransferInfo, PendingTransfersResponse};
+use crate::snapshots::update_balance;
+use crate::state::{
+    PendingTransfer, NEXT_PENDING_TRANSFER_ID, PAUSE, PENDING_TRANSFERS,
+    PENDING_TRANSFERS_RECIPIENT, PENDING_TRANSFERS_SENDER,
+};
+use crate::vesting::ensure_unlocked;
+use crate::wallet_limits::ensure_within_wallet_limits;
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+fn load_pending(storage: &dyn Storage, id: u64) -> Result<PendingTransfer, ContractError> {
+    PENDING_TRANSFERS
+        .may_load(storage, id)?
+        .ok_or(ContractError::PendingTransferNotFound { id })
+}
+
+fn remove_pending(storage: &mut dyn Storage, id: u64, pending: &PendingTransfer) {
+    PENDING_TRANSFERS.remove(storage, id);
+    PENDING_TRANSFERS_SENDER.remove(storage, (&pending.sender, id));
+    PENDING_TRANSFERS_RECIPIENT.remove(storage, (&pending.recipient, id));
+}
+
+/// Moves `amount` from the sender into escrow, held on the balance of this contract,
+/// until the recipient claims it or, once `expires` passed, the sender reclaims it
+pub fn execute_transfer_pending(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipient: String,
+    amount: Uint128,
+    expires: Expiration,
+) -> Result<Response, ContractError> {
+    if amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+    // the sender must be able to get the tokens back at some point
+    if matches!(expires, Expiration::Never {}) || expires.is_expired(&env.block) {
+        return Err(ContractError::InvalidExpiration {});
+    }
+    PAUSE.assert_not_paused(deps.storage, &env.block)?;
+    ensure_not_frozen(deps.storage, &info.sender)?;
+    ensure_unlocked(deps.storage, &env.block, &info.sender, amount)?;
+    let rcpt_addr = deps.api.addr_validate(&recipient)?;
+
+    update_balance(
+        deps.storage,
+        env.block.height,
+        &info.sender,
+        |balance: Option<Uint128>| -> StdResult<_> {
+            Ok(balance.unwrap_or_default().checked_sub(amount)?)
+        },
+    )?;
+    update_balance(
+        deps.storage,
+        env.block.height,
+        &env.contract.address,
+        |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + amount) },
+    )?;
+
+    let id = NEXT_PENDING_TRANSFER_ID
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+    NEXT_PENDING_TRANSFER_ID.save(deps.storage, &(id + 1))?;
+    let pending = PendingTransfer {
+        sender: info.sender.clone(),
+        recipient: rcpt_addr,
+        amount,
+        expires,
+    };
+    PENDING_TRANSFERS.save(deps.storage, id, &pending)?;
+    PENDING_TRANSFERS_SENDER.save(deps.storage, (&pending.sender, id), &Empty {})?;
+    PENDING_TRANSFERS_RECIPIENT.save(deps.storage, (&pending.recipient, id), &Empty {})?;
+
+    let res = Response::new()
+        .add_attribute("action", "transfer_pending")
+        .add_attribute("id", id.to_string())
+        .add_attribute("from", info.sender)
+        .add_attribute("to", recipient)
+        .add_attribute("amount", amount)
+        .add_attribute("expires", expires.to_string());
+    Ok(res)
+}
+
+/// Completes a pending transfer to the recipient, as if it was sent directly by its sender
+pub fn execute_claim_transfer(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    let pending = load_pending(deps.storage, id)?;
+    if pending.recipient != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    if pending.expires.is_expired(&env.block) {
+        return Err(ContractError::PendingTransferExpired { id });
+    }
+    PAUSE.assert_not_paused(deps.storage, &env.block)?;
+
+    let amount = pending.amount;
+    let (net, fee) = split_transfer_fee(deps.storage, &pending.sender, &pending.recipient, amount)?;
+    ensure_within_wallet_limits(
+        deps.storage,
+        Some(&pending.sender),
+        &pending.recipient,
+        amount,
+        net,
+    )?;
+    update_balance(
+        deps.storage,
+        env.block.height,
+        &env.contract.address,
+        |balance: Option<Uint128>| -> StdResult<_> {
+            Ok(balance.unwrap_or_default().checked_sub(amount)?)
+        },
+    )?;
+    update_balance(
+        deps.storage,
+        env.block.height,
+        &pending.recipient,
+        |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + net) },
+    )?;
+    remove_pending(deps.storage, id, &pending);
+
+    let mut hooks =
+        transfer_hook_msgs(deps.storage, &pending.sender, Some(&pending.recipient), net)?;
+    hooks.extend(collect_transfer_fee(
+        deps.storage,
+        env.block.height,
+        &pending.sender,
+        fee.as_ref(),
+    )?);
+    let res = Response::new()
+        .add_submessages(hooks)
+        .add_attribute("action", "claim_transfer")
+        .add_attribute("id", id.to_string())
+        .add_attribute("from", pending.sender)
+        .add_attribute("to", pending.recipient)
+        .add_attribute("amount", amount)
+        .add_attributes(fee_attributes(net, fee.as_ref()));
+    Ok(res)
+}
+
+/// Returns the tokens of an expired pending transfer to its sender
+pub fn execute_reclaim_transfer(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    let pending = load_pending(deps.storage, id)?;
+    if pending.sender != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    if !pending.expires.is_expired(&env.block) {
+        return Err(ContractError::PendingTransferNotExpired { id });
+    }
+
+    let amount = pending.amount;
+    update_balance(
+        deps.storage,
+        env.block.height,
+        &env.contract.address,
+        |balance: Option<Uint128>| -> StdResult<_> {
+            Ok(balance.unwrap_or_default().checked_sub(amount)?)
+        },
+    )?;
+    update_balance(
+        deps.storage,
+        env.block.height,
+        &pending.sender,
+        |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + amount) },
+    )?;
+    remove_pending(deps.storage, id, &pending);
+
+    let res = Response::new()
+        .add_attribute("action", "reclaim_transfer")
+        .add_attribute("id", id.to_string())
+        .add_attribute("to", pending.sender)
+        .add_attribute("amount", amount);
+    Ok(res)
+}
+
+/// Lists the pending transfers found in the given index, by ascending id
+fn query_pending_transfers(
+    deps: Deps,
+    index: Map<(&Addr, u64), Empty>,
+    address: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<PendingTransfersResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let transfers = index
+        .prefix(&address)
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|id| {
+            let id = id?;
+            let pending = PENDING_TRANSFERS.load(deps.storage, id)?;
+            Ok(PendingTransferInfo {
+                id,
+                sender: pending.sender.into(),
+                recipient: pending.recipient.into(),
+                amount: pending.amount,
+                expires: pending.expires,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(PendingTransfersResponse { transfers })
+}
+
+pub fn query_pending_transfers_by_sender(
+    deps: Deps,
+    sender: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<PendingTransfersResponse> {
+    query_pending_transfers(deps, PENDING_TRANSFERS_SENDER, sender, start_after, limit)
+}
+
+pub fn query_pending_transfers_by_recipient(
+    deps: Deps,
+    recipient: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<PendingTransfersResponse> {
+    query_pending_transfers(
+        deps,
+        PENDING_TRANSFERS_RECIPIENT,
+        recipient,
+        start_after,
+        limit,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{from_binary, Env};
+    use cw20::Cw20Coin;
+
+    use crate::contract::{execute, instantiate, query, query_balance, query_token_info};
+    use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+
+    fn balance(deps: Deps, address: &str) -> u128 {
+        query_balance(deps, address.to_string())
+            .unwrap()
+            .balance
+            .u128()
+    }
+
+    fn env_at(height: u64) -> Env {
+        let mut env = mock_env();
+        env.block.height = height;
+        env
+    }
+
+    fn by_sender(deps: Deps, sender: &str) -> Vec<PendingTransferInfo> {
+        let msg = QueryMsg::PendingTransfersBySender {
+            sender: sender.to_string(),
+            start_after: None,
+            limit: None,
+        };
+        let res: PendingTransfersResponse =
+            from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+        res.transfers
+    }
+
+    fn by_recipient(deps: Deps, recipient: &str) -> Vec<PendingTransferInfo> {
+        let msg = QueryMsg::PendingTransfersByRecipient {
+            recipient: recipient.to_string(),
+            start_after: None,
+            limit: None,
+        };
+        let res: PendingTransfersResponse =
+            from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+        res.transfers
+    }
+
+    #[test]
+    fn pending_transfers_claimed_or_reclaimed() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            name: "Treasury".to_string(),
+            symbol: "TRSY".to_string(),
+            decimals: 6,
+            initial_balances: vec![Cw20Coin {
+                address: "alice".to_string(),
+                amount: Uint128::new(1000),
+            }],
+            mint: None,
+            marketing: None,
+            snapshots: false,
+            freezer: None,
+            pauser: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let height = mock_env().block.height;
+        let contract = mock_env().contract.address;
+
+        let pending =
+            |recipient: &str, amount: u128, expires: Expiration| ExecuteMsg::TransferPending {
+                recipient: recipient.to_string(),
+                amount: Uint128::new(amount),
+                expires,
+            };
+        let alice = mock_info("alice", &[]);
+
+        // the sender must be able to reclaim at some point
+        for expires in [Expiration::Never {}, Expiration::AtHeight(height)] {
+            let msg = pending("bob", 100, expires);
+            let err = execute(deps.as_mut(), mock_env(), alice.clone(), msg).unwrap_err();
+            assert_eq!(err, ContractError::InvalidExpiration {});
+        }
+
+        // tokens are held in escrow by the contract
+        let expires = Expiration::AtHeight(height + 100);
+        let msg = pending("bob", 300, expires);
+        let res = execute(deps.as_mut(), mock_env(), alice.clone(), msg).unwrap();
+        assert_eq!(res.attributes[1], ("id", "0"));
+        let msg = pending("typo", 200, expires);
+        execute(deps.as_mut(), mock_env(), alice.clone(), msg).unwrap();
+        assert_eq!(balance(deps.as_ref(), "alice"), 500);
+        assert_eq!(balance(deps.as_ref(), contract.as_str()), 500);
+        assert_eq!(
+            query_token_info(deps.as_ref()).unwrap().total_supply,
+            Uint128::new(1000)
+        );
+        assert_eq!(
+            by_sender(deps.as_ref(), "alice"),
+            vec![
+                PendingTransferInfo {
+                    id: 0,
+                    sender: "alice".to_string(),
+                    recipient: "bob".to_string(),
+                    amount: Uint128::new(300),
+                    expires,
+                },
+                PendingTransferInfo {
+                    id: 1,
+                    sender: "alice".to_string(),
+                    recipient: "typo".to_string(),
+                    amount: Uint128::new(200),
+                    expires,
+                }
+            ]
+        );
+        assert_eq!(by_recipient(deps.as_ref(), "bob").len(), 1);
+
+        // only the recipient claims, before expiry
+        let claim = |id| ExecuteMsg::ClaimTransfer { id };
+        let err = execute(deps.as_mut(), mock_env(), alice.clone(), claim(0)).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), claim(0)).unwrap();
+        assert_eq!(balance(deps.as_ref(), "bob"), 300);
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), claim(0)).unwrap_err();
+        assert_eq!(err, ContractError::PendingTransferNotFound { id: 0 });
+        let err = execute(
+            deps.as_mut(),
+            env_at(height + 100),
+            mock_info("typo", &[]),
+            claim(1),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::PendingTransferExpired { id: 1 });
+
+        // only the sender reclaims, after expiry
+        let reclaim = ExecuteMsg::ReclaimTransfer { id: 1 };
+        let err = execute(deps.as_mut(), mock_env(), alice.clone(), reclaim.clone()).unwrap_err();
+        assert_eq!(err, ContractError::PendingTransferNotExpired { id: 1 });
+        let err = execute(
+            deps.as_mut(),
+            env_at(height + 100),
+            mock_info("typo", &[]),
+            reclaim.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), env_at(height + 100), alice, reclaim).unwrap();
+        assert_eq!(balance(deps.as_ref(), "alice"), 700);
+        assert_eq!(balance(deps.as_ref(), contract.as_str()), 0);
+        assert_eq!(by_sender(deps.as_ref(), "alice"), vec![]);
+        assert_eq!(by_recipient(deps.as_ref(), "typo"), vec![]);
+    }
+}
@@ -0,0 +1,616 @@
+use cosmwasm_std::{
+    attr, Addr, Attribute, Deps, DepsMut, Empty, MessageInfo, Order, Response, StdError, StdResult,
+    Storage, SubMsg, Uint128,
+};
+use cw_storage_plus::Bound;
+
+use crate::error::ContractError;
+use crate::hooks::{assert_minter, transfer_hook_msgs};
+use crate::msg::{
+    BurnTaxConfig, BurnTaxResponse, FeeExemptResponse, TransferFeeConfig, TransferFeeResponse,
+};
+use crate::snapshots::update_balance;
+use crate::state::{BurnTax, TransferFee, BURN_TAX, FEE_EXEMPT, TRANSFER_FEE};
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+/// Fees are given in basis points of the amount transferred
+const BPS_DENOMINATOR: u128 = 10_000;
+
+/// Splits a transfer of `amount` from `from` to `to` into the amount `to` receives and the fee
+/// taken from it, along with who gets the fee. No fee is taken if none is configured,
+/// if either side is exempt or if it rounds down to zero.
+pub fn split_transfer_fee(
+    storage: &dyn Storage,
+    from: &Addr,
+    to: &Addr,
+    amount: Uint128,
+) -> StdResult<(Uint128, Option<(Addr, Uint128)>)> {
+    let config = match TRANSFER_FEE.may_load(storage)? {
+        Some(config) => config,
+        None => return Ok((amount, None)),
+    };
+    if FEE_EXEMPT.has(storage, from) || FEE_EXEMPT.has(storage, to) {
+        return Ok((amount, None));
+    }
+
+    let fee = amount.multiply_ratio(config.bps, BPS_DENOMINATOR);
+    if fee.is_zero() {
+        return Ok((amount, None));
+    }
+    Ok((amount - fee, Some((config.recipient, fee))))
+}
+
+/// Splits a burn of `amount` from `from` into the amount actually destroyed and the tax redirected
+/// to the treasury. No tax is taken if none is configured, if `from` is exempt or if it rounds
+/// down to zero.
+pub fn split_burn_tax(
+    storage: &dyn Storage,
+    from: &Addr,
+    amount: Uint128,
+) -> StdResult<(Uint128, Option<(Addr, Uint128)>)> {
+    let config = match BURN_TAX.may_load(storage)? {
+        Some(config) => config,
+        None => return Ok((amount, None)),
+    };
+    if FEE_EXEMPT.has(storage, from) {
+        return Ok((amount, None));
+    }
+
+    let tax = amount.multiply_ratio(config.bps, BPS_DENOMINATOR);
+    if tax.is_zero() {
+        return Ok((amount, None));
+    }
+    Ok((amount - tax, Some((config.treasury, tax))))
+}
+
+/// Splits the fees off every credit of a batch transfer, adding a single credit of their total
+/// to the fee recipient. Returns the new credits and the total fee.
+pub fn split_batch_transfer_fees(
+    storage: &dyn Storage,
+    from: &Addr,
+    credits: Vec<(Addr, Uint128)>,
+) -> StdResult<(Vec<(Addr, Uint128)>, Uint128)> {
+    let mut fee_credit: Option<(Addr, Uint128)> = None;
+    let mut net_credits = Vec::with_capacity(credits.len() + 1);
+    for (rcpt_addr, amount) in credits {
+        let (net, fee) = split_transfer_fee(storage, from, &rcpt_addr, amount)?;
+        if let Some((fee_recipient, fee)) = fee {
+            let total = fee_credit.get_or_insert((fee_recipient, Uint128::zero()));
+            total.1 = total.1.checked_add(fee).map_err(StdError::overflow)?;
+        }
+        net_credits.push((rcpt_addr, net));
+    }
+
+    let total_fee = fee_credit.as_ref().map(|(_, fee)| *fee).unwrap_or_default();
+    net_credits.extend(fee_credit);
+    Ok((net_credits, total_fee))
+}
+
+/// Credits the fee of a transfer from `from` to its recipient, returning the transfer hook
+/// messages for this movement
+pub fn collect_transfer_fee(
+    storage: &mut dyn Storage,
+    height: u64,
+    from: &Addr,
+    fee: Option<&(Addr, Uint128)>,
+) -> StdResult<Vec<SubMsg>> {
+    match fee {
+        Some((fee_recipient, fee)) => {
+            update_balance(
+                storage,
+                height,
+                fee_recipient,
+                |balance: Option<Uint128>| -> StdResult<_> {
+                    Ok(balance.unwrap_or_default() + fee)
+                },
+            )?;
+            transfer_hook_msgs(storage, from, Some(fee_recipient), *fee)
+        }
+        None => Ok(vec![]),
+    }
+}
+
+/// Attributes reporting the net amount received and the fee, only if a fee was taken
+pub fn fee_attributes(net: Uint128, fee: Option<&(Addr, Uint128)>) -> Vec<Attribute> {
+    match fee {
+        Some((_, fee)) => vec![attr("net_amount", net), attr("fee", *fee)],
+        None => vec![],
+    }
+}
+
+/// Sets or removes (with `None`) the transfer fee. Must be called by the minter
+pub fn execute_update_transfer_fee(
+    deps: DepsMut,
+    info: MessageInfo,
+    fee: Option<TransferFeeConfig>,
+) -> Result<Response, ContractError> {
+    assert_minter(deps.storage, &info.sender)?;
+
+    let res = Response::new().add_attribute("action", "update_transfer_fee");
+    let res = match fee {
+        Some(fee) => {
+            if u128::from(fee.bps) >= BPS_DENOMINATOR {
+                return Err(ContractError::InvalidTransferFee {});
+            }
+            let recipient = deps.api.addr_validate(&fee.recipient)?;
+            TRANSFER_FEE.save(
+                deps.storage,
+                &TransferFee {
+                    bps: fee.bps,
+                    recipient: recipient.clone(),
+                },
+            )?;
+            res.add_attribute("bps", fee.bps.to_string())
+                .add_attribute("recipient", recipient)
+        }
+        None => {
+            TRANSFER_FEE.remove(deps.storage);
+            res.add_attribute("bps", "None")
+        }
+    };
+    Ok(res)
+}
+
+/// Sets or removes (with `None`) the burn tax. Must be called by the minter
+pub fn execute_update_burn_tax(
+    deps: DepsMut,
+    info: MessageInfo,
+    tax: Option<BurnTaxConfig>,
+) -> Result<Response, ContractError> {
+    assert_minter(deps.storage, &info.sender)?;
+
+    let res = Response::new().add_attribute("action", "update_burn_tax");
+    let res = match tax {
+        Some(tax) => {
+            if u128::from(tax.bps) > BPS_DENOMINATOR {
+                return Err(ContractError::InvalidBurnTax {});
+            }
+            let treasury = deps.api.addr_validate(&tax.treasury)?;
+            BURN_TAX.save(
+                deps.storage,
+                &BurnTax {
+                    bps: tax.bps,
+                    treasury: treasury.clone(),
+                },
+            )?;
+            res.add_attribute("bps", tax.bps.to_string())
+                .add_attribute("treasury", treasury)
+        }
+        None => {
+            BURN_TAX.remove(deps.storage);
+            res.add_attribute("bps", "None")
+        }
+    };
+    Ok(res)
+}
+
+/// Exempts transfers from or to `address` from the fee, and its burns from the tax, or removes
+/// the exemption.
+/// Must be called by the minter
+pub fn execute_set_fee_exempt(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+    exempt: bool,
+) -> Result<Response, ContractError> {
+    assert_minter(deps.storage, &info.sender)?;
+    let addr = deps.api.addr_validate(&address)?;
+    if exempt {
+        FEE_EXEMPT.save(deps.storage, &addr, &Empty {})?;
+    } else {
+        FEE_EXEMPT.remove(deps.storage, &addr);
+    }
+
+    let res = Response::new()
+        .add_attribute("action", "set_fee_exempt")
+        .add_attribute("address", addr)
+        .add_attribute("exempt", exempt.to_string());
+    Ok(res)
+}
+
+pub fn query_transfer_fee(deps: Deps) -> StdResult<Option<TransferFeeResponse>> {
+    let fee = TRANSFER_FEE
+        .may_load(deps.storage)?
+        .map(|fee| TransferFeeResponse {
+            bps: fee.bps,
+            recipient: fee.recipient.into(),
+        });
+    Ok(fee)
+}
+
+pub fn query_burn_tax(deps: Deps) -> StdResult<Option<BurnTaxResponse>> {
+    let tax = BURN_TAX.may_load(deps.storage)?.map(|tax| BurnTaxResponse {
+        bps: tax.bps,
+        treasury: tax.treasury.into(),
+    });
+    Ok(tax)
+}
+
+pub fn query_fee_exempt(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<FeeExemptResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|s| Bound::ExclusiveRaw(s.into()));
+
+    let addresses = FEE_EXEMPT
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(Into::into))
+        .collect::<StdResult<_>>()?;
+    Ok(FeeExemptResponse { addresses })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{attr, from_binary, Binary};
+    use cw20::{Cw20Coin, Cw20ReceiveMsg, MinterResponse};
+
+    use crate::contract::{execute, instantiate, query, query_balance, query_token_info};
+    use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+
+    const MINTER: &str = "minter";
+    const TREASURY: &str = "treasury";
+
+    fn setup(deps: DepsMut) {
+        let msg = InstantiateMsg {
+            name: "Taxed".to_string(),
+            symbol: "TAX".to_string(),
+            decimals: 6,
+            initial_balances: vec![Cw20Coin {
+                address: "alice".to_string(),
+                amount: Uint128::new(100_000),
+            }],
+            mint: Some(MinterResponse {
+                minter: MINTER.to_string(),
+                cap: None,
+            }),
+            marketing: None,
+            snapshots: false,
+            freezer: None,
+            pauser: None,
+        };
+        instantiate(deps, mock_env(), mock_info("creator", &[]), msg).unwrap();
+    }
+
+    fn set_fee(deps: DepsMut, sender: &str, bps: u16) -> Result<Response, ContractError> {
+        let msg = ExecuteMsg::UpdateTransferFee {
+            fee: Some(TransferFeeConfig {
+                bps,
+                recipient: TREASURY.to_string(),
+            }),
+        };
+        execute(deps, mock_env(), mock_info(sender, &[]), msg)
+    }
+
+    fn balance(deps: Deps, address: &str) -> u128 {
+        query_balance(deps, address.to_string())
+            .unwrap()
+            .balance
+            .u128()
+    }
+
+    #[test]
+    fn transfers_pay_fee() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+
+        // only the minter configures the fee, which must be below 100%
+        let err = set_fee(deps.as_mut(), "alice", 250).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let err = set_fee(deps.as_mut(), MINTER, 10_000).unwrap_err();
+        assert_eq!(err, ContractError::InvalidTransferFee {});
+        set_fee(deps.as_mut(), MINTER, 250).unwrap();
+        let fee: Option<TransferFeeResponse> =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::TransferFee {}).unwrap())
+                .unwrap();
+        assert_eq!(
+            fee,
+            Some(TransferFeeResponse {
+                bps: 250,
+                recipient: TREASURY.to_string(),
+            })
+        );
+
+        // 2.5% of a transfer goes to the treasury
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[]),
+            ExecuteMsg::Transfer {
+                recipient: "bob".to_string(),
+                amount: Uint128::new(10_000),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            res.attributes[3..],
+            [
+                attr("amount", "10000"),
+                attr("net_amount", "9750"),
+                attr("fee", "250"),
+            ]
+        );
+        assert_eq!(balance(deps.as_ref(), "alice"), 90_000);
+        assert_eq!(balance(deps.as_ref(), "bob"), 9_750);
+        assert_eq!(balance(deps.as_ref(), TREASURY), 250);
+
+        // contracts are notified of the net amount they received
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[]),
+            ExecuteMsg::Send {
+                contract: "vault".to_string(),
+                amount: Uint128::new(1_000),
+                msg: Binary::from(b"deposit"),
+            },
+        )
+        .unwrap();
+        let receive = Cw20ReceiveMsg {
+            sender: "alice".to_string(),
+            amount: Uint128::new(975),
+            msg: Binary::from(b"deposit"),
+        };
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(receive.into_cosmos_msg("vault").unwrap())]
+        );
+        assert_eq!(balance(deps.as_ref(), TREASURY), 275);
+
+        // batches pay the fee on every transfer, and amounts too small are not charged
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[]),
+            ExecuteMsg::TransferBatch {
+                recipients: vec![
+                    ("bob".to_string(), Uint128::new(2_000)),
+                    ("carl".to_string(), Uint128::new(39)),
+                ],
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            res.attributes[3..],
+            [
+                attr("amount", "2039"),
+                attr("net_amount", "1989"),
+                attr("fee", "50"),
+            ]
+        );
+        assert_eq!(balance(deps.as_ref(), "bob"), 11_700);
+        assert_eq!(balance(deps.as_ref(), "carl"), 39);
+        assert_eq!(balance(deps.as_ref(), TREASURY), 325);
+
+        // spending an allowance pays the fee as well
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[]),
+            ExecuteMsg::IncreaseAllowance {
+                spender: "carl".to_string(),
+                amount: Uint128::new(4_000),
+                expires: None,
+            },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("carl", &[]),
+            ExecuteMsg::TransferFrom {
+                owner: "alice".to_string(),
+                recipient: "carl".to_string(),
+                amount: Uint128::new(4_000),
+            },
+        )
+        .unwrap();
+        assert_eq!(balance(deps.as_ref(), "carl"), 3_939);
+        assert_eq!(balance(deps.as_ref(), TREASURY), 425);
+
+        // the total supply is untouched
+        let total: u128 = ["alice", "bob", "carl", "vault", TREASURY]
+            .iter()
+            .map(|addr| balance(deps.as_ref(), addr))
+            .sum();
+        assert_eq!(total, 100_000);
+    }
+
+    #[test]
+    fn exempt_addresses_pay_no_fee() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        set_fee(deps.as_mut(), MINTER, 100).unwrap();
+
+        let exempt = |address: &str, exempt: bool| ExecuteMsg::SetFeeExempt {
+            address: address.to_string(),
+            exempt,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[]),
+            exempt("alice", true),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MINTER, &[]),
+            exempt("exchange", true),
+        )
+        .unwrap();
+        let res: FeeExemptResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::FeeExempt {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.addresses, vec!["exchange"]);
+
+        // transfers to or from an exempt address are not charged
+        let transfer = |recipient: &str, amount: u128| ExecuteMsg::Transfer {
+            recipient: recipient.to_string(),
+            amount: Uint128::new(amount),
+        };
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[]),
+            transfer("exchange", 10_000),
+        )
+        .unwrap();
+        assert_eq!(res.attributes.len(), 4);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("exchange", &[]),
+            transfer("bob", 5_000),
+        )
+        .unwrap();
+        assert_eq!(balance(deps.as_ref(), "bob"), 5_000);
+        assert_eq!(balance(deps.as_ref(), TREASURY), 0);
+
+        // until the exemption or the fee is removed
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MINTER, &[]),
+            exempt("exchange", false),
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("exchange", &[]),
+            transfer("bob", 5_000),
+        )
+        .unwrap();
+        assert_eq!(balance(deps.as_ref(), "bob"), 9_950);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MINTER, &[]),
+            ExecuteMsg::UpdateTransferFee { fee: None },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bob", &[]),
+            transfer("carl", 9_950),
+        )
+        .unwrap();
+        assert_eq!(balance(deps.as_ref(), "carl"), 9_950);
+        assert_eq!(balance(deps.as_ref(), TREASURY), 50);
+    }
+
+    #[test]
+    fn burns_pay_tax() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+
+        // only the minter configures the tax, which can be all of the burn
+        let set_tax = |bps: u16| ExecuteMsg::UpdateBurnTax {
+            tax: Some(BurnTaxConfig {
+                bps,
+                treasury: TREASURY.to_string(),
+            }),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[]),
+            set_tax(2000),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MINTER, &[]),
+            set_tax(10_001),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidBurnTax {});
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MINTER, &[]),
+            set_tax(2000),
+        )
+        .unwrap();
+        let tax: Option<BurnTaxResponse> =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::BurnTax {}).unwrap()).unwrap();
+        assert_eq!(
+            tax,
+            Some(BurnTaxResponse {
+                bps: 2000,
+                treasury: TREASURY.to_string(),
+            })
+        );
+
+        // 20% of a burn goes to the treasury, the rest is destroyed
+        let burn = ExecuteMsg::Burn {
+            amount: Uint128::new(1000),
+            memo: None,
+        };
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[]),
+            burn.clone(),
+        )
+        .unwrap();
+        assert!(res.attributes.contains(&attr("net_amount", "800")));
+        assert!(res.attributes.contains(&attr("fee", "200")));
+        assert_eq!(balance(deps.as_ref(), "alice"), 99_000);
+        assert_eq!(balance(deps.as_ref(), TREASURY), 200);
+        assert_eq!(
+            query_token_info(deps.as_ref()).unwrap().total_supply,
+            Uint128::new(99_200)
+        );
+
+        // as with burns from an allowance
+        let allow = ExecuteMsg::IncreaseAllowance {
+            spender: "bob".to_string(),
+            amount: Uint128::new(500),
+            expires: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), allow).unwrap();
+        let burn_from = ExecuteMsg::BurnFrom {
+            owner: "alice".to_string(),
+            amount: Uint128::new(500),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), burn_from).unwrap();
+        assert_eq!(balance(deps.as_ref(), TREASURY), 300);
+
+        // exempt accounts burn everything
+        let exempt = ExecuteMsg::SetFeeExempt {
+            address: "alice".to_string(),
+            exempt: true,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), exempt).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), burn).unwrap();
+        assert_eq!(balance(deps.as_ref(), TREASURY), 300);
+        assert_eq!(
+            query_token_info(deps.as_ref()).unwrap().total_supply,
+            Uint128::new(97_800)
+        );
+    }
+}
@@ -0,0 +1,272 @@
+use cosmwasm_std::{Addr, Deps, DepsMut, Empty, MessageInfo, Order, Response, StdResult, Storage};
+use cw_storage_plus::Bound;
+
+use crate::error::ContractError;
+use crate::msg::{FreezerResponse, FrozenAccountsResponse};
+use crate::state::{FREEZER, FROZEN};
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+/// Fails if tokens cannot be moved or approved from the given account
+pub fn ensure_not_frozen(storage: &dyn Storage, addr: &Addr) -> Result<(), ContractError> {
+    if FROZEN.has(storage, addr) {
+        return Err(ContractError::AccountFrozen {
+            address: addr.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Only the freezer can manage the frozen accounts. Without one, the extension is disabled
+fn assert_freezer(storage: &dyn Storage, sender: &Addr) -> Result<(), ContractError> {
+    let freezer = FREEZER
+        .may_load(storage)?
+        .ok_or(ContractError::Unauthorized {})?;
+    if freezer != *sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
+pub fn execute_freeze(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    assert_freezer(deps.storage, &info.sender)?;
+    let addr = deps.api.addr_validate(&address)?;
+    FROZEN.save(deps.storage, &addr, &Empty {})?;
+
+    let res = Response::new()
+        .add_attribute("action", "freeze")
+        .add_attribute("address", addr);
+    Ok(res)
+}
+
+pub fn execute_unfreeze(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    assert_freezer(deps.storage, &info.sender)?;
+    let addr = deps.api.addr_validate(&address)?;
+    FROZEN.remove(deps.storage, &addr);
+
+    let res = Response::new()
+        .add_attribute("action", "unfreeze")
+        .add_attribute("address", addr);
+    Ok(res)
+}
+
+/// Hands the freezer role over to another address
+pub fn execute_update_freezer(
+    deps: DepsMut,
+    info: MessageInfo,
+    new_freezer: String,
+) -> Result<Response, ContractError> {
+    assert_freezer(deps.storage, &info.sender)?;
+    let new_freezer = deps.api.addr_validate(&new_freezer)?;
+    FREEZER.save(deps.storage, &new_freezer)?;
+
+    let res = Response::new()
+        .add_attribute("action", "update_freezer")
+        .add_attribute("new_freezer", new_freezer);
+    Ok(res)
+}
+
+pub fn query_freezer(deps: Deps) -> StdResult<FreezerResponse> {
+    let freezer = FREEZER.may_load(deps.storage)?.map(Addr::into_string);
+    Ok(FreezerResponse { freezer })
+}
+
+pub fn query_frozen_accounts(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<FrozenAccountsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|s| Bound::ExclusiveRaw(s.into()));
+
+    let accounts = FROZEN
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(Into::into))
+        .collect::<StdResult<_>>()?;
+    Ok(FrozenAccountsResponse { accounts })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{from_binary, Uint128};
+    use cw20::Cw20Coin;
+
+    use crate::contract::{execute, instantiate, query, query_balance};
+    use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+
+    fn instantiate_with_freezer(deps: DepsMut, freezer: Option<&str>) {
+        let msg = InstantiateMsg {
+            name: "Regulated".to_string(),
+            symbol: "REG".to_string(),
+            decimals: 6,
+            initial_balances: vec![
+                Cw20Coin {
+                    address: "alice".to_string(),
+                    amount: Uint128::new(1000),
+                },
+                Cw20Coin {
+                    address: "bob".to_string(),
+                    amount: Uint128::new(1000),
+                },
+            ],
+            mint: None,
+            marketing: None,
+            snapshots: false,
+            freezer: freezer.map(String::from),
+            pauser: None,
+        };
+        instantiate(deps, mock_env(), mock_info("creator", &[]), msg).unwrap();
+    }
+
+    fn transfer(deps: DepsMut, sender: &str, recipient: &str) -> Result<Response, ContractError> {
+        let msg = ExecuteMsg::Transfer {
+            recipient: recipient.to_string(),
+            amount: Uint128::new(100),
+        };
+        execute(deps, mock_env(), mock_info(sender, &[]), msg)
+    }
+
+    fn list_frozen(deps: Deps, start_after: Option<&str>) -> Vec<String> {
+        let msg = QueryMsg::FrozenAccounts {
+            start_after: start_after.map(String::from),
+            limit: None,
+        };
+        let res: FrozenAccountsResponse =
+            from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+        res.accounts
+    }
+
+    #[test]
+    fn frozen_accounts_cannot_move_tokens() {
+        let mut deps = mock_dependencies();
+        instantiate_with_freezer(deps.as_mut(), Some("freezer"));
+
+        let freeze = |address: &str| ExecuteMsg::Freeze {
+            address: address.to_string(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[]),
+            freeze("bob"),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        // approve before freezing, to check the allowance cannot be used anymore
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[]),
+            ExecuteMsg::IncreaseAllowance {
+                spender: "bob".to_string(),
+                amount: Uint128::new(500),
+                expires: None,
+            },
+        )
+        .unwrap();
+        let freezer = mock_info("freezer", &[]);
+        execute(deps.as_mut(), mock_env(), freezer.clone(), freeze("alice")).unwrap();
+        execute(deps.as_mut(), mock_env(), freezer.clone(), freeze("carl")).unwrap();
+        assert_eq!(list_frozen(deps.as_ref(), None), vec!["alice", "carl"]);
+        assert_eq!(list_frozen(deps.as_ref(), Some("alice")), vec!["carl"]);
+
+        let frozen = ContractError::AccountFrozen {
+            address: "alice".to_string(),
+        };
+        assert_eq!(transfer(deps.as_mut(), "alice", "bob").unwrap_err(), frozen);
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bob", &[]),
+            ExecuteMsg::TransferFrom {
+                owner: "alice".to_string(),
+                recipient: "bob".to_string(),
+                amount: Uint128::new(100),
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err, frozen);
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[]),
+            ExecuteMsg::IncreaseAllowance {
+                spender: "bob".to_string(),
+                amount: Uint128::new(500),
+                expires: None,
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err, frozen);
+
+        // frozen accounts can still receive tokens
+        transfer(deps.as_mut(), "bob", "alice").unwrap();
+        assert_eq!(
+            query_balance(deps.as_ref(), "alice".to_string())
+                .unwrap()
+                .balance,
+            Uint128::new(1100)
+        );
+
+        // the role can be handed over, and unfreezing restores the account
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            freezer,
+            ExecuteMsg::UpdateFreezer {
+                new_freezer: "compliance".to_string(),
+            },
+        )
+        .unwrap();
+        let res: FreezerResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Freezer {}).unwrap()).unwrap();
+        assert_eq!(res.freezer, Some("compliance".to_string()));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("compliance", &[]),
+            ExecuteMsg::Unfreeze {
+                address: "alice".to_string(),
+            },
+        )
+        .unwrap();
+        assert_eq!(list_frozen(deps.as_ref(), None), vec!["carl"]);
+        transfer(deps.as_mut(), "alice", "bob").unwrap();
+    }
+
+    #[test]
+    fn disabled_without_freezer() {
+        let mut deps = mock_dependencies();
+        instantiate_with_freezer(deps.as_mut(), None);
+
+        let res: FreezerResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Freezer {}).unwrap()).unwrap();
+        assert_eq!(res.freezer, None);
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            ExecuteMsg::Freeze {
+                address: "alice".to_string(),
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        transfer(deps.as_mut(), "alice", "bob").unwrap();
+    }
+}
@@ -0,0 +1,420 @@
+use cosmwasm_std::{Addr, Deps, DepsMut, Empty, Order, Response, StdResult, Storage, Uint128};
+use cw_storage_plus::Bound;
+
+use crate::error::ContractError;
+use crate::msg::{AccountBalance, NumHoldersResponse, TopHoldersResponse};
+use crate::rebase::{rebase_index, to_amount};
+use crate::state::{HoldersBackfill, BALANCES, HOLDERS_BACKFILL, HOLDERS_BY_BALANCE, NUM_HOLDERS};
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+/// Maximum number of balances indexed by a single `ContinueHoldersBackfill`, to keep each
+/// message well within the gas limit
+pub const MAX_BACKFILL_BATCH: usize = 100;
+
+/// Keeps the number of holders and the holders by balance up to date when the balance of `addr`
+/// changes from `before` to `after`. Called by `update_balance`, so all balance changes are covered
+pub fn record_holder_change(
+    storage: &mut dyn Storage,
+    addr: &Addr,
+    before: Uint128,
+    after: Uint128,
+) -> StdResult<()> {
+    // accounts the backfill did not reach yet are indexed with their balance once it does
+    if let Some(backfill) = HOLDERS_BACKFILL.may_load(storage)? {
+        match backfill.start_after {
+            Some(last) if addr <= &last => {}
+            _ => return Ok(()),
+        }
+    }
+    if !before.is_zero() {
+        HOLDERS_BY_BALANCE.remove(storage, (before.u128(), addr));
+    }
+    if !after.is_zero() {
+        HOLDERS_BY_BALANCE.save(storage, (after.u128(), addr), &Empty {})?;
+    }
+
+    let num_holders = NUM_HOLDERS.may_load(storage)?.unwrap_or_default();
+    let num_holders = match (before.is_zero(), after.is_zero()) {
+        (true, false) => num_holders + 1,
+        (false, true) => num_holders.saturating_sub(1),
+        _ => return Ok(()),
+    };
+    NUM_HOLDERS.save(storage, &num_holders)
+}
+
+/// Starts counting and indexing the holders from scratch, for contracts migrated from a version
+/// without them. The balances are then indexed with `ContinueHoldersBackfill`.
+pub fn init_holders(storage: &mut dyn Storage) -> StdResult<bool> {
+    if NUM_HOLDERS.may_load(storage)?.is_some() {
+        return Ok(false);
+    }
+    NUM_HOLDERS.save(storage, &0)?;
+    HOLDERS_BACKFILL.save(storage, &HoldersBackfill { start_after: None })?;
+    Ok(true)
+}
+
+/// Indexes the holders among the next batch of balances, and ends the backfill once they are
+/// all done. Anyone can call it, until the backfill is over.
+pub fn execute_continue_holders_backfill(deps: DepsMut) -> Result<Response, ContractError> {
+    let backfill = HOLDERS_BACKFILL
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoHoldersBackfillInProgress {})?;
+
+    let start = backfill.start_after.as_ref().map(Bound::exclusive);
+    let balances = BALANCES
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(MAX_BACKFILL_BATCH)
+        .collect::<StdResult<Vec<_>>>()?;
+    let count = balances.len();
+    let mut num_holders = NUM_HOLDERS.load(deps.storage)?;
+    for (addr, balance) in balances.iter().filter(|(_, balance)| !balance.is_zero()) {
+        HOLDERS_BY_BALANCE.save(deps.storage, (balance.u128(), addr), &Empty {})?;
+        num_holders += 1;
+    }
+    NUM_HOLDERS.save(deps.storage, &num_holders)?;
+
+    let res = Response::new()
+        .add_attribute("action", "continue_holders_backfill")
+        .add_attribute("balances", count.to_string());
+    match balances.into_iter().last() {
+        Some((last, _)) if count == MAX_BACKFILL_BATCH => {
+            HOLDERS_BACKFILL.save(
+                deps.storage,
+                &HoldersBackfill {
+                    start_after: Some(last),
+                },
+            )?;
+            Ok(res.add_attribute("finished", "false"))
+        }
+        _ => {
+            HOLDERS_BACKFILL.remove(deps.storage);
+            Ok(res.add_attribute("finished", "true"))
+        }
+    }
+}
+
+/// Whether every holder is counted and indexed, that is no backfill is in progress
+fn holders_complete(storage: &dyn Storage) -> StdResult<bool> {
+    Ok(HOLDERS_BACKFILL.may_load(storage)?.is_none())
+}
+
+pub fn query_num_holders(deps: Deps) -> StdResult<NumHoldersResponse> {
+    let num_holders = NUM_HOLDERS.may_load(deps.storage)?.unwrap_or_default();
+    Ok(NumHoldersResponse {
+        num_holders,
+        complete: holders_complete(deps.storage)?,
+    })
+}
+
+/// Lists the holders by descending balance. Pass the last holder returned as `start_after`
+/// to get the next page
+pub fn query_top_holders(
+    deps: Deps,
+    start_after: Option<AccountBalance>,
+    limit: Option<u32>,
+) -> StdResult<TopHoldersResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    // holders are indexed by the shares stored, which rebased balances cannot be mapped back to
+    let index = rebase_index(deps.storage)?;
+    let end = match start_after {
+        Some(holder) => {
+            let address = deps.api.addr_validate(&holder.address)?;
+            let shares = match index {
+                Some(_) => BALANCES
+                    .may_load(deps.storage, &address)?
+                    .unwrap_or_default(),
+                None => holder.balance,
+            };
+            Some((shares.u128(), address))
+        }
+        None => None,
+    };
+    let end = end
+        .as_ref()
+        .map(|(balance, addr)| Bound::exclusive((*balance, addr)));
+
+    let holders = HOLDERS_BY_BALANCE
+        .keys(deps.storage, None, end, Order::Descending)
+        .take(limit)
+        .map(|item| {
+            item.map(|(balance, address)| AccountBalance {
+                address: address.into(),
+                balance: match index {
+                    Some(index) => to_amount(Uint128::new(balance), index),
+                    None => Uint128::new(balance),
+                },
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(TopHoldersResponse {
+        holders,
+        complete: holders_complete(deps.storage)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{from_binary, DepsMut};
+    use cw20::{Cw20Coin, MinterResponse};
+
+    use crate::contract::{execute, instantiate, migrate, query};
+    use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+
+    fn num_holders(deps: Deps) -> u64 {
+        let res: NumHoldersResponse =
+            from_binary(&query(deps, mock_env(), QueryMsg::NumHolders {}).unwrap()).unwrap();
+        assert!(res.complete);
+        res.num_holders
+    }
+
+    fn exec(deps: DepsMut, sender: &str, msg: ExecuteMsg) -> Response {
+        execute(deps, mock_env(), mock_info(sender, &[]), msg).unwrap()
+    }
+
+    /// Migrates from a version without holders, clearing them beforehand
+    fn migrate_without_holders(mut deps: DepsMut) {
+        NUM_HOLDERS.remove(deps.storage);
+        let indexed = HOLDERS_BY_BALANCE
+            .keys(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap();
+        for (balance, addr) in indexed {
+            HOLDERS_BY_BALANCE.remove(deps.storage, (balance, &addr));
+        }
+        cw2::set_contract_version(deps.storage, "crates.io:cw20-base", "0.16.0").unwrap();
+        let msg = MigrateMsg {
+            import: None,
+            decimals: None,
+        };
+        let res = migrate(deps.branch(), mock_env(), msg).unwrap();
+        assert!(res
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "holders_backfill" && attr.value == "started"));
+    }
+
+    fn continue_backfill(deps: DepsMut) -> bool {
+        let res = exec(deps, "anyone", ExecuteMsg::ContinueHoldersBackfill {});
+        let finished = res.attributes.iter().find(|attr| attr.key == "finished");
+        finished.unwrap().value == "true"
+    }
+
+    #[test]
+    fn holders_are_counted() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            name: "Community".to_string(),
+            symbol: "COMM".to_string(),
+            decimals: 6,
+            initial_balances: vec![
+                Cw20Coin {
+                    address: "alice".to_string(),
+                    amount: Uint128::new(1000),
+                },
+                Cw20Coin {
+                    address: "bob".to_string(),
+                    amount: Uint128::zero(),
+                },
+            ],
+            mint: Some(MinterResponse {
+                minter: "minter".to_string(),
+                cap: None,
+            }),
+            marketing: None,
+            snapshots: false,
+            freezer: None,
+            pauser: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        assert_eq!(num_holders(deps.as_ref()), 1);
+
+        // minting and transferring to new accounts adds holders, more tokens for a holder do not
+        let mint = |recipient: &str| ExecuteMsg::Mint {
+            recipient: recipient.to_string(),
+            amount: Uint128::new(100),
+        };
+        exec(deps.as_mut(), "minter", mint("bob"));
+        exec(deps.as_mut(), "minter", mint("alice"));
+        assert_eq!(num_holders(deps.as_ref()), 2);
+        let transfer = |recipient: &str, amount: u128| ExecuteMsg::Transfer {
+            recipient: recipient.to_string(),
+            amount: Uint128::new(amount),
+        };
+        exec(deps.as_mut(), "alice", transfer("carl", 100));
+        assert_eq!(num_holders(deps.as_ref()), 3);
+
+        // emptying an account removes it
+        exec(deps.as_mut(), "bob", transfer("carl", 100));
+        assert_eq!(num_holders(deps.as_ref()), 2);
+        exec(
+            deps.as_mut(),
+            "carl",
+            ExecuteMsg::Burn {
+                amount: Uint128::new(200),
+                memo: None,
+            },
+        );
+        assert_eq!(num_holders(deps.as_ref()), 1);
+
+        // a migration from a version without the count backfills it
+        migrate_without_holders(deps.as_mut());
+        assert!(continue_backfill(deps.as_mut()));
+        assert_eq!(num_holders(deps.as_ref()), 1);
+        assert_eq!(
+            top_holders(deps.as_ref(), None, None),
+            vec![holder("alice", 1000)]
+        );
+    }
+
+    fn holder(address: &str, balance: u128) -> AccountBalance {
+        AccountBalance {
+            address: address.to_string(),
+            balance: Uint128::new(balance),
+        }
+    }
+
+    fn top_holders(
+        deps: Deps,
+        start_after: Option<AccountBalance>,
+        limit: Option<u32>,
+    ) -> Vec<AccountBalance> {
+        let msg = QueryMsg::TopHolders { start_after, limit };
+        let res: TopHoldersResponse = from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+        assert!(res.complete);
+        res.holders
+    }
+
+    #[test]
+    fn holders_backfilled_in_batches() {
+        let mut deps = mock_dependencies();
+        let initial_balances = (0..150)
+            .map(|i| Cw20Coin {
+                address: format!("holder{:03}", i),
+                amount: Uint128::new(10),
+            })
+            .collect();
+        let msg = InstantiateMsg {
+            name: "Crowd".to_string(),
+            symbol: "CROWD".to_string(),
+            decimals: 6,
+            initial_balances,
+            mint: None,
+            marketing: None,
+            snapshots: false,
+            freezer: None,
+            pauser: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        migrate_without_holders(deps.as_mut());
+
+        // holder queries are incomplete until the backfill is over
+        let res: NumHoldersResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::NumHolders {}).unwrap())
+                .unwrap();
+        assert_eq!(
+            res,
+            NumHoldersResponse {
+                num_holders: 0,
+                complete: false
+            }
+        );
+        assert!(!continue_backfill(deps.as_mut()));
+        let msg = QueryMsg::TopHolders {
+            start_after: None,
+            limit: None,
+        };
+        let res: TopHoldersResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert!(!res.complete);
+        assert_eq!(res.holders.len(), 10);
+
+        // balances changing in between are counted once, whether the backfill reached them or not
+        let transfer = ExecuteMsg::Transfer {
+            recipient: "zed".to_string(),
+            amount: Uint128::new(10),
+        };
+        exec(deps.as_mut(), "holder010", transfer);
+        let res: NumHoldersResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::NumHolders {}).unwrap())
+                .unwrap();
+        assert_eq!(res.num_holders, 99);
+        assert!(continue_backfill(deps.as_mut()));
+        assert_eq!(num_holders(deps.as_ref()), 150);
+        assert_eq!(
+            top_holders(deps.as_ref(), None, Some(2)),
+            vec![holder("zed", 10), holder("holder149", 10)]
+        );
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::ContinueHoldersBackfill {},
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::NoHoldersBackfillInProgress {});
+    }
+
+    #[test]
+    fn top_holders_by_descending_balance() {
+        let mut deps = mock_dependencies();
+        let coin = |address: &str, amount: u128| Cw20Coin {
+            address: address.to_string(),
+            amount: Uint128::new(amount),
+        };
+        let msg = InstantiateMsg {
+            name: "Whales".to_string(),
+            symbol: "WHALE".to_string(),
+            decimals: 6,
+            initial_balances: vec![
+                coin("alice", 300),
+                coin("bob", 500),
+                coin("carl", 300),
+                coin("dave", 100),
+            ],
+            mint: None,
+            marketing: None,
+            snapshots: true,
+            freezer: None,
+            pauser: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        // equal balances are ordered by descending address
+        let expected = vec![
+            holder("bob", 500),
+            holder("carl", 300),
+            holder("alice", 300),
+            holder("dave", 100),
+        ];
+        assert_eq!(top_holders(deps.as_ref(), None, None), expected);
+        let page = top_holders(deps.as_ref(), None, Some(2));
+        assert_eq!(page, expected[..2]);
+        let page = top_holders(deps.as_ref(), page.last().cloned(), Some(2));
+        assert_eq!(page, expected[2..]);
+
+        // balance changes move the holders around, emptied accounts are dropped
+        exec(
+            deps.as_mut(),
+            "dave",
+            ExecuteMsg::Transfer {
+                recipient: "alice".to_string(),
+                amount: Uint128::new(100),
+            },
+        );
+        assert_eq!(
+            top_holders(deps.as_ref(), None, None),
+            vec![
+                holder("bob", 500),
+                holder("alice", 400),
+                holder("carl", 300)
+            ]
+        );
+    }
+}
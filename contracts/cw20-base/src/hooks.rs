@@ -0,0 +1,123 @@
+use cosmwasm_std::{
+    Addr, CosmosMsg, Deps, DepsMut, MessageInfo, Response, StdResult, Storage, SubMsg, Uint128,
+};
+use cw20::{Cw20MintCallbackMsg, Cw20TransferHookMsg};
+
+use crate::error::ContractError;
+use crate::msg::MintCallbackResponse;
+use crate::state::{HOOKS, MINT_CALLBACK, TOKEN_INFO};
+
+/// Reply id of the transfer hook messages, whose failures are ignored
+pub const TRANSFER_HOOK_REPLY_ID: u64 = 2;
+
+/// Prepares a `Cw20TransferHookMsg` for every registered hook. They are sent as submessages
+/// replying on error, so a failing hook contract cannot block token movements.
+pub fn transfer_hook_msgs(
+    storage: &dyn Storage,
+    sender: &Addr,
+    recipient: Option<&Addr>,
+    amount: Uint128,
+) -> StdResult<Vec<SubMsg>> {
+    let msg = Cw20TransferHookMsg {
+        sender: sender.to_string(),
+        recipient: recipient.map(Addr::to_string),
+        amount,
+    };
+    HOOKS.prepare_hooks(storage, |h| {
+        msg.clone()
+            .into_cosmos_msg(h)
+            .map(|msg| SubMsg::reply_on_error(msg, TRANSFER_HOOK_REPLY_ID))
+    })
+}
+
+/// Prepares the `Cw20MintCallbackMsg` for `minter`, if it requested callbacks and still is the
+/// minter. It is sent as a plain message, so the mint fails along with the callback.
+pub fn mint_callback_msg(
+    storage: &dyn Storage,
+    minter: &Addr,
+    recipient: &Addr,
+    amount: Uint128,
+) -> StdResult<Option<CosmosMsg>> {
+    match MINT_CALLBACK.may_load(storage)? {
+        Some(addr) if addr == *minter && assert_minter(storage, minter).is_ok() => {
+            let msg = Cw20MintCallbackMsg {
+                recipient: recipient.to_string(),
+                amount,
+            };
+            Ok(Some(msg.into_cosmos_msg(minter)?))
+        }
+        _ => Ok(None),
+    }
+}
+
+pub(crate) fn assert_minter(storage: &dyn Storage, sender: &Addr) -> Result<(), ContractError> {
+    let config = TOKEN_INFO
+        .may_load(storage)?
+        .ok_or(ContractError::Unauthorized {})?;
+    let mint = config.mint.ok_or(ContractError::Unauthorized {})?;
+    if mint.minter != *sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
+pub fn execute_add_hook(
+    deps: DepsMut,
+    info: MessageInfo,
+    addr: String,
+) -> Result<Response, ContractError> {
+    assert_minter(deps.storage, &info.sender)?;
+    let addr = deps.api.addr_validate(&addr)?;
+    HOOKS.add_hook(deps.storage, addr.clone())?;
+
+    let res = Response::new()
+        .add_attribute("action", "add_hook")
+        .add_attribute("hook", addr)
+        .add_attribute("sender", info.sender);
+    Ok(res)
+}
+
+pub fn execute_remove_hook(
+    deps: DepsMut,
+    info: MessageInfo,
+    addr: String,
+) -> Result<Response, ContractError> {
+    assert_minter(deps.storage, &info.sender)?;
+    let addr = deps.api.addr_validate(&addr)?;
+    HOOKS.remove_hook(deps.storage, addr.clone())?;
+
+    let res = Response::new()
+        .add_attribute("action", "remove_hook")
+        .add_attribute("hook", addr)
+        .add_attribute("sender", info.sender);
+    Ok(res)
+}
+
+pub fn execute_update_mint_callback(
+    deps: DepsMut,
+    info: MessageInfo,
+    enabled: bool,
+) -> Result<Response, ContractError> {
+    assert_minter(deps.storage, &info.sender)?;
+    if enabled {
+        if deps.querier.query_wasm_contract_info(&info.sender).is_err() {
+            return Err(ContractError::MintCallbackNotContract {});
+        }
+        MINT_CALLBACK.save(deps.storage, &info.sender)?;
+    } else {
+        MINT_CALLBACK.remove(deps.storage);
+    }
+
+    let res = Response::new()
+        .add_attribute("action", "update_mint_callback")
+        .add_attribute("enabled", enabled.to_string());
+    Ok(res)
+}
+
+pub fn query_mint_callback(deps: Deps) -> StdResult<MintCallbackResponse> {
+    let enabled = match MINT_CALLBACK.may_load(deps.storage)? {
+        Some(addr) => assert_minter(deps.storage, &addr).is_ok(),
+        None => false,
+    };
+    Ok(MintCallbackResponse { enabled })
+}
@@ -0,0 +1,286 @@
+use cosmwasm_std::{Addr, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Storage};
+use cw20::{AllowanceResponse, Cw20Coin};
+
+use crate::error::ContractError;
+use crate::msg::{ImportConfig, ImportStatusResponse, ImportedAllowance};
+use crate::snapshots::{record_total_supply, update_balance};
+use crate::state::{LegacyImport, ALLOWANCES, ALLOWANCES_SPENDER, LEGACY_IMPORT, TOKEN_INFO};
+
+/// Maximum number of balances and allowances set by a single `ImportBalances`,
+/// to keep each message well within the gas limit
+pub const MAX_IMPORT_BATCH: usize = 100;
+
+/// Starts a legacy import, called on migration. Replaces any import in progress.
+pub fn start_import(deps: DepsMut, config: ImportConfig) -> Result<Response, ContractError> {
+    let import = LegacyImport {
+        importer: deps.api.addr_validate(&config.importer)?,
+        expected_total_supply: config.expected_total_supply,
+    };
+    LEGACY_IMPORT.save(deps.storage, &import)?;
+
+    let res = Response::new()
+        .add_attribute("action", "start_import")
+        .add_attribute("importer", import.importer)
+        .add_attribute("expected_total_supply", import.expected_total_supply);
+    Ok(res)
+}
+
+fn assert_importer(storage: &dyn Storage, sender: &Addr) -> Result<LegacyImport, ContractError> {
+    let import = LEGACY_IMPORT
+        .may_load(storage)?
+        .ok_or(ContractError::NoImportInProgress {})?;
+    if import.importer != *sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(import)
+}
+
+pub fn execute_import_balances(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    balances: Vec<Cw20Coin>,
+    allowances: Vec<ImportedAllowance>,
+) -> Result<Response, ContractError> {
+    assert_importer(deps.storage, &info.sender)?;
+    if balances.len() + allowances.len() > MAX_IMPORT_BATCH {
+        return Err(ContractError::ImportBatchTooLarge {
+            max: MAX_IMPORT_BATCH,
+        });
+    }
+
+    // balances are overwritten, so the total supply only changes by the difference
+    let mut config = TOKEN_INFO.load(deps.storage)?;
+    for row in &balances {
+        let address = deps.api.addr_validate(&row.address)?;
+        let mut previous = None;
+        update_balance(
+            deps.storage,
+            env.block.height,
+            &address,
+            |balance| -> StdResult<_> {
+                previous = balance;
+                Ok(row.amount)
+            },
+        )?;
+        config.total_supply = config.total_supply - previous.unwrap_or_default() + row.amount;
+    }
+    TOKEN_INFO.save(deps.storage, &config)?;
+    record_total_supply(deps.storage, env.block.height, config.total_supply)?;
+
+    for row in &allowances {
+        let owner = deps.api.addr_validate(&row.owner)?;
+        let spender = deps.api.addr_validate(&row.spender)?;
+        if row.allowance.is_zero() {
+            ALLOWANCES.remove(deps.storage, (&owner, &spender));
+            ALLOWANCES_SPENDER.remove(deps.storage, (&spender, &owner));
+        } else {
+            let allowance = AllowanceResponse {
+                allowance: row.allowance,
+                expires: row.expires,
+            };
+            ALLOWANCES.save(deps.storage, (&owner, &spender), &allowance)?;
+            ALLOWANCES_SPENDER.save(deps.storage, (&spender, &owner), &allowance)?;
+        }
+    }
+
+    let res = Response::new()
+        .add_attribute("action", "import_balances")
+        .add_attribute("balances", balances.len().to_string())
+        .add_attribute("allowances", allowances.len().to_string())
+        .add_attribute("total_supply", config.total_supply);
+    Ok(res)
+}
+
+/// Ends the import, checking nothing was lost or imported twice along the way
+pub fn execute_finish_import(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let import = assert_importer(deps.storage, &info.sender)?;
+    let total_supply = TOKEN_INFO.load(deps.storage)?.total_supply;
+    if total_supply != import.expected_total_supply {
+        return Err(ContractError::ImportChecksumMismatch {
+            expected: import.expected_total_supply,
+            actual: total_supply,
+        });
+    }
+    LEGACY_IMPORT.remove(deps.storage);
+
+    let res = Response::new()
+        .add_attribute("action", "finish_import")
+        .add_attribute("total_supply", total_supply);
+    Ok(res)
+}
+
+pub fn query_import_status(deps: Deps) -> StdResult<Option<ImportStatusResponse>> {
+    let import = match LEGACY_IMPORT.may_load(deps.storage)? {
+        Some(import) => import,
+        None => return Ok(None),
+    };
+    let total_supply = TOKEN_INFO.load(deps.storage)?.total_supply;
+    Ok(Some(ImportStatusResponse {
+        importer: import.importer.into(),
+        expected_total_supply: import.expected_total_supply,
+        total_supply,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{from_binary, Uint128};
+    use cw20::Expiration;
+
+    use crate::allowances::query_allowance;
+    use crate::contract::{execute, instantiate, migrate, query, query_balance, query_token_info};
+    use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+
+    fn coin(address: &str, amount: u128) -> Cw20Coin {
+        Cw20Coin {
+            address: address.to_string(),
+            amount: Uint128::new(amount),
+        }
+    }
+
+    fn import(
+        deps: DepsMut,
+        sender: &str,
+        balances: Vec<Cw20Coin>,
+        allowances: Vec<ImportedAllowance>,
+    ) -> Result<Response, ContractError> {
+        let msg = ExecuteMsg::ImportBalances {
+            balances,
+            allowances,
+        };
+        execute(deps, mock_env(), mock_info(sender, &[]), msg)
+    }
+
+    fn finish(deps: DepsMut, sender: &str) -> Result<Response, ContractError> {
+        execute(
+            deps,
+            mock_env(),
+            mock_info(sender, &[]),
+            ExecuteMsg::FinishImport {},
+        )
+    }
+
+    fn import_status(deps: Deps) -> Option<ImportStatusResponse> {
+        from_binary(&query(deps, mock_env(), QueryMsg::ImportStatus {}).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn import_legacy_balances() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            name: "Redeployed".to_string(),
+            symbol: "RDP".to_string(),
+            decimals: 6,
+            initial_balances: vec![],
+            mint: None,
+            marketing: None,
+            snapshots: false,
+            freezer: None,
+            pauser: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        // nothing to import before the migration starts it
+        let err = import(deps.as_mut(), "importer", vec![coin("alice", 1)], vec![]).unwrap_err();
+        assert_eq!(err, ContractError::NoImportInProgress {});
+
+        let msg = MigrateMsg {
+            import: Some(ImportConfig {
+                importer: "importer".to_string(),
+                expected_total_supply: Uint128::new(1500),
+            }),
+            decimals: None,
+        };
+        migrate(deps.as_mut(), mock_env(), msg).unwrap();
+        assert_eq!(
+            import_status(deps.as_ref()),
+            Some(ImportStatusResponse {
+                importer: "importer".to_string(),
+                expected_total_supply: Uint128::new(1500),
+                total_supply: Uint128::zero(),
+            })
+        );
+
+        let err = import(deps.as_mut(), "alice", vec![coin("alice", 1)], vec![]).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let too_many = (0..=MAX_IMPORT_BATCH)
+            .map(|i| coin(&format!("addr{:04}", i), 1))
+            .collect();
+        let err = import(deps.as_mut(), "importer", too_many, vec![]).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::ImportBatchTooLarge {
+                max: MAX_IMPORT_BATCH
+            }
+        );
+
+        let expires = Expiration::AtHeight(123_456);
+        let allowances = vec![ImportedAllowance {
+            owner: "alice".to_string(),
+            spender: "bob".to_string(),
+            allowance: Uint128::new(300),
+            expires,
+        }];
+        import(
+            deps.as_mut(),
+            "importer",
+            vec![coin("alice", 1000), coin("bob", 200)],
+            allowances,
+        )
+        .unwrap();
+        // sending a batch again does not count it twice
+        import(deps.as_mut(), "importer", vec![coin("bob", 200)], vec![]).unwrap();
+
+        // the checksum catches missing balances
+        let err = finish(deps.as_mut(), "importer").unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::ImportChecksumMismatch {
+                expected: Uint128::new(1500),
+                actual: Uint128::new(1200),
+            }
+        );
+        import(deps.as_mut(), "importer", vec![coin("carl", 300)], vec![]).unwrap();
+        let err = finish(deps.as_mut(), "alice").unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        finish(deps.as_mut(), "importer").unwrap();
+
+        assert_eq!(import_status(deps.as_ref()), None);
+        assert_eq!(
+            query_token_info(deps.as_ref()).unwrap().total_supply,
+            Uint128::new(1500)
+        );
+        assert_eq!(
+            query_balance(deps.as_ref(), "bob".to_string())
+                .unwrap()
+                .balance,
+            Uint128::new(200)
+        );
+        assert_eq!(
+            query_allowance(deps.as_ref(), "alice".to_string(), "bob".to_string()).unwrap(),
+            AllowanceResponse {
+                allowance: Uint128::new(300),
+                expires,
+            }
+        );
+
+        // the imported allowance is usable, and the import cannot be reopened
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bob", &[]),
+            ExecuteMsg::TransferFrom {
+                owner: "alice".to_string(),
+                recipient: "bob".to_string(),
+                amount: Uint128::new(300),
+            },
+        )
+        .unwrap();
+        let err = import(deps.as_mut(), "importer", vec![coin("alice", 1)], vec![]).unwrap_err();
+        assert_eq!(err, ContractError::NoImportInProgress {});
+    }
+}
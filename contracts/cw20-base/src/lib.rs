@@ -14,11 +14,30 @@ For more information on this contract, please check out the
 [README](https://github.com/CosmWasm/cw-plus/blob/main/contracts/cw20-base/README.md).
 */
 
+pub mod allowance_period;
 pub mod allowances;
+pub mod burn;
 pub mod contract;
 pub mod enumerable;
 mod error;
+pub mod escrow;
+pub mod fee;
+pub mod freeze;
+pub mod holders;
+pub mod hooks;
+pub mod import;
+pub mod mint_limit;
+pub mod mint_schedule;
+pub mod minters;
 pub mod msg;
+pub mod permit;
+pub mod rebase;
+pub mod recover;
+pub mod rescale;
+pub mod snapshots;
 pub mod state;
+pub mod vesting;
+pub mod votes;
+pub mod wallet_limits;
 
 pub use crate::error::ContractError;
@@ -0,0 +1,280 @@
+use cosmwasm_std::{
+    BlockInfo, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Storage, Uint128,
+};
+use cw_utils::Duration;
+
+use crate::error::ContractError;
+use crate::msg::{MintRateLimit, MintRateLimitResponse};
+use crate::state::{MintWindow, MINT_RATE_LIMIT, MINT_WINDOW, TOKEN_INFO};
+
+/// Returns the window containing the given block. A new one, with nothing minted yet,
+/// is started if the last window is over.
+fn current_window(
+    storage: &dyn Storage,
+    block: &BlockInfo,
+    limit: &MintRateLimit,
+) -> StdResult<MintWindow> {
+    match MINT_WINDOW.may_load(storage)? {
+        Some(window) if !window.ends.is_expired(block) => Ok(window),
+        _ => Ok(MintWindow {
+            ends: limit.window.after(block),
+            minted: Uint128::zero(),
+        }),
+    }
+}
+
+/// Accounts for `amount` newly minted tokens in the current window, failing if this
+/// exceeds the mint rate limit. Does nothing if no limit is set.
+pub fn apply_mint_rate_limit(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    let limit = match MINT_RATE_LIMIT.may_load(storage)? {
+        Some(limit) => limit,
+        None => return Ok(()),
+    };
+
+    let mut window = current_window(storage, block, &limit)?;
+    let remaining = limit.max_amount.saturating_sub(window.minted);
+    if amount > remaining {
+        return Err(ContractError::MintRateLimitExceeded { remaining });
+    }
+    window.minted += amount;
+    MINT_WINDOW.save(storage, &window)?;
+    Ok(())
+}
+
+/// Sets or removes (with `None`) the mint rate limit. Must be called by the minter.
+/// The current window is dropped, so the next mint starts a new one.
+pub fn execute_update_mint_rate_limit(
+    deps: DepsMut,
+    info: MessageInfo,
+    rate_limit: Option<MintRateLimit>,
+) -> Result<Response, ContractError> {
+    let config = TOKEN_INFO
+        .may_load(deps.storage)?
+        .ok_or(ContractError::Unauthorized {})?;
+    let mint = config.mint.ok_or(ContractError::Unauthorized {})?;
+    if mint.minter != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    MINT_WINDOW.remove(deps.storage);
+    let res = match rate_limit {
+        Some(limit) => {
+            if limit.window == Duration::Height(0) || limit.window == Duration::Time(0) {
+                return Err(ContractError::InvalidMintWindow {});
+            }
+            MINT_RATE_LIMIT.save(deps.storage, &limit)?;
+            Response::new()
+                .add_attribute("action", "update_mint_rate_limit")
+                .add_attribute("max_amount", limit.max_amount)
+                .add_attribute("window", limit.window.to_string())
+        }
+        None => {
+            MINT_RATE_LIMIT.remove(deps.storage);
+            Response::new()
+                .add_attribute("action", "update_mint_rate_limit")
+                .add_attribute("max_amount", "None")
+        }
+    };
+    Ok(res)
+}
+
+pub fn query_mint_rate_limit(deps: Deps, env: Env) -> StdResult<MintRateLimitResponse> {
+    let limit = match MINT_RATE_LIMIT.may_load(deps.storage)? {
+        Some(limit) => limit,
+        None => {
+            return Ok(MintRateLimitResponse {
+                rate_limit: None,
+                remaining: None,
+                window_ends: None,
+            })
+        }
+    };
+
+    // a window only really starts with its first mint
+    let window = MINT_WINDOW
+        .may_load(deps.storage)?
+        .filter(|window| !window.ends.is_expired(&env.block));
+    let minted = window.as_ref().map(|w| w.minted).unwrap_or_default();
+    Ok(MintRateLimitResponse {
+        remaining: Some(limit.max_amount.saturating_sub(minted)),
+        window_ends: window.map(|w| w.ends),
+        rate_limit: Some(limit),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::from_binary;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cw20::{Expiration, MinterResponse};
+
+    use crate::contract::{execute, instantiate, query, query_balance};
+    use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+
+    fn query_limit(deps: Deps, env: Env) -> MintRateLimitResponse {
+        from_binary(&query(deps, env, QueryMsg::MintRateLimit {}).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn mint_rate_limit_per_window() {
+        let mut deps = mock_dependencies();
+        let minter = "minter";
+        let msg = InstantiateMsg {
+            name: "Limited".to_string(),
+            symbol: "LIM".to_string(),
+            decimals: 6,
+            initial_balances: vec![],
+            mint: Some(MinterResponse {
+                minter: minter.to_string(),
+                cap: Some(Uint128::new(1500)),
+            }),
+            marketing: None,
+            snapshots: false,
+            freezer: None,
+            pauser: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        assert_eq!(
+            query_limit(deps.as_ref(), mock_env()),
+            MintRateLimitResponse {
+                rate_limit: None,
+                remaining: None,
+                window_ends: None,
+            }
+        );
+
+        // only the minter can set a limit, and the window cannot be empty
+        let limit = MintRateLimit {
+            max_amount: Uint128::new(1000),
+            window: Duration::Height(10),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::UpdateMintRateLimit {
+                rate_limit: Some(limit.clone()),
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(minter, &[]),
+            ExecuteMsg::UpdateMintRateLimit {
+                rate_limit: Some(MintRateLimit {
+                    max_amount: Uint128::new(1000),
+                    window: Duration::Time(0),
+                }),
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidMintWindow {});
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(minter, &[]),
+            ExecuteMsg::UpdateMintRateLimit {
+                rate_limit: Some(limit.clone()),
+            },
+        )
+        .unwrap();
+
+        // nothing minted yet, so no window started
+        let mut env = mock_env();
+        assert_eq!(
+            query_limit(deps.as_ref(), env.clone()),
+            MintRateLimitResponse {
+                rate_limit: Some(limit.clone()),
+                remaining: Some(Uint128::new(1000)),
+                window_ends: None,
+            }
+        );
+
+        let mint = |amount: u128| ExecuteMsg::Mint {
+            recipient: "rcpt".to_string(),
+            amount: Uint128::new(amount),
+        };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(minter, &[]),
+            mint(600),
+        )
+        .unwrap();
+        env.block.height += 5;
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(minter, &[]),
+            mint(401),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::MintRateLimitExceeded {
+                remaining: Uint128::new(400)
+            }
+        );
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(minter, &[]),
+            mint(400),
+        )
+        .unwrap();
+        assert_eq!(
+            query_limit(deps.as_ref(), env.clone()),
+            MintRateLimitResponse {
+                rate_limit: Some(limit),
+                remaining: Some(Uint128::zero()),
+                window_ends: Some(Expiration::AtHeight(mock_env().block.height + 10)),
+            }
+        );
+
+        // the next window starts fresh, but the absolute cap still applies
+        env.block.height += 5;
+        assert_eq!(
+            query_limit(deps.as_ref(), env.clone()).remaining,
+            Some(Uint128::new(1000))
+        );
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(minter, &[]),
+            mint(600),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::CannotExceedCap {});
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(minter, &[]),
+            mint(500),
+        )
+        .unwrap();
+        assert_eq!(
+            query_balance(deps.as_ref(), "rcpt".to_string())
+                .unwrap()
+                .balance,
+            Uint128::new(1500)
+        );
+
+        // the limit can be removed again
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(minter, &[]),
+            ExecuteMsg::UpdateMintRateLimit { rate_limit: None },
+        )
+        .unwrap();
+        assert_eq!(query_limit(deps.as_ref(), env).rate_limit, None);
+    }
+}
@@ -0,0 +1,265 @@
+use cosmwasm_std::{BlockInfo, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Uint128};
+use cw20::Expiration;
+use cw_utils::Duration;
+
+use crate::error::ContractError;
+use crate::hooks::assert_minter;
+use crate::msg::{MintScheduleMsg, MintScheduleResponse};
+use crate::snapshots::{record_total_supply, update_balance};
+use crate::state::{MintSchedule, MINT_SCHEDULE, PAUSE, TOKEN_INFO};
+use crate::wallet_limits::ensure_within_wallet_limits;
+
+/// Number of periods over since the schedule started, at most all of them
+fn periods_over(schedule: &MintSchedule, block: &BlockInfo) -> u64 {
+    // the start is always recorded in the unit of the period
+    let periods = match (schedule.period, schedule.start) {
+        (Duration::Height(period), Expiration::AtHeight(start)) => {
+            block.height.saturating_sub(start) / period
+        }
+        (Duration::Time(period), Expiration::AtTime(start)) => {
+            block.time.seconds().saturating_sub(start.seconds()) / period
+        }
+        _ => 0,
+    };
+    periods.min(schedule.num_periods)
+}
+
+/// Tokens unlocked by the schedule, but not minted yet
+fn vested(schedule: &MintSchedule, block: &BlockInfo) -> Uint128 {
+    let unlocked = schedule
+        .amount_per_period
+        .checked_mul(periods_over(schedule, block).into())
+        .unwrap_or(Uint128::MAX);
+    unlocked.saturating_sub(schedule.minted)
+}
+
+/// Sets the emission schedule, starting now, or removes it (with `None`). Must be called by the minter.
+/// Tokens vested under the previous schedule that were not minted yet are dropped.
+pub fn execute_set_mint_schedule(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    schedule: Option<MintScheduleMsg>,
+) -> Result<Response, ContractError> {
+    assert_minter(deps.storage, &info.sender)?;
+
+    let msg = match schedule {
+        Some(msg) => msg,
+        None => {
+            MINT_SCHEDULE.remove(deps.storage);
+            let res = Response::new()
+                .add_attribute("action", "set_mint_schedule")
+                .add_attribute("recipient", "None");
+            return Ok(res);
+        }
+    };
+    let start = match msg.period {
+        Duration::Height(0) | Duration::Time(0) => {
+            return Err(ContractError::InvalidMintSchedule {})
+        }
+        Duration::Height(_) => Expiration::AtHeight(env.block.height),
+        Duration::Time(_) => Expiration::AtTime(env.block.time),
+    };
+    if msg.amount_per_period.is_zero() || msg.num_periods == 0 {
+        return Err(ContractError::InvalidMintSchedule {});
+    }
+    let schedule = MintSchedule {
+        recipient: deps.api.addr_validate(&msg.recipient)?,
+        amount_per_period: msg.amount_per_period,
+        period: msg.period,
+        num_periods: msg.num_periods,
+        start,
+        minted: Uint128::zero(),
+    };
+    MINT_SCHEDULE.save(deps.storage, &schedule)?;
+
+    let res = Response::new()
+        .add_attribute("action", "set_mint_schedule")
+        .add_attribute("recipient", schedule.recipient)
+        .add_attribute("amount_per_period", schedule.amount_per_period)
+        .add_attribute("period", schedule.period.to_string())
+        .add_attribute("num_periods", schedule.num_periods.to_string());
+    Ok(res)
+}
+
+/// Mints everything unlocked by the schedule so far to its recipient. Anyone can call it.
+pub fn execute_mint_vested(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    PAUSE.assert_not_paused(deps.storage, &env.block)?;
+    let mut schedule = MINT_SCHEDULE
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NothingVested {})?;
+    let amount = vested(&schedule, &env.block);
+    if amount.is_zero() {
+        return Err(ContractError::NothingVested {});
+    }
+
+    // minting is disabled once the minter is removed, and the cap still applies
+    let mut config = TOKEN_INFO.load(deps.storage)?;
+    if config.mint.is_none() {
+        return Err(ContractError::Unauthorized {});
+    }
+    config.total_supply += amount;
+    if let Some(limit) = config.get_cap() {
+        if config.total_supply > limit {
+            return Err(ContractError::CannotExceedCap {});
+        }
+    }
+    ensure_within_wallet_limits(deps.storage, None, &schedule.recipient, amount, amount)?;
+    TOKEN_INFO.save(deps.storage, &config)?;
+    record_total_supply(deps.storage, env.block.height, config.total_supply)?;
+    schedule.minted += amount;
+    MINT_SCHEDULE.save(deps.storage, &schedule)?;
+
+    update_balance(
+        deps.storage,
+        env.block.height,
+        &schedule.recipient,
+        |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + amount) },
+    )?;
+
+    let res = Response::new()
+        .add_attribute("action", "mint_vested")
+        .add_attribute("to", schedule.recipient)
+        .add_attribute("amount", amount);
+    Ok(res)
+}
+
+pub fn query_mint_schedule(deps: Deps, env: Env) -> StdResult<Option<MintScheduleResponse>> {
+    let schedule = match MINT_SCHEDULE.may_load(deps.storage)? {
+        Some(schedule) => schedule,
+        None => return Ok(None),
+    };
+    let total = schedule
+        .amount_per_period
+        .checked_mul(schedule.num_periods.into())
+        .unwrap_or(Uint128::MAX);
+    Ok(Some(MintScheduleResponse {
+        vested: vested(&schedule, &env.block),
+        remaining: total.saturating_sub(schedule.minted),
+        recipient: schedule.recipient.into(),
+        amount_per_period: schedule.amount_per_period,
+        period: schedule.period,
+        num_periods: schedule.num_periods,
+        start: schedule.start,
+        minted: schedule.minted,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::from_binary;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cw20::MinterResponse;
+
+    use crate::contract::{execute, instantiate, query, query_balance};
+    use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+
+    fn query_schedule(deps: Deps, env: Env) -> Option<MintScheduleResponse> {
+        from_binary(&query(deps, env, QueryMsg::MintSchedule {}).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn mint_vested_per_period() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            name: "Emitted".to_string(),
+            symbol: "EMT".to_string(),
+            decimals: 6,
+            initial_balances: vec![],
+            mint: Some(MinterResponse {
+                minter: "minter".to_string(),
+                cap: Some(Uint128::new(3500)),
+            }),
+            marketing: None,
+            snapshots: false,
+            freezer: None,
+            pauser: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        assert_eq!(query_schedule(deps.as_ref(), mock_env()), None);
+
+        let schedule = MintScheduleMsg {
+            recipient: "treasury".to_string(),
+            amount_per_period: Uint128::new(1000),
+            period: Duration::Time(86400),
+            num_periods: 4,
+        };
+        let set_schedule = |schedule: MintScheduleMsg| ExecuteMsg::SetMintSchedule {
+            schedule: Some(schedule),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("treasury", &[]),
+            set_schedule(schedule.clone()),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("minter", &[]),
+            set_schedule(MintScheduleMsg {
+                period: Duration::Height(0),
+                ..schedule.clone()
+            }),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidMintSchedule {});
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("minter", &[]),
+            set_schedule(schedule),
+        )
+        .unwrap();
+
+        // nothing before the first period is over
+        let mut env = mock_env();
+        let crank = |deps: DepsMut, env: Env| {
+            execute(
+                deps,
+                env,
+                mock_info("anyone", &[]),
+                ExecuteMsg::MintVested {},
+            )
+        };
+        let err = crank(deps.as_mut(), env.clone()).unwrap_err();
+        assert_eq!(err, ContractError::NothingVested {});
+
+        // anyone can mint everything unlocked so far
+        env.block.time = env.block.time.plus_seconds(2 * 86400 + 100);
+        assert_eq!(
+            query_schedule(deps.as_ref(), env.clone()),
+            Some(MintScheduleResponse {
+                recipient: "treasury".to_string(),
+                amount_per_period: Uint128::new(1000),
+                period: Duration::Time(86400),
+                num_periods: 4,
+                start: Expiration::AtTime(mock_env().block.time),
+                minted: Uint128::zero(),
+                vested: Uint128::new(2000),
+                remaining: Uint128::new(4000),
+            })
+        );
+        crank(deps.as_mut(), env.clone()).unwrap();
+        let err = crank(deps.as_mut(), env.clone()).unwrap_err();
+        assert_eq!(err, ContractError::NothingVested {});
+        assert_eq!(
+            query_balance(deps.as_ref(), "treasury".to_string())
+                .unwrap()
+                .balance,
+            Uint128::new(2000)
+        );
+
+        // emissions stop after the last period, and respect the cap
+        env.block.time = env.block.time.plus_seconds(10 * 86400);
+        let res = query_schedule(deps.as_ref(), env.clone()).unwrap();
+        assert_eq!(res.vested, Uint128::new(2000));
+        assert_eq!(res.remaining, Uint128::new(2000));
+        let err = crank(deps.as_mut(), env.clone()).unwrap_err();
+        assert_eq!(err, ContractError::CannotExceedCap {});
+    }
+}
@@ -0,0 +1,280 @@
+use cosmwasm_std::{
+    Addr, Deps, DepsMut, MessageInfo, Order, Response, StdResult, Storage, Uint128,
+};
+use cw_storage_plus::Bound;
+
+use crate::error::ContractError;
+use crate::msg::{MinterInfo, MintersResponse};
+use crate::state::{MinterAllowance, MINTERS, TOKEN_INFO};
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+/// The minter set on the token administers the additional minters
+fn assert_minter_admin(storage: &dyn Storage, sender: &Addr) -> Result<(), ContractError> {
+    let config = TOKEN_INFO
+        .may_load(storage)?
+        .ok_or(ContractError::Unauthorized {})?;
+    let mint = config.mint.ok_or(ContractError::Unauthorized {})?;
+    if mint.minter != *sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
+/// Adds `amount` to the tokens minted by an additional minter, failing if it is not one
+/// or if this would exceed its cap
+pub fn charge_minter(
+    storage: &mut dyn Storage,
+    minter: &Addr,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    let mut allowance = MINTERS
+        .may_load(storage, minter)?
+        .ok_or(ContractError::Unauthorized {})?;
+    allowance.minted += amount;
+    if let Some(cap) = allowance.cap {
+        if allowance.minted > cap {
+            return Err(ContractError::CannotExceedCap {});
+        }
+    }
+    MINTERS.save(storage, minter, &allowance)?;
+    Ok(())
+}
+
+/// Grants mint rights to `address` up to `cap` tokens, or updates its cap if it already
+/// has them. The tokens it already minted keep counting towards the new cap.
+pub fn execute_set_minter(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+    cap: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    assert_minter_admin(deps.storage, &info.sender)?;
+    let minter = deps.api.addr_validate(&address)?;
+    MINTERS.update(deps.storage, &minter, |allowance| -> StdResult<_> {
+        let minted = allowance.map(|a| a.minted).unwrap_or_default();
+        Ok(MinterAllowance { cap, minted })
+    })?;
+
+    let res = Response::new()
+        .add_attribute("action", "set_minter")
+        .add_attribute("minter", minter)
+        .add_attribute(
+            "cap",
+            cap.map(|c| c.to_string())
+                .unwrap_or_else(|| "None".to_string()),
+        );
+    Ok(res)
+}
+
+pub fn execute_remove_minter(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    assert_minter_admin(deps.storage, &info.sender)?;
+    let minter = deps.api.addr_validate(&address)?;
+    if !MINTERS.has(deps.storage, &minter) {
+        return Err(ContractError::NotMinter {});
+    }
+    MINTERS.remove(deps.storage, &minter);
+
+    let res = Response::new()
+        .add_attribute("action", "remove_minter")
+        .add_attribute("minter", minter);
+    Ok(res)
+}
+
+pub fn query_minters(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<MintersResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|s| Bound::ExclusiveRaw(s.into()));
+
+    let minters = MINTERS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            item.map(|(address, allowance)| MinterInfo {
+                address: address.into(),
+                cap: allowance.cap,
+                minted: allowance.minted,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(MintersResponse { minters })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::from_binary;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cw20::MinterResponse;
+
+    use crate::contract::{execute, instantiate, query, query_minter, query_token_info};
+    use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+
+    fn mint(deps: DepsMut, sender: &str, amount: u128) -> Result<Response, ContractError> {
+        let msg = ExecuteMsg::Mint {
+            recipient: "rcpt".to_string(),
+            amount: Uint128::new(amount),
+        };
+        execute(deps, mock_env(), mock_info(sender, &[]), msg)
+    }
+
+    #[test]
+    fn minters_with_individual_caps() {
+        let mut deps = mock_dependencies();
+        let admin = "admin";
+        let msg = InstantiateMsg {
+            name: "Bridged".to_string(),
+            symbol: "BRG".to_string(),
+            decimals: 6,
+            initial_balances: vec![],
+            mint: Some(MinterResponse {
+                minter: admin.to_string(),
+                cap: Some(Uint128::new(5000)),
+            }),
+            marketing: None,
+            snapshots: false,
+            freezer: None,
+            pauser: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        // only the admin manages minters
+        let set_minter = |address: &str, cap: Option<u128>| ExecuteMsg::SetMinter {
+            address: address.to_string(),
+            cap: cap.map(Uint128::new),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bridge", &[]),
+            set_minter("bridge", None),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let err = mint(deps.as_mut(), "bridge", 100).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        let admin_info = mock_info(admin, &[]);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            admin_info.clone(),
+            set_minter("bridge", Some(1000)),
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            admin_info.clone(),
+            set_minter("rewards", None),
+        )
+        .unwrap();
+
+        // each minter is held to its own cap
+        mint(deps.as_mut(), "bridge", 800).unwrap();
+        let err = mint(deps.as_mut(), "bridge", 201).unwrap_err();
+        assert_eq!(err, ContractError::CannotExceedCap {});
+        mint(deps.as_mut(), "rewards", 3000).unwrap();
+        mint(deps.as_mut(), admin, 500).unwrap();
+
+        // and all of them to the cap on the total supply
+        let err = mint(deps.as_mut(), "rewards", 1000).unwrap_err();
+        assert_eq!(err, ContractError::CannotExceedCap {});
+        assert_eq!(
+            query_token_info(deps.as_ref()).unwrap().total_supply,
+            Uint128::new(4300)
+        );
+
+        let minters: MintersResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Minters {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            minters.minters,
+            vec![
+                MinterInfo {
+                    address: "bridge".to_string(),
+                    cap: Some(Uint128::new(1000)),
+                    minted: Uint128::new(800),
+                },
+                MinterInfo {
+                    address: "rewards".to_string(),
+                    cap: None,
+                    minted: Uint128::new(3000),
+                },
+            ]
+        );
+
+        // raising a cap keeps what was already minted
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            admin_info.clone(),
+            set_minter("bridge", Some(1500)),
+        )
+        .unwrap();
+        mint(deps.as_mut(), "bridge", 700).unwrap();
+        let err = mint(deps.as_mut(), "bridge", 1).unwrap_err();
+        assert_eq!(err, ContractError::CannotExceedCap {});
+
+        // removed minters lose their rights
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            admin_info.clone(),
+            ExecuteMsg::RemoveMinter {
+                address: "rewards".to_string(),
+            },
+        )
+        .unwrap();
+        let err = mint(deps.as_mut(), "rewards", 1).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            admin_info,
+            ExecuteMsg::RemoveMinter {
+                address: "rewards".to_string(),
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::NotMinter {});
+
+        // without an admin, nobody can mint anymore
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(admin, &[]),
+            ExecuteMsg::RenounceMinter {},
+        )
+        .unwrap();
+        assert_eq!(query_minter(deps.as_ref()).unwrap(), None);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(admin, &[]),
+            set_minter("bridge", Some(5000)),
+        )
+        .unwrap_err();
+        let err = mint(deps.as_mut(), "bridge", 1).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+    }
+}
@@ -1,11 +1,10 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{StdError, StdResult, Uint128};
-use cw20::{Cw20Coin, Logo, MinterResponse};
+use cosmwasm_std::{Binary, Decimal, StdError, StdResult, Timestamp, Uint128};
+use cw20::{Cw20Coin, Expiration, Logo, MinterResponse, UncheckedDenom};
+use cw_utils::Duration;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-pub use cw20::Cw20ExecuteMsg as ExecuteMsg;
-
 #[cw_serde]
 pub struct InstantiateMarketingInfo {
     pub project: Option<String>,
@@ -23,6 +22,17 @@ pub struct InstantiateMsg {
     pub initial_balances: Vec<Cw20Coin>,
     pub mint: Option<MinterResponse>,
     pub marketing: Option<InstantiateMarketingInfo>,
+    /// If set, the history of balances and total supply is recorded,
+    /// enabling the `BalanceAt` and `TotalSupplyAt` queries
+    #[serde(default)]
+    pub snapshots: bool,
+    /// If set, enables the freeze extension with this address as the freezer,
+    /// who can stop accounts from moving or approving their tokens
+    #[serde(default)]
+    pub freezer: Option<String>,
+    /// If set, this address can pause all token movements and minting during incidents
+    #[serde(default)]
+    pub pauser: Option<String>,
 }
 
 impl InstantiateMsg {
@@ -70,6 +80,329 @@ impl InstantiateMsg {
     }
 }
 
+/// ExecuteMsg is a superset of `cw20::Cw20ExecuteMsg`, adding the messages specific
+/// to this implementation. All spec messages keep the same wire format.
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// Transfer is a base message to move tokens to another account without triggering actions
+    Transfer { recipient: String, amount: Uint128 },
+    /// Burn is a base message to destroy tokens forever. The optional memo is
+    /// reported with the burn, e.g. to explain its reason
+    Burn {
+        amount: Uint128,
+        memo: Option<String>,
+    },
+    /// Send is a base message to transfer tokens to a contract and trigger an action
+    /// on the receiving contract.
+    Send {
+        contract: String,
+        amount: Uint128,
+        msg: Binary,
+    },
+    /// Like Send, but the receiving contract must accept the tokens by returning a
+    /// `Cw20ReceiveAck` as its response data, or the send is reverted. The payload of
+    /// the ack becomes the response data.
+    SendAcked {
+        contract: String,
+        amount: Uint128,
+        msg: Binary,
+    },
+    /// Like Send, but degrades to a plain Transfer when `contract` is not a contract,
+    /// instead of failing on the receive message
+    SendOrTransfer {
+        contract: String,
+        amount: Uint128,
+        msg: Binary,
+    },
+    /// Only with "approval" extension. Allows spender to access an additional amount tokens
+    /// from the owner's (env.sender) account. If expires is Some(), overwrites current allowance
+    /// expiration with this one.
+    IncreaseAllowance {
+        spender: String,
+        amount: Uint128,
+        expires: Option<Expiration>,
+    },
+    /// Only with "approval" extension. Lowers the spender's access of tokens
+    /// from the owner's (env.sender) account by amount. If expires is Some(), overwrites current
+    /// allowance expiration with this one.
+    DecreaseAllowance {
+        spender: String,
+        amount: Uint128,
+        expires: Option<Expiration>,
+    },
+    /// Only with "approval" extension. Limits how much of its allowance the spender can use per
+    /// period, on top of the allowance itself. Setting None removes the limit.
+    SetAllowancePeriod {
+        spender: String,
+        period: Option<AllowancePeriod>,
+    },
+    /// Only with "approval" extension. Transfers amount tokens from owner -> recipient
+    /// if `env.sender` has sufficient pre-approval.
+    TransferFrom {
+        owner: String,
+        recipient: String,
+        amount: Uint128,
+    },
+    /// Only with "approval" extension. Sends amount tokens from owner -> contract
+    /// if `env.sender` has sufficient pre-approval.
+    SendFrom {
+        owner: String,
+        contract: String,
+        amount: Uint128,
+        msg: Binary,
+    },
+    /// Only with "approval" extension. Destroys tokens forever.
+    /// The burner set with `UpdateBurner` does not need an allowance
+    BurnFrom { owner: String, amount: Uint128 },
+    /// Only with "permit" extension. Sets the allowance of spender on the owner's account to
+    /// amount (with no expiration), authorized by the owner's signature of the matching
+    /// `cw20::Cw20Permit` instead of `env.sender`. Anyone may submit it before the deadline, and
+    /// nonce must be the owner's current one, which is then incremented.
+    Permit {
+        owner: String,
+        spender: String,
+        amount: Uint128,
+        nonce: u64,
+        deadline: Expiration,
+        /// The owner's compressed secp256k1 public key, which must derive to the owner address
+        pubkey: Binary,
+        signature: Binary,
+    },
+    /// Only with "permit" extension. Transfers amount tokens from owner -> recipient,
+    /// authorized by the owner's signature of the matching `cw20::Cw20TransferPermit` instead
+    /// of an allowance. Anyone may submit it before the deadline, and nonce must be the owner's
+    /// current one, which is then incremented.
+    PermitTransferFrom {
+        owner: String,
+        recipient: String,
+        amount: Uint128,
+        nonce: u64,
+        deadline: Expiration,
+        /// The owner's compressed secp256k1 public key, which must derive to the owner address
+        pubkey: Binary,
+        signature: Binary,
+    },
+    /// Only with the "mintable" extension. If authorized, creates amount new tokens
+    /// and adds to the recipient balance.
+    Mint { recipient: String, amount: Uint128 },
+    /// Only with the "mintable" extension. The current minter may propose
+    /// a new minter, who only takes over once it calls `AcceptMinter`.
+    /// Setting None withdraws the proposal.
+    UpdateMinter { new_minter: Option<String> },
+    /// If proposed as the new minter with `UpdateMinter`, takes the role over
+    AcceptMinter {},
+    /// Only with the "mintable" extension. The current minter removes the
+    /// token's minter forever.
+    RenounceMinter {},
+    /// Only with the "marketing" extension. If authorized, updates marketing metadata.
+    /// Setting None/null for any of these will leave it unchanged.
+    /// Setting Some("") will clear this field on the contract storage
+    UpdateMarketing {
+        /// A URL pointing to the project behind this token.
+        project: Option<String>,
+        /// A longer description of the token and it's utility. Designed for tooltips or such
+        description: Option<String>,
+        /// The address (if any) who can update this data structure. A new address only
+        /// becomes the marketing account once it calls `AcceptMarketing`
+        marketing: Option<String>,
+    },
+    /// If proposed as the new "marketing" role with `UpdateMarketing`, takes it over
+    AcceptMarketing {},
+    /// If set as the "marketing" role on the contract, upload a new URL, SVG, or PNG for the token
+    UploadLogo(Logo),
+    /// Registers a contract to be notified of every transfer, send and burn
+    /// with a `Cw20TransferHookMsg`. Must be called by the minter
+    AddHook { addr: String },
+    /// Removes a transfer hook. Must be called by the minter
+    RemoveHook { addr: String },
+    /// Only with the "mintable" extension. If enabled, the minter is called with a
+    /// `Cw20MintCallbackMsg` after each of its mints, which fail if the callback does.
+    /// Must be called by the minter, which must be a contract to enable it
+    UpdateMintCallback { enabled: bool },
+    /// Moves tokens from the sender to many recipients at once, like a `Transfer`
+    /// to each of them, debiting the sender only once
+    TransferBatch { recipients: Vec<(String, Uint128)> },
+    /// Sends tokens from the sender to many contracts at once, like a `Send` to each
+    /// of them with the given message, debiting the sender only once
+    SendBatch {
+        contracts: Vec<(String, Uint128, Binary)>,
+    },
+    /// Only with the "mintable" extension. The current minter may limit how many tokens
+    /// can be minted per window, on top of the cap. Setting None removes the limit.
+    UpdateMintRateLimit { rate_limit: Option<MintRateLimit> },
+    /// Moves amount from the sender into escrow, where `recipient` can claim it with
+    /// `ClaimTransfer` until `expires`. Afterwards, the sender can get it back with
+    /// `ReclaimTransfer`. This guards large transfers against mistyped recipients.
+    TransferPending {
+        recipient: String,
+        amount: Uint128,
+        expires: Expiration,
+    },
+    /// Completes the pending transfer with the given id. Must be called by its recipient
+    /// before it expires
+    ClaimTransfer { id: u64 },
+    /// Returns the tokens of the pending transfer with the given id. Must be called by its
+    /// sender once it expired
+    ReclaimTransfer { id: u64 },
+    /// Only with the "mintable" extension. The current minter may set a rebaser, allowed to
+    /// rebase the supply, or remove it with None. Not available with balance snapshots.
+    UpdateRebaser { new_rebaser: Option<String> },
+    /// If set as the rebaser, sets the amount of tokens per share, which scales all balances
+    /// and the total supply at once. Allowances are not rebased.
+    Rebase { index: Decimal },
+    /// Gives the voting power of the whole balance of the sender to `delegatee`, which may be
+    /// the sender itself, or takes it back with None. Balances carry no votes until delegated.
+    /// Not available with a rebasing supply.
+    Delegate { delegatee: Option<String> },
+    /// Only with the "mintable" extension. The current minter may set a burner, allowed to `BurnFrom`
+    /// any account without an allowance (e.g. for bridge redemptions), or remove it with None.
+    UpdateBurner { new_burner: Option<String> },
+    /// Only with the "mintable" extension. The current minter may set an emission schedule, starting
+    /// now, or remove it with None. This replaces the current schedule, dropping any vested tokens
+    /// not minted yet.
+    SetMintSchedule { schedule: Option<MintScheduleMsg> },
+    /// Only with the "mintable" extension. Mints all tokens unlocked by the emission schedule so far
+    /// to its recipient. Anyone can call it.
+    MintVested {},
+    /// Only with the "mintable" extension. The current minter may lock tokens of an account
+    /// (e.g. a team allocation), such that it cannot move its balance below the amount still
+    /// locked, or remove the lock with None. Replaces any previous lock of the account.
+    SetVestingLock {
+        address: String,
+        lock: Option<VestingLock>,
+    },
+    /// Only with the "mintable" extension. The current minter may grant mint rights to
+    /// another address, up to cap tokens in total (None for no limit other than the token cap).
+    /// If the address already has them, only its cap is updated.
+    SetMinter {
+        address: String,
+        cap: Option<Uint128>,
+    },
+    /// Only with the "mintable" extension. The current minter may revoke the mint rights
+    /// granted with `SetMinter`.
+    RemoveMinter { address: String },
+    /// Only with the "freeze" extension. If authorized, stops the account from transferring,
+    /// sending, burning or approving its tokens, and its allowances from being used.
+    Freeze { address: String },
+    /// Only with the "freeze" extension. If authorized, lifts the freeze of the account.
+    Unfreeze { address: String },
+    /// Only with the "freeze" extension. The current freezer may hand the role over to another address.
+    UpdateFreezer { new_freezer: String },
+    /// Only with the "pausable" extension. If authorized, halts all transfers, sends, burns and
+    /// mints until the given expiration, or until unpaused if None. Overwrites any running pause.
+    Pause { until: Option<Expiration> },
+    /// Only with the "pausable" extension. If authorized, lifts the pause.
+    Unpause {},
+    /// Only with the "pausable" extension. The current pauser may set a new pauser.
+    /// Setting the pauser to None will remove the ability to pause forever.
+    UpdatePauser { new_pauser: Option<String> },
+    /// Only with the "transfer fee" extension. The current minter may take a fee from all
+    /// transfers and sends, paid to the given recipient. Setting None removes the fee.
+    UpdateTransferFee { fee: Option<TransferFeeConfig> },
+    /// Only with the "transfer fee" extension. The current minter may exempt transfers from
+    /// or to the given address from the fee, and its burns from the burn tax, or remove the exemption.
+    SetFeeExempt { address: String, exempt: bool },
+    /// Only with the "transfer fee" extension. The current minter may redirect part of all
+    /// burns to the given treasury instead of destroying it. Setting None removes the tax.
+    UpdateBurnTax { tax: Option<BurnTaxConfig> },
+    /// Only with the "wallet limits" extension. The current minter may cap the balance of every
+    /// address and the amount of every transfer. Setting None removes the limits.
+    UpdateWalletLimits { limits: Option<WalletLimits> },
+    /// Only with the "wallet limits" extension. The current minter may exempt the given address
+    /// from the wallet limits, or remove the exemption.
+    SetLimitExempt { address: String, exempt: bool },
+    /// The current minter may send the whole balance of a native coin or another cw20 token
+    /// held by this contract, e.g. sent to it by mistake, to the recipient.
+    /// This token itself cannot be recovered.
+    RecoverStuck {
+        asset: UncheckedDenom,
+        recipient: String,
+    },
+    /// Only during a legacy import started by `MigrateMsg`. The importer sets the given
+    /// balances and allowances, overwriting any previous value for the same accounts, so a
+    /// batch can safely be sent again. At most `MAX_IMPORT_BATCH` entries per message.
+    ImportBalances {
+        balances: Vec<Cw20Coin>,
+        allowances: Vec<ImportedAllowance>,
+    },
+    /// Only during a legacy import. The importer ends it, which fails unless the total
+    /// supply matches the one expected when the import was started.
+    FinishImport {},
+    /// Only during a change of decimals started by `MigrateMsg`, which halts every other
+    /// message. Anyone can rescale the next `MAX_RESCALE_BATCH` balances, then allowances,
+    /// then escrowed transfers, until all are done.
+    ContinueRescale {},
+    /// Only while the holders of a token migrated from a version without them are being
+    /// indexed. Anyone can index the holders among the next `MAX_BACKFILL_BATCH` balances,
+    /// until all are done.
+    ContinueHoldersBackfill {},
+}
+
+#[cw_serde]
+pub struct TransferFeeConfig {
+    /// Fee in basis points of the amount transferred, must be below 10000
+    pub bps: u16,
+    pub recipient: String,
+}
+
+#[cw_serde]
+pub struct BurnTaxConfig {
+    /// Tax in basis points of the amount burned, at most 10000
+    pub bps: u16,
+    pub treasury: String,
+}
+
+#[cw_serde]
+pub struct WalletLimits {
+    /// Most tokens any address not exempt may hold, checked when it receives or mints tokens
+    pub max_balance: Option<Uint128>,
+    /// Most tokens moved by a single transfer, unless the sender or recipient is exempt
+    pub max_transfer: Option<Uint128>,
+}
+
+/// An allowance exported from the old token
+#[cw_serde]
+pub struct ImportedAllowance {
+    pub owner: String,
+    pub spender: String,
+    pub allowance: Uint128,
+    pub expires: Expiration,
+}
+
+/// Unlocks `amount_per_period` tokens for the recipient at the end of each of the
+/// `num_periods` periods
+#[cw_serde]
+pub struct MintScheduleMsg {
+    pub recipient: String,
+    pub amount_per_period: Uint128,
+    pub period: Duration,
+    pub num_periods: u64,
+}
+
+/// Keeps `amount` tokens of an account locked until `start`, then unlocks them linearly until `end`
+#[cw_serde]
+pub struct VestingLock {
+    pub amount: Uint128,
+    pub start: Timestamp,
+    pub end: Timestamp,
+}
+
+/// Limits spending an allowance to `max_amount` tokens per window. A window starts with the first
+/// spend after the previous one is over, and lasts for `period`.
+#[cw_serde]
+pub struct AllowancePeriod {
+    pub max_amount: Uint128,
+    pub period: Duration,
+}
+
+/// Limits minting to `max_amount` tokens per window. A window starts with the first mint
+/// after the previous one is over, and lasts for `window`.
+#[cw_serde]
+pub struct MintRateLimit {
+    pub max_amount: Uint128,
+    pub window: Duration,
+}
+
 #[cw_serde]
 #[derive(QueryResponses)]
 pub enum QueryMsg {
@@ -80,13 +413,42 @@ pub enum QueryMsg {
     #[returns(cw20::TokenInfoResponse)]
     TokenInfo {},
     /// Only with "mintable" extension.
-    /// Returns who can mint and the hard cap on maximum tokens after minting.
-    #[returns(cw20::MinterResponse)]
+    /// Returns who can mint and the hard cap on maximum tokens after minting,
+    /// along with the proposed new minter, if any.
+    #[returns(Option<CurrentMinterResponse>)]
     Minter {},
+    /// Only with "mintable" extension.
+    /// Returns the mint rate limit, if any, and how much can still be minted in the current window.
+    #[returns(MintRateLimitResponse)]
+    MintRateLimit {},
+    /// Only with "mintable" extension.
+    /// Returns the emission schedule, if any, with the tokens vested and still to be minted.
+    #[returns(Option<MintScheduleResponse>)]
+    MintSchedule {},
+    /// Returns the vesting lock of the given address, if any, with the amounts currently
+    /// locked and unlocked out of its balance.
+    #[returns(VestingLockResponse)]
+    VestingLock { address: String },
+    /// Only with "mintable" extension.
+    /// Returns the additional minters with their caps and how much they minted. Supports pagination.
+    #[returns(MintersResponse)]
+    Minters {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
     /// Only with "allowance" extension.
     /// Returns how much spender can use from owner account, 0 if unset.
     #[returns(cw20::AllowanceResponse)]
     Allowance { owner: String, spender: String },
+    /// Only with "allowance" extension.
+    /// Returns the limit per period of the allowance, if any, and how much spender can still use
+    /// in the current period.
+    #[returns(AllowancePeriodResponse)]
+    AllowancePeriod { owner: String, spender: String },
+    /// Only with "permit" extension.
+    /// Returns the nonce the next permit signed by owner must use.
+    #[returns(cw20::NonceResponse)]
+    Nonce { owner: String },
     /// Only with "enumerable" extension (and "allowances")
     /// Returns all allowances this owner has approved. Supports pagination.
     #[returns(cw20::AllAllowancesResponse)]
@@ -110,6 +472,13 @@ pub enum QueryMsg {
         start_after: Option<String>,
         limit: Option<u32>,
     },
+    /// Only with "enumerable" extension
+    /// Returns all accounts that have balances along with their balance. Supports pagination.
+    #[returns(AllAccountsWithBalancesResponse)]
+    AllAccountsWithBalances {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
     /// Only with "marketing" extension
     /// Returns more metadata on the contract to display in the client:
     /// - description, logo, project url, etc.
@@ -120,10 +489,351 @@ pub enum QueryMsg {
     /// contract.
     #[returns(cw20::DownloadLogoResponse)]
     DownloadLogo {},
+    /// Only with "marketing" extension
+    /// Returns the address proposed as the new marketing account, if any.
+    #[returns(PendingMarketingResponse)]
+    PendingMarketing {},
+    /// Only with "freeze" extension.
+    /// Returns who can freeze accounts, None if the extension is disabled.
+    #[returns(FreezerResponse)]
+    Freezer {},
+    /// Only with "freeze" extension.
+    /// Returns all frozen accounts. Supports pagination.
+    #[returns(FrozenAccountsResponse)]
+    FrozenAccounts {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Only with "pausable" extension.
+    /// Returns whether the token is paused, and until when.
+    #[returns(cw_controllers::PausedResponse)]
+    Paused {},
+    /// Only with "pausable" extension.
+    /// Returns who can pause the token.
+    #[returns(cw_controllers::AdminResponse)]
+    Pauser {},
+    /// Only with "transfer fee" extension.
+    /// Returns the fee taken from transfers, if any.
+    #[returns(Option<TransferFeeResponse>)]
+    TransferFee {},
+    /// Only with "transfer fee" extension.
+    /// Returns the tax redirected to the treasury on burns, if any.
+    #[returns(Option<BurnTaxResponse>)]
+    BurnTax {},
+    /// Only with "transfer fee" extension.
+    /// Returns all addresses exempt from the transfer fee and burn tax. Supports pagination.
+    #[returns(FeeExemptResponse)]
+    FeeExempt {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Only with "wallet limits" extension.
+    /// Returns the limits on balances and transfers, if any.
+    #[returns(Option<WalletLimits>)]
+    WalletLimits {},
+    /// Only with "wallet limits" extension.
+    /// Returns all addresses exempt from the wallet limits. Supports pagination.
+    #[returns(LimitExemptResponse)]
+    LimitExempt {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Returns the pending transfers sent by the given address, by ascending id.
+    #[returns(PendingTransfersResponse)]
+    PendingTransfersBySender {
+        sender: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Returns the pending transfers to the given address, by ascending id.
+    #[returns(PendingTransfersResponse)]
+    PendingTransfersByRecipient {
+        recipient: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Returns the number of accounts holding a non-zero balance.
+    #[returns(NumHoldersResponse)]
+    NumHolders {},
+    /// Returns the accounts with a non-zero balance by descending balance, ties broken by
+    /// descending address. Pass the last holder returned as `start_after` for the next page.
+    #[returns(TopHoldersResponse)]
+    TopHolders {
+        start_after: Option<AccountBalance>,
+        limit: Option<u32>,
+    },
+    /// Returns who can rebase the supply, if anyone, and the current amount of tokens per share.
+    #[returns(RebaseInfoResponse)]
+    RebaseInfo {},
+    /// Returns who can burn from any account without an allowance, if anyone.
+    #[returns(BurnerResponse)]
+    Burner {},
+    /// Returns the total amount of tokens ever burned.
+    #[returns(TotalBurnedResponse)]
+    TotalBurned {},
+    /// Returns the amount of tokens ever burned from the given address, 0 if unset.
+    #[returns(BurnedByResponse)]
+    BurnedBy { address: String },
+    /// Returns the legacy import in progress, if any.
+    #[returns(Option<ImportStatusResponse>)]
+    ImportStatus {},
+    /// Returns the change of decimals in progress, if any.
+    #[returns(Option<RescaleStatusResponse>)]
+    RescaleStatus {},
+    /// Shows all registered transfer hooks.
+    #[returns(cw_controllers::HooksResponse)]
+    Hooks {},
+    /// Returns whether the minter gets a callback after each of its mints.
+    #[returns(MintCallbackResponse)]
+    MintCallback {},
+    /// Only with snapshots enabled. Returns the balance of the given address
+    /// at the beginning of the block with the given height, 0 if unset.
+    #[returns(cw20::BalanceResponse)]
+    BalanceAt { address: String, height: u64 },
+    /// Only with snapshots enabled. Returns the total supply at the beginning
+    /// of the block with the given height.
+    #[returns(TotalSupplyResponse)]
+    TotalSupplyAt { height: u64 },
+    /// Returns who the given address delegated its votes to, if anyone.
+    #[returns(DelegateResponse)]
+    Delegate { address: String },
+    /// Returns the votes currently delegated to the given address.
+    #[returns(VotesResponse)]
+    Votes { address: String },
+    /// Returns the votes delegated to the given address at the beginning of the block with
+    /// the given height.
+    #[returns(VotesResponse)]
+    VotesAt { address: String, height: u64 },
+}
+
+#[cw_serde]
+pub struct DelegateResponse {
+    pub delegate: Option<String>,
+}
+
+#[cw_serde]
+pub struct VotesResponse {
+    pub votes: Uint128,
+}
+
+#[cw_serde]
+pub struct TotalSupplyResponse {
+    pub total_supply: Uint128,
+}
+
+#[cw_serde]
+pub struct AllowancePeriodResponse {
+    pub period: Option<AllowancePeriod>,
+    /// How much can still be spent in the current period, None if there is no limit
+    pub remaining: Option<Uint128>,
+    /// End of the current period, None if no period is running
+    pub window_ends: Option<Expiration>,
+}
+
+#[cw_serde]
+pub struct MintRateLimitResponse {
+    pub rate_limit: Option<MintRateLimit>,
+    /// How many tokens can still be minted in the current window, None if there is no limit
+    pub remaining: Option<Uint128>,
+    /// End of the current window, None if no window is running
+    pub window_ends: Option<Expiration>,
+}
+
+#[cw_serde]
+pub struct MinterInfo {
+    pub address: String,
+    pub cap: Option<Uint128>,
+    pub minted: Uint128,
+}
+
+#[cw_serde]
+pub struct MintScheduleResponse {
+    pub recipient: String,
+    pub amount_per_period: Uint128,
+    pub period: Duration,
+    pub num_periods: u64,
+    pub start: Expiration,
+    /// Tokens minted so far
+    pub minted: Uint128,
+    /// Tokens unlocked but not minted yet
+    pub vested: Uint128,
+    /// Tokens still to be minted, vested or not
+    pub remaining: Uint128,
+}
+
+#[cw_serde]
+pub struct AccountBalance {
+    pub address: String,
+    pub balance: Uint128,
+}
+
+#[cw_serde]
+pub struct AllAccountsWithBalancesResponse {
+    pub accounts: Vec<AccountBalance>,
+}
+
+#[cw_serde]
+pub struct MintersResponse {
+    pub minters: Vec<MinterInfo>,
+}
+
+/// Like `cw20::MinterResponse`, along with the minter proposed by `UpdateMinter`
+#[cw_serde]
+pub struct CurrentMinterResponse {
+    pub minter: String,
+    pub cap: Option<Uint128>,
+    /// left out while nothing is pending, so the response matches the cw20 spec
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pending_minter: Option<String>,
+}
+
+#[cw_serde]
+pub struct PendingMarketingResponse {
+    pub pending_marketing: Option<String>,
+}
+
+#[cw_serde]
+pub struct FreezerResponse {
+    pub freezer: Option<String>,
+}
+
+#[cw_serde]
+pub struct FrozenAccountsResponse {
+    pub accounts: Vec<String>,
+}
+
+#[cw_serde]
+pub struct TransferFeeResponse {
+    pub bps: u16,
+    pub recipient: String,
+}
+
+#[cw_serde]
+pub struct BurnTaxResponse {
+    pub bps: u16,
+    pub treasury: String,
+}
+
+#[cw_serde]
+pub struct FeeExemptResponse {
+    pub addresses: Vec<String>,
+}
+
+#[cw_serde]
+pub struct LimitExemptResponse {
+    pub addresses: Vec<String>,
+}
+
+#[cw_serde]
+pub struct PendingTransferInfo {
+    pub id: u64,
+    pub sender: String,
+    pub recipient: String,
+    pub amount: Uint128,
+    pub expires: Expiration,
+}
+
+#[cw_serde]
+pub struct PendingTransfersResponse {
+    pub transfers: Vec<PendingTransferInfo>,
+}
+
+#[cw_serde]
+pub struct NumHoldersResponse {
+    pub num_holders: u64,
+    /// False while the holders of a migrated token are being backfilled, when only part of
+    /// them are counted
+    pub complete: bool,
+}
+
+#[cw_serde]
+pub struct TopHoldersResponse {
+    pub holders: Vec<AccountBalance>,
+    /// False while the holders of a migrated token are being backfilled, when only part of
+    /// them are listed
+    pub complete: bool,
+}
+
+#[cw_serde]
+pub struct RebaseInfoResponse {
+    pub rebaser: Option<String>,
+    /// Amount of tokens per share, 1 until the first rebase
+    pub index: Decimal,
+}
+
+#[cw_serde]
+pub struct VestingLockResponse {
+    pub lock: Option<VestingLock>,
+    pub locked: Uint128,
+    /// Balance that can be moved right now
+    pub unlocked: Uint128,
+}
+
+#[cw_serde]
+pub struct MintCallbackResponse {
+    pub enabled: bool,
+}
+
+#[cw_serde]
+pub struct BurnerResponse {
+    pub burner: Option<String>,
+}
+
+#[cw_serde]
+pub struct TotalBurnedResponse {
+    pub total_burned: Uint128,
+}
+
+#[cw_serde]
+pub struct BurnedByResponse {
+    pub burned: Uint128,
+}
+
+#[cw_serde]
+pub struct ImportStatusResponse {
+    pub importer: String,
+    pub expected_total_supply: Uint128,
+    pub total_supply: Uint128,
+}
+
+#[cw_serde]
+pub struct RescaleStatusResponse {
+    pub from_decimals: u8,
+    pub to_decimals: u8,
+    /// What is being rescaled, in this order
+    pub phase: RescalePhase,
+}
+
+#[cw_serde]
+pub enum RescalePhase {
+    Balances,
+    Allowances,
+    PendingTransfers,
+    AllowancePeriods,
+    VestingLocks,
+    Minters,
+    BurnedBy,
 }
 
 #[derive(Serialize, Deserialize, JsonSchema)]
-pub struct MigrateMsg {}
+pub struct MigrateMsg {
+    /// If set, starts a legacy import, to bring over the balances and allowances
+    /// exported from an old cw20 instance with `ExecuteMsg::ImportBalances`
+    #[serde(default)]
+    pub import: Option<ImportConfig>,
+    /// If set, changes the decimals of the token, then halts it until all balances and
+    /// allowances are rescaled to them with `ExecuteMsg::ContinueRescale`
+    #[serde(default)]
+    pub decimals: Option<u8>,
+}
+
+#[cw_serde]
+pub struct ImportConfig {
+    /// The only address allowed to import balances and finish the import
+    pub importer: String,
+    /// Total supply of the old token, checked when finishing the import
+    pub expected_total_supply: Uint128,
+}
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,479 @@
+use cosmwasm_std::{attr, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Uint128};
+use cw20::{AllowanceResponse, Cw20Permit, Cw20TransferPermit, Expiration, NonceResponse};
+
+use crate::allowance_period::remove_allowance_period;
+use crate::error::ContractError;
+use crate::fee::{collect_transfer_fee, fee_attributes, split_transfer_fee};
+use crate::freeze::ensure_not_frozen;
+use crate::hooks::transfer_hook_msgs;
+use crate::snapshots::update_balance;
+use crate::state::{ALLOWANCES, ALLOWANCES_SPENDER, NONCES, PAUSE};
+use crate::vesting::ensure_unlocked;
+use crate::wallet_limits::ensure_within_wallet_limits;
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute_permit(
+    deps: DepsMut,
+    env: Env,
+    owner: String,
+    spender: String,
+    amount: Uint128,
+    nonce: u64,
+    deadline: Expiration,
+    pubkey: Binary,
+    signature: Binary,
+) -> Result<Response, ContractError> {
+    let owner_addr = deps.api.addr_validate(&owner)?;
+    let spender_addr = deps.api.addr_validate(&spender)?;
+    if spender_addr == owner_addr {
+        return Err(ContractError::CannotSetOwnAccount {});
+    }
+    ensure_not_frozen(deps.storage, &owner_addr)?;
+
+    let permit = Cw20Permit {
+        chain_id: env.block.chain_id.clone(),
+        contract: env.contract.address.to_string(),
+        owner,
+        spender,
+        amount,
+        nonce,
+        deadline,
+    };
+    NONCES.use_permit(
+        deps.api,
+        deps.storage,
+        &env.block,
+        &owner_addr,
+        nonce,
+        &deadline,
+        permit.sign_bytes()?.as_slice(),
+        &pubkey,
+        &signature,
+    )?;
+
+    // the permit sets the allowance, like an EIP-2612 approval
+    if amount.is_zero() {
+        ALLOWANCES.remove(deps.storage, (&owner_addr, &spender_addr));
+        ALLOWANCES_SPENDER.remove(deps.storage, (&spender_addr, &owner_addr));
+        remove_allowance_period(deps.storage, &owner_addr, &spender_addr);
+    } else {
+        let allowance = AllowanceResponse {
+            allowance: amount,
+            expires: Expiration::Never {},
+        };
+        ALLOWANCES.save(deps.storage, (&owner_addr, &spender_addr), &allowance)?;
+        ALLOWANCES_SPENDER.save(deps.storage, (&spender_addr, &owner_addr), &allowance)?;
+    }
+
+    let res = Response::new().add_attributes(vec![
+        attr("action", "permit"),
+        attr("owner", permit.owner),
+        attr("spender", permit.spender),
+        attr("amount", amount),
+        attr("nonce", nonce.to_string()),
+    ]);
+    Ok(res)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute_permit_transfer_from(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    owner: String,
+    recipient: String,
+    amount: Uint128,
+    nonce: u64,
+    deadline: Expiration,
+    pubkey: Binary,
+    signature: Binary,
+) -> Result<Response, ContractError> {
+    if amount == Uint128::zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+    let owner_addr = deps.api.addr_validate(&owner)?;
+    let rcpt_addr = deps.api.addr_validate(&recipient)?;
+    ensure_not_frozen(deps.storage, &owner_addr)?;
+    ensure_unlocked(deps.storage, &env.block, &owner_addr, amount)?;
+    PAUSE.assert_not_paused(deps.storage, &env.block)?;
+    let (net, fee) = split_transfer_fee(deps.storage, &owner_addr, &rcpt_addr, amount)?;
+    ensure_within_wallet_limits(deps.storage, Some(&owner_addr), &rcpt_addr, amount, net)?;
+
+    let permit = Cw20TransferPermit {
+        chain_id: env.block.chain_id.clone(),
+        contract: env.contract.address.to_string(),
+        owner,
+        recipient,
+        amount,
+        nonce,
+        deadline,
+    };
+    NONCES.use_permit(
+        deps.api,
+        deps.storage,
+        &env.block,
+        &owner_addr,
+        nonce,
+        &deadline,
+        permit.sign_bytes()?.as_slice(),
+        &pubkey,
+        &signature,
+    )?;
+
+    update_balance(
+        deps.storage,
+        env.block.height,
+        &owner_addr,
+        |balance: Option<Uint128>| -> StdResult<_> {
+            Ok(balance.unwrap_or_default().checked_sub(amount)?)
+        },
+    )?;
+    update_balance(
+        deps.storage,
+        env.block.height,
+        &rcpt_addr,
+        |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + net) },
+    )?;
+
+    let mut hooks = transfer_hook_msgs(deps.storage, &owner_addr, Some(&rcpt_addr), net)?;
+    hooks.extend(collect_transfer_fee(
+        deps.storage,
+        env.block.height,
+        &owner_addr,
+        fee.as_ref(),
+    )?);
+    let res = Response::new()
+        .add_submessages(hooks)
+        .add_attributes(vec![
+            attr("action", "permit_transfer_from"),
+            attr("from", permit.owner),
+            attr("to", permit.recipient),
+            attr("by", info.sender),
+            attr("amount", amount),
+            attr("nonce", nonce.to_string()),
+        ])
+        .add_attributes(fee_attributes(net, fee.as_ref()));
+    Ok(res)
+}
+
+pub fn query_nonce(deps: Deps, owner: String) -> StdResult<NonceResponse> {
+    let owner_addr = deps.api.addr_validate(&owner)?;
+    let nonce = NONCES.nonce(deps.storage, &owner_addr)?;
+    Ok(NonceResponse { nonce })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{from_binary, DepsMut, StdError};
+    use cw20::Cw20Coin;
+    use cw_controllers::{pubkey_to_address, PermitError};
+    use k256::ecdsa::signature::Signer;
+    use k256::ecdsa::{Signature, SigningKey};
+
+    use crate::contract::{execute, instantiate, query, query_balance};
+    use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+
+    struct Signer256 {
+        key: SigningKey,
+        addr: String,
+    }
+
+    impl Signer256 {
+        fn new(seed: u8) -> Self {
+            let key = SigningKey::from_bytes(&[seed; 32]).unwrap();
+            let pubkey = key.verifying_key().to_bytes();
+            let addr = pubkey_to_address(&pubkey, "cosmos").unwrap();
+            Signer256 { key, addr }
+        }
+
+        fn permit(
+            &self,
+            env: &Env,
+            spender: &str,
+            amount: u128,
+            nonce: u64,
+            deadline: Expiration,
+        ) -> ExecuteMsg {
+            let permit = Cw20Permit {
+                chain_id: env.block.chain_id.clone(),
+                contract: env.contract.address.to_string(),
+                owner: self.addr.clone(),
+                spender: spender.to_string(),
+                amount: Uint128::new(amount),
+                nonce,
+                deadline,
+            };
+            let signature: Signature = self.key.sign(permit.sign_bytes().unwrap().as_slice());
+            ExecuteMsg::Permit {
+                owner: permit.owner,
+                spender: permit.spender,
+                amount: permit.amount,
+                nonce,
+                deadline,
+                pubkey: Binary::from(self.key.verifying_key().to_bytes().as_slice()),
+                signature: Binary::from(signature.as_ref()),
+            }
+        }
+
+        fn transfer_permit(
+            &self,
+            env: &Env,
+            recipient: &str,
+            amount: u128,
+            nonce: u64,
+            deadline: Expiration,
+        ) -> ExecuteMsg {
+            let permit = Cw20TransferPermit {
+                chain_id: env.block.chain_id.clone(),
+                contract: env.contract.address.to_string(),
+                owner: self.addr.clone(),
+                recipient: recipient.to_string(),
+                amount: Uint128::new(amount),
+                nonce,
+                deadline,
+            };
+            let signature: Signature = self.key.sign(permit.sign_bytes().unwrap().as_slice());
+            ExecuteMsg::PermitTransferFrom {
+                owner: permit.owner,
+                recipient: permit.recipient,
+                amount: permit.amount,
+                nonce,
+                deadline,
+                pubkey: Binary::from(self.key.verifying_key().to_bytes().as_slice()),
+                signature: Binary::from(signature.as_ref()),
+            }
+        }
+    }
+
+    fn do_instantiate(deps: DepsMut, addr: &str, amount: Uint128) {
+        let instantiate_msg = InstantiateMsg {
+            name: "Auto Gen".to_string(),
+            symbol: "AUTO".to_string(),
+            decimals: 3,
+            initial_balances: vec![Cw20Coin {
+                address: addr.into(),
+                amount,
+            }],
+            mint: None,
+            marketing: None,
+            snapshots: false,
+            freezer: None,
+            pauser: None,
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps, mock_env(), info, instantiate_msg).unwrap();
+    }
+
+    fn nonce(deps: Deps, owner: &str) -> u64 {
+        let msg = QueryMsg::Nonce {
+            owner: owner.to_string(),
+        };
+        let res: NonceResponse = from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+        res.nonce
+    }
+
+    #[test]
+    fn permit_grants_allowance() {
+        let mut deps = mock_dependencies();
+        let owner = Signer256::new(7);
+        let spender = "spender";
+        let env = mock_env();
+        do_instantiate(deps.as_mut(), &owner.addr, Uint128::new(1000));
+        assert_eq!(nonce(deps.as_ref(), &owner.addr), 0);
+
+        // anyone may submit a signed permit
+        let deadline = Expiration::AtHeight(env.block.height + 10);
+        let msg = owner.permit(&env, spender, 300, 0, deadline);
+        let info = mock_info("relayer", &[]);
+        execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+        assert_eq!(nonce(deps.as_ref(), &owner.addr), 1);
+
+        let allowance = crate::allowances::query_allowance(
+            deps.as_ref(),
+            owner.addr.clone(),
+            spender.to_string(),
+        )
+        .unwrap();
+        assert_eq!(
+            allowance,
+            AllowanceResponse {
+                allowance: Uint128::new(300),
+                expires: Expiration::Never {},
+            }
+        );
+
+        // it cannot be replayed
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Permit(PermitError::InvalidNonce { expected: 1 })
+        );
+
+        // the spender can use it
+        let msg = ExecuteMsg::TransferFrom {
+            owner: owner.addr.clone(),
+            recipient: "recipient".to_string(),
+            amount: Uint128::new(300),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info(spender, &[]), msg).unwrap();
+        let balance = query_balance(deps.as_ref(), "recipient".to_string()).unwrap();
+        assert_eq!(balance.balance, Uint128::new(300));
+
+        // a zero permit clears the allowance
+        let msg = owner.permit(&env, spender, 0, 1, deadline);
+        execute(deps.as_mut(), env, info, msg).unwrap();
+        let allowance = crate::allowances::query_allowance(
+            deps.as_ref(),
+            owner.addr.clone(),
+            spender.to_string(),
+        )
+        .unwrap();
+        assert_eq!(allowance, AllowanceResponse::default());
+    }
+
+    #[test]
+    fn invalid_permits_rejected() {
+        let mut deps = mock_dependencies();
+        let owner = Signer256::new(7);
+        let other = Signer256::new(9);
+        let spender = "spender";
+        let env = mock_env();
+        let info = mock_info("relayer", &[]);
+        do_instantiate(deps.as_mut(), &owner.addr, Uint128::new(1000));
+        let deadline = Expiration::AtHeight(env.block.height + 10);
+
+        // past the deadline
+        let msg = owner.permit(
+            &env,
+            spender,
+            300,
+            0,
+            Expiration::AtHeight(env.block.height),
+        );
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert_eq!(err, ContractError::Permit(PermitError::Expired {}));
+
+        // nonce from the future
+        let msg = owner.permit(&env, spender, 300, 1, deadline);
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Permit(PermitError::InvalidNonce { expected: 0 })
+        );
+
+        // signed by someone else's key
+        let mut msg = other.permit(&env, spender, 300, 0, deadline);
+        if let ExecuteMsg::Permit { owner: o, .. } = &mut msg {
+            *o = owner.addr.clone();
+        }
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert_eq!(err, ContractError::Permit(PermitError::InvalidSigner {}));
+
+        // tampered amount
+        let mut msg = owner.permit(&env, spender, 300, 0, deadline);
+        if let ExecuteMsg::Permit { amount, .. } = &mut msg {
+            *amount = Uint128::new(1000);
+        }
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert_eq!(err, ContractError::Permit(PermitError::InvalidSignature {}));
+
+        // signed for another token contract
+        let mut other_env = env.clone();
+        other_env.contract.address = cosmwasm_std::Addr::unchecked("other_token");
+        let msg = owner.permit(&other_env, spender, 300, 0, deadline);
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert_eq!(err, ContractError::Permit(PermitError::InvalidSignature {}));
+
+        assert_eq!(nonce(deps.as_ref(), &owner.addr), 0);
+    }
+
+    #[test]
+    fn permit_transfer_from_moves_tokens() {
+        let mut deps = mock_dependencies();
+        let owner = Signer256::new(7);
+        let other = Signer256::new(9);
+        let env = mock_env();
+        let info = mock_info("relayer", &[]);
+        do_instantiate(deps.as_mut(), &owner.addr, Uint128::new(1000));
+        let deadline = Expiration::AtHeight(env.block.height + 10);
+
+        // the relayer submits the transfer, no allowance needed
+        let msg = owner.transfer_permit(&env, "recipient", 400, 0, deadline);
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+        assert_eq!(res.attributes[0], attr("action", "permit_transfer_from"));
+        assert_eq!(res.attributes[3], attr("by", "relayer"));
+        let balance = query_balance(deps.as_ref(), "recipient".to_string()).unwrap();
+        assert_eq!(balance.balance, Uint128::new(400));
+        let balance = query_balance(deps.as_ref(), owner.addr.clone()).unwrap();
+        assert_eq!(balance.balance, Uint128::new(600));
+
+        // it cannot be replayed, and shares the nonce with allowance permits
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Permit(PermitError::InvalidNonce { expected: 1 })
+        );
+        let msg = owner.permit(&env, "spender", 100, 1, deadline);
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        assert_eq!(nonce(deps.as_ref(), &owner.addr), 2);
+
+        // the recipient is part of the signed payload
+        let msg = match owner.transfer_permit(&env, "recipient", 400, 2, deadline) {
+            ExecuteMsg::PermitTransferFrom {
+                owner,
+                amount,
+                nonce,
+                deadline,
+                pubkey,
+                signature,
+                ..
+            } => ExecuteMsg::PermitTransferFrom {
+                owner,
+                recipient: "thief".to_string(),
+                amount,
+                nonce,
+                deadline,
+                pubkey,
+                signature,
+            },
+            _ => unreachable!(),
+        };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert_eq!(err, ContractError::Permit(PermitError::InvalidSignature {}));
+
+        // only the owner can sign, before the deadline, and for no more than the balance
+        let msg = match other.transfer_permit(&env, "recipient", 400, 2, deadline) {
+            ExecuteMsg::PermitTransferFrom {
+                recipient,
+                amount,
+                nonce,
+                deadline,
+                pubkey,
+                signature,
+                ..
+            } => ExecuteMsg::PermitTransferFrom {
+                owner: owner.addr.clone(),
+                recipient,
+                amount,
+                nonce,
+                deadline,
+                pubkey,
+                signature,
+            },
+            _ => unreachable!(),
+        };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert_eq!(err, ContractError::Permit(PermitError::InvalidSigner {}));
+        let mut late = env.clone();
+        late.block.height += 10;
+        let msg = owner.transfer_permit(&late, "recipient", 400, 2, deadline);
+        let err = execute(deps.as_mut(), late, info.clone(), msg).unwrap_err();
+        assert_eq!(err, ContractError::Permit(PermitError::Expired {}));
+        let msg = owner.transfer_permit(&env, "recipient", 601, 2, deadline);
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::Std(StdError::Overflow { .. })));
+    }
+}
@@ -0,0 +1,281 @@
+use cosmwasm_std::{
+    Addr, Decimal, Deps, DepsMut, MessageInfo, Response, StdResult, Storage, Uint128,
+};
+
+use crate::error::ContractError;
+use crate::hooks::assert_minter;
+use crate::msg::RebaseInfoResponse;
+use crate::state::{BALANCES, REBASER, REBASE_INDEX, SNAPSHOTS, TOKEN_INFO};
+use crate::votes::has_delegations;
+
+/// Returns the amount of tokens per share, or None if the supply was never rebased,
+/// in which case balances are stored as plain amounts
+pub fn rebase_index(storage: &dyn Storage) -> StdResult<Option<Decimal>> {
+    REBASE_INDEX.may_load(storage)
+}
+
+/// Converts stored shares to the token amount they are worth, rounding down
+pub fn to_amount(shares: Uint128, index: Decimal) -> Uint128 {
+    shares * index
+}
+
+/// Converts a token amount to the shares it is worth, rounding down
+pub fn to_shares(amount: Uint128, index: Decimal) -> Uint128 {
+    amount.multiply_ratio(Decimal::one().atomics(), index.atomics())
+}
+
+/// Returns the balance of `addr` in tokens
+pub fn load_balance(storage: &dyn Storage, addr: &Addr) -> StdResult<Uint128> {
+    let stored = BALANCES.may_load(storage, addr)?.unwrap_or_default();
+    Ok(match rebase_index(storage)? {
+        Some(index) => to_amount(stored, index),
+        None => stored,
+    })
+}
+
+/// Sets the rebaser, or removes the role (with `None`), which leaves the index as it is.
+/// Must be called by the minter.
+pub fn execute_update_rebaser(
+    deps: DepsMut,
+    info: MessageInfo,
+    new_rebaser: Option<String>,
+) -> Result<Response, ContractError> {
+    assert_minter(deps.storage, &info.sender)?;
+    // the balance history would be recorded in shares, at an index that is not kept
+    if SNAPSHOTS.may_load(deps.storage)?.unwrap_or_default() {
+        return Err(ContractError::RebaseWithSnapshots {});
+    }
+    if has_delegations(deps.storage) {
+        return Err(ContractError::RebaseWithVotes {});
+    }
+
+    let rebaser = new_rebaser
+        .as_deref()
+        .map(|addr| deps.api.addr_validate(addr))
+        .transpose()?;
+    match &rebaser {
+        Some(rebaser) => REBASER.save(deps.storage, rebaser)?,
+        None => REBASER.remove(deps.storage),
+    }
+
+    let res = Response::new()
+        .add_attribute("action", "update_rebaser")
+        .add_attribute(
+            "new_rebaser",
+            rebaser
+                .map(Addr::into_string)
+                .unwrap_or_else(|| "None".to_string()),
+        );
+    Ok(res)
+}
+
+/// Sets the amount of tokens per share, scaling all balances and the total supply at once.
+/// Must be called by the rebaser.
+pub fn execute_rebase(
+    deps: DepsMut,
+    info: MessageInfo,
+    index: Decimal,
+) -> Result<Response, ContractError> {
+    match REBASER.may_load(deps.storage)? {
+        Some(rebaser) if rebaser == info.sender => (),
+        _ => return Err(ContractError::Unauthorized {}),
+    }
+    if index.is_zero() {
+        return Err(ContractError::InvalidRebaseIndex {});
+    }
+
+    // balances were stored as amounts, and thus as shares at an index of 1, until the first rebase
+    let previous = rebase_index(deps.storage)?.unwrap_or_else(Decimal::one);
+    let mut config = TOKEN_INFO.load(deps.storage)?;
+    config.total_supply = to_amount(to_shares(config.total_supply, previous), index);
+    TOKEN_INFO.save(deps.storage, &config)?;
+    REBASE_INDEX.save(deps.storage, &index)?;
+
+    let res = Response::new()
+        .add_attribute("action", "rebase")
+        .add_attribute("previous_index", previous.to_string())
+        .add_attribute("index", index.to_string())
+        .add_attribute("total_supply", config.total_supply);
+    Ok(res)
+}
+
+pub fn query_rebase_info(deps: Deps) -> StdResult<RebaseInfoResponse> {
+    let rebaser = REBASER.may_load(deps.storage)?.map(Addr::into_string);
+    let index = rebase_index(deps.storage)?.unwrap_or_else(Decimal::one);
+    Ok(RebaseInfoResponse { rebaser, index })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{from_binary, DepsMut};
+    use cw20::{Cw20Coin, MinterResponse};
+
+    use crate::allowances::query_allowance;
+    use crate::contract::{execute, instantiate, query, query_balance, query_token_info};
+    use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+
+    fn balance(deps: Deps, address: &str) -> u128 {
+        query_balance(deps, address.to_string())
+            .unwrap()
+            .balance
+            .u128()
+    }
+
+    fn total_supply(deps: Deps) -> u128 {
+        query_token_info(deps).unwrap().total_supply.u128()
+    }
+
+    fn exec(deps: DepsMut, sender: &str, msg: ExecuteMsg) -> Result<Response, ContractError> {
+        execute(deps, mock_env(), mock_info(sender, &[]), msg)
+    }
+
+    fn do_instantiate(deps: DepsMut, snapshots: bool) {
+        let msg = InstantiateMsg {
+            name: "Staked Atom".to_string(),
+            symbol: "STATOM".to_string(),
+            decimals: 6,
+            initial_balances: vec![
+                Cw20Coin {
+                    address: "alice".to_string(),
+                    amount: Uint128::new(1000),
+                },
+                Cw20Coin {
+                    address: "bob".to_string(),
+                    amount: Uint128::new(3000),
+                },
+            ],
+            mint: Some(MinterResponse {
+                minter: "minter".to_string(),
+                cap: None,
+            }),
+            marketing: None,
+            snapshots,
+            freezer: None,
+            pauser: None,
+        };
+        instantiate(deps, mock_env(), mock_info("creator", &[]), msg).unwrap();
+    }
+
+    #[test]
+    fn rebase_scales_all_balances() {
+        let mut deps = mock_dependencies();
+        do_instantiate(deps.as_mut(), false);
+
+        let set_rebaser = ExecuteMsg::UpdateRebaser {
+            new_rebaser: Some("oracle".to_string()),
+        };
+        let err = exec(deps.as_mut(), "oracle", set_rebaser.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        exec(deps.as_mut(), "minter", set_rebaser).unwrap();
+
+        let rebase = |index: &str| ExecuteMsg::Rebase {
+            index: index.parse().unwrap(),
+        };
+        let err = exec(deps.as_mut(), "minter", rebase("1.1")).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let err = exec(deps.as_mut(), "oracle", rebase("0")).unwrap_err();
+        assert_eq!(err, ContractError::InvalidRebaseIndex {});
+
+        // staking rewards accrue to all holders at once
+        exec(deps.as_mut(), "oracle", rebase("1.1")).unwrap();
+        assert_eq!(balance(deps.as_ref(), "alice"), 1100);
+        assert_eq!(balance(deps.as_ref(), "bob"), 3300);
+        assert_eq!(total_supply(deps.as_ref()), 4400);
+        let info: RebaseInfoResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::RebaseInfo {}).unwrap())
+                .unwrap();
+        assert_eq!(
+            info,
+            RebaseInfoResponse {
+                rebaser: Some("oracle".to_string()),
+                index: "1.1".parse().unwrap(),
+            }
+        );
+
+        // transfers, mints and burns are in rebased amounts, allowances are not rebased
+        exec(
+            deps.as_mut(),
+            "bob",
+            ExecuteMsg::Transfer {
+                recipient: "carl".to_string(),
+                amount: Uint128::new(550),
+            },
+        )
+        .unwrap();
+        exec(
+            deps.as_mut(),
+            "minter",
+            ExecuteMsg::Mint {
+                recipient: "carl".to_string(),
+                amount: Uint128::new(110),
+            },
+        )
+        .unwrap();
+        exec(
+            deps.as_mut(),
+            "alice",
+            ExecuteMsg::IncreaseAllowance {
+                spender: "carl".to_string(),
+                amount: Uint128::new(330),
+                expires: None,
+            },
+        )
+        .unwrap();
+        exec(
+            deps.as_mut(),
+            "carl",
+            ExecuteMsg::BurnFrom {
+                owner: "alice".to_string(),
+                amount: Uint128::new(110),
+            },
+        )
+        .unwrap();
+        assert_eq!(balance(deps.as_ref(), "alice"), 990);
+        assert_eq!(balance(deps.as_ref(), "bob"), 2750);
+        assert_eq!(balance(deps.as_ref(), "carl"), 660);
+        assert_eq!(total_supply(deps.as_ref()), 4400);
+
+        // a slashing rebases down, except for allowances
+        exec(deps.as_mut(), "oracle", rebase("0.55")).unwrap();
+        assert_eq!(balance(deps.as_ref(), "alice"), 495);
+        assert_eq!(balance(deps.as_ref(), "bob"), 1375);
+        assert_eq!(balance(deps.as_ref(), "carl"), 330);
+        assert_eq!(total_supply(deps.as_ref()), 2200);
+        assert_eq!(
+            query_allowance(deps.as_ref(), "alice".to_string(), "carl".to_string())
+                .unwrap()
+                .allowance,
+            Uint128::new(220)
+        );
+
+        // removing the rebaser keeps the last index
+        exec(
+            deps.as_mut(),
+            "minter",
+            ExecuteMsg::UpdateRebaser { new_rebaser: None },
+        )
+        .unwrap();
+        let err = exec(deps.as_mut(), "oracle", rebase("1")).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        assert_eq!(balance(deps.as_ref(), "alice"), 495);
+    }
+
+    #[test]
+    fn no_rebasing_with_snapshots() {
+        let mut deps = mock_dependencies();
+        do_instantiate(deps.as_mut(), true);
+
+        let err = exec(
+            deps.as_mut(),
+            "minter",
+            ExecuteMsg::UpdateRebaser {
+                new_rebaser: Some("oracle".to_string()),
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::RebaseWithSnapshots {});
+    }
+}
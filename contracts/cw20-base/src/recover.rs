@@ -0,0 +1,169 @@
+use cosmwasm_std::{
+    to_binary, BankMsg, Coin, CosmosMsg, DepsMut, Env, MessageInfo, Response, WasmMsg,
+};
+use cw20::{Cw20Contract, Cw20ExecuteMsg, UncheckedDenom};
+
+use crate::error::ContractError;
+use crate::hooks::assert_minter;
+
+/// Sends the whole balance of a native coin or another cw20 token held by this contract,
+/// most likely sent to it by mistake, to the recipient. Must be called by the minter.
+/// The token managed by this contract cannot be recovered, as its balances belong to the holders.
+pub fn execute_recover_stuck(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    asset: UncheckedDenom,
+    recipient: String,
+) -> Result<Response, ContractError> {
+    assert_minter(deps.storage, &info.sender)?;
+    let recipient = deps.api.addr_validate(&recipient)?;
+
+    let (msg, denom, amount): (CosmosMsg, _, _) = match asset {
+        UncheckedDenom::Native(denom) => {
+            let balance = deps.querier.query_balance(&env.contract.address, &denom)?;
+            let msg = BankMsg::Send {
+                to_address: recipient.to_string(),
+                amount: vec![Coin {
+                    denom: denom.clone(),
+                    amount: balance.amount,
+                }],
+            };
+            (msg.into(), denom, balance.amount)
+        }
+        UncheckedDenom::Cw20(contract) => {
+            let contract = deps.api.addr_validate(&contract)?;
+            if contract == env.contract.address {
+                return Err(ContractError::CannotRecoverOwnToken {});
+            }
+            let amount =
+                Cw20Contract(contract.clone()).balance(&deps.querier, &env.contract.address)?;
+            let msg = WasmMsg::Execute {
+                contract_addr: contract.to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: recipient.to_string(),
+                    amount,
+                })?,
+                funds: vec![],
+            };
+            (msg.into(), format!("cw20:{}", contract), amount)
+        }
+    };
+    if amount.is_zero() {
+        return Err(ContractError::NothingToRecover {});
+    }
+
+    let res = Response::new()
+        .add_message(msg)
+        .add_attribute("action", "recover_stuck")
+        .add_attribute("denom", denom)
+        .add_attribute("amount", amount)
+        .add_attribute("recipient", recipient);
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::testing::{mock_dependencies_with_balance, mock_env, mock_info};
+    use cosmwasm_std::{
+        coin, coins, from_binary, ContractResult, SystemResult, Uint128, WasmQuery,
+    };
+    use cw20::{BalanceResponse, Cw20QueryMsg, MinterResponse};
+
+    use crate::contract::{execute, instantiate};
+    use crate::msg::{ExecuteMsg, InstantiateMsg};
+
+    fn recover(
+        deps: DepsMut,
+        sender: &str,
+        asset: UncheckedDenom,
+    ) -> Result<Response, ContractError> {
+        let msg = ExecuteMsg::RecoverStuck {
+            asset,
+            recipient: "victim".to_string(),
+        };
+        execute(deps, mock_env(), mock_info(sender, &[]), msg)
+    }
+
+    #[test]
+    fn recover_stuck_tokens() {
+        let mut deps = mock_dependencies_with_balance(&coins(500, "ucosm"));
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { contract_addr, msg } if contract_addr == "other-token" => {
+                let msg: Cw20QueryMsg = from_binary(msg).unwrap();
+                assert_eq!(
+                    msg,
+                    Cw20QueryMsg::Balance {
+                        address: mock_env().contract.address.to_string()
+                    }
+                );
+                let res = BalanceResponse {
+                    balance: Uint128::new(700),
+                };
+                SystemResult::Ok(ContractResult::Ok(to_binary(&res).unwrap()))
+            }
+            _ => panic!("unexpected query: {:?}", query),
+        });
+        let msg = InstantiateMsg {
+            name: "Cash Token".to_string(),
+            symbol: "CASH".to_string(),
+            decimals: 6,
+            initial_balances: vec![],
+            mint: Some(MinterResponse {
+                minter: "minter".to_string(),
+                cap: None,
+            }),
+            marketing: None,
+            snapshots: false,
+            freezer: None,
+            pauser: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let native = UncheckedDenom::Native("ucosm".to_string());
+        let err = recover(deps.as_mut(), "victim", native.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        let res = recover(deps.as_mut(), "minter", native).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "victim".to_string(),
+                amount: vec![coin(500, "ucosm")],
+            })
+        );
+        let err = recover(
+            deps.as_mut(),
+            "minter",
+            UncheckedDenom::Native("uatom".to_string()),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::NothingToRecover {});
+
+        let res = recover(
+            deps.as_mut(),
+            "minter",
+            UncheckedDenom::Cw20("other-token".to_string()),
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "other-token".to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "victim".to_string(),
+                    amount: Uint128::new(700),
+                })
+                .unwrap(),
+                funds: vec![],
+            })
+        );
+
+        // the balances of this token belong to its holders
+        let own = UncheckedDenom::Cw20(mock_env().contract.address.to_string());
+        let err = recover(deps.as_mut(), "minter", own).unwrap_err();
+        assert_eq!(err, ContractError::CannotRecoverOwnToken {});
+    }
+}
@@ -0,0 +1,603 @@
+use cosmwasm_std::{
+    Addr, Deps, DepsMut, Env, Order, Response, StdError, StdResult, Storage, Uint128,
+};
+use cw_storage_plus::{Bound, Map};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::error::ContractError;
+use crate::msg::{RescalePhase, RescaleStatusResponse};
+use crate::snapshots::{record_total_supply, update_balance};
+use crate::state::{
+    Rescale, RescaleCursor, ALLOWANCES, ALLOWANCES_SPENDER, ALLOWANCE_PERIODS, ALLOWANCE_WINDOWS,
+    BALANCES, BURNED_BY, MINTERS, MINT_RATE_LIMIT, MINT_SCHEDULE, MINT_WINDOW, PENDING_TRANSFERS,
+    RESCALE, TOKEN_INFO, TOTAL_BURNED, VESTING_LOCKS,
+};
+
+/// Maximum number of balances, allowances or other entries rescaled by a single `ContinueRescale`,
+/// to keep each message well within the gas limit
+pub const MAX_RESCALE_BATCH: usize = 100;
+
+fn factor(rescale: &Rescale) -> Uint128 {
+    let diff = rescale.from_decimals.abs_diff(rescale.to_decimals);
+    Uint128::new(10).pow(diff as u32)
+}
+
+/// Converts an amount with the old decimals to the new ones, rounding down.
+/// Larger amounts saturate, so unlimited allowances stay unlimited.
+pub fn scale(rescale: &Rescale, amount: Uint128) -> Uint128 {
+    if rescale.to_decimals > rescale.from_decimals {
+        amount.saturating_mul(factor(rescale))
+    } else {
+        amount / factor(rescale)
+    }
+}
+
+/// Rescales the next batch of entries of a map by account. Returns how many there were, and
+/// the last one if there may be more.
+fn rescale_by_account<'a, T>(
+    storage: &mut dyn Storage,
+    map: Map<'a, &'a Addr, T>,
+    start_after: Option<&'a Addr>,
+    convert: impl Fn(T) -> T,
+) -> StdResult<(usize, Option<Addr>)>
+where
+    T: Serialize + DeserializeOwned,
+{
+    let entries = map
+        .range(
+            storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(MAX_RESCALE_BATCH)
+        .collect::<StdResult<Vec<_>>>()?;
+    let count = entries.len();
+    let mut last = None;
+    for (addr, value) in entries {
+        map.save(storage, &addr, &convert(value))?;
+        last = Some(addr);
+    }
+    Ok((count, last.filter(|_| count == MAX_RESCALE_BATCH)))
+}
+
+/// Fails while the balances are being rescaled, as they are not all in the same unit yet
+pub fn ensure_not_rescaling(storage: &dyn Storage) -> Result<(), ContractError> {
+    if RESCALE.may_load(storage)?.is_some() {
+        return Err(ContractError::RescaleInProgress {});
+    }
+    Ok(())
+}
+
+/// Starts changing the decimals of the token, called on migration. The mint cap, limit and
+/// schedule and the total burned are rescaled at once, while balances, allowances and other
+/// amounts by account are then rescaled with `ContinueRescale`, and the token stays halted until
+/// they all are.
+pub fn start_rescale(deps: DepsMut, decimals: u8) -> Result<Response, ContractError> {
+    ensure_not_rescaling(deps.storage)?;
+    let mut config = TOKEN_INFO.load(deps.storage)?;
+    if decimals > 18 || decimals == config.decimals {
+        return Err(ContractError::InvalidRescale {});
+    }
+
+    let rescale = Rescale {
+        from_decimals: config.decimals,
+        to_decimals: decimals,
+        cursor: RescaleCursor::Balances { start_after: None },
+    };
+    // every balance fits once rescaled if their total does
+    if rescale.to_decimals > rescale.from_decimals {
+        config
+            .total_supply
+            .checked_mul(factor(&rescale))
+            .map_err(StdError::overflow)?;
+    }
+    config.decimals = decimals;
+    if let Some(mint) = config.mint.as_mut() {
+        mint.cap = mint.cap.map(|cap| scale(&rescale, cap));
+    }
+    TOKEN_INFO.save(deps.storage, &config)?;
+    if let Some(mut limit) = MINT_RATE_LIMIT.may_load(deps.storage)? {
+        limit.max_amount = scale(&rescale, limit.max_amount);
+        MINT_RATE_LIMIT.save(deps.storage, &limit)?;
+    }
+    if let Some(mut window) = MINT_WINDOW.may_load(deps.storage)? {
+        window.minted = scale(&rescale, window.minted);
+        MINT_WINDOW.save(deps.storage, &window)?;
+    }
+    if let Some(mut schedule) = MINT_SCHEDULE.may_load(deps.storage)? {
+        schedule.amount_per_period = scale(&rescale, schedule.amount_per_period);
+        schedule.minted = scale(&rescale, schedule.minted);
+        MINT_SCHEDULE.save(deps.storage, &schedule)?;
+    }
+    if let Some(burned) = TOTAL_BURNED.may_load(deps.storage)? {
+        TOTAL_BURNED.save(deps.storage, &scale(&rescale, burned))?;
+    }
+    RESCALE.save(deps.storage, &rescale)?;
+
+    let res = Response::new()
+        .add_attribute("action", "start_rescale")
+        .add_attribute("from_decimals", rescale.from_decimals.to_string())
+        .add_attribute("to_decimals", rescale.to_decimals.to_string());
+    Ok(res)
+}
+
+/// Rescales the next batch of balances, then of allowances, escrowed transfers, allowance periods,
+/// vesting locks, minters and amounts burned by account, and ends the rescale once they are all
+/// done. Anyone can call it, until the rescale is over.
+pub fn execute_continue_rescale(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let mut rescale = RESCALE
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoRescaleInProgress {})?;
+
+    let mut res = Response::new().add_attribute("action", "continue_rescale");
+    rescale.cursor = match rescale.cursor.clone() {
+        RescaleCursor::Balances { start_after } => {
+            let start = start_after.as_ref().map(Bound::exclusive);
+            let accounts = BALANCES
+                .keys(deps.storage, start, None, Order::Ascending)
+                .take(MAX_RESCALE_BATCH)
+                .collect::<StdResult<Vec<_>>>()?;
+
+            // balances may round down, so the total supply is kept as their sum
+            let mut config = TOKEN_INFO.load(deps.storage)?;
+            for addr in &accounts {
+                let mut before = Uint128::zero();
+                let after = update_balance(
+                    deps.storage,
+                    env.block.height,
+                    addr,
+                    |balance| -> StdResult<_> {
+                        before = balance.unwrap_or_default();
+                        Ok(scale(&rescale, before))
+                    },
+                )?;
+                config.total_supply = config.total_supply - before + after;
+            }
+            TOKEN_INFO.save(deps.storage, &config)?;
+            record_total_supply(deps.storage, env.block.height, config.total_supply)?;
+
+            let count = accounts.len();
+            res = res
+                .add_attribute("balances", count.to_string())
+                .add_attribute("total_supply", config.total_supply);
+            // a short batch was the last one
+            match accounts.into_iter().last() {
+                Some(last) if count == MAX_RESCALE_BATCH => RescaleCursor::Balances {
+                    start_after: Some(last),
+                },
+                _ => RescaleCursor::Allowances { start_after: None },
+            }
+        }
+        RescaleCursor::Allowances { start_after } => {
+            let start = start_after
+                .as_ref()
+                .map(|(owner, spender)| Bound::exclusive((owner, spender)));
+            let allowances = ALLOWANCES
+                .range(deps.storage, start, None, Order::Ascending)
+                .take(MAX_RESCALE_BATCH)
+                .collect::<StdResult<Vec<_>>>()?;
+            for ((owner, spender), mut allowance) in allowances.iter().cloned() {
+                allowance.allowance = scale(&rescale, allowance.allowance);
+                ALLOWANCES.save(deps.storage, (&owner, &spender), &allowance)?;
+                ALLOWANCES_SPENDER.save(deps.storage, (&spender, &owner), &allowance)?;
+            }
+
+            let count = allowances.len();
+            res = res.add_attribute("allowances", count.to_string());
+            match allowances.into_iter().last() {
+                Some((last, _)) if count == MAX_RESCALE_BATCH => RescaleCursor::Allowances {
+                    start_after: Some(last),
+                },
+                _ => RescaleCursor::PendingTransfers { start_after: None },
+            }
+        }
+        RescaleCursor::PendingTransfers { start_after } => {
+            // the escrow balance of this contract was rescaled with the others, and rounding
+            // down each transfer never claims more than it holds
+            let start = start_after.map(Bound::exclusive);
+            let transfers = PENDING_TRANSFERS
+                .range(deps.storage, start, None, Order::Ascending)
+                .take(MAX_RESCALE_BATCH)
+                .collect::<StdResult<Vec<_>>>()?;
+            for (id, mut pending) in transfers.iter().cloned() {
+                pending.amount = scale(&rescale, pending.amount);
+                PENDING_TRANSFERS.save(deps.storage, id, &pending)?;
+            }
+
+            let count = transfers.len();
+            res = res.add_attribute("pending_transfers", count.to_string());
+            match transfers.into_iter().last() {
+                Some((last, _)) if count == MAX_RESCALE_BATCH => RescaleCursor::PendingTransfers {
+                    start_after: Some(last),
+                },
+                _ => RescaleCursor::AllowancePeriods { start_after: None },
+            }
+        }
+        RescaleCursor::AllowancePeriods { start_after } => {
+            // windows only exist along with the period of their allowance
+            let start = start_after
+                .as_ref()
+                .map(|(owner, spender)| Bound::exclusive((owner, spender)));
+            let periods = ALLOWANCE_PERIODS
+                .range(deps.storage, start, None, Order::Ascending)
+                .take(MAX_RESCALE_BATCH)
+                .collect::<StdResult<Vec<_>>>()?;
+            for ((owner, spender), mut period) in periods.iter().cloned() {
+                let key = (&owner, &spender);
+                period.max_amount = scale(&rescale, period.max_amount);
+                ALLOWANCE_PERIODS.save(deps.storage, key, &period)?;
+                if let Some(mut window) = ALLOWANCE_WINDOWS.may_load(deps.storage, key)? {
+                    window.spent = scale(&rescale, window.spent);
+                    ALLOWANCE_WINDOWS.save(deps.storage, key, &window)?;
+                }
+            }
+
+            let count = periods.len();
+            res = res.add_attribute("allowance_periods", count.to_string());
+            match periods.into_iter().last() {
+                Some((last, _)) if count == MAX_RESCALE_BATCH => RescaleCursor::AllowancePeriods {
+                    start_after: Some(last),
+                },
+                _ => RescaleCursor::VestingLocks { start_after: None },
+            }
+        }
+        RescaleCursor::VestingLocks { start_after } => {
+            let (count, last) = rescale_by_account(
+                deps.storage,
+                VESTING_LOCKS,
+                start_after.as_ref(),
+                |mut lock| {
+                    lock.amount = scale(&rescale, lock.amount);
+                    lock
+                },
+            )?;
+            res = res.add_attribute("vesting_locks", count.to_string());
+            match last {
+                Some(last) => RescaleCursor::VestingLocks {
+                    start_after: Some(last),
+                },
+                None => RescaleCursor::Minters { start_after: None },
+            }
+        }
+        RescaleCursor::Minters { start_after } => {
+            let (count, last) = rescale_by_account(
+                deps.storage,
+                MINTERS,
+                start_after.as_ref(),
+                |mut allowance| {
+                    allowance.cap = allowance.cap.map(|cap| scale(&rescale, cap));
+                    allowance.minted = scale(&rescale, allowance.minted);
+                    allowance
+                },
+            )?;
+            res = res.add_attribute("minters", count.to_string());
+            match last {
+                Some(last) => RescaleCursor::Minters {
+                    start_after: Some(last),
+                },
+                None => RescaleCursor::BurnedBy { start_after: None },
+            }
+        }
+        RescaleCursor::BurnedBy { start_after } => {
+            let (count, last) =
+                rescale_by_account(deps.storage, BURNED_BY, start_after.as_ref(), |burned| {
+                    scale(&rescale, burned)
+                })?;
+            res = res.add_attribute("burned_by", count.to_string());
+            match last {
+                Some(last) => RescaleCursor::BurnedBy {
+                    start_after: Some(last),
+                },
+                None => {
+                    RESCALE.remove(deps.storage);
+                    return Ok(res.add_attribute("finished", "true"));
+                }
+            }
+        }
+    };
+    RESCALE.save(deps.storage, &rescale)?;
+    Ok(res.add_attribute("finished", "false"))
+}
+
+pub fn query_rescale_status(deps: Deps) -> StdResult<Option<RescaleStatusResponse>> {
+    Ok(RESCALE
+        .may_load(deps.storage)?
+        .map(|rescale| RescaleStatusResponse {
+            from_decimals: rescale.from_decimals,
+            to_decimals: rescale.to_decimals,
+            phase: match rescale.cursor {
+                RescaleCursor::Balances { .. } => RescalePhase::Balances,
+                RescaleCursor::Allowances { .. } => RescalePhase::Allowances,
+                RescaleCursor::PendingTransfers { .. } => RescalePhase::PendingTransfers,
+                RescaleCursor::AllowancePeriods { .. } => RescalePhase::AllowancePeriods,
+                RescaleCursor::VestingLocks { .. } => RescalePhase::VestingLocks,
+                RescaleCursor::Minters { .. } => RescalePhase::Minters,
+                RescaleCursor::BurnedBy { .. } => RescalePhase::BurnedBy,
+            },
+        }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::from_binary;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MOCK_CONTRACT_ADDR};
+    use cw20::{Cw20Coin, Expiration, MinterResponse};
+    use cw_utils::Duration;
+
+    use crate::allowances::query_allowance;
+    use crate::contract::{execute, instantiate, migrate, query, query_balance, query_token_info};
+    use crate::msg::{
+        AllowancePeriod, ExecuteMsg, InstantiateMsg, MigrateMsg, MintRateLimit, QueryMsg,
+        VestingLock,
+    };
+    use crate::state::{AllowanceWindow, MintSchedule, MintWindow, MinterAllowance};
+
+    fn setup(deps: DepsMut, initial_balances: Vec<Cw20Coin>) {
+        let msg = InstantiateMsg {
+            name: "Misprinted".to_string(),
+            symbol: "MSP".to_string(),
+            decimals: 6,
+            initial_balances,
+            mint: Some(MinterResponse {
+                minter: "minter".to_string(),
+                cap: Some(Uint128::new(1_000_000_000)),
+            }),
+            marketing: None,
+            snapshots: false,
+            freezer: None,
+            pauser: None,
+        };
+        instantiate(deps, mock_env(), mock_info("creator", &[]), msg).unwrap();
+    }
+
+    fn rescale_to(deps: DepsMut, decimals: u8) -> Result<Response, ContractError> {
+        let msg = MigrateMsg {
+            import: None,
+            decimals: Some(decimals),
+        };
+        migrate(deps, mock_env(), msg)
+    }
+
+    fn continue_rescale(deps: DepsMut) -> Response {
+        let msg = ExecuteMsg::ContinueRescale {};
+        execute(deps, mock_env(), mock_info("anyone", &[]), msg).unwrap()
+    }
+
+    /// Continues the rescale until it is over, returning the last response
+    fn finish_rescale(mut deps: DepsMut) -> Response {
+        loop {
+            let res = continue_rescale(deps.branch());
+            if res.attributes.last().unwrap().value == "true" {
+                return res;
+            }
+        }
+    }
+
+    fn rescale_status(deps: Deps) -> Option<RescaleStatusResponse> {
+        from_binary(&query(deps, mock_env(), QueryMsg::RescaleStatus {}).unwrap()).unwrap()
+    }
+
+    fn balance(deps: Deps, address: &str) -> Uint128 {
+        query_balance(deps, address.to_string()).unwrap().balance
+    }
+
+    #[test]
+    fn decimals_are_added() {
+        let mut deps = mock_dependencies();
+        let initial_balances = vec![
+            Cw20Coin {
+                address: "alice".to_string(),
+                amount: Uint128::new(1_500_000),
+            },
+            Cw20Coin {
+                address: "bob".to_string(),
+                amount: Uint128::new(500_000),
+            },
+        ];
+        setup(deps.as_mut(), initial_balances);
+        let allow = ExecuteMsg::IncreaseAllowance {
+            spender: "bob".to_string(),
+            amount: Uint128::new(250_000),
+            expires: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), allow).unwrap();
+        let escrow = ExecuteMsg::TransferPending {
+            recipient: "carl".to_string(),
+            amount: Uint128::new(100_000),
+            expires: Expiration::AtHeight(mock_env().block.height + 100),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), escrow).unwrap();
+
+        let err = rescale_to(deps.as_mut(), 6).unwrap_err();
+        assert_eq!(err, ContractError::InvalidRescale {});
+        rescale_to(deps.as_mut(), 8).unwrap();
+        let err = rescale_to(deps.as_mut(), 9).unwrap_err();
+        assert_eq!(err, ContractError::RescaleInProgress {});
+        let info = query_token_info(deps.as_ref()).unwrap();
+        assert_eq!(info.decimals, 8);
+        assert_eq!(
+            rescale_status(deps.as_ref()),
+            Some(RescaleStatusResponse {
+                from_decimals: 6,
+                to_decimals: 8,
+                phase: RescalePhase::Balances,
+            })
+        );
+
+        // the token is halted meanwhile
+        let transfer = ExecuteMsg::Transfer {
+            recipient: "bob".to_string(),
+            amount: Uint128::new(1),
+        };
+        let err =
+            execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), transfer).unwrap_err();
+        assert_eq!(err, ContractError::RescaleInProgress {});
+
+        // balances, then allowances
+        continue_rescale(deps.as_mut());
+        assert_eq!(balance(deps.as_ref(), "alice"), Uint128::new(140_000_000));
+        assert_eq!(
+            rescale_status(deps.as_ref()).unwrap().phase,
+            RescalePhase::Allowances
+        );
+        continue_rescale(deps.as_mut());
+        assert_eq!(
+            rescale_status(deps.as_ref()).unwrap().phase,
+            RescalePhase::PendingTransfers
+        );
+        finish_rescale(deps.as_mut());
+        assert_eq!(rescale_status(deps.as_ref()), None);
+
+        let info = query_token_info(deps.as_ref()).unwrap();
+        assert_eq!(info.total_supply, Uint128::new(200_000_000));
+        let allowance = query_allowance(deps.as_ref(), "alice".to_string(), "bob".to_string());
+        assert_eq!(allowance.unwrap().allowance, Uint128::new(25_000_000));
+        let cap = crate::contract::query_minter(deps.as_ref())
+            .unwrap()
+            .unwrap()
+            .cap;
+        assert_eq!(cap, Some(Uint128::new(100_000_000_000)));
+
+        // and moves again once done, escrowed transfers included
+        let claim = ExecuteMsg::ClaimTransfer { id: 0 };
+        execute(deps.as_mut(), mock_env(), mock_info("carl", &[]), claim).unwrap();
+        assert_eq!(balance(deps.as_ref(), "carl"), Uint128::new(10_000_000));
+        assert_eq!(balance(deps.as_ref(), MOCK_CONTRACT_ADDR), Uint128::zero());
+    }
+
+    #[test]
+    fn decimals_are_removed_in_batches() {
+        let mut deps = mock_dependencies();
+        let initial_balances = (0..=MAX_RESCALE_BATCH)
+            .map(|i| Cw20Coin {
+                address: format!("addr{:04}", i),
+                amount: Uint128::new(1_234_567),
+            })
+            .collect();
+        setup(deps.as_mut(), initial_balances);
+
+        rescale_to(deps.as_mut(), 4).unwrap();
+        let res = continue_rescale(deps.as_mut());
+        assert!(res.attributes.contains(&("balances", "100").into()));
+        assert_eq!(balance(deps.as_ref(), "addr0000"), Uint128::new(12_345));
+        assert_eq!(balance(deps.as_ref(), "addr0100"), Uint128::new(1_234_567));
+        let state = RESCALE.load(deps.as_ref().storage).unwrap();
+        assert_eq!(
+            state.cursor,
+            RescaleCursor::Balances {
+                start_after: Some(Addr::unchecked("addr0099"))
+            }
+        );
+        let res = continue_rescale(deps.as_mut());
+        assert!(res.attributes.contains(&("balances", "1").into()));
+        assert_eq!(balance(deps.as_ref(), "addr0100"), Uint128::new(12_345));
+
+        // the dust rounded away leaves the supply
+        let total_supply = query_token_info(deps.as_ref()).unwrap().total_supply;
+        assert_eq!(total_supply, Uint128::new(12_345 * 101));
+        finish_rescale(deps.as_mut());
+        assert_eq!(rescale_status(deps.as_ref()), None);
+    }
+
+    #[test]
+    fn other_amounts_are_rescaled() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut(), vec![]);
+        let alice = Addr::unchecked("alice");
+        let bob = Addr::unchecked("bob");
+        let storage = deps.as_mut().storage;
+        let limit = MintRateLimit {
+            max_amount: Uint128::new(5_000_000),
+            window: Duration::Height(100),
+        };
+        MINT_RATE_LIMIT.save(storage, &limit).unwrap();
+        let window = MintWindow {
+            ends: Expiration::AtHeight(mock_env().block.height + 100),
+            minted: Uint128::new(1_234_567),
+        };
+        MINT_WINDOW.save(storage, &window).unwrap();
+        let schedule = MintSchedule {
+            recipient: alice.clone(),
+            amount_per_period: Uint128::new(1_000_000),
+            period: Duration::Height(10),
+            num_periods: 4,
+            start: Expiration::AtHeight(mock_env().block.height),
+            minted: Uint128::new(2_000_000),
+        };
+        MINT_SCHEDULE.save(storage, &schedule).unwrap();
+        TOTAL_BURNED
+            .save(storage, &Uint128::new(3_000_000))
+            .unwrap();
+        BURNED_BY
+            .save(storage, &bob, &Uint128::new(3_000_000))
+            .unwrap();
+        let lock = VestingLock {
+            amount: Uint128::new(700_000),
+            start: mock_env().block.time,
+            end: mock_env().block.time.plus_seconds(1000),
+        };
+        VESTING_LOCKS.save(storage, &alice, &lock).unwrap();
+        let minter = MinterAllowance {
+            cap: Some(Uint128::new(9_000_000)),
+            minted: Uint128::new(1_000_000),
+        };
+        MINTERS.save(storage, &bob, &minter).unwrap();
+        let period = AllowancePeriod {
+            max_amount: Uint128::new(400_000),
+            period: Duration::Height(10),
+        };
+        ALLOWANCE_PERIODS
+            .save(storage, (&alice, &bob), &period)
+            .unwrap();
+        let spent = AllowanceWindow {
+            ends: Expiration::AtHeight(mock_env().block.height + 10),
+            spent: Uint128::new(100_000),
+        };
+        ALLOWANCE_WINDOWS
+            .save(storage, (&alice, &bob), &spent)
+            .unwrap();
+
+        rescale_to(deps.as_mut(), 4).unwrap();
+        // amounts not by account are rescaled at once
+        let storage = deps.as_ref().storage;
+        let limit = MINT_RATE_LIMIT.load(storage).unwrap();
+        assert_eq!(limit.max_amount, Uint128::new(50_000));
+        assert_eq!(
+            MINT_WINDOW.load(storage).unwrap().minted,
+            Uint128::new(12_345)
+        );
+        let schedule = MINT_SCHEDULE.load(storage).unwrap();
+        assert_eq!(schedule.amount_per_period, Uint128::new(10_000));
+        assert_eq!(schedule.minted, Uint128::new(20_000));
+        assert_eq!(TOTAL_BURNED.load(storage).unwrap(), Uint128::new(30_000));
+
+        let res = finish_rescale(deps.as_mut());
+        assert!(res.attributes.contains(&("burned_by", "1").into()));
+        let storage = deps.as_ref().storage;
+        assert_eq!(
+            VESTING_LOCKS.load(storage, &alice).unwrap().amount,
+            Uint128::new(7_000)
+        );
+        assert_eq!(
+            MINTERS.load(storage, &bob).unwrap(),
+            MinterAllowance {
+                cap: Some(Uint128::new(90_000)),
+                minted: Uint128::new(10_000),
+            }
+        );
+        assert_eq!(BURNED_BY.load(storage, &bob).unwrap(), Uint128::new(30_000));
+        let key = (&alice, &bob);
+        assert_eq!(
+            ALLOWANCE_PERIODS.load(storage, key).unwrap().max_amount,
+            Uint128::new(4_000)
+        );
+        assert_eq!(
+            ALLOWANCE_WINDOWS.load(storage, key).unwrap().spent,
+            Uint128::new(1_000)
+        );
+    }
+}
@@ -0,0 +1,202 @@
+use cosmwasm_std::{Addr, Deps, StdError, StdResult, Storage, Uint128};
+use cw20::BalanceResponse;
+
+use crate::holders::record_holder_change;
+use crate::msg::TotalSupplyResponse;
+use crate::rebase::{rebase_index, to_amount, to_shares};
+use crate::state::{BALANCES, BALANCE_SNAPSHOTS, SNAPSHOTS, TOTAL_SUPPLY_SNAPSHOTS};
+use crate::votes::record_vote_change;
+
+fn snapshots_enabled(storage: &dyn Storage) -> StdResult<bool> {
+    Ok(SNAPSHOTS.may_load(storage)?.unwrap_or_default())
+}
+
+fn ensure_snapshots_enabled(storage: &dyn Storage) -> StdResult<()> {
+    if !snapshots_enabled(storage)? {
+        return Err(StdError::generic_err(
+            "Snapshots are not enabled for this token",
+        ));
+    }
+    Ok(())
+}
+
+/// Updates the balance of `addr`, recording its history if snapshots are enabled.
+/// Also keeps the number of holders up to date. Once the supply is rebased, `action` still works
+/// on token amounts, which are converted from and to the shares stored
+pub fn update_balance<A, E>(
+    storage: &mut dyn Storage,
+    height: u64,
+    addr: &Addr,
+    action: A,
+) -> Result<Uint128, E>
+where
+    A: FnOnce(Option<Uint128>) -> Result<Uint128, E>,
+    E: From<StdError>,
+{
+    let index = rebase_index(storage)?;
+    let mut before = Uint128::zero();
+    let action = |balance: Option<Uint128>| {
+        before = balance.unwrap_or_default();
+        match index {
+            Some(index) => {
+                action(balance.map(|shares| to_amount(shares, index))).map(|a| to_shares(a, index))
+            }
+            None => action(balance),
+        }
+    };
+    let after = if snapshots_enabled(storage)? {
+        BALANCE_SNAPSHOTS.update(storage, addr, height, action)?
+    } else {
+        BALANCES.update(storage, addr, action)?
+    };
+    record_holder_change(storage, addr, before, after)?;
+    let (before, after) = match index {
+        Some(index) => (to_amount(before, index), to_amount(after, index)),
+        None => (before, after),
+    };
+    record_vote_change(storage, height, addr, before, after)?;
+    Ok(after)
+}
+
+/// Records the new total supply if snapshots are enabled. Must be called whenever it changes
+pub fn record_total_supply(
+    storage: &mut dyn Storage,
+    height: u64,
+    total_supply: Uint128,
+) -> StdResult<()> {
+    if snapshots_enabled(storage)? {
+        TOTAL_SUPPLY_SNAPSHOTS.save(storage, &total_supply, height)?;
+    }
+    Ok(())
+}
+
+/// Returns the balance at the beginning of the block with the given height
+pub fn query_balance_at(deps: Deps, address: String, height: u64) -> StdResult<BalanceResponse> {
+    ensure_snapshots_enabled(deps.storage)?;
+    let address = deps.api.addr_validate(&address)?;
+    let balance = BALANCE_SNAPSHOTS
+        .may_load_at_height(deps.storage, &address, height)?
+        .unwrap_or_default();
+    Ok(BalanceResponse { balance })
+}
+
+/// Returns the total supply at the beginning of the block with the given height
+pub fn query_total_supply_at(deps: Deps, height: u64) -> StdResult<TotalSupplyResponse> {
+    ensure_snapshots_enabled(deps.storage)?;
+    let total_supply = TOTAL_SUPPLY_SNAPSHOTS
+        .may_load_at_height(deps.storage, height)?
+        .unwrap_or_default();
+    Ok(TotalSupplyResponse { total_supply })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{from_binary, DepsMut, Env};
+    use cw20::{Cw20Coin, MinterResponse};
+
+    use crate::contract::{execute, instantiate, query};
+    use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+
+    const OWNER: &str = "owner";
+    const MINTER: &str = "minter";
+    const OTHER: &str = "other";
+
+    fn do_instantiate(deps: DepsMut, env: Env, snapshots: bool) {
+        let instantiate_msg = InstantiateMsg {
+            name: "Auto Gen".to_string(),
+            symbol: "AUTO".to_string(),
+            decimals: 3,
+            initial_balances: vec![Cw20Coin {
+                address: OWNER.into(),
+                amount: Uint128::new(1000),
+            }],
+            mint: Some(MinterResponse {
+                minter: MINTER.into(),
+                cap: None,
+            }),
+            marketing: None,
+            snapshots,
+            freezer: None,
+            pauser: None,
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps, env, info, instantiate_msg).unwrap();
+    }
+
+    fn env_at(height: u64) -> Env {
+        let mut env = mock_env();
+        env.block.height = height;
+        env
+    }
+
+    fn balance_at(deps: Deps, address: &str, height: u64) -> u128 {
+        let msg = QueryMsg::BalanceAt {
+            address: address.to_string(),
+            height,
+        };
+        let res: BalanceResponse = from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+        res.balance.u128()
+    }
+
+    fn total_supply_at(deps: Deps, height: u64) -> u128 {
+        let msg = QueryMsg::TotalSupplyAt { height };
+        let res: TotalSupplyResponse = from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+        res.total_supply.u128()
+    }
+
+    #[test]
+    fn balances_and_supply_history() {
+        let mut deps = mock_dependencies();
+        do_instantiate(deps.as_mut(), env_at(100), true);
+
+        let msg = ExecuteMsg::Transfer {
+            recipient: OTHER.to_string(),
+            amount: Uint128::new(300),
+        };
+        execute(deps.as_mut(), env_at(110), mock_info(OWNER, &[]), msg).unwrap();
+        let msg = ExecuteMsg::Mint {
+            recipient: OTHER.to_string(),
+            amount: Uint128::new(500),
+        };
+        execute(deps.as_mut(), env_at(120), mock_info(MINTER, &[]), msg).unwrap();
+        let msg = ExecuteMsg::Burn {
+            amount: Uint128::new(200),
+            memo: None,
+        };
+        execute(deps.as_mut(), env_at(130), mock_info(OWNER, &[]), msg).unwrap();
+
+        // values are the ones at the beginning of each block
+        let deps = deps.as_ref();
+        assert_eq!(balance_at(deps, OWNER, 100), 0);
+        assert_eq!(balance_at(deps, OWNER, 101), 1000);
+        assert_eq!(balance_at(deps, OWNER, 110), 1000);
+        assert_eq!(balance_at(deps, OWNER, 111), 700);
+        assert_eq!(balance_at(deps, OWNER, 131), 500);
+        assert_eq!(balance_at(deps, OTHER, 110), 0);
+        assert_eq!(balance_at(deps, OTHER, 111), 300);
+        assert_eq!(balance_at(deps, OTHER, 121), 800);
+
+        assert_eq!(total_supply_at(deps, 100), 0);
+        assert_eq!(total_supply_at(deps, 101), 1000);
+        assert_eq!(total_supply_at(deps, 120), 1000);
+        assert_eq!(total_supply_at(deps, 121), 1500);
+        assert_eq!(total_supply_at(deps, 131), 1300);
+    }
+
+    #[test]
+    fn disabled_by_default() {
+        let mut deps = mock_dependencies();
+        do_instantiate(deps.as_mut(), env_at(100), false);
+
+        let msg = QueryMsg::BalanceAt {
+            address: OWNER.to_string(),
+            height: 101,
+        };
+        query(deps.as_ref(), mock_env(), msg).unwrap_err();
+        let msg = QueryMsg::TotalSupplyAt { height: 101 };
+        query(deps.as_ref(), mock_env(), msg).unwrap_err();
+    }
+}
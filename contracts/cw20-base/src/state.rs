@@ -1,8 +1,12 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Uint128};
-use cw_storage_plus::{Item, Map};
+use cosmwasm_std::{Addr, Decimal, Empty, Uint128};
+use cw_controllers::{Admin, Hooks, Pausable, Permits};
+use cw_storage_plus::{Item, Map, SnapshotItem, SnapshotMap, Strategy};
+use cw_utils::Duration;
 
-use cw20::{AllowanceResponse, Logo, MarketingInfoResponse};
+use cw20::{AllowanceResponse, Expiration, Logo, MarketingInfoResponse};
+
+use crate::msg::{AllowancePeriod, MintRateLimit, VestingLock, WalletLimits};
 
 #[cw_serde]
 pub struct TokenInfo {
@@ -20,17 +24,199 @@ pub struct MinterData {
     pub cap: Option<Uint128>,
 }
 
+/// Mint rights granted by the minter to another address
+#[cw_serde]
+pub struct MinterAllowance {
+    /// cap is how many tokens this minter can issue in total
+    pub cap: Option<Uint128>,
+    pub minted: Uint128,
+}
+
+#[cw_serde]
+pub struct TransferFee {
+    /// fee taken from every transfer, in basis points of the amount
+    pub bps: u16,
+    pub recipient: Addr,
+}
+
+#[cw_serde]
+pub struct BurnTax {
+    /// part of every burn sent to the treasury instead, in basis points of the amount
+    pub bps: u16,
+    pub treasury: Addr,
+}
+
+/// Emission schedule set by the minter
+#[cw_serde]
+pub struct MintSchedule {
+    pub recipient: Addr,
+    pub amount_per_period: Uint128,
+    pub period: Duration,
+    pub num_periods: u64,
+    /// recorded as a height or a time, like the period
+    pub start: Expiration,
+    pub minted: Uint128,
+}
+
+/// Tokens minted in the current mint rate limit window
+#[cw_serde]
+pub struct MintWindow {
+    pub ends: Expiration,
+    pub minted: Uint128,
+}
+
+/// Tokens spent out of an allowance in its current period
+#[cw_serde]
+pub struct AllowanceWindow {
+    pub ends: Expiration,
+    pub spent: Uint128,
+}
+
+/// A legacy import in progress, started on migration
+#[cw_serde]
+pub struct LegacyImport {
+    pub importer: Addr,
+    pub expected_total_supply: Uint128,
+}
+
+/// A change of decimals in progress, started on migration
+#[cw_serde]
+pub struct Rescale {
+    pub from_decimals: u8,
+    pub to_decimals: u8,
+    /// Where the next batch starts
+    pub cursor: RescaleCursor,
+}
+
+#[cw_serde]
+pub enum RescaleCursor {
+    Balances { start_after: Option<Addr> },
+    Allowances { start_after: Option<(Addr, Addr)> },
+    PendingTransfers { start_after: Option<u64> },
+    AllowancePeriods { start_after: Option<(Addr, Addr)> },
+    VestingLocks { start_after: Option<Addr> },
+    Minters { start_after: Option<Addr> },
+    BurnedBy { start_after: Option<Addr> },
+}
+
 impl TokenInfo {
     pub fn get_cap(&self) -> Option<Uint128> {
         self.mint.as_ref().and_then(|v| v.cap)
     }
 }
 
+#[cw_serde]
+pub struct HoldersBackfill {
+    /// The last account indexed, accounts after it are not counted yet
+    pub start_after: Option<Addr>,
+}
+
 pub const TOKEN_INFO: Item<TokenInfo> = Item::new("token_info");
 pub const MARKETING_INFO: Item<MarketingInfoResponse> = Item::new("marketing_info");
 pub const LOGO: Item<Logo> = Item::new("logo");
+/// Proposed as the new marketing account, until it accepts
+pub const PENDING_MARKETING: Item<Addr> = Item::new("pending_marketing");
+/// Proposed as the new minter, until it accepts
+pub const PENDING_MINTER: Item<Addr> = Item::new("pending_minter");
 pub const BALANCES: Map<&Addr, Uint128> = Map::new("balance");
+/// Number of accounts with a non-zero balance
+pub const NUM_HOLDERS: Item<u64> = Item::new("num_holders");
+/// All accounts with a non-zero balance, by balance
+pub const HOLDERS_BY_BALANCE: Map<(u128, &Addr), Empty> = Map::new("holders_by_balance");
+/// Only while the holders of a token migrated from a version without them are being indexed
+pub const HOLDERS_BACKFILL: Item<HoldersBackfill> = Item::new("holders_backfill");
+/// Whether the history of balances and total supply is recorded, set on instantiation
+pub const SNAPSHOTS: Item<bool> = Item::new("snapshots");
+/// Same balances as BALANCES, along with their history. Only written to if SNAPSHOTS is set
+pub const BALANCE_SNAPSHOTS: SnapshotMap<&Addr, Uint128> = SnapshotMap::new(
+    "balance",
+    "balance__checkpoints",
+    "balance__changelog",
+    Strategy::EveryBlock,
+);
+/// History of the total supply. Only written to if SNAPSHOTS is set
+pub const TOTAL_SUPPLY_SNAPSHOTS: SnapshotItem<Uint128> = SnapshotItem::new(
+    "total_supply",
+    "total_supply__checkpoints",
+    "total_supply__changelog",
+    Strategy::EveryBlock,
+);
+/// Who each holder delegated the votes of its balance to
+pub const DELEGATES: Map<&Addr, Addr> = Map::new("delegates");
+/// Votes delegated to each address, along with their history
+pub const VOTES: SnapshotMap<&Addr, Uint128> = SnapshotMap::new(
+    "votes",
+    "votes__checkpoints",
+    "votes__changelog",
+    Strategy::EveryBlock,
+);
 pub const ALLOWANCES: Map<(&Addr, &Addr), AllowanceResponse> = Map::new("allowance");
 // TODO: After https://github.com/CosmWasm/cw-plus/issues/670 is implemented, replace this with a `MultiIndex` over `ALLOWANCES`
 pub const ALLOWANCES_SPENDER: Map<(&Addr, &Addr), AllowanceResponse> =
     Map::new("allowance_spender");
+/// Optional limit per period on allowances, set by their owner
+pub const ALLOWANCE_PERIODS: Map<(&Addr, &Addr), AllowancePeriod> = Map::new("allowance_periods");
+pub const ALLOWANCE_WINDOWS: Map<(&Addr, &Addr), AllowanceWindow> = Map::new("allowance_windows");
+/// Nonce the next permit signed by each owner must use
+pub const NONCES: Permits = Permits::new("permit_nonces");
+/// Contracts notified of every transfer, send and burn
+pub const HOOKS: Hooks = Hooks::new("transfer_hooks");
+/// Minter that requested a `Cw20MintCallbackMsg` after every mint. Ignored once it is no
+/// longer the minter
+pub const MINT_CALLBACK: Item<Addr> = Item::new("mint_callback");
+/// Optional limit on the tokens minted per window, set by the minter
+pub const MINT_RATE_LIMIT: Item<MintRateLimit> = Item::new("mint_rate_limit");
+pub const MINT_WINDOW: Item<MintWindow> = Item::new("mint_window");
+/// Optional emission schedule, minted by anyone with `MintVested`
+pub const MINT_SCHEDULE: Item<MintSchedule> = Item::new("mint_schedule");
+/// Vesting locks set by the minter on specific accounts
+pub const VESTING_LOCKS: Map<&Addr, VestingLock> = Map::new("vesting_locks");
+/// Additional minters, administered by the minter in TOKEN_INFO
+pub const MINTERS: Map<&Addr, MinterAllowance> = Map::new("minters");
+/// Only with the freeze extension, who can freeze accounts. Set on instantiation
+pub const FREEZER: Item<Addr> = Item::new("freezer");
+/// Accounts that cannot move or approve their tokens
+pub const FROZEN: Map<&Addr, Empty> = Map::new("frozen");
+/// Who can pause the token, if anyone
+pub const PAUSER: Admin = Admin::new("pauser");
+pub const PAUSE: Pausable = Pausable::new("paused");
+/// Optional fee taken from transfers and sends, set by the minter
+pub const TRANSFER_FEE: Item<TransferFee> = Item::new("transfer_fee");
+/// Optional part of burns redirected to a treasury, set by the minter
+pub const BURN_TAX: Item<BurnTax> = Item::new("burn_tax");
+/// Accounts whose incoming and outgoing transfers are not charged the fee, nor their burns the tax
+pub const FEE_EXEMPT: Map<&Addr, Empty> = Map::new("fee_exempt");
+/// Optional caps on balances and transfers, set by the minter
+pub const WALLET_LIMITS: Item<WalletLimits> = Item::new("wallet_limits");
+/// Accounts not held to the wallet limits
+pub const LIMIT_EXEMPT: Map<&Addr, Empty> = Map::new("limit_exempt");
+/// Total amount of tokens ever burned
+pub const TOTAL_BURNED: Item<Uint128> = Item::new("total_burned");
+#[cw_serde]
+pub struct PendingTransfer {
+    pub sender: Addr,
+    pub recipient: Addr,
+    pub amount: Uint128,
+    pub expires: Expiration,
+}
+
+/// Transfers held in escrow until claimed by the recipient or reclaimed by the sender
+pub const PENDING_TRANSFERS: Map<u64, PendingTransfer> = Map::new("pending_transfers");
+/// Ids of the pending transfers per sender
+pub const PENDING_TRANSFERS_SENDER: Map<(&Addr, u64), Empty> = Map::new("pending_transfers_sender");
+/// Ids of the pending transfers per recipient
+pub const PENDING_TRANSFERS_RECIPIENT: Map<(&Addr, u64), Empty> =
+    Map::new("pending_transfers_recipient");
+pub const NEXT_PENDING_TRANSFER_ID: Item<u64> = Item::new("next_pending_transfer_id");
+/// Only if set by the minter, who can rebase the supply
+pub const REBASER: Item<Addr> = Item::new("rebaser");
+/// Amount of tokens per share stored in BALANCES, only set once the supply was rebased
+pub const REBASE_INDEX: Item<Decimal> = Item::new("rebase_index");
+/// Only if set by the minter, who can burn from any account without an allowance
+pub const BURNER: Item<Addr> = Item::new("burner");
+/// Amount of tokens ever burned from each account
+pub const BURNED_BY: Map<&Addr, Uint128> = Map::new("burned_by");
+/// Only while balances exported from an old token are being imported
+pub const LEGACY_IMPORT: Item<LegacyImport> = Item::new("legacy_import");
+/// Only while balances and allowances are being converted to new decimals
+pub const RESCALE: Item<Rescale> = Item::new("rescale");
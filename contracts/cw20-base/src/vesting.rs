@@ -0,0 +1,299 @@
+use cosmwasm_std::{
+    Addr, BlockInfo, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Storage, Timestamp,
+    Uint128,
+};
+
+use crate::error::ContractError;
+use crate::hooks::assert_minter;
+use crate::msg::{VestingLock, VestingLockResponse};
+use crate::rebase::load_balance;
+use crate::state::VESTING_LOCKS;
+
+/// Tokens still locked at `time`, rounded up
+fn locked_at(lock: &VestingLock, time: Timestamp) -> Uint128 {
+    if time <= lock.start {
+        return lock.amount;
+    }
+    if time >= lock.end {
+        return Uint128::zero();
+    }
+    let elapsed = time.seconds() - lock.start.seconds();
+    let duration = lock.end.seconds() - lock.start.seconds();
+    lock.amount - lock.amount.multiply_ratio(elapsed, duration)
+}
+
+fn locked_amount(storage: &dyn Storage, block: &BlockInfo, addr: &Addr) -> StdResult<Uint128> {
+    Ok(match VESTING_LOCKS.may_load(storage, addr)? {
+        Some(lock) => locked_at(&lock, block.time),
+        None => Uint128::zero(),
+    })
+}
+
+/// Fails if moving `amount` tokens out of the account of `addr` would leave less than the amount
+/// still locked. Must be checked before every debit the account initiates
+pub fn ensure_unlocked(
+    storage: &dyn Storage,
+    block: &BlockInfo,
+    addr: &Addr,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    let locked = locked_amount(storage, block, addr)?;
+    if locked.is_zero() {
+        return Ok(());
+    }
+    // an insufficient balance is left to fail on the debit itself
+    match load_balance(storage, addr)?.checked_sub(amount) {
+        Ok(remaining) if remaining < locked => Err(ContractError::TokensLocked {
+            address: addr.to_string(),
+            locked,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Sets the vesting lock of an account, or removes it (with `None`). Must be called by the minter.
+pub fn execute_set_vesting_lock(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+    lock: Option<VestingLock>,
+) -> Result<Response, ContractError> {
+    assert_minter(deps.storage, &info.sender)?;
+    let addr = deps.api.addr_validate(&address)?;
+
+    let res = Response::new()
+        .add_attribute("action", "set_vesting_lock")
+        .add_attribute("address", addr.as_str());
+    let res = match lock {
+        Some(lock) => {
+            if lock.amount.is_zero() || lock.end <= lock.start {
+                return Err(ContractError::InvalidVestingLock {});
+            }
+            VESTING_LOCKS.save(deps.storage, &addr, &lock)?;
+            res.add_attribute("amount", lock.amount)
+                .add_attribute("start", lock.start.to_string())
+                .add_attribute("end", lock.end.to_string())
+        }
+        None => {
+            VESTING_LOCKS.remove(deps.storage, &addr);
+            res.add_attribute("amount", "None")
+        }
+    };
+    Ok(res)
+}
+
+pub fn query_vesting_lock(deps: Deps, env: Env, address: String) -> StdResult<VestingLockResponse> {
+    let addr = deps.api.addr_validate(&address)?;
+    let lock = VESTING_LOCKS.may_load(deps.storage, &addr)?;
+    let locked = locked_amount(deps.storage, &env.block, &addr)?;
+    let unlocked = load_balance(deps.storage, &addr)?.saturating_sub(locked);
+    Ok(VestingLockResponse {
+        lock,
+        locked,
+        unlocked,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::from_binary;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cw20::{Cw20Coin, MinterResponse};
+
+    use crate::contract::{execute, instantiate, query};
+    use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+
+    const DAY: u64 = 24 * 60 * 60;
+
+    fn do_instantiate(deps: DepsMut) {
+        let msg = InstantiateMsg {
+            name: "Project".to_string(),
+            symbol: "PROJ".to_string(),
+            decimals: 6,
+            initial_balances: vec![Cw20Coin {
+                address: "team".to_string(),
+                amount: Uint128::new(1000),
+            }],
+            mint: Some(MinterResponse {
+                minter: "minter".to_string(),
+                cap: None,
+            }),
+            marketing: None,
+            snapshots: false,
+            freezer: None,
+            pauser: None,
+        };
+        instantiate(deps, mock_env(), mock_info("creator", &[]), msg).unwrap();
+    }
+
+    fn env_at(days: u64) -> Env {
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(days * DAY);
+        env
+    }
+
+    fn transfer(amount: u128) -> ExecuteMsg {
+        ExecuteMsg::Transfer {
+            recipient: "alice".to_string(),
+            amount: Uint128::new(amount),
+        }
+    }
+
+    fn vesting_lock(deps: Deps, env: Env) -> VestingLockResponse {
+        let msg = QueryMsg::VestingLock {
+            address: "team".to_string(),
+        };
+        from_binary(&query(deps, env, msg).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn locked_tokens_cannot_move() {
+        let mut deps = mock_dependencies();
+        do_instantiate(deps.as_mut());
+
+        // 800 tokens are locked for 10 days, then unlock over 100 days
+        let now = mock_env().block.time;
+        let lock = VestingLock {
+            amount: Uint128::new(800),
+            start: now.plus_seconds(10 * DAY),
+            end: now.plus_seconds(110 * DAY),
+        };
+        let set_lock = ExecuteMsg::SetVestingLock {
+            address: "team".to_string(),
+            lock: Some(lock.clone()),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("team", &[]),
+            set_lock.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("minter", &[]),
+            set_lock,
+        )
+        .unwrap();
+        assert_eq!(
+            vesting_lock(deps.as_ref(), mock_env()),
+            VestingLockResponse {
+                lock: Some(lock),
+                locked: Uint128::new(800),
+                unlocked: Uint128::new(200),
+            }
+        );
+
+        // only the unlocked part can be moved, by any means
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("team", &[]),
+            transfer(200),
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("team", &[]),
+            transfer(1),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::TokensLocked {
+                address: "team".to_string(),
+                locked: Uint128::new(800),
+            }
+        );
+        let burn = ExecuteMsg::Burn {
+            amount: Uint128::new(1),
+            memo: None,
+        };
+        let err = execute(deps.as_mut(), env_at(5), mock_info("team", &[]), burn).unwrap_err();
+        assert!(matches!(err, ContractError::TokensLocked { .. }));
+
+        // tokens unlock linearly after the start
+        let res = vesting_lock(deps.as_ref(), env_at(35));
+        assert_eq!(res.locked, Uint128::new(600));
+        assert_eq!(res.unlocked, Uint128::new(200));
+        execute(
+            deps.as_mut(),
+            env_at(35),
+            mock_info("team", &[]),
+            transfer(200),
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            env_at(35),
+            mock_info("team", &[]),
+            transfer(1),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::TokensLocked { .. }));
+
+        // and are all free at the end
+        execute(
+            deps.as_mut(),
+            env_at(110),
+            mock_info("team", &[]),
+            transfer(600),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn remove_vesting_lock() {
+        let mut deps = mock_dependencies();
+        do_instantiate(deps.as_mut());
+        let now = mock_env().block.time;
+
+        let msg = ExecuteMsg::SetVestingLock {
+            address: "team".to_string(),
+            lock: Some(VestingLock {
+                amount: Uint128::new(1000),
+                start: now,
+                end: now,
+            }),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("minter", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidVestingLock {});
+
+        let msg = ExecuteMsg::SetVestingLock {
+            address: "team".to_string(),
+            lock: Some(VestingLock {
+                amount: Uint128::new(1000),
+                start: now,
+                end: now.plus_seconds(DAY),
+            }),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("minter", &[]), msg).unwrap();
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("team", &[]),
+            transfer(1000),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::TokensLocked { .. }));
+
+        let msg = ExecuteMsg::SetVestingLock {
+            address: "team".to_string(),
+            lock: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("minter", &[]), msg).unwrap();
+        assert_eq!(vesting_lock(deps.as_ref(), mock_env()).lock, None);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("team", &[]),
+            transfer(1000),
+        )
+        .unwrap();
+    }
+}
@@ -0,0 +1,266 @@
+use cosmwasm_std::{
+    Addr, Deps, DepsMut, Env, MessageInfo, Order, Response, StdResult, Storage, Uint128,
+};
+
+use crate::error::ContractError;
+use crate::msg::{DelegateResponse, VotesResponse};
+use crate::rebase::load_balance;
+use crate::state::{DELEGATES, REBASER, REBASE_INDEX, VOTES};
+
+/// Whether any holder delegated its votes, which rules out rebasing the supply
+pub fn has_delegations(storage: &dyn Storage) -> bool {
+    DELEGATES
+        .keys(storage, None, None, Order::Ascending)
+        .next()
+        .is_some()
+}
+
+fn move_votes(
+    storage: &mut dyn Storage,
+    height: u64,
+    from: Option<&Addr>,
+    to: Option<&Addr>,
+    amount: Uint128,
+) -> StdResult<()> {
+    if amount.is_zero() || from == to {
+        return Ok(());
+    }
+    if let Some(from) = from {
+        VOTES.update(storage, from, height, |votes| -> StdResult<_> {
+            Ok(votes.unwrap_or_default().checked_sub(amount)?)
+        })?;
+    }
+    if let Some(to) = to {
+        VOTES.update(storage, to, height, |votes| -> StdResult<_> {
+            Ok(votes.unwrap_or_default().checked_add(amount)?)
+        })?;
+    }
+    Ok(())
+}
+
+/// Moves the votes of the delegate of `addr`, if any, along with its balance changing from
+/// `before` to `after`. Called by `update_balance`, so all balance changes are covered
+pub fn record_vote_change(
+    storage: &mut dyn Storage,
+    height: u64,
+    addr: &Addr,
+    before: Uint128,
+    after: Uint128,
+) -> StdResult<()> {
+    let delegate = match DELEGATES.may_load(storage, addr)? {
+        Some(delegate) => delegate,
+        None => return Ok(()),
+    };
+    if after > before {
+        move_votes(storage, height, None, Some(&delegate), after - before)
+    } else {
+        move_votes(storage, height, Some(&delegate), None, before - after)
+    }
+}
+
+/// Gives the voting power of the whole balance of the sender, now and to come, to `delegatee`
+/// (possibly itself), or takes it back with `None`
+pub fn execute_delegate(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    delegatee: Option<String>,
+) -> Result<Response, ContractError> {
+    // votes could not follow the balances changed by a rebase
+    if REBASER.may_load(deps.storage)?.is_some() || REBASE_INDEX.may_load(deps.storage)?.is_some() {
+        return Err(ContractError::RebaseWithVotes {});
+    }
+
+    let delegatee = delegatee
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+    let previous = DELEGATES.may_load(deps.storage, &info.sender)?;
+    let balance = load_balance(deps.storage, &info.sender)?;
+    move_votes(
+        deps.storage,
+        env.block.height,
+        previous.as_ref(),
+        delegatee.as_ref(),
+        balance,
+    )?;
+    match &delegatee {
+        Some(delegatee) => DELEGATES.save(deps.storage, &info.sender, delegatee)?,
+        None => DELEGATES.remove(deps.storage, &info.sender),
+    }
+
+    let to_attr = |addr: Option<Addr>| addr.map_or_else(|| "None".to_string(), Addr::into_string);
+    let res = Response::new()
+        .add_attribute("action", "delegate")
+        .add_attribute("delegator", info.sender)
+        .add_attribute("from_delegate", to_attr(previous))
+        .add_attribute("to_delegate", to_attr(delegatee))
+        .add_attribute("amount", balance);
+    Ok(res)
+}
+
+pub fn query_delegate(deps: Deps, address: String) -> StdResult<DelegateResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let delegate = DELEGATES.may_load(deps.storage, &address)?;
+    Ok(DelegateResponse {
+        delegate: delegate.map(Addr::into_string),
+    })
+}
+
+pub fn query_votes(deps: Deps, address: String) -> StdResult<VotesResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let votes = VOTES.may_load(deps.storage, &address)?.unwrap_or_default();
+    Ok(VotesResponse { votes })
+}
+
+/// Returns the votes delegated to `address` at the beginning of the block with the given height
+pub fn query_votes_at(deps: Deps, address: String, height: u64) -> StdResult<VotesResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let votes = VOTES
+        .may_load_at_height(deps.storage, &address, height)?
+        .unwrap_or_default();
+    Ok(VotesResponse { votes })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::from_binary;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cw20::{Cw20Coin, MinterResponse};
+
+    use crate::contract::{execute, instantiate, query};
+    use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+
+    fn env_at(height: u64) -> Env {
+        let mut env = mock_env();
+        env.block.height = height;
+        env
+    }
+
+    fn setup(deps: DepsMut) {
+        let msg = InstantiateMsg {
+            name: "Governance".to_string(),
+            symbol: "GOV".to_string(),
+            decimals: 6,
+            initial_balances: vec![
+                Cw20Coin {
+                    address: "alice".to_string(),
+                    amount: Uint128::new(1000),
+                },
+                Cw20Coin {
+                    address: "bob".to_string(),
+                    amount: Uint128::new(500),
+                },
+            ],
+            mint: Some(MinterResponse {
+                minter: "minter".to_string(),
+                cap: None,
+            }),
+            marketing: None,
+            snapshots: false,
+            freezer: None,
+            pauser: None,
+        };
+        instantiate(deps, env_at(100), mock_info("creator", &[]), msg).unwrap();
+    }
+
+    fn delegate(deps: DepsMut, height: u64, sender: &str, delegatee: Option<&str>) {
+        let msg = ExecuteMsg::Delegate {
+            delegatee: delegatee.map(str::to_string),
+        };
+        execute(deps, env_at(height), mock_info(sender, &[]), msg).unwrap();
+    }
+
+    fn votes_at(deps: Deps, address: &str, height: u64) -> u128 {
+        let msg = QueryMsg::VotesAt {
+            address: address.to_string(),
+            height,
+        };
+        let res: VotesResponse = from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+        res.votes.u128()
+    }
+
+    fn votes(deps: Deps, address: &str) -> u128 {
+        let msg = QueryMsg::Votes {
+            address: address.to_string(),
+        };
+        let res: VotesResponse = from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+        res.votes.u128()
+    }
+
+    #[test]
+    fn delegated_votes_follow_balances() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+
+        // balances only count once delegated, possibly to oneself
+        assert_eq!(votes(deps.as_ref(), "alice"), 0);
+        delegate(deps.as_mut(), 110, "alice", Some("alice"));
+        delegate(deps.as_mut(), 110, "bob", Some("alice"));
+        assert_eq!(votes(deps.as_ref(), "alice"), 1500);
+        let res: DelegateResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Delegate {
+                    address: "bob".to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.delegate, Some("alice".to_string()));
+
+        // transfers move the votes between delegates
+        let transfer = ExecuteMsg::Transfer {
+            recipient: "carl".to_string(),
+            amount: Uint128::new(300),
+        };
+        execute(deps.as_mut(), env_at(120), mock_info("bob", &[]), transfer).unwrap();
+        delegate(deps.as_mut(), 120, "carl", Some("dave"));
+        let mint = ExecuteMsg::Mint {
+            recipient: "carl".to_string(),
+            amount: Uint128::new(200),
+        };
+        execute(deps.as_mut(), env_at(130), mock_info("minter", &[]), mint).unwrap();
+        assert_eq!(votes(deps.as_ref(), "alice"), 1200);
+        assert_eq!(votes(deps.as_ref(), "dave"), 500);
+
+        // taking the votes back
+        delegate(deps.as_mut(), 140, "bob", None);
+        assert_eq!(votes(deps.as_ref(), "alice"), 1000);
+
+        // with their history, as of the beginning of each block
+        assert_eq!(votes_at(deps.as_ref(), "alice", 110), 0);
+        assert_eq!(votes_at(deps.as_ref(), "alice", 111), 1500);
+        assert_eq!(votes_at(deps.as_ref(), "alice", 121), 1200);
+        assert_eq!(votes_at(deps.as_ref(), "dave", 130), 300);
+        assert_eq!(votes_at(deps.as_ref(), "dave", 131), 500);
+        assert_eq!(votes_at(deps.as_ref(), "alice", 141), 1000);
+    }
+
+    #[test]
+    fn votes_exclude_rebasing() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+
+        delegate(deps.as_mut(), 110, "alice", Some("alice"));
+        let msg = ExecuteMsg::UpdateRebaser {
+            new_rebaser: Some("rebaser".to_string()),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("minter", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::RebaseWithVotes {});
+
+        delegate(deps.as_mut(), 110, "alice", None);
+        let msg = ExecuteMsg::UpdateRebaser {
+            new_rebaser: Some("rebaser".to_string()),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("minter", &[]), msg).unwrap();
+        let msg = ExecuteMsg::Delegate {
+            delegatee: Some("alice".to_string()),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::RebaseWithVotes {});
+    }
+}
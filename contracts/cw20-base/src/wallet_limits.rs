@@ -0,0 +1,365 @@
+use std::collections::BTreeMap;
+
+use cosmwasm_std::{
+    Addr, Deps, DepsMut, Empty, MessageInfo, Order, Response, StdError, StdResult, Storage, Uint128,
+};
+use cw_storage_plus::Bound;
+
+use crate::error::ContractError;
+use crate::hooks::assert_minter;
+use crate::msg::{LimitExemptResponse, WalletLimits};
+use crate::rebase::load_balance;
+use crate::state::{LIMIT_EXEMPT, WALLET_LIMITS};
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+fn check_transfer(
+    storage: &dyn Storage,
+    limits: &WalletLimits,
+    from: &Addr,
+    to: &Addr,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    if let Some(max_transfer) = limits.max_transfer {
+        let exempt = LIMIT_EXEMPT.has(storage, from) || LIMIT_EXEMPT.has(storage, to);
+        if amount > max_transfer && !exempt {
+            return Err(ContractError::MaxTransferExceeded { max_transfer });
+        }
+    }
+    Ok(())
+}
+
+fn check_balance(
+    storage: &dyn Storage,
+    limits: &WalletLimits,
+    to: &Addr,
+    credited: Uint128,
+) -> Result<(), ContractError> {
+    if let Some(max_balance) = limits.max_balance {
+        if LIMIT_EXEMPT.has(storage, to) {
+            return Ok(());
+        }
+        let balance = load_balance(storage, to)?
+            .checked_add(credited)
+            .map_err(StdError::overflow)?;
+        if balance > max_balance {
+            return Err(ContractError::MaxBalanceExceeded {
+                address: to.to_string(),
+                max_balance,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Fails if moving `amount` from `from` (None for mints) to `to`, who is credited `credited`,
+/// breaks the wallet limits. Must be checked before the balances are updated
+pub fn ensure_within_wallet_limits(
+    storage: &dyn Storage,
+    from: Option<&Addr>,
+    to: &Addr,
+    amount: Uint128,
+    credited: Uint128,
+) -> Result<(), ContractError> {
+    let limits = match WALLET_LIMITS.may_load(storage)? {
+        Some(limits) => limits,
+        None => return Ok(()),
+    };
+    if let Some(from) = from {
+        check_transfer(storage, &limits, from, to, amount)?;
+    }
+    check_balance(storage, &limits, to, credited)
+}
+
+/// Like `ensure_within_wallet_limits` for every transfer of a batch, given the amounts sent and
+/// the matching credits. Recipients showing up several times are held to the limit on their total
+pub fn ensure_batch_within_wallet_limits(
+    storage: &dyn Storage,
+    from: &Addr,
+    amounts: &[(Addr, Uint128)],
+    credits: &[(Addr, Uint128)],
+) -> Result<(), ContractError> {
+    let limits = match WALLET_LIMITS.may_load(storage)? {
+        Some(limits) => limits,
+        None => return Ok(()),
+    };
+    let mut totals: BTreeMap<&Addr, Uint128> = BTreeMap::new();
+    for ((to, amount), (_, credited)) in amounts.iter().zip(credits) {
+        check_transfer(storage, &limits, from, to, *amount)?;
+        let total = totals.entry(to).or_default();
+        *total = total.checked_add(*credited).map_err(StdError::overflow)?;
+    }
+    for (to, credited) in totals {
+        check_balance(storage, &limits, to, credited)?;
+    }
+    Ok(())
+}
+
+/// Sets or removes (with `None`) the wallet limits. Must be called by the minter
+pub fn execute_update_wallet_limits(
+    deps: DepsMut,
+    info: MessageInfo,
+    limits: Option<WalletLimits>,
+) -> Result<Response, ContractError> {
+    assert_minter(deps.storage, &info.sender)?;
+
+    let res = Response::new().add_attribute("action", "update_wallet_limits");
+    let res = match limits {
+        Some(limits) => {
+            let is_zero = |limit: Option<Uint128>| limit.unwrap_or(Uint128::MAX).is_zero();
+            if is_zero(limits.max_balance) || is_zero(limits.max_transfer) {
+                return Err(ContractError::InvalidWalletLimits {});
+            }
+            WALLET_LIMITS.save(deps.storage, &limits)?;
+            let to_attr = |limit: Option<Uint128>| {
+                limit.map_or_else(|| "None".to_string(), |limit| limit.to_string())
+            };
+            res.add_attribute("max_balance", to_attr(limits.max_balance))
+                .add_attribute("max_transfer", to_attr(limits.max_transfer))
+        }
+        None => {
+            WALLET_LIMITS.remove(deps.storage);
+            res.add_attribute("max_balance", "None")
+                .add_attribute("max_transfer", "None")
+        }
+    };
+    Ok(res)
+}
+
+/// Exempts `address` from the wallet limits, or removes the exemption. Must be called by the minter
+pub fn execute_set_limit_exempt(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+    exempt: bool,
+) -> Result<Response, ContractError> {
+    assert_minter(deps.storage, &info.sender)?;
+    let addr = deps.api.addr_validate(&address)?;
+    if exempt {
+        LIMIT_EXEMPT.save(deps.storage, &addr, &Empty {})?;
+    } else {
+        LIMIT_EXEMPT.remove(deps.storage, &addr);
+    }
+
+    let res = Response::new()
+        .add_attribute("action", "set_limit_exempt")
+        .add_attribute("address", addr)
+        .add_attribute("exempt", exempt.to_string());
+    Ok(res)
+}
+
+pub fn query_wallet_limits(deps: Deps) -> StdResult<Option<WalletLimits>> {
+    WALLET_LIMITS.may_load(deps.storage)
+}
+
+pub fn query_limit_exempt(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<LimitExemptResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|s| Bound::ExclusiveRaw(s.into()));
+
+    let addresses = LIMIT_EXEMPT
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(Into::into))
+        .collect::<StdResult<_>>()?;
+    Ok(LimitExemptResponse { addresses })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::from_binary;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cw20::{Cw20Coin, MinterResponse};
+
+    use crate::contract::{execute, instantiate, query, query_balance};
+    use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+
+    const MINTER: &str = "minter";
+
+    fn setup(deps: DepsMut) {
+        let msg = InstantiateMsg {
+            name: "Fair".to_string(),
+            symbol: "FAIR".to_string(),
+            decimals: 6,
+            initial_balances: vec![Cw20Coin {
+                address: "pool".to_string(),
+                amount: Uint128::new(100_000),
+            }],
+            mint: Some(MinterResponse {
+                minter: MINTER.to_string(),
+                cap: None,
+            }),
+            marketing: None,
+            snapshots: false,
+            freezer: None,
+            pauser: None,
+        };
+        instantiate(deps, mock_env(), mock_info("creator", &[]), msg).unwrap();
+    }
+
+    fn transfer(recipient: &str, amount: u128) -> ExecuteMsg {
+        ExecuteMsg::Transfer {
+            recipient: recipient.to_string(),
+            amount: Uint128::new(amount),
+        }
+    }
+
+    fn balance(deps: Deps, address: &str) -> u128 {
+        query_balance(deps, address.to_string())
+            .unwrap()
+            .balance
+            .u128()
+    }
+
+    #[test]
+    fn whales_are_limited() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+
+        // only the minter configures the limits, which cannot be zero
+        let limits = WalletLimits {
+            max_balance: Some(Uint128::new(5_000)),
+            max_transfer: Some(Uint128::new(2_000)),
+        };
+        let update = |limits: WalletLimits| ExecuteMsg::UpdateWalletLimits {
+            limits: Some(limits),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("pool", &[]),
+            update(limits.clone()),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MINTER, &[]),
+            update(WalletLimits {
+                max_balance: None,
+                max_transfer: Some(Uint128::zero()),
+            }),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidWalletLimits {});
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MINTER, &[]),
+            update(limits.clone()),
+        )
+        .unwrap();
+        let res: Option<WalletLimits> =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::WalletLimits {}).unwrap())
+                .unwrap();
+        assert_eq!(res, Some(limits));
+
+        // the pool holding most of the supply must be exempt to distribute it
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("pool", &[]),
+            transfer("alice", 4_000),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::MaxTransferExceeded {
+                max_transfer: Uint128::new(2_000)
+            }
+        );
+        let exempt = ExecuteMsg::SetLimitExempt {
+            address: "pool".to_string(),
+            exempt: true,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), exempt).unwrap();
+        let res: LimitExemptResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::LimitExempt {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.addresses, vec!["pool"]);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("pool", &[]),
+            transfer("alice", 4_000),
+        )
+        .unwrap();
+
+        // others are held to both limits
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[]),
+            transfer("bob", 2_001),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::MaxTransferExceeded {
+                max_transfer: Uint128::new(2_000)
+            }
+        );
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("pool", &[]),
+            transfer("alice", 1_001),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::MaxBalanceExceeded {
+                address: "alice".to_string(),
+                max_balance: Uint128::new(5_000),
+            }
+        );
+
+        // within a batch, recipients are held to the limit on their total
+        let batch = ExecuteMsg::TransferBatch {
+            recipients: vec![
+                ("bob".to_string(), Uint128::new(2_000)),
+                ("bob".to_string(), Uint128::new(2_000)),
+                ("bob".to_string(), Uint128::new(2_000)),
+            ],
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("pool", &[]), batch).unwrap_err();
+        assert!(matches!(err, ContractError::MaxBalanceExceeded { .. }));
+
+        // mints as well
+        let mint = ExecuteMsg::Mint {
+            recipient: "alice".to_string(),
+            amount: Uint128::new(1_001),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint).unwrap_err();
+        assert!(matches!(err, ContractError::MaxBalanceExceeded { .. }));
+
+        // until the limits are removed
+        let update = ExecuteMsg::UpdateWalletLimits { limits: None };
+        execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), update).unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("pool", &[]),
+            transfer("alice", 10_000),
+        )
+        .unwrap();
+        assert_eq!(balance(deps.as_ref(), "alice"), 14_000);
+    }
+}
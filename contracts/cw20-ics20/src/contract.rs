@@ -1,8 +1,8 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    from_binary, to_binary, Addr, Binary, Deps, DepsMut, Env, IbcMsg, IbcQuery, MessageInfo, Order,
-    PortIdResponse, Response, StdError, StdResult,
+    from_binary, to_binary, Addr, Binary, Deps, DepsMut, Empty, Env, IbcMsg, IbcQuery, MessageInfo,
+    Order, PortIdResponse, Response, StdError, StdResult, Storage, Uint128,
 };
 use semver::Version;
 
@@ -12,15 +12,22 @@ use cw_storage_plus::Bound;
 
 use crate::amount::Amount;
 use crate::error::ContractError;
-use crate::ibc::Ics20Packet;
-use crate::migrations::{v1, v2};
+use crate::forward::forward_memo;
+use crate::ibc::{send_amount, Ics20Packet};
+use crate::migrations::{fixed_rate_limits, v1, v2};
 use crate::msg::{
-    AllowMsg, AllowedInfo, AllowedResponse, ChannelResponse, ConfigResponse, ExecuteMsg, InitMsg,
-    ListAllowedResponse, ListChannelsResponse, MigrateMsg, PortResponse, QueryMsg, TransferMsg,
+    AllowMsg, AllowOnChannelMsg, AllowedInfo, AllowedResponse, ChannelAllowedInfo,
+    ChannelAllowedResponse, ChannelResponse, ConfigResponse, ExecuteMsg, InFlightPacketInfo,
+    InFlightPacketsResponse, InFlightTotalsResponse, InitMsg, ListAllowedResponse,
+    ListChannelAllowedResponse, ListChannelsResponse, MigrateMsg, PortResponse, QueryMsg,
+    RateLimitResponse, TransferMsg,
 };
 use crate::state::{
-    increase_channel_balance, AllowInfo, Config, ADMIN, ALLOW_LIST, CHANNEL_INFO, CHANNEL_STATE,
-    CONFIG,
+    apply_rate_limit, current_rate_limit_usage, increase_channel_balance, record_in_flight,
+    reduce_channel_balance, remove_in_flight, AllowInfo, ChannelAllowInfo, Config, InFlightPacket,
+    RateLimit, ADMIN, ALLOW_LIST, CHANNEL_ALLOW_LIST, CHANNEL_INFO, CHANNEL_STATE, CLOSED_CHANNELS,
+    CONFIG, IN_FLIGHT_AMOUNTS, IN_FLIGHT_COUNT, IN_FLIGHT_PACKETS, NEXT_SEQUENCE, RATE_LIMITS,
+    RATE_LIMIT_USAGE, RECOVERED_PACKETS, RESTRICTED_CHANNELS,
 };
 use cw_utils::{maybe_addr, nonpayable, one_coin};
 
@@ -70,10 +77,26 @@ pub fn execute(
             execute_transfer(deps, env, msg, Amount::Native(coin), info.sender)
         }
         ExecuteMsg::Allow(allow) => execute_allow(deps, env, info, allow),
+        ExecuteMsg::AllowOnChannel(allow) => execute_allow_on_channel(deps, info, allow),
+        ExecuteMsg::DisallowOnChannel { channel, denom } => {
+            execute_disallow_on_channel(deps, info, channel, denom)
+        }
+        ExecuteMsg::ClearChannelAllowList { channel } => {
+            execute_clear_channel_allow_list(deps, info, channel)
+        }
         ExecuteMsg::UpdateAdmin { admin } => {
             let admin = deps.api.addr_validate(&admin)?;
             Ok(ADMIN.execute_update_admin(deps, info, Some(admin))?)
         }
+        ExecuteMsg::SetRateLimit {
+            channel,
+            denom,
+            limit,
+        } => execute_set_rate_limit(deps, info, channel, denom, limit),
+        ExecuteMsg::CloseChannel { channel } => execute_close_channel(deps, info, channel),
+        ExecuteMsg::RecoverPacket { channel, sequence } => {
+            execute_recover_packet(deps, env, info, channel, sequence)
+        }
     }
 }
 
@@ -108,6 +131,11 @@ pub fn execute_transfer(
     if !CHANNEL_INFO.has(deps.storage, &msg.channel) {
         return Err(ContractError::NoSuchChannel { id: msg.channel });
     }
+    if CLOSED_CHANNELS.has(deps.storage, &msg.channel) {
+        return Err(ContractError::ChannelClosed {
+            channel: msg.channel,
+        });
+    }
     let config = CONFIG.load(deps.storage)?;
 
     // if cw20 token, validate and ensure it is whitelisted, or we set default gas limit
@@ -121,6 +149,8 @@ pub fn execute_transfer(
         }
     };
 
+    assert_allowed_on_channel(deps.storage, &msg.channel, &amount)?;
+
     // delta from user is in seconds
     let timeout_delta = match msg.timeout {
         Some(t) => t,
@@ -130,18 +160,40 @@ pub fn execute_transfer(
     let timeout = env.block.time.plus_seconds(timeout_delta);
 
     // build ics20 packet
+    let memo = forward_memo(&msg.forward, msg.memo)?;
     let packet = Ics20Packet::new(
         amount.amount(),
         amount.denom(),
         sender.as_ref(),
         &msg.remote_address,
-    );
+    )
+    .with_memo(memo);
     packet.validate()?;
 
+    // failed transfers still count towards the limit, so a compromised channel cannot drain
+    // more than the limit by making transfers fail
+    apply_rate_limit(
+        deps.storage,
+        env.block.time,
+        &msg.channel,
+        &amount.denom(),
+        amount.amount(),
+    )?;
+
     // Update the balance now (optimistically) like ibctransfer modules.
     // In on_packet_failure (ack with error message or a timeout), we reduce the balance appropriately.
     // This means the channel works fine if success acks are not relayed.
     increase_channel_balance(deps.storage, &msg.channel, &amount.denom(), amount.amount())?;
+    record_in_flight(
+        deps.storage,
+        &msg.channel,
+        &InFlightPacket {
+            denom: amount.denom(),
+            amount: amount.amount(),
+            sender: packet.sender.clone(),
+            timeout,
+        },
+    )?;
 
     // prepare ibc message
     let msg = IbcMsg::SendPacket {
@@ -158,6 +210,10 @@ pub fn execute_transfer(
         .add_attribute("receiver", &packet.receiver)
         .add_attribute("denom", &packet.denom)
         .add_attribute("amount", &packet.amount.to_string());
+    let res = match &packet.memo {
+        Some(memo) => res.add_attribute("memo", memo),
+        None => res,
+    };
     Ok(res)
 }
 
@@ -202,11 +258,198 @@ pub fn execute_allow(
     Ok(res)
 }
 
+/// Fails if the channel is restricted to an allowlist without this denom, or if the transfer
+/// would bring its outstanding balance over the cap
+fn assert_allowed_on_channel(
+    storage: &dyn Storage,
+    channel: &str,
+    amount: &Amount,
+) -> Result<(), ContractError> {
+    let denom = amount.denom();
+    let allow = match CHANNEL_ALLOW_LIST.may_load(storage, (channel, &denom))? {
+        Some(allow) => allow,
+        None if RESTRICTED_CHANNELS.has(storage, channel) => {
+            return Err(ContractError::NotAllowedOnChannel {
+                channel: channel.to_string(),
+                denom,
+            })
+        }
+        None => return Ok(()),
+    };
+    if let Some(max) = allow.max_outstanding {
+        let outstanding = CHANNEL_STATE
+            .may_load(storage, (channel, &denom))?
+            .unwrap_or_default()
+            .outstanding;
+        let available = max.saturating_sub(outstanding);
+        if amount.amount() > available {
+            return Err(ContractError::ChannelCapExceeded { available });
+        }
+    }
+    Ok(())
+}
+
+pub fn execute_allow_on_channel(
+    deps: DepsMut,
+    info: MessageInfo,
+    allow: AllowOnChannelMsg,
+) -> Result<Response, ContractError> {
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+
+    let set = ChannelAllowInfo {
+        max_outstanding: allow.max_outstanding,
+    };
+    CHANNEL_ALLOW_LIST.save(deps.storage, (&allow.channel, &allow.denom), &set)?;
+    RESTRICTED_CHANNELS.save(deps.storage, &allow.channel, &Empty {})?;
+
+    let max_outstanding = match allow.max_outstanding {
+        Some(max) => max.to_string(),
+        None => "None".to_string(),
+    };
+    let res = Response::new()
+        .add_attribute("action", "allow_on_channel")
+        .add_attribute("channel", allow.channel)
+        .add_attribute("denom", allow.denom)
+        .add_attribute("max_outstanding", max_outstanding);
+    Ok(res)
+}
+
+pub fn execute_disallow_on_channel(
+    deps: DepsMut,
+    info: MessageInfo,
+    channel: String,
+    denom: String,
+) -> Result<Response, ContractError> {
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+    CHANNEL_ALLOW_LIST.remove(deps.storage, (&channel, &denom));
+
+    let res = Response::new()
+        .add_attribute("action", "disallow_on_channel")
+        .add_attribute("channel", channel)
+        .add_attribute("denom", denom);
+    Ok(res)
+}
+
+/// Lifts the restriction of the channel, which stays restricted when the last denom of its
+/// allowlist is disallowed
+pub fn execute_clear_channel_allow_list(
+    deps: DepsMut,
+    info: MessageInfo,
+    channel: String,
+) -> Result<Response, ContractError> {
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+    let denoms = CHANNEL_ALLOW_LIST
+        .prefix(&channel)
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for denom in denoms {
+        CHANNEL_ALLOW_LIST.remove(deps.storage, (&channel, &denom));
+    }
+    RESTRICTED_CHANNELS.remove(deps.storage, &channel);
+
+    let res = Response::new()
+        .add_attribute("action", "clear_channel_allow_list")
+        .add_attribute("channel", channel);
+    Ok(res)
+}
+
+/// The gov contract can set, change or remove the rate limit of a channel for any denom.
+/// Transfers already made keep counting towards a changed limit.
+pub fn execute_set_rate_limit(
+    deps: DepsMut,
+    info: MessageInfo,
+    channel: String,
+    denom: String,
+    limit: Option<RateLimit>,
+) -> Result<Response, ContractError> {
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+
+    let res = Response::new()
+        .add_attribute("action", "set_rate_limit")
+        .add_attribute("channel", &channel)
+        .add_attribute("denom", &denom);
+    let res = match limit {
+        Some(limit) => {
+            if limit.window == 0 {
+                return Err(ContractError::InvalidRateLimitWindow);
+            }
+            RATE_LIMITS.save(deps.storage, (&channel, &denom), &limit)?;
+            res.add_attribute("max_amount", limit.max_amount)
+                .add_attribute("window", limit.window.to_string())
+        }
+        None => {
+            RATE_LIMITS.remove(deps.storage, (&channel, &denom));
+            RATE_LIMIT_USAGE.remove(deps.storage, (&channel, &denom));
+            res.add_attribute("max_amount", "None")
+        }
+    };
+    Ok(res)
+}
+
 const MIGRATE_MIN_VERSION: &str = "0.11.1";
 const MIGRATE_VERSION_2: &str = "0.12.0-alpha1";
 // the new functionality starts in 0.13.1, this is the last release that needs to be migrated to v3
 const MIGRATE_VERSION_3: &str = "0.13.0";
 
+/// The gov contract can close a compromised channel for good, rejecting any further transfer over it.
+/// Tokens coming back over it are still accepted
+pub fn execute_close_channel(
+    deps: DepsMut,
+    info: MessageInfo,
+    channel: String,
+) -> Result<Response, ContractError> {
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+    if !CHANNEL_INFO.has(deps.storage, &channel) {
+        return Err(ContractError::NoSuchChannel { id: channel });
+    }
+    CLOSED_CHANNELS.save(deps.storage, &channel, &Empty {})?;
+
+    Ok(Response::new()
+        .add_attribute("action", "close_channel")
+        .add_attribute("channel", channel))
+}
+
+/// Once a closed channel will not relay a packet's timeout anymore, the gov contract can refund
+/// its sender, as the timeout would have
+pub fn execute_recover_packet(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    channel: String,
+    sequence: u64,
+) -> Result<Response, ContractError> {
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+    if !CLOSED_CHANNELS.has(deps.storage, &channel) {
+        return Err(ContractError::ChannelNotClosed { channel });
+    }
+    let packet = IN_FLIGHT_PACKETS
+        .may_load(deps.storage, (&channel, sequence))?
+        .ok_or_else(|| ContractError::NoSuchInFlightPacket {
+            channel: channel.clone(),
+            sequence,
+        })?;
+    if env.block.time < packet.timeout {
+        return Err(ContractError::PacketNotTimedOut { channel, sequence });
+    }
+
+    reduce_channel_balance(deps.storage, &channel, &packet.denom, packet.amount)?;
+    remove_in_flight(deps.storage, &channel, sequence)?;
+    RECOVERED_PACKETS.save(deps.storage, (&channel, sequence), &Empty {})?;
+
+    let refund = send_amount(
+        Amount::from_parts(packet.denom.clone(), packet.amount),
+        packet.sender.clone(),
+    );
+    Ok(Response::new()
+        .add_message(refund)
+        .add_attribute("action", "recover_packet")
+        .add_attribute("channel", channel)
+        .add_attribute("sequence", sequence.to_string())
+        .add_attribute("sender", packet.sender)
+        .add_attribute("denom", packet.denom)
+        .add_attribute("amount", packet.amount))
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn migrate(mut deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
     let version: Version = CONTRACT_VERSION.parse().map_err(from_semver)?;
@@ -247,6 +490,29 @@ pub fn migrate(mut deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response,
     if storage_version <= MIGRATE_VERSION_3.parse().map_err(from_semver)? {
         v2::update_balances(deps.branch(), &env)?;
     }
+    // rate limits used to be counted over fixed windows
+    fixed_rate_limits::migrate_windows(deps.storage, env.block.time)?;
+    // channels were restricted as long as their allowlist had entries
+    let restricted = CHANNEL_ALLOW_LIST
+        .keys(deps.storage, None, None, Order::Ascending)
+        .map(|key| key.map(|(channel, _)| channel))
+        .collect::<StdResult<Vec<_>>>()?;
+    for channel in restricted {
+        RESTRICTED_CHANNELS.save(deps.storage, &channel, &Empty {})?;
+    }
+    // channels connected before in-flight packets were tracked need their next sequence
+    for next in msg.next_sequences {
+        CHANNEL_INFO.load(deps.storage, &next.channel)?;
+        NEXT_SEQUENCE.save(deps.storage, &next.channel, &next.sequence)?;
+    }
+    for channel in CHANNEL_INFO.keys(deps.storage, None, None, Order::Ascending) {
+        let channel = channel?;
+        if !NEXT_SEQUENCE.has(deps.storage, &channel)
+            && !CLOSED_CHANNELS.has(deps.storage, &channel)
+        {
+            return Err(ContractError::UntrackedChannel { channel });
+        }
+    }
     // otherwise no migration (yet) - add them here
 
     // always allow setting the default gas limit via MigrateMsg, even if same version
@@ -271,7 +537,7 @@ fn from_semver(err: semver::Error) -> StdError {
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Port {} => to_binary(&query_port(deps)?),
         QueryMsg::ListChannels {} => to_binary(&query_list(deps)?),
@@ -281,7 +547,24 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::ListAllowed { start_after, limit } => {
             to_binary(&list_allowed(deps, start_after, limit)?)
         }
+        QueryMsg::ChannelAllowed { channel, denom } => {
+            to_binary(&query_channel_allowed(deps, channel, denom)?)
+        }
+        QueryMsg::ListChannelAllowed {
+            channel,
+            start_after,
+            limit,
+        } => to_binary(&list_channel_allowed(deps, channel, start_after, limit)?),
+        QueryMsg::RateLimit { channel, denom } => {
+            to_binary(&query_rate_limit(deps, env, channel, denom)?)
+        }
         QueryMsg::Admin {} => to_binary(&ADMIN.query_admin(deps)?),
+        QueryMsg::InFlightPackets {
+            channel,
+            start_after,
+            limit,
+        } => to_binary(&list_in_flight_packets(deps, channel, start_after, limit)?),
+        QueryMsg::InFlightTotals { channel } => to_binary(&query_in_flight_totals(deps, channel)?),
     }
 }
 
@@ -317,9 +600,12 @@ pub fn query_channel(deps: Deps, id: String) -> StdResult<ChannelResponse> {
     // we want (Vec<outstanding>, Vec<total>)
     let (balances, total_sent) = state.into_iter().unzip();
 
+    let closed = CLOSED_CHANNELS.has(deps.storage, &id);
+
     Ok(ChannelResponse {
         info,
         balances,
+        closed,
         total_sent,
     })
 }
@@ -377,15 +663,138 @@ fn list_allowed(
     Ok(ListAllowedResponse { allow })
 }
 
+fn query_channel_allowed(
+    deps: Deps,
+    channel: String,
+    denom: String,
+) -> StdResult<ChannelAllowedResponse> {
+    let outstanding = CHANNEL_STATE
+        .may_load(deps.storage, (&channel, &denom))?
+        .unwrap_or_default()
+        .outstanding;
+    let res = match CHANNEL_ALLOW_LIST.may_load(deps.storage, (&channel, &denom))? {
+        Some(allow) => ChannelAllowedResponse {
+            is_allowed: true,
+            max_outstanding: allow.max_outstanding,
+            outstanding,
+        },
+        None => ChannelAllowedResponse {
+            is_allowed: !RESTRICTED_CHANNELS.has(deps.storage, &channel),
+            max_outstanding: None,
+            outstanding,
+        },
+    };
+    Ok(res)
+}
+
+fn list_channel_allowed(
+    deps: Deps,
+    channel: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<ListChannelAllowedResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.as_deref().map(Bound::exclusive);
+
+    let allow = CHANNEL_ALLOW_LIST
+        .prefix(&channel)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (denom, allow) = item?;
+            let outstanding = CHANNEL_STATE
+                .may_load(deps.storage, (&channel, &denom))?
+                .unwrap_or_default()
+                .outstanding;
+            Ok(ChannelAllowedInfo {
+                denom,
+                max_outstanding: allow.max_outstanding,
+                outstanding,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(ListChannelAllowedResponse { allow })
+}
+
+fn query_rate_limit(
+    deps: Deps,
+    env: Env,
+    channel: String,
+    denom: String,
+) -> StdResult<RateLimitResponse> {
+    let res = match current_rate_limit_usage(deps.storage, env.block.time, &channel, &denom)? {
+        None => RateLimitResponse {
+            limit: None,
+            sent: Uint128::zero(),
+            remaining: None,
+            next_release: None,
+        },
+        Some((limit, usage)) => {
+            let sent = usage.sent();
+            let next_release = usage
+                .buckets
+                .iter()
+                .map(|bucket| limit.bucket_expires(bucket.start))
+                .min();
+            RateLimitResponse {
+                sent,
+                remaining: Some(limit.max_amount.saturating_sub(sent)),
+                next_release,
+                limit: Some(limit),
+            }
+        }
+    };
+    Ok(res)
+}
+
+fn list_in_flight_packets(
+    deps: Deps,
+    channel: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<InFlightPacketsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let packets = IN_FLIGHT_PACKETS
+        .prefix(&channel)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            item.map(|(sequence, packet)| InFlightPacketInfo {
+                sequence,
+                denom: packet.denom,
+                amount: packet.amount,
+                sender: packet.sender,
+                timeout: packet.timeout,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(InFlightPacketsResponse { packets })
+}
+
+fn query_in_flight_totals(deps: Deps, channel: String) -> StdResult<InFlightTotalsResponse> {
+    let packets = IN_FLIGHT_COUNT
+        .may_load(deps.storage, &channel)?
+        .unwrap_or_default();
+    let amounts = IN_FLIGHT_AMOUNTS
+        .prefix(&channel)
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(denom, amount)| Amount::from_parts(denom, amount)))
+        .collect::<StdResult<_>>()?;
+    Ok(InFlightTotalsResponse { packets, amounts })
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     use crate::test_helpers::*;
 
     use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
-    use cosmwasm_std::{coin, coins, CosmosMsg, IbcMsg, StdError, Uint128};
+    use cosmwasm_std::{attr, coin, coins, CosmosMsg, IbcMsg, StdError, Uint128};
 
+    use crate::msg::{ForwardHop, NextSequence, TransferMsg};
     use crate::state::ChannelState;
+    use cw_controllers::AdminError;
     use cw_utils::PaymentError;
 
     #[test]
@@ -431,6 +840,8 @@ mod test {
             channel: send_channel.to_string(),
             remote_address: "foreign-address".to_string(),
             timeout: None,
+            memo: None,
+            forward: vec![],
         };
 
         // works with proper funds
@@ -453,9 +864,61 @@ mod test {
             assert_eq!(msg.denom.as_str(), "ucosm");
             assert_eq!(msg.sender.as_str(), "foobar");
             assert_eq!(msg.receiver.as_str(), "foreign-address");
+            assert_eq!(msg.memo, None);
+        } else {
+            panic!("Unexpected return message: {:?}", res.messages[0]);
+        }
+
+        // the memo is passed on in the packet
+        let memo =
+            r#"{"forward":{"receiver":"final-rcpt","port":"transfer","channel":"channel-7"}}"#;
+        let msg = ExecuteMsg::Transfer(TransferMsg {
+            memo: Some(memo.to_string()),
+            ..transfer.clone()
+        });
+        let info = mock_info("foobar", &coins(1234567, "ucosm"));
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert!(res.attributes.contains(&attr("memo", memo)));
+        if let CosmosMsg::Ibc(IbcMsg::SendPacket { data, .. }) = &res.messages[0].msg {
+            let msg: Ics20Packet = from_binary(data).unwrap();
+            assert_eq!(msg.memo.as_deref(), Some(memo));
+        } else {
+            panic!("Unexpected return message: {:?}", res.messages[0]);
+        }
+
+        // forwarding paths end up in the memo too, and are validated
+        let hop = ForwardHop {
+            channel: "channel-7".to_string(),
+            receiver: "final-rcpt".to_string(),
+            port: None,
+            timeout: Some(600),
+            retries: None,
+        };
+        let msg = ExecuteMsg::Transfer(TransferMsg {
+            forward: vec![hop.clone()],
+            ..transfer.clone()
+        });
+        let info = mock_info("foobar", &coins(1234567, "ucosm"));
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        if let CosmosMsg::Ibc(IbcMsg::SendPacket { data, .. }) = &res.messages[0].msg {
+            let msg: Ics20Packet = from_binary(data).unwrap();
+            assert_eq!(
+                msg.memo.unwrap(),
+                r#"{"forward":{"receiver":"final-rcpt","port":"transfer","channel":"channel-7","timeout":"600s"}}"#
+            );
         } else {
             panic!("Unexpected return message: {:?}", res.messages[0]);
         }
+        let msg = ExecuteMsg::Transfer(TransferMsg {
+            forward: vec![ForwardHop {
+                receiver: "".to_string(),
+                ..hop
+            }],
+            ..transfer.clone()
+        });
+        let info = mock_info("foobar", &coins(1234567, "ucosm"));
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::EmptyForwardReceiver {});
 
         // reject with no funds
         let msg = ExecuteMsg::Transfer(transfer.clone());
@@ -492,6 +955,8 @@ mod test {
             channel: send_channel.to_string(),
             remote_address: "foreign-address".to_string(),
             timeout: Some(7777),
+            memo: None,
+            forward: vec![],
         };
         let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
             sender: "my-account".into(),
@@ -528,6 +993,341 @@ mod test {
         assert_eq!(err, ContractError::Payment(PaymentError::NonPayable {}));
     }
 
+    #[test]
+    fn allowlist_per_channel_and_denom() {
+        let send_channel = "channel-5";
+        let mut deps = setup(&[send_channel, "channel-10"], &[]);
+
+        let allow = |denom: &str, max_outstanding: Option<u128>| {
+            ExecuteMsg::AllowOnChannel(AllowOnChannelMsg {
+                channel: send_channel.to_string(),
+                denom: denom.to_string(),
+                max_outstanding: max_outstanding.map(Uint128::new),
+            })
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("foobar", &[]),
+            allow("ucosm", Some(1000)),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Admin(AdminError::NotAdmin {}));
+        let gov = mock_info("gov", &[]);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            gov.clone(),
+            allow("ucosm", Some(1000)),
+        )
+        .unwrap();
+        execute(deps.as_mut(), mock_env(), gov.clone(), allow("uatom", None)).unwrap();
+
+        let transfer = |channel: &str| {
+            ExecuteMsg::Transfer(TransferMsg {
+                channel: channel.to_string(),
+                remote_address: "foreign-address".to_string(),
+                timeout: None,
+                memo: None,
+                forward: vec![],
+            })
+        };
+        let send = |deps: DepsMut, channel: &str, amount: u128, denom: &str| {
+            execute(
+                deps,
+                mock_env(),
+                mock_info("foobar", &coins(amount, denom)),
+                transfer(channel),
+            )
+        };
+
+        // only allowed denoms go over the channel, up to their cap
+        let err = send(deps.as_mut(), send_channel, 100, "ujuno").unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::NotAllowedOnChannel {
+                channel: send_channel.to_string(),
+                denom: "ujuno".to_string(),
+            }
+        );
+        send(deps.as_mut(), send_channel, 800, "ucosm").unwrap();
+        let err = send(deps.as_mut(), send_channel, 201, "ucosm").unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::ChannelCapExceeded {
+                available: Uint128::new(200)
+            }
+        );
+        send(deps.as_mut(), send_channel, 5000, "uatom").unwrap();
+        // channels without an allowlist are not restricted
+        send(deps.as_mut(), "channel-10", 5000, "ujuno").unwrap();
+
+        let query_allowed = |deps: Deps, channel: &str, denom: &str| -> ChannelAllowedResponse {
+            let msg = QueryMsg::ChannelAllowed {
+                channel: channel.to_string(),
+                denom: denom.to_string(),
+            };
+            from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap()
+        };
+        assert_eq!(
+            query_allowed(deps.as_ref(), send_channel, "ucosm"),
+            ChannelAllowedResponse {
+                is_allowed: true,
+                max_outstanding: Some(Uint128::new(1000)),
+                outstanding: Uint128::new(800),
+            }
+        );
+        assert!(!query_allowed(deps.as_ref(), send_channel, "ujuno").is_allowed);
+        assert!(query_allowed(deps.as_ref(), "channel-10", "ujuno").is_allowed);
+        let msg = QueryMsg::ListChannelAllowed {
+            channel: send_channel.to_string(),
+            start_after: None,
+            limit: None,
+        };
+        let list: ListChannelAllowedResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(
+            list.allow,
+            vec![
+                ChannelAllowedInfo {
+                    denom: "uatom".to_string(),
+                    max_outstanding: None,
+                    outstanding: Uint128::new(5000),
+                },
+                ChannelAllowedInfo {
+                    denom: "ucosm".to_string(),
+                    max_outstanding: Some(Uint128::new(1000)),
+                    outstanding: Uint128::new(800),
+                },
+            ]
+        );
+
+        // raising the cap and disallowing
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            gov.clone(),
+            allow("ucosm", Some(2000)),
+        )
+        .unwrap();
+        send(deps.as_mut(), send_channel, 201, "ucosm").unwrap();
+        let disallow = |denom: &str| ExecuteMsg::DisallowOnChannel {
+            channel: send_channel.to_string(),
+            denom: denom.to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), gov.clone(), disallow("uatom")).unwrap();
+        let err = send(deps.as_mut(), send_channel, 1, "uatom").unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::NotAllowedOnChannel {
+                channel: send_channel.to_string(),
+                denom: "uatom".to_string(),
+            }
+        );
+
+        // the channel stays restricted once its allowlist is empty
+        execute(deps.as_mut(), mock_env(), gov.clone(), disallow("ucosm")).unwrap();
+        let err = send(deps.as_mut(), send_channel, 1, "ujuno").unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::NotAllowedOnChannel {
+                channel: send_channel.to_string(),
+                denom: "ujuno".to_string(),
+            }
+        );
+        assert!(!query_allowed(deps.as_ref(), send_channel, "ujuno").is_allowed);
+
+        // until its allowlist is cleared
+        let clear = ExecuteMsg::ClearChannelAllowList {
+            channel: send_channel.to_string(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("foobar", &[]),
+            clear.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Admin(AdminError::NotAdmin {}));
+        execute(deps.as_mut(), mock_env(), gov, clear).unwrap();
+        send(deps.as_mut(), send_channel, 1, "ujuno").unwrap();
+        assert!(query_allowed(deps.as_ref(), send_channel, "ujuno").is_allowed);
+    }
+
+    #[test]
+    fn rate_limits_per_channel_and_denom() {
+        let send_channel = "channel-5";
+        let mut deps = setup(&[send_channel, "channel-10"], &[]);
+
+        let set_limit = |channel: &str, limit: Option<RateLimit>| ExecuteMsg::SetRateLimit {
+            channel: channel.to_string(),
+            denom: "ucosm".to_string(),
+            limit,
+        };
+        let limit = RateLimit {
+            max_amount: Uint128::new(1000),
+            window: 86400,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("foobar", &[]),
+            set_limit(send_channel, Some(limit.clone())),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Admin(AdminError::NotAdmin {}));
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("gov", &[]),
+            set_limit(
+                send_channel,
+                Some(RateLimit {
+                    max_amount: Uint128::new(1000),
+                    window: 0,
+                }),
+            ),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidRateLimitWindow);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("gov", &[]),
+            set_limit(send_channel, Some(limit.clone())),
+        )
+        .unwrap();
+
+        let transfer = |channel: &str| {
+            ExecuteMsg::Transfer(TransferMsg {
+                channel: channel.to_string(),
+                remote_address: "foreign-address".to_string(),
+                timeout: None,
+                memo: None,
+                forward: vec![],
+            })
+        };
+        let mut env = mock_env();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("foobar", &coins(600, "ucosm")),
+            transfer(send_channel),
+        )
+        .unwrap();
+        env.block.time = env.block.time.plus_seconds(3600);
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("foobar", &coins(401, "ucosm")),
+            transfer(send_channel),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::RateLimitExceeded {
+                remaining: Uint128::new(400)
+            }
+        );
+        // other channels and denoms are not limited
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("foobar", &coins(401, "ucosm")),
+            transfer("channel-10"),
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("foobar", &coins(401, "uatom")),
+            transfer(send_channel),
+        )
+        .unwrap();
+
+        let query_limit = |deps: Deps, env: Env| -> RateLimitResponse {
+            let msg = QueryMsg::RateLimit {
+                channel: send_channel.to_string(),
+                denom: "ucosm".to_string(),
+            };
+            from_binary(&query(deps, env, msg).unwrap()).unwrap()
+        };
+        let first_release = limit.bucket_expires(limit.bucket_start(mock_env().block.time));
+        assert_eq!(
+            query_limit(deps.as_ref(), env.clone()),
+            RateLimitResponse {
+                limit: Some(limit.clone()),
+                sent: Uint128::new(600),
+                remaining: Some(Uint128::new(400)),
+                next_release: Some(first_release),
+            }
+        );
+
+        // the window is rolling, so the limit cannot be sent twice around the end of a window
+        env.block.time = mock_env().block.time.plus_seconds(86399);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("foobar", &coins(400, "ucosm")),
+            transfer(send_channel),
+        )
+        .unwrap();
+        env.block.time = mock_env().block.time.plus_seconds(86401);
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("foobar", &coins(1, "ucosm")),
+            transfer(send_channel),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::RateLimitExceeded {
+                remaining: Uint128::zero()
+            }
+        );
+
+        // transfers stop counting once their bucket is out of the window
+        env.block.time = first_release;
+        assert_eq!(
+            query_limit(deps.as_ref(), env.clone()),
+            RateLimitResponse {
+                limit: Some(limit.clone()),
+                sent: Uint128::new(400),
+                remaining: Some(Uint128::new(600)),
+                next_release: Some(
+                    limit.bucket_expires(
+                        limit.bucket_start(mock_env().block.time.plus_seconds(86399))
+                    )
+                ),
+            }
+        );
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("foobar", &coins(600, "ucosm")),
+            transfer(send_channel),
+        )
+        .unwrap();
+
+        // and the limit can be removed
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("gov", &[]),
+            set_limit(send_channel, None),
+        )
+        .unwrap();
+        assert_eq!(query_limit(deps.as_ref(), env.clone()).limit, None);
+        execute(
+            deps.as_mut(),
+            env,
+            mock_info("foobar", &coins(5000, "ucosm")),
+            transfer(send_channel),
+        )
+        .unwrap();
+    }
+
     #[test]
     fn execute_cw20_fails_if_not_whitelisted_unless_default_gas_limit() {
         let send_channel = "channel-15";
@@ -538,6 +1338,8 @@ mod test {
             channel: send_channel.to_string(),
             remote_address: "foreign-address".to_string(),
             timeout: Some(7777),
+            memo: None,
+            forward: vec![],
         };
         let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
             sender: "my-account".into(),
@@ -556,6 +1358,7 @@ mod test {
             mock_env(),
             MigrateMsg {
                 default_gas_limit: Some(123456),
+                next_sequences: vec![],
             },
         )
         .unwrap();
@@ -595,6 +1398,7 @@ mod test {
             mock_env(),
             MigrateMsg {
                 default_gas_limit: Some(123456),
+                next_sequences: vec![],
             },
         )
         .unwrap();
@@ -608,4 +1412,127 @@ mod test {
         let config = query_config(deps.as_ref()).unwrap();
         assert_eq!(config.default_gas_limit, Some(123456));
     }
+
+    #[test]
+    fn migration_restricts_channels_with_allowlist() {
+        let send_channel = "channel-5";
+        let mut deps = setup(&[send_channel, "channel-10"], &[]);
+        // allowlist entries saved before channels were flagged as restricted
+        let allow = ChannelAllowInfo {
+            max_outstanding: None,
+        };
+        CHANNEL_ALLOW_LIST
+            .save(deps.as_mut().storage, (send_channel, "ucosm"), &allow)
+            .unwrap();
+
+        let msg = MigrateMsg {
+            default_gas_limit: None,
+            next_sequences: vec![],
+        };
+        migrate(deps.as_mut(), mock_env(), msg).unwrap();
+        assert!(RESTRICTED_CHANNELS.has(deps.as_ref().storage, send_channel));
+        assert!(!RESTRICTED_CHANNELS.has(deps.as_ref().storage, "channel-10"));
+    }
+
+    #[test]
+    fn migration_seeds_next_sequences() {
+        let send_channel = "channel-5";
+        let mut deps = setup(&[send_channel, "channel-10"], &[]);
+        // channels connected before their sequence was tracked, one of them closed since
+        NEXT_SEQUENCE.remove(deps.as_mut().storage, send_channel);
+        NEXT_SEQUENCE.remove(deps.as_mut().storage, "channel-10");
+        CLOSED_CHANNELS
+            .save(deps.as_mut().storage, "channel-10", &Empty {})
+            .unwrap();
+
+        // transfers over them cannot be tracked
+        let transfer = ExecuteMsg::Transfer(TransferMsg {
+            channel: send_channel.to_string(),
+            remote_address: "foreign-address".to_string(),
+            timeout: None,
+            memo: None,
+            forward: vec![],
+        });
+        let info = mock_info("local-sender", &coins(1234, "ucosm"));
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), transfer.clone()).unwrap_err();
+        let untracked = ContractError::UntrackedChannel {
+            channel: send_channel.to_string(),
+        };
+        assert_eq!(err, untracked);
+
+        // so migrating requires the sequence of every open channel
+        let msg = |next_sequences| MigrateMsg {
+            default_gas_limit: None,
+            next_sequences,
+        };
+        let err = migrate(deps.as_mut(), mock_env(), msg(vec![])).unwrap_err();
+        assert_eq!(err, untracked);
+        let unknown = NextSequence {
+            channel: "channel-3".to_string(),
+            sequence: 1,
+        };
+        migrate(deps.as_mut(), mock_env(), msg(vec![unknown])).unwrap_err();
+        let next = NextSequence {
+            channel: send_channel.to_string(),
+            sequence: 7,
+        };
+        migrate(deps.as_mut(), mock_env(), msg(vec![next])).unwrap();
+
+        execute(deps.as_mut(), mock_env(), info, transfer).unwrap();
+        let packets = list_in_flight_packets(deps.as_ref(), send_channel.to_string(), None, None)
+            .unwrap()
+            .packets;
+        assert_eq!(packets.len(), 1);
+        assert_eq!(packets[0].sequence, 7);
+    }
+
+    #[test]
+    fn migration_counts_fixed_rate_limit_windows() {
+        let send_channel = "channel-5";
+        let mut deps = setup(&[send_channel], &[]);
+        let limit = RateLimit {
+            max_amount: Uint128::new(1000),
+            window: 86400,
+        };
+        let now = mock_env().block.time;
+        RATE_LIMITS
+            .save(deps.as_mut().storage, (send_channel, "ucosm"), &limit)
+            .unwrap();
+        // a fixed window still running, and one over
+        let window = fixed_rate_limits::RateLimitWindow {
+            ends: now.plus_seconds(10),
+            sent: Uint128::new(700),
+        };
+        fixed_rate_limits::RATE_LIMIT_WINDOWS
+            .save(deps.as_mut().storage, (send_channel, "ucosm"), &window)
+            .unwrap();
+        let window = fixed_rate_limits::RateLimitWindow {
+            ends: now,
+            sent: Uint128::new(100),
+        };
+        fixed_rate_limits::RATE_LIMIT_WINDOWS
+            .save(deps.as_mut().storage, (send_channel, "uatom"), &window)
+            .unwrap();
+
+        let msg = MigrateMsg {
+            default_gas_limit: None,
+            next_sequences: vec![],
+        };
+        migrate(deps.as_mut(), mock_env(), msg).unwrap();
+
+        // what was sent in the running window counts for a whole rolling window
+        let res = query_rate_limit(
+            deps.as_ref(),
+            mock_env(),
+            send_channel.to_string(),
+            "ucosm".to_string(),
+        )
+        .unwrap();
+        assert_eq!(res.remaining, Some(Uint128::new(300)));
+        assert_eq!(
+            res.next_release,
+            Some(limit.bucket_expires(limit.bucket_start(now)))
+        );
+        assert!(fixed_rate_limits::RATE_LIMIT_WINDOWS.is_empty(deps.as_ref().storage));
+    }
 }
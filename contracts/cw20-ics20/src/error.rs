@@ -2,7 +2,7 @@ use std::num::TryFromIntError;
 use std::string::FromUtf8Error;
 use thiserror::Error;
 
-use cosmwasm_std::StdError;
+use cosmwasm_std::{StdError, Uint128};
 use cw_controllers::AdminError;
 use cw_utils::PaymentError;
 
@@ -65,6 +65,47 @@ pub enum ContractError {
 
     #[error("You can only send cw20 tokens that have been explicitly allowed by governance")]
     NotOnAllowList,
+
+    #[error("Denom {denom} is not allowed on channel {channel}")]
+    NotAllowedOnChannel { channel: String, denom: String },
+
+    #[error(
+        "Transfer exceeds the max outstanding balance of the channel, only {available} can be sent"
+    )]
+    ChannelCapExceeded { available: Uint128 },
+
+    #[error("Invalid channel to forward on: {channel}")]
+    InvalidForwardChannel { channel: String },
+
+    #[error("Receiver to forward to cannot be empty")]
+    EmptyForwardReceiver {},
+
+    #[error("Forward timeout cannot be zero")]
+    InvalidForwardTimeout {},
+
+    #[error("Cannot forward over more than {max} hops")]
+    TooManyHops { max: usize },
+
+    #[error("Transfer exceeds the rate limit of the channel, only {remaining} can be sent in the current window")]
+    RateLimitExceeded { remaining: Uint128 },
+
+    #[error("Rate limit window cannot be empty")]
+    InvalidRateLimitWindow,
+
+    #[error("Channel {channel} is closed")]
+    ChannelClosed { channel: String },
+
+    #[error("Channel {channel} must be closed first")]
+    ChannelNotClosed { channel: String },
+
+    #[error("No packet {sequence} in flight on channel {channel}")]
+    NoSuchInFlightPacket { channel: String, sequence: u64 },
+
+    #[error("Packet {sequence} on channel {channel} has not timed out yet")]
+    PacketNotTimedOut { channel: String, sequence: u64 },
+
+    #[error("The next sequence of channel {channel} must be given on migration")]
+    UntrackedChannel { channel: String },
 }
 
 impl From<FromUtf8Error> for ContractError {
@@ -0,0 +1,173 @@
+use serde::Serialize;
+
+use cosmwasm_std::to_vec;
+
+use crate::error::ContractError;
+use crate::msg::ForwardHop;
+
+/// Longest forwarding path we build a memo for
+pub const MAX_HOPS: usize = 5;
+
+const DEFAULT_FORWARD_PORT: &str = "transfer";
+
+/// The memo format of the packet-forward-middleware.
+/// Defined here: https://github.com/strangelove-ventures/packet-forward-middleware#full-example
+#[derive(Serialize, Debug)]
+struct PacketMetadata {
+    forward: ForwardMetadata,
+}
+
+#[derive(Serialize, Debug)]
+struct ForwardMetadata {
+    receiver: String,
+    port: String,
+    channel: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timeout: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retries: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next: Option<Next>,
+}
+
+/// The middleware accepts the memo for the next hop as an object or as a JSON string
+#[derive(Serialize, Debug)]
+#[serde(untagged)]
+enum Next {
+    Forward(Box<PacketMetadata>),
+    Memo(String),
+}
+
+fn validate_hop(hop: &ForwardHop) -> Result<(), ContractError> {
+    let valid_channel = match hop.channel.strip_prefix("channel-") {
+        Some(id) => !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit()),
+        None => false,
+    };
+    if !valid_channel {
+        return Err(ContractError::InvalidForwardChannel {
+            channel: hop.channel.clone(),
+        });
+    }
+    if hop.receiver.is_empty() {
+        return Err(ContractError::EmptyForwardReceiver {});
+    }
+    if hop.timeout == Some(0) {
+        return Err(ContractError::InvalidForwardTimeout {});
+    }
+    Ok(())
+}
+
+/// Builds the packet memo forwarding the tokens along the given hops. The memo set by the sender,
+/// if any, is passed on to the last hop. Without hops, that memo is used as is.
+pub fn forward_memo(
+    hops: &[ForwardHop],
+    memo: Option<String>,
+) -> Result<Option<String>, ContractError> {
+    if hops.is_empty() {
+        return Ok(memo);
+    }
+    if hops.len() > MAX_HOPS {
+        return Err(ContractError::TooManyHops { max: MAX_HOPS });
+    }
+
+    // nest the hops from the last one
+    let mut next = memo.map(Next::Memo);
+    for hop in hops.iter().rev() {
+        validate_hop(hop)?;
+        let metadata = PacketMetadata {
+            forward: ForwardMetadata {
+                receiver: hop.receiver.clone(),
+                port: hop
+                    .port
+                    .clone()
+                    .unwrap_or_else(|| DEFAULT_FORWARD_PORT.to_string()),
+                channel: hop.channel.clone(),
+                timeout: hop.timeout.map(|t| format!("{}s", t)),
+                retries: hop.retries,
+                next,
+            },
+        };
+        next = Some(Next::Forward(Box::new(metadata)));
+    }
+
+    match next {
+        Some(Next::Forward(metadata)) => Ok(Some(String::from_utf8(to_vec(&metadata)?)?)),
+        _ => unreachable!("there is at least one hop"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn hop(channel: &str, receiver: &str) -> ForwardHop {
+        ForwardHop {
+            channel: channel.to_string(),
+            receiver: receiver.to_string(),
+            port: None,
+            timeout: None,
+            retries: None,
+        }
+    }
+
+    #[test]
+    fn builds_forward_memo() {
+        // no hops leaves the memo alone
+        assert_eq!(forward_memo(&[], None).unwrap(), None);
+        assert_eq!(
+            forward_memo(&[], Some("hello".to_string())).unwrap(),
+            Some("hello".to_string())
+        );
+
+        let memo = forward_memo(&[hop("channel-7", "osmo1rcpt")], None).unwrap();
+        assert_eq!(
+            memo.unwrap(),
+            r#"{"forward":{"receiver":"osmo1rcpt","port":"transfer","channel":"channel-7"}}"#
+        );
+
+        // hops are nested in order, with the sender's memo for the last one
+        let first = ForwardHop {
+            port: Some("wasm.juno1gateway".to_string()),
+            timeout: Some(600),
+            retries: Some(2),
+            ..hop("channel-7", "osmo1pfm")
+        };
+        let memo = forward_memo(
+            &[first, hop("channel-42", "juno1rcpt")],
+            Some(r#"{"wasm":{}}"#.to_string()),
+        )
+        .unwrap();
+        let expected = r#"{"forward":{"receiver":"osmo1pfm","port":"wasm.juno1gateway","channel":"channel-7","timeout":"600s","retries":2,"next":{"forward":{"receiver":"juno1rcpt","port":"transfer","channel":"channel-42","next":"{\"wasm\":{}}"}}}}"#;
+        assert_eq!(memo.unwrap(), expected);
+    }
+
+    #[test]
+    fn validates_hops() {
+        let err = forward_memo(&[hop("chan-7", "osmo1rcpt")], None).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidForwardChannel {
+                channel: "chan-7".to_string()
+            }
+        );
+        let err = forward_memo(&[hop("channel-", "osmo1rcpt")], None).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidForwardChannel {
+                channel: "channel-".to_string()
+            }
+        );
+        let err = forward_memo(&[hop("channel-7", "")], None).unwrap_err();
+        assert_eq!(err, ContractError::EmptyForwardReceiver {});
+        let no_time = ForwardHop {
+            timeout: Some(0),
+            ..hop("channel-7", "osmo1rcpt")
+        };
+        let err = forward_memo(&[no_time], None).unwrap_err();
+        assert_eq!(err, ContractError::InvalidForwardTimeout {});
+
+        let path = vec![hop("channel-7", "osmo1rcpt"); MAX_HOPS + 1];
+        let err = forward_memo(&path, None).unwrap_err();
+        assert_eq!(err, ContractError::TooManyHops { max: MAX_HOPS });
+    }
+}
@@ -6,14 +6,15 @@ use cosmwasm_std::{
     attr, entry_point, from_binary, to_binary, BankMsg, Binary, CosmosMsg, Deps, DepsMut, Env,
     IbcBasicResponse, IbcChannel, IbcChannelCloseMsg, IbcChannelConnectMsg, IbcChannelOpenMsg,
     IbcEndpoint, IbcOrder, IbcPacket, IbcPacketAckMsg, IbcPacketReceiveMsg, IbcPacketTimeoutMsg,
-    IbcReceiveResponse, Reply, Response, SubMsg, SubMsgResult, Uint128, WasmMsg,
+    IbcReceiveResponse, Reply, Response, Storage, SubMsg, SubMsgResult, Uint128, WasmMsg,
 };
 
 use crate::amount::Amount;
 use crate::error::{ContractError, Never};
+use crate::msg::Ics20CallbackMsg;
 use crate::state::{
-    reduce_channel_balance, undo_reduce_channel_balance, ChannelInfo, ReplyArgs, ALLOW_LIST,
-    CHANNEL_INFO, CONFIG, REPLY_ARGS,
+    reduce_channel_balance, remove_in_flight, undo_reduce_channel_balance, ChannelInfo, ReplyArgs,
+    ALLOW_LIST, CHANNEL_INFO, CONFIG, NEXT_SEQUENCE, RECOVERED_PACKETS, REPLY_ARGS,
 };
 use cw20::Cw20ExecuteMsg;
 
@@ -33,6 +34,10 @@ pub struct Ics20Packet {
     pub receiver: String,
     /// the sender address
     pub sender: String,
+    /// optional memo for the destination chain, e.g. forwarding instructions or callbacks.
+    /// Left out of the JSON if unset, to stay compatible with chains not supporting it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
 }
 
 impl Ics20Packet {
@@ -42,9 +47,15 @@ impl Ics20Packet {
             amount,
             sender: sender.to_string(),
             receiver: receiver.to_string(),
+            memo: None,
         }
     }
 
+    pub fn with_memo(mut self, memo: Option<String>) -> Self {
+        self.memo = memo;
+        self
+    }
+
     pub fn validate(&self) -> Result<(), ContractError> {
         if self.amount.u128() > (u64::MAX as u128) {
             Err(ContractError::AmountOverflow {})
@@ -77,6 +88,10 @@ fn ack_fail(err: String) -> Binary {
 
 const RECEIVE_ID: u64 = 1337;
 const ACK_FAILURE_ID: u64 = 0xfa17;
+const CALLBACK_ID: u64 = 0xca11;
+
+/// Gas available to the sender contract to handle an `Ics20CallbackMsg`, so it cannot block the ack
+pub const CALLBACK_GAS_LIMIT: u64 = 300_000;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn reply(deps: DepsMut, _env: Env, reply: Reply) -> Result<Response, ContractError> {
@@ -111,6 +126,8 @@ pub fn reply(deps: DepsMut, _env: Env, reply: Reply) -> Result<Response, Contrac
             SubMsgResult::Ok(_) => Ok(Response::new()),
             SubMsgResult::Err(err) => Ok(Response::new().set_data(ack_fail(err))),
         },
+        // callbacks are fire-and-forget, a failing sender contract must not block the ack
+        CALLBACK_ID => Ok(Response::new()),
         _ => Err(ContractError::UnknownReplyId { id: reply.id }),
     }
 }
@@ -143,6 +160,7 @@ pub fn ibc_channel_connect(
         connection_id: channel.connection_id,
     };
     CHANNEL_INFO.save(deps.storage, &info.id, &info)?;
+    NEXT_SEQUENCE.save(deps.storage, &info.id, &1)?;
 
     Ok(IbcBasicResponse::default())
 }
@@ -297,6 +315,11 @@ pub fn ibc_packet_ack(
     // TODO: unsure... as it is now a failed ack handling would revert the tx and would be
     // retried again and again. is that good?
     let ics20msg: Ics20Ack = from_binary(&msg.acknowledgement.data)?;
+    let packet = &msg.original_packet;
+    if let Some(res) = on_recovered_packet(deps.storage, packet)? {
+        return Ok(res);
+    }
+    remove_in_flight(deps.storage, &packet.src.channel_id, packet.sequence)?;
     match ics20msg {
         Ics20Ack::Result(_) => on_packet_success(deps, msg.original_packet),
         Ics20Ack::Error(err) => on_packet_failure(deps, msg.original_packet, err),
@@ -312,12 +335,61 @@ pub fn ibc_packet_timeout(
 ) -> Result<IbcBasicResponse, ContractError> {
     // TODO: trap error like in receive? (same question as ack above)
     let packet = msg.packet;
+    if let Some(res) = on_recovered_packet(deps.storage, &packet)? {
+        return Ok(res);
+    }
+    remove_in_flight(deps.storage, &packet.src.channel_id, packet.sequence)?;
     on_packet_failure(deps, packet, "timeout".to_string())
 }
 
+/// Packets recovered by the gov contract were already refunded, whatever their outcome
+fn on_recovered_packet(
+    storage: &mut dyn Storage,
+    packet: &IbcPacket,
+) -> Result<Option<IbcBasicResponse>, ContractError> {
+    let key = (packet.src.channel_id.as_str(), packet.sequence);
+    if !RECOVERED_PACKETS.has(storage, key) {
+        return Ok(None);
+    }
+    RECOVERED_PACKETS.remove(storage, key);
+    Ok(Some(
+        IbcBasicResponse::new()
+            .add_attribute("action", "acknowledge")
+            .add_attribute("channel", &packet.src.channel_id)
+            .add_attribute("sequence", packet.sequence.to_string())
+            .add_attribute("recovered", "true"),
+    ))
+}
+
+/// Notifies the sender of the outcome of its transfer, if it is a contract
+fn sender_callback(
+    deps: Deps,
+    packet: &IbcPacket,
+    msg: &Ics20Packet,
+    error: Option<String>,
+) -> Result<Option<SubMsg>, ContractError> {
+    // only contracts have contract info
+    if deps.querier.query_wasm_contract_info(&msg.sender).is_err() {
+        return Ok(None);
+    }
+    let amount = Amount::from_parts(msg.denom.clone(), msg.amount);
+    let callback = Ics20CallbackMsg {
+        channel: packet.src.channel_id.clone(),
+        receiver: msg.receiver.clone(),
+        success: error.is_none(),
+        refund: error.as_ref().map(|_| amount.clone()),
+        error,
+        amount,
+    };
+    let mut submsg = SubMsg::reply_on_error(callback.into_cosmos_msg(&msg.sender)?, CALLBACK_ID);
+    submsg.gas_limit = Some(CALLBACK_GAS_LIMIT);
+    Ok(Some(submsg))
+}
+
 // update the balance stored on this (channel, denom) index
-fn on_packet_success(_deps: DepsMut, packet: IbcPacket) -> Result<IbcBasicResponse, ContractError> {
+fn on_packet_success(deps: DepsMut, packet: IbcPacket) -> Result<IbcBasicResponse, ContractError> {
     let msg: Ics20Packet = from_binary(&packet.data)?;
+    let callback = sender_callback(deps.as_ref(), &packet, &msg, None)?;
 
     // similar event messages like ibctransfer module
     let attributes = vec![
@@ -329,7 +401,9 @@ fn on_packet_success(_deps: DepsMut, packet: IbcPacket) -> Result<IbcBasicRespon
         attr("success", "true"),
     ];
 
-    Ok(IbcBasicResponse::new().add_attributes(attributes))
+    Ok(IbcBasicResponse::new()
+        .add_submessages(callback)
+        .add_attributes(attributes))
 }
 
 // return the tokens to sender
@@ -348,10 +422,13 @@ fn on_packet_failure(
     let send = send_amount(to_send, msg.sender.clone());
     let mut submsg = SubMsg::reply_on_error(send, ACK_FAILURE_ID);
     submsg.gas_limit = gas_limit;
+    // the callback comes after the refund, so the sender already has its tokens back
+    let callback = sender_callback(deps.as_ref(), &packet, &msg, Some(err.clone()))?;
 
     // similar event messages like ibctransfer module
     let res = IbcBasicResponse::new()
         .add_submessage(submsg)
+        .add_submessages(callback)
         .add_attribute("action", "acknowledge")
         .add_attribute("sender", msg.sender)
         .add_attribute("receiver", msg.receiver)
@@ -363,7 +440,7 @@ fn on_packet_failure(
     Ok(res)
 }
 
-fn send_amount(amount: Amount, recipient: String) -> CosmosMsg {
+pub(crate) fn send_amount(amount: Amount, recipient: String) -> CosmosMsg {
     match amount {
         Amount::Native(coin) => BankMsg::Send {
             to_address: recipient,
@@ -390,10 +467,17 @@ mod test {
     use super::*;
     use crate::test_helpers::*;
 
-    use crate::contract::{execute, migrate, query_channel};
-    use crate::msg::{ExecuteMsg, MigrateMsg, TransferMsg};
+    use crate::contract::{execute, migrate, query, query_channel};
+    use crate::msg::{
+        ExecuteMsg, InFlightPacketInfo, InFlightPacketsResponse, InFlightTotalsResponse,
+        MigrateMsg, QueryMsg, TransferMsg,
+    };
     use cosmwasm_std::testing::{mock_env, mock_info};
-    use cosmwasm_std::{coins, to_vec, IbcEndpoint, IbcMsg, IbcTimeout, Timestamp};
+    use cosmwasm_std::{
+        coins, from_binary, from_slice, to_vec, ContractInfoResponse, ContractResult,
+        IbcAcknowledgement, IbcEndpoint, IbcMsg, IbcTimeout, SystemError, SystemResult, Timestamp,
+        WasmQuery,
+    };
     use cw20::Cw20ReceiveMsg;
 
     #[test]
@@ -421,6 +505,14 @@ mod test {
 
         let encdoded = String::from_utf8(to_vec(&packet).unwrap()).unwrap();
         assert_eq!(expected, encdoded.as_str());
+
+        // the memo is only there if set
+        let packet = packet.with_memo(Some(r#"{"forward":{"port":"transfer"}}"#.to_string()));
+        let expected = r#"{"amount":"12345","denom":"ucosm","receiver":"wasm1fucynrfkrt684pm8jrt8la5h2csvs5cnldcgqc","sender":"cosmos1zedxv25ah8fksmg2lzrndrpkvsjqgk4zt5ff7n","memo":"{\"forward\":{\"port\":\"transfer\"}}"}"#;
+        let encdoded = String::from_utf8(to_vec(&packet).unwrap()).unwrap();
+        assert_eq!(expected, encdoded.as_str());
+        let decoded: Ics20Packet = from_slice(expected.as_bytes()).unwrap();
+        assert_eq!(decoded, packet);
     }
 
     fn cw20_payment(
@@ -465,6 +557,7 @@ mod test {
             amount: amount.into(),
             sender: "remote-sender".to_string(),
             receiver: receiver.to_string(),
+            memo: None,
         };
         print!("Packet denom: {}", &data.denom);
         IbcPacket::new(
@@ -511,6 +604,8 @@ mod test {
             channel: send_channel.to_string(),
             remote_address: "remote-rcpt".to_string(),
             timeout: None,
+            memo: None,
+            forward: vec![],
         };
         let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
             sender: "local-sender".to_string(),
@@ -525,6 +620,7 @@ mod test {
             amount: Uint128::new(987654321),
             sender: "local-sender".to_string(),
             receiver: "remote-rcpt".to_string(),
+            memo: None,
         };
         let timeout = mock_env().block.time.plus_seconds(DEFAULT_TIMEOUT);
         assert_eq!(
@@ -591,6 +687,8 @@ mod test {
             channel: send_channel.to_string(),
             remote_address: "my-remote-address".to_string(),
             timeout: None,
+            memo: None,
+            forward: vec![],
         });
         let info = mock_info("local-sender", &coins(987654321, denom));
         execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -626,6 +724,339 @@ mod test {
         assert_eq!(state.total_sent, vec![Amount::native(987654321, denom)]);
     }
 
+    #[test]
+    fn sender_contracts_are_called_back() {
+        let send_channel = "channel-9";
+        let mut deps = setup(&[send_channel], &[]);
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::ContractInfo { contract_addr } if contract_addr == "sender-contract" => {
+                SystemResult::Ok(ContractResult::Ok(
+                    to_binary(&ContractInfoResponse::new(1, "creator")).unwrap(),
+                ))
+            }
+            _ => SystemResult::Err(SystemError::NoSuchContract {
+                addr: "unknown".to_string(),
+            }),
+        });
+
+        // sends tokens and returns the packet the relayer would deliver
+        let send = |deps: DepsMut, sender: &str| -> IbcPacket {
+            let msg = ExecuteMsg::Transfer(TransferMsg {
+                channel: send_channel.to_string(),
+                remote_address: "remote-rcpt".to_string(),
+                timeout: None,
+                memo: None,
+                forward: vec![],
+            });
+            let info = mock_info(sender, &coins(1000, "ucosm"));
+            let res = execute(deps, mock_env(), info, msg).unwrap();
+            match &res.messages[0].msg {
+                CosmosMsg::Ibc(IbcMsg::SendPacket { data, timeout, .. }) => IbcPacket::new(
+                    data.clone(),
+                    IbcEndpoint {
+                        port_id: CONTRACT_PORT.to_string(),
+                        channel_id: send_channel.to_string(),
+                    },
+                    IbcEndpoint {
+                        port_id: REMOTE_PORT.to_string(),
+                        channel_id: format!("{}5", send_channel),
+                    },
+                    1,
+                    timeout.clone(),
+                ),
+                msg => panic!("Unexpected return message: {:?}", msg),
+            }
+        };
+        let callback = |success: bool, error: Option<&str>| {
+            let callback = Ics20CallbackMsg {
+                channel: send_channel.to_string(),
+                receiver: "remote-rcpt".to_string(),
+                amount: Amount::native(1000, "ucosm"),
+                success,
+                error: error.map(String::from),
+                refund: error.map(|_| Amount::native(1000, "ucosm")),
+            };
+            let mut msg = SubMsg::reply_on_error(
+                callback.into_cosmos_msg("sender-contract").unwrap(),
+                CALLBACK_ID,
+            );
+            msg.gas_limit = Some(CALLBACK_GAS_LIMIT);
+            msg
+        };
+
+        // the contract is told about successful transfers
+        let packet = send(deps.as_mut(), "sender-contract");
+        let ack = IbcPacketAckMsg::new(IbcAcknowledgement::new(ack_success()), packet);
+        let res = ibc_packet_ack(deps.as_mut(), mock_env(), ack).unwrap();
+        assert_eq!(res.messages, vec![callback(true, None)]);
+
+        // and about refunds, after getting its tokens back
+        let packet = send(deps.as_mut(), "sender-contract");
+        let timeout = IbcPacketTimeoutMsg::new(packet);
+        let res = ibc_packet_timeout(deps.as_mut(), mock_env(), timeout).unwrap();
+        let mut refund = native_payment(1000, "ucosm", "sender-contract");
+        refund.id = ACK_FAILURE_ID;
+        assert_eq!(res.messages, vec![refund, callback(false, Some("timeout"))]);
+        let packet = send(deps.as_mut(), "sender-contract");
+        let ack = IbcPacketAckMsg::new(
+            IbcAcknowledgement::new(ack_fail("bad coin".to_string())),
+            packet,
+        );
+        let res = ibc_packet_ack(deps.as_mut(), mock_env(), ack).unwrap();
+        assert_eq!(res.messages[1], callback(false, Some("bad coin")));
+
+        // a failing callback does not fail the ack
+        let failed = Reply {
+            id: CALLBACK_ID,
+            result: SubMsgResult::Err("unknown variant".to_string()),
+        };
+        let res = reply(deps.as_mut(), mock_env(), failed).unwrap();
+        assert_eq!(res.data, None);
+
+        // accounts are not called back
+        let packet = send(deps.as_mut(), "local-sender");
+        let ack = IbcPacketAckMsg::new(IbcAcknowledgement::new(ack_success()), packet);
+        let res = ibc_packet_ack(deps.as_mut(), mock_env(), ack).unwrap();
+        assert!(res.messages.is_empty());
+    }
+
+    #[test]
+    fn in_flight_packets_are_tracked() {
+        let send_channel = "channel-9";
+        let mut deps = setup(&[send_channel], &[]);
+
+        // sends tokens and returns the packet the relayer would deliver
+        let send = |deps: DepsMut, sender: &str, amount: u128, sequence: u64| -> IbcPacket {
+            let msg = ExecuteMsg::Transfer(TransferMsg {
+                channel: send_channel.to_string(),
+                remote_address: "remote-rcpt".to_string(),
+                timeout: None,
+                memo: None,
+                forward: vec![],
+            });
+            let info = mock_info(sender, &coins(amount, "ucosm"));
+            let res = execute(deps, mock_env(), info, msg).unwrap();
+            match &res.messages[0].msg {
+                CosmosMsg::Ibc(IbcMsg::SendPacket { data, timeout, .. }) => IbcPacket::new(
+                    data.clone(),
+                    IbcEndpoint {
+                        port_id: CONTRACT_PORT.to_string(),
+                        channel_id: send_channel.to_string(),
+                    },
+                    IbcEndpoint {
+                        port_id: REMOTE_PORT.to_string(),
+                        channel_id: format!("{}5", send_channel),
+                    },
+                    sequence,
+                    timeout.clone(),
+                ),
+                msg => panic!("Unexpected return message: {:?}", msg),
+            }
+        };
+        let in_flight = |deps: Deps| -> (Vec<InFlightPacketInfo>, InFlightTotalsResponse) {
+            let msg = QueryMsg::InFlightPackets {
+                channel: send_channel.to_string(),
+                start_after: None,
+                limit: None,
+            };
+            let res: InFlightPacketsResponse =
+                from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+            let msg = QueryMsg::InFlightTotals {
+                channel: send_channel.to_string(),
+            };
+            let totals = from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+            (res.packets, totals)
+        };
+
+        let first = send(deps.as_mut(), "local-sender", 1000, 1);
+        let second = send(deps.as_mut(), "other-sender", 234, 2);
+        let (packets, totals) = in_flight(deps.as_ref());
+        let timeout = mock_env().block.time.plus_seconds(DEFAULT_TIMEOUT);
+        assert_eq!(
+            packets,
+            vec![
+                InFlightPacketInfo {
+                    sequence: 1,
+                    denom: "ucosm".to_string(),
+                    amount: Uint128::new(1000),
+                    sender: "local-sender".to_string(),
+                    timeout,
+                },
+                InFlightPacketInfo {
+                    sequence: 2,
+                    denom: "ucosm".to_string(),
+                    amount: Uint128::new(234),
+                    sender: "other-sender".to_string(),
+                    timeout,
+                },
+            ]
+        );
+        assert_eq!(
+            totals,
+            InFlightTotalsResponse {
+                packets: 2,
+                amounts: vec![Amount::native(1234, "ucosm")],
+            }
+        );
+
+        // packets are forgotten once acknowledged
+        let ack = IbcPacketAckMsg::new(IbcAcknowledgement::new(ack_success()), first);
+        ibc_packet_ack(deps.as_mut(), mock_env(), ack).unwrap();
+        let (packets, totals) = in_flight(deps.as_ref());
+        assert_eq!(packets.len(), 1);
+        assert_eq!(packets[0].sequence, 2);
+        assert_eq!(
+            totals,
+            InFlightTotalsResponse {
+                packets: 1,
+                amounts: vec![Amount::native(234, "ucosm")],
+            }
+        );
+
+        // or timed out
+        let timeout = IbcPacketTimeoutMsg::new(second);
+        ibc_packet_timeout(deps.as_mut(), mock_env(), timeout).unwrap();
+        let (packets, totals) = in_flight(deps.as_ref());
+        assert_eq!(packets, vec![]);
+        assert_eq!(
+            totals,
+            InFlightTotalsResponse {
+                packets: 0,
+                amounts: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn timed_out_packets_are_recovered_on_closed_channels() {
+        let send_channel = "channel-9";
+        let mut deps = setup(&[send_channel], &[]);
+        let transfer = ExecuteMsg::Transfer(TransferMsg {
+            channel: send_channel.to_string(),
+            remote_address: "remote-rcpt".to_string(),
+            timeout: None,
+            memo: None,
+            forward: vec![],
+        });
+        let info = mock_info("local-sender", &coins(1000, "ucosm"));
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), transfer.clone()).unwrap();
+        let packet = match &res.messages[0].msg {
+            CosmosMsg::Ibc(IbcMsg::SendPacket { data, timeout, .. }) => IbcPacket::new(
+                data.clone(),
+                IbcEndpoint {
+                    port_id: CONTRACT_PORT.to_string(),
+                    channel_id: send_channel.to_string(),
+                },
+                IbcEndpoint {
+                    port_id: REMOTE_PORT.to_string(),
+                    channel_id: format!("{}5", send_channel),
+                },
+                1,
+                timeout.clone(),
+            ),
+            msg => panic!("Unexpected return message: {:?}", msg),
+        };
+        let recover = ExecuteMsg::RecoverPacket {
+            channel: send_channel.to_string(),
+            sequence: 1,
+        };
+        let mut timed_out = mock_env();
+        timed_out.block.time = timed_out.block.time.plus_seconds(DEFAULT_TIMEOUT);
+
+        // only packets of closed channels can be recovered
+        let err = execute(
+            deps.as_mut(),
+            timed_out.clone(),
+            mock_info("gov", &[]),
+            recover.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::ChannelNotClosed {
+                channel: send_channel.to_string()
+            }
+        );
+
+        // only gov can close a channel
+        let close = ExecuteMsg::CloseChannel {
+            channel: send_channel.to_string(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            close.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Admin(_)));
+        execute(deps.as_mut(), mock_env(), mock_info("gov", &[]), close).unwrap();
+        assert!(
+            query_channel(deps.as_ref(), send_channel.to_string())
+                .unwrap()
+                .closed
+        );
+
+        // nothing can be sent anymore
+        let err = execute(deps.as_mut(), mock_env(), info, transfer).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::ChannelClosed {
+                channel: send_channel.to_string()
+            }
+        );
+
+        // the packet is refunded once it timed out
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("gov", &[]),
+            recover.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::PacketNotTimedOut {
+                channel: send_channel.to_string(),
+                sequence: 1
+            }
+        );
+        let res = execute(
+            deps.as_mut(),
+            timed_out.clone(),
+            mock_info("gov", &[]),
+            recover.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "local-sender".to_string(),
+                amount: coins(1000, "ucosm"),
+            })]
+        );
+        assert_eq!(res.attributes[0], attr("action", "recover_packet"));
+        let state = query_channel(deps.as_ref(), send_channel.to_string()).unwrap();
+        assert_eq!(state.balances, vec![Amount::native(0, "ucosm")]);
+
+        // only once
+        let err = execute(deps.as_mut(), timed_out, mock_info("gov", &[]), recover).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::NoSuchInFlightPacket {
+                channel: send_channel.to_string(),
+                sequence: 1
+            }
+        );
+
+        // and relaying its timeout later on does not refund it again
+        let timeout = IbcPacketTimeoutMsg::new(packet);
+        let res = ibc_packet_timeout(deps.as_mut(), mock_env(), timeout).unwrap();
+        assert_eq!(res.messages, vec![]);
+        let state = query_channel(deps.as_ref(), send_channel.to_string()).unwrap();
+        assert_eq!(state.balances, vec![Amount::native(0, "ucosm")]);
+    }
+
     #[test]
     fn check_gas_limit_handles_all_cases() {
         let send_channel = "channel-9";
@@ -648,6 +1079,7 @@ mod test {
             mock_env(),
             MigrateMsg {
                 default_gas_limit: Some(def_limit),
+                next_sequences: vec![],
             },
         )
         .unwrap();
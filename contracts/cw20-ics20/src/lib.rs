@@ -14,6 +14,7 @@ For more information on this contract, please check out the
 pub mod amount;
 pub mod contract;
 mod error;
+pub mod forward;
 pub mod ibc;
 mod migrations;
 pub mod msg;
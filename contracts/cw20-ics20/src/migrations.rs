@@ -84,3 +84,45 @@ pub mod v2 {
         Ok(())
     }
 }
+
+// rate limits were counted over fixed windows, starting with the first transfer after the previous one ended
+pub mod fixed_rate_limits {
+    use cosmwasm_schema::cw_serde;
+    use cosmwasm_std::{Order, StdResult, Storage, Timestamp, Uint128};
+    use cw_storage_plus::Map;
+
+    use crate::state::{RateLimitBucket, RateLimitUsage, RATE_LIMITS, RATE_LIMIT_USAGE};
+
+    #[cw_serde]
+    pub struct RateLimitWindow {
+        pub ends: Timestamp,
+        pub sent: Uint128,
+    }
+
+    pub const RATE_LIMIT_WINDOWS: Map<(&str, &str), RateLimitWindow> =
+        Map::new("rate_limit_windows");
+
+    /// Counts what was sent in the windows still running as if it was sent now, so that the
+    /// limit cannot be exceeded across the migration
+    pub fn migrate_windows(storage: &mut dyn Storage, now: Timestamp) -> StdResult<()> {
+        let windows = RATE_LIMIT_WINDOWS
+            .range(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for ((channel, denom), window) in windows {
+            RATE_LIMIT_WINDOWS.remove(storage, (&channel, &denom));
+            if now >= window.ends || window.sent.is_zero() {
+                continue;
+            }
+            if let Some(limit) = RATE_LIMITS.may_load(storage, (&channel, &denom))? {
+                let usage = RateLimitUsage {
+                    buckets: vec![RateLimitBucket {
+                        start: limit.bucket_start(now),
+                        sent: window.sent,
+                    }],
+                };
+                RATE_LIMIT_USAGE.save(storage, (&channel, &denom), &usage)?;
+            }
+        }
+        Ok(())
+    }
+}
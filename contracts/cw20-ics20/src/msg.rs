@@ -2,7 +2,9 @@ use cosmwasm_schema::{cw_serde, QueryResponses};
 use cw20::Cw20ReceiveMsg;
 
 use crate::amount::Amount;
-use crate::state::ChannelInfo;
+use cosmwasm_std::{to_binary, CosmosMsg, StdResult, Timestamp, Uint128, WasmMsg};
+
+use crate::state::{ChannelInfo, RateLimit};
 
 #[cw_serde]
 pub struct InitMsg {
@@ -26,6 +28,16 @@ pub struct AllowMsg {
 #[cw_serde]
 pub struct MigrateMsg {
     pub default_gas_limit: Option<u64>,
+    /// The next send sequence of the channels connected before in-flight packets were tracked,
+    /// as reported by the IBC module. Required for each of them that is not closed
+    #[serde(default)]
+    pub next_sequences: Vec<NextSequence>,
+}
+
+#[cw_serde]
+pub struct NextSequence {
+    pub channel: String,
+    pub sequence: u64,
 }
 
 #[cw_serde]
@@ -36,8 +48,40 @@ pub enum ExecuteMsg {
     Transfer(TransferMsg),
     /// This must be called by gov_contract, will allow a new cw20 token to be sent
     Allow(AllowMsg),
+    /// This must be called by gov_contract. Allows the denom on the channel, possibly capping the amount
+    /// outstanding on it. Once a channel has such an entry, only the denoms allowed on it can be sent over it.
+    /// Calling it again updates the cap
+    AllowOnChannel(AllowOnChannelMsg),
+    /// This must be called by gov_contract. Stops the denom from being sent over the channel.
+    /// Tokens coming back over the channel are still accepted
+    DisallowOnChannel { channel: String, denom: String },
+    /// This must be called by gov_contract. Removes the allowlist of the channel, so that it
+    /// accepts every token allowed globally again
+    ClearChannelAllowList { channel: String },
     /// Change the admin (must be called by current admin)
     UpdateAdmin { admin: String },
+    /// This must be called by gov_contract. Caps the amount of denom sent over the channel per window,
+    /// or removes the cap if None. Denom is the native denom, or "cw20:<contract address>" for cw20 tokens
+    SetRateLimit {
+        channel: String,
+        denom: String,
+        limit: Option<RateLimit>,
+    },
+    /// This must be called by gov_contract. Marks the channel as closed (e.g. if the chain on the other
+    /// side was compromised), nothing can be sent over it anymore. This cannot be undone
+    CloseChannel { channel: String },
+    /// This must be called by gov_contract, on a closed channel. Refunds the sender of a packet that
+    /// timed out but whose timeout was never relayed
+    RecoverPacket { channel: String, sequence: u64 },
+}
+
+#[cw_serde]
+pub struct AllowOnChannelMsg {
+    pub channel: String,
+    /// The native denom, or "cw20:<contract address>" for cw20 tokens
+    pub denom: String,
+    /// If set, transfers are rejected once this much is outstanding on the channel
+    pub max_outstanding: Option<Uint128>,
 }
 
 /// This is the message we accept via Receive
@@ -51,6 +95,64 @@ pub struct TransferMsg {
     pub remote_address: String,
     /// How long the packet lives in seconds. If not specified, use default_timeout
     pub timeout: Option<u64>,
+    /// An optional memo to add to the ics20 packet, e.g. to forward the tokens to another
+    /// chain with the packet-forward-middleware or to trigger a callback on the destination
+    #[serde(default)]
+    pub memo: Option<String>,
+    /// If set, the tokens are forwarded along this path after arriving on the remote chain, using the
+    /// packet-forward-middleware. remote_address is then the receiver on the first chain, and memo is
+    /// passed on to the last one
+    #[serde(default)]
+    pub forward: Vec<ForwardHop>,
+}
+
+/// One hop of a forwarding path, as understood by the packet-forward-middleware
+#[cw_serde]
+pub struct ForwardHop {
+    /// The channel to forward the tokens on, from the chain they arrived on
+    pub channel: String,
+    /// The receiver on the chain the tokens are forwarded to
+    pub receiver: String,
+    /// The port to forward the tokens on, "transfer" if not specified
+    pub port: Option<String>,
+    /// How long the forwarded packet lives in seconds. If not specified, use the middleware's default
+    pub timeout: Option<u64>,
+    /// How many times the middleware retries forwarding on timeout. If not specified, use its default
+    pub retries: Option<u8>,
+}
+
+/// Sent to the sender of a transfer, if it is a contract, once the packet is acknowledged or timed out.
+/// Should be de/serialized under the `Ics20Callback()` variant in its ExecuteMsg
+#[cw_serde]
+pub struct Ics20CallbackMsg {
+    /// The local channel the packet was sent on
+    pub channel: String,
+    pub receiver: String,
+    pub amount: Amount,
+    /// True if the tokens arrived on the remote chain
+    pub success: bool,
+    /// The error acknowledged by the remote chain, or "timeout". None on success
+    pub error: Option<String>,
+    /// The tokens sent back to the sender, None on success
+    pub refund: Option<Amount>,
+}
+
+impl Ics20CallbackMsg {
+    /// creates a cosmos_msg sending this struct to the named contract
+    pub fn into_cosmos_msg<T: Into<String>>(self, contract_addr: T) -> StdResult<CosmosMsg> {
+        let execute = WasmMsg::Execute {
+            contract_addr: contract_addr.into(),
+            msg: to_binary(&CallbackExecuteMsg::Ics20Callback(self))?,
+            funds: vec![],
+        };
+        Ok(execute.into())
+    }
+}
+
+// This is just a helper to properly serialize the above message
+#[cw_serde]
+enum CallbackExecuteMsg {
+    Ics20Callback(Ics20CallbackMsg),
 }
 
 #[cw_serde]
@@ -79,6 +181,29 @@ pub enum QueryMsg {
         start_after: Option<String>,
         limit: Option<u32>,
     },
+    /// Query if denom can be sent over the given channel, and how much of it is outstanding there.
+    #[returns(ChannelAllowedResponse)]
+    ChannelAllowed { channel: String, denom: String },
+    /// List all denoms allowed on the given channel, with their outstanding balances.
+    #[returns(ListChannelAllowedResponse)]
+    ListChannelAllowed {
+        channel: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Returns the rate limit of the channel for denom, if any, and how much was sent in the current window.
+    #[returns(RateLimitResponse)]
+    RateLimit { channel: String, denom: String },
+    /// List the packets sent over the given channel that were neither acknowledged nor timed out yet.
+    #[returns(InFlightPacketsResponse)]
+    InFlightPackets {
+        channel: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Returns how many packets are in flight over the given channel, and their total per denom.
+    #[returns(InFlightTotalsResponse)]
+    InFlightTotals { channel: String },
 }
 
 #[cw_serde]
@@ -92,6 +217,8 @@ pub struct ChannelResponse {
     pub info: ChannelInfo,
     /// How many tokens we currently have pending over this channel
     pub balances: Vec<Amount>,
+    /// Whether the channel was closed by the gov contract
+    pub closed: bool,
     /// The total number of tokens that have been sent over this channel
     /// (even if many have been returned, so balance is low)
     pub total_sent: Vec<Amount>,
@@ -125,3 +252,56 @@ pub struct AllowedInfo {
     pub contract: String,
     pub gas_limit: Option<u64>,
 }
+
+#[cw_serde]
+pub struct ChannelAllowedResponse {
+    /// True if the denom is allowed on the channel, or if the channel is not restricted
+    pub is_allowed: bool,
+    pub max_outstanding: Option<Uint128>,
+    pub outstanding: Uint128,
+}
+
+#[cw_serde]
+pub struct ListChannelAllowedResponse {
+    pub allow: Vec<ChannelAllowedInfo>,
+}
+
+#[cw_serde]
+pub struct ChannelAllowedInfo {
+    pub denom: String,
+    pub max_outstanding: Option<Uint128>,
+    pub outstanding: Uint128,
+}
+
+#[cw_serde]
+pub struct RateLimitResponse {
+    pub limit: Option<RateLimit>,
+    /// Amount sent over the rolling window ending now
+    pub sent: Uint128,
+    /// Amount that can still be sent now, None if there is no limit
+    pub remaining: Option<Uint128>,
+    /// When the oldest transfer still counted stops counting, None if none is
+    pub next_release: Option<Timestamp>,
+}
+
+#[cw_serde]
+pub struct InFlightPacketsResponse {
+    pub packets: Vec<InFlightPacketInfo>,
+}
+
+#[cw_serde]
+pub struct InFlightPacketInfo {
+    pub sequence: u64,
+    pub denom: String,
+    pub amount: Uint128,
+    pub sender: String,
+    pub timeout: Timestamp,
+}
+
+#[cw_serde]
+pub struct InFlightTotalsResponse {
+    /// Number of packets in flight
+    pub packets: u64,
+    /// Amount in flight per denom
+    pub amounts: Vec<Amount>,
+}
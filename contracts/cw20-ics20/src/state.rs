@@ -1,5 +1,5 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, IbcEndpoint, StdResult, Storage, Uint128};
+use cosmwasm_std::{Addr, Empty, IbcEndpoint, StdResult, Storage, Timestamp, Uint128};
 use cw_controllers::Admin;
 use cw_storage_plus::{Item, Map};
 
@@ -21,6 +21,41 @@ pub const CHANNEL_STATE: Map<(&str, &str), ChannelState> = Map::new("channel_sta
 /// Every cw20 contract we allow to be sent is stored here, possibly with a gas_limit
 pub const ALLOW_LIST: Map<&Addr, AllowInfo> = Map::new("allow_list");
 
+/// indexed by (channel_id, denom), the tokens allowed on a channel, possibly with a cap on the outstanding balance.
+/// Only restricted channels check it
+pub const CHANNEL_ALLOW_LIST: Map<(&str, &str), ChannelAllowInfo> = Map::new("channel_allow_list");
+
+/// channels only accepting the tokens on their allowlist, even once it is empty.
+/// Channels not restricted accept every token allowed globally
+pub const RESTRICTED_CHANNELS: Map<&str, Empty> = Map::new("restricted_channels");
+
+/// indexed by (channel_id, denom), optional cap on the amount sent over the channel per window
+pub const RATE_LIMITS: Map<(&str, &str), RateLimit> = Map::new("rate_limits");
+
+/// indexed by (channel_id, denom), the amounts sent still counting towards the rate limit
+pub const RATE_LIMIT_USAGE: Map<(&str, &str), RateLimitUsage> = Map::new("rate_limit_usage");
+
+/// indexed by channel_id, the sequence the next packet sent over the channel will get.
+/// The contract is the only sender on its channels, whose sequences start at 1, so they can be
+/// predicted. Those of channels connected before this was introduced are set on migration
+pub const NEXT_SEQUENCE: Map<&str, u64> = Map::new("next_sequence");
+
+/// indexed by (channel_id, sequence), the packets sent that were neither acknowledged nor timed out yet
+pub const IN_FLIGHT_PACKETS: Map<(&str, u64), InFlightPacket> = Map::new("in_flight_packets");
+
+/// indexed by channel_id, the number of packets in flight
+pub const IN_FLIGHT_COUNT: Map<&str, u64> = Map::new("in_flight_count");
+
+/// indexed by (channel_id, denom), the amount in flight
+pub const IN_FLIGHT_AMOUNTS: Map<(&str, &str), Uint128> = Map::new("in_flight_amounts");
+
+/// channels closed by the gov contract, nothing can be sent over them anymore
+pub const CLOSED_CHANNELS: Map<&str, Empty> = Map::new("closed_channels");
+
+/// indexed by (channel_id, sequence), the packets refunded by the gov contract after they timed out.
+/// Their acknowledgement or timeout, if ever relayed, must not refund them again
+pub const RECOVERED_PACKETS: Map<(&str, u64), Empty> = Map::new("recovered_packets");
+
 #[cw_serde]
 #[derive(Default)]
 pub struct ChannelState {
@@ -49,6 +84,70 @@ pub struct AllowInfo {
     pub gas_limit: Option<u64>,
 }
 
+#[cw_serde]
+pub struct ChannelAllowInfo {
+    pub max_outstanding: Option<Uint128>,
+}
+
+/// Transfers are counted in buckets of this fraction of the rate limit window
+const RATE_LIMIT_BUCKETS: u64 = 10;
+
+#[cw_serde]
+pub struct RateLimit {
+    /// maximum amount that can be sent over any window
+    pub max_amount: Uint128,
+    /// length of the rolling window in seconds
+    pub window: u64,
+}
+
+impl RateLimit {
+    fn bucket_length(&self) -> u64 {
+        (self.window / RATE_LIMIT_BUCKETS).max(1)
+    }
+
+    /// Start of the bucket the transfers made at `time` are counted in
+    pub fn bucket_start(&self, time: Timestamp) -> Timestamp {
+        Timestamp::from_seconds(time.seconds() - time.seconds() % self.bucket_length())
+    }
+
+    /// When the bucket starting at `start` stops counting, once it is entirely out of the window
+    pub fn bucket_expires(&self, start: Timestamp) -> Timestamp {
+        Timestamp::from_seconds(
+            start
+                .seconds()
+                .saturating_add(self.bucket_length())
+                .saturating_add(self.window),
+        )
+    }
+}
+
+/// The amounts sent in the buckets still counting towards a rate limit
+#[cw_serde]
+#[derive(Default)]
+pub struct RateLimitUsage {
+    pub buckets: Vec<RateLimitBucket>,
+}
+
+impl RateLimitUsage {
+    pub fn sent(&self) -> Uint128 {
+        self.buckets.iter().map(|bucket| bucket.sent).sum()
+    }
+}
+
+#[cw_serde]
+pub struct RateLimitBucket {
+    pub start: Timestamp,
+    pub sent: Uint128,
+}
+
+#[cw_serde]
+pub struct InFlightPacket {
+    pub denom: String,
+    pub amount: Uint128,
+    pub sender: String,
+    pub timeout: Timestamp,
+}
+
 #[cw_serde]
 pub struct ReplyArgs {
     pub channel: String,
@@ -108,3 +207,109 @@ pub fn undo_reduce_channel_balance(
     })?;
     Ok(())
 }
+
+/// Records a packet about to be sent over the channel
+pub fn record_in_flight(
+    storage: &mut dyn Storage,
+    channel: &str,
+    packet: &InFlightPacket,
+) -> Result<(), ContractError> {
+    let sequence = NEXT_SEQUENCE.may_load(storage, channel)?.ok_or_else(|| {
+        ContractError::UntrackedChannel {
+            channel: channel.to_string(),
+        }
+    })?;
+    NEXT_SEQUENCE.save(storage, channel, &(sequence + 1))?;
+    IN_FLIGHT_PACKETS.save(storage, (channel, sequence), packet)?;
+    IN_FLIGHT_COUNT.update(storage, channel, |count| -> StdResult<_> {
+        Ok(count.unwrap_or_default() + 1)
+    })?;
+    IN_FLIGHT_AMOUNTS.update(
+        storage,
+        (channel, &packet.denom),
+        |amount| -> StdResult<_> { Ok(amount.unwrap_or_default() + packet.amount) },
+    )?;
+    Ok(())
+}
+
+/// Forgets a packet once it was acknowledged or timed out. Does nothing for untracked packets
+pub fn remove_in_flight(storage: &mut dyn Storage, channel: &str, sequence: u64) -> StdResult<()> {
+    let packet = match IN_FLIGHT_PACKETS.may_load(storage, (channel, sequence))? {
+        Some(packet) => packet,
+        None => return Ok(()),
+    };
+    IN_FLIGHT_PACKETS.remove(storage, (channel, sequence));
+
+    let count = IN_FLIGHT_COUNT
+        .may_load(storage, channel)?
+        .unwrap_or_default()
+        .saturating_sub(1);
+    if count == 0 {
+        IN_FLIGHT_COUNT.remove(storage, channel);
+    } else {
+        IN_FLIGHT_COUNT.save(storage, channel, &count)?;
+    }
+
+    let key = (channel, packet.denom.as_str());
+    let amount = IN_FLIGHT_AMOUNTS
+        .may_load(storage, key)?
+        .unwrap_or_default()
+        .saturating_sub(packet.amount);
+    if amount.is_zero() {
+        IN_FLIGHT_AMOUNTS.remove(storage, key);
+    } else {
+        IN_FLIGHT_AMOUNTS.save(storage, key, &amount)?;
+    }
+    Ok(())
+}
+
+/// Returns the rate limit of the given channel and denom, if set, with what was sent over the
+/// rolling window ending now. Transfers are counted in buckets of a tenth of the window, each
+/// counting until it is entirely out of the window, so no window ever sees more than the limit.
+pub fn current_rate_limit_usage(
+    storage: &dyn Storage,
+    now: Timestamp,
+    channel: &str,
+    denom: &str,
+) -> StdResult<Option<(RateLimit, RateLimitUsage)>> {
+    let limit = match RATE_LIMITS.may_load(storage, (channel, denom))? {
+        Some(limit) => limit,
+        None => return Ok(None),
+    };
+    let mut usage = RATE_LIMIT_USAGE
+        .may_load(storage, (channel, denom))?
+        .unwrap_or_default();
+    usage
+        .buckets
+        .retain(|bucket| now < limit.bucket_expires(bucket.start));
+    Ok(Some((limit, usage)))
+}
+
+/// Accounts for `amount` sent over the channel now, failing if this exceeds the rate limit over
+/// the rolling window. Does nothing if no limit is set for this channel and denom.
+pub fn apply_rate_limit(
+    storage: &mut dyn Storage,
+    now: Timestamp,
+    channel: &str,
+    denom: &str,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    let (limit, mut usage) = match current_rate_limit_usage(storage, now, channel, denom)? {
+        Some(current) => current,
+        None => return Ok(()),
+    };
+    let remaining = limit.max_amount.saturating_sub(usage.sent());
+    if amount > remaining {
+        return Err(ContractError::RateLimitExceeded { remaining });
+    }
+    let start = limit.bucket_start(now);
+    match usage.buckets.last_mut() {
+        Some(bucket) if bucket.start == start => bucket.sent += amount,
+        _ => usage.buckets.push(RateLimitBucket {
+            start,
+            sent: amount,
+        }),
+    }
+    RATE_LIMIT_USAGE.save(storage, (channel, denom), &usage)?;
+    Ok(())
+}
@@ -0,0 +1,513 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    coins, to_binary, Addr, BankMsg, Binary, Deps, DepsMut, Empty, Env, MessageInfo, Response,
+    StdResult, Storage, SubMsg, Uint128, WasmMsg,
+};
+use sha2::{Digest, Sha256};
+
+use cw2::set_contract_version;
+use cw20::{Cw20ExecuteMsg, Denom};
+
+use crate::error::ContractError;
+use crate::msg::{
+    ConfigResponse, ExecuteMsg, InstantiateMsg, IsClaimedResponse, LatestStageResponse, QueryMsg,
+    Stage, StageResponse,
+};
+use crate::state::{Config, StageInfo, ADMIN, CLAIMED, CONFIG, LATEST_STAGE, STAGES};
+
+// version info for migration info
+const CONTRACT_NAME: &str = "crates.io:cw20-merkle-airdrop";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    let owner = match msg.owner {
+        Some(owner) => deps.api.addr_validate(&owner)?,
+        None => info.sender,
+    };
+    ADMIN.set(deps.branch(), Some(owner))?;
+    CONFIG.save(deps.storage, &Config { asset: msg.asset })?;
+    LATEST_STAGE.save(deps.storage, &0)?;
+    for stage in msg.stages {
+        register_stage(deps.storage, &env, stage)?;
+    }
+    Ok(Response::default())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::Claim {
+            stage,
+            amount,
+            proof,
+        } => execute_claim(deps, env, info, stage, amount, proof),
+        ExecuteMsg::RegisterStage(stage) => execute_register_stage(deps, env, info, stage),
+        ExecuteMsg::Clawback { stage } => execute_clawback(deps, env, info, stage),
+        ExecuteMsg::UpdateAdmin { admin } => {
+            let admin = deps.api.addr_validate(&admin)?;
+            Ok(ADMIN.execute_update_admin(deps, info, Some(admin))?)
+        }
+    }
+}
+
+/// Stores a new stage under the next number and returns that number
+fn register_stage(storage: &mut dyn Storage, env: &Env, stage: Stage) -> Result<u8, ContractError> {
+    let mut merkle_root = [0; 32];
+    hex::decode_to_slice(&stage.merkle_root, &mut merkle_root)
+        .map_err(|_| ContractError::InvalidMerkleRoot {})?;
+    if stage.expiration.is_expired(&env.block) {
+        return Err(ContractError::InvalidExpiration {});
+    }
+
+    let number = LATEST_STAGE
+        .load(storage)?
+        .checked_add(1)
+        .ok_or(ContractError::TooManyStages {})?;
+    let info = StageInfo {
+        merkle_root,
+        expiration: stage.expiration,
+        total_amount: stage.total_amount,
+        claimed_amount: Uint128::zero(),
+        clawed_back: false,
+    };
+    STAGES.save(storage, number, &info)?;
+    LATEST_STAGE.save(storage, &number)?;
+    Ok(number)
+}
+
+pub fn execute_register_stage(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    stage: Stage,
+) -> Result<Response, ContractError> {
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+    let merkle_root = stage.merkle_root.clone();
+    let number = register_stage(deps.storage, &env, stage)?;
+
+    let res = Response::new()
+        .add_attribute("action", "register_stage")
+        .add_attribute("stage", number.to_string())
+        .add_attribute("merkle_root", merkle_root);
+    Ok(res)
+}
+
+fn load_stage(storage: &dyn Storage, stage: u8) -> Result<StageInfo, ContractError> {
+    STAGES
+        .may_load(storage, stage)?
+        .ok_or(ContractError::StageNotFound { stage })
+}
+
+/// Checks the proof of the `address ++ amount` leaf against the merkle root. Each pair of
+/// hashes is sorted before hashing, so the proof does not need to tell left from right
+fn verify_proof(
+    merkle_root: &[u8; 32],
+    address: &Addr,
+    amount: Uint128,
+    proof: Vec<String>,
+) -> Result<(), ContractError> {
+    let leaf: [u8; 32] = Sha256::digest(format!("{}{}", address, amount)).into();
+    let hash = proof.into_iter().try_fold(leaf, |hash, sibling| {
+        let mut sibling_hash = [0; 32];
+        hex::decode_to_slice(sibling, &mut sibling_hash)
+            .map_err(|_| ContractError::InvalidProof {})?;
+        let mut pair = [hash, sibling_hash];
+        pair.sort_unstable();
+        Ok::<_, ContractError>(Sha256::digest(pair.concat()).into())
+    })?;
+    if hash != *merkle_root {
+        return Err(ContractError::InvalidProof {});
+    }
+    Ok(())
+}
+
+pub fn execute_claim(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    stage: u8,
+    amount: Uint128,
+    proof: Vec<String>,
+) -> Result<Response, ContractError> {
+    let mut stage_info = load_stage(deps.storage, stage)?;
+    if stage_info.expiration.is_expired(&env.block) {
+        return Err(ContractError::StageExpired { stage });
+    }
+    if CLAIMED.has(deps.storage, (stage, &info.sender)) {
+        return Err(ContractError::Claimed { stage });
+    }
+    verify_proof(&stage_info.merkle_root, &info.sender, amount, proof)?;
+    // a faulty tree must not pay out the tokens of other stages
+    stage_info.claimed_amount += amount;
+    if stage_info.claimed_amount > stage_info.total_amount {
+        return Err(ContractError::ExceedsStageAmount { stage });
+    }
+
+    STAGES.save(deps.storage, stage, &stage_info)?;
+    CLAIMED.save(deps.storage, (stage, &info.sender), &Empty {})?;
+
+    let asset = CONFIG.load(deps.storage)?.asset;
+    let res = Response::new()
+        .add_submessage(send_tokens(&asset, &info.sender, amount)?)
+        .add_attribute("action", "claim")
+        .add_attribute("stage", stage.to_string())
+        .add_attribute("address", info.sender)
+        .add_attribute("amount", amount);
+    Ok(res)
+}
+
+pub fn execute_clawback(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    stage: u8,
+) -> Result<Response, ContractError> {
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+    let mut stage_info = load_stage(deps.storage, stage)?;
+    if !stage_info.expiration.is_expired(&env.block) {
+        return Err(ContractError::StageNotExpired { stage });
+    }
+    if stage_info.clawed_back {
+        return Err(ContractError::ClawedBack { stage });
+    }
+    stage_info.clawed_back = true;
+    STAGES.save(deps.storage, stage, &stage_info)?;
+
+    let unclaimed = stage_info.total_amount - stage_info.claimed_amount;
+    let asset = CONFIG.load(deps.storage)?.asset;
+    let mut res = Response::new()
+        .add_attribute("action", "clawback")
+        .add_attribute("stage", stage.to_string())
+        .add_attribute("amount", unclaimed);
+    if !unclaimed.is_zero() {
+        res = res.add_submessage(send_tokens(&asset, &info.sender, unclaimed)?);
+    }
+    Ok(res)
+}
+
+fn send_tokens(denom: &Denom, to: &Addr, amount: Uint128) -> StdResult<SubMsg> {
+    let message = match denom {
+        Denom::Native(denom) => SubMsg::new(BankMsg::Send {
+            to_address: to.to_string(),
+            amount: coins(amount.u128(), denom),
+        }),
+        Denom::Cw20(addr) => {
+            let transfer = Cw20ExecuteMsg::Transfer {
+                recipient: to.into(),
+                amount,
+            };
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: addr.into(),
+                msg: to_binary(&transfer)?,
+                funds: vec![],
+            })
+        }
+    };
+    Ok(message)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Admin {} => to_binary(&ADMIN.query_admin(deps)?),
+        QueryMsg::Config {} => to_binary(&ConfigResponse {
+            asset: CONFIG.load(deps.storage)?.asset,
+        }),
+        QueryMsg::LatestStage {} => to_binary(&LatestStageResponse {
+            latest_stage: LATEST_STAGE.load(deps.storage)?,
+        }),
+        QueryMsg::Stage { stage } => to_binary(&query_stage(deps, stage)?),
+        QueryMsg::IsClaimed { stage, address } => {
+            to_binary(&query_is_claimed(deps, stage, address)?)
+        }
+    }
+}
+
+pub fn query_stage(deps: Deps, stage: u8) -> StdResult<StageResponse> {
+    let info = STAGES.load(deps.storage, stage)?;
+    Ok(StageResponse {
+        stage,
+        merkle_root: hex::encode(info.merkle_root),
+        expiration: info.expiration,
+        total_amount: info.total_amount,
+        claimed_amount: info.claimed_amount,
+        clawed_back: info.clawed_back,
+    })
+}
+
+pub fn query_is_claimed(deps: Deps, stage: u8, address: String) -> StdResult<IsClaimedResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let is_claimed = CLAIMED.has(deps.storage, (stage, &address));
+    Ok(IsClaimedResponse { is_claimed })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{from_binary, CosmosMsg};
+    use cw_controllers::AdminError;
+    use cw_utils::Expiration;
+
+    const DENOM: &str = "ustake";
+    const CW20_ADDRESS: &str = "wasm1token";
+
+    fn leaf(address: &str, amount: u128) -> [u8; 32] {
+        Sha256::digest(format!("{}{}", address, amount)).into()
+    }
+
+    fn parent(left: [u8; 32], right: [u8; 32]) -> [u8; 32] {
+        let mut pair = [left, right];
+        pair.sort_unstable();
+        Sha256::digest(pair.concat()).into()
+    }
+
+    /// Builds the tree for alice 100, bob 200 and carl 300, returning the root
+    /// and the proofs of alice and carl
+    fn merkle_tree() -> (String, Vec<String>, Vec<String>) {
+        let alice = leaf("alice", 100);
+        let bob = leaf("bob", 200);
+        let carl = leaf("carl", 300);
+        let root = parent(parent(alice, bob), carl);
+        let alice_proof = vec![hex::encode(bob), hex::encode(carl)];
+        let carl_proof = vec![hex::encode(parent(alice, bob))];
+        (hex::encode(root), alice_proof, carl_proof)
+    }
+
+    fn stage(merkle_root: &str, expires_at_height: u64) -> Stage {
+        Stage {
+            merkle_root: merkle_root.to_string(),
+            expiration: Expiration::AtHeight(expires_at_height),
+            total_amount: Uint128::new(600),
+        }
+    }
+
+    fn claim(stage: u8, amount: u128, proof: &[String]) -> ExecuteMsg {
+        ExecuteMsg::Claim {
+            stage,
+            amount: Uint128::new(amount),
+            proof: proof.to_vec(),
+        }
+    }
+
+    fn exec(deps: DepsMut, sender: &str, msg: ExecuteMsg) -> Result<Response, ContractError> {
+        execute(deps, mock_env(), mock_info(sender, &[]), msg)
+    }
+
+    fn do_instantiate(deps: DepsMut, asset: Denom, stages: Vec<Stage>) {
+        let msg = InstantiateMsg {
+            owner: Some("owner".to_string()),
+            asset,
+            stages,
+        };
+        instantiate(deps, mock_env(), mock_info("creator", &[]), msg).unwrap();
+    }
+
+    #[test]
+    fn claim_with_proof() {
+        let mut deps = mock_dependencies();
+        let (root, alice_proof, carl_proof) = merkle_tree();
+        let height = mock_env().block.height;
+        do_instantiate(
+            deps.as_mut(),
+            Denom::Native(DENOM.to_string()),
+            vec![stage(&root, height + 100)],
+        );
+
+        // only the allocation proven for the sender can be claimed
+        let err = exec(deps.as_mut(), "alice", claim(1, 200, &alice_proof)).unwrap_err();
+        assert_eq!(err, ContractError::InvalidProof {});
+        let err = exec(deps.as_mut(), "bob", claim(1, 100, &alice_proof)).unwrap_err();
+        assert_eq!(err, ContractError::InvalidProof {});
+        let err = exec(deps.as_mut(), "alice", claim(2, 100, &alice_proof)).unwrap_err();
+        assert_eq!(err, ContractError::StageNotFound { stage: 2 });
+
+        let res = exec(deps.as_mut(), "alice", claim(1, 100, &alice_proof)).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "alice".to_string(),
+                amount: coins(100, DENOM),
+            })]
+        );
+        assert!(
+            query_is_claimed(deps.as_ref(), 1, "alice".to_string())
+                .unwrap()
+                .is_claimed
+        );
+        assert!(
+            !query_is_claimed(deps.as_ref(), 1, "carl".to_string())
+                .unwrap()
+                .is_claimed
+        );
+
+        // once only
+        let err = exec(deps.as_mut(), "alice", claim(1, 100, &alice_proof)).unwrap_err();
+        assert_eq!(err, ContractError::Claimed { stage: 1 });
+
+        exec(deps.as_mut(), "carl", claim(1, 300, &carl_proof)).unwrap();
+        let info = query_stage(deps.as_ref(), 1).unwrap();
+        assert_eq!(info.merkle_root, root);
+        assert_eq!(info.claimed_amount, Uint128::new(400));
+    }
+
+    #[test]
+    fn stages_and_clawback() {
+        let mut deps = mock_dependencies();
+        let (root, alice_proof, carl_proof) = merkle_tree();
+        let height = mock_env().block.height;
+        do_instantiate(
+            deps.as_mut(),
+            Denom::Cw20(Addr::unchecked(CW20_ADDRESS)),
+            vec![],
+        );
+
+        // only the owner registers stages, with a valid root and deadline
+        let err = exec(
+            deps.as_mut(),
+            "alice",
+            ExecuteMsg::RegisterStage(stage(&root, height + 10)),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Admin(AdminError::NotAdmin {}));
+        let err = exec(
+            deps.as_mut(),
+            "owner",
+            ExecuteMsg::RegisterStage(stage("deadbeef", height + 10)),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidMerkleRoot {});
+        let err = exec(
+            deps.as_mut(),
+            "owner",
+            ExecuteMsg::RegisterStage(stage(&root, height)),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidExpiration {});
+
+        exec(
+            deps.as_mut(),
+            "owner",
+            ExecuteMsg::RegisterStage(stage(&root, height + 10)),
+        )
+        .unwrap();
+        exec(
+            deps.as_mut(),
+            "owner",
+            ExecuteMsg::RegisterStage(stage(&root, height + 20)),
+        )
+        .unwrap();
+        let res: LatestStageResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::LatestStage {}).unwrap())
+                .unwrap();
+        assert_eq!(res.latest_stage, 2);
+
+        // claims are tracked per stage
+        exec(deps.as_mut(), "alice", claim(1, 100, &alice_proof)).unwrap();
+        let res = exec(deps.as_mut(), "alice", claim(2, 100, &alice_proof)).unwrap();
+        let transfer = Cw20ExecuteMsg::Transfer {
+            recipient: "alice".to_string(),
+            amount: Uint128::new(100),
+        };
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: CW20_ADDRESS.to_string(),
+                msg: to_binary(&transfer).unwrap(),
+                funds: vec![],
+            }))]
+        );
+
+        // nothing can be clawed back before the deadline
+        let err = exec(deps.as_mut(), "owner", ExecuteMsg::Clawback { stage: 1 }).unwrap_err();
+        assert_eq!(err, ContractError::StageNotExpired { stage: 1 });
+
+        // after it, claims are closed and the owner gets the rest
+        let mut env = mock_env();
+        env.block.height += 10;
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("carl", &[]),
+            claim(1, 300, &carl_proof),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::StageExpired { stage: 1 });
+        let info = mock_info("carl", &[]);
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            ExecuteMsg::Clawback { stage: 1 },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Admin(AdminError::NotAdmin {}));
+        let info = mock_info("owner", &[]);
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::Clawback { stage: 1 },
+        )
+        .unwrap();
+        let transfer = Cw20ExecuteMsg::Transfer {
+            recipient: "owner".to_string(),
+            amount: Uint128::new(500),
+        };
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: CW20_ADDRESS.to_string(),
+                msg: to_binary(&transfer).unwrap(),
+                funds: vec![],
+            }))]
+        );
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            ExecuteMsg::Clawback { stage: 1 },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::ClawedBack { stage: 1 });
+
+        // the later stage is still open
+        execute(
+            deps.as_mut(),
+            env,
+            mock_info("carl", &[]),
+            claim(2, 300, &carl_proof),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn stage_numbers_do_not_wrap() {
+        let mut deps = mock_dependencies();
+        let (root, _, _) = merkle_tree();
+        let height = mock_env().block.height;
+        do_instantiate(deps.as_mut(), Denom::Native(DENOM.to_string()), vec![]);
+        LATEST_STAGE.save(deps.as_mut().storage, &254).unwrap();
+
+        let msg = ExecuteMsg::RegisterStage(stage(&root, height + 10));
+        exec(deps.as_mut(), "owner", msg.clone()).unwrap();
+        let err = exec(deps.as_mut(), "owner", msg).unwrap_err();
+        assert_eq!(err, ContractError::TooManyStages {});
+        assert!(STAGES.has(deps.as_ref().storage, 255));
+        assert!(!STAGES.has(deps.as_ref().storage, 0));
+    }
+}
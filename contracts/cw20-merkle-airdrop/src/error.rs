@@ -0,0 +1,42 @@
+use cosmwasm_std::StdError;
+use cw_controllers::AdminError;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    Admin(#[from] AdminError),
+
+    #[error("Merkle root must be 32 bytes in hex")]
+    InvalidMerkleRoot {},
+
+    #[error("Merkle proof does not match the stage root")]
+    InvalidProof {},
+
+    #[error("Cannot register a stage that already expired")]
+    InvalidExpiration {},
+
+    #[error("Stage {stage} does not exist")]
+    StageNotFound { stage: u8 },
+
+    #[error("Stage {stage} expired")]
+    StageExpired { stage: u8 },
+
+    #[error("Stage {stage} has not expired yet")]
+    StageNotExpired { stage: u8 },
+
+    #[error("Allocation of stage {stage} already claimed")]
+    Claimed { stage: u8 },
+
+    #[error("Claims exceed the total amount of stage {stage}")]
+    ExceedsStageAmount { stage: u8 },
+
+    #[error("Unclaimed tokens of stage {stage} already clawed back")]
+    ClawedBack { stage: u8 },
+
+    #[error("No more than 255 stages can be registered")]
+    TooManyStages {},
+}
@@ -0,0 +1,17 @@
+/*!
+This is an airdrop of [cw20](https://github.com/CosmWasm/cw-plus/blob/main/packages/cw20/README.md)
+or native tokens held by the contract. Every stage commits to its allocations with the root of a
+merkle tree, so that users can claim their own allocation by submitting a merkle proof, without
+the contract ever storing the full list. Unclaimed tokens can be clawed back by the owner once the
+claim deadline of a stage passed.
+
+For more information on this contract, please check out the
+[README](https://github.com/CosmWasm/cw-plus/blob/main/contracts/cw20-merkle-airdrop/README.md).
+*/
+
+pub mod contract;
+mod error;
+pub mod msg;
+pub mod state;
+
+pub use crate::error::ContractError;
@@ -0,0 +1,89 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::Uint128;
+
+use cw20::Denom;
+use cw_utils::Expiration;
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    /// The owner, who can register stages and claw back unclaimed tokens. The sender by default
+    pub owner: Option<String>,
+    /// The token airdropped in all stages, either native or cw20
+    pub asset: Denom,
+    /// Stages to register right away, numbered from 1
+    pub stages: Vec<Stage>,
+}
+
+#[cw_serde]
+pub struct Stage {
+    /// Hex encoded sha256 root of the merkle tree of `address ++ amount` leaves
+    pub merkle_root: String,
+    /// Deadline for claims
+    pub expiration: Expiration,
+    /// Sum of all allocations of the stage
+    pub total_amount: Uint128,
+}
+
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// Claims the allocation of the sender in `stage`, proven by the hex encoded sibling
+    /// hashes from the leaf to the root
+    Claim {
+        stage: u8,
+        amount: Uint128,
+        proof: Vec<String>,
+    },
+    /// Adds a new stage. Only the owner can call it. The contract must hold enough
+    /// tokens for it to be claimed in full
+    RegisterStage(Stage),
+    /// Sends the tokens left unclaimed in an expired stage to the owner. Only the owner can call it
+    Clawback { stage: u8 },
+    /// Changes the owner. Only the owner can call it
+    UpdateAdmin { admin: String },
+}
+
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    /// Returns the owner.
+    #[returns(cw_controllers::AdminResponse)]
+    Admin {},
+    /// Returns the airdropped token.
+    #[returns(ConfigResponse)]
+    Config {},
+    /// Returns the number of the last stage registered, 0 if none.
+    #[returns(LatestStageResponse)]
+    LatestStage {},
+    /// Returns the root, deadline and claimed amount of a stage.
+    #[returns(StageResponse)]
+    Stage { stage: u8 },
+    /// Returns whether `address` claimed its allocation in `stage`.
+    #[returns(IsClaimedResponse)]
+    IsClaimed { stage: u8, address: String },
+}
+
+#[cw_serde]
+pub struct ConfigResponse {
+    pub asset: Denom,
+}
+
+#[cw_serde]
+pub struct LatestStageResponse {
+    pub latest_stage: u8,
+}
+
+#[cw_serde]
+pub struct StageResponse {
+    pub stage: u8,
+    /// Hex encoded merkle root
+    pub merkle_root: String,
+    pub expiration: Expiration,
+    pub total_amount: Uint128,
+    pub claimed_amount: Uint128,
+    pub clawed_back: bool,
+}
+
+#[cw_serde]
+pub struct IsClaimedResponse {
+    pub is_claimed: bool,
+}
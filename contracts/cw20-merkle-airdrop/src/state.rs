@@ -0,0 +1,31 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Empty, Uint128};
+use cw20::Denom;
+use cw_controllers::Admin;
+use cw_storage_plus::{Item, Map};
+use cw_utils::Expiration;
+
+#[cw_serde]
+pub struct Config {
+    /// The token airdropped in all stages
+    pub asset: Denom,
+}
+
+#[cw_serde]
+pub struct StageInfo {
+    /// Sha256 root of the merkle tree of `address ++ amount` leaves
+    pub merkle_root: [u8; 32],
+    /// Deadline for claims, after which the unclaimed tokens can be clawed back
+    pub expiration: Expiration,
+    /// Sum of all allocations of the stage
+    pub total_amount: Uint128,
+    pub claimed_amount: Uint128,
+    pub clawed_back: bool,
+}
+
+pub const ADMIN: Admin = Admin::new("admin");
+pub const CONFIG: Item<Config> = Item::new("airdrop_config");
+pub const LATEST_STAGE: Item<u8> = Item::new("latest_stage");
+pub const STAGES: Map<u8, StageInfo> = Map::new("stages");
+/// Allocations claimed, by stage and address
+pub const CLAIMED: Map<(u8, &Addr), Empty> = Map::new("claimed");
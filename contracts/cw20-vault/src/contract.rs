@@ -0,0 +1,537 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    coins, from_binary, to_binary, Addr, BankMsg, Binary, Decimal, Deps, DepsMut, Env, MessageInfo,
+    Response, StdResult, Storage, SubMsg, Uint128, WasmMsg,
+};
+
+use cw2::set_contract_version;
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg, Denom, MinterResponse};
+use cw20_base::allowances::{
+    execute_burn_from, execute_decrease_allowance, execute_increase_allowance, execute_send_from,
+    execute_transfer_from, query_allowance,
+};
+use cw20_base::contract::{
+    execute_burn, execute_mint, execute_send, execute_transfer, query_balance, query_token_info,
+};
+use cw20_base::enumerable::{query_all_accounts, query_owner_allowances};
+use cw20_base::state::TOKEN_INFO;
+use cw_utils::must_pay;
+
+use crate::error::ContractError;
+use crate::msg::{
+    AssetsResponse, ExecuteMsg, InstantiateMsg, QueryMsg, ReceiveMsg, SharesResponse,
+    VaultInfoResponse,
+};
+use crate::state::{Config, CONFIG, TOTAL_ASSETS};
+
+// version info for migration info
+const CONTRACT_NAME: &str = "crates.io:cw20-vault";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    // the vault itself is the only minter of its shares
+    let token = cw20_base::msg::InstantiateMsg {
+        name: msg.name,
+        symbol: msg.symbol,
+        decimals: msg.decimals,
+        initial_balances: vec![],
+        mint: Some(MinterResponse {
+            minter: env.contract.address.to_string(),
+            cap: None,
+        }),
+        marketing: None,
+        snapshots: false,
+        freezer: None,
+        pauser: None,
+    };
+    cw20_base::contract::instantiate(deps.branch(), env, info, token)?;
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    CONFIG.save(
+        deps.storage,
+        &Config {
+            underlying: msg.underlying,
+        },
+    )?;
+    TOTAL_ASSETS.save(deps.storage, &Uint128::zero())?;
+    Ok(Response::default())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::Deposit { recipient } => {
+            let assets = must_pay_native(deps.storage, &info)?;
+            let recipient = recipient.unwrap_or_else(|| info.sender.to_string());
+            execute_deposit(deps, env, info.sender, recipient, assets)
+        }
+        ExecuteMsg::Donate {} => {
+            let assets = must_pay_native(deps.storage, &info)?;
+            execute_donate(deps, info.sender, assets)
+        }
+        ExecuteMsg::Withdraw { shares, recipient } => {
+            execute_withdraw(deps, env, info, shares, recipient)
+        }
+        ExecuteMsg::Receive(msg) => execute_receive(deps, env, info, msg),
+        ExecuteMsg::Transfer { recipient, amount } => {
+            Ok(execute_transfer(deps, env, info, recipient, amount)?)
+        }
+        ExecuteMsg::Burn { amount } => Ok(execute_burn(deps, env, info, amount, None)?),
+        ExecuteMsg::Send {
+            contract,
+            amount,
+            msg,
+        } => Ok(execute_send(deps, env, info, contract, amount, msg)?),
+        ExecuteMsg::IncreaseAllowance {
+            spender,
+            amount,
+            expires,
+        } => Ok(execute_increase_allowance(
+            deps, env, info, spender, amount, expires,
+        )?),
+        ExecuteMsg::DecreaseAllowance {
+            spender,
+            amount,
+            expires,
+        } => Ok(execute_decrease_allowance(
+            deps, env, info, spender, amount, expires,
+        )?),
+        ExecuteMsg::TransferFrom {
+            owner,
+            recipient,
+            amount,
+        } => Ok(execute_transfer_from(
+            deps, env, info, owner, recipient, amount,
+        )?),
+        ExecuteMsg::SendFrom {
+            owner,
+            contract,
+            amount,
+            msg,
+        } => Ok(execute_send_from(
+            deps, env, info, owner, contract, amount, msg,
+        )?),
+        ExecuteMsg::BurnFrom { owner, amount } => {
+            Ok(execute_burn_from(deps, env, info, owner, amount)?)
+        }
+    }
+}
+
+fn must_pay_native(storage: &dyn Storage, info: &MessageInfo) -> Result<Uint128, ContractError> {
+    match CONFIG.load(storage)?.underlying {
+        Denom::Native(denom) => Ok(must_pay(info, &denom)?),
+        Denom::Cw20(addr) => Err(ContractError::WrongToken(addr.into_string())),
+    }
+}
+
+pub fn execute_receive(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    wrapper: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    // info.sender is the address of the cw20 contract (that re-sent this message).
+    // wrapper.sender is the address of the user that requested the cw20 contract to send this.
+    match CONFIG.load(deps.storage)?.underlying {
+        Denom::Cw20(addr) if addr == info.sender => (),
+        Denom::Cw20(addr) => return Err(ContractError::WrongToken(addr.into_string())),
+        Denom::Native(denom) => return Err(ContractError::WrongToken(denom)),
+    }
+
+    let sender = deps.api.addr_validate(&wrapper.sender)?;
+    match from_binary(&wrapper.msg)? {
+        ReceiveMsg::Deposit { recipient } => {
+            let recipient = recipient.unwrap_or(wrapper.sender);
+            execute_deposit(deps, env, sender, recipient, wrapper.amount)
+        }
+        ReceiveMsg::Donate {} => execute_donate(deps, sender, wrapper.amount),
+    }
+}
+
+/// Returns the total assets and shares, each with one virtual unit on top. This keeps the exchange
+/// rate defined on an empty vault and makes inflating it with donations unprofitable.
+fn virtual_totals(storage: &dyn Storage) -> StdResult<(Uint128, Uint128)> {
+    let total_assets = TOTAL_ASSETS.load(storage)?;
+    let total_shares = TOKEN_INFO.load(storage)?.total_supply;
+    Ok((total_assets + Uint128::one(), total_shares + Uint128::one()))
+}
+
+/// Shares a deposit of `assets` is worth, rounded down in favour of the vault
+fn to_shares(storage: &dyn Storage, assets: Uint128) -> StdResult<Uint128> {
+    let (total_assets, total_shares) = virtual_totals(storage)?;
+    Ok(assets.multiply_ratio(total_shares, total_assets))
+}
+
+/// Underlying tokens `shares` are worth, rounded down in favour of the vault
+fn to_assets(storage: &dyn Storage, shares: Uint128) -> StdResult<Uint128> {
+    let (total_assets, total_shares) = virtual_totals(storage)?;
+    Ok(shares.multiply_ratio(total_assets, total_shares))
+}
+
+pub fn execute_deposit(
+    mut deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    recipient: String,
+    assets: Uint128,
+) -> Result<Response, ContractError> {
+    let shares = to_shares(deps.storage, assets)?;
+    if shares.is_zero() {
+        return Err(ContractError::ZeroShares {});
+    }
+    let total_assets = TOTAL_ASSETS.load(deps.storage)?;
+
+    let minter = MessageInfo {
+        sender: env.contract.address.clone(),
+        funds: vec![],
+    };
+    let minted = execute_mint(deps.branch(), env, minter, recipient.clone(), shares)?;
+    TOTAL_ASSETS.save(deps.storage, &(total_assets + assets))?;
+
+    let res = Response::new()
+        .add_submessages(minted.messages)
+        .add_attribute("action", "deposit")
+        .add_attribute("from", sender)
+        .add_attribute("to", recipient)
+        .add_attribute("assets", assets)
+        .add_attribute("shares", shares);
+    Ok(res)
+}
+
+pub fn execute_donate(
+    deps: DepsMut,
+    sender: Addr,
+    assets: Uint128,
+) -> Result<Response, ContractError> {
+    let total_assets = TOTAL_ASSETS.load(deps.storage)? + assets;
+    TOTAL_ASSETS.save(deps.storage, &total_assets)?;
+
+    let res = Response::new()
+        .add_attribute("action", "donate")
+        .add_attribute("from", sender)
+        .add_attribute("assets", assets)
+        .add_attribute("total_assets", total_assets);
+    Ok(res)
+}
+
+pub fn execute_withdraw(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    shares: Uint128,
+    recipient: Option<String>,
+) -> Result<Response, ContractError> {
+    let recipient = match recipient {
+        Some(recipient) => deps.api.addr_validate(&recipient)?,
+        None => info.sender.clone(),
+    };
+    let assets = to_assets(deps.storage, shares)?;
+    if assets.is_zero() {
+        return Err(ContractError::ZeroAssets {});
+    }
+    let total_assets = TOTAL_ASSETS.load(deps.storage)?;
+    let sender = info.sender.clone();
+
+    let burned = execute_burn(deps.branch(), env, info, shares, None)?;
+    TOTAL_ASSETS.save(deps.storage, &(total_assets - assets))?;
+
+    let underlying = CONFIG.load(deps.storage)?.underlying;
+    let res = Response::new()
+        .add_submessages(burned.messages)
+        .add_submessage(send_tokens(&underlying, &recipient, assets)?)
+        .add_attribute("action", "withdraw")
+        .add_attribute("from", sender)
+        .add_attribute("to", recipient)
+        .add_attribute("shares", shares)
+        .add_attribute("assets", assets);
+    Ok(res)
+}
+
+fn send_tokens(denom: &Denom, to: &Addr, amount: Uint128) -> StdResult<SubMsg> {
+    let message = match denom {
+        Denom::Native(denom) => SubMsg::new(BankMsg::Send {
+            to_address: to.to_string(),
+            amount: coins(amount.u128(), denom),
+        }),
+        Denom::Cw20(addr) => {
+            let transfer = Cw20ExecuteMsg::Transfer {
+                recipient: to.into(),
+                amount,
+            };
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: addr.into(),
+                msg: to_binary(&transfer)?,
+                funds: vec![],
+            })
+        }
+    };
+    Ok(message)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::VaultInfo {} => to_binary(&query_vault_info(deps)?),
+        QueryMsg::ConvertToShares { assets } => to_binary(&SharesResponse {
+            shares: to_shares(deps.storage, assets)?,
+        }),
+        QueryMsg::ConvertToAssets { shares } => to_binary(&AssetsResponse {
+            assets: to_assets(deps.storage, shares)?,
+        }),
+        QueryMsg::Balance { address } => to_binary(&query_balance(deps, address)?),
+        QueryMsg::TokenInfo {} => to_binary(&query_token_info(deps)?),
+        QueryMsg::Allowance { owner, spender } => {
+            to_binary(&query_allowance(deps, owner, spender)?)
+        }
+        QueryMsg::AllAllowances {
+            owner,
+            start_after,
+            limit,
+        } => to_binary(&query_owner_allowances(deps, owner, start_after, limit)?),
+        QueryMsg::AllAccounts { start_after, limit } => {
+            to_binary(&query_all_accounts(deps, start_after, limit)?)
+        }
+    }
+}
+
+pub fn query_vault_info(deps: Deps) -> StdResult<VaultInfoResponse> {
+    let underlying = CONFIG.load(deps.storage)?.underlying;
+    let total_assets = TOTAL_ASSETS.load(deps.storage)?;
+    let total_shares = TOKEN_INFO.load(deps.storage)?.total_supply;
+    let (virtual_assets, virtual_shares) = virtual_totals(deps.storage)?;
+    Ok(VaultInfoResponse {
+        underlying,
+        total_assets,
+        total_shares,
+        exchange_rate: Decimal::from_ratio(virtual_assets, virtual_shares),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{coin, CosmosMsg};
+    use cw_utils::PaymentError;
+
+    const DENOM: &str = "ustake";
+    const CW20_ADDRESS: &str = "wasm1underlying";
+
+    fn do_instantiate(deps: DepsMut, underlying: Denom) {
+        let msg = InstantiateMsg {
+            name: "Vault Stake".to_string(),
+            symbol: "vSTAKE".to_string(),
+            decimals: 6,
+            underlying,
+        };
+        instantiate(deps, mock_env(), mock_info("creator", &[]), msg).unwrap();
+    }
+
+    fn exec(
+        deps: DepsMut,
+        sender: &str,
+        funds: u128,
+        msg: ExecuteMsg,
+    ) -> Result<Response, ContractError> {
+        let funds = if funds == 0 {
+            vec![]
+        } else {
+            coins(funds, DENOM)
+        };
+        execute(deps, mock_env(), mock_info(sender, &funds), msg)
+    }
+
+    fn deposit() -> ExecuteMsg {
+        ExecuteMsg::Deposit { recipient: None }
+    }
+
+    fn withdraw(shares: u128) -> ExecuteMsg {
+        ExecuteMsg::Withdraw {
+            shares: Uint128::new(shares),
+            recipient: None,
+        }
+    }
+
+    fn shares(deps: Deps, address: &str) -> u128 {
+        query_balance(deps, address.to_string())
+            .unwrap()
+            .balance
+            .u128()
+    }
+
+    fn receive(sender: &str, amount: u128, msg: ReceiveMsg) -> ExecuteMsg {
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: sender.to_string(),
+            amount: Uint128::new(amount),
+            msg: to_binary(&msg).unwrap(),
+        })
+    }
+
+    #[test]
+    fn donations_raise_exchange_rate() {
+        let mut deps = mock_dependencies();
+        do_instantiate(deps.as_mut(), Denom::Native(DENOM.to_string()));
+        let info = query_vault_info(deps.as_ref()).unwrap();
+        assert_eq!(info.exchange_rate, Decimal::one());
+
+        // the first deposit gets shares one for one
+        let res = exec(deps.as_mut(), "alice", 1000, deposit()).unwrap();
+        assert_eq!(res.attributes[0], ("action", "deposit"));
+        assert_eq!(shares(deps.as_ref(), "alice"), 1000);
+
+        // yield is shared by all holders
+        exec(deps.as_mut(), "rewards", 500, ExecuteMsg::Donate {}).unwrap();
+        let info = query_vault_info(deps.as_ref()).unwrap();
+        assert_eq!(info.total_assets, Uint128::new(1500));
+        assert_eq!(info.total_shares, Uint128::new(1000));
+        assert_eq!(info.exchange_rate, Decimal::from_ratio(1501u128, 1001u128));
+
+        // later deposits get fewer shares
+        exec(deps.as_mut(), "bob", 1501, deposit()).unwrap();
+        assert_eq!(shares(deps.as_ref(), "bob"), 1001);
+        let res: AssetsResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::ConvertToAssets {
+                    shares: Uint128::new(1000),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.assets, Uint128::new(1499));
+
+        // and withdrawals pay out the yield, rounded down
+        let res = exec(deps.as_mut(), "alice", 0, withdraw(1000)).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "alice".to_string(),
+                amount: coins(1499, DENOM),
+            })]
+        );
+        assert_eq!(shares(deps.as_ref(), "alice"), 0);
+        let info = query_vault_info(deps.as_ref()).unwrap();
+        assert_eq!(info.total_assets, Uint128::new(1502));
+        assert_eq!(info.total_shares, Uint128::new(1001));
+
+        // no more than the balance can be withdrawn
+        let err = exec(deps.as_mut(), "bob", 0, withdraw(1002)).unwrap_err();
+        assert!(matches!(err, ContractError::Base(_)));
+    }
+
+    #[test]
+    fn shares_are_cw20_tokens() {
+        let mut deps = mock_dependencies();
+        do_instantiate(deps.as_mut(), Denom::Native(DENOM.to_string()));
+        exec(deps.as_mut(), "alice", 1000, deposit()).unwrap();
+
+        exec(
+            deps.as_mut(),
+            "alice",
+            0,
+            ExecuteMsg::Transfer {
+                recipient: "bob".to_string(),
+                amount: Uint128::new(400),
+            },
+        )
+        .unwrap();
+        assert_eq!(shares(deps.as_ref(), "alice"), 600);
+        assert_eq!(shares(deps.as_ref(), "bob"), 400);
+
+        // the new holder can withdraw
+        let res = exec(deps.as_mut(), "bob", 0, withdraw(400)).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "bob".to_string(),
+                amount: coins(400, DENOM),
+            })]
+        );
+        assert_eq!(
+            query_token_info(deps.as_ref()).unwrap().total_supply,
+            Uint128::new(600)
+        );
+    }
+
+    #[test]
+    fn only_underlying_can_be_deposited() {
+        let mut deps = mock_dependencies();
+        do_instantiate(deps.as_mut(), Denom::Native(DENOM.to_string()));
+
+        let info = mock_info("alice", &[coin(1000, "uother")]);
+        let err = execute(deps.as_mut(), mock_env(), info, deposit()).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Payment(PaymentError::MissingDenom(DENOM.to_string()))
+        );
+        let msg = receive("alice", 1000, ReceiveMsg::Deposit { recipient: None });
+        let err = exec(deps.as_mut(), CW20_ADDRESS, 0, msg).unwrap_err();
+        assert_eq!(err, ContractError::WrongToken(DENOM.to_string()));
+
+        // deposits too small for a single share are rejected, rather than lost
+        exec(deps.as_mut(), "alice", 1, deposit()).unwrap();
+        exec(deps.as_mut(), "alice", 10_000, ExecuteMsg::Donate {}).unwrap();
+        let err = exec(deps.as_mut(), "bob", 5000, deposit()).unwrap_err();
+        assert_eq!(err, ContractError::ZeroShares {});
+        let err = exec(deps.as_mut(), "alice", 0, withdraw(0)).unwrap_err();
+        assert_eq!(err, ContractError::ZeroAssets {});
+    }
+
+    #[test]
+    fn cw20_underlying() {
+        let mut deps = mock_dependencies();
+        do_instantiate(deps.as_mut(), Denom::Cw20(Addr::unchecked(CW20_ADDRESS)));
+
+        let err = exec(deps.as_mut(), "alice", 1000, deposit()).unwrap_err();
+        assert_eq!(err, ContractError::WrongToken(CW20_ADDRESS.to_string()));
+        let msg = receive("alice", 1000, ReceiveMsg::Deposit { recipient: None });
+        let err = exec(deps.as_mut(), "wasm1other", 0, msg).unwrap_err();
+        assert_eq!(err, ContractError::WrongToken(CW20_ADDRESS.to_string()));
+
+        // deposit for someone else, then donate
+        let msg = receive(
+            "alice",
+            1000,
+            ReceiveMsg::Deposit {
+                recipient: Some("carl".to_string()),
+            },
+        );
+        exec(deps.as_mut(), CW20_ADDRESS, 0, msg).unwrap();
+        assert_eq!(shares(deps.as_ref(), "carl"), 1000);
+        let msg = receive("rewards", 1000, ReceiveMsg::Donate {});
+        exec(deps.as_mut(), CW20_ADDRESS, 0, msg).unwrap();
+
+        let msg = ExecuteMsg::Withdraw {
+            shares: Uint128::new(500),
+            recipient: Some("dave".to_string()),
+        };
+        let res = exec(deps.as_mut(), "carl", 0, msg).unwrap();
+        let transfer = Cw20ExecuteMsg::Transfer {
+            recipient: "dave".to_string(),
+            amount: Uint128::new(999),
+        };
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: CW20_ADDRESS.to_string(),
+                msg: to_binary(&transfer).unwrap(),
+                funds: vec![],
+            }))]
+        );
+    }
+}
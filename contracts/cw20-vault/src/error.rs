@@ -0,0 +1,24 @@
+use cosmwasm_std::StdError;
+use cw_utils::PaymentError;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    Payment(#[from] PaymentError),
+
+    #[error("{0}")]
+    Base(#[from] cw20_base::ContractError),
+
+    #[error("Only the underlying token {0} can be deposited")]
+    WrongToken(String),
+
+    #[error("Deposit is too small to mint any shares")]
+    ZeroShares {},
+
+    #[error("Withdrawal is too small to pay out any of the underlying token")]
+    ZeroAssets {},
+}
@@ -0,0 +1,17 @@
+/*!
+This is an interest-bearing wrapper of a native or cw20 token. Deposits of the underlying token
+mint shares, which are themselves a [cw20](https://github.com/CosmWasm/cw-plus/blob/main/packages/cw20/README.md)
+token implemented by [cw20-base](https://github.com/CosmWasm/cw-plus/blob/main/contracts/cw20-base/README.md).
+Yield donated to the vault raises the exchange rate of all shares at once, so they can be
+withdrawn for more of the underlying token than was deposited.
+
+For more information on this contract, please check out the
+[README](https://github.com/CosmWasm/cw-plus/blob/main/contracts/cw20-vault/README.md).
+*/
+
+pub mod contract;
+mod error;
+pub mod msg;
+pub mod state;
+
+pub use crate::error::ContractError;
@@ -0,0 +1,140 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{Binary, Decimal, Uint128};
+
+use cw20::{Cw20ReceiveMsg, Denom, Expiration};
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    /// Name of the share token
+    pub name: String,
+    /// Symbol of the share token
+    pub symbol: String,
+    /// Decimals of the share token, usually the same as the underlying token
+    pub decimals: u8,
+    /// The token deposited into the vault
+    pub underlying: Denom,
+}
+
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// Deposits the native underlying tokens sent with the message, minting shares
+    /// to `recipient` (the sender by default) at the current exchange rate
+    Deposit { recipient: Option<String> },
+    /// Adds the native underlying tokens sent with the message to the vault without minting
+    /// any shares, raising the exchange rate for all holders
+    Donate {},
+    /// Burns `shares` of the sender and sends the underlying tokens they are worth
+    /// to `recipient` (the sender by default)
+    Withdraw {
+        shares: Uint128,
+        recipient: Option<String>,
+    },
+    /// Deposits or donates cw20 underlying tokens, depending on the embedded `ReceiveMsg`
+    Receive(Cw20ReceiveMsg),
+    /// Transfer is a base message to move shares to another account without triggering actions
+    Transfer { recipient: String, amount: Uint128 },
+    /// Burn is a base message to destroy shares forever, leaving their underlying tokens
+    /// to the other holders
+    Burn { amount: Uint128 },
+    /// Send is a base message to transfer shares to a contract and trigger an action
+    /// on the receiving contract.
+    Send {
+        contract: String,
+        amount: Uint128,
+        msg: Binary,
+    },
+    /// Allows spender to access an additional amount of shares from the owner's (env.sender) account.
+    /// If expires is Some(), overwrites current allowance expiration with this one.
+    IncreaseAllowance {
+        spender: String,
+        amount: Uint128,
+        expires: Option<Expiration>,
+    },
+    /// Lowers the spender's access of shares from the owner's (env.sender) account by amount.
+    /// If expires is Some(), overwrites current allowance expiration with this one.
+    DecreaseAllowance {
+        spender: String,
+        amount: Uint128,
+        expires: Option<Expiration>,
+    },
+    /// Transfers amount of shares from owner -> recipient if `env.sender` has sufficient pre-approval.
+    TransferFrom {
+        owner: String,
+        recipient: String,
+        amount: Uint128,
+    },
+    /// Sends amount of shares from owner -> contract if `env.sender` has sufficient pre-approval.
+    SendFrom {
+        owner: String,
+        contract: String,
+        amount: Uint128,
+        msg: Binary,
+    },
+    /// Destroys shares of the owner forever if `env.sender` has sufficient pre-approval.
+    BurnFrom { owner: String, amount: Uint128 },
+}
+
+#[cw_serde]
+pub enum ReceiveMsg {
+    /// Deposits the received tokens, minting shares to `recipient` (the sender by default)
+    Deposit { recipient: Option<String> },
+    /// Adds the received tokens to the vault without minting any shares
+    Donate {},
+}
+
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    /// Returns the underlying token, the total assets and shares and the exchange rate.
+    #[returns(VaultInfoResponse)]
+    VaultInfo {},
+    /// Returns the shares a deposit of `assets` would mint at the current exchange rate.
+    #[returns(SharesResponse)]
+    ConvertToShares { assets: Uint128 },
+    /// Returns the underlying tokens `shares` would be withdrawn for at the current exchange rate.
+    #[returns(AssetsResponse)]
+    ConvertToAssets { shares: Uint128 },
+    /// Returns the current share balance of the given address, 0 if unset.
+    #[returns(cw20::BalanceResponse)]
+    Balance { address: String },
+    /// Returns metadata on the share token.
+    #[returns(cw20::TokenInfoResponse)]
+    TokenInfo {},
+    /// Returns how many shares the spender can use from the owner account, 0 if unset.
+    #[returns(cw20::AllowanceResponse)]
+    Allowance { owner: String, spender: String },
+    /// Returns all allowances this owner has approved. Supports pagination.
+    #[returns(cw20::AllAllowancesResponse)]
+    AllAllowances {
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Returns all accounts that have share balances. Supports pagination.
+    #[returns(cw20::AllAccountsResponse)]
+    AllAccounts {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+}
+
+#[cw_serde]
+pub struct VaultInfoResponse {
+    pub underlying: Denom,
+    /// Underlying tokens held for the share holders
+    pub total_assets: Uint128,
+    /// Shares in circulation
+    pub total_shares: Uint128,
+    /// Underlying tokens per share
+    pub exchange_rate: Decimal,
+}
+
+#[cw_serde]
+pub struct SharesResponse {
+    pub shares: Uint128,
+}
+
+#[cw_serde]
+pub struct AssetsResponse {
+    pub assets: Uint128,
+}
@@ -0,0 +1,15 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::Uint128;
+use cw20::Denom;
+use cw_storage_plus::Item;
+
+#[cw_serde]
+pub struct Config {
+    /// The token deposited into the vault
+    pub underlying: Denom,
+}
+
+pub const CONFIG: Item<Config> = Item::new("vault_config");
+/// Underlying tokens deposited and donated, less the ones withdrawn. Tokens sent to the
+/// contract any other way are not counted, so they cannot be used to skew the exchange rate
+pub const TOTAL_ASSETS: Item<Uint128> = Item::new("total_assets");
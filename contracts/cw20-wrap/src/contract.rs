@@ -0,0 +1,279 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    coins, to_binary, BankMsg, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult,
+    Uint128,
+};
+
+use cw2::set_contract_version;
+use cw20::MinterResponse;
+use cw20_base::allowances::{
+    execute_decrease_allowance, execute_increase_allowance, execute_send_from,
+    execute_transfer_from, query_allowance,
+};
+use cw20_base::contract::{
+    execute_burn, execute_mint, execute_send, execute_transfer, query_balance, query_token_info,
+};
+use cw20_base::enumerable::{query_all_accounts, query_owner_allowances};
+use cw_utils::must_pay;
+
+use crate::error::ContractError;
+use crate::msg::{DenomResponse, ExecuteMsg, InstantiateMsg, QueryMsg};
+use crate::state::{Config, CONFIG};
+
+// version info for migration info
+const CONTRACT_NAME: &str = "crates.io:cw20-wrap";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    // the wrapper itself is the only minter, so that every token is backed by a deposited coin
+    let token = cw20_base::msg::InstantiateMsg {
+        name: msg.name,
+        symbol: msg.symbol,
+        decimals: msg.decimals,
+        initial_balances: vec![],
+        mint: Some(MinterResponse {
+            minter: env.contract.address.to_string(),
+            cap: None,
+        }),
+        marketing: None,
+        snapshots: false,
+        freezer: None,
+        pauser: None,
+    };
+    cw20_base::contract::instantiate(deps.branch(), env, info, token)?;
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    CONFIG.save(deps.storage, &Config { denom: msg.denom })?;
+    Ok(Response::default())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::Deposit { recipient } => execute_deposit(deps, env, info, recipient),
+        ExecuteMsg::Withdraw { amount, recipient } => {
+            execute_withdraw(deps, env, info, amount, recipient)
+        }
+        ExecuteMsg::Transfer { recipient, amount } => {
+            Ok(execute_transfer(deps, env, info, recipient, amount)?)
+        }
+        ExecuteMsg::Send {
+            contract,
+            amount,
+            msg,
+        } => Ok(execute_send(deps, env, info, contract, amount, msg)?),
+        ExecuteMsg::IncreaseAllowance {
+            spender,
+            amount,
+            expires,
+        } => Ok(execute_increase_allowance(
+            deps, env, info, spender, amount, expires,
+        )?),
+        ExecuteMsg::DecreaseAllowance {
+            spender,
+            amount,
+            expires,
+        } => Ok(execute_decrease_allowance(
+            deps, env, info, spender, amount, expires,
+        )?),
+        ExecuteMsg::TransferFrom {
+            owner,
+            recipient,
+            amount,
+        } => Ok(execute_transfer_from(
+            deps, env, info, owner, recipient, amount,
+        )?),
+        ExecuteMsg::SendFrom {
+            owner,
+            contract,
+            amount,
+            msg,
+        } => Ok(execute_send_from(
+            deps, env, info, owner, contract, amount, msg,
+        )?),
+    }
+}
+
+pub fn execute_deposit(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipient: Option<String>,
+) -> Result<Response, ContractError> {
+    let denom = CONFIG.load(deps.storage)?.denom;
+    let amount = must_pay(&info, &denom)?;
+    let recipient = recipient.unwrap_or_else(|| info.sender.to_string());
+
+    let minter = MessageInfo {
+        sender: env.contract.address.clone(),
+        funds: vec![],
+    };
+    let minted = execute_mint(deps.branch(), env, minter, recipient.clone(), amount)?;
+
+    let res = Response::new()
+        .add_submessages(minted.messages)
+        .add_attribute("action", "deposit")
+        .add_attribute("from", info.sender)
+        .add_attribute("to", recipient)
+        .add_attribute("amount", amount);
+    Ok(res)
+}
+
+pub fn execute_withdraw(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: Uint128,
+    recipient: Option<String>,
+) -> Result<Response, ContractError> {
+    let recipient = match recipient {
+        Some(recipient) => deps.api.addr_validate(&recipient)?,
+        None => info.sender.clone(),
+    };
+    let sender = info.sender.clone();
+    let burned = execute_burn(deps.branch(), env, info, amount, None)?;
+
+    let denom = CONFIG.load(deps.storage)?.denom;
+    let res = Response::new()
+        .add_submessages(burned.messages)
+        .add_message(BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: coins(amount.u128(), denom),
+        })
+        .add_attribute("action", "withdraw")
+        .add_attribute("from", sender)
+        .add_attribute("to", recipient)
+        .add_attribute("amount", amount);
+    Ok(res)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Denom {} => to_binary(&query_denom(deps)?),
+        QueryMsg::Balance { address } => to_binary(&query_balance(deps, address)?),
+        QueryMsg::TokenInfo {} => to_binary(&query_token_info(deps)?),
+        QueryMsg::Allowance { owner, spender } => {
+            to_binary(&query_allowance(deps, owner, spender)?)
+        }
+        QueryMsg::AllAllowances {
+            owner,
+            start_after,
+            limit,
+        } => to_binary(&query_owner_allowances(deps, owner, start_after, limit)?),
+        QueryMsg::AllAccounts { start_after, limit } => {
+            to_binary(&query_all_accounts(deps, start_after, limit)?)
+        }
+    }
+}
+
+pub fn query_denom(deps: Deps) -> StdResult<DenomResponse> {
+    let denom = CONFIG.load(deps.storage)?.denom;
+    Ok(DenomResponse { denom })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{coin, SubMsg};
+    use cw_utils::PaymentError;
+
+    const DENOM: &str = "ibc/27394FB092D2ECCD56123C74F36E4C1F926001CEADA9CA97EA622B25F41E5EB2";
+
+    fn do_instantiate(deps: DepsMut) {
+        let msg = InstantiateMsg {
+            name: "Wrapped Atom".to_string(),
+            symbol: "wATOM".to_string(),
+            decimals: 6,
+            denom: DENOM.to_string(),
+        };
+        instantiate(deps, mock_env(), mock_info("creator", &[]), msg).unwrap();
+    }
+
+    fn balance(deps: Deps, address: &str) -> u128 {
+        query_balance(deps, address.to_string())
+            .unwrap()
+            .balance
+            .u128()
+    }
+
+    fn total_supply(deps: Deps) -> u128 {
+        query_token_info(deps).unwrap().total_supply.u128()
+    }
+
+    #[test]
+    fn deposit_and_withdraw() {
+        let mut deps = mock_dependencies();
+        do_instantiate(deps.as_mut());
+        assert_eq!(query_denom(deps.as_ref()).unwrap().denom, DENOM);
+
+        // deposits mint one token per coin
+        let info = mock_info("alice", &coins(1000, DENOM));
+        let msg = ExecuteMsg::Deposit { recipient: None };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let info = mock_info("alice", &coins(500, DENOM));
+        let msg = ExecuteMsg::Deposit {
+            recipient: Some("bob".to_string()),
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(balance(deps.as_ref(), "alice"), 1000);
+        assert_eq!(balance(deps.as_ref(), "bob"), 500);
+        assert_eq!(total_supply(deps.as_ref()), 1500);
+
+        // withdrawals burn the tokens and pay out the coins
+        let msg = ExecuteMsg::Withdraw {
+            amount: Uint128::new(300),
+            recipient: Some("carl".to_string()),
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), msg).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "carl".to_string(),
+                amount: coins(300, DENOM),
+            })]
+        );
+        assert_eq!(balance(deps.as_ref(), "bob"), 200);
+        assert_eq!(total_supply(deps.as_ref()), 1200);
+
+        // no more than the balance can be withdrawn
+        let msg = ExecuteMsg::Withdraw {
+            amount: Uint128::new(201),
+            recipient: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::Base(_)));
+    }
+
+    #[test]
+    fn only_wrapped_denom_can_be_deposited() {
+        let mut deps = mock_dependencies();
+        do_instantiate(deps.as_mut());
+
+        let msg = ExecuteMsg::Deposit { recipient: None };
+        let info = mock_info("alice", &[coin(1000, "uother")]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Payment(PaymentError::MissingDenom(DENOM.to_string()))
+        );
+        let info = mock_info("alice", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::Payment(PaymentError::NoFunds {}));
+        assert_eq!(total_supply(deps.as_ref()), 0);
+    }
+}
@@ -0,0 +1,17 @@
+/*!
+This is a [cw20](https://github.com/CosmWasm/cw-plus/blob/main/packages/cw20/README.md) wrapper of a
+native denom. Deposited coins mint the same amount of cw20 tokens, implemented by
+[cw20-base](https://github.com/CosmWasm/cw-plus/blob/main/contracts/cw20-base/README.md), which
+are burned again to withdraw the coins. This lets protocols that only support cw20 tokens handle
+native and IBC denoms the same way.
+
+For more information on this contract, please check out the
+[README](https://github.com/CosmWasm/cw-plus/blob/main/contracts/cw20-wrap/README.md).
+*/
+
+pub mod contract;
+mod error;
+pub mod msg;
+pub mod state;
+
+pub use crate::error::ContractError;
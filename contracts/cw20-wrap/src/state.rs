@@ -0,0 +1,10 @@
+use cosmwasm_schema::cw_serde;
+use cw_storage_plus::Item;
+
+#[cw_serde]
+pub struct Config {
+    /// The native denom backing the wrapped tokens
+    pub denom: String,
+}
+
+pub const CONFIG: Item<Config> = Item::new("wrap_config");
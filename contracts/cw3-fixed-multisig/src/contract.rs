@@ -1,23 +1,34 @@
 use std::cmp::Ordering;
+use std::collections::BTreeMap;
 
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    to_binary, Binary, BlockInfo, CosmosMsg, Deps, DepsMut, Empty, Env, MessageInfo, Order,
-    Response, StdResult,
+    from_binary, to_binary, Addr, BankMsg, Binary, BlockInfo, CosmosMsg, Decimal, Deps, DepsMut,
+    Empty, Env, Event, MessageInfo, Order, Reply, Response, StdResult, Storage, SubMsg,
+    SubMsgResult, WasmMsg,
 };
 
 use cw2::set_contract_version;
+use cw20::Cw20ReceiveMsg;
 use cw3::{
-    Ballot, Proposal, ProposalListResponse, ProposalResponse, Status, Vote, VoteInfo,
-    VoteListResponse, VoteResponse, VoterDetail, VoterListResponse, VoterResponse, Votes,
+    is_valid_options, vote_commitment, Ballot, CloseReward, DepositError, DepositStatus,
+    ExecutionWindow, Proposal, ProposalHookMsg, ProposalKind, ProposalListResponse,
+    ProposalMetadata, ProposalOption, ProposalResponse, Status, TallyResponse, Vote,
+    VoteCommitmentResponse, VoteInfo, VoteListResponse, VotePermit, VoteResponse, VoterDetail,
+    VoterListResponse, VoterResponse, Votes, WinCondition,
 };
 use cw_storage_plus::Bound;
-use cw_utils::{Expiration, ThresholdResponse};
+use cw_utils::{Duration, Expiration, Threshold, ThresholdError, ThresholdResponse};
 
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::state::{next_id, Config, BALLOTS, CONFIG, PROPOSALS, VOTERS};
+use crate::msg::{ExecuteMsg, ExportResponse, InstantiateMsg, QueryMsg, Voter};
+use crate::state::{
+    clear_commitments, next_id, proposal_hook_msgs, remove_proposal, status_hook_msgs, Commitment,
+    Config, EmergencyTrack, BALLOTS, CONFIG, OPEN_PROPOSALS, PROPOSALS, PROPOSALS_BY_PROPOSER,
+    PROPOSAL_HOOKS, PROPOSAL_HOOK_REPLY_ID, SCHEDULED_PROPOSALS, VOTERS, VOTE_COMMITMENTS,
+    VOTE_PERMITS,
+};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:cw3-fixed-multisig";
@@ -26,7 +37,7 @@ const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     _info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
@@ -36,6 +47,47 @@ pub fn instantiate(
     let total_weight = msg.voters.iter().map(|v| v.weight).sum();
 
     msg.threshold.validate(total_weight)?;
+    if let Some(cast_threshold) = msg.cast_threshold {
+        if !matches!(msg.threshold, Threshold::AbsoluteCount { .. }) {
+            return Err(ContractError::InvalidCastThreshold {});
+        }
+        if cast_threshold.is_zero() || cast_threshold > Decimal::one() {
+            return Err(ThresholdError::InvalidThreshold {}.into());
+        }
+    }
+
+    let proposal_deposit = msg
+        .proposal_deposit
+        .map(|deposit| deposit.into_checked(deps.as_ref()))
+        .transpose()?;
+    if let Some(close_reward) = &msg.close_reward {
+        close_reward.validate(proposal_deposit.as_ref())?;
+    }
+
+    let emergency = msg
+        .emergency
+        .map(|track| -> Result<_, ContractError> {
+            let track = EmergencyTrack {
+                council: track
+                    .council
+                    .iter()
+                    .map(|addr| deps.api.addr_validate(addr))
+                    .collect::<StdResult<_>>()?,
+                threshold: track.threshold,
+                voting_period: track.voting_period,
+            };
+            if track.council.is_empty() || !track.is_shorter(msg.max_voting_period) {
+                return Err(ContractError::InvalidEmergencyTrack {});
+            }
+            track.threshold.validate(total_weight)?;
+            Ok(track)
+        })
+        .transpose()?;
+    if let Some(private_voting) = &msg.private_voting {
+        if !private_voting.is_valid(msg.max_voting_period) {
+            return Err(ContractError::InvalidPrivateVoting {});
+        }
+    }
 
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
@@ -43,13 +95,19 @@ pub fn instantiate(
         threshold: msg.threshold,
         total_weight,
         max_voting_period: msg.max_voting_period,
+        proposal_deposit,
+        close_reward: msg.close_reward,
+        allow_revoting: msg.allow_revoting,
+        cast_threshold: msg.cast_threshold,
+        emergency,
+        private_voting: msg.private_voting,
     };
     CONFIG.save(deps.storage, &cfg)?;
 
     // add all voters
     for voter in msg.voters.iter() {
         let key = deps.api.addr_validate(&voter.addr)?;
-        VOTERS.save(deps.storage, &key, &voter.weight)?;
+        VOTERS.save(deps.storage, &key, &voter.weight, env.block.height)?;
     }
     Ok(Response::default())
 }
@@ -62,18 +120,180 @@ pub fn execute(
     msg: ExecuteMsg,
 ) -> Result<Response<Empty>, ContractError> {
     match msg {
-        ExecuteMsg::Propose {
+        msg @ (ExecuteMsg::Propose { .. }
+        | ExecuteMsg::ProposeSignaling { .. }
+        | ExecuteMsg::ProposeEmergency { .. }
+        | ExecuteMsg::ProposeChoice { .. }) => execute_propose_msg(deps, env, info, msg, false),
+        ExecuteMsg::Receive(wrapper) => execute_receive(deps, env, info, wrapper),
+        ExecuteMsg::Amend {
+            proposal_id,
             title,
             description,
             msgs,
-            latest,
-        } => execute_propose(deps, env, info, title, description, msgs, latest),
+        } => execute_amend(deps, env, info, proposal_id, title, description, msgs),
         ExecuteMsg::Vote { proposal_id, vote } => execute_vote(deps, env, info, proposal_id, vote),
-        ExecuteMsg::Execute { proposal_id } => execute_execute(deps, env, info, proposal_id),
+        ExecuteMsg::CommitVote {
+            proposal_id,
+            commitment,
+        } => execute_commit_vote(deps, env, info, proposal_id, commitment),
+        ExecuteMsg::RevealVote {
+            proposal_id,
+            vote,
+            salt,
+        } => execute_reveal_vote(deps, env, info, proposal_id, vote, salt),
+        ExecuteMsg::VoteChoice {
+            proposal_id,
+            option,
+        } => execute_vote_choice(deps, env, info, proposal_id, option),
+        ExecuteMsg::RelayVote {
+            ballot,
+            pubkey,
+            signature,
+        } => execute_relay_vote(deps, env, info, ballot, pubkey, signature),
+        ExecuteMsg::Execute {
+            proposal_id,
+            msg_indices,
+        } => execute_execute(deps, env, info, proposal_id, msg_indices),
+        ExecuteMsg::DispatchProposal {
+            proposal_id,
+            msg_indices,
+        } => execute_dispatch_proposal(deps, env, info, proposal_id, msg_indices),
         ExecuteMsg::Close { proposal_id } => execute_close(deps, env, info, proposal_id),
+        ExecuteMsg::Cancel { proposal_id } => execute_cancel(deps, env, info, proposal_id),
+        ExecuteMsg::CloseMany { start_after, limit } => {
+            execute_close_many(deps, env, info, start_after, limit)
+        }
+        ExecuteMsg::PruneProposals {
+            older_than,
+            start_after,
+            limit,
+        } => execute_prune_proposals(deps, env, info, older_than, start_after, limit),
+        ExecuteMsg::UpdateVoters { add, remove } => {
+            execute_update_voters(deps, env, info, add, remove)
+        }
+        ExecuteMsg::AddProposalHook { addr } => execute_add_proposal_hook(deps, env, info, addr),
+        ExecuteMsg::RemoveProposalHook { addr } => {
+            execute_remove_proposal_hook(deps, env, info, addr)
+        }
+    }
+}
+
+/// Creates the proposal of the message, whose deposit was already paid if `deposit_paid`
+fn execute_propose_msg(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+    deposit_paid: bool,
+) -> Result<Response<Empty>, ContractError> {
+    match msg {
+        ExecuteMsg::Propose {
+            title,
+            description,
+            msgs,
+            latest,
+            execution_window,
+            metadata,
+        } => execute_propose(
+            deps,
+            env,
+            info,
+            title,
+            description,
+            msgs,
+            latest,
+            execution_window,
+            metadata,
+            vec![],
+            None,
+            deposit_paid,
+            ProposalKind::Action,
+        ),
+        ExecuteMsg::ProposeSignaling {
+            title,
+            description,
+            latest,
+            metadata,
+        } => execute_propose(
+            deps,
+            env,
+            info,
+            title,
+            description,
+            vec![],
+            latest,
+            None,
+            metadata,
+            vec![],
+            None,
+            deposit_paid,
+            ProposalKind::Signaling,
+        ),
+        ExecuteMsg::ProposeEmergency {
+            title,
+            description,
+            msgs,
+            metadata,
+        } => execute_propose(
+            deps,
+            env,
+            info,
+            title,
+            description,
+            msgs,
+            None,
+            None,
+            metadata,
+            vec![],
+            None,
+            deposit_paid,
+            ProposalKind::Emergency,
+        ),
+        ExecuteMsg::ProposeChoice {
+            title,
+            description,
+            options,
+            win_condition,
+            latest,
+            metadata,
+        } => execute_propose(
+            deps,
+            env,
+            info,
+            title,
+            description,
+            vec![],
+            latest,
+            None,
+            metadata,
+            options,
+            Some(win_condition),
+            deposit_paid,
+            ProposalKind::Choice,
+        ),
+        _ => Err(ContractError::NotProposal {}),
     }
 }
 
+/// Creates a proposal for the sender of the cw20 tokens, which are its deposit
+pub fn execute_receive(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    wrapper: Cw20ReceiveMsg,
+) -> Result<Response<Empty>, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    cfg.proposal_deposit
+        .ok_or(DepositError::InvalidCw20 {})?
+        .check_cw20_deposit_paid(&info.sender, wrapper.amount)?;
+    let proposer = MessageInfo {
+        sender: deps.api.addr_validate(&wrapper.sender)?,
+        funds: vec![],
+    };
+    execute_propose_msg(deps, env, proposer, from_binary(&wrapper.msg)?, true)
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn execute_propose(
     deps: DepsMut,
     env: Env,
@@ -83,7 +303,23 @@ pub fn execute_propose(
     msgs: Vec<CosmosMsg>,
     // we ignore earliest
     latest: Option<Expiration>,
+    execution_window: Option<ExecutionWindow>,
+    metadata: Option<ProposalMetadata>,
+    options: Vec<ProposalOption>,
+    win_condition: Option<WinCondition>,
+    deposit_paid: bool,
+    kind: ProposalKind,
 ) -> Result<Response<Empty>, ContractError> {
+    if let Some(metadata) = &metadata {
+        if !metadata.is_valid() {
+            return Err(ContractError::InvalidMetadata {});
+        }
+    }
+    if kind == ProposalKind::Choice
+        && !(is_valid_options(&options) && win_condition.iter().all(WinCondition::is_valid))
+    {
+        return Err(ContractError::InvalidOptions {});
+    }
     // only members of the multisig can create a proposal
     let vote_power = VOTERS
         .may_load(deps.storage, &info.sender)?
@@ -91,8 +327,36 @@ pub fn execute_propose(
 
     let cfg = CONFIG.load(deps.storage)?;
 
+    // Check that the native deposit was paid (as needed).
+    if let Some(deposit) = cfg.proposal_deposit.as_ref() {
+        deposit.check_native_deposit_paid(&info)?;
+    }
+
+    // emergency proposals are only for the council, with their own voting period and threshold
+    let (max_voting_period, threshold, cast_threshold) = if kind == ProposalKind::Emergency {
+        let track = cfg.emergency.ok_or(ContractError::NoEmergencyTrack {})?;
+        if !track.council.contains(&info.sender) {
+            return Err(ContractError::Unauthorized {});
+        }
+        (track.voting_period, track.threshold, None)
+    } else if kind == ProposalKind::Choice {
+        (cfg.max_voting_period, cfg.threshold, None)
+    } else {
+        (cfg.max_voting_period, cfg.threshold, cfg.cast_threshold)
+    };
+    // votes are committed before being revealed on private proposals
+    let private_voting = match kind {
+        ProposalKind::Action | ProposalKind::Signaling => cfg.private_voting,
+        _ => None,
+    };
+    // the proposer of a choice or private proposal votes like anyone else
+    let proposer_power = match (kind, &private_voting) {
+        (ProposalKind::Choice, _) | (_, Some(_)) => 0,
+        _ => vote_power,
+    };
+
     // max expires also used as default
-    let max_expires = cfg.max_voting_period.after(&env.block);
+    let max_expires = max_voting_period.after(&env.block);
     let mut expires = latest.unwrap_or(max_expires);
     let comp = expires.partial_cmp(&max_expires);
     if let Some(Ordering::Greater) = comp {
@@ -100,6 +364,28 @@ pub fn execute_propose(
     } else if comp.is_none() {
         return Err(ContractError::WrongExpiration {});
     }
+    if let Some(window) = &execution_window {
+        if !window.is_valid() || window.is_over(&env.block) {
+            return Err(ContractError::InvalidExecutionWindow {});
+        }
+    }
+    // the voting period is for commitments then, followed by the reveal period
+    let commit_deadline = match &private_voting {
+        Some(private_voting) => {
+            let deadline = expires;
+            expires = (deadline + private_voting.reveal_period)?;
+            Some(deadline)
+        }
+        None => None,
+    };
+
+    // Take the cw20 token deposit, if required and not sent along with the proposal
+    let take_deposit_msg = match cfg.proposal_deposit.as_ref() {
+        Some(deposit_info) if !deposit_paid => {
+            deposit_info.get_take_deposit_messages(&info.sender, &env.contract.address)?
+        }
+        _ => vec![],
+    };
 
     // create a proposal
     let mut prop = Proposal {
@@ -109,30 +395,145 @@ pub fn execute_propose(
         expires,
         msgs,
         status: Status::Open,
-        votes: Votes::yes(vote_power),
-        threshold: cfg.threshold,
+        votes: Votes::yes(proposer_power),
+        threshold,
         total_weight: cfg.total_weight,
         proposer: info.sender.clone(),
-        deposit: None,
+        deposit_status: cfg.proposal_deposit.as_ref().map(|_| DepositStatus::Held),
+        deposit: cfg.proposal_deposit,
+        kind,
+        execution_error: None,
+        cast_threshold,
+        execution_window,
+        metadata,
+        choice_votes: vec![0; options.len()],
+        options,
+        win_condition,
+        executed_msgs: vec![],
+        commit_deadline,
     };
     prop.update_status(&env.block);
+    let deposit_msgs = prop.conclude_signaling(&env.block)?;
     let id = next_id(deps.storage)?;
     PROPOSALS.save(deps.storage, id, &prop)?;
+    PROPOSALS_BY_PROPOSER.save(deps.storage, (&info.sender, id), &Empty {})?;
+    if prop.execution_window.is_some() {
+        SCHEDULED_PROPOSALS.save(deps.storage, id, &Empty {})?;
+    }
+    if prop.status == Status::Open {
+        OPEN_PROPOSALS.save(deps.storage, id, &Empty {})?;
+    }
 
     // add the first yes vote from voter
-    let ballot = Ballot {
-        weight: vote_power,
-        vote: Vote::Yes,
+    if kind != ProposalKind::Choice && private_voting.is_none() {
+        let ballot = Ballot {
+            weight: vote_power,
+            vote: Vote::Yes,
+            split: None,
+            choice: None,
+        };
+        BALLOTS.save(deps.storage, (id, &info.sender), &ballot)?;
+    }
+
+    let created = ProposalHookMsg::Created {
+        proposal_id: id,
+        proposer: info.sender.to_string(),
     };
-    BALLOTS.save(deps.storage, (id, &info.sender), &ballot)?;
+    let mut hooks = proposal_hook_msgs(deps.storage, created)?;
+    hooks.extend(status_hook_msgs(deps.storage, id, prop.status)?);
 
     Ok(Response::new()
+        .add_messages(take_deposit_msg)
+        .add_messages(deposit_msgs)
+        .add_submessages(hooks)
         .add_attribute("action", "propose")
         .add_attribute("sender", info.sender)
         .add_attribute("proposal_id", id.to_string())
         .add_attribute("status", format!("{:?}", prop.status)))
 }
 
+pub fn execute_amend(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proposal_id: u64,
+    title: Option<String>,
+    description: Option<String>,
+    msgs: Option<Vec<CosmosMsg>>,
+) -> Result<Response<Empty>, ContractError> {
+    let mut prop = PROPOSALS.load(deps.storage, proposal_id)?;
+    if prop.proposer != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    if prop.current_status(&env.block) != Status::Open {
+        return Err(ContractError::NotOpen {});
+    }
+    let others_voted = BALLOTS
+        .prefix(proposal_id)
+        .keys(deps.storage, None, None, Order::Ascending)
+        .any(|voter| !matches!(voter, Ok(voter) if voter == info.sender));
+    let committed = VOTE_COMMITMENTS
+        .prefix(proposal_id)
+        .keys(deps.storage, None, None, Order::Ascending)
+        .next()
+        .is_some();
+    if others_voted || committed {
+        return Err(ContractError::VotesCast {});
+    }
+    if msgs.is_some() && prop.kind == ProposalKind::Signaling {
+        return Err(ContractError::SignalingProposal {});
+    }
+    if msgs.is_some() && prop.kind == ProposalKind::Choice {
+        return Err(ContractError::ChoiceProposal {});
+    }
+
+    if let Some(title) = title {
+        prop.title = title;
+    }
+    if let Some(description) = description {
+        prop.description = description;
+    }
+    if let Some(msgs) = msgs {
+        prop.msgs = msgs;
+    }
+    // the proposer votes again on the amended proposal, at their current weight as when proposing
+    let previous_status = prop.status;
+    prop.votes = Votes::default();
+    BALLOTS.remove(deps.storage, (proposal_id, &info.sender));
+    if prop.kind != ProposalKind::Choice && prop.commit_deadline.is_none() {
+        let vote_power = VOTERS
+            .may_load(deps.storage, &info.sender)?
+            .unwrap_or_default();
+        let ballot = Ballot {
+            weight: vote_power,
+            vote: Vote::Yes,
+            split: None,
+            choice: None,
+        };
+        BALLOTS.save(deps.storage, (proposal_id, &info.sender), &ballot)?;
+        prop.votes.add_vote(Vote::Yes, vote_power);
+    }
+    prop.update_status(&env.block);
+    let deposit_msgs = prop.conclude_signaling(&env.block)?;
+    PROPOSALS.save(deps.storage, proposal_id, &prop)?;
+    if prop.status != Status::Open {
+        OPEN_PROPOSALS.remove(deps.storage, proposal_id);
+    }
+    let hooks = if prop.status != previous_status {
+        status_hook_msgs(deps.storage, proposal_id, prop.status)?
+    } else {
+        vec![]
+    };
+
+    Ok(Response::new()
+        .add_messages(deposit_msgs)
+        .add_submessages(hooks)
+        .add_attribute("action", "amend")
+        .add_attribute("sender", info.sender)
+        .add_attribute("proposal_id", proposal_id.to_string())
+        .add_attribute("status", format!("{:?}", prop.status)))
+}
+
 pub fn execute_vote(
     deps: DepsMut,
     env: Env,
@@ -140,15 +541,36 @@ pub fn execute_vote(
     proposal_id: u64,
     vote: Vote,
 ) -> Result<Response<Empty>, ContractError> {
-    // only members of the multisig with weight >= 1 can vote
-    let voter_power = VOTERS.may_load(deps.storage, &info.sender)?;
+    cast_vote(deps, env, info, proposal_id, vote, false)
+}
+
+/// Casts the vote, which must have been `revealed` on private proposals
+fn cast_vote(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proposal_id: u64,
+    vote: Vote,
+    revealed: bool,
+) -> Result<Response<Empty>, ContractError> {
+    // ensure proposal exists and can be voted on
+    let mut prop = PROPOSALS.load(deps.storage, proposal_id)?;
+    if prop.kind == ProposalKind::Choice {
+        return Err(ContractError::ChoiceProposal {});
+    }
+    if prop.commit_deadline.is_some() && !revealed {
+        return Err(ContractError::PrivateProposal {});
+    }
+
+    // only members of the multisig with weight >= 1 can vote,
+    // as of the end of the block the proposal was created in
+    let voter_power =
+        VOTERS.may_load_at_height(deps.storage, &info.sender, prop.start_height + 1)?;
     let vote_power = match voter_power {
         Some(power) if power >= 1 => power,
         _ => return Err(ContractError::Unauthorized {}),
     };
 
-    // ensure proposal exists and can be voted on
-    let mut prop = PROPOSALS.load(deps.storage, proposal_id)?;
     // Allow voting on Passed and Rejected proposals too,
     if ![Status::Open, Status::Passed, Status::Rejected].contains(&prop.status) {
         return Err(ContractError::NotOpen {});
@@ -158,115 +580,863 @@ pub fn execute_vote(
         return Err(ContractError::Expired {});
     }
 
-    // cast vote if no vote previously cast
-    BALLOTS.update(deps.storage, (proposal_id, &info.sender), |bal| match bal {
-        Some(_) => Err(ContractError::AlreadyVoted {}),
-        None => Ok(Ballot {
-            weight: vote_power,
-            vote,
-        }),
-    })?;
+    // cast vote if no vote previously cast, or if it can still be changed
+    let cfg = CONFIG.load(deps.storage)?;
+    let key = (proposal_id, &info.sender);
+    let previous = BALLOTS.may_load(deps.storage, key)?;
+    if let Some(ballot) = &previous {
+        if !cfg.allow_revoting || prop.status != Status::Open {
+            return Err(ContractError::AlreadyVoted {});
+        }
+        prop.votes.remove_ballot(ballot);
+    }
+    let ballot = Ballot {
+        weight: vote_power,
+        vote,
+        split: None,
+        choice: None,
+    };
+    BALLOTS.save(deps.storage, key, &ballot)?;
 
     // update vote tally
+    let previous_status = prop.status;
     prop.votes.add_vote(vote, vote_power);
     prop.update_status(&env.block);
+    let deposit_msgs = prop.conclude_signaling(&env.block)?;
     PROPOSALS.save(deps.storage, proposal_id, &prop)?;
+    if prop.status != Status::Open {
+        OPEN_PROPOSALS.remove(deps.storage, proposal_id);
+    }
+    let hooks = if prop.status != previous_status {
+        status_hook_msgs(deps.storage, proposal_id, prop.status)?
+    } else {
+        vec![]
+    };
 
     Ok(Response::new()
+        .add_messages(deposit_msgs)
+        .add_submessages(hooks)
+        .add_events(vote_changed_event(
+            proposal_id,
+            &info.sender,
+            previous,
+            &ballot,
+        ))
         .add_attribute("action", "vote")
         .add_attribute("sender", info.sender)
         .add_attribute("proposal_id", proposal_id.to_string())
         .add_attribute("status", format!("{:?}", prop.status)))
 }
 
-pub fn execute_execute(
+/// Records the commitment of the voter to a vote on a private proposal, holding their bond
+pub fn execute_commit_vote(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
     proposal_id: u64,
-) -> Result<Response, ContractError> {
-    // anyone can trigger this if the vote passed
+    commitment: Binary,
+) -> Result<Response<Empty>, ContractError> {
+    let prop = PROPOSALS.load(deps.storage, proposal_id)?;
+    if prop.commit_deadline.is_none() {
+        return Err(ContractError::NotPrivateProposal {});
+    }
 
-    let mut prop = PROPOSALS.load(deps.storage, proposal_id)?;
-    // we allow execution even after the proposal "expiration" as long as all vote come in before
-    // that point. If it was approved on time, it can be executed any time.
-    prop.update_status(&env.block);
-    if prop.status != Status::Passed {
-        return Err(ContractError::WrongExecuteStatus {});
+    // only members of the multisig with weight >= 1 can vote,
+    // as of the end of the block the proposal was created in
+    let voter_power =
+        VOTERS.may_load_at_height(deps.storage, &info.sender, prop.start_height + 1)?;
+    if !matches!(voter_power, Some(power) if power >= 1) {
+        return Err(ContractError::Unauthorized {});
     }
 
-    // set it to executed
-    prop.status = Status::Executed;
-    PROPOSALS.save(deps.storage, proposal_id, &prop)?;
+    if prop.status != Status::Open {
+        return Err(ContractError::NotOpen {});
+    }
+    if !prop.is_committing(&env.block) {
+        return Err(ContractError::CommitmentsClosed {});
+    }
+    let key = (proposal_id, &info.sender);
+    if VOTE_COMMITMENTS.has(deps.storage, key) {
+        return Err(ContractError::AlreadyVoted {});
+    }
+    let bond = CONFIG
+        .load(deps.storage)?
+        .private_voting
+        .and_then(|p| p.bond);
+    if info.funds != bond.clone().into_iter().collect::<Vec<_>>() {
+        return Err(ContractError::WrongBond {});
+    }
+    let commitment = Commitment {
+        hash: commitment,
+        bond,
+    };
+    VOTE_COMMITMENTS.save(deps.storage, key, &commitment)?;
 
-    // dispatch all proposed messages
     Ok(Response::new()
-        .add_messages(prop.msgs)
-        .add_attribute("action", "execute")
+        .add_attribute("action", "commit_vote")
         .add_attribute("sender", info.sender)
         .add_attribute("proposal_id", proposal_id.to_string()))
 }
 
-pub fn execute_close(
+/// Casts the vote the voter committed to, refunding their bond
+pub fn execute_reveal_vote(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
     proposal_id: u64,
+    vote: Vote,
+    salt: Binary,
 ) -> Result<Response<Empty>, ContractError> {
-    // anyone can trigger this if the vote passed
+    let prop = PROPOSALS.load(deps.storage, proposal_id)?;
+    let key = (proposal_id, &info.sender);
+    let commitment = VOTE_COMMITMENTS
+        .may_load(deps.storage, key)?
+        .ok_or(ContractError::NoCommitment {})?;
+    if prop.is_committing(&env.block) {
+        return Err(ContractError::CommitmentsOpen {});
+    }
+    if prop.expires.is_expired(&env.block) {
+        return Err(ContractError::Expired {});
+    }
+    if vote_commitment(proposal_id, info.sender.as_str(), vote, &salt)? != commitment.hash {
+        return Err(ContractError::InvalidReveal {});
+    }
+    VOTE_COMMITMENTS.remove(deps.storage, key);
+
+    let refund = commitment.bond.map(|bond| BankMsg::Send {
+        to_address: info.sender.to_string(),
+        amount: vec![bond],
+    });
+    let res = cast_vote(deps, env, info, proposal_id, vote, true)?;
+    Ok(res.add_messages(refund))
+}
+
+/// Casts the vote the voter signed off-chain, for the relayer
+pub fn execute_relay_vote(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    ballot: VotePermit,
+    pubkey: Binary,
+    signature: Binary,
+) -> Result<Response<Empty>, ContractError> {
+    let voter = deps.api.addr_validate(&ballot.voter)?;
+    // the ballot must have been signed for this chain and contract
+    let ballot = VotePermit {
+        chain_id: env.block.chain_id.clone(),
+        contract: env.contract.address.to_string(),
+        ..ballot
+    };
+    VOTE_PERMITS.use_permit(
+        deps.api,
+        deps.storage,
+        &env.block,
+        &voter,
+        ballot.nonce,
+        &ballot.deadline,
+        ballot.sign_bytes()?.as_slice(),
+        &pubkey,
+        &signature,
+    )?;
+
+    let signer = MessageInfo {
+        sender: voter,
+        funds: vec![],
+    };
+    let res = execute_vote(deps, env, signer, ballot.proposal_id, ballot.vote)?;
+    Ok(res.add_attribute("relayer", info.sender))
+}
 
+pub fn execute_vote_choice(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proposal_id: u64,
+    option: u32,
+) -> Result<Response<Empty>, ContractError> {
     let mut prop = PROPOSALS.load(deps.storage, proposal_id)?;
-    if [Status::Executed, Status::Rejected, Status::Passed].contains(&prop.status) {
-        return Err(ContractError::WrongCloseStatus {});
+    if prop.kind != ProposalKind::Choice {
+        return Err(ContractError::NotChoiceProposal {});
     }
-    // Avoid closing of Passed due to expiration proposals
-    if prop.current_status(&env.block) == Status::Passed {
-        return Err(ContractError::WrongCloseStatus {});
+    let index = option as usize;
+    if index >= prop.options.len() {
+        return Err(ContractError::UnknownOption { option });
     }
-    if !prop.expires.is_expired(&env.block) {
-        return Err(ContractError::NotExpired {});
+
+    // only members of the multisig with weight >= 1 can vote,
+    // as of the end of the block the proposal was created in
+    let voter_power =
+        VOTERS.may_load_at_height(deps.storage, &info.sender, prop.start_height + 1)?;
+    let vote_power = match voter_power {
+        Some(power) if power >= 1 => power,
+        _ => return Err(ContractError::Unauthorized {}),
+    };
+
+    // the winning option is settled once passed, so votes are only cast while open
+    if prop.status != Status::Open {
+        return Err(ContractError::NotOpen {});
+    }
+    if prop.expires.is_expired(&env.block) {
+        return Err(ContractError::Expired {});
     }
 
-    // set it to failed
-    prop.status = Status::Rejected;
+    // cast vote if no vote previously cast, or if it can still be changed
+    let cfg = CONFIG.load(deps.storage)?;
+    let key = (proposal_id, &info.sender);
+    if let Some(previous) = BALLOTS.may_load(deps.storage, key)? {
+        if !cfg.allow_revoting {
+            return Err(ContractError::AlreadyVoted {});
+        }
+        if let Some(choice) = previous.choice {
+            prop.choice_votes[choice as usize] -= previous.weight;
+        }
+    }
+    let ballot = Ballot {
+        weight: vote_power,
+        vote: Vote::Yes,
+        split: None,
+        choice: Some(option),
+    };
+    BALLOTS.save(deps.storage, key, &ballot)?;
+
+    // update vote tally
+    prop.choice_votes[index] += vote_power;
+    prop.update_status(&env.block);
     PROPOSALS.save(deps.storage, proposal_id, &prop)?;
+    let hooks = if prop.status != Status::Open {
+        OPEN_PROPOSALS.remove(deps.storage, proposal_id);
+        status_hook_msgs(deps.storage, proposal_id, prop.status)?
+    } else {
+        vec![]
+    };
 
     Ok(Response::new()
-        .add_attribute("action", "close")
+        .add_submessages(hooks)
+        .add_attribute("action", "vote_choice")
         .add_attribute("sender", info.sender)
-        .add_attribute("proposal_id", proposal_id.to_string()))
+        .add_attribute("proposal_id", proposal_id.to_string())
+        .add_attribute("option", option.to_string())
+        .add_attribute("status", format!("{:?}", prop.status)))
 }
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::Threshold {} => to_binary(&query_threshold(deps)?),
-        QueryMsg::Proposal { proposal_id } => to_binary(&query_proposal(deps, env, proposal_id)?),
-        QueryMsg::Vote { proposal_id, voter } => to_binary(&query_vote(deps, proposal_id, voter)?),
-        QueryMsg::ListProposals { start_after, limit } => {
-            to_binary(&list_proposals(deps, env, start_after, limit)?)
-        }
-        QueryMsg::ReverseProposals {
-            start_before,
-            limit,
-        } => to_binary(&reverse_proposals(deps, env, start_before, limit)?),
-        QueryMsg::ListVotes {
-            proposal_id,
-            start_after,
-            limit,
-        } => to_binary(&list_votes(deps, proposal_id, start_after, limit)?),
+/// Tells the previous vote of a voter who changed it, if any
+fn vote_changed_event(
+    proposal_id: u64,
+    voter: &Addr,
+    previous: Option<Ballot>,
+    ballot: &Ballot,
+) -> Option<Event> {
+    previous.map(|previous| {
+        Event::new("vote_changed")
+            .add_attribute("proposal_id", proposal_id.to_string())
+            .add_attribute("voter", voter)
+            .add_attribute("previous_vote", format!("{:?}", previous.vote))
+            .add_attribute("vote", format!("{:?}", ballot.vote))
+    })
+}
+
+pub fn execute_execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proposal_id: u64,
+    msg_indices: Option<Vec<u32>>,
+) -> Result<Response, ContractError> {
+    // anyone can trigger this if the vote passed
+
+    let prop = PROPOSALS.load(deps.storage, proposal_id)?;
+    if prop.kind == ProposalKind::Signaling {
+        return Err(ContractError::SignalingProposal {});
+    }
+    // we allow execution even after the proposal "expiration" as long as all vote come in before
+    // that point. If it was approved on time, it can be executed any time, and executed again
+    // if its messages failed.
+    let status = prop.current_status(&env.block);
+    if status != Status::Passed && status != Status::ExecutionFailed {
+        return Err(ContractError::WrongExecuteStatus {});
+    }
+    if let Some(window) = &prop.execution_window {
+        if !window.is_open(&env.block) {
+            return Err(ContractError::OutsideExecutionWindow {});
+        }
+    }
+    if prop.msg_indices_to_execute(msg_indices.clone()).is_none() {
+        return Err(ContractError::InvalidMsgIndices {});
+    }
+
+    // dispatch the proposed messages through the multisig itself, so that their failure is
+    // recorded instead of reverting the execution
+    let dispatch = WasmMsg::Execute {
+        contract_addr: env.contract.address.to_string(),
+        msg: to_binary(&ExecuteMsg::DispatchProposal {
+            proposal_id,
+            msg_indices,
+        })?,
+        funds: vec![],
+    };
+    Ok(Response::new()
+        .add_submessage(SubMsg::reply_on_error(dispatch, proposal_id))
+        .add_attribute("action", "execute")
+        .add_attribute("sender", info.sender)
+        .add_attribute("proposal_id", proposal_id.to_string()))
+}
+
+pub fn execute_dispatch_proposal(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proposal_id: u64,
+    msg_indices: Option<Vec<u32>>,
+) -> Result<Response, ContractError> {
+    if info.sender != env.contract.address {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut prop = PROPOSALS.load(deps.storage, proposal_id)?;
+    let msg_indices = prop
+        .msg_indices_to_execute(msg_indices)
+        .ok_or(ContractError::InvalidMsgIndices {})?;
+    let msgs = prop.msgs_to_execute();
+    let msgs: Vec<_> = msg_indices
+        .iter()
+        .map(|index| msgs[*index as usize].clone())
+        .collect();
+    let executed = msg_indices
+        .iter()
+        .map(u32::to_string)
+        .collect::<Vec<_>>()
+        .join(",");
+    prop.executed_msgs.extend(msg_indices);
+    prop.executed_msgs.sort_unstable();
+
+    // it stays passed until all its messages are executed
+    if !prop.all_msgs_executed() {
+        prop.status = Status::Passed;
+        prop.execution_error = None;
+        PROPOSALS.save(deps.storage, proposal_id, &prop)?;
+        return Ok(Response::new()
+            .add_messages(msgs)
+            .add_attribute("action", "dispatch_proposal")
+            .add_attribute("proposal_id", proposal_id.to_string())
+            .add_attribute("msg_indices", executed));
+    }
+
+    // set it to executed, refunding the deposit if configured to do so
+    prop.status = Status::Executed;
+    prop.execution_error = None;
+    let deposit_msgs = prop.settle_deposit(true)?;
+    PROPOSALS.save(deps.storage, proposal_id, &prop)?;
+    SCHEDULED_PROPOSALS.remove(deps.storage, proposal_id);
+    OPEN_PROPOSALS.remove(deps.storage, proposal_id);
+    let refunds = clear_commitments(deps.storage, &env.block, proposal_id, &prop)?;
+    let hooks = status_hook_msgs(deps.storage, proposal_id, prop.status)?;
+
+    Ok(Response::new()
+        .add_messages(deposit_msgs)
+        .add_messages(refunds)
+        .add_messages(msgs)
+        .add_submessages(hooks)
+        .add_attribute("action", "dispatch_proposal")
+        .add_attribute("proposal_id", proposal_id.to_string()))
+}
+
+pub fn execute_close(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proposal_id: u64,
+) -> Result<Response<Empty>, ContractError> {
+    // anyone can trigger this if the vote passed
+
+    let cfg = CONFIG.load(deps.storage)?;
+    let prop = PROPOSALS.load(deps.storage, proposal_id)?;
+    check_closable(&prop, &env.block)?;
+
+    let (mut msgs, hooks) = close_proposal(
+        deps.storage,
+        &env.block,
+        &cfg,
+        &info.sender,
+        proposal_id,
+        prop,
+    )?;
+    msgs.extend(treasury_reward(
+        deps.as_ref(),
+        &env,
+        &cfg,
+        &info.sender,
+        1,
+        &msgs,
+    )?);
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_submessages(hooks)
+        .add_attribute("action", "close")
+        .add_attribute("sender", info.sender)
+        .add_attribute("proposal_id", proposal_id.to_string()))
+}
+
+pub fn execute_cancel(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proposal_id: u64,
+) -> Result<Response<Empty>, ContractError> {
+    // only the proposer can withdraw it, until anybody votes against it
+    let mut prop = PROPOSALS.load(deps.storage, proposal_id)?;
+    if prop.proposer != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    check_cancellable(&prop, &env.block)?;
+    if prop.has_opposing_votes() {
+        return Err(ContractError::OpposedProposal {});
+    }
+
+    // set it to cancelled, settling the deposit as for rejected proposals
+    prop.status = Status::Cancelled;
+    let deposit_msgs = prop.settle_deposit(false)?;
+    PROPOSALS.save(deps.storage, proposal_id, &prop)?;
+    SCHEDULED_PROPOSALS.remove(deps.storage, proposal_id);
+    OPEN_PROPOSALS.remove(deps.storage, proposal_id);
+    let refunds = clear_commitments(deps.storage, &env.block, proposal_id, &prop)?;
+    let hooks = status_hook_msgs(deps.storage, proposal_id, prop.status)?;
+
+    Ok(Response::new()
+        .add_messages(deposit_msgs)
+        .add_messages(refunds)
+        .add_submessages(hooks)
+        .add_attribute("action", "cancel")
+        .add_attribute("sender", info.sender)
+        .add_attribute("proposal_id", proposal_id.to_string()))
+}
+
+pub fn execute_close_many(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> Result<Response<Empty>, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    // only `limit` proposals are read, closable or not, so the gas does not grow with the history
+    let proposals = PROPOSALS
+        .range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    let last_read = proposals.last().map(|(id, _)| *id);
+    let read = proposals.len();
+    let closable: Vec<_> = proposals
+        .into_iter()
+        .filter(|(_, prop)| check_closable(prop, &env.block).is_ok())
+        .collect();
+    // a full batch may be followed by closable proposals
+    let last_read = match last_read {
+        Some(id) if !closable.is_empty() || read == limit => id,
+        _ => return Err(ContractError::NothingToClose {}),
+    };
+
+    let mut msgs = vec![];
+    let mut hooks = vec![];
+    let mut ids = vec![];
+    for (id, prop) in closable {
+        let (close_msgs, close_hooks) =
+            close_proposal(deps.storage, &env.block, &cfg, &info.sender, id, prop)?;
+        msgs.extend(close_msgs);
+        hooks.extend(close_hooks);
+        ids.push(id.to_string());
+    }
+    let closed = ids.len() as u128;
+    if closed > 0 {
+        msgs.extend(treasury_reward(
+            deps.as_ref(),
+            &env,
+            &cfg,
+            &info.sender,
+            closed,
+            &msgs,
+        )?);
+    }
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_submessages(hooks)
+        .add_attribute("action", "close_many")
+        .add_attribute("sender", info.sender)
+        .add_attribute("proposal_ids", ids.join(","))
+        .add_attribute("last_proposal_id", last_read.to_string()))
+}
+
+/// Removes old proposals to reclaim their storage. Anyone can do so, as only proposals that
+/// are over, with their deposit settled, are removed.
+pub fn execute_prune_proposals(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    older_than: Duration,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> Result<Response<Empty>, ContractError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    // only `limit` proposals are read, prunable or not, as for `execute_close_many`
+    let proposals = PROPOSALS
+        .range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    let last_read = proposals.last().map(|(id, _)| *id);
+    let read = proposals.len();
+    let prunable: Vec<_> = proposals
+        .into_iter()
+        .filter(|(_, prop)| is_prunable(prop, older_than, &env.block))
+        .collect();
+    let last_read = match last_read {
+        Some(id) if !prunable.is_empty() || read == limit => id,
+        _ => return Err(ContractError::NothingToPrune {}),
+    };
+
+    let mut ids = vec![];
+    for (id, prop) in prunable {
+        remove_proposal(deps.storage, id, &prop)?;
+        ids.push(id.to_string());
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "prune_proposals")
+        .add_attribute("sender", info.sender)
+        .add_attribute("proposal_ids", ids.join(","))
+        .add_attribute("last_proposal_id", last_read.to_string()))
+}
+
+/// Whether the proposal was executed, rejected or cancelled, with its deposit settled, and expired
+/// `older_than` ago. Never if `older_than` is not in the unit of its expiration
+fn is_prunable(prop: &Proposal, older_than: Duration, block: &BlockInfo) -> bool {
+    [Status::Executed, Status::Rejected, Status::Cancelled].contains(&prop.status)
+        && prop.deposit_status != Some(DepositStatus::Held)
+        && matches!(prop.expires + older_than, Ok(end) if end.is_expired(block))
+}
+
+/// Proposals can be cancelled until they are executed, or concluded otherwise (eg. signaling
+/// proposals that passed)
+fn check_cancellable(prop: &Proposal, block: &BlockInfo) -> Result<(), ContractError> {
+    let status = prop.current_status(block);
+    let pending = [Status::Open, Status::Passed, Status::ExecutionFailed].contains(&status);
+    if !pending || (prop.kind == ProposalKind::Signaling && status == Status::Passed) {
+        return Err(ContractError::WrongCancelStatus {});
+    }
+    Ok(())
+}
+
+fn check_closable(prop: &Proposal, block: &BlockInfo) -> Result<(), ContractError> {
+    // Proposals rejected by votes can be closed once, to settle their deposit,
+    // and so can signaling proposals that passed on expiration
+    let concluded = prop.status == Status::Rejected
+        || (prop.kind == ProposalKind::Signaling && prop.current_status(block) == Status::Passed);
+    if concluded && prop.deposit_status == Some(DepositStatus::Held) {
+        return Ok(());
+    }
+    // Passed proposals can be closed once their execution window is over too
+    let missed = matches!(&prop.execution_window, Some(window) if window.is_over(block));
+    let status = prop.current_status(block);
+    if missed && [Status::Passed, Status::ExecutionFailed].contains(&status) {
+        return Ok(());
+    }
+    if [
+        Status::Executed,
+        Status::Rejected,
+        Status::Passed,
+        Status::Cancelled,
+    ]
+    .contains(&prop.status)
+    {
+        return Err(ContractError::WrongCloseStatus {});
+    }
+    // Avoid closing of Passed due to expiration proposals
+    if prop.current_status(block) == Status::Passed {
+        return Err(ContractError::WrongCloseStatus {});
+    }
+    if !prop.expires.is_expired(block) {
+        return Err(ContractError::NotExpired {});
+    }
+    Ok(())
+}
+
+/// Sets the proposal to failed, paying the closing reward out of its deposit and refunding
+/// the rest if configured to do so. Passed signaling proposals only have their deposit settled
+fn close_proposal(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    cfg: &Config,
+    closer: &Addr,
+    proposal_id: u64,
+    mut prop: Proposal,
+) -> StdResult<(Vec<CosmosMsg>, Vec<SubMsg>)> {
+    let previous_status = prop.status;
+    let mut msgs = prop.conclude_signaling(block)?;
+    OPEN_PROPOSALS.remove(storage, proposal_id);
+    let refunds = clear_commitments(storage, block, proposal_id, &prop)?;
+    msgs.extend(refunds.into_iter().map(CosmosMsg::from));
+    if prop.kind == ProposalKind::Signaling && prop.status == Status::Passed {
+        PROPOSALS.save(storage, proposal_id, &prop)?;
+        let hooks = if previous_status != Status::Passed {
+            status_hook_msgs(storage, proposal_id, prop.status)?
+        } else {
+            vec![]
+        };
+        return Ok((msgs, hooks));
+    }
+    prop.status = Status::Rejected;
+    SCHEDULED_PROPOSALS.remove(storage, proposal_id);
+    let reward = cfg
+        .close_reward
+        .as_ref()
+        .map(CloseReward::deposit_share)
+        .unwrap_or_default();
+    msgs.extend(prop.settle_deposit_with_reward(closer, reward)?);
+    PROPOSALS.save(storage, proposal_id, &prop)?;
+    let hooks = if previous_status != Status::Rejected {
+        status_hook_msgs(storage, proposal_id, prop.status)?
+    } else {
+        vec![]
+    };
+    Ok((msgs, hooks))
+}
+
+/// The reward for closing `closed` proposals paid by the multisig, if configured so
+fn treasury_reward(
+    deps: Deps,
+    env: &Env,
+    cfg: &Config,
+    closer: &Addr,
+    closed: u128,
+    outgoing: &[CosmosMsg],
+) -> StdResult<Option<CosmosMsg>> {
+    match &cfg.close_reward {
+        Some(reward) => reward.get_treasury_reward_message(
+            &deps.querier,
+            &env.contract.address,
+            closer,
+            closed,
+            outgoing,
+        ),
+        _ => Ok(None),
+    }
+}
+
+/// Registers a contract told of the lifecycle of proposals. Only the multisig itself can do so,
+/// executing a passed proposal.
+pub fn execute_add_proposal_hook(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    addr: String,
+) -> Result<Response<Empty>, ContractError> {
+    if info.sender != env.contract.address {
+        return Err(ContractError::Unauthorized {});
+    }
+    let hook = deps.api.addr_validate(&addr)?;
+    PROPOSAL_HOOKS.add_hook(deps.storage, hook)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "add_proposal_hook")
+        .add_attribute("hook", addr))
+}
+
+/// Unregisters a proposal hook. Only the multisig itself can do so, executing a passed proposal.
+pub fn execute_remove_proposal_hook(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    addr: String,
+) -> Result<Response<Empty>, ContractError> {
+    if info.sender != env.contract.address {
+        return Err(ContractError::Unauthorized {});
+    }
+    let hook = deps.api.addr_validate(&addr)?;
+    PROPOSAL_HOOKS.remove_hook(deps.storage, hook)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_proposal_hook")
+        .add_attribute("hook", addr))
+}
+
+/// Rotates the voters of the multisig. Only the multisig itself can do so, executing a passed
+/// proposal. Open proposals keep the voters (and total weight) they were created with.
+pub fn execute_update_voters(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    add: Vec<Voter>,
+    remove: Vec<String>,
+) -> Result<Response<Empty>, ContractError> {
+    if info.sender != env.contract.address {
+        return Err(ContractError::Unauthorized {});
+    }
+    let mut cfg = CONFIG.load(deps.storage)?;
+
+    // work out the new weights before writing anything, to check them
+    let mut changes: BTreeMap<Addr, Option<u64>> = BTreeMap::new();
+    for addr in remove {
+        changes.insert(deps.api.addr_validate(&addr)?, None);
+    }
+    for voter in add {
+        changes.insert(deps.api.addr_validate(&voter.addr)?, Some(voter.weight));
+    }
+    let mut total_weight = cfg.total_weight;
+    let mut voters = VOTERS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .count();
+    for (addr, weight) in &changes {
+        if let Some(old) = VOTERS.may_load(deps.storage, addr)? {
+            total_weight -= old;
+            voters -= 1;
+        }
+        if let Some(weight) = weight {
+            total_weight += weight;
+            voters += 1;
+        }
+    }
+    if voters == 0 {
+        return Err(ContractError::NoVoters {});
+    }
+    cfg.threshold.validate(total_weight)?;
+    if let Some(track) = &cfg.emergency {
+        track.threshold.validate(total_weight)?;
+    }
+
+    for (addr, weight) in &changes {
+        match weight {
+            Some(weight) => VOTERS.save(deps.storage, addr, weight, env.block.height)?,
+            None => VOTERS.remove(deps.storage, addr, env.block.height)?,
+        }
+    }
+    cfg.total_weight = total_weight;
+    CONFIG.save(deps.storage, &cfg)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_voters")
+        .add_attribute("sender", info.sender)
+        .add_attribute("total_weight", total_weight.to_string()))
+}
+
+/// Only failed dispatches of proposals are replied to, with the proposal id
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    let error = match msg.result {
+        SubMsgResult::Err(error) => error,
+        SubMsgResult::Ok(_) => return Ok(Response::new()),
+    };
+    // hooks may fail without blocking the multisig, this only records it
+    if msg.id == PROPOSAL_HOOK_REPLY_ID {
+        return Ok(Response::new()
+            .add_attribute("action", "proposal_hook_failed")
+            .add_attribute("error", error));
+    }
+    // failed proposals can be executed again, cancelled, or closed once expired, but no longer
+    // voted on, even if they only passed on expiration
+    let mut prop = PROPOSALS.load(deps.storage, msg.id)?;
+    prop.status = Status::ExecutionFailed;
+    prop.execution_error = Some(error.clone());
+    PROPOSALS.save(deps.storage, msg.id, &prop)?;
+    OPEN_PROPOSALS.remove(deps.storage, msg.id);
+
+    Ok(Response::new()
+        .add_attribute("action", "execution_failed")
+        .add_attribute("proposal_id", msg.id.to_string())
+        .add_attribute("status", format!("{:?}", prop.status))
+        .add_attribute("error", error))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Threshold {} => to_binary(&query_threshold(deps)?),
+        QueryMsg::Proposal { proposal_id } => to_binary(&query_proposal(deps, env, proposal_id)?),
+        QueryMsg::ProposalTally { proposal_id } => to_binary(&query_tally(deps, env, proposal_id)?),
+        QueryMsg::Vote { proposal_id, voter } => to_binary(&query_vote(deps, proposal_id, voter)?),
+        QueryMsg::ListProposals { start_after, limit } => {
+            to_binary(&list_proposals(deps, env, start_after, limit)?)
+        }
+        QueryMsg::ReverseProposals {
+            start_before,
+            limit,
+        } => to_binary(&reverse_proposals(deps, env, start_before, limit)?),
+        QueryMsg::ListProposalsByStatus {
+            status,
+            start_after,
+            limit,
+        } => to_binary(&list_proposals_by_status(
+            deps,
+            env,
+            status,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::ScheduledExecutions { start_after, limit } => {
+            to_binary(&list_scheduled_executions(deps, env, start_after, limit)?)
+        }
+        QueryMsg::PendingVotes {
+            voter,
+            start_after,
+            limit,
+        } => to_binary(&list_pending_votes(deps, env, voter, start_after, limit)?),
+        QueryMsg::ListProposalsByProposer {
+            proposer,
+            start_after,
+            limit,
+        } => to_binary(&list_proposals_by_proposer(
+            deps,
+            env,
+            proposer,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::ListVotes {
+            proposal_id,
+            start_after,
+            limit,
+        } => to_binary(&list_votes(deps, proposal_id, start_after, limit)?),
         QueryMsg::Voter { address } => to_binary(&query_voter(deps, address)?),
         QueryMsg::ListVoters { start_after, limit } => {
             to_binary(&list_voters(deps, start_after, limit)?)
         }
+        QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::ProposalHooks {} => to_binary(&PROPOSAL_HOOKS.query_hooks(deps)?),
+        QueryMsg::Export {} => to_binary(&query_export(deps)?),
+        QueryMsg::VoteNonce { voter } => {
+            let voter = deps.api.addr_validate(&voter)?;
+            to_binary(&VOTE_PERMITS.query_nonce(deps, &voter)?)
+        }
+        QueryMsg::VoteCommitment { proposal_id, voter } => {
+            to_binary(&query_vote_commitment(deps, proposal_id, voter)?)
+        }
     }
 }
 
+fn query_config(deps: Deps) -> StdResult<Config> {
+    CONFIG.load(deps.storage)
+}
+
 fn query_threshold(deps: Deps) -> StdResult<ThresholdResponse> {
     let cfg = CONFIG.load(deps.storage)?;
     Ok(cfg.threshold.to_response(cfg.total_weight))
 }
 
+fn query_tally(deps: Deps, env: Env, id: u64) -> StdResult<TallyResponse> {
+    let prop = PROPOSALS.load(deps.storage, id)?;
+    Ok(prop.tally(prop.current_status(&env.block)))
+}
+
 fn query_proposal(deps: Deps, env: Env, id: u64) -> StdResult<ProposalResponse> {
     let prop = PROPOSALS.load(deps.storage, id)?;
     let status = prop.current_status(&env.block);
@@ -279,8 +1449,18 @@ fn query_proposal(deps: Deps, env: Env, id: u64) -> StdResult<ProposalResponse>
         status,
         expires: prop.expires,
         deposit: prop.deposit,
+        deposit_status: prop.deposit_status,
         proposer: prop.proposer,
         threshold,
+        kind: prop.kind,
+        execution_error: prop.execution_error,
+        cast_threshold: prop.cast_threshold,
+        execution_window: prop.execution_window,
+        metadata: prop.metadata,
+        options: prop.options,
+        win_condition: prop.win_condition,
+        executed_msgs: prop.executed_msgs,
+        commit_deadline: prop.commit_deadline,
     })
 }
 
@@ -305,28 +1485,135 @@ fn list_proposals(
     Ok(ProposalListResponse { proposals })
 }
 
-fn reverse_proposals(
+fn list_proposals_by_status(
     deps: Deps,
     env: Env,
-    start_before: Option<u64>,
+    status: Status,
+    start_after: Option<u64>,
     limit: Option<u32>,
 ) -> StdResult<ProposalListResponse> {
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
-    let end = start_before.map(Bound::exclusive);
-    let props: StdResult<Vec<_>> = PROPOSALS
-        .range(deps.storage, None, end, Order::Descending)
-        .take(limit)
+    let start = start_after.map(Bound::exclusive);
+    // statuses change with time, so they are checked as proposals are listed
+    let proposals = PROPOSALS
+        .range(deps.storage, start, None, Order::Ascending)
         .map(|p| map_proposal(&env.block, p))
-        .collect();
+        .filter(|p| !matches!(p, Ok(p) if p.status != status))
+        .take(limit)
+        .collect::<StdResult<_>>()?;
 
-    Ok(ProposalListResponse { proposals: props? })
+    Ok(ProposalListResponse { proposals })
 }
 
-fn map_proposal(
-    block: &BlockInfo,
-    item: StdResult<(u64, Proposal)>,
-) -> StdResult<ProposalResponse> {
-    item.map(|(id, prop)| {
+fn list_proposals_by_proposer(
+    deps: Deps,
+    env: Env,
+    proposer: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ProposalListResponse> {
+    let proposer = deps.api.addr_validate(&proposer)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let proposals = PROPOSALS_BY_PROPOSER
+        .prefix(&proposer)
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|id| {
+            let id = id?;
+            map_proposal(&env.block, Ok((id, PROPOSALS.load(deps.storage, id)?)))
+        })
+        .collect::<StdResult<_>>()?;
+
+    Ok(ProposalListResponse { proposals })
+}
+
+fn reverse_proposals(
+    deps: Deps,
+    env: Env,
+    start_before: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ProposalListResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let end = start_before.map(Bound::exclusive);
+    let props: StdResult<Vec<_>> = PROPOSALS
+        .range(deps.storage, None, end, Order::Descending)
+        .take(limit)
+        .map(|p| map_proposal(&env.block, p))
+        .collect();
+
+    Ok(ProposalListResponse { proposals: props? })
+}
+
+fn list_scheduled_executions(
+    deps: Deps,
+    env: Env,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ProposalListResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    // only the passed ones are pending, until their window is over
+    let pending = |prop: &ProposalResponse| {
+        [Status::Passed, Status::ExecutionFailed].contains(&prop.status)
+            && matches!(&prop.execution_window, Some(window) if !window.is_over(&env.block))
+    };
+    let proposals = SCHEDULED_PROPOSALS
+        .keys(deps.storage, start, None, Order::Ascending)
+        .map(|id| {
+            let id = id?;
+            map_proposal(&env.block, Ok((id, PROPOSALS.load(deps.storage, id)?)))
+        })
+        .filter(|p| !matches!(p, Ok(p) if !pending(p)))
+        .take(limit)
+        .collect::<StdResult<_>>()?;
+
+    Ok(ProposalListResponse { proposals })
+}
+
+fn list_pending_votes(
+    deps: Deps,
+    env: Env,
+    voter: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ProposalListResponse> {
+    let voter = deps.api.addr_validate(&voter)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    // still open, as of the current block, and the voter could vote but did not yet
+    let pending = |id: u64, prop: &Proposal| -> StdResult<bool> {
+        if prop.current_status(&env.block) != Status::Open
+            || BALLOTS.has(deps.storage, (id, &voter))
+        {
+            return Ok(false);
+        }
+        let power = VOTERS.may_load_at_height(deps.storage, &voter, prop.start_height + 1)?;
+        Ok(matches!(power, Some(power) if power >= 1))
+    };
+    let proposals = OPEN_PROPOSALS
+        .keys(deps.storage, start, None, Order::Ascending)
+        .map(|id| {
+            let id = id?;
+            let prop = PROPOSALS.load(deps.storage, id)?;
+            if pending(id, &prop)? {
+                map_proposal(&env.block, Ok((id, prop))).map(Some)
+            } else {
+                Ok(None)
+            }
+        })
+        .filter_map(StdResult::transpose)
+        .take(limit)
+        .collect::<StdResult<_>>()?;
+
+    Ok(ProposalListResponse { proposals })
+}
+
+fn map_proposal(
+    block: &BlockInfo,
+    item: StdResult<(u64, Proposal)>,
+) -> StdResult<ProposalResponse> {
+    item.map(|(id, prop)| {
         let status = prop.current_status(block);
         let threshold = prop.threshold.to_response(prop.total_weight);
         ProposalResponse {
@@ -336,9 +1623,19 @@ fn map_proposal(
             msgs: prop.msgs,
             status,
             deposit: prop.deposit,
+            deposit_status: prop.deposit_status,
             proposer: prop.proposer,
             expires: prop.expires,
             threshold,
+            kind: prop.kind,
+            execution_error: prop.execution_error,
+            cast_threshold: prop.cast_threshold,
+            execution_window: prop.execution_window,
+            metadata: prop.metadata,
+            options: prop.options,
+            win_condition: prop.win_condition,
+            executed_msgs: prop.executed_msgs,
+            commit_deadline: prop.commit_deadline,
         }
     })
 }
@@ -351,10 +1648,24 @@ fn query_vote(deps: Deps, proposal_id: u64, voter: String) -> StdResult<VoteResp
         voter: voter.into(),
         vote: b.vote,
         weight: b.weight,
+        split: b.split,
+        choice: b.choice,
     });
     Ok(VoteResponse { vote })
 }
 
+fn query_vote_commitment(
+    deps: Deps,
+    proposal_id: u64,
+    voter: String,
+) -> StdResult<VoteCommitmentResponse> {
+    let voter = deps.api.addr_validate(&voter)?;
+    let commitment = VOTE_COMMITMENTS.may_load(deps.storage, (proposal_id, &voter))?;
+    Ok(VoteCommitmentResponse {
+        commitment: commitment.map(|commitment| commitment.hash),
+    })
+}
+
 fn list_votes(
     deps: Deps,
     proposal_id: u64,
@@ -374,6 +1685,8 @@ fn list_votes(
                 voter: addr.into(),
                 vote: ballot.vote,
                 weight: ballot.weight,
+                split: ballot.split,
+                choice: ballot.choice,
             })
         })
         .collect::<StdResult<_>>()?;
@@ -409,17 +1722,38 @@ fn list_voters(
     Ok(VoterListResponse { voters })
 }
 
+fn query_export(deps: Deps) -> StdResult<ExportResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let voters = VOTERS
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            item.map(|(addr, weight)| VoterDetail {
+                addr: addr.into(),
+                weight,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(ExportResponse {
+        voters,
+        threshold: cfg.threshold,
+        max_voting_period: cfg.max_voting_period,
+    })
+}
+
 #[cfg(test)]
 mod tests {
-    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-    use cosmwasm_std::{coin, from_binary, BankMsg, Decimal};
+    use cosmwasm_std::testing::{
+        mock_dependencies, mock_dependencies_with_balance, mock_env, mock_info, MOCK_CONTRACT_ADDR,
+    };
+    use cosmwasm_std::{attr, coin, coins, from_binary, BankMsg, Decimal, Uint128};
 
     use cw2::{get_contract_version, ContractVersion};
-    use cw_utils::{Duration, Threshold};
-
-    use crate::msg::Voter;
+    use cw20::UncheckedDenom;
+    use cw3::{DepositError, UncheckedDepositForfeit, UncheckedDepositInfo};
+    use cw_utils::{Duration, PaymentError, Threshold, ThresholdError};
 
     use super::*;
+    use crate::msg::UncheckedEmergencyTrack;
 
     fn mock_env_height(height_delta: u64) -> Env {
         let mut env = mock_env();
@@ -474,6 +1808,12 @@ mod tests {
             voters,
             threshold,
             max_voting_period,
+            proposal_deposit: None,
+            close_reward: None,
+            allow_revoting: false,
+            cast_threshold: None,
+            emergency: None,
+            private_voting: None,
         };
         instantiate(deps, mock_env(), info, instantiate_msg)
     }
@@ -511,6 +1851,12 @@ mod tests {
                 quorum: Decimal::percent(1),
             },
             max_voting_period,
+            proposal_deposit: None,
+            close_reward: None,
+            allow_revoting: false,
+            cast_threshold: None,
+            emergency: None,
+            private_voting: None,
         };
         let err = instantiate(
             deps.as_mut(),
@@ -581,6 +1927,8 @@ mod tests {
             description: "Do we reward her?".to_string(),
             msgs,
             latest: None,
+            execution_window: None,
+            metadata: None,
         };
         let res = execute(deps.as_mut(), mock_env(), info, proposal).unwrap();
 
@@ -621,6 +1969,8 @@ mod tests {
             description: "Do we reward her?".to_string(),
             msgs: msgs.clone(),
             latest: None,
+            execution_window: None,
+            metadata: None,
         };
         let err = execute(deps.as_mut(), mock_env(), info, proposal.clone()).unwrap_err();
         assert_eq!(err, ContractError::Unauthorized {});
@@ -632,6 +1982,8 @@ mod tests {
             description: "Do we reward her?".to_string(),
             msgs,
             latest: Some(Expiration::AtHeight(123456)),
+            execution_window: None,
+            metadata: None,
         };
         let err = execute(deps.as_mut(), mock_env(), info, proposal_wrong_exp).unwrap_err();
         assert_eq!(err, ContractError::WrongExpiration {});
@@ -686,6 +2038,8 @@ mod tests {
             description: "Do I pay her?".to_string(),
             msgs,
             latest: None,
+            execution_window: None,
+            metadata: None,
         };
         let res = execute(deps.as_mut(), mock_env(), info.clone(), proposal).unwrap();
 
@@ -798,6 +2152,8 @@ mod tests {
             description: "Do I pay her?".to_string(),
             msgs,
             latest: None,
+            execution_window: None,
+            metadata: None,
         };
         let res = execute(deps.as_mut(), mock_env(), info, proposal).unwrap();
 
@@ -910,6 +2266,8 @@ mod tests {
             description: "Do I pay her?".to_string(),
             msgs: msgs.clone(),
             latest: None,
+            execution_window: None,
+            metadata: None,
         };
         let res = execute(deps.as_mut(), mock_env(), info.clone(), proposal).unwrap();
 
@@ -917,7 +2275,10 @@ mod tests {
         let proposal_id: u64 = res.attributes[2].value.parse().unwrap();
 
         // Only Passed can be executed
-        let execution = ExecuteMsg::Execute { proposal_id };
+        let execution = ExecuteMsg::Execute {
+            proposal_id,
+            msg_indices: None,
+        };
         let err = execute(deps.as_mut(), mock_env(), info, execution.clone()).unwrap_err();
         assert_eq!(err, ContractError::WrongExecuteStatus {});
 
@@ -948,22 +2309,137 @@ mod tests {
         let info = mock_info(SOMEBODY, &[]);
         let res = execute(deps.as_mut(), mock_env(), info.clone(), execution).unwrap();
 
-        // Verify
+        // Verify the messages are dispatched by the multisig itself
+        let dispatch = ExecuteMsg::DispatchProposal {
+            proposal_id,
+            msg_indices: None,
+        };
+        let submsg = WasmMsg::Execute {
+            contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+            msg: to_binary(&dispatch).unwrap(),
+            funds: vec![],
+        };
         assert_eq!(
             res,
             Response::new()
-                .add_messages(msgs)
+                .add_submessage(SubMsg::reply_on_error(submsg, proposal_id))
                 .add_attribute("action", "execute")
                 .add_attribute("sender", SOMEBODY)
                 .add_attribute("proposal_id", proposal_id.to_string())
         );
 
+        // Only the multisig can dispatch them
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), dispatch.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let contract = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        let res = execute(deps.as_mut(), mock_env(), contract, dispatch).unwrap();
+        assert_eq!(
+            res,
+            Response::new()
+                .add_messages(msgs)
+                .add_attribute("action", "dispatch_proposal")
+                .add_attribute("proposal_id", proposal_id.to_string())
+        );
+
         // In passing: Try to close Executed fails
         let closing = ExecuteMsg::Close { proposal_id };
         let err = execute(deps.as_mut(), mock_env(), info, closing).unwrap_err();
         assert_eq!(err, ContractError::WrongCloseStatus {});
     }
 
+    #[test]
+    fn proposal_deposit_settled() {
+        let mut deps = mock_dependencies();
+
+        let instantiate_msg = InstantiateMsg {
+            voters: vec![voter(OWNER, 1), voter(VOTER1, 2), voter(VOTER2, 3)],
+            threshold: Threshold::AbsoluteCount { weight: 3 },
+            max_voting_period: Duration::Time(2000000),
+            proposal_deposit: Some(UncheckedDepositInfo {
+                amount: Uint128::new(10),
+                denom: UncheckedDenom::Native("TOKEN".to_string()),
+                refund_failed_proposals: true,
+                refund_passed_proposals: false,
+                forfeit: Some(UncheckedDepositForfeit::Burn {}),
+            }),
+            close_reward: None,
+            allow_revoting: false,
+            cast_threshold: None,
+            emergency: None,
+            private_voting: None,
+        };
+        let info = mock_info(OWNER, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, instantiate_msg).unwrap();
+        let cfg: Config =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+        assert_eq!(cfg.proposal_deposit.unwrap().amount, Uint128::new(10));
+
+        let proposal = ExecuteMsg::Propose {
+            title: "Burn".to_string(),
+            description: "Burn my deposit".to_string(),
+            msgs: vec![],
+            latest: None,
+            execution_window: None,
+            metadata: None,
+        };
+
+        // The deposit must be paid
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(VOTER2, &[]),
+            proposal.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Deposit(DepositError::Payment(PaymentError::NoFunds {}))
+        );
+
+        // Passed proposals burn their deposit on execution
+        let info = mock_info(VOTER2, &coins(10, "TOKEN"));
+        execute(deps.as_mut(), mock_env(), info, proposal.clone()).unwrap();
+        let execution = ExecuteMsg::Execute {
+            proposal_id: 1,
+            msg_indices: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), execution).unwrap();
+        let dispatch = ExecuteMsg::DispatchProposal {
+            proposal_id: 1,
+            msg_indices: None,
+        };
+        let contract = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        let res = execute(deps.as_mut(), mock_env(), contract, dispatch).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Burn {
+                amount: coins(10, "TOKEN")
+            })
+        );
+
+        // Proposals rejected by votes are refunded on close, without waiting for expiration
+        let info = mock_info(OWNER, &coins(10, "TOKEN"));
+        execute(deps.as_mut(), mock_env(), info, proposal).unwrap();
+        for voter in [VOTER1, VOTER2] {
+            let vote = ExecuteMsg::Vote {
+                proposal_id: 2,
+                vote: Vote::No,
+            };
+            execute(deps.as_mut(), mock_env(), mock_info(voter, &[]), vote).unwrap();
+        }
+        let closing = ExecuteMsg::Close { proposal_id: 2 };
+        let res = execute(deps.as_mut(), mock_env(), mock_info(VOTER1, &[]), closing).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: OWNER.to_string(),
+                amount: coins(10, "TOKEN"),
+            })
+        );
+        let prop = query_proposal(deps.as_ref(), mock_env(), 2).unwrap();
+        assert_eq!(prop.deposit_status, Some(DepositStatus::Refunded));
+    }
+
     #[test]
     fn proposal_pass_on_expiration() {
         let mut deps = mock_dependencies();
@@ -988,6 +2464,8 @@ mod tests {
             description: "Do I pay her?".to_string(),
             msgs,
             latest: None,
+            execution_window: None,
+            metadata: None,
         };
         let res = execute(deps.as_mut(), mock_env(), info, proposal).unwrap();
 
@@ -1044,7 +2522,10 @@ mod tests {
             deps.as_mut(),
             env,
             info,
-            ExecuteMsg::Execute { proposal_id },
+            ExecuteMsg::Execute {
+                proposal_id,
+                msg_indices: None,
+            },
         )
         .unwrap();
         assert_eq!(
@@ -1078,6 +2559,8 @@ mod tests {
             description: "Do I pay her?".to_string(),
             msgs: msgs.clone(),
             latest: None,
+            execution_window: None,
+            metadata: None,
         };
         let res = execute(deps.as_mut(), mock_env(), info, proposal).unwrap();
 
@@ -1101,6 +2584,8 @@ mod tests {
             description: "Pay somebody after time?".to_string(),
             msgs,
             latest: Some(Expiration::AtHeight(123456)),
+            execution_window: None,
+            metadata: None,
         };
         let res = execute(deps.as_mut(), mock_env(), info.clone(), proposal).unwrap();
 
@@ -1132,4 +2617,1868 @@ mod tests {
         let err = execute(deps.as_mut(), mock_env(), info, closing).unwrap_err();
         assert_eq!(err, ContractError::WrongCloseStatus {});
     }
+
+    #[test]
+    fn voters_updated_by_proposals() {
+        let mut deps = mock_dependencies();
+
+        let threshold = Threshold::AbsoluteCount { weight: 10 };
+        let info = mock_info(OWNER, &[]);
+        setup_test_case(deps.as_mut(), info, threshold, Duration::Time(2000000)).unwrap();
+
+        let proposal = ExecuteMsg::Propose {
+            title: "Pay somebody".to_string(),
+            description: "Do I pay her?".to_string(),
+            msgs: vec![],
+            latest: None,
+            execution_window: None,
+            metadata: None,
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(VOTER1, &[]),
+            proposal.clone(),
+        )
+        .unwrap();
+
+        // Only the multisig itself can update its voters
+        let rotate = ExecuteMsg::UpdateVoters {
+            add: vec![voter("newvoter", 5)],
+            remove: vec![VOTER5.to_string()],
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env_height(1),
+            mock_info(OWNER, &[]),
+            rotate.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        // Keeping the threshold reachable
+        let contract = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        let shrink = ExecuteMsg::UpdateVoters {
+            add: vec![],
+            remove: vec![VOTER3.to_string(), VOTER4.to_string(), VOTER5.to_string()],
+        };
+        let err = execute(deps.as_mut(), mock_env_height(1), contract.clone(), shrink).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Threshold(ThresholdError::UnreachableWeight {})
+        );
+        execute(deps.as_mut(), mock_env_height(1), contract, rotate).unwrap();
+        assert_eq!(
+            query_voter(deps.as_ref(), VOTER5.to_string())
+                .unwrap()
+                .weight,
+            None
+        );
+        assert_eq!(
+            query_voter(deps.as_ref(), "newvoter".to_string())
+                .unwrap()
+                .weight,
+            Some(5)
+        );
+        assert_eq!(CONFIG.load(&deps.storage).unwrap().total_weight, 17);
+
+        // Open proposals keep their voters
+        let vote = |proposal_id| ExecuteMsg::Vote {
+            proposal_id,
+            vote: Vote::Yes,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env_height(2),
+            mock_info("newvoter", &[]),
+            vote(1),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(
+            deps.as_mut(),
+            mock_env_height(2),
+            mock_info(VOTER5, &[]),
+            vote(1),
+        )
+        .unwrap();
+
+        // While new ones use the new set
+        execute(
+            deps.as_mut(),
+            mock_env_height(2),
+            mock_info(VOTER1, &[]),
+            proposal,
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env_height(2),
+            mock_info("newvoter", &[]),
+            vote(2),
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            mock_env_height(2),
+            mock_info(VOTER5, &[]),
+            vote(2),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+    }
+
+    #[test]
+    fn closers_are_rewarded() {
+        let mut deps = mock_dependencies();
+        let instantiate_msg = |close_reward| InstantiateMsg {
+            voters: vec![voter(OWNER, 1), voter(VOTER1, 2), voter(VOTER2, 3)],
+            threshold: Threshold::AbsoluteCount { weight: 3 },
+            max_voting_period: Duration::Time(2000000),
+            proposal_deposit: Some(UncheckedDepositInfo {
+                amount: Uint128::new(10),
+                denom: UncheckedDenom::Native("TOKEN".to_string()),
+                refund_failed_proposals: true,
+                refund_passed_proposals: true,
+                forfeit: None,
+            }),
+            close_reward: Some(close_reward),
+            allow_revoting: false,
+            cast_threshold: None,
+            emergency: None,
+            private_voting: None,
+        };
+        let info = mock_info(OWNER, &[]);
+        let err = instantiate(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            instantiate_msg(CloseReward::Deposit {
+                amount: Uint128::zero(),
+            }),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Deposit(DepositError::InvalidCloseReward {})
+        );
+
+        let propose = |deps: DepsMut| {
+            let proposal = ExecuteMsg::Propose {
+                title: "Stale".to_string(),
+                description: "Nobody will vote".to_string(),
+                msgs: vec![],
+                latest: None,
+                execution_window: None,
+                metadata: None,
+            };
+            let info = mock_info(OWNER, &coins(10, "TOKEN"));
+            execute(deps, mock_env(), info, proposal).unwrap();
+        };
+        let close_many = |deps: DepsMut, start_after, limit| {
+            let mut env = mock_env();
+            env.block.time = env.block.time.plus_seconds(2000001);
+            let info = mock_info(SOMEBODY, &[]);
+            execute(
+                deps,
+                env,
+                info,
+                ExecuteMsg::CloseMany { start_after, limit },
+            )
+        };
+        let send = |to: &str, amount, denom| {
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: to.to_string(),
+                amount: coins(amount, denom),
+            })
+        };
+
+        // Paid out of the deposits, refunding the rest
+        let reward = CloseReward::Deposit {
+            amount: Uint128::new(3),
+        };
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            instantiate_msg(reward),
+        )
+        .unwrap();
+        for _ in 0..3 {
+            propose(deps.as_mut());
+        }
+        let res = close_many(deps.as_mut(), None, Some(2)).unwrap();
+        assert_eq!(res.attributes[2], attr("proposal_ids", "1,2"));
+        assert_eq!(res.attributes[3], attr("last_proposal_id", "2"));
+        let msgs: Vec<_> = res.messages.into_iter().map(|m| m.msg).collect();
+        assert_eq!(
+            msgs,
+            vec![
+                send(SOMEBODY, 3, "TOKEN"),
+                send(OWNER, 7, "TOKEN"),
+                send(SOMEBODY, 3, "TOKEN"),
+                send(OWNER, 7, "TOKEN"),
+            ]
+        );
+        // only `limit` proposals are read, the next call resuming after the last one
+        for _ in 0..2 {
+            propose(deps.as_mut());
+        }
+        let res = close_many(deps.as_mut(), Some(2), Some(2)).unwrap();
+        assert_eq!(res.attributes[2], attr("proposal_ids", "3,4"));
+        assert_eq!(res.attributes[3], attr("last_proposal_id", "4"));
+        let res = close_many(deps.as_mut(), None, Some(2)).unwrap();
+        assert_eq!(res.attributes[2], attr("proposal_ids", ""));
+        assert_eq!(res.messages, vec![]);
+        let res = close_many(deps.as_mut(), Some(4), Some(2)).unwrap();
+        assert_eq!(res.attributes[2], attr("proposal_ids", "5"));
+        let err = close_many(deps.as_mut(), Some(4), None).unwrap_err();
+        assert_eq!(err, ContractError::NothingToClose {});
+        let err = close_many(deps.as_mut(), None, None).unwrap_err();
+        assert_eq!(err, ContractError::NothingToClose {});
+
+        // Or by the multisig, as long as it can
+        let reward = CloseReward::Treasury {
+            amount: coin(5, "REWARD"),
+        };
+        let mut deps = mock_dependencies_with_balance(&coins(8, "REWARD"));
+        instantiate(deps.as_mut(), mock_env(), info, instantiate_msg(reward)).unwrap();
+        for _ in 0..2 {
+            propose(deps.as_mut());
+        }
+        let res = close_many(deps.as_mut(), None, None).unwrap();
+        assert_eq!(res.attributes[2], attr("proposal_ids", "1,2"));
+        let msgs: Vec<_> = res.messages.into_iter().map(|m| m.msg).collect();
+        assert_eq!(
+            msgs,
+            vec![
+                send(OWNER, 10, "TOKEN"),
+                send(OWNER, 10, "TOKEN"),
+                send(SOMEBODY, 8, "REWARD"),
+            ]
+        );
+    }
+
+    #[test]
+    fn old_proposals_pruned() {
+        let mut deps = mock_dependencies();
+        let instantiate_msg = InstantiateMsg {
+            voters: vec![voter(OWNER, 1), voter(VOTER1, 2), voter(VOTER2, 3)],
+            threshold: Threshold::AbsoluteCount { weight: 4 },
+            max_voting_period: Duration::Time(2000000),
+            proposal_deposit: Some(UncheckedDepositInfo {
+                amount: Uint128::new(10),
+                denom: UncheckedDenom::Native("TOKEN".to_string()),
+                refund_failed_proposals: true,
+                refund_passed_proposals: true,
+                forfeit: None,
+            }),
+            close_reward: None,
+            allow_revoting: false,
+            cast_threshold: None,
+            emergency: None,
+            private_voting: None,
+        };
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &[]),
+            instantiate_msg,
+        )
+        .unwrap();
+
+        let proposal = ExecuteMsg::Propose {
+            title: "Old".to_string(),
+            description: "Soon forgotten".to_string(),
+            msgs: vec![],
+            latest: None,
+            execution_window: None,
+            metadata: None,
+        };
+        for _ in 0..3 {
+            let info = mock_info(OWNER, &coins(10, "TOKEN"));
+            execute(deps.as_mut(), mock_env(), info, proposal.clone()).unwrap();
+        }
+        // the second one is rejected by votes, but its deposit is held until closed
+        for voter in [VOTER1, VOTER2] {
+            let vote = ExecuteMsg::Vote {
+                proposal_id: 2,
+                vote: Vote::No,
+            };
+            execute(deps.as_mut(), mock_env(), mock_info(voter, &[]), vote).unwrap();
+        }
+        let at = |seconds| {
+            let mut env = mock_env();
+            env.block.time = env.block.time.plus_seconds(seconds);
+            env
+        };
+        let close = ExecuteMsg::Close { proposal_id: 1 };
+        execute(deps.as_mut(), at(2000001), mock_info(SOMEBODY, &[]), close).unwrap();
+
+        let prune = |deps: DepsMut, seconds, older_than| {
+            let msg = ExecuteMsg::PruneProposals {
+                older_than,
+                start_after: None,
+                limit: None,
+            };
+            execute(deps, at(seconds), mock_info(SOMEBODY, &[]), msg)
+        };
+        // Proposals are kept for the retention period, measured like the voting period
+        let err = prune(deps.as_mut(), 2000050, Duration::Time(100)).unwrap_err();
+        assert_eq!(err, ContractError::NothingToPrune {});
+        let err = prune(deps.as_mut(), 2000200, Duration::Height(1)).unwrap_err();
+        assert_eq!(err, ContractError::NothingToPrune {});
+        let res = prune(deps.as_mut(), 2000200, Duration::Time(100)).unwrap();
+        assert_eq!(res.attributes[2], attr("proposal_ids", "1"));
+        let err = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Proposal { proposal_id: 1 },
+        )
+        .unwrap_err();
+        assert!(matches!(err, cosmwasm_std::StdError::NotFound { .. }));
+
+        // Along with their votes, once their deposit is settled
+        let close = ExecuteMsg::Close { proposal_id: 2 };
+        execute(deps.as_mut(), at(2000200), mock_info(SOMEBODY, &[]), close).unwrap();
+        let res = prune(deps.as_mut(), 2000200, Duration::Time(100)).unwrap();
+        assert_eq!(res.attributes[2], attr("proposal_ids", "2"));
+        let votes: VoteListResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::ListVotes {
+                    proposal_id: 2,
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert!(votes.votes.is_empty());
+        let proposals: ProposalListResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::ListProposalsByProposer {
+                    proposer: OWNER.to_string(),
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let ids: Vec<_> = proposals.proposals.iter().map(|prop| prop.id).collect();
+        assert_eq!(ids, vec![3]);
+
+        // Only `limit` proposals are read, the next call resuming after the last one
+        for _ in 0..2 {
+            let info = mock_info(OWNER, &coins(10, "TOKEN"));
+            execute(deps.as_mut(), mock_env(), info, proposal.clone()).unwrap();
+        }
+        let close = ExecuteMsg::CloseMany {
+            start_after: None,
+            limit: None,
+        };
+        execute(deps.as_mut(), at(2000001), mock_info(SOMEBODY, &[]), close).unwrap();
+        let prune = |deps: DepsMut, start_after| {
+            let msg = ExecuteMsg::PruneProposals {
+                older_than: Duration::Time(100),
+                start_after,
+                limit: Some(2),
+            };
+            execute(deps, at(2000200), mock_info(SOMEBODY, &[]), msg)
+        };
+        let res = prune(deps.as_mut(), None).unwrap();
+        assert_eq!(res.attributes[2], attr("proposal_ids", "3,4"));
+        assert_eq!(res.attributes[3], attr("last_proposal_id", "4"));
+        let res = prune(deps.as_mut(), Some(4)).unwrap();
+        assert_eq!(res.attributes[2], attr("proposal_ids", "5"));
+        let err = prune(deps.as_mut(), Some(5)).unwrap_err();
+        assert_eq!(err, ContractError::NothingToPrune {});
+    }
+
+    #[test]
+    fn signaling_proposals_conclude_when_passed() {
+        let mut deps = mock_dependencies();
+        let instantiate_msg = InstantiateMsg {
+            voters: vec![voter(OWNER, 1), voter(VOTER1, 2), voter(VOTER2, 3)],
+            threshold: Threshold::ThresholdQuorum {
+                threshold: Decimal::percent(50),
+                quorum: Decimal::percent(1),
+            },
+            max_voting_period: Duration::Time(2000000),
+            proposal_deposit: Some(UncheckedDepositInfo {
+                amount: Uint128::new(10),
+                denom: UncheckedDenom::Native("TOKEN".to_string()),
+                refund_failed_proposals: false,
+                refund_passed_proposals: true,
+                forfeit: None,
+            }),
+            close_reward: None,
+            allow_revoting: false,
+            cast_threshold: None,
+            emergency: None,
+            private_voting: None,
+        };
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &[]),
+            instantiate_msg,
+        )
+        .unwrap();
+        let proposal = ExecuteMsg::ProposeSignaling {
+            title: "Poll".to_string(),
+            description: "Shall we?".to_string(),
+            latest: None,
+            metadata: None,
+        };
+        let refund = |to: &str| {
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: to.to_string(),
+                amount: coins(10, "TOKEN"),
+            })
+        };
+
+        // Passing by votes settles the deposit right away
+        let info = mock_info(VOTER2, &coins(10, "TOKEN"));
+        let res = execute(deps.as_mut(), mock_env(), info, proposal.clone()).unwrap();
+        assert_eq!(res.messages[0].msg, refund(VOTER2));
+        let prop = query_proposal(deps.as_ref(), mock_env(), 1).unwrap();
+        assert_eq!(prop.kind, ProposalKind::Signaling);
+        assert_eq!(prop.status, Status::Passed);
+        assert_eq!(prop.deposit_status, Some(DepositStatus::Refunded));
+
+        // And there is nothing to execute
+        let execution = ExecuteMsg::Execute {
+            proposal_id: 1,
+            msg_indices: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), execution).unwrap_err();
+        assert_eq!(err, ContractError::SignalingProposal {});
+
+        // Passing on expiration, the deposit is settled by closing it
+        let info = mock_info(OWNER, &coins(10, "TOKEN"));
+        execute(deps.as_mut(), mock_env(), info, proposal).unwrap();
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(2000001);
+        let closing = ExecuteMsg::Close { proposal_id: 2 };
+        let res = execute(deps.as_mut(), env.clone(), mock_info(VOTER1, &[]), closing).unwrap();
+        assert_eq!(res.messages[0].msg, refund(OWNER));
+        let prop = query_proposal(deps.as_ref(), env.clone(), 2).unwrap();
+        assert_eq!(prop.status, Status::Passed);
+        assert_eq!(prop.deposit_status, Some(DepositStatus::Refunded));
+        let closing = ExecuteMsg::Close { proposal_id: 2 };
+        let err = execute(deps.as_mut(), env, mock_info(VOTER1, &[]), closing).unwrap_err();
+        assert_eq!(err, ContractError::WrongCloseStatus {});
+    }
+
+    #[test]
+    fn proposer_amends_before_votes() {
+        let mut deps = mock_dependencies();
+        let info = mock_info(VOTER3, &[]);
+        let threshold = Threshold::AbsoluteCount { weight: 6 };
+        setup_test_case(deps.as_mut(), info.clone(), threshold, Duration::Height(10)).unwrap();
+
+        let proposal = ExecuteMsg::Propose {
+            title: "Pay somebody".to_string(),
+            description: "Typo in the amonut".to_string(),
+            msgs: vec![],
+            latest: None,
+            execution_window: None,
+            metadata: None,
+        };
+        execute(deps.as_mut(), mock_env(), info.clone(), proposal).unwrap();
+        let amend = ExecuteMsg::Amend {
+            proposal_id: 1,
+            title: None,
+            description: Some("Fixed the amount".to_string()),
+            msgs: Some(vec![CosmosMsg::Bank(BankMsg::Send {
+                to_address: SOMEBODY.to_string(),
+                amount: coins(1, "BTC"),
+            })]),
+        };
+
+        // Only the proposer can amend
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(VOTER4, &[]),
+            amend.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        // Voting yes again on the amended proposal
+        execute(deps.as_mut(), mock_env(), info.clone(), amend.clone()).unwrap();
+        let prop = query_proposal(deps.as_ref(), mock_env(), 1).unwrap();
+        assert_eq!(prop.title, "Pay somebody");
+        assert_eq!(prop.description, "Fixed the amount");
+        assert_eq!(prop.msgs.len(), 1);
+        let vote = query_vote(deps.as_ref(), 1, VOTER3.to_string()).unwrap();
+        assert_eq!(vote.vote.unwrap().weight, 3);
+        let votes = PROPOSALS.load(deps.as_ref().storage, 1).unwrap().votes;
+        assert_eq!(votes, Votes::yes(3));
+
+        // Until others vote on it
+        execute(deps.as_mut(), mock_env(), info.clone(), amend.clone()).unwrap();
+        let vote = ExecuteMsg::Vote {
+            proposal_id: 1,
+            vote: Vote::Yes,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(VOTER1, &[]), vote).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), info, amend).unwrap_err();
+        assert_eq!(err, ContractError::VotesCast {});
+    }
+
+    #[test]
+    fn proposals_listed_by_status_and_proposer() {
+        let mut deps = mock_dependencies();
+        let threshold = Threshold::AbsoluteCount { weight: 4 };
+        let info = mock_info(OWNER, &[]);
+        setup_test_case(deps.as_mut(), info, threshold, Duration::Height(10)).unwrap();
+
+        for proposer in [VOTER1, VOTER4, VOTER1] {
+            let proposal = ExecuteMsg::Propose {
+                title: "Title".to_string(),
+                description: "Description".to_string(),
+                msgs: vec![],
+                latest: None,
+                execution_window: None,
+                metadata: None,
+            };
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(proposer, &[]),
+                proposal,
+            )
+            .unwrap();
+        }
+        let ids = |msg: QueryMsg| {
+            let res: ProposalListResponse =
+                from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+            res.proposals.iter().map(|p| p.id).collect::<Vec<u64>>()
+        };
+
+        let by_status = |status, start_after| QueryMsg::ListProposalsByStatus {
+            status,
+            start_after,
+            limit: None,
+        };
+        assert_eq!(ids(by_status(Status::Open, None)), vec![1, 3]);
+        assert_eq!(ids(by_status(Status::Open, Some(1))), vec![3]);
+        assert_eq!(ids(by_status(Status::Passed, None)), vec![2]);
+
+        let by_proposer = |proposer: &str, limit| QueryMsg::ListProposalsByProposer {
+            proposer: proposer.to_string(),
+            start_after: None,
+            limit,
+        };
+        assert_eq!(ids(by_proposer(VOTER1, None)), vec![1, 3]);
+        assert_eq!(ids(by_proposer(VOTER1, Some(1))), vec![1]);
+        assert_eq!(ids(by_proposer(VOTER4, None)), vec![2]);
+        assert!(ids(by_proposer(VOTER5, None)).is_empty());
+    }
+
+    #[test]
+    fn proposal_tally_breakdown() {
+        let mut deps = mock_dependencies();
+        let threshold = Threshold::ThresholdQuorum {
+            threshold: Decimal::percent(51),
+            quorum: Decimal::percent(50),
+        };
+        let info = mock_info(VOTER3, &[]);
+        setup_test_case(deps.as_mut(), info.clone(), threshold, Duration::Height(10)).unwrap();
+        let proposal = ExecuteMsg::Propose {
+            title: "Title".to_string(),
+            description: "Description".to_string(),
+            msgs: vec![],
+            latest: None,
+            execution_window: None,
+            metadata: None,
+        };
+        execute(deps.as_mut(), mock_env(), info, proposal).unwrap();
+        for (voter, vote) in [(VOTER1, Vote::No), (VOTER2, Vote::Abstain)] {
+            let vote = ExecuteMsg::Vote {
+                proposal_id: 1,
+                vote,
+            };
+            execute(deps.as_mut(), mock_env(), mock_info(voter, &[]), vote).unwrap();
+        }
+
+        let msg = QueryMsg::ProposalTally { proposal_id: 1 };
+        let tally: TallyResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(
+            tally,
+            TallyResponse {
+                votes: Votes {
+                    yes: 3,
+                    no: 1,
+                    abstain: 2,
+                    veto: 0,
+                },
+                turnout: Decimal::from_ratio(6u128, 17u128),
+                threshold: ThresholdResponse::ThresholdQuorum {
+                    threshold: Decimal::percent(51),
+                    quorum: Decimal::percent(50),
+                    total_weight: 17,
+                },
+                cast_threshold: None,
+                status: Status::Open,
+                choice_votes: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn failed_execution_recorded() {
+        let mut deps = mock_dependencies();
+        let instantiate_msg = InstantiateMsg {
+            voters: vec![voter(OWNER, 1), voter(VOTER1, 2)],
+            threshold: Threshold::AbsoluteCount { weight: 1 },
+            max_voting_period: Duration::Time(2000000),
+            proposal_deposit: Some(UncheckedDepositInfo {
+                amount: Uint128::new(10),
+                denom: UncheckedDenom::Native("TOKEN".to_string()),
+                refund_failed_proposals: true,
+                refund_passed_proposals: true,
+                forfeit: None,
+            }),
+            close_reward: None,
+            allow_revoting: false,
+            cast_threshold: None,
+            emergency: None,
+            private_voting: None,
+        };
+        let info = mock_info(OWNER, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, instantiate_msg).unwrap();
+
+        // Passes right away, by the proposer vote
+        let proposal = ExecuteMsg::Propose {
+            title: "Pay".to_string(),
+            description: "Pay somebody".to_string(),
+            msgs: vec![CosmosMsg::Bank(BankMsg::Send {
+                to_address: SOMEBODY.to_string(),
+                amount: coins(1, "BTC"),
+            })],
+            latest: None,
+            execution_window: None,
+            metadata: None,
+        };
+        let info = mock_info(OWNER, &coins(10, "TOKEN"));
+        execute(deps.as_mut(), mock_env(), info, proposal.clone()).unwrap();
+        let execution = ExecuteMsg::Execute {
+            proposal_id: 1,
+            msg_indices: None,
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(SOMEBODY, &[]),
+            execution.clone(),
+        )
+        .unwrap();
+
+        // The dispatch failed and was reverted, the error is recorded
+        let failure = Reply {
+            id: 1,
+            result: SubMsgResult::Err("insufficient funds".to_string()),
+        };
+        let res = reply(deps.as_mut(), mock_env(), failure.clone()).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "execution_failed"),
+                attr("proposal_id", "1"),
+                attr("status", "ExecutionFailed"),
+                attr("error", "insufficient funds"),
+            ]
+        );
+        let prop = query_proposal(deps.as_ref(), mock_env(), 1).unwrap();
+        assert_eq!(prop.status, Status::ExecutionFailed);
+        assert_eq!(prop.execution_error, Some("insufficient funds".to_string()));
+        assert_eq!(prop.deposit_status, Some(DepositStatus::Held));
+
+        // It cannot be voted on, nor closed before it expires
+        let vote = ExecuteMsg::Vote {
+            proposal_id: 1,
+            vote: Vote::No,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(VOTER1, &[]), vote).unwrap_err();
+        assert_eq!(err, ContractError::NotOpen {});
+        let closing = ExecuteMsg::Close { proposal_id: 1 };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(SOMEBODY, &[]),
+            closing.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::NotExpired {});
+
+        // But it can be executed again, succeeding this time
+        let info = mock_info(SOMEBODY, &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, execution).unwrap();
+        assert_eq!(res.messages.len(), 1);
+        let dispatch = ExecuteMsg::DispatchProposal {
+            proposal_id: 1,
+            msg_indices: None,
+        };
+        let contract = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        execute(deps.as_mut(), mock_env(), contract, dispatch).unwrap();
+        let prop = query_proposal(deps.as_ref(), mock_env(), 1).unwrap();
+        assert_eq!(prop.status, Status::Executed);
+        assert_eq!(prop.execution_error, None);
+        assert_eq!(prop.deposit_status, Some(DepositStatus::Refunded));
+
+        // Or closed once expired, refunding the deposit as for failed proposals
+        let info = mock_info(OWNER, &coins(10, "TOKEN"));
+        execute(deps.as_mut(), mock_env(), info, proposal).unwrap();
+        let failure = Reply { id: 2, ..failure };
+        reply(deps.as_mut(), mock_env(), failure).unwrap();
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(2000001);
+        let closing = ExecuteMsg::Close { proposal_id: 2 };
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(SOMEBODY, &[]),
+            closing,
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: OWNER.to_string(),
+                amount: coins(10, "TOKEN"),
+            })
+        );
+        let prop = query_proposal(deps.as_ref(), env, 2).unwrap();
+        assert_eq!(prop.status, Status::Rejected);
+        assert_eq!(prop.deposit_status, Some(DepositStatus::Refunded));
+    }
+
+    #[test]
+    fn votes_changed_while_open() {
+        let mut deps = mock_dependencies();
+        let instantiate_msg = |allow_revoting| InstantiateMsg {
+            voters: vec![voter(OWNER, 1), voter(VOTER1, 2), voter(VOTER2, 3)],
+            threshold: Threshold::AbsoluteCount { weight: 4 },
+            max_voting_period: Duration::Time(2000000),
+            proposal_deposit: None,
+            close_reward: None,
+            allow_revoting,
+            cast_threshold: None,
+            emergency: None,
+            private_voting: None,
+        };
+        let info = mock_info(OWNER, &[]);
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            instantiate_msg(true),
+        )
+        .unwrap();
+        let proposal = ExecuteMsg::Propose {
+            title: "Pay".to_string(),
+            description: "Pay somebody".to_string(),
+            msgs: vec![],
+            latest: None,
+            execution_window: None,
+            metadata: None,
+        };
+        execute(deps.as_mut(), mock_env(), info, proposal.clone()).unwrap();
+        let vote = |vote| ExecuteMsg::Vote {
+            proposal_id: 1,
+            vote,
+        };
+
+        // The latest vote counts
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(VOTER1, &[]),
+            vote(Vote::No),
+        )
+        .unwrap();
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(VOTER1, &[]),
+            vote(Vote::Yes),
+        )
+        .unwrap();
+        assert_eq!(
+            res.events,
+            vec![Event::new("vote_changed")
+                .add_attribute("proposal_id", "1")
+                .add_attribute("voter", VOTER1)
+                .add_attribute("previous_vote", "No")
+                .add_attribute("vote", "Yes")]
+        );
+        let tally = query_tally(deps.as_ref(), mock_env(), 1).unwrap();
+        assert_eq!((tally.votes.yes, tally.votes.no), (3, 0));
+        assert_eq!(tally.status, Status::Open);
+        let ballot = query_vote(deps.as_ref(), 1, VOTER1.to_string()).unwrap();
+        assert_eq!(ballot.vote.unwrap().vote, Vote::Yes);
+
+        // Until the proposal is no longer open
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(VOTER2, &[]),
+            vote(Vote::Yes),
+        )
+        .unwrap();
+        assert!(res.events.is_empty());
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(VOTER2, &[]),
+            vote(Vote::No),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::AlreadyVoted {});
+
+        // Votes are final unless configured otherwise
+        let mut deps = mock_dependencies();
+        let info = mock_info(OWNER, &[]);
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            instantiate_msg(false),
+        )
+        .unwrap();
+        execute(deps.as_mut(), mock_env(), info, proposal).unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(VOTER1, &[]),
+            vote(Vote::No),
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(VOTER1, &[]),
+            vote(Vote::Yes),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::AlreadyVoted {});
+    }
+
+    #[test]
+    fn absolute_count_with_cast_threshold() {
+        let mut deps = mock_dependencies();
+        let instantiate_msg = |threshold, cast_threshold| InstantiateMsg {
+            voters: vec![
+                voter(OWNER, 1),
+                voter(VOTER1, 2),
+                voter(VOTER2, 3),
+                voter(VOTER3, 4),
+            ],
+            threshold,
+            max_voting_period: Duration::Time(2000000),
+            proposal_deposit: None,
+            close_reward: None,
+            allow_revoting: false,
+            cast_threshold,
+            emergency: None,
+            private_voting: None,
+        };
+        let info = mock_info(OWNER, &[]);
+
+        // Only on top of an absolute count, with a valid percentage
+        let quorum = Threshold::ThresholdQuorum {
+            threshold: Decimal::percent(51),
+            quorum: Decimal::percent(10),
+        };
+        let msg = instantiate_msg(quorum, Some(Decimal::percent(60)));
+        let err = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidCastThreshold {});
+        let count = Threshold::AbsoluteCount { weight: 3 };
+        let msg = instantiate_msg(count.clone(), Some(Decimal::zero()));
+        let err = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Threshold(ThresholdError::InvalidThreshold {})
+        );
+        let msg = instantiate_msg(count, Some(Decimal::percent(60)));
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // Enough yes weight, but not 60% of the possible votes yet
+        let proposal = ExecuteMsg::Propose {
+            title: "Pay".to_string(),
+            description: "Pay somebody".to_string(),
+            msgs: vec![],
+            latest: None,
+            execution_window: None,
+            metadata: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info(VOTER3, &[]), proposal).unwrap();
+        assert_eq!(res.attributes[3], attr("status", "Open"));
+        let vote = |vote| ExecuteMsg::Vote {
+            proposal_id: 1,
+            vote,
+        };
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(VOTER2, &[]),
+            vote(Vote::No),
+        )
+        .unwrap();
+        assert_eq!(res.attributes[3], attr("status", "Open"));
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(VOTER1, &[]),
+            vote(Vote::Yes),
+        )
+        .unwrap();
+        assert_eq!(res.attributes[3], attr("status", "Passed"));
+
+        let prop = query_proposal(deps.as_ref(), mock_env(), 1).unwrap();
+        assert_eq!(prop.cast_threshold, Some(Decimal::percent(60)));
+        let tally = query_tally(deps.as_ref(), mock_env(), 1).unwrap();
+        assert_eq!(tally.cast_threshold, Some(Decimal::percent(60)));
+    }
+
+    #[test]
+    fn scheduled_execution_within_window() {
+        let mut deps = mock_dependencies();
+        let instantiate_msg = InstantiateMsg {
+            voters: vec![voter(OWNER, 1), voter(VOTER1, 2)],
+            threshold: Threshold::AbsoluteCount { weight: 1 },
+            max_voting_period: Duration::Height(100),
+            proposal_deposit: None,
+            close_reward: None,
+            allow_revoting: false,
+            cast_threshold: None,
+            emergency: None,
+            private_voting: None,
+        };
+        let info = mock_info(OWNER, &[]);
+        instantiate(deps.as_mut(), mock_env(), info.clone(), instantiate_msg).unwrap();
+
+        let height = mock_env().block.height;
+        let window = |start, end| ExecutionWindow {
+            start: Expiration::AtHeight(height + start),
+            end: Expiration::AtHeight(height + end),
+        };
+        let proposal = |execution_window| ExecuteMsg::Propose {
+            title: "Pay".to_string(),
+            description: "Pay somebody at the next epoch".to_string(),
+            msgs: vec![CosmosMsg::Bank(BankMsg::Send {
+                to_address: SOMEBODY.to_string(),
+                amount: coins(1, "BTC"),
+            })],
+            latest: None,
+            execution_window: Some(execution_window),
+            metadata: None,
+        };
+        let at = |blocks| {
+            let mut env = mock_env();
+            env.block.height += blocks;
+            env
+        };
+        let scheduled = |deps: Deps, env| -> Vec<u64> {
+            let msg = QueryMsg::ScheduledExecutions {
+                start_after: None,
+                limit: None,
+            };
+            let res: ProposalListResponse = from_binary(&query(deps, env, msg).unwrap()).unwrap();
+            res.proposals.into_iter().map(|p| p.id).collect()
+        };
+
+        // The window must start before it ends, and not be over
+        for invalid in [window(20, 10), window(0, 0)] {
+            let err =
+                execute(deps.as_mut(), mock_env(), info.clone(), proposal(invalid)).unwrap_err();
+            assert_eq!(err, ContractError::InvalidExecutionWindow {});
+        }
+
+        // Passed proposals wait for their window, in the queue
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            proposal(window(100, 110)),
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            proposal(window(50, 60)),
+        )
+        .unwrap();
+        assert_eq!(scheduled(deps.as_ref(), mock_env()), vec![1, 2]);
+        let execution = |proposal_id| ExecuteMsg::Execute {
+            proposal_id,
+            msg_indices: None,
+        };
+        let err = execute(deps.as_mut(), at(99), info.clone(), execution(1)).unwrap_err();
+        assert_eq!(err, ContractError::OutsideExecutionWindow {});
+
+        // Then anyone can execute them
+        execute(
+            deps.as_mut(),
+            at(100),
+            mock_info(SOMEBODY, &[]),
+            execution(1),
+        )
+        .unwrap();
+        let dispatch = ExecuteMsg::DispatchProposal {
+            proposal_id: 1,
+            msg_indices: None,
+        };
+        let contract = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        execute(deps.as_mut(), at(100), contract, dispatch).unwrap();
+        assert_eq!(scheduled(deps.as_ref(), at(55)), vec![2]);
+
+        // Until their window is over, when they can only be closed
+        assert_eq!(scheduled(deps.as_ref(), at(60)), Vec::<u64>::new());
+        let err = execute(deps.as_mut(), at(60), info.clone(), execution(2)).unwrap_err();
+        assert_eq!(err, ContractError::OutsideExecutionWindow {});
+        let closing = ExecuteMsg::Close { proposal_id: 2 };
+        let err = execute(deps.as_mut(), at(59), info.clone(), closing.clone()).unwrap_err();
+        assert_eq!(err, ContractError::WrongCloseStatus {});
+        execute(deps.as_mut(), at(60), info, closing).unwrap();
+        let prop = query_proposal(deps.as_ref(), at(60), 2).unwrap();
+        assert_eq!(prop.status, Status::Rejected);
+        assert_eq!(prop.execution_window, Some(window(50, 60)));
+    }
+
+    #[test]
+    fn emergency_proposals_for_council() {
+        let mut deps = mock_dependencies();
+        let instantiate_msg = |emergency| InstantiateMsg {
+            voters: vec![
+                voter(OWNER, 1),
+                voter(VOTER1, 2),
+                voter(VOTER2, 3),
+                voter(VOTER3, 4),
+            ],
+            threshold: Threshold::AbsoluteCount { weight: 4 },
+            max_voting_period: Duration::Time(2000000),
+            proposal_deposit: None,
+            close_reward: None,
+            allow_revoting: false,
+            cast_threshold: None,
+            emergency,
+            private_voting: None,
+        };
+        let track = |council: Vec<&str>, weight, voting_period| UncheckedEmergencyTrack {
+            council: council.into_iter().map(String::from).collect(),
+            threshold: Threshold::AbsoluteCount { weight },
+            voting_period,
+        };
+        let info = mock_info(OWNER, &[]);
+
+        // The council must not be empty, nor vote for longer, and the threshold be reachable
+        let invalid = vec![
+            (
+                track(vec![], 8, Duration::Time(3600)),
+                ContractError::InvalidEmergencyTrack {},
+            ),
+            (
+                track(vec![VOTER1], 8, Duration::Time(3000000)),
+                ContractError::InvalidEmergencyTrack {},
+            ),
+            (
+                track(vec![VOTER1], 8, Duration::Height(10)),
+                ContractError::InvalidEmergencyTrack {},
+            ),
+            (
+                track(vec![VOTER1], 11, Duration::Time(3600)),
+                ContractError::Threshold(ThresholdError::UnreachableWeight {}),
+            ),
+        ];
+        for (track, expected) in invalid {
+            let msg = instantiate_msg(Some(track));
+            let err = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+            assert_eq!(err, expected);
+        }
+
+        let proposal = ExecuteMsg::ProposeEmergency {
+            title: "Pause".to_string(),
+            description: "Pause the protocol".to_string(),
+            msgs: vec![],
+            metadata: None,
+        };
+        // Emergency proposals must be enabled
+        instantiate(deps.as_mut(), mock_env(), info, instantiate_msg(None)).unwrap();
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(VOTER1, &[]),
+            proposal.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::NoEmergencyTrack {});
+
+        let mut deps = mock_dependencies();
+        let track = track(vec![VOTER1, SOMEBODY], 8, Duration::Time(3600));
+        let msg = instantiate_msg(Some(track));
+        instantiate(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+
+        // Only for the voters in the council
+        for proposer in [OWNER, SOMEBODY] {
+            let err = execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(proposer, &[]),
+                proposal.clone(),
+            )
+            .unwrap_err();
+            assert_eq!(err, ContractError::Unauthorized {});
+        }
+        execute(deps.as_mut(), mock_env(), mock_info(VOTER1, &[]), proposal).unwrap();
+        let prop = query_proposal(deps.as_ref(), mock_env(), 1).unwrap();
+        assert_eq!(prop.kind, ProposalKind::Emergency);
+        assert_eq!(
+            prop.expires,
+            Expiration::AtTime(mock_env().block.time.plus_seconds(3600))
+        );
+
+        // The emergency threshold applies, rather than the usual one
+        let vote = ExecuteMsg::Vote {
+            proposal_id: 1,
+            vote: Vote::Yes,
+        };
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(VOTER2, &[]),
+            vote.clone(),
+        )
+        .unwrap();
+        assert_eq!(res.attributes[3], attr("status", "Open"));
+        let res = execute(deps.as_mut(), mock_env(), mock_info(VOTER3, &[]), vote).unwrap();
+        assert_eq!(res.attributes[3], attr("status", "Passed"));
+    }
+
+    #[test]
+    fn proposal_metadata_validated() {
+        let mut deps = mock_dependencies();
+        let info = mock_info(OWNER, &[]);
+        let threshold = Threshold::AbsoluteCount { weight: 3 };
+        let voting_period = Duration::Time(2000000);
+        setup_test_case(deps.as_mut(), info.clone(), threshold, voting_period).unwrap();
+        let proposal = |metadata| ExecuteMsg::Propose {
+            title: "Pay".to_string(),
+            description: "Pay somebody".to_string(),
+            msgs: vec![],
+            latest: None,
+            execution_window: None,
+            metadata: Some(metadata),
+        };
+        let tags = |tags: &[&str]| tags.iter().map(|tag| tag.to_string()).collect();
+
+        // Fields must be within their bounds
+        let invalid = vec![
+            ProposalMetadata {
+                link: Some(String::new()),
+                ..ProposalMetadata::default()
+            },
+            ProposalMetadata {
+                link: Some("https://forum.example.com/t/ pay".to_string()),
+                ..ProposalMetadata::default()
+            },
+            ProposalMetadata {
+                ipfs_hash: Some("Qm/not-a-hash".to_string()),
+                ..ProposalMetadata::default()
+            },
+            ProposalMetadata {
+                tags: tags(&["treasury", "treasury"]),
+                ..ProposalMetadata::default()
+            },
+            ProposalMetadata {
+                tags: tags(&["a", "b", "c", "d", "e", "f", "g", "h", "i"]),
+                ..ProposalMetadata::default()
+            },
+            ProposalMetadata {
+                tags: tags(&[&"x".repeat(33)]),
+                ..ProposalMetadata::default()
+            },
+        ];
+        for metadata in invalid {
+            let err =
+                execute(deps.as_mut(), mock_env(), info.clone(), proposal(metadata)).unwrap_err();
+            assert_eq!(err, ContractError::InvalidMetadata {});
+        }
+
+        // and are returned by queries
+        let metadata = ProposalMetadata {
+            link: Some("https://forum.example.com/t/pay-somebody/42".to_string()),
+            ipfs_hash: Some("QmYwAPJzv5CZsnA625s3Xf2nemtYgPpHdWEz79ojWnPbdG".to_string()),
+            tags: tags(&["treasury", "grants"]),
+        };
+        execute(deps.as_mut(), mock_env(), info, proposal(metadata.clone())).unwrap();
+        let prop = query_proposal(deps.as_ref(), mock_env(), 1).unwrap();
+        assert_eq!(prop.metadata, Some(metadata));
+    }
+
+    #[test]
+    fn choice_proposals_execute_winning_option() {
+        let mut deps = mock_dependencies();
+        let info = mock_info(OWNER, &[]);
+        let threshold = Threshold::AbsoluteCount { weight: 6 };
+        let voting_period = Duration::Time(2000000);
+        setup_test_case(deps.as_mut(), info.clone(), threshold, voting_period).unwrap();
+        let pay = |amount| {
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: SOMEBODY.into(),
+                amount: coins(amount, "BTC"),
+            })
+        };
+        let option = |label: &str, msgs| ProposalOption {
+            label: label.to_string(),
+            msgs,
+        };
+        let proposal = |options| ExecuteMsg::ProposeChoice {
+            title: "Grant".to_string(),
+            description: "How much shall we pay somebody?".to_string(),
+            options,
+            win_condition: WinCondition::Threshold {},
+            latest: None,
+            metadata: None,
+        };
+
+        // Options must be several, with distinct labels
+        for options in [
+            vec![option("Nothing", vec![])],
+            vec![option("Some", vec![pay(1)]), option("Some", vec![pay(2)])],
+        ] {
+            let err =
+                execute(deps.as_mut(), mock_env(), info.clone(), proposal(options)).unwrap_err();
+            assert_eq!(err, ContractError::InvalidOptions {});
+        }
+        let options = vec![
+            option("Nothing", vec![]),
+            option("Some", vec![pay(1)]),
+            option("Lots", vec![pay(100)]),
+        ];
+        execute(deps.as_mut(), mock_env(), info, proposal(options)).unwrap();
+
+        // Choice proposals are only voted on by option
+        let vote = ExecuteMsg::Vote {
+            proposal_id: 1,
+            vote: Vote::Yes,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(VOTER1, &[]), vote).unwrap_err();
+        assert_eq!(err, ContractError::ChoiceProposal {});
+        let vote_choice = |option| ExecuteMsg::VoteChoice {
+            proposal_id: 1,
+            option,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(VOTER1, &[]),
+            vote_choice(3),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::UnknownOption { option: 3 });
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(VOTER3, &[]),
+            vote_choice(1),
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(VOTER2, &[]),
+            vote_choice(0),
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(VOTER3, &[]),
+            vote_choice(2),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::AlreadyVoted {});
+
+        // The option passing the threshold wins
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(VOTER4, &[]),
+            vote_choice(1),
+        )
+        .unwrap();
+        assert_eq!(
+            res.attributes,
+            [
+                ("action", "vote_choice"),
+                ("sender", VOTER4),
+                ("proposal_id", "1"),
+                ("option", "1"),
+                ("status", "Passed"),
+            ],
+        );
+        let tally: TallyResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::ProposalTally { proposal_id: 1 },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(tally.choice_votes, vec![2, 7, 0]);
+        let ballot: VoteResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Vote {
+                    proposal_id: 1,
+                    voter: VOTER4.into(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(ballot.vote.unwrap().choice, Some(1));
+
+        // and only its messages are executed
+        let contract = mock_info(mock_env().contract.address.as_str(), &[]);
+        let dispatch = ExecuteMsg::DispatchProposal {
+            proposal_id: 1,
+            msg_indices: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), contract, dispatch).unwrap();
+        assert_eq!(res.messages, vec![SubMsg::new(pay(1))]);
+    }
+
+    #[test]
+    fn signed_ballots_relayed() {
+        use k256::ecdsa::signature::Signer;
+        use k256::ecdsa::{Signature, SigningKey};
+
+        let mut deps = mock_dependencies();
+        let key = SigningKey::from_bytes(&[7; 32]).unwrap();
+        let pubkey = Binary::from(key.verifying_key().to_bytes().as_slice());
+        let signer = cw_controllers::pubkey_to_address(&pubkey, "cosmos").unwrap();
+        let instantiate_msg = InstantiateMsg {
+            voters: vec![voter(OWNER, 1), voter(&signer, 2), voter(VOTER1, 3)],
+            threshold: Threshold::AbsoluteCount { weight: 3 },
+            max_voting_period: Duration::Time(2000000),
+            proposal_deposit: None,
+            close_reward: None,
+            allow_revoting: false,
+            cast_threshold: None,
+            emergency: None,
+            private_voting: None,
+        };
+        let info = mock_info(OWNER, &[]);
+        instantiate(deps.as_mut(), mock_env(), info.clone(), instantiate_msg).unwrap();
+        let proposal = ExecuteMsg::Propose {
+            title: "Pay".to_string(),
+            description: "Pay somebody".to_string(),
+            msgs: vec![],
+            latest: None,
+            execution_window: None,
+            metadata: None,
+        };
+        execute(deps.as_mut(), mock_env(), info, proposal).unwrap();
+
+        let relay = |contract: &str, nonce| {
+            let ballot = VotePermit {
+                chain_id: mock_env().block.chain_id,
+                contract: contract.to_string(),
+                voter: signer.clone(),
+                proposal_id: 1,
+                vote: Vote::Yes,
+                nonce,
+                deadline: Expiration::AtHeight(mock_env().block.height + 10),
+            };
+            let signature: Signature = key.sign(ballot.sign_bytes().unwrap().as_slice());
+            ExecuteMsg::RelayVote {
+                ballot,
+                pubkey: pubkey.clone(),
+                signature: Binary::from(signature.as_ref()),
+            }
+        };
+        let vote_nonce = |deps: Deps| {
+            let msg = QueryMsg::VoteNonce {
+                voter: signer.clone(),
+            };
+            let res: cw_controllers::PermitNonceResponse =
+                from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+            res.nonce
+        };
+        let contract = mock_env().contract.address;
+
+        // Ballots signed for other contracts are not valid here
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(SOMEBODY, &[]),
+            relay("other", 0),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Permit(cw_controllers::PermitError::InvalidSignature {})
+        );
+
+        // Anyone can relay the vote, counted with the weight of the voter
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(SOMEBODY, &[]),
+            relay(contract.as_str(), 0),
+        )
+        .unwrap();
+        assert_eq!(
+            res.attributes,
+            [
+                ("action", "vote"),
+                ("sender", signer.as_str()),
+                ("proposal_id", "1"),
+                ("status", "Passed"),
+                ("relayer", SOMEBODY),
+            ],
+        );
+        assert_eq!(get_tally(deps.as_ref(), 1), 3);
+        assert_eq!(vote_nonce(deps.as_ref()), 1);
+
+        // but only once
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(SOMEBODY, &[]),
+            relay(contract.as_str(), 0),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Permit(cw_controllers::PermitError::InvalidNonce { expected: 1 })
+        );
+    }
+
+    #[test]
+    fn pending_votes_listed() {
+        let mut deps = mock_dependencies();
+        let instantiate_msg = InstantiateMsg {
+            voters: vec![voter(OWNER, 1), voter(VOTER1, 2), voter(VOTER2, 3)],
+            threshold: Threshold::AbsoluteCount { weight: 4 },
+            max_voting_period: Duration::Time(2000000),
+            proposal_deposit: None,
+            close_reward: None,
+            allow_revoting: false,
+            cast_threshold: None,
+            emergency: None,
+            private_voting: None,
+        };
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &[]),
+            instantiate_msg,
+        )
+        .unwrap();
+        let proposal = ExecuteMsg::Propose {
+            title: "Pay".to_string(),
+            description: "Pay somebody".to_string(),
+            msgs: vec![],
+            latest: None,
+            execution_window: None,
+            metadata: None,
+        };
+        for proposer in [OWNER, VOTER1] {
+            let info = mock_info(proposer, &[]);
+            execute(deps.as_mut(), mock_env(), info, proposal.clone()).unwrap();
+        }
+        let pending = |deps: Deps, env: Env, voter: &str, start_after| {
+            let msg = QueryMsg::PendingVotes {
+                voter: voter.to_string(),
+                start_after,
+                limit: None,
+            };
+            let res: ProposalListResponse = from_binary(&query(deps, env, msg).unwrap()).unwrap();
+            res.proposals.iter().map(|p| p.id).collect::<Vec<u64>>()
+        };
+
+        // Proposers voted on their own proposal, others did not
+        assert_eq!(pending(deps.as_ref(), mock_env(), VOTER1, None), vec![1]);
+        assert_eq!(pending(deps.as_ref(), mock_env(), VOTER2, None), vec![1, 2]);
+        assert_eq!(pending(deps.as_ref(), mock_env(), VOTER2, Some(1)), vec![2]);
+        assert_eq!(
+            pending(deps.as_ref(), mock_env(), SOMEBODY, None),
+            Vec::<u64>::new()
+        );
+
+        // Decided proposals are no longer pending
+        let vote = ExecuteMsg::Vote {
+            proposal_id: 2,
+            vote: Vote::Yes,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(VOTER2, &[]), vote).unwrap();
+        assert_eq!(pending(deps.as_ref(), mock_env(), VOTER2, None), vec![1]);
+        assert_eq!(
+            pending(deps.as_ref(), mock_env(), OWNER, None),
+            Vec::<u64>::new()
+        );
+
+        // Nor expired ones
+        let env = mock_env_time(2000001);
+        assert_eq!(pending(deps.as_ref(), env, VOTER2, None), Vec::<u64>::new());
+    }
+
+    #[test]
+    fn proposal_messages_executed_in_ranges() {
+        let mut deps = mock_dependencies();
+        let instantiate_msg = InstantiateMsg {
+            voters: vec![voter(OWNER, 1), voter(VOTER1, 2)],
+            threshold: Threshold::AbsoluteCount { weight: 1 },
+            max_voting_period: Duration::Time(2000000),
+            proposal_deposit: None,
+            close_reward: None,
+            allow_revoting: false,
+            cast_threshold: None,
+            emergency: None,
+            private_voting: None,
+        };
+        let info = mock_info(OWNER, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, instantiate_msg).unwrap();
+
+        // Passes right away, by the proposer vote
+        let pay = |amount| {
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: SOMEBODY.to_string(),
+                amount: coins(amount, "BTC"),
+            })
+        };
+        let proposal = ExecuteMsg::Propose {
+            title: "Pay".to_string(),
+            description: "Pay somebody in three parts".to_string(),
+            msgs: vec![pay(1), pay(2), pay(3)],
+            latest: None,
+            execution_window: None,
+            metadata: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), proposal).unwrap();
+
+        // Indices must be ascending, in range and not executed yet
+        for msg_indices in [vec![], vec![2, 0], vec![1, 1], vec![3]] {
+            let execution = ExecuteMsg::Execute {
+                proposal_id: 1,
+                msg_indices: Some(msg_indices),
+            };
+            let info = mock_info(SOMEBODY, &[]);
+            let err = execute(deps.as_mut(), mock_env(), info, execution).unwrap_err();
+            assert_eq!(err, ContractError::InvalidMsgIndices {});
+        }
+
+        // Executing some of the messages leaves it passed
+        let execute_range = |mut deps: DepsMut, msg_indices: Option<Vec<u32>>| {
+            let execution = ExecuteMsg::Execute {
+                proposal_id: 1,
+                msg_indices: msg_indices.clone(),
+            };
+            execute(
+                deps.branch(),
+                mock_env(),
+                mock_info(SOMEBODY, &[]),
+                execution,
+            )
+            .unwrap();
+            let dispatch = ExecuteMsg::DispatchProposal {
+                proposal_id: 1,
+                msg_indices,
+            };
+            let contract = mock_info(MOCK_CONTRACT_ADDR, &[]);
+            execute(deps, mock_env(), contract, dispatch).unwrap()
+        };
+        let res = execute_range(deps.as_mut(), Some(vec![0, 2]));
+        assert_eq!(res.messages, vec![SubMsg::new(pay(1)), SubMsg::new(pay(3))]);
+        let prop = query_proposal(deps.as_ref(), mock_env(), 1).unwrap();
+        assert_eq!(prop.status, Status::Passed);
+        assert_eq!(prop.executed_msgs, vec![0, 2]);
+        let execution = ExecuteMsg::Execute {
+            proposal_id: 1,
+            msg_indices: Some(vec![2]),
+        };
+        let info = mock_info(SOMEBODY, &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, execution).unwrap_err();
+        assert_eq!(err, ContractError::InvalidMsgIndices {});
+
+        // A failing range is recorded without losing track of the executed ones
+        let failure = Reply {
+            id: 1,
+            result: SubMsgResult::Err("insufficient funds".to_string()),
+        };
+        reply(deps.as_mut(), mock_env(), failure).unwrap();
+        let prop = query_proposal(deps.as_ref(), mock_env(), 1).unwrap();
+        assert_eq!(prop.status, Status::ExecutionFailed);
+        assert_eq!(prop.executed_msgs, vec![0, 2]);
+
+        // And the remaining messages are executed by default, completing the proposal
+        let res = execute_range(deps.as_mut(), None);
+        assert_eq!(res.messages, vec![SubMsg::new(pay(2))]);
+        let prop = query_proposal(deps.as_ref(), mock_env(), 1).unwrap();
+        assert_eq!(prop.status, Status::Executed);
+        assert_eq!(prop.execution_error, None);
+        assert_eq!(prop.executed_msgs, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn proposer_cancels_until_opposed() {
+        let mut deps = mock_dependencies();
+        let info = mock_info(VOTER3, &[]);
+        let threshold = Threshold::AbsoluteCount { weight: 6 };
+        setup_test_case(deps.as_mut(), info.clone(), threshold, Duration::Height(10)).unwrap();
+
+        let proposal = ExecuteMsg::Propose {
+            title: "Pay somebody".to_string(),
+            description: "Do I pay her?".to_string(),
+            msgs: vec![],
+            latest: None,
+            execution_window: None,
+            metadata: None,
+        };
+        for _ in 0..2 {
+            execute(deps.as_mut(), mock_env(), info.clone(), proposal.clone()).unwrap();
+        }
+
+        // Only the proposer can cancel
+        let cancel = ExecuteMsg::Cancel { proposal_id: 1 };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(VOTER4, &[]),
+            cancel.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        // Even once voted for
+        let vote = ExecuteMsg::Vote {
+            proposal_id: 1,
+            vote: Vote::Yes,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(VOTER1, &[]), vote).unwrap();
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), cancel.clone()).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "cancel"),
+                attr("sender", VOTER3),
+                attr("proposal_id", "1"),
+            ]
+        );
+        let prop = query_proposal(deps.as_ref(), mock_env(), 1).unwrap();
+        assert_eq!(prop.status, Status::Cancelled);
+
+        // After which it is over
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), cancel).unwrap_err();
+        assert_eq!(err, ContractError::WrongCancelStatus {});
+        let vote = ExecuteMsg::Vote {
+            proposal_id: 1,
+            vote: Vote::Yes,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(VOTER2, &[]), vote).unwrap_err();
+        assert_eq!(err, ContractError::NotOpen {});
+        let mut env = mock_env();
+        env.block.height += 10;
+        let closing = ExecuteMsg::Close { proposal_id: 1 };
+        let err = execute(deps.as_mut(), env, mock_info(VOTER2, &[]), closing).unwrap_err();
+        assert_eq!(err, ContractError::WrongCloseStatus {});
+
+        // But not once voted against
+        let vote = ExecuteMsg::Vote {
+            proposal_id: 2,
+            vote: Vote::No,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(VOTER1, &[]), vote).unwrap();
+        let cancel = ExecuteMsg::Cancel { proposal_id: 2 };
+        let err = execute(deps.as_mut(), mock_env(), info, cancel).unwrap_err();
+        assert_eq!(err, ContractError::OpposedProposal {});
+    }
+
+    #[test]
+    fn private_votes_committed_then_revealed() {
+        let mut deps = mock_dependencies();
+        let instantiate_msg = |reveal_period| InstantiateMsg {
+            voters: vec![voter(OWNER, 1), voter(VOTER1, 1), voter(VOTER2, 1)],
+            threshold: Threshold::AbsoluteCount { weight: 2 },
+            max_voting_period: Duration::Height(10),
+            proposal_deposit: None,
+            close_reward: None,
+            allow_revoting: false,
+            cast_threshold: None,
+            emergency: None,
+            private_voting: Some(cw3::PrivateVoting {
+                reveal_period,
+                bond: Some(coin(5, "TOKEN")),
+            }),
+        };
+        let info = mock_info(OWNER, &[]);
+        let err = instantiate(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            instantiate_msg(Duration::Time(5)),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidPrivateVoting {});
+        let msg = instantiate_msg(Duration::Height(5));
+        instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        // Voting is for commitments, followed by the reveal period
+        let proposal = ExecuteMsg::Propose {
+            title: "Pay somebody".to_string(),
+            description: "Do I pay her?".to_string(),
+            msgs: vec![],
+            latest: None,
+            execution_window: None,
+            metadata: None,
+        };
+        execute(deps.as_mut(), mock_env(), info, proposal.clone()).unwrap();
+        let prop = query_proposal(deps.as_ref(), mock_env(), 1).unwrap();
+        let height = mock_env().block.height;
+        assert_eq!(
+            prop.commit_deadline,
+            Some(Expiration::AtHeight(height + 10))
+        );
+        assert_eq!(prop.expires, Expiration::AtHeight(height + 15));
+        let votes = PROPOSALS.load(&deps.storage, 1).unwrap().votes;
+        assert_eq!(votes, Votes::yes(0));
+        let vote = ExecuteMsg::Vote {
+            proposal_id: 1,
+            vote: Vote::Yes,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(VOTER1, &[]), vote).unwrap_err();
+        assert_eq!(err, ContractError::PrivateProposal {});
+
+        // Commitments are bonded
+        let commit = |voter: &str, vote| ExecuteMsg::CommitVote {
+            proposal_id: 1,
+            commitment: vote_commitment(1, voter, vote, voter.as_bytes()).unwrap(),
+        };
+        let bond = coins(5, "TOKEN");
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(VOTER1, &[]),
+            commit(VOTER1, Vote::Yes),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::WrongBond {});
+        for (voter, vote) in [(VOTER1, Vote::Yes), (OWNER, Vote::Yes), (VOTER2, Vote::No)] {
+            let info = mock_info(voter, &bond);
+            execute(deps.as_mut(), mock_env(), info, commit(voter, vote)).unwrap();
+        }
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(VOTER1, &bond),
+            commit(VOTER1, Vote::No),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::AlreadyVoted {});
+
+        // And revealed once closed
+        let reveal = |voter: &str, vote| ExecuteMsg::RevealVote {
+            proposal_id: 1,
+            vote,
+            salt: Binary::from(voter.as_bytes()),
+        };
+        let info = mock_info(VOTER1, &[]);
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            reveal(VOTER1, Vote::Yes),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::CommitmentsOpen {});
+        let env = mock_env_height(10);
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(VOTER3, &bond),
+            commit(VOTER3, Vote::Yes),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            reveal(VOTER1, Vote::No),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidReveal {});
+        let res = execute(deps.as_mut(), env.clone(), info, reveal(VOTER1, Vote::Yes)).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: VOTER1.to_string(),
+                amount: bond.clone(),
+            })]
+        );
+        let info = mock_info(OWNER, &[]);
+        execute(deps.as_mut(), env, info, reveal(OWNER, Vote::Yes)).unwrap();
+        let prop = query_proposal(deps.as_ref(), mock_env_height(10), 1).unwrap();
+        assert_eq!(prop.status, Status::Passed);
+        let votes = PROPOSALS.load(&deps.storage, 1).unwrap().votes;
+        assert_eq!(votes, Votes::yes(2));
+
+        // Votes not revealed in time are not counted, their bond is forfeited
+        let info = mock_info(VOTER2, &[]);
+        let env = mock_env_height(15);
+        let err = execute(deps.as_mut(), env.clone(), info, reveal(VOTER2, Vote::No)).unwrap_err();
+        assert_eq!(err, ContractError::Expired {});
+        let commitment = query_vote_commitment(deps.as_ref(), 1, VOTER2.to_string()).unwrap();
+        assert!(commitment.commitment.is_some());
+        let commitment = query_vote_commitment(deps.as_ref(), 1, VOTER1.to_string()).unwrap();
+        assert_eq!(commitment.commitment, None);
+
+        // And removed once the proposal is executed
+        let dispatch = ExecuteMsg::DispatchProposal {
+            proposal_id: 1,
+            msg_indices: None,
+        };
+        let info = mock_info(env.contract.address.as_str(), &[]);
+        let res = execute(deps.as_mut(), env, info, dispatch).unwrap();
+        assert_eq!(res.messages, vec![]);
+        let commitment = query_vote_commitment(deps.as_ref(), 1, VOTER2.to_string()).unwrap();
+        assert_eq!(commitment.commitment, None);
+
+        // Proposals ending while votes can still be revealed refund their bonds
+        execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), proposal).unwrap();
+        let info = mock_info(VOTER1, &bond);
+        let commit_2 = ExecuteMsg::CommitVote {
+            proposal_id: 2,
+            commitment: vote_commitment(2, VOTER1, Vote::No, b"salt").unwrap(),
+        };
+        execute(deps.as_mut(), mock_env(), info, commit_2).unwrap();
+        let cancel = ExecuteMsg::Cancel { proposal_id: 2 };
+        let res = execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), cancel).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: VOTER1.to_string(),
+                amount: bond,
+            })]
+        );
+        let commitment = query_vote_commitment(deps.as_ref(), 2, VOTER1.to_string()).unwrap();
+        assert_eq!(commitment.commitment, None);
+    }
 }
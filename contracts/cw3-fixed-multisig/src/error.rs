@@ -1,4 +1,6 @@
 use cosmwasm_std::StdError;
+use cw3::DepositError;
+use cw_controllers::{HookError, PermitError};
 use cw_utils::ThresholdError;
 
 use thiserror::Error;
@@ -11,6 +13,12 @@ pub enum ContractError {
     #[error("{0}")]
     Threshold(#[from] ThresholdError),
 
+    #[error("{0}")]
+    Hook(#[from] HookError),
+
+    #[error("{0}")]
+    Permit(#[from] PermitError),
+
     #[error("Required weight cannot be zero")]
     ZeroWeight {},
 
@@ -38,9 +46,90 @@ pub enum ContractError {
     #[error("Already voted on this proposal")]
     AlreadyVoted {},
 
+    #[error("Proposal cannot be amended once others voted on it")]
+    VotesCast {},
+
     #[error("Proposal must have passed and not yet been executed")]
     WrongExecuteStatus {},
 
+    #[error("Signaling proposals cannot be executed")]
+    SignalingProposal {},
+
+    #[error("Choice proposals are voted on and amended by option")]
+    ChoiceProposal {},
+
+    #[error("Only choice proposals are voted on by option")]
+    NotChoiceProposal {},
+
+    #[error("Choice proposals need 2 to 16 options with distinct labels of up to 64 bytes, and a quorum of at most 1")]
+    InvalidOptions {},
+
+    #[error("Proposal has no option {option}")]
+    UnknownOption { option: u32 },
+
+    #[error("Message indices must be ascending, and of messages not executed yet")]
+    InvalidMsgIndices {},
+
     #[error("Cannot close completed or passed proposals")]
     WrongCloseStatus {},
+
+    #[error("Only proposals not executed nor concluded yet can be cancelled")]
+    WrongCancelStatus {},
+
+    #[error("Proposals voted against cannot be cancelled by their proposer")]
+    OpposedProposal {},
+
+    #[error("No proposal can be closed")]
+    NothingToClose {},
+
+    #[error("No proposal can be pruned")]
+    NothingToPrune {},
+
+    #[error("Execution window must start before it ends, and not be over already")]
+    InvalidExecutionWindow {},
+
+    #[error("Proposal can only be executed within its execution window")]
+    OutsideExecutionWindow {},
+
+    #[error("A cast threshold can only be combined with an absolute count threshold")]
+    InvalidCastThreshold {},
+
+    #[error("The emergency council must not be empty, and its voting period not be longer than the max voting period, with the same unit")]
+    InvalidEmergencyTrack {},
+
+    #[error("Emergency proposals are not enabled")]
+    NoEmergencyTrack {},
+
+    #[error("Proposal metadata must have non-empty fields within their bounds, a link without whitespace, an alphanumeric IPFS hash and distinct tags")]
+    InvalidMetadata {},
+
+    #[error("Only proposals can be created with a cw20 deposit")]
+    NotProposal {},
+
+    #[error("Private voting must have a reveal period with the unit of the max voting period, and a non-zero bond")]
+    InvalidPrivateVoting {},
+
+    #[error("Votes on private proposals are committed, then revealed")]
+    PrivateProposal {},
+
+    #[error("Only votes on private proposals are committed")]
+    NotPrivateProposal {},
+
+    #[error("Commitments to votes on this proposal are closed")]
+    CommitmentsClosed {},
+
+    #[error("Votes are revealed once commitments are closed")]
+    CommitmentsOpen {},
+
+    #[error("No commitment to reveal")]
+    NoCommitment {},
+
+    #[error("Vote and salt do not match the commitment")]
+    InvalidReveal {},
+
+    #[error("Commitments must be sent along with exactly the bond, if any")]
+    WrongBond {},
+
+    #[error("{0}")]
+    Deposit(#[from] DepositError),
 }
@@ -1,13 +1,19 @@
 #![cfg(test)]
 
-use cosmwasm_std::{to_binary, Addr, Empty, Uint128, WasmMsg};
-use cw20::{BalanceResponse, MinterResponse};
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    to_binary, Addr, Binary, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdError, StdResult,
+    Uint128, WasmMsg,
+};
+use cw20::{BalanceResponse, Cw20Coin, Cw20ExecuteMsg, MinterResponse, UncheckedDenom};
 use cw20_base::msg::QueryMsg;
-use cw3::Vote;
+use cw3::{ProposalHookMsg, UncheckedDepositInfo, Vote};
+use cw_controllers::HooksResponse;
 use cw_multi_test::{App, Contract, ContractWrapper, Executor};
+use cw_storage_plus::Item;
 use cw_utils::{Duration, Threshold};
 
-use crate::contract::{execute, instantiate, query};
+use crate::contract::{execute, instantiate, query, reply};
 use crate::msg::{ExecuteMsg, InstantiateMsg, Voter};
 
 fn mock_app() -> App {
@@ -15,10 +21,51 @@ fn mock_app() -> App {
 }
 
 pub fn contract_cw3_fixed_multisig() -> Box<dyn Contract<Empty>> {
-    let contract = ContractWrapper::new(execute, instantiate, query);
+    let contract = ContractWrapper::new(execute, instantiate, query).with_reply(reply);
     Box::new(contract)
 }
 
+#[cw_serde]
+enum HookExecuteMsg {
+    ProposalHook(ProposalHookMsg),
+}
+
+const HOOK_MSGS: Item<Vec<ProposalHookMsg>> = Item::new("hook_msgs");
+
+// records the proposal hook messages it is sent, returning them to any query
+fn contract_hook_recorder() -> Box<dyn Contract<Empty>> {
+    fn instantiate(deps: DepsMut, _: Env, _: MessageInfo, _: Empty) -> StdResult<Response> {
+        HOOK_MSGS.save(deps.storage, &vec![])?;
+        Ok(Response::new())
+    }
+    fn execute(deps: DepsMut, _: Env, _: MessageInfo, msg: HookExecuteMsg) -> StdResult<Response> {
+        let HookExecuteMsg::ProposalHook(msg) = msg;
+        HOOK_MSGS.update(deps.storage, |mut msgs| -> StdResult<_> {
+            msgs.push(msg);
+            Ok(msgs)
+        })?;
+        Ok(Response::new())
+    }
+    fn query(deps: Deps, _: Env, _: Empty) -> StdResult<Binary> {
+        to_binary(&HOOK_MSGS.load(deps.storage)?)
+    }
+    Box::new(ContractWrapper::new(execute, instantiate, query))
+}
+
+// fails on any proposal hook message
+fn contract_failing_hook() -> Box<dyn Contract<Empty>> {
+    fn instantiate(_: DepsMut, _: Env, _: MessageInfo, _: Empty) -> StdResult<Response> {
+        Ok(Response::new())
+    }
+    fn execute(_: DepsMut, _: Env, _: MessageInfo, _: HookExecuteMsg) -> StdResult<Response> {
+        Err(StdError::generic_err("hook failed"))
+    }
+    fn query(_: Deps, _: Env, _: Empty) -> StdResult<Binary> {
+        to_binary(&Empty {})
+    }
+    Box::new(ContractWrapper::new(execute, instantiate, query))
+}
+
 pub fn contract_cw20() -> Box<dyn Contract<Empty>> {
     let contract = ContractWrapper::new(
         cw20_base::contract::execute,
@@ -56,6 +103,12 @@ fn cw3_controls_cw20() {
         ],
         threshold: Threshold::AbsoluteCount { weight: 2 },
         max_voting_period: Duration::Height(3),
+        proposal_deposit: None,
+        close_reward: None,
+        allow_revoting: false,
+        cast_threshold: None,
+        emergency: None,
+        private_voting: None,
     };
 
     let multisig_addr = router
@@ -82,6 +135,9 @@ fn cw3_controls_cw20() {
             cap: None,
         }),
         marketing: None,
+        snapshots: false,
+        freezer: None,
+        pauser: None,
     };
     let cw20_addr = router
         .instantiate_contract(
@@ -112,6 +168,8 @@ fn cw3_controls_cw20() {
         description: "Need to mint tokens".to_string(),
         msgs: vec![execute_mint_msg.into()],
         latest: None,
+        execution_window: None,
+        metadata: None,
     };
     // propose mint
     router
@@ -128,7 +186,10 @@ fn cw3_controls_cw20() {
         .unwrap();
 
     // only 1 vote and msg mint fails
-    let execute_proposal_msg = ExecuteMsg::Execute { proposal_id: 1 };
+    let execute_proposal_msg = ExecuteMsg::Execute {
+        proposal_id: 1,
+        msg_indices: None,
+    };
     // execute mint
     router
         .execute_contract(addr1, multisig_addr, &execute_proposal_msg, &[])
@@ -146,3 +207,284 @@ fn cw3_controls_cw20() {
     // compare minted amount
     assert_eq!(balance.balance, mint_amount);
 }
+
+#[test]
+// cw20 deposits can be sent along with proposals, rather than allowed
+fn cw20_deposit_sent_with_proposal() {
+    let mut router = mock_app();
+
+    let proposer = Addr::unchecked("addr1");
+    let voter = Addr::unchecked("addr2");
+    let cw20_id = router.store_code(contract_cw20());
+    let cw20_instantiate_msg = cw20_base::msg::InstantiateMsg {
+        name: "Deposit Token".parse().unwrap(),
+        symbol: "DPT".parse().unwrap(),
+        decimals: 6,
+        initial_balances: vec![Cw20Coin {
+            address: proposer.to_string(),
+            amount: Uint128::new(10),
+        }],
+        mint: None,
+        marketing: None,
+        snapshots: false,
+        freezer: None,
+        pauser: None,
+    };
+    let cw20_addr = router
+        .instantiate_contract(
+            cw20_id,
+            proposer.clone(),
+            &cw20_instantiate_msg,
+            &[],
+            "Deposit",
+            None,
+        )
+        .unwrap();
+
+    let cw3_id = router.store_code(contract_cw3_fixed_multisig());
+    let cw3_instantiate_msg = InstantiateMsg {
+        voters: vec![
+            Voter {
+                addr: proposer.to_string(),
+                weight: 1,
+            },
+            Voter {
+                addr: voter.to_string(),
+                weight: 1,
+            },
+        ],
+        threshold: Threshold::AbsoluteCount { weight: 2 },
+        max_voting_period: Duration::Height(3),
+        proposal_deposit: Some(UncheckedDepositInfo {
+            amount: Uint128::new(10),
+            denom: UncheckedDenom::Cw20(cw20_addr.to_string()),
+            refund_failed_proposals: true,
+            refund_passed_proposals: true,
+            forfeit: None,
+        }),
+        close_reward: None,
+        allow_revoting: false,
+        cast_threshold: None,
+        emergency: None,
+        private_voting: None,
+    };
+    let multisig_addr = router
+        .instantiate_contract(
+            cw3_id,
+            proposer.clone(),
+            &cw3_instantiate_msg,
+            &[],
+            "Consortium",
+            None,
+        )
+        .unwrap();
+    let balance = |router: &App, address: &Addr| {
+        let balance: BalanceResponse = router
+            .wrap()
+            .query_wasm_smart(
+                &cw20_addr,
+                &QueryMsg::Balance {
+                    address: address.to_string(),
+                },
+            )
+            .unwrap();
+        balance.balance
+    };
+
+    // send the deposit along with the proposal
+    let propose_msg = ExecuteMsg::ProposeSignaling {
+        title: "Agree".to_string(),
+        description: "We agree".to_string(),
+        latest: None,
+        metadata: None,
+    };
+    let send_msg = Cw20ExecuteMsg::Send {
+        contract: multisig_addr.to_string(),
+        amount: Uint128::new(10),
+        msg: to_binary(&propose_msg).unwrap(),
+    };
+    router
+        .execute_contract(proposer.clone(), cw20_addr.clone(), &send_msg, &[])
+        .unwrap();
+    assert_eq!(balance(&router, &multisig_addr), Uint128::new(10));
+
+    // it is refunded to the proposer once the proposal passed
+    let vote_msg = ExecuteMsg::Vote {
+        proposal_id: 1,
+        vote: Vote::Yes,
+    };
+    router
+        .execute_contract(voter, multisig_addr.clone(), &vote_msg, &[])
+        .unwrap();
+    assert_eq!(balance(&router, &multisig_addr), Uint128::zero());
+    assert_eq!(balance(&router, &proposer), Uint128::new(10));
+}
+
+#[test]
+// registered contracts are told of the lifecycle of proposals, failing ones without blocking it
+fn proposal_hooks_notified() {
+    let mut router = mock_app();
+
+    let addr1 = Addr::unchecked("addr1");
+    let addr2 = Addr::unchecked("addr2");
+    let cw3_id = router.store_code(contract_cw3_fixed_multisig());
+    let cw3_instantiate_msg = InstantiateMsg {
+        voters: vec![
+            Voter {
+                addr: addr1.to_string(),
+                weight: 1,
+            },
+            Voter {
+                addr: addr2.to_string(),
+                weight: 1,
+            },
+        ],
+        threshold: Threshold::AbsoluteCount { weight: 2 },
+        max_voting_period: Duration::Height(3),
+        proposal_deposit: None,
+        close_reward: None,
+        allow_revoting: false,
+        cast_threshold: None,
+        emergency: None,
+        private_voting: None,
+    };
+    let multisig_addr = router
+        .instantiate_contract(
+            cw3_id,
+            addr1.clone(),
+            &cw3_instantiate_msg,
+            &[],
+            "Consortium",
+            None,
+        )
+        .unwrap();
+    let recorder_id = router.store_code(contract_hook_recorder());
+    let recorder_addr = router
+        .instantiate_contract(recorder_id, addr1.clone(), &Empty {}, &[], "Recorder", None)
+        .unwrap();
+    let failing_id = router.store_code(contract_failing_hook());
+    let failing_addr = router
+        .instantiate_contract(failing_id, addr1.clone(), &Empty {}, &[], "Failing", None)
+        .unwrap();
+
+    // only the multisig itself can register hooks
+    let add_hook = |hook: &Addr| ExecuteMsg::AddProposalHook {
+        addr: hook.to_string(),
+    };
+    router
+        .execute_contract(
+            addr1.clone(),
+            multisig_addr.clone(),
+            &add_hook(&recorder_addr),
+            &[],
+        )
+        .unwrap_err();
+
+    // so it takes a proposal, executed to register both hooks
+    let propose = |msgs: Vec<ExecuteMsg>| ExecuteMsg::Propose {
+        title: "Hooks".to_string(),
+        description: "Register hooks".to_string(),
+        msgs: msgs
+            .iter()
+            .map(|msg| {
+                WasmMsg::Execute {
+                    contract_addr: multisig_addr.to_string(),
+                    msg: to_binary(msg).unwrap(),
+                    funds: vec![],
+                }
+                .into()
+            })
+            .collect(),
+        latest: None,
+        execution_window: None,
+        metadata: None,
+    };
+    let vote = |proposal_id, vote| ExecuteMsg::Vote { proposal_id, vote };
+    let register = propose(vec![add_hook(&recorder_addr), add_hook(&failing_addr)]);
+    router
+        .execute_contract(addr1.clone(), multisig_addr.clone(), &register, &[])
+        .unwrap();
+    router
+        .execute_contract(
+            addr2.clone(),
+            multisig_addr.clone(),
+            &vote(1, Vote::Yes),
+            &[],
+        )
+        .unwrap();
+    router
+        .execute_contract(
+            addr1.clone(),
+            multisig_addr.clone(),
+            &ExecuteMsg::Execute {
+                proposal_id: 1,
+                msg_indices: None,
+            },
+            &[],
+        )
+        .unwrap();
+    let hooks: HooksResponse = router
+        .wrap()
+        .query_wasm_smart(&multisig_addr, &crate::msg::QueryMsg::ProposalHooks {})
+        .unwrap();
+    assert_eq!(
+        hooks.hooks,
+        vec![recorder_addr.to_string(), failing_addr.to_string()]
+    );
+
+    // a proposal passing and executed, and one rejected by votes
+    router
+        .execute_contract(addr1.clone(), multisig_addr.clone(), &propose(vec![]), &[])
+        .unwrap();
+    router
+        .execute_contract(
+            addr2.clone(),
+            multisig_addr.clone(),
+            &vote(2, Vote::Yes),
+            &[],
+        )
+        .unwrap();
+    router
+        .execute_contract(
+            addr2.clone(),
+            multisig_addr.clone(),
+            &ExecuteMsg::Execute {
+                proposal_id: 2,
+                msg_indices: None,
+            },
+            &[],
+        )
+        .unwrap();
+    router
+        .execute_contract(addr1.clone(), multisig_addr.clone(), &propose(vec![]), &[])
+        .unwrap();
+    let res = router
+        .execute_contract(addr2, multisig_addr, &vote(3, Vote::No), &[])
+        .unwrap();
+    assert!(res
+        .events
+        .iter()
+        .flat_map(|event| &event.attributes)
+        .any(|attr| attr.key == "action" && attr.value == "proposal_hook_failed"));
+
+    let recorded: Vec<ProposalHookMsg> = router
+        .wrap()
+        .query_wasm_smart(&recorder_addr, &Empty {})
+        .unwrap();
+    assert_eq!(
+        recorded,
+        vec![
+            ProposalHookMsg::Created {
+                proposal_id: 2,
+                proposer: addr1.to_string(),
+            },
+            ProposalHookMsg::Passed { proposal_id: 2 },
+            ProposalHookMsg::Executed { proposal_id: 2 },
+            ProposalHookMsg::Created {
+                proposal_id: 3,
+                proposer: addr1.to_string(),
+            },
+            ProposalHookMsg::Rejected { proposal_id: 3 },
+        ]
+    );
+}
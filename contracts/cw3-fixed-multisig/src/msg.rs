@@ -1,6 +1,10 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{CosmosMsg, Empty};
-use cw3::Vote;
+use cosmwasm_std::{Binary, CosmosMsg, Decimal, Empty};
+use cw20::Cw20ReceiveMsg;
+use cw3::{
+    CloseReward, ExecutionWindow, PrivateVoting, ProposalMetadata, ProposalOption,
+    UncheckedDepositInfo, Vote, VotePermit, VoterDetail, WinCondition,
+};
 use cw_utils::{Duration, Expiration, Threshold};
 
 #[cw_serde]
@@ -8,6 +12,37 @@ pub struct InstantiateMsg {
     pub voters: Vec<Voter>,
     pub threshold: Threshold,
     pub max_voting_period: Duration,
+    /// The cost of creating a proposal (if any).
+    #[serde(default)]
+    pub proposal_deposit: Option<UncheckedDepositInfo>,
+    /// The reward for closing a rejected proposal (if any).
+    #[serde(default)]
+    pub close_reward: Option<CloseReward>,
+    /// Whether voters can change their vote while the proposal is open.
+    #[serde(default)]
+    pub allow_revoting: bool,
+    /// The share of the votes cast (but abstain) that must also be yes, with an `AbsoluteCount`
+    /// threshold (if any).
+    #[serde(default)]
+    pub cast_threshold: Option<Decimal>,
+    /// Lets a council of voters propose with a shorter voting period and its own threshold,
+    /// for incident response (if any).
+    #[serde(default)]
+    pub emergency: Option<UncheckedEmergencyTrack>,
+    /// Has voters commit to their vote during the voting period, and reveal it after, on
+    /// proposals other than emergency and choice ones (if any).
+    #[serde(default)]
+    pub private_voting: Option<PrivateVoting>,
+}
+
+/// Validated into `EmergencyTrack` on instantiation
+#[cw_serde]
+pub struct UncheckedEmergencyTrack {
+    /// Must not be empty. Only those of them who are voters can propose
+    pub council: Vec<String>,
+    pub threshold: Threshold,
+    /// Must have the unit of the max voting period, and not be longer
+    pub voting_period: Duration,
 }
 
 #[cw_serde]
@@ -25,17 +60,136 @@ pub enum ExecuteMsg {
         msgs: Vec<CosmosMsg<Empty>>,
         // note: we ignore API-spec'd earliest if passed, always opens immediately
         latest: Option<Expiration>,
+        /// When the proposal can be executed once passed, eg. exactly at an epoch boundary
+        #[serde(default)]
+        execution_window: Option<ExecutionWindow>,
+        /// Structured data for governance UIs, eg. a link to the discussion
+        #[serde(default)]
+        metadata: Option<ProposalMetadata>,
+    },
+    /// Proposes to record a decision, with nothing to execute once it passed
+    ProposeSignaling {
+        title: String,
+        description: String,
+        latest: Option<Expiration>,
+        /// Structured data for governance UIs, eg. a link to the discussion
+        #[serde(default)]
+        metadata: Option<ProposalMetadata>,
+    },
+    /// Proposes an urgent action, voted on with the emergency voting period and threshold.
+    /// Only for voters in the emergency council
+    ProposeEmergency {
+        title: String,
+        description: String,
+        msgs: Vec<CosmosMsg<Empty>>,
+        /// Structured data for governance UIs, eg. a link to the discussion
+        #[serde(default)]
+        metadata: Option<ProposalMetadata>,
+    },
+    /// Proposes several options, the one winning as per `win_condition` having its messages
+    /// executed. Voted on with `VoteChoice`
+    ProposeChoice {
+        title: String,
+        description: String,
+        options: Vec<ProposalOption>,
+        win_condition: WinCondition,
+        // note: we ignore API-spec'd earliest if passed, always opens immediately
+        latest: Option<Expiration>,
+        /// Structured data for governance UIs, eg. a link to the discussion
+        #[serde(default)]
+        metadata: Option<ProposalMetadata>,
+    },
+    /// Creates the proposal of the message, one of the above, paying its cw20 deposit with the
+    /// tokens sent
+    Receive(Cw20ReceiveMsg),
+    /// Updates an open proposal nobody else voted on yet. Only for the proposer, whose own
+    /// vote is withdrawn
+    Amend {
+        proposal_id: u64,
+        title: Option<String>,
+        description: Option<String>,
+        msgs: Option<Vec<CosmosMsg<Empty>>>,
     },
     Vote {
         proposal_id: u64,
         vote: Vote,
     },
+    /// Votes for one of the options of a choice proposal, by its index
+    VoteChoice {
+        proposal_id: u64,
+        option: u32,
+    },
+    /// Casts the vote of a voter who signed the ballot off-chain, so that voters without gas
+    /// tokens can vote. Anyone may submit it before the deadline, and nonce must be the voter's
+    /// current one, which is then incremented
+    RelayVote {
+        ballot: VotePermit,
+        /// The voter's compressed secp256k1 public key, which must derive to the voter address
+        pubkey: Binary,
+        signature: Binary,
+    },
+    /// Commits to a vote on a private proposal, with the `vote_commitment` of the vote and a
+    /// secret salt, along with the bond if any. Only while commitments are open
+    CommitVote {
+        proposal_id: u64,
+        commitment: Binary,
+    },
+    /// Casts the vote committed to, once commitments are closed and until the proposal expires
+    RevealVote {
+        proposal_id: u64,
+        vote: Vote,
+        salt: Binary,
+    },
     Execute {
         proposal_id: u64,
+        /// The ascending indices of the messages to execute, all those not executed yet if
+        /// unset. The proposal is `Executed` once all its messages are.
+        #[serde(default)]
+        msg_indices: Option<Vec<u32>>,
+    },
+    /// Dispatches the messages of a proposal being executed. Only for the multisig itself, as
+    /// a submessage of `Execute`, so that failing messages leave the proposal `ExecutionFailed`
+    DispatchProposal {
+        proposal_id: u64,
+        #[serde(default)]
+        msg_indices: Option<Vec<u32>>,
     },
     Close {
         proposal_id: u64,
     },
+    /// Withdraws a proposal not executed yet. Only for its proposer, until anybody votes against it
+    Cancel {
+        proposal_id: u64,
+    },
+    /// Closes the proposals that can be closed among the next `limit` ones after `start_after`.
+    /// The last one read is returned in the `last_proposal_id` attribute, to continue from there
+    CloseMany {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Removes the executed, rejected or cancelled proposals, along with their votes, once
+    /// `older_than` passed since they expired, among the next `limit` ones after `start_after`.
+    /// The last one read is returned in the `last_proposal_id` attribute, to continue from there
+    PruneProposals {
+        older_than: Duration,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Removes and then adds (or updates) voters. Only for the multisig itself, executing a
+    /// passed proposal
+    UpdateVoters {
+        add: Vec<Voter>,
+        remove: Vec<String>,
+    },
+    /// Registers a contract to be told of the lifecycle of proposals. Only for the multisig
+    /// itself, executing a passed proposal
+    AddProposalHook {
+        addr: String,
+    },
+    /// Unregisters a proposal hook. Only for the multisig itself, executing a passed proposal
+    RemoveProposalHook {
+        addr: String,
+    },
 }
 
 // We can also add this as a cw3 extension
@@ -46,6 +200,8 @@ pub enum QueryMsg {
     Threshold {},
     #[returns(cw3::ProposalResponse)]
     Proposal { proposal_id: u64 },
+    #[returns(cw3::TallyResponse)]
+    ProposalTally { proposal_id: u64 },
     #[returns(cw3::ProposalListResponse)]
     ListProposals {
         start_after: Option<u64>,
@@ -56,6 +212,32 @@ pub enum QueryMsg {
         start_before: Option<u64>,
         limit: Option<u32>,
     },
+    /// Lists the proposals with the given current status, skipping the others.
+    #[returns(cw3::ProposalListResponse)]
+    ListProposalsByStatus {
+        status: cw3::Status,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    #[returns(cw3::ProposalListResponse)]
+    ListProposalsByProposer {
+        proposer: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Passed proposals waiting for their execution window, or within it
+    #[returns(cw3::ProposalListResponse)]
+    ScheduledExecutions {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Open proposals the voter can vote on, but did not yet
+    #[returns(cw3::ProposalListResponse)]
+    PendingVotes {
+        voter: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
     #[returns(cw3::VoteResponse)]
     Vote { proposal_id: u64, voter: String },
     #[returns(cw3::VoteListResponse)]
@@ -71,4 +253,28 @@ pub enum QueryMsg {
         start_after: Option<String>,
         limit: Option<u32>,
     },
+    /// Gets the current configuration.
+    #[returns(crate::state::Config)]
+    Config {},
+    /// Contracts told of the lifecycle of proposals
+    #[returns(cw_controllers::HooksResponse)]
+    ProposalHooks {},
+    /// The nonce the next ballot signed by the voter must use
+    #[returns(cw_controllers::PermitNonceResponse)]
+    VoteNonce { voter: String },
+    /// The commitment of the voter to a vote on a private proposal, until it is revealed
+    #[returns(cw3::VoteCommitmentResponse)]
+    VoteCommitment { proposal_id: u64, voter: String },
+    /// All the voters with their weights, along with the voting rules, to instantiate the
+    /// cw4-group and configure the cw3-flex-multisig replacing this contract
+    #[returns(ExportResponse)]
+    Export {},
+}
+
+#[cw_serde]
+pub struct ExportResponse {
+    /// Every voter, including those with no weight, to become the members of the group
+    pub voters: Vec<VoterDetail>,
+    pub threshold: Threshold,
+    pub max_voting_period: Duration,
 }
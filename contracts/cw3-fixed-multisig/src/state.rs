@@ -1,8 +1,11 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, StdResult, Storage};
+use cosmwasm_std::{
+    Addr, BankMsg, Binary, BlockInfo, Coin, Decimal, Empty, Order, StdResult, Storage, SubMsg,
+};
 
-use cw3::{Ballot, Proposal};
-use cw_storage_plus::{Item, Map};
+use cw3::{Ballot, CloseReward, DepositInfo, PrivateVoting, Proposal, ProposalHookMsg, Status};
+use cw_controllers::{Hooks, Permits};
+use cw_storage_plus::{Item, Map, SnapshotMap, Strategy};
 use cw_utils::{Duration, Threshold};
 
 #[cw_serde]
@@ -10,18 +13,158 @@ pub struct Config {
     pub threshold: Threshold,
     pub total_weight: u64,
     pub max_voting_period: Duration,
+    /// The price, if any, of creating a new proposal.
+    pub proposal_deposit: Option<DepositInfo>,
+    /// The reward, if any, for closing a rejected proposal.
+    #[serde(default)]
+    pub close_reward: Option<CloseReward>,
+    /// Whether voters can change their vote while the proposal is open.
+    #[serde(default)]
+    pub allow_revoting: bool,
+    /// The share of the votes cast (but abstain) that must also be yes, if any.
+    #[serde(default)]
+    pub cast_threshold: Option<Decimal>,
+    /// How emergency proposals are created and voted on, if they can be.
+    #[serde(default)]
+    pub emergency: Option<EmergencyTrack>,
+    /// Whether voters commit to their vote before revealing it, on proposals other than
+    /// emergency and choice ones.
+    #[serde(default)]
+    pub private_voting: Option<PrivateVoting>,
+}
+
+#[cw_serde]
+pub struct EmergencyTrack {
+    /// The voters among them can create emergency proposals
+    pub council: Vec<Addr>,
+    /// Applied instead of the threshold of the config
+    pub threshold: Threshold,
+    /// Applied instead of the max voting period, with the same unit
+    pub voting_period: Duration,
+}
+
+impl EmergencyTrack {
+    /// Whether the voting period is no longer than `max_voting_period`, with the same unit
+    pub fn is_shorter(&self, max_voting_period: Duration) -> bool {
+        match (self.voting_period, max_voting_period) {
+            (Duration::Height(period), Duration::Height(max)) => period <= max,
+            (Duration::Time(period), Duration::Time(max)) => period <= max,
+            _ => false,
+        }
+    }
 }
 
 // unique items
 pub const CONFIG: Item<Config> = Item::new("config");
 pub const PROPOSAL_COUNT: Item<u64> = Item::new("proposal_count");
 
+/// The commitment of a voter to a vote on a private proposal, until it is revealed
+#[cw_serde]
+pub struct Commitment {
+    pub hash: Binary,
+    /// Refunded when the vote is revealed
+    pub bond: Option<Coin>,
+}
+
 // multiple-item map
 pub const BALLOTS: Map<(u64, &Addr), Ballot> = Map::new("votes");
 pub const PROPOSALS: Map<u64, Proposal> = Map::new("proposals");
+// ids of the proposals of each proposer, to list them
+pub const PROPOSALS_BY_PROPOSER: Map<(&Addr, u64), Empty> = Map::new("proposals_by_proposer");
+// ids of the proposals with an execution window, until they are executed or closed
+pub const SCHEDULED_PROPOSALS: Map<u64, Empty> = Map::new("scheduled_proposals");
+// ids of the proposals open for votes, until votes decide them or they are closed
+pub const OPEN_PROPOSALS: Map<u64, Empty> = Map::new("open_proposals");
+// contracts told of proposals being created, passed, rejected, executed or vetoed
+pub const PROPOSAL_HOOKS: Hooks = Hooks::new("proposal_hooks");
+// nonces of the ballots voters signed off-chain, for anyone to relay
+pub const VOTE_PERMITS: Permits = Permits::new("vote_permits");
+// commitments to votes on private proposals, until they are revealed
+pub const VOTE_COMMITMENTS: Map<(u64, &Addr), Commitment> = Map::new("vote_commitments");
+// failed proposal hooks are replied to with this id, apart from the proposal ids of dispatches
+pub const PROPOSAL_HOOK_REPLY_ID: u64 = u64::MAX;
 
 // multiple-item maps
-pub const VOTERS: Map<&Addr, u64> = Map::new("voters");
+// snapshotted, so that votes on open proposals use the voter set they were created with
+pub const VOTERS: SnapshotMap<&Addr, u64> = SnapshotMap::new(
+    "voters",
+    "voters__checkpoints",
+    "voters__changelog",
+    Strategy::EveryBlock,
+);
+
+/// Messages telling every proposal hook of the event. Their failure is replied to rather than
+/// reverting, so that a faulty hook cannot block the multisig
+pub fn proposal_hook_msgs(storage: &dyn Storage, msg: ProposalHookMsg) -> StdResult<Vec<SubMsg>> {
+    PROPOSAL_HOOKS.prepare_hooks(storage, |hook| {
+        Ok(SubMsg::reply_on_error(
+            msg.clone().into_cosmos_msg(hook)?,
+            PROPOSAL_HOOK_REPLY_ID,
+        ))
+    })
+}
+
+/// Messages telling the proposal hooks that the status of the proposal changed, if they are
+pub fn status_hook_msgs(
+    storage: &dyn Storage,
+    proposal_id: u64,
+    status: Status,
+) -> StdResult<Vec<SubMsg>> {
+    match ProposalHookMsg::status_changed(proposal_id, status) {
+        Some(msg) => proposal_hook_msgs(storage, msg),
+        None => Ok(vec![]),
+    }
+}
+
+/// Removes the proposal along with its votes and whatever indexes it
+pub fn remove_proposal(storage: &mut dyn Storage, id: u64, prop: &Proposal) -> StdResult<()> {
+    let voters = BALLOTS
+        .prefix(id)
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for voter in voters {
+        BALLOTS.remove(storage, (id, &voter));
+    }
+    // along with the commitments never revealed
+    let committed = VOTE_COMMITMENTS
+        .prefix(id)
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for voter in committed {
+        VOTE_COMMITMENTS.remove(storage, (id, &voter));
+    }
+    PROPOSALS.remove(storage, id);
+    PROPOSALS_BY_PROPOSER.remove(storage, (&prop.proposer, id));
+    SCHEDULED_PROPOSALS.remove(storage, id);
+    OPEN_PROPOSALS.remove(storage, id);
+    Ok(())
+}
+
+/// Removes the vote commitments never revealed once the proposal is over. Their bonds are refunded
+/// if they could still have been revealed, and stay in the contract otherwise.
+pub fn clear_commitments(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    id: u64,
+    prop: &Proposal,
+) -> StdResult<Vec<BankMsg>> {
+    let committed = VOTE_COMMITMENTS
+        .prefix(id)
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let revealable = !prop.expires.is_expired(block);
+    let mut refunds = vec![];
+    for (voter, commitment) in committed {
+        VOTE_COMMITMENTS.remove(storage, (id, &voter));
+        if let (true, Some(bond)) = (revealable, commitment.bond) {
+            refunds.push(BankMsg::Send {
+                to_address: voter.into_string(),
+                amount: vec![bond],
+            });
+        }
+    }
+    Ok(refunds)
+}
 
 pub fn next_id(store: &mut dyn Storage) -> StdResult<u64> {
     let id: u64 = PROPOSAL_COUNT.may_load(store)?.unwrap_or_default() + 1;
@@ -0,0 +1,253 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Order, Reply, Response, StdError,
+    StdResult, SubMsg, Uint64, WasmMsg,
+};
+
+use cw2::set_contract_version;
+use cw3::UncheckedDepositInfo;
+use cw3_flex_multisig::state::Executor;
+use cw4::Member;
+use cw_storage_plus::Bound;
+use cw_utils::{maybe_addr, parse_reply_instantiate_data, Duration, Threshold};
+
+use crate::error::ContractError;
+use crate::msg::{ExecuteMsg, InstantiateMsg, MultisigInfo, MultisigListResponse, QueryMsg};
+use crate::state::{Config, PendingMultisig, CONFIG, MULTISIGS, PENDING_GROUP, PENDING_MULTISIG};
+
+// version info for migration info
+const CONTRACT_NAME: &str = "crates.io:cw3-flex-factory";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+const GROUP_REPLY_ID: u64 = 1;
+const MULTISIG_REPLY_ID: u64 = 2;
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    let cfg = Config {
+        group_code_id: msg.group_code_id,
+        multisig_code_id: msg.multisig_code_id,
+    };
+    CONFIG.save(deps.storage, &cfg)?;
+    Ok(Response::default())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::CreateMultisig {
+            label,
+            members,
+            threshold,
+            max_voting_period,
+            executor,
+            proposal_deposit,
+        } => execute_create_multisig(
+            deps,
+            env,
+            label,
+            members,
+            threshold,
+            max_voting_period,
+            executor,
+            proposal_deposit,
+        ),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute_create_multisig(
+    deps: DepsMut,
+    env: Env,
+    label: String,
+    members: Vec<Member>,
+    threshold: Threshold,
+    max_voting_period: Duration,
+    executor: Option<Executor>,
+    proposal_deposit: Option<UncheckedDepositInfo>,
+) -> Result<Response, ContractError> {
+    // fail before instantiating anything if the multisig would reject the group
+    let total_weight = members
+        .iter()
+        .try_fold(Uint64::zero(), |total, m| {
+            total.checked_add(m.weight.into())
+        })
+        .map_err(StdError::from)?;
+    threshold.validate(total_weight.u64())?;
+
+    let cfg = CONFIG.load(deps.storage)?;
+    PENDING_MULTISIG.save(
+        deps.storage,
+        &PendingMultisig {
+            label: label.clone(),
+            threshold,
+            max_voting_period,
+            executor,
+            proposal_deposit,
+        },
+    )?;
+
+    // the factory administers the group until the multisig exists
+    let factory = env.contract.address.to_string();
+    let group_msg = cw4_group::msg::InstantiateMsg {
+        admin: Some(factory.clone()),
+        members,
+        allow_leave: false,
+        weight_decimals: 0,
+        decimal_members: vec![],
+    };
+    let instantiate = WasmMsg::Instantiate {
+        admin: Some(factory),
+        code_id: cfg.group_code_id,
+        msg: to_binary(&group_msg)?,
+        funds: vec![],
+        label: format!("{} group", label),
+    };
+
+    let res = Response::new()
+        .add_submessage(SubMsg::reply_on_success(instantiate, GROUP_REPLY_ID))
+        .add_attribute("action", "create_multisig");
+    Ok(res)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match msg.id {
+        GROUP_REPLY_ID => reply_group_instantiated(deps, env, msg),
+        MULTISIG_REPLY_ID => reply_multisig_instantiated(deps, msg),
+        id => Err(ContractError::UnknownReplyId { id }),
+    }
+}
+
+fn reply_group_instantiated(
+    deps: DepsMut,
+    env: Env,
+    msg: Reply,
+) -> Result<Response, ContractError> {
+    let res = parse_reply_instantiate_data(msg)?;
+    let group = deps.api.addr_validate(&res.contract_address)?;
+    PENDING_GROUP.save(deps.storage, &group)?;
+
+    let cfg = CONFIG.load(deps.storage)?;
+    let pending = PENDING_MULTISIG.load(deps.storage)?;
+    PENDING_MULTISIG.remove(deps.storage);
+
+    let multisig_msg = cw3_flex_multisig::msg::InstantiateMsg {
+        group_addr: group.to_string(),
+        threshold: pending.threshold,
+        max_voting_period: pending.max_voting_period,
+        executor: pending.executor,
+        proposal_deposit: pending.proposal_deposit,
+        veto: None,
+        execution_delay: None,
+        close_reward: None,
+        threshold_profiles: vec![],
+        allow_revoting: false,
+        proposers: None,
+        extra_groups: vec![],
+        emergency: None,
+        quadratic_voting: false,
+        timelock: None,
+        private_voting: None,
+    };
+    let instantiate = WasmMsg::Instantiate {
+        admin: Some(env.contract.address.to_string()),
+        code_id: cfg.multisig_code_id,
+        msg: to_binary(&multisig_msg)?,
+        funds: vec![],
+        label: pending.label,
+    };
+
+    Ok(Response::new().add_submessage(SubMsg::reply_on_success(instantiate, MULTISIG_REPLY_ID)))
+}
+
+fn reply_multisig_instantiated(deps: DepsMut, msg: Reply) -> Result<Response, ContractError> {
+    let res = parse_reply_instantiate_data(msg)?;
+    let multisig = deps.api.addr_validate(&res.contract_address)?;
+    let group = PENDING_GROUP.load(deps.storage)?;
+    PENDING_GROUP.remove(deps.storage);
+    MULTISIGS.save(deps.storage, &multisig, &group)?;
+
+    // register the multisig for membership changes, then hand over all admin rights to it
+    let add_hook = cw4_group::msg::ExecuteMsg::AddHook {
+        addr: multisig.to_string(),
+    };
+    let update_admin = cw4_group::msg::ExecuteMsg::UpdateAdmin {
+        admin: Some(multisig.to_string()),
+    };
+    let msgs = vec![
+        WasmMsg::Execute {
+            contract_addr: group.to_string(),
+            msg: to_binary(&add_hook)?,
+            funds: vec![],
+        },
+        WasmMsg::Execute {
+            contract_addr: group.to_string(),
+            msg: to_binary(&update_admin)?,
+            funds: vec![],
+        },
+        WasmMsg::UpdateAdmin {
+            contract_addr: group.to_string(),
+            admin: multisig.to_string(),
+        },
+        WasmMsg::UpdateAdmin {
+            contract_addr: multisig.to_string(),
+            admin: multisig.to_string(),
+        },
+    ];
+
+    let res = Response::new()
+        .add_messages(msgs)
+        .add_attribute("multisig", multisig)
+        .add_attribute("group", group);
+    Ok(res)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_binary(&CONFIG.load(deps.storage)?),
+        QueryMsg::ListMultisigs { start_after, limit } => {
+            to_binary(&list_multisigs(deps, start_after, limit)?)
+        }
+    }
+}
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+fn list_multisigs(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<MultisigListResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let addr = maybe_addr(deps.api, start_after)?;
+    let start = addr.as_ref().map(Bound::exclusive);
+
+    let multisigs = MULTISIGS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            item.map(|(multisig, group)| MultisigInfo {
+                multisig: multisig.into(),
+                group: group.into(),
+            })
+        })
+        .collect::<StdResult<_>>()?;
+
+    Ok(MultisigListResponse { multisigs })
+}
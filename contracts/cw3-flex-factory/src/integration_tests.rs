@@ -0,0 +1,219 @@
+#![cfg(test)]
+
+use cosmwasm_std::{to_binary, Addr, CosmosMsg, Empty, StdError, WasmMsg};
+use cw3::{ProposalResponse, Status, Vote};
+use cw4::{Member, MemberResponse};
+use cw_controllers::{AdminResponse, HooksResponse};
+use cw_multi_test::{next_block, App, Contract, ContractWrapper, Executor};
+use cw_utils::{Duration, Threshold, ThresholdError};
+
+use crate::contract::{execute, instantiate, query, reply};
+use crate::msg::{ExecuteMsg, InstantiateMsg, MultisigInfo, MultisigListResponse, QueryMsg};
+use crate::ContractError;
+
+const OWNER: &str = "owner";
+const VOTER1: &str = "voter1";
+const VOTER2: &str = "voter2";
+const NEWBIE: &str = "newbie";
+
+fn member<T: Into<String>>(addr: T, weight: u64) -> Member {
+    Member {
+        addr: addr.into(),
+        weight,
+    }
+}
+
+pub fn contract_factory() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(execute, instantiate, query).with_reply(reply);
+    Box::new(contract)
+}
+
+pub fn contract_group() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        cw4_group::contract::execute,
+        cw4_group::contract::instantiate,
+        cw4_group::contract::query,
+    );
+    Box::new(contract)
+}
+
+pub fn contract_flex() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        cw3_flex_multisig::contract::execute,
+        cw3_flex_multisig::contract::instantiate,
+        cw3_flex_multisig::contract::query,
+    )
+    .with_reply(cw3_flex_multisig::contract::reply);
+    Box::new(contract)
+}
+
+// stores all codes and instantiates the factory
+fn setup_factory(app: &mut App) -> Addr {
+    let group_code_id = app.store_code(contract_group());
+    let multisig_code_id = app.store_code(contract_flex());
+    let factory_id = app.store_code(contract_factory());
+    let msg = InstantiateMsg {
+        group_code_id,
+        multisig_code_id,
+    };
+    app.instantiate_contract(
+        factory_id,
+        Addr::unchecked(OWNER),
+        &msg,
+        &[],
+        "factory",
+        None,
+    )
+    .unwrap()
+}
+
+fn create_msg(threshold: Threshold) -> ExecuteMsg {
+    ExecuteMsg::CreateMultisig {
+        label: "dao".to_string(),
+        members: vec![member(OWNER, 1), member(VOTER1, 2), member(VOTER2, 3)],
+        threshold,
+        max_voting_period: Duration::Time(3600),
+        executor: None,
+        proposal_deposit: None,
+    }
+}
+
+fn list_multisigs(app: &App, factory: &Addr) -> Vec<MultisigInfo> {
+    let msg = QueryMsg::ListMultisigs {
+        start_after: None,
+        limit: None,
+    };
+    let res: MultisigListResponse = app.wrap().query_wasm_smart(factory, &msg).unwrap();
+    res.multisigs
+}
+
+#[test]
+fn create_multisig_wires_group() {
+    let mut app = App::default();
+    let factory = setup_factory(&mut app);
+
+    let msg = create_msg(Threshold::AbsoluteCount { weight: 3 });
+    app.execute_contract(Addr::unchecked(OWNER), factory.clone(), &msg, &[])
+        .unwrap();
+
+    let multisigs = list_multisigs(&app, &factory);
+    assert_eq!(multisigs.len(), 1);
+    let multisig = Addr::unchecked(&multisigs[0].multisig);
+    let group = Addr::unchecked(&multisigs[0].group);
+
+    // the multisig controls the group and is notified of its changes
+    let admin: AdminResponse = app
+        .wrap()
+        .query_wasm_smart(&group, &cw4_group::msg::QueryMsg::Admin {})
+        .unwrap();
+    assert_eq!(admin.admin, Some(multisig.to_string()));
+    let hooks: HooksResponse = app
+        .wrap()
+        .query_wasm_smart(&group, &cw4_group::msg::QueryMsg::Hooks {})
+        .unwrap();
+    assert_eq!(hooks.hooks, vec![multisig.to_string()]);
+
+    // and can migrate both contracts
+    assert_eq!(
+        app.contract_data(&group).unwrap().admin,
+        Some(multisig.clone())
+    );
+    assert_eq!(
+        app.contract_data(&multisig).unwrap().admin,
+        Some(multisig.clone())
+    );
+
+    // members can vote through the multisig to change the group, from the next block on
+    app.update_block(next_block);
+    let update = cw4_group::msg::ExecuteMsg::UpdateMembers {
+        remove: vec![],
+        add: vec![member(NEWBIE, 1)],
+    };
+    let propose = cw3_flex_multisig::msg::ExecuteMsg::Propose {
+        title: "Welcome".to_string(),
+        description: "Add a new member".to_string(),
+        msgs: vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: group.to_string(),
+            msg: to_binary(&update).unwrap(),
+            funds: vec![],
+        })],
+        latest: None,
+        threshold_profile: None,
+        execution_window: None,
+        metadata: None,
+    };
+    app.execute_contract(Addr::unchecked(VOTER1), multisig.clone(), &propose, &[])
+        .unwrap();
+    let vote = cw3_flex_multisig::msg::ExecuteMsg::Vote {
+        proposal_id: 1,
+        vote: Vote::Yes,
+    };
+    app.execute_contract(Addr::unchecked(OWNER), multisig.clone(), &vote, &[])
+        .unwrap();
+    let proposal: ProposalResponse = app
+        .wrap()
+        .query_wasm_smart(
+            &multisig,
+            &cw3_flex_multisig::msg::QueryMsg::Proposal { proposal_id: 1 },
+        )
+        .unwrap();
+    assert_eq!(proposal.status, Status::Passed);
+
+    // executing calls back into the multisig through the hook
+    let exec = cw3_flex_multisig::msg::ExecuteMsg::Execute {
+        proposal_id: 1,
+        msg_indices: None,
+    };
+    app.execute_contract(Addr::unchecked(OWNER), multisig, &exec, &[])
+        .unwrap();
+    let newbie: MemberResponse = app
+        .wrap()
+        .query_wasm_smart(
+            &group,
+            &cw4_group::msg::QueryMsg::Member {
+                addr: NEWBIE.to_string(),
+                at_height: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(newbie.weight, Some(1));
+}
+
+#[test]
+fn invalid_threshold_creates_nothing() {
+    let mut app = App::default();
+    let factory = setup_factory(&mut app);
+
+    let msg = create_msg(Threshold::AbsoluteCount { weight: 100 });
+    let err = app
+        .execute_contract(Addr::unchecked(OWNER), factory.clone(), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        ContractError::Threshold(ThresholdError::UnreachableWeight {}),
+        err.downcast().unwrap()
+    );
+    assert_eq!(list_multisigs(&app, &factory), vec![]);
+}
+
+#[test]
+fn overflowing_weights_create_nothing() {
+    let mut app = App::default();
+    let factory = setup_factory(&mut app);
+
+    let msg = ExecuteMsg::CreateMultisig {
+        label: "dao".to_string(),
+        members: vec![member(VOTER1, u64::MAX), member(VOTER2, 1)],
+        threshold: Threshold::AbsoluteCount { weight: 1 },
+        max_voting_period: Duration::Time(3600),
+        executor: None,
+        proposal_deposit: None,
+    };
+    let err = app
+        .execute_contract(Addr::unchecked(OWNER), factory.clone(), &msg, &[])
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::Std(StdError::Overflow { .. })
+    ));
+    assert_eq!(list_multisigs(&app, &factory), vec![]);
+}
@@ -0,0 +1,17 @@
+/*!
+This is a reference factory for [cw3-flex-multisig](https://github.com/CosmWasm/cw-plus/blob/main/contracts/cw3-flex-multisig/README.md)
+deployments. It instantiates a [cw4-group](https://github.com/CosmWasm/cw-plus/blob/main/contracts/cw4-group/README.md)
+and a flex multisig voting with it in a single transaction, and wires them together:
+the multisig becomes the admin of the group and is registered as its hook.
+
+For more information on this contract, please check out the
+[README](https://github.com/CosmWasm/cw-plus/blob/main/contracts/cw3-flex-factory/README.md).
+*/
+
+pub mod contract;
+mod error;
+mod integration_tests;
+pub mod msg;
+pub mod state;
+
+pub use crate::error::ContractError;
@@ -0,0 +1,54 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cw3::UncheckedDepositInfo;
+use cw3_flex_multisig::state::Executor;
+use cw4::Member;
+use cw_utils::{Duration, Threshold};
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    /// Code id of the cw4-group contract to deploy
+    pub group_code_id: u64,
+    /// Code id of the cw3-flex-multisig contract to deploy
+    pub multisig_code_id: u64,
+}
+
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// Deploys a cw4-group with the given members, and a cw3-flex-multisig backed by it.
+    /// The multisig becomes the admin of the group, is registered as its hook, and is set
+    /// as wasm admin of both contracts.
+    CreateMultisig {
+        label: String,
+        members: Vec<Member>,
+        threshold: Threshold,
+        max_voting_period: Duration,
+        // who is able to execute passed proposals
+        // None means that anyone can execute
+        executor: Option<Executor>,
+        /// The cost of creating a proposal (if any).
+        proposal_deposit: Option<UncheckedDepositInfo>,
+    },
+}
+
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    #[returns(crate::state::Config)]
+    Config {},
+    #[returns(MultisigListResponse)]
+    ListMultisigs {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+}
+
+#[cw_serde]
+pub struct MultisigInfo {
+    pub multisig: String,
+    pub group: String,
+}
+
+#[cw_serde]
+pub struct MultisigListResponse {
+    pub multisigs: Vec<MultisigInfo>,
+}
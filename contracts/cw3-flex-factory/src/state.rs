@@ -0,0 +1,30 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::Addr;
+use cw3::UncheckedDepositInfo;
+use cw3_flex_multisig::state::Executor;
+use cw_storage_plus::{Item, Map};
+use cw_utils::{Duration, Threshold};
+
+#[cw_serde]
+pub struct Config {
+    /// Code id used to instantiate the cw4-group contracts
+    pub group_code_id: u64,
+    /// Code id used to instantiate the cw3-flex-multisig contracts
+    pub multisig_code_id: u64,
+}
+
+/// Multisig settings kept between the group instantiation and its reply
+#[cw_serde]
+pub struct PendingMultisig {
+    pub label: String,
+    pub threshold: Threshold,
+    pub max_voting_period: Duration,
+    pub executor: Option<Executor>,
+    pub proposal_deposit: Option<UncheckedDepositInfo>,
+}
+
+pub const CONFIG: Item<Config> = Item::new("config");
+pub const PENDING_MULTISIG: Item<PendingMultisig> = Item::new("pending_multisig");
+pub const PENDING_GROUP: Item<Addr> = Item::new("pending_group");
+/// Deployed multisigs, along with the group backing them
+pub const MULTISIGS: Map<&Addr, Addr> = Map::new("multisigs");
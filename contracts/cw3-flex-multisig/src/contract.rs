@@ -3,28 +3,48 @@ use std::cmp::Ordering;
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    to_binary, Binary, BlockInfo, CosmosMsg, Deps, DepsMut, Empty, Env, MessageInfo, Order,
-    Response, StdResult,
+    from_binary, to_binary, Addr, BankMsg, Binary, BlockInfo, CosmosMsg, Deps, DepsMut, Empty, Env,
+    Event, MessageInfo, Order, Reply, Response, StdError, StdResult, Storage, SubMsg, SubMsgResult,
+    Uint64, WasmMsg,
 };
 
-use cw2::set_contract_version;
+use cw2::{get_contract_version, set_contract_version};
 
+use cw20::Cw20ReceiveMsg;
 use cw3::{
-    Ballot, Proposal, ProposalListResponse, ProposalResponse, Status, Vote, VoteInfo,
-    VoteListResponse, VoteResponse, VoterDetail, VoterListResponse, VoterResponse, Votes,
+    is_valid_options, is_valid_split_vote, largest_option, vote_commitment, Ballot, CloseReward,
+    DepositError, DepositForfeit, DepositStatus, ExecutionWindow, Proposal, ProposalHookMsg,
+    ProposalKind, ProposalListResponse, ProposalMetadata, ProposalOption, ProposalResponse, Status,
+    TallyResponse, Vote, VoteCommitmentResponse, VoteInfo, VoteListResponse, VotePermit,
+    VoteResponse, VoterListResponse, VoterResponse, Votes, WeightedVoteOption, WinCondition,
+};
+use cw3_fixed_multisig::state::{
+    clear_commitments, next_id, proposal_hook_msgs, remove_proposal, status_hook_msgs, Commitment,
+    BALLOTS, OPEN_PROPOSALS, PROPOSALS, PROPOSALS_BY_PROPOSER, PROPOSAL_HOOKS,
+    PROPOSAL_HOOK_REPLY_ID, SCHEDULED_PROPOSALS, VOTE_COMMITMENTS, VOTE_PERMITS,
 };
-use cw3_fixed_multisig::state::{next_id, BALLOTS, PROPOSALS};
 use cw4::{Cw4Contract, MemberChangedHookMsg, MemberDiff};
-use cw_storage_plus::Bound;
-use cw_utils::{maybe_addr, Expiration, ThresholdResponse};
+use cw_storage_plus::{Bound, Map};
+use cw_utils::{
+    ensure_from_older_version, maybe_addr, parse_reply_execute_data, Duration, Expiration,
+    Threshold, ThresholdResponse,
+};
 
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::state::{Config, CONFIG};
+use crate::msg::{
+    ExecutableAtResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, ScheduleResponse,
+    ThresholdProfileResponse, ThresholdProfilesResponse, TimelockExecuteMsg, UncheckedProposers,
+};
+use crate::state::{
+    add_weight, Config, EmergencyTrack, Proposers, VetoConfig, WeightedGroup, CONFIG,
+    EXECUTION_TIMELOCKS, QUADRATIC_TOTAL, THRESHOLD_PROFILES, TIMELOCK_ETAS, VETO_TIMELOCKS,
+};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:cw3-flex-multisig";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+// a cw3-fixed-multisig can be migrated in place, its proposals being stored alike
+const FIXED_CONTRACT_NAME: &str = "crates.io:cw3-fixed-multisig";
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -38,13 +58,95 @@ pub fn instantiate(
             addr: msg.group_addr.clone(),
         }
     })?);
-    let total_weight = group_addr.total_weight(&deps.querier)?;
-    msg.threshold.validate(total_weight)?;
+    let mut extra_groups: Vec<WeightedGroup> = vec![];
+    for group in msg.extra_groups {
+        let addr =
+            deps.api
+                .addr_validate(&group.addr)
+                .map_err(|_| ContractError::InvalidGroup {
+                    addr: group.addr.clone(),
+                })?;
+        if addr == group_addr.0 || extra_groups.iter().any(|extra| extra.group.0 == addr) {
+            return Err(ContractError::InvalidGroup { addr: group.addr });
+        }
+        if group.multiplier == 0 {
+            return Err(ContractError::InvalidGroupMultiplier {});
+        }
+        extra_groups.push(WeightedGroup {
+            group: Cw4Contract(addr),
+            multiplier: group.multiplier,
+        });
+    }
 
     let proposal_deposit = msg
         .proposal_deposit
         .map(|deposit| deposit.into_checked(deps.as_ref()))
         .transpose()?;
+    if let Some(close_reward) = &msg.close_reward {
+        close_reward.validate(proposal_deposit.as_ref())?;
+    }
+
+    let veto = msg
+        .veto
+        .map(|veto| -> Result<_, ContractError> {
+            if !same_unit(msg.max_voting_period, veto.timelock_duration) {
+                return Err(ContractError::InvalidVetoTimelock {});
+            }
+            Ok(VetoConfig {
+                vetoer: deps.api.addr_validate(&veto.vetoer)?,
+                timelock_duration: veto.timelock_duration,
+                burn_deposit: veto.burn_deposit,
+            })
+        })
+        .transpose()?;
+
+    if let Some(execution_delay) = msg.execution_delay {
+        if !same_unit(msg.max_voting_period, execution_delay) {
+            return Err(ContractError::InvalidExecutionDelay {});
+        }
+    }
+
+    let proposers = msg
+        .proposers
+        .map(|proposers| -> Result<_, ContractError> {
+            match proposers {
+                UncheckedProposers::Only(addrs) if addrs.is_empty() => {
+                    Err(ContractError::NoProposers {})
+                }
+                UncheckedProposers::Only(addrs) => Ok(Proposers::Only(
+                    addrs
+                        .iter()
+                        .map(|addr| deps.api.addr_validate(addr))
+                        .collect::<StdResult<_>>()?,
+                )),
+                UncheckedProposers::MinWeight(min) => Ok(Proposers::MinWeight(min)),
+            }
+        })
+        .transpose()?;
+
+    let emergency = msg
+        .emergency
+        .map(|track| -> Result<_, ContractError> {
+            let track = EmergencyTrack {
+                council: track
+                    .council
+                    .iter()
+                    .map(|addr| deps.api.addr_validate(addr))
+                    .collect::<StdResult<_>>()?,
+                threshold: track.threshold,
+                voting_period: track.voting_period,
+            };
+            if track.council.is_empty() || !track.is_shorter(msg.max_voting_period) {
+                return Err(ContractError::InvalidEmergencyTrack {});
+            }
+            Ok(track)
+        })
+        .transpose()?;
+    if let Some(private_voting) = &msg.private_voting {
+        if !private_voting.is_valid(msg.max_voting_period) {
+            return Err(ContractError::InvalidPrivateVoting {});
+        }
+    }
 
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
@@ -54,7 +156,35 @@ pub fn instantiate(
         group_addr,
         executor: msg.executor,
         proposal_deposit,
+        veto,
+        execution_delay: msg.execution_delay,
+        close_reward: msg.close_reward,
+        allow_revoting: msg.allow_revoting,
+        proposers,
+        extra_groups,
+        emergency,
+        quadratic_voting: msg.quadratic_voting,
+        timelock: msg
+            .timelock
+            .map(|addr| deps.api.addr_validate(&addr))
+            .transpose()?,
+        private_voting: msg.private_voting,
     };
+    if cfg.quadratic_voting {
+        QUADRATIC_TOTAL.save(deps.storage, &cfg.count_voting_power(&deps.querier)?)?;
+    }
+    let total_weight = cfg.total_weight(deps.storage, &deps.querier)?;
+    cfg.threshold.validate(total_weight)?;
+    if let Some(track) = &cfg.emergency {
+        track.threshold.validate(total_weight)?;
+    }
+    for profile in &msg.threshold_profiles {
+        profile.threshold.validate(total_weight)?;
+        if profile.name.is_empty() || THRESHOLD_PROFILES.has(deps.storage, &profile.name) {
+            return Err(ContractError::InvalidThresholdProfile {});
+        }
+        THRESHOLD_PROFILES.save(deps.storage, &profile.name, &profile.threshold)?;
+    }
     CONFIG.save(deps.storage, &cfg)?;
 
     Ok(Response::default())
@@ -68,21 +198,199 @@ pub fn execute(
     msg: ExecuteMsg,
 ) -> Result<Response<Empty>, ContractError> {
     match msg {
-        ExecuteMsg::Propose {
+        msg @ (ExecuteMsg::Propose { .. }
+        | ExecuteMsg::ProposeSignaling { .. }
+        | ExecuteMsg::ProposeEmergency { .. }
+        | ExecuteMsg::ProposeChoice { .. }) => execute_propose_msg(deps, env, info, msg, false),
+        ExecuteMsg::Receive(wrapper) => execute_receive(deps, env, info, wrapper),
+        ExecuteMsg::Amend {
+            proposal_id,
             title,
             description,
             msgs,
-            latest,
-        } => execute_propose(deps, env, info, title, description, msgs, latest),
+        } => execute_amend(deps, env, info, proposal_id, title, description, msgs),
         ExecuteMsg::Vote { proposal_id, vote } => execute_vote(deps, env, info, proposal_id, vote),
-        ExecuteMsg::Execute { proposal_id } => execute_execute(deps, env, info, proposal_id),
+        ExecuteMsg::CommitVote {
+            proposal_id,
+            commitment,
+        } => execute_commit_vote(deps, env, info, proposal_id, commitment),
+        ExecuteMsg::RevealVote {
+            proposal_id,
+            vote,
+            salt,
+        } => execute_reveal_vote(deps, env, info, proposal_id, vote, salt),
+        ExecuteMsg::VoteWeighted {
+            proposal_id,
+            options,
+        } => execute_vote_weighted(deps, env, info, proposal_id, options),
+        ExecuteMsg::VoteChoice {
+            proposal_id,
+            option,
+        } => execute_vote_choice(deps, env, info, proposal_id, option),
+        ExecuteMsg::RelayVote {
+            ballot,
+            pubkey,
+            signature,
+        } => execute_relay_vote(deps, env, info, ballot, pubkey, signature),
+        ExecuteMsg::Execute {
+            proposal_id,
+            msg_indices,
+        } => execute_execute(deps, env, info, proposal_id, msg_indices),
+        ExecuteMsg::DispatchProposal {
+            proposal_id,
+            msg_indices,
+        } => execute_dispatch_proposal(deps, env, info, proposal_id, msg_indices),
         ExecuteMsg::Close { proposal_id } => execute_close(deps, env, info, proposal_id),
-        ExecuteMsg::MemberChangedHook(MemberChangedHookMsg { diffs }) => {
+        ExecuteMsg::Cancel { proposal_id } => execute_cancel(deps, env, info, proposal_id),
+        ExecuteMsg::CloseMany { start_after, limit } => {
+            execute_close_many(deps, env, info, start_after, limit)
+        }
+        ExecuteMsg::PruneProposals {
+            older_than,
+            start_after,
+            limit,
+        } => execute_prune_proposals(deps, env, info, older_than, start_after, limit),
+        ExecuteMsg::Veto { proposal_id } => execute_veto(deps, env, info, proposal_id),
+        ExecuteMsg::UpdateConfig {
+            threshold,
+            max_voting_period,
+            group_addr,
+        } => execute_update_config(deps, env, info, threshold, max_voting_period, group_addr),
+        ExecuteMsg::MemberChangedHook(MemberChangedHookMsg { diffs, .. }) => {
             execute_membership_hook(deps, env, info, diffs)
         }
+        ExecuteMsg::AddProposalHook { addr } => execute_add_proposal_hook(deps, env, info, addr),
+        ExecuteMsg::RemoveProposalHook { addr } => {
+            execute_remove_proposal_hook(deps, env, info, addr)
+        }
+        ExecuteMsg::TimelockExecuted { proposal_id, error } => {
+            execute_timelock_executed(deps, info, proposal_id, error)
+        }
+    }
+}
+
+/// Creates the proposal of the message, whose deposit was already paid if `deposit_paid`
+fn execute_propose_msg(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+    deposit_paid: bool,
+) -> Result<Response<Empty>, ContractError> {
+    match msg {
+        ExecuteMsg::Propose {
+            title,
+            description,
+            msgs,
+            latest,
+            threshold_profile,
+            execution_window,
+            metadata,
+        } => execute_propose(
+            deps,
+            env,
+            info,
+            title,
+            description,
+            msgs,
+            latest,
+            threshold_profile,
+            execution_window,
+            metadata,
+            vec![],
+            None,
+            deposit_paid,
+            ProposalKind::Action,
+        ),
+        ExecuteMsg::ProposeSignaling {
+            title,
+            description,
+            latest,
+            threshold_profile,
+            metadata,
+        } => execute_propose(
+            deps,
+            env,
+            info,
+            title,
+            description,
+            vec![],
+            latest,
+            threshold_profile,
+            None,
+            metadata,
+            vec![],
+            None,
+            deposit_paid,
+            ProposalKind::Signaling,
+        ),
+        ExecuteMsg::ProposeEmergency {
+            title,
+            description,
+            msgs,
+            metadata,
+        } => execute_propose(
+            deps,
+            env,
+            info,
+            title,
+            description,
+            msgs,
+            None,
+            None,
+            None,
+            metadata,
+            vec![],
+            None,
+            deposit_paid,
+            ProposalKind::Emergency,
+        ),
+        ExecuteMsg::ProposeChoice {
+            title,
+            description,
+            options,
+            win_condition,
+            latest,
+            metadata,
+        } => execute_propose(
+            deps,
+            env,
+            info,
+            title,
+            description,
+            vec![],
+            latest,
+            None,
+            None,
+            metadata,
+            options,
+            Some(win_condition),
+            deposit_paid,
+            ProposalKind::Choice,
+        ),
+        _ => Err(ContractError::NotProposal {}),
     }
 }
 
+/// Creates a proposal for the sender of the cw20 tokens, which are its deposit
+pub fn execute_receive(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    wrapper: Cw20ReceiveMsg,
+) -> Result<Response<Empty>, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    cfg.proposal_deposit
+        .ok_or(DepositError::InvalidCw20 {})?
+        .check_cw20_deposit_paid(&info.sender, wrapper.amount)?;
+    let proposer = MessageInfo {
+        sender: deps.api.addr_validate(&wrapper.sender)?,
+        funds: vec![],
+    };
+    execute_propose_msg(deps, env, proposer, from_binary(&wrapper.msg)?, true)
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn execute_propose(
     deps: DepsMut,
     env: Env,
@@ -92,27 +400,84 @@ pub fn execute_propose(
     msgs: Vec<CosmosMsg>,
     // we ignore earliest
     latest: Option<Expiration>,
+    threshold_profile: Option<String>,
+    execution_window: Option<ExecutionWindow>,
+    metadata: Option<ProposalMetadata>,
+    options: Vec<ProposalOption>,
+    win_condition: Option<WinCondition>,
+    deposit_paid: bool,
+    kind: ProposalKind,
 ) -> Result<Response<Empty>, ContractError> {
+    if let Some(metadata) = &metadata {
+        if !metadata.is_valid() {
+            return Err(ContractError::InvalidMetadata {});
+        }
+    }
+    if kind == ProposalKind::Choice
+        && !(is_valid_options(&options) && win_condition.iter().all(WinCondition::is_valid))
+    {
+        return Err(ContractError::InvalidOptions {});
+    }
     // only members of the multisig can create a proposal
     let cfg = CONFIG.load(deps.storage)?;
 
+    let threshold = match &threshold_profile {
+        Some(name) => THRESHOLD_PROFILES
+            .may_load(deps.storage, name)?
+            .ok_or_else(|| ContractError::UnknownThresholdProfile { name: name.clone() })?,
+        None => cfg.threshold.clone(),
+    };
+    let emergency = match kind {
+        ProposalKind::Emergency => Some(
+            cfg.emergency
+                .as_ref()
+                .ok_or(ContractError::NoEmergencyTrack {})?,
+        ),
+        _ => None,
+    };
+
     // Check that the native deposit was paid (as needed).
     if let Some(deposit) = cfg.proposal_deposit.as_ref() {
         deposit.check_native_deposit_paid(&info)?;
     }
 
-    // Only members of the multisig can create a proposal
+    // Only members of the multisig can create a proposal, unless restricted to some proposers
     // Non-voting members are special - they are allowed to create a proposal and
     // therefore "vote", but they aren't allowed to vote otherwise.
     // Such vote is also special, because despite having 0 weight it still counts when
     // counting threshold passing
-    let vote_power = cfg
-        .group_addr
-        .is_member(&deps.querier, &info.sender, None)?
-        .ok_or(ContractError::Unauthorized {})?;
+    // Emergency proposals are rather for members of the council, with their own voting period
+    // and threshold
+    let (vote_power, max_voting_period, threshold) = match emergency {
+        Some(track) => {
+            if !track.council.contains(&info.sender) {
+                return Err(ContractError::Unauthorized {});
+            }
+            let vote_power = cfg
+                .member_weight(&deps.querier, &info.sender, None)?
+                .ok_or(ContractError::Unauthorized {})?;
+            (vote_power, track.voting_period, track.threshold.clone())
+        }
+        None => (
+            cfg.proposer_power(&deps.querier, &info.sender)?,
+            cfg.max_voting_period,
+            threshold,
+        ),
+    };
+
+    // votes are committed before being revealed on private proposals
+    let private_voting = match kind {
+        ProposalKind::Action | ProposalKind::Signaling => cfg.private_voting.clone(),
+        _ => None,
+    };
+    // the proposer of a choice or private proposal votes like anyone else
+    let proposer_power = match (kind, &private_voting) {
+        (ProposalKind::Choice, _) | (_, Some(_)) => 0,
+        _ => vote_power,
+    };
 
     // max expires also used as default
-    let max_expires = cfg.max_voting_period.after(&env.block);
+    let max_expires = max_voting_period.after(&env.block);
     let mut expires = latest.unwrap_or(max_expires);
     let comp = expires.partial_cmp(&max_expires);
     if let Some(Ordering::Greater) = comp {
@@ -120,16 +485,35 @@ pub fn execute_propose(
     } else if comp.is_none() {
         return Err(ContractError::WrongExpiration {});
     }
-
-    // Take the cw20 token deposit, if required. We do this before
-    // creating the proposal struct below so that we can avoid a clone
-    // and move the loaded deposit info into it.
-    let take_deposit_msg = if let Some(deposit_info) = cfg.proposal_deposit.as_ref() {
-        deposit_info.get_take_deposit_messages(&info.sender, &env.contract.address)?
-    } else {
-        vec![]
+    if let Some(window) = &execution_window {
+        // windows have the unit of the voting period, to compare with timelocks
+        if !window.is_valid()
+            || window.is_over(&env.block)
+            || max_expires.partial_cmp(&window.start).is_none()
+        {
+            return Err(ContractError::InvalidExecutionWindow {});
+        }
+    }
+    // the voting period is for commitments then, followed by the reveal period
+    let commit_deadline = match &private_voting {
+        Some(private_voting) => {
+            let deadline = expires;
+            expires = (deadline + private_voting.reveal_period)?;
+            Some(deadline)
+        }
+        None => None,
     };
 
+    // Take the cw20 token deposit, if required and not sent along with the proposal. We do
+    // this before creating the proposal struct below so that we can avoid a clone and move the
+    // loaded deposit info into it.
+    let take_deposit_msg =
+        if let Some(deposit_info) = cfg.proposal_deposit.as_ref().filter(|_| !deposit_paid) {
+            deposit_info.get_take_deposit_messages(&info.sender, &env.contract.address)?
+        } else {
+            vec![]
+        };
+
     // create a proposal
     let mut prop = Proposal {
         title,
@@ -138,28 +522,165 @@ pub fn execute_propose(
         expires,
         msgs,
         status: Status::Open,
-        votes: Votes::yes(vote_power),
-        threshold: cfg.threshold,
-        total_weight: cfg.group_addr.total_weight(&deps.querier)?,
+        votes: Votes::yes(proposer_power),
+        threshold,
+        total_weight: cfg.total_weight(deps.storage, &deps.querier)?,
         proposer: info.sender.clone(),
+        deposit_status: cfg.proposal_deposit.as_ref().map(|_| DepositStatus::Held),
         deposit: cfg.proposal_deposit,
+        kind,
+        execution_error: None,
+        cast_threshold: None,
+        execution_window,
+        metadata,
+        choice_votes: vec![0; options.len()],
+        options,
+        win_condition,
+        executed_msgs: vec![],
+        commit_deadline,
     };
     prop.update_status(&env.block);
+    let deposit_msgs = prop.conclude_signaling(&env.block)?;
     let id = next_id(deps.storage)?;
+    let timelock_events = start_timelocks(
+        deps.storage,
+        cfg.veto.as_ref(),
+        cfg.execution_delay,
+        id,
+        &mut prop,
+        &env.block,
+    )?;
     PROPOSALS.save(deps.storage, id, &prop)?;
+    PROPOSALS_BY_PROPOSER.save(deps.storage, (&info.sender, id), &Empty {})?;
+    if prop.execution_window.is_some() {
+        SCHEDULED_PROPOSALS.save(deps.storage, id, &Empty {})?;
+    }
+    if prop.status == Status::Open {
+        OPEN_PROPOSALS.save(deps.storage, id, &Empty {})?;
+    }
 
     // add the first yes vote from voter
-    let ballot = Ballot {
-        weight: vote_power,
-        vote: Vote::Yes,
+    if kind != ProposalKind::Choice && private_voting.is_none() {
+        let ballot = Ballot {
+            weight: vote_power,
+            vote: Vote::Yes,
+            split: None,
+            choice: None,
+        };
+        BALLOTS.save(deps.storage, (id, &info.sender), &ballot)?;
+    }
+
+    let created = ProposalHookMsg::Created {
+        proposal_id: id,
+        proposer: info.sender.to_string(),
     };
-    BALLOTS.save(deps.storage, (id, &info.sender), &ballot)?;
+    let mut hooks = proposal_hook_msgs(deps.storage, created)?;
+    hooks.extend(status_hook_msgs(deps.storage, id, prop.status)?);
 
-    Ok(Response::new()
+    let mut res = Response::new()
         .add_messages(take_deposit_msg)
+        .add_messages(deposit_msgs)
+        .add_submessages(hooks)
+        .add_events(timelock_events)
         .add_attribute("action", "propose")
         .add_attribute("sender", info.sender)
         .add_attribute("proposal_id", id.to_string())
+        .add_attribute("status", format!("{:?}", prop.status));
+    if let Some(name) = threshold_profile {
+        res = res.add_attribute("threshold_profile", name);
+    }
+    Ok(res)
+}
+
+pub fn execute_amend(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proposal_id: u64,
+    title: Option<String>,
+    description: Option<String>,
+    msgs: Option<Vec<CosmosMsg>>,
+) -> Result<Response<Empty>, ContractError> {
+    let mut prop = PROPOSALS.load(deps.storage, proposal_id)?;
+    if prop.proposer != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    if prop.current_status(&env.block) != Status::Open {
+        return Err(ContractError::NotOpen {});
+    }
+    let others_voted = BALLOTS
+        .prefix(proposal_id)
+        .keys(deps.storage, None, None, Order::Ascending)
+        .any(|voter| !matches!(voter, Ok(voter) if voter == info.sender));
+    let committed = VOTE_COMMITMENTS
+        .prefix(proposal_id)
+        .keys(deps.storage, None, None, Order::Ascending)
+        .next()
+        .is_some();
+    if others_voted || committed {
+        return Err(ContractError::VotesCast {});
+    }
+    if msgs.is_some() && prop.kind == ProposalKind::Signaling {
+        return Err(ContractError::SignalingProposal {});
+    }
+    if msgs.is_some() && prop.kind == ProposalKind::Choice {
+        return Err(ContractError::ChoiceProposal {});
+    }
+
+    if let Some(title) = title {
+        prop.title = title;
+    }
+    if let Some(description) = description {
+        prop.description = description;
+    }
+    if let Some(msgs) = msgs {
+        prop.msgs = msgs;
+    }
+    // the proposer votes again on the amended proposal, at their current weight as when proposing
+    let previous_status = prop.status;
+    prop.votes = Votes::default();
+    BALLOTS.remove(deps.storage, (proposal_id, &info.sender));
+    let cfg = CONFIG.load(deps.storage)?;
+    if prop.kind != ProposalKind::Choice && prop.commit_deadline.is_none() {
+        let vote_power = cfg
+            .member_weight(&deps.querier, &info.sender, None)?
+            .unwrap_or_default();
+        let ballot = Ballot {
+            weight: vote_power,
+            vote: Vote::Yes,
+            split: None,
+            choice: None,
+        };
+        BALLOTS.save(deps.storage, (proposal_id, &info.sender), &ballot)?;
+        prop.votes.add_vote(Vote::Yes, vote_power);
+    }
+    prop.update_status(&env.block);
+    let deposit_msgs = prop.conclude_signaling(&env.block)?;
+    let timelock_events = start_timelocks(
+        deps.storage,
+        cfg.veto.as_ref(),
+        cfg.execution_delay,
+        proposal_id,
+        &mut prop,
+        &env.block,
+    )?;
+    PROPOSALS.save(deps.storage, proposal_id, &prop)?;
+    if prop.status != Status::Open {
+        OPEN_PROPOSALS.remove(deps.storage, proposal_id);
+    }
+    let hooks = if prop.status != previous_status {
+        status_hook_msgs(deps.storage, proposal_id, prop.status)?
+    } else {
+        vec![]
+    };
+
+    Ok(Response::new()
+        .add_messages(deposit_msgs)
+        .add_submessages(hooks)
+        .add_events(timelock_events)
+        .add_attribute("action", "amend")
+        .add_attribute("sender", info.sender)
+        .add_attribute("proposal_id", proposal_id.to_string())
         .add_attribute("status", format!("{:?}", prop.status)))
 }
 
@@ -169,14 +690,55 @@ pub fn execute_vote(
     info: MessageInfo,
     proposal_id: u64,
     vote: Vote,
+) -> Result<Response<Empty>, ContractError> {
+    cast_vote(deps, env, info, proposal_id, vote, None, false)
+}
+
+/// Votes with the weight of the sender split across options, as given by their shares
+pub fn execute_vote_weighted(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proposal_id: u64,
+    options: Vec<WeightedVoteOption>,
+) -> Result<Response<Empty>, ContractError> {
+    if !is_valid_split_vote(&options) {
+        return Err(ContractError::InvalidSplitVote {});
+    }
+    let vote = largest_option(&options).ok_or(ContractError::InvalidSplitVote {})?;
+    cast_vote(deps, env, info, proposal_id, vote, Some(options), false)
+}
+
+/// Casts the vote, which must have been `revealed` on private proposals
+fn cast_vote(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proposal_id: u64,
+    vote: Vote,
+    split: Option<Vec<WeightedVoteOption>>,
+    revealed: bool,
 ) -> Result<Response<Empty>, ContractError> {
     // only members of the multisig can vote
     let cfg = CONFIG.load(deps.storage)?;
 
     // ensure proposal exists and can be voted on
     let mut prop = PROPOSALS.load(deps.storage, proposal_id)?;
+    if prop.kind == ProposalKind::Choice {
+        return Err(ContractError::ChoiceProposal {});
+    }
+    if prop.commit_deadline.is_some() && !revealed {
+        return Err(ContractError::PrivateProposal {});
+    }
     // Allow voting on Passed and Rejected proposals too,
-    if ![Status::Open, Status::Passed, Status::Rejected].contains(&prop.status) {
+    if ![
+        Status::Open,
+        Status::Passed,
+        Status::Rejected,
+        Status::VetoTimelock,
+    ]
+    .contains(&prop.status)
+    {
         return Err(ContractError::NotOpen {});
     }
     // if they are not expired
@@ -188,2351 +750,6891 @@ pub fn execute_vote(
     // Additional check if weight >= 1
     // use a snapshot of "start of proposal"
     let vote_power = cfg
-        .group_addr
-        .is_voting_member(&deps.querier, &info.sender, prop.start_height)?
+        .voting_power(&deps.querier, &info.sender, prop.start_height)?
         .ok_or(ContractError::Unauthorized {})?;
 
-    // cast vote if no vote previously cast
-    BALLOTS.update(deps.storage, (proposal_id, &info.sender), |bal| match bal {
-        Some(_) => Err(ContractError::AlreadyVoted {}),
-        None => Ok(Ballot {
-            weight: vote_power,
-            vote,
-        }),
-    })?;
+    // cast vote if no vote previously cast, or if it can still be changed
+    let key = (proposal_id, &info.sender);
+    let previous = BALLOTS.may_load(deps.storage, key)?;
+    if let Some(ballot) = &previous {
+        if !cfg.allow_revoting || prop.status != Status::Open {
+            return Err(ContractError::AlreadyVoted {});
+        }
+        prop.votes.remove_ballot(ballot);
+    }
+    let ballot = Ballot {
+        weight: vote_power,
+        vote,
+        split: split.clone(),
+        choice: None,
+    };
+    BALLOTS.save(deps.storage, key, &ballot)?;
 
     // update vote tally
-    prop.votes.add_vote(vote, vote_power);
+    let previous_status = prop.status;
+    match &split {
+        Some(options) => prop.votes.add_split_vote(options, vote_power),
+        None => prop.votes.add_vote(vote, vote_power),
+    }
     prop.update_status(&env.block);
+    let deposit_msgs = prop.conclude_signaling(&env.block)?;
+    let timelock_events = start_timelocks(
+        deps.storage,
+        cfg.veto.as_ref(),
+        cfg.execution_delay,
+        proposal_id,
+        &mut prop,
+        &env.block,
+    )?;
     PROPOSALS.save(deps.storage, proposal_id, &prop)?;
+    if prop.status != Status::Open {
+        OPEN_PROPOSALS.remove(deps.storage, proposal_id);
+    }
+    let hooks = if prop.status != previous_status {
+        status_hook_msgs(deps.storage, proposal_id, prop.status)?
+    } else {
+        vec![]
+    };
 
     Ok(Response::new()
+        .add_messages(deposit_msgs)
+        .add_submessages(hooks)
+        .add_events(timelock_events)
+        .add_events(vote_changed_event(
+            proposal_id,
+            &info.sender,
+            previous,
+            &ballot,
+        ))
         .add_attribute("action", "vote")
         .add_attribute("sender", info.sender)
         .add_attribute("proposal_id", proposal_id.to_string())
         .add_attribute("status", format!("{:?}", prop.status)))
 }
 
-pub fn execute_execute(
+/// Records the commitment of the voter to a vote on a private proposal, holding their bond
+pub fn execute_commit_vote(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
     proposal_id: u64,
-) -> Result<Response, ContractError> {
-    let mut prop = PROPOSALS.load(deps.storage, proposal_id)?;
-    // we allow execution even after the proposal "expiration" as long as all vote come in before
-    // that point. If it was approved on time, it can be executed any time.
-    prop.update_status(&env.block);
-    if prop.status != Status::Passed {
-        return Err(ContractError::WrongExecuteStatus {});
-    }
-
+    commitment: Binary,
+) -> Result<Response<Empty>, ContractError> {
     let cfg = CONFIG.load(deps.storage)?;
-    cfg.authorize(&deps.querier, &info.sender)?;
+    let prop = PROPOSALS.load(deps.storage, proposal_id)?;
+    if prop.commit_deadline.is_none() {
+        return Err(ContractError::NotPrivateProposal {});
+    }
 
-    // set it to executed
-    prop.status = Status::Executed;
-    PROPOSALS.save(deps.storage, proposal_id, &prop)?;
+    // Only voting members of the multisig can vote, as of the start of the proposal
+    cfg.voting_power(&deps.querier, &info.sender, prop.start_height)?
+        .ok_or(ContractError::Unauthorized {})?;
 
-    // Unconditionally refund here.
-    let response = match prop.deposit {
-        Some(deposit) => {
-            Response::new().add_message(deposit.get_return_deposit_message(&prop.proposer)?)
-        }
-        None => Response::new(),
+    if prop.status != Status::Open {
+        return Err(ContractError::NotOpen {});
+    }
+    if !prop.is_committing(&env.block) {
+        return Err(ContractError::CommitmentsClosed {});
+    }
+    let key = (proposal_id, &info.sender);
+    if VOTE_COMMITMENTS.has(deps.storage, key) {
+        return Err(ContractError::AlreadyVoted {});
+    }
+    let bond = cfg.private_voting.and_then(|p| p.bond);
+    if info.funds != bond.clone().into_iter().collect::<Vec<_>>() {
+        return Err(ContractError::WrongBond {});
+    }
+    let commitment = Commitment {
+        hash: commitment,
+        bond,
     };
+    VOTE_COMMITMENTS.save(deps.storage, key, &commitment)?;
 
-    // dispatch all proposed messages
-    Ok(response
-        .add_messages(prop.msgs)
-        .add_attribute("action", "execute")
+    Ok(Response::new()
+        .add_attribute("action", "commit_vote")
         .add_attribute("sender", info.sender)
         .add_attribute("proposal_id", proposal_id.to_string()))
 }
 
-pub fn execute_close(
+/// Casts the vote the voter committed to, refunding their bond
+pub fn execute_reveal_vote(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
     proposal_id: u64,
+    vote: Vote,
+    salt: Binary,
 ) -> Result<Response<Empty>, ContractError> {
-    // anyone can trigger this if the vote passed
-
-    let mut prop = PROPOSALS.load(deps.storage, proposal_id)?;
-    if [Status::Executed, Status::Rejected, Status::Passed].contains(&prop.status) {
-        return Err(ContractError::WrongCloseStatus {});
+    let prop = PROPOSALS.load(deps.storage, proposal_id)?;
+    let key = (proposal_id, &info.sender);
+    let commitment = VOTE_COMMITMENTS
+        .may_load(deps.storage, key)?
+        .ok_or(ContractError::NoCommitment {})?;
+    if prop.is_committing(&env.block) {
+        return Err(ContractError::CommitmentsOpen {});
     }
-    // Avoid closing of Passed due to expiration proposals
-    if prop.current_status(&env.block) == Status::Passed {
-        return Err(ContractError::WrongCloseStatus {});
+    if prop.expires.is_expired(&env.block) {
+        return Err(ContractError::Expired {});
     }
-    if !prop.expires.is_expired(&env.block) {
-        return Err(ContractError::NotExpired {});
+    if vote_commitment(proposal_id, info.sender.as_str(), vote, &salt)? != commitment.hash {
+        return Err(ContractError::InvalidReveal {});
     }
+    VOTE_COMMITMENTS.remove(deps.storage, key);
 
-    // set it to failed
-    prop.status = Status::Rejected;
-    PROPOSALS.save(deps.storage, proposal_id, &prop)?;
-
-    // Refund the deposit if we have been configured to do so.
-    let mut response = Response::new();
-    if let Some(deposit) = prop.deposit {
-        if deposit.refund_failed_proposals {
-            response = response.add_message(deposit.get_return_deposit_message(&prop.proposer)?)
-        }
-    }
+    let refund = commitment.bond.map(|bond| BankMsg::Send {
+        to_address: info.sender.to_string(),
+        amount: vec![bond],
+    });
+    let res = cast_vote(deps, env, info, proposal_id, vote, None, true)?;
+    Ok(res.add_messages(refund))
+}
 
-    Ok(response
-        .add_attribute("action", "close")
-        .add_attribute("sender", info.sender)
-        .add_attribute("proposal_id", proposal_id.to_string()))
+/// Casts the vote the voter signed off-chain, for the relayer
+pub fn execute_relay_vote(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    ballot: VotePermit,
+    pubkey: Binary,
+    signature: Binary,
+) -> Result<Response<Empty>, ContractError> {
+    let voter = deps.api.addr_validate(&ballot.voter)?;
+    // the ballot must have been signed for this chain and contract
+    let ballot = VotePermit {
+        chain_id: env.block.chain_id.clone(),
+        contract: env.contract.address.to_string(),
+        ..ballot
+    };
+    VOTE_PERMITS.use_permit(
+        deps.api,
+        deps.storage,
+        &env.block,
+        &voter,
+        ballot.nonce,
+        &ballot.deadline,
+        ballot.sign_bytes()?.as_slice(),
+        &pubkey,
+        &signature,
+    )?;
+
+    let signer = MessageInfo {
+        sender: voter,
+        funds: vec![],
+    };
+    let res = cast_vote(
+        deps,
+        env,
+        signer,
+        ballot.proposal_id,
+        ballot.vote,
+        None,
+        false,
+    )?;
+    Ok(res.add_attribute("relayer", info.sender))
 }
 
-pub fn execute_membership_hook(
+/// Votes for one of the options of a choice proposal
+pub fn execute_vote_choice(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
-    _diffs: Vec<MemberDiff>,
+    proposal_id: u64,
+    option: u32,
 ) -> Result<Response<Empty>, ContractError> {
-    // This is now a no-op
-    // But we leave the authorization check as a demo
     let cfg = CONFIG.load(deps.storage)?;
-    if info.sender != cfg.group_addr.0 {
-        return Err(ContractError::Unauthorized {});
+
+    let mut prop = PROPOSALS.load(deps.storage, proposal_id)?;
+    if prop.kind != ProposalKind::Choice {
+        return Err(ContractError::NotChoiceProposal {});
+    }
+    let index = option as usize;
+    if index >= prop.options.len() {
+        return Err(ContractError::UnknownOption { option });
+    }
+    // the winning option is settled once passed, so votes are only cast while open
+    if prop.status != Status::Open {
+        return Err(ContractError::NotOpen {});
+    }
+    if prop.expires.is_expired(&env.block) {
+        return Err(ContractError::Expired {});
     }
 
-    Ok(Response::default())
-}
+    // Only voting members of the multisig can vote, as of the start of the proposal
+    let vote_power = cfg
+        .voting_power(&deps.querier, &info.sender, prop.start_height)?
+        .ok_or(ContractError::Unauthorized {})?;
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::Threshold {} => to_binary(&query_threshold(deps)?),
-        QueryMsg::Proposal { proposal_id } => to_binary(&query_proposal(deps, env, proposal_id)?),
-        QueryMsg::Vote { proposal_id, voter } => to_binary(&query_vote(deps, proposal_id, voter)?),
-        QueryMsg::ListProposals { start_after, limit } => {
-            to_binary(&list_proposals(deps, env, start_after, limit)?)
+    // cast vote if no vote previously cast, or if it can still be changed
+    let key = (proposal_id, &info.sender);
+    if let Some(previous) = BALLOTS.may_load(deps.storage, key)? {
+        if !cfg.allow_revoting {
+            return Err(ContractError::AlreadyVoted {});
         }
-        QueryMsg::ReverseProposals {
-            start_before,
-            limit,
-        } => to_binary(&reverse_proposals(deps, env, start_before, limit)?),
-        QueryMsg::ListVotes {
-            proposal_id,
-            start_after,
-            limit,
-        } => to_binary(&list_votes(deps, proposal_id, start_after, limit)?),
-        QueryMsg::Voter { address } => to_binary(&query_voter(deps, address)?),
-        QueryMsg::ListVoters { start_after, limit } => {
-            to_binary(&list_voters(deps, start_after, limit)?)
+        if let Some(choice) = previous.choice {
+            prop.choice_votes[choice as usize] -= previous.weight;
         }
-        QueryMsg::Config {} => to_binary(&query_config(deps)?),
     }
-}
+    let ballot = Ballot {
+        weight: vote_power,
+        vote: Vote::Yes,
+        split: None,
+        choice: Some(option),
+    };
+    BALLOTS.save(deps.storage, key, &ballot)?;
 
-fn query_threshold(deps: Deps) -> StdResult<ThresholdResponse> {
-    let cfg = CONFIG.load(deps.storage)?;
-    let total_weight = cfg.group_addr.total_weight(&deps.querier)?;
-    Ok(cfg.threshold.to_response(total_weight))
-}
+    // update vote tally
+    prop.choice_votes[index] += vote_power;
+    prop.update_status(&env.block);
+    let timelock_events = start_timelocks(
+        deps.storage,
+        cfg.veto.as_ref(),
+        cfg.execution_delay,
+        proposal_id,
+        &mut prop,
+        &env.block,
+    )?;
+    PROPOSALS.save(deps.storage, proposal_id, &prop)?;
+    let hooks = if prop.status != Status::Open {
+        OPEN_PROPOSALS.remove(deps.storage, proposal_id);
+        status_hook_msgs(deps.storage, proposal_id, prop.status)?
+    } else {
+        vec![]
+    };
 
-fn query_config(deps: Deps) -> StdResult<Config> {
-    CONFIG.load(deps.storage)
+    Ok(Response::new()
+        .add_submessages(hooks)
+        .add_events(timelock_events)
+        .add_attribute("action", "vote_choice")
+        .add_attribute("sender", info.sender)
+        .add_attribute("proposal_id", proposal_id.to_string())
+        .add_attribute("option", option.to_string())
+        .add_attribute("status", format!("{:?}", prop.status)))
 }
 
-fn query_proposal(deps: Deps, env: Env, id: u64) -> StdResult<ProposalResponse> {
-    let prop = PROPOSALS.load(deps.storage, id)?;
-    let status = prop.current_status(&env.block);
-    let threshold = prop.threshold.to_response(prop.total_weight);
-    Ok(ProposalResponse {
-        id,
-        title: prop.title,
-        description: prop.description,
-        msgs: prop.msgs,
-        status,
-        expires: prop.expires,
-        proposer: prop.proposer,
-        deposit: prop.deposit,
-        threshold,
+/// Tells the previous vote of a voter who changed it, if any
+fn vote_changed_event(
+    proposal_id: u64,
+    voter: &Addr,
+    previous: Option<Ballot>,
+    ballot: &Ballot,
+) -> Option<Event> {
+    previous.map(|previous| {
+        Event::new("vote_changed")
+            .add_attribute("proposal_id", proposal_id.to_string())
+            .add_attribute("voter", voter)
+            .add_attribute("previous_vote", format!("{:?}", previous.vote))
+            .add_attribute("vote", format!("{:?}", ballot.vote))
     })
 }
 
-// settings for pagination
-const MAX_LIMIT: u32 = 30;
-const DEFAULT_LIMIT: u32 = 10;
-
-fn list_proposals(
-    deps: Deps,
+pub fn execute_execute(
+    deps: DepsMut,
     env: Env,
-    start_after: Option<u64>,
-    limit: Option<u32>,
-) -> StdResult<ProposalListResponse> {
-    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
-    let start = start_after.map(Bound::exclusive);
-    let proposals = PROPOSALS
-        .range(deps.storage, start, None, Order::Ascending)
-        .take(limit)
-        .map(|p| map_proposal(&env.block, p))
-        .collect::<StdResult<_>>()?;
+    info: MessageInfo,
+    proposal_id: u64,
+    msg_indices: Option<Vec<u32>>,
+) -> Result<Response, ContractError> {
+    let prop = PROPOSALS.load(deps.storage, proposal_id)?;
+    if prop.kind == ProposalKind::Signaling {
+        return Err(ContractError::SignalingProposal {});
+    }
+    let cfg = CONFIG.load(deps.storage)?;
+    let execution_timelock = timelock_end(
+        deps.storage,
+        &EXECUTION_TIMELOCKS,
+        cfg.execution_delay,
+        proposal_id,
+        &prop,
+        &env.block,
+    )?;
+    // we allow execution even after the proposal "expiration" as long as all vote come in before
+    // that point. If it was approved on time, it can be executed any time, once it can no longer
+    // be vetoed and its execution timelock expired. It can be executed again if its messages failed.
+    let status = proposal_status(
+        deps.storage,
+        cfg.veto.as_ref(),
+        proposal_id,
+        &prop,
+        &env.block,
+    )?;
+    if status == Status::VetoTimelock {
+        return Err(ContractError::InVetoTimelock {});
+    }
+    if status != Status::Passed && status != Status::ExecutionFailed {
+        return Err(ContractError::WrongExecuteStatus {});
+    }
+    if let Some(end) = execution_timelock {
+        if !end.is_expired(&env.block) {
+            return Err(ContractError::InExecutionTimelock {});
+        }
+    }
+    if let Some(window) = &prop.execution_window {
+        if !window.is_open(&env.block) {
+            return Err(ContractError::OutsideExecutionWindow {});
+        }
+    }
 
-    Ok(ProposalListResponse { proposals })
+    cfg.authorize(&deps.querier, &info.sender)?;
+
+    // with a timelock contract, queue the messages there instead, replying with when they run
+    if let Some(timelock) = cfg.timelock {
+        if msg_indices.is_some() {
+            return Err(ContractError::PartialQueue {});
+        }
+        let schedule = WasmMsg::Execute {
+            contract_addr: timelock.to_string(),
+            msg: to_binary(&TimelockExecuteMsg::Schedule {
+                proposal_id,
+                msgs: prop.msgs_to_execute(),
+            })?,
+            funds: vec![],
+        };
+        return Ok(Response::new()
+            .add_submessage(SubMsg::reply_always(schedule, proposal_id))
+            .add_attribute("action", "execute")
+            .add_attribute("sender", info.sender)
+            .add_attribute("proposal_id", proposal_id.to_string())
+            .add_attribute("timelock", timelock));
+    }
+
+    if prop.msg_indices_to_execute(msg_indices.clone()).is_none() {
+        return Err(ContractError::InvalidMsgIndices {});
+    }
+
+    // dispatch the proposed messages through the multisig itself, so that their failure is
+    // recorded instead of reverting the execution
+    let dispatch = WasmMsg::Execute {
+        contract_addr: env.contract.address.to_string(),
+        msg: to_binary(&ExecuteMsg::DispatchProposal {
+            proposal_id,
+            msg_indices,
+        })?,
+        funds: vec![],
+    };
+    Ok(Response::new()
+        .add_submessage(SubMsg::reply_on_error(dispatch, proposal_id))
+        .add_attribute("action", "execute")
+        .add_attribute("sender", info.sender)
+        .add_attribute("proposal_id", proposal_id.to_string()))
 }
 
-fn reverse_proposals(
-    deps: Deps,
+pub fn execute_dispatch_proposal(
+    deps: DepsMut,
     env: Env,
-    start_before: Option<u64>,
-    limit: Option<u32>,
-) -> StdResult<ProposalListResponse> {
-    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
-    let end = start_before.map(Bound::exclusive);
-    let props: StdResult<Vec<_>> = PROPOSALS
-        .range(deps.storage, None, end, Order::Descending)
-        .take(limit)
-        .map(|p| map_proposal(&env.block, p))
+    info: MessageInfo,
+    proposal_id: u64,
+    msg_indices: Option<Vec<u32>>,
+) -> Result<Response, ContractError> {
+    if info.sender != env.contract.address {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut prop = PROPOSALS.load(deps.storage, proposal_id)?;
+    let msg_indices = prop
+        .msg_indices_to_execute(msg_indices)
+        .ok_or(ContractError::InvalidMsgIndices {})?;
+    let msgs = prop.msgs_to_execute();
+    let msgs: Vec<_> = msg_indices
+        .iter()
+        .map(|index| msgs[*index as usize].clone())
         .collect();
+    let executed = msg_indices
+        .iter()
+        .map(u32::to_string)
+        .collect::<Vec<_>>()
+        .join(",");
+    prop.executed_msgs.extend(msg_indices);
+    prop.executed_msgs.sort_unstable();
+
+    // it stays passed until all its messages are executed
+    if !prop.all_msgs_executed() {
+        prop.status = Status::Passed;
+        prop.execution_error = None;
+        PROPOSALS.save(deps.storage, proposal_id, &prop)?;
+        return Ok(Response::new()
+            .add_messages(msgs)
+            .add_attribute("action", "dispatch_proposal")
+            .add_attribute("proposal_id", proposal_id.to_string())
+            .add_attribute("msg_indices", executed));
+    }
 
-    Ok(ProposalListResponse { proposals: props? })
-}
+    // set it to executed, refunding the deposit if configured to do so
+    prop.status = Status::Executed;
+    prop.execution_error = None;
+    let deposit_msgs = prop.settle_deposit(true)?;
+    PROPOSALS.save(deps.storage, proposal_id, &prop)?;
+    VETO_TIMELOCKS.remove(deps.storage, proposal_id);
+    EXECUTION_TIMELOCKS.remove(deps.storage, proposal_id);
+    SCHEDULED_PROPOSALS.remove(deps.storage, proposal_id);
+    OPEN_PROPOSALS.remove(deps.storage, proposal_id);
+    let refunds = clear_commitments(deps.storage, &env.block, proposal_id, &prop)?;
+    let hooks = status_hook_msgs(deps.storage, proposal_id, prop.status)?;
 
-fn map_proposal(
-    block: &BlockInfo,
-    item: StdResult<(u64, Proposal)>,
-) -> StdResult<ProposalResponse> {
-    item.map(|(id, prop)| {
-        let status = prop.current_status(block);
-        let threshold = prop.threshold.to_response(prop.total_weight);
-        ProposalResponse {
-            id,
-            title: prop.title,
-            description: prop.description,
-            msgs: prop.msgs,
-            status,
-            expires: prop.expires,
-            deposit: prop.deposit,
-            proposer: prop.proposer,
-            threshold,
-        }
-    })
+    Ok(Response::new()
+        .add_messages(deposit_msgs)
+        .add_messages(refunds)
+        .add_messages(msgs)
+        .add_submessages(hooks)
+        .add_attribute("action", "dispatch_proposal")
+        .add_attribute("proposal_id", proposal_id.to_string()))
 }
 
-fn query_vote(deps: Deps, proposal_id: u64, voter: String) -> StdResult<VoteResponse> {
-    let voter_addr = deps.api.addr_validate(&voter)?;
-    let prop = BALLOTS.may_load(deps.storage, (proposal_id, &voter_addr))?;
-    let vote = prop.map(|b| VoteInfo {
-        proposal_id,
-        voter,
-        vote: b.vote,
-        weight: b.weight,
-    });
-    Ok(VoteResponse { vote })
+/// Records the messages of the proposal as queued in the timelock contract, until it tells
+/// how their execution went
+fn proposal_queued(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    let proposal_id = msg.id;
+    let data = parse_reply_execute_data(msg)?
+        .data
+        .ok_or(ContractError::NoTimelockEta {})?;
+    let ScheduleResponse { eta } = from_binary(&data)?;
+
+    // the proposal passed, so its deposit is settled as for executed ones
+    let mut prop = PROPOSALS.load(deps.storage, proposal_id)?;
+    prop.status = Status::Queued;
+    prop.execution_error = None;
+    let deposit_msgs = prop.settle_deposit(true)?;
+    PROPOSALS.save(deps.storage, proposal_id, &prop)?;
+    TIMELOCK_ETAS.save(deps.storage, proposal_id, &eta)?;
+    VETO_TIMELOCKS.remove(deps.storage, proposal_id);
+    EXECUTION_TIMELOCKS.remove(deps.storage, proposal_id);
+    SCHEDULED_PROPOSALS.remove(deps.storage, proposal_id);
+    OPEN_PROPOSALS.remove(deps.storage, proposal_id);
+    let refunds = clear_commitments(deps.storage, &env.block, proposal_id, &prop)?;
+
+    Ok(Response::new()
+        .add_messages(deposit_msgs)
+        .add_messages(refunds)
+        .add_attribute("action", "queue_proposal")
+        .add_attribute("proposal_id", proposal_id.to_string())
+        .add_attribute("eta", eta.to_string()))
 }
 
-fn list_votes(
-    deps: Deps,
+pub fn execute_timelock_executed(
+    deps: DepsMut,
+    info: MessageInfo,
     proposal_id: u64,
-    start_after: Option<String>,
-    limit: Option<u32>,
-) -> StdResult<VoteListResponse> {
-    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
-    let addr = maybe_addr(deps.api, start_after)?;
-    let start = addr.as_ref().map(Bound::exclusive);
+    error: Option<String>,
+) -> Result<Response<Empty>, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    if cfg.timelock.as_ref() != Some(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    let mut prop = PROPOSALS.load(deps.storage, proposal_id)?;
+    if prop.status != Status::Queued {
+        return Err(ContractError::NotQueued {});
+    }
 
-    let votes = BALLOTS
-        .prefix(proposal_id)
-        .range(deps.storage, start, None, Order::Ascending)
-        .take(limit)
-        .map(|item| {
-            item.map(|(addr, ballot)| VoteInfo {
-                proposal_id,
-                voter: addr.into(),
-                vote: ballot.vote,
-                weight: ballot.weight,
-            })
-        })
-        .collect::<StdResult<_>>()?;
+    // failed messages can be queued again, executing the proposal again
+    prop.status = match error {
+        Some(_) => Status::ExecutionFailed,
+        None => Status::Executed,
+    };
+    prop.execution_error = error;
+    PROPOSALS.save(deps.storage, proposal_id, &prop)?;
+    TIMELOCK_ETAS.remove(deps.storage, proposal_id);
+    let hooks = status_hook_msgs(deps.storage, proposal_id, prop.status)?;
 
-    Ok(VoteListResponse { votes })
+    Ok(Response::new()
+        .add_submessages(hooks)
+        .add_attribute("action", "timelock_executed")
+        .add_attribute("proposal_id", proposal_id.to_string())
+        .add_attribute("status", format!("{:?}", prop.status)))
 }
 
-fn query_voter(deps: Deps, voter: String) -> StdResult<VoterResponse> {
+pub fn execute_close(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proposal_id: u64,
+) -> Result<Response<Empty>, ContractError> {
+    // anyone can trigger this if the vote passed
+
     let cfg = CONFIG.load(deps.storage)?;
-    let voter_addr = deps.api.addr_validate(&voter)?;
-    let weight = cfg.group_addr.is_member(&deps.querier, &voter_addr, None)?;
+    let prop = PROPOSALS.load(deps.storage, proposal_id)?;
+    check_closable(&prop, &env.block)?;
+
+    let (mut msgs, hooks) = close_proposal(
+        deps.storage,
+        &env.block,
+        &cfg,
+        &info.sender,
+        proposal_id,
+        prop,
+    )?;
+    msgs.extend(treasury_reward(
+        deps.as_ref(),
+        &env,
+        &cfg,
+        &info.sender,
+        1,
+        &msgs,
+    )?);
 
-    Ok(VoterResponse { weight })
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_submessages(hooks)
+        .add_attribute("action", "close")
+        .add_attribute("sender", info.sender)
+        .add_attribute("proposal_id", proposal_id.to_string()))
 }
 
-fn list_voters(
-    deps: Deps,
-    start_after: Option<String>,
-    limit: Option<u32>,
-) -> StdResult<VoterListResponse> {
+pub fn execute_cancel(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proposal_id: u64,
+) -> Result<Response<Empty>, ContractError> {
     let cfg = CONFIG.load(deps.storage)?;
-    let voters = cfg
-        .group_addr
-        .list_members(&deps.querier, start_after, limit)?
-        .into_iter()
-        .map(|member| VoterDetail {
-            addr: member.addr,
-            weight: member.weight,
-        })
-        .collect();
-    Ok(VoterListResponse { voters })
-}
+    let mut prop = PROPOSALS.load(deps.storage, proposal_id)?;
+    let status = proposal_status(
+        deps.storage,
+        cfg.veto.as_ref(),
+        proposal_id,
+        &prop,
+        &env.block,
+    )?;
+    let pending = [
+        Status::Pending,
+        Status::Open,
+        Status::Passed,
+        Status::VetoTimelock,
+        Status::ExecutionFailed,
+    ];
+    if !pending.contains(&status)
+        || (prop.kind == ProposalKind::Signaling && status == Status::Passed)
+    {
+        return Err(ContractError::WrongCancelStatus {});
+    }
 
-#[cfg(test)]
-mod tests {
-    use cosmwasm_std::{coin, coins, Addr, BankMsg, Coin, Decimal, Timestamp, Uint128};
-
-    use cw2::{query_contract_info, ContractVersion};
-    use cw20::{Cw20Coin, UncheckedDenom};
-    use cw3::{DepositError, UncheckedDepositInfo};
-    use cw4::{Cw4ExecuteMsg, Member};
-    use cw4_group::helpers::Cw4GroupContract;
-    use cw_multi_test::{
-        next_block, App, AppBuilder, BankSudo, Contract, ContractWrapper, Executor, SudoMsg,
+    // the vetoer can cancel it at any time, burning the deposit if configured to do so, while
+    // the proposer can only withdraw it until anybody votes against it
+    prop.status = Status::Cancelled;
+    let deposit_msgs = match cfg.veto {
+        Some(veto) if veto.vetoer == info.sender => match veto.burn_deposit {
+            true => prop.forfeit_deposit(DepositForfeit::Burn {})?,
+            false => prop.settle_deposit(false)?,
+        },
+        _ if prop.proposer == info.sender => {
+            if prop.has_opposing_votes() {
+                return Err(ContractError::OpposedProposal {});
+            }
+            prop.settle_deposit(false)?
+        }
+        _ => return Err(ContractError::Unauthorized {}),
     };
-    use cw_utils::{Duration, Threshold};
+    PROPOSALS.save(deps.storage, proposal_id, &prop)?;
+    VETO_TIMELOCKS.remove(deps.storage, proposal_id);
+    EXECUTION_TIMELOCKS.remove(deps.storage, proposal_id);
+    SCHEDULED_PROPOSALS.remove(deps.storage, proposal_id);
+    OPEN_PROPOSALS.remove(deps.storage, proposal_id);
+    let refunds = clear_commitments(deps.storage, &env.block, proposal_id, &prop)?;
+    let hooks = status_hook_msgs(deps.storage, proposal_id, prop.status)?;
 
-    use super::*;
+    Ok(Response::new()
+        .add_messages(deposit_msgs)
+        .add_messages(refunds)
+        .add_submessages(hooks)
+        .add_attribute("action", "cancel")
+        .add_attribute("sender", info.sender)
+        .add_attribute("proposal_id", proposal_id.to_string()))
+}
 
-    const OWNER: &str = "admin0001";
-    const VOTER1: &str = "voter0001";
-    const VOTER2: &str = "voter0002";
-    const VOTER3: &str = "voter0003";
-    const VOTER4: &str = "voter0004";
-    const VOTER5: &str = "voter0005";
-    const SOMEBODY: &str = "somebody";
+pub fn execute_close_many(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> Result<Response<Empty>, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    // only `limit` proposals are read, closable or not, so the gas does not grow with the history
+    let proposals = PROPOSALS
+        .range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    let last_read = proposals.last().map(|(id, _)| *id);
+    let read = proposals.len();
+    let closable: Vec<_> = proposals
+        .into_iter()
+        .filter(|(_, prop)| check_closable(prop, &env.block).is_ok())
+        .collect();
+    // a full batch may be followed by closable proposals
+    let last_read = match last_read {
+        Some(id) if !closable.is_empty() || read == limit => id,
+        _ => return Err(ContractError::NothingToClose {}),
+    };
 
-    fn member<T: Into<String>>(addr: T, weight: u64) -> Member {
-        Member {
-            addr: addr.into(),
-            weight,
-        }
+    let mut msgs = vec![];
+    let mut hooks = vec![];
+    let mut ids = vec![];
+    for (id, prop) in closable {
+        let (close_msgs, close_hooks) =
+            close_proposal(deps.storage, &env.block, &cfg, &info.sender, id, prop)?;
+        msgs.extend(close_msgs);
+        hooks.extend(close_hooks);
+        ids.push(id.to_string());
     }
-
-    pub fn contract_flex() -> Box<dyn Contract<Empty>> {
-        let contract = ContractWrapper::new(
-            crate::contract::execute,
-            crate::contract::instantiate,
-            crate::contract::query,
-        );
-        Box::new(contract)
+    let closed = ids.len() as u128;
+    if closed > 0 {
+        msgs.extend(treasury_reward(
+            deps.as_ref(),
+            &env,
+            &cfg,
+            &info.sender,
+            closed,
+            &msgs,
+        )?);
     }
 
-    pub fn contract_group() -> Box<dyn Contract<Empty>> {
-        let contract = ContractWrapper::new(
-            cw4_group::contract::execute,
-            cw4_group::contract::instantiate,
-            cw4_group::contract::query,
-        );
-        Box::new(contract)
-    }
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_submessages(hooks)
+        .add_attribute("action", "close_many")
+        .add_attribute("sender", info.sender)
+        .add_attribute("proposal_ids", ids.join(","))
+        .add_attribute("last_proposal_id", last_read.to_string()))
+}
 
-    fn contract_cw20() -> Box<dyn Contract<Empty>> {
-        let contract = ContractWrapper::new(
-            cw20_base::contract::execute,
-            cw20_base::contract::instantiate,
-            cw20_base::contract::query,
-        );
-        Box::new(contract)
+/// Removes old proposals to reclaim their storage. Anyone can do so, as only proposals that
+/// are over, with their deposit settled, are removed.
+pub fn execute_prune_proposals(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    older_than: Duration,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> Result<Response<Empty>, ContractError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    // only `limit` proposals are read, prunable or not, as for `execute_close_many`
+    let proposals = PROPOSALS
+        .range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    let last_read = proposals.last().map(|(id, _)| *id);
+    let read = proposals.len();
+    let prunable: Vec<_> = proposals
+        .into_iter()
+        .filter(|(_, prop)| is_prunable(prop, older_than, &env.block))
+        .collect();
+    let last_read = match last_read {
+        Some(id) if !prunable.is_empty() || read == limit => id,
+        _ => return Err(ContractError::NothingToPrune {}),
+    };
+
+    let mut ids = vec![];
+    for (id, prop) in prunable {
+        remove_proposal(deps.storage, id, &prop)?;
+        VETO_TIMELOCKS.remove(deps.storage, id);
+        EXECUTION_TIMELOCKS.remove(deps.storage, id);
+        TIMELOCK_ETAS.remove(deps.storage, id);
+        ids.push(id.to_string());
     }
 
-    fn mock_app(init_funds: &[Coin]) -> App {
-        AppBuilder::new().build(|router, _, storage| {
-            router
-                .bank
-                .init_balance(storage, &Addr::unchecked(OWNER), init_funds.to_vec())
-                .unwrap();
-        })
+    Ok(Response::new()
+        .add_attribute("action", "prune_proposals")
+        .add_attribute("sender", info.sender)
+        .add_attribute("proposal_ids", ids.join(","))
+        .add_attribute("last_proposal_id", last_read.to_string()))
+}
+
+/// Whether the proposal was executed, rejected, vetoed or cancelled, with its deposit settled,
+/// and expired `older_than` ago. Never if `older_than` is not in the unit of its expiration
+fn is_prunable(prop: &Proposal, older_than: Duration, block: &BlockInfo) -> bool {
+    [
+        Status::Executed,
+        Status::Rejected,
+        Status::Vetoed,
+        Status::Cancelled,
+    ]
+    .contains(&prop.status)
+        && prop.deposit_status != Some(DepositStatus::Held)
+        && matches!(prop.expires + older_than, Ok(end) if end.is_expired(block))
+}
+
+fn check_closable(prop: &Proposal, block: &BlockInfo) -> Result<(), ContractError> {
+    // Proposals rejected by votes can be closed once, to settle their deposit,
+    // and so can signaling proposals that passed on expiration
+    let concluded = prop.status == Status::Rejected
+        || (prop.kind == ProposalKind::Signaling && prop.current_status(block) == Status::Passed);
+    if concluded && prop.deposit_status == Some(DepositStatus::Held) {
+        return Ok(());
     }
+    // Passed proposals can be closed once their execution window is over too
+    let missed = matches!(&prop.execution_window, Some(window) if window.is_over(block));
+    let status = prop.current_status(block);
+    let passed = [
+        Status::Passed,
+        Status::VetoTimelock,
+        Status::ExecutionFailed,
+    ];
+    if missed && passed.contains(&status) {
+        return Ok(());
+    }
+    if [
+        Status::Executed,
+        Status::Rejected,
+        Status::Passed,
+        Status::VetoTimelock,
+        Status::Vetoed,
+        Status::Queued,
+        Status::Cancelled,
+    ]
+    .contains(&prop.status)
+    {
+        return Err(ContractError::WrongCloseStatus {});
+    }
+    // Avoid closing of Passed due to expiration proposals
+    if prop.current_status(block) == Status::Passed {
+        return Err(ContractError::WrongCloseStatus {});
+    }
+    if !prop.expires.is_expired(block) {
+        return Err(ContractError::NotExpired {});
+    }
+    Ok(())
+}
 
-    // uploads code and returns address of group contract
-    fn instantiate_group(app: &mut App, members: Vec<Member>) -> Addr {
-        let group_id = app.store_code(contract_group());
-        let msg = cw4_group::msg::InstantiateMsg {
-            admin: Some(OWNER.into()),
-            members,
+/// Sets the proposal to failed, paying the closing reward out of its deposit and refunding
+/// the rest if configured to do so. Passed signaling proposals only have their deposit settled
+fn close_proposal(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    cfg: &Config,
+    closer: &Addr,
+    proposal_id: u64,
+    mut prop: Proposal,
+) -> StdResult<(Vec<CosmosMsg>, Vec<SubMsg>)> {
+    let previous_status = prop.status;
+    let mut msgs = prop.conclude_signaling(block)?;
+    OPEN_PROPOSALS.remove(storage, proposal_id);
+    let refunds = clear_commitments(storage, block, proposal_id, &prop)?;
+    msgs.extend(refunds.into_iter().map(CosmosMsg::from));
+    if prop.kind == ProposalKind::Signaling && prop.status == Status::Passed {
+        PROPOSALS.save(storage, proposal_id, &prop)?;
+        let hooks = if previous_status != Status::Passed {
+            status_hook_msgs(storage, proposal_id, prop.status)?
+        } else {
+            vec![]
         };
-        app.instantiate_contract(group_id, Addr::unchecked(OWNER), &msg, &[], "group", None)
-            .unwrap()
+        return Ok((msgs, hooks));
     }
+    prop.status = Status::Rejected;
+    SCHEDULED_PROPOSALS.remove(storage, proposal_id);
+    let reward = cfg
+        .close_reward
+        .as_ref()
+        .map(CloseReward::deposit_share)
+        .unwrap_or_default();
+    msgs.extend(prop.settle_deposit_with_reward(closer, reward)?);
+    PROPOSALS.save(storage, proposal_id, &prop)?;
+    let hooks = if previous_status != Status::Rejected {
+        status_hook_msgs(storage, proposal_id, prop.status)?
+    } else {
+        vec![]
+    };
+    Ok((msgs, hooks))
+}
 
-    #[track_caller]
-    fn instantiate_flex(
-        app: &mut App,
-        group: Addr,
-        threshold: Threshold,
-        max_voting_period: Duration,
-        executor: Option<crate::state::Executor>,
-        proposal_deposit: Option<UncheckedDepositInfo>,
-    ) -> Addr {
-        let flex_id = app.store_code(contract_flex());
-        let msg = crate::msg::InstantiateMsg {
-            group_addr: group.to_string(),
-            threshold,
-            max_voting_period,
-            executor,
-            proposal_deposit,
-        };
-        app.instantiate_contract(flex_id, Addr::unchecked(OWNER), &msg, &[], "flex", None)
-            .unwrap()
+/// The reward for closing `closed` proposals paid by the multisig, if configured so
+fn treasury_reward(
+    deps: Deps,
+    env: &Env,
+    cfg: &Config,
+    closer: &Addr,
+    closed: u128,
+    outgoing: &[CosmosMsg],
+) -> StdResult<Option<CosmosMsg>> {
+    match &cfg.close_reward {
+        Some(reward) => reward.get_treasury_reward_message(
+            &deps.querier,
+            &env.contract.address,
+            closer,
+            closed,
+            outgoing,
+        ),
+        _ => Ok(None),
     }
+}
 
-    // this will set up both contracts, instantiating the group with
-    // all voters defined above, and the multisig pointing to it and given threshold criteria.
-    // Returns (multisig address, group address).
-    #[track_caller]
-    fn setup_test_case_fixed(
-        app: &mut App,
-        weight_needed: u64,
-        max_voting_period: Duration,
-        init_funds: Vec<Coin>,
-        multisig_as_group_admin: bool,
-    ) -> (Addr, Addr) {
-        setup_test_case(
-            app,
-            Threshold::AbsoluteCount {
-                weight: weight_needed,
-            },
-            max_voting_period,
-            init_funds,
-            multisig_as_group_admin,
-            None,
-            None,
-        )
+pub fn execute_veto(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proposal_id: u64,
+) -> Result<Response<Empty>, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let veto = match cfg.veto {
+        Some(veto) if veto.vetoer == info.sender => veto,
+        _ => return Err(ContractError::Unauthorized {}),
+    };
+
+    let mut prop = PROPOSALS.load(deps.storage, proposal_id)?;
+    let status = proposal_status(deps.storage, Some(&veto), proposal_id, &prop, &env.block)?;
+    if status != Status::VetoTimelock {
+        return Err(ContractError::WrongVetoStatus {});
     }
 
-    #[track_caller]
-    fn setup_test_case(
-        app: &mut App,
-        threshold: Threshold,
-        max_voting_period: Duration,
-        init_funds: Vec<Coin>,
-        multisig_as_group_admin: bool,
-        executor: Option<crate::state::Executor>,
-        proposal_deposit: Option<UncheckedDepositInfo>,
-    ) -> (Addr, Addr) {
-        // 1. Instantiate group contract with members (and OWNER as admin)
-        let members = vec![
-            member(OWNER, 0),
-            member(VOTER1, 1),
-            member(VOTER2, 2),
-            member(VOTER3, 3),
-            member(VOTER4, 12), // so that he alone can pass a 50 / 52% threshold proposal
-            member(VOTER5, 5),
-        ];
-        let group_addr = instantiate_group(app, members);
-        app.update_block(next_block);
+    // set it to vetoed, burning the deposit if configured to do so
+    prop.status = Status::Vetoed;
+    let deposit_msgs = match veto.burn_deposit {
+        true => prop.forfeit_deposit(DepositForfeit::Burn {})?,
+        false => prop.settle_deposit(false)?,
+    };
+    PROPOSALS.save(deps.storage, proposal_id, &prop)?;
+    VETO_TIMELOCKS.remove(deps.storage, proposal_id);
+    EXECUTION_TIMELOCKS.remove(deps.storage, proposal_id);
+    SCHEDULED_PROPOSALS.remove(deps.storage, proposal_id);
+    OPEN_PROPOSALS.remove(deps.storage, proposal_id);
+    let refunds = clear_commitments(deps.storage, &env.block, proposal_id, &prop)?;
+    let hooks = status_hook_msgs(deps.storage, proposal_id, prop.status)?;
 
-        // 2. Set up Multisig backed by this group
-        let flex_addr = instantiate_flex(
-            app,
-            group_addr.clone(),
-            threshold,
-            max_voting_period,
-            executor,
-            proposal_deposit,
-        );
-        app.update_block(next_block);
+    Ok(Response::new()
+        .add_messages(deposit_msgs)
+        .add_messages(refunds)
+        .add_submessages(hooks)
+        .add_attribute("action", "veto")
+        .add_attribute("sender", info.sender)
+        .add_attribute("proposal_id", proposal_id.to_string()))
+}
 
-        // 3. (Optional) Set the multisig as the group owner
-        if multisig_as_group_admin {
-            let update_admin = Cw4ExecuteMsg::UpdateAdmin {
-                admin: Some(flex_addr.to_string()),
-            };
-            app.execute_contract(
-                Addr::unchecked(OWNER),
-                group_addr.clone(),
-                &update_admin,
-                &[],
-            )
-            .unwrap();
-            app.update_block(next_block);
+/// Opens the veto window and the execution timelock of a proposal that just passed by votes,
+/// if enabled. Returns the event announcing the execution timelock
+fn start_timelocks(
+    storage: &mut dyn Storage,
+    veto: Option<&VetoConfig>,
+    execution_delay: Option<Duration>,
+    id: u64,
+    prop: &mut Proposal,
+    block: &BlockInfo,
+) -> StdResult<Vec<Event>> {
+    // signaling proposals have nothing to execute, so passing is final
+    if prop.status != Status::Passed || prop.kind == ProposalKind::Signaling {
+        return Ok(vec![]);
+    }
+    if let Some(veto) = veto {
+        prop.status = Status::VetoTimelock;
+        VETO_TIMELOCKS.save(storage, id, &veto.timelock_duration.after(block))?;
+    }
+    match execution_delay {
+        Some(execution_delay) => {
+            let end = execution_delay.after(block);
+            EXECUTION_TIMELOCKS.save(storage, id, &end)?;
+            Ok(vec![Event::new("execution_timelock")
+                .add_attribute("proposal_id", id.to_string())
+                .add_attribute("executable_at", end.to_string())])
         }
+        None => Ok(vec![]),
+    }
+}
 
-        // Bonus: set some funds on the multisig contract for future proposals
-        if !init_funds.is_empty() {
-            app.send_tokens(Addr::unchecked(OWNER), flex_addr.clone(), &init_funds)
-                .unwrap();
+/// The end of a timelock of `duration` after the proposal passed, if it did: recorded in
+/// `timelocks` if it passed by votes, or counted from its expiration if it passed then
+fn timelock_end(
+    storage: &dyn Storage,
+    timelocks: &Map<u64, Expiration>,
+    duration: Option<Duration>,
+    id: u64,
+    prop: &Proposal,
+    block: &BlockInfo,
+) -> StdResult<Option<Expiration>> {
+    if let Some(end) = timelocks.may_load(storage, id)? {
+        return Ok(Some(end));
+    }
+    match duration {
+        Some(duration)
+            if prop.status == Status::Open && prop.current_status(block) == Status::Passed =>
+        {
+            Ok(Some((prop.expires + duration)?))
         }
-        (flex_addr, group_addr)
+        _ => Ok(None),
     }
+}
 
-    fn proposal_info() -> (Vec<CosmosMsg<Empty>>, String, String) {
-        let bank_msg = BankMsg::Send {
-            to_address: SOMEBODY.into(),
-            amount: coins(1, "BTC"),
-        };
-        let msgs = vec![bank_msg.into()];
-        let title = "Pay somebody".to_string();
-        let description = "Do I pay her?".to_string();
-        (msgs, title, description)
+/// The current status of the proposal, accounting for the veto window after it passed
+fn proposal_status(
+    storage: &dyn Storage,
+    veto: Option<&VetoConfig>,
+    id: u64,
+    prop: &Proposal,
+    block: &BlockInfo,
+) -> StdResult<Status> {
+    let status = prop.current_status(block);
+    if ![Status::Passed, Status::VetoTimelock].contains(&status)
+        || prop.kind == ProposalKind::Signaling
+    {
+        return Ok(status);
     }
-
-    fn pay_somebody_proposal() -> ExecuteMsg {
-        let (msgs, title, description) = proposal_info();
-        ExecuteMsg::Propose {
-            title,
-            description,
-            msgs,
-            latest: None,
-        }
+    let veto_duration = veto.map(|veto| veto.timelock_duration);
+    match timelock_end(storage, &VETO_TIMELOCKS, veto_duration, id, prop, block)? {
+        Some(end) if !end.is_expired(block) => Ok(Status::VetoTimelock),
+        _ => Ok(Status::Passed),
     }
+}
 
-    fn text_proposal() -> ExecuteMsg {
+/// When the proposal can be executed, if it passed but is still timelocked
+fn query_executable_at(deps: Deps, env: Env, id: u64) -> StdResult<ExecutableAtResponse> {
+    let prop = PROPOSALS.load(deps.storage, id)?;
+    let cfg = CONFIG.load(deps.storage)?;
+    let status = proposal_status(deps.storage, cfg.veto.as_ref(), id, &prop, &env.block)?;
+    // queued proposals are executed by the timelock contract
+    if status == Status::Queued {
+        let executable_at = TIMELOCK_ETAS
+            .may_load(deps.storage, id)?
+            .filter(|eta| !eta.is_expired(&env.block));
+        return Ok(ExecutableAtResponse { executable_at });
+    }
+    if ![Status::Passed, Status::VetoTimelock].contains(&status)
+        || prop.kind == ProposalKind::Signaling
+    {
+        return Ok(ExecutableAtResponse {
+            executable_at: None,
+        });
+    }
+    let veto_duration = cfg.veto.map(|veto| veto.timelock_duration);
+    let ends = [
+        timelock_end(
+            deps.storage,
+            &VETO_TIMELOCKS,
+            veto_duration,
+            id,
+            &prop,
+            &env.block,
+        )?,
+        timelock_end(
+            deps.storage,
+            &EXECUTION_TIMELOCKS,
+            cfg.execution_delay,
+            id,
+            &prop,
+            &env.block,
+        )?,
+        prop.execution_window.map(|window| window.start),
+    ];
+    // all have the unit of the voting period, so they compare
+    let executable_at = ends
+        .into_iter()
+        .flatten()
+        .filter(|end| !end.is_expired(&env.block))
+        .reduce(|a, b| if b > a { b } else { a });
+    Ok(ExecutableAtResponse { executable_at })
+}
+
+/// Registers a contract told of the lifecycle of proposals. Only the multisig itself can do so,
+/// executing a passed proposal.
+pub fn execute_add_proposal_hook(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    addr: String,
+) -> Result<Response<Empty>, ContractError> {
+    if info.sender != env.contract.address {
+        return Err(ContractError::Unauthorized {});
+    }
+    let hook = deps.api.addr_validate(&addr)?;
+    PROPOSAL_HOOKS.add_hook(deps.storage, hook)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "add_proposal_hook")
+        .add_attribute("hook", addr))
+}
+
+/// Unregisters a proposal hook. Only the multisig itself can do so, executing a passed proposal.
+pub fn execute_remove_proposal_hook(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    addr: String,
+) -> Result<Response<Empty>, ContractError> {
+    if info.sender != env.contract.address {
+        return Err(ContractError::Unauthorized {});
+    }
+    let hook = deps.api.addr_validate(&addr)?;
+    PROPOSAL_HOOKS.remove_hook(deps.storage, hook)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_proposal_hook")
+        .add_attribute("hook", addr))
+}
+
+/// Changes the rules for proposals to come. Only the multisig itself can do so, executing a
+/// passed proposal.
+pub fn execute_update_config(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    threshold: Option<Threshold>,
+    max_voting_period: Option<Duration>,
+    group_addr: Option<String>,
+) -> Result<Response<Empty>, ContractError> {
+    if info.sender != env.contract.address {
+        return Err(ContractError::Unauthorized {});
+    }
+    let mut cfg = CONFIG.load(deps.storage)?;
+
+    if let Some(group_addr) = group_addr {
+        let addr =
+            deps.api
+                .addr_validate(&group_addr)
+                .map_err(|_| ContractError::InvalidGroup {
+                    addr: group_addr.clone(),
+                })?;
+        if cfg.extra_groups.iter().any(|extra| extra.group.0 == addr) {
+            return Err(ContractError::InvalidGroup { addr: group_addr });
+        }
+        cfg.group_addr = Cw4Contract(addr);
+        if cfg.quadratic_voting {
+            QUADRATIC_TOTAL.save(deps.storage, &cfg.count_voting_power(&deps.querier)?)?;
+        }
+    }
+    if let Some(threshold) = threshold {
+        cfg.threshold = threshold;
+    }
+    if let Some(max_voting_period) = max_voting_period {
+        cfg.max_voting_period = max_voting_period;
+    }
+
+    // the threshold must be attainable by the (possibly new) group
+    let total_weight = cfg.total_weight(deps.storage, &deps.querier)?;
+    cfg.threshold.validate(total_weight)?;
+    if let Some(track) = &cfg.emergency {
+        if !track.is_shorter(cfg.max_voting_period) {
+            return Err(ContractError::InvalidEmergencyTrack {});
+        }
+        track.threshold.validate(total_weight)?;
+    }
+    if let Some(veto) = &cfg.veto {
+        if !same_unit(cfg.max_voting_period, veto.timelock_duration) {
+            return Err(ContractError::InvalidVetoTimelock {});
+        }
+    }
+    if let Some(execution_delay) = cfg.execution_delay {
+        if !same_unit(cfg.max_voting_period, execution_delay) {
+            return Err(ContractError::InvalidExecutionDelay {});
+        }
+    }
+    CONFIG.save(deps.storage, &cfg)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_config")
+        .add_attribute("sender", info.sender)
+        .add_attribute("group_addr", cfg.group_addr.addr()))
+}
+
+/// Whether both durations are heights, or both are times
+fn same_unit(a: Duration, b: Duration) -> bool {
+    matches!(
+        (a, b),
+        (Duration::Height(_), Duration::Height(_)) | (Duration::Time(_), Duration::Time(_))
+    )
+}
+
+/// Keeps the total voting power up to date with quadratic voting, for which it cannot be
+/// queried from the groups. A no-op otherwise.
+pub fn execute_membership_hook(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    diffs: Vec<MemberDiff>,
+) -> Result<Response<Empty>, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let multiplier = cfg
+        .groups()
+        .find(|(group, _)| info.sender == group.0)
+        .map(|(_, multiplier)| multiplier)
+        .ok_or(ContractError::Unauthorized {})?;
+    if !cfg.quadratic_voting {
+        return Ok(Response::default());
+    }
+
+    let mut total = Uint64::from(QUADRATIC_TOTAL.load(deps.storage)?);
+    for diff in diffs {
+        let addr = deps.api.addr_validate(&diff.key)?;
+        // the group already applied the diff, while the other groups did not change
+        let new_weight = cfg
+            .summed_weight(&deps.querier, &addr, None)?
+            .unwrap_or_default();
+        let changed = add_weight(0, diff.new.unwrap_or_default(), multiplier)?;
+        let old_weight = add_weight(
+            new_weight.saturating_sub(changed),
+            diff.old.unwrap_or_default(),
+            multiplier,
+        )?;
+        // saturating, so that a total gone stale cannot block the group from updating members
+        total = total
+            .saturating_sub(cfg.scale(old_weight).into())
+            .checked_add(cfg.scale(new_weight).into())
+            .map_err(StdError::from)?;
+    }
+    QUADRATIC_TOTAL.save(deps.storage, &total.u64())?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_total_weight")
+        .add_attribute("total_weight", total))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    if get_contract_version(deps.storage)?.contract == FIXED_CONTRACT_NAME {
+        return migrate_from_fixed(deps, env, msg);
+    }
+    ensure_from_older_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    // older versions added up the voting power on every proposal, rather than storing it
+    let cfg = CONFIG.load(deps.storage)?;
+    if cfg.quadratic_voting && QUADRATIC_TOTAL.may_load(deps.storage)?.is_none() {
+        QUADRATIC_TOTAL.save(deps.storage, &cfg.count_voting_power(&deps.querier)?)?;
+    }
+    Ok(Response::new().add_attribute("action", "migrate"))
+}
+
+/// Replaces the voters of a cw3-fixed-multisig by a group, keeping its voting rules, deposits
+/// and proposals. Proposals still open are closed as they stand, since their votes were cast by
+/// the voters, and their deposits refunded.
+fn migrate_from_fixed(deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    let group_addr = msg.group_addr.ok_or(ContractError::GroupRequired {})?;
+    let group_addr = Cw4Contract(
+        deps.api
+            .addr_validate(&group_addr)
+            .map_err(|_| ContractError::InvalidGroup { addr: group_addr })?,
+    );
+
+    let fixed = cw3_fixed_multisig::state::CONFIG.load(deps.storage)?;
+    let cfg = Config {
+        threshold: fixed.threshold,
+        max_voting_period: fixed.max_voting_period,
+        group_addr,
+        executor: None,
+        proposal_deposit: fixed.proposal_deposit,
+        veto: None,
+        close_reward: fixed.close_reward,
+        execution_delay: None,
+        allow_revoting: fixed.allow_revoting,
+        proposers: None,
+        extra_groups: vec![],
+        quadratic_voting: false,
+        emergency: fixed.emergency.map(|track| EmergencyTrack {
+            council: track.council,
+            threshold: track.threshold,
+            voting_period: track.voting_period,
+        }),
+        timelock: None,
+        private_voting: fixed.private_voting,
+    };
+    let total_weight = cfg.total_weight(deps.storage, &deps.querier)?;
+    cfg.threshold.validate(total_weight)?;
+    if let Some(track) = &cfg.emergency {
+        track.threshold.validate(total_weight)?;
+    }
+    CONFIG.save(deps.storage, &cfg)?;
+
+    let open = OPEN_PROPOSALS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut closed = 0;
+    let mut refunds = vec![];
+    let mut hooks = vec![];
+    for id in open {
+        let mut prop = PROPOSALS.load(deps.storage, id)?;
+        prop.update_status(&env.block);
+        if prop.status == Status::Open {
+            prop.status = Status::Rejected;
+            closed += 1;
+            if let (Some(deposit), Some(DepositStatus::Held)) = (&prop.deposit, prop.deposit_status)
+            {
+                refunds.push(deposit.get_return_deposit_message(&prop.proposer)?);
+                prop.deposit_status = Some(DepositStatus::Refunded);
+            }
+        }
+        PROPOSALS.save(deps.storage, id, &prop)?;
+        OPEN_PROPOSALS.remove(deps.storage, id);
+        let bonds = clear_commitments(deps.storage, &env.block, id, &prop)?;
+        refunds.extend(bonds.into_iter().map(CosmosMsg::from));
+        hooks.extend(status_hook_msgs(deps.storage, id, prop.status)?);
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    Ok(Response::new()
+        .add_messages(refunds)
+        .add_submessages(hooks)
+        .add_attribute("action", "migrate")
+        .add_attribute("from", FIXED_CONTRACT_NAME)
+        .add_attribute("closed", closed.to_string()))
+}
+
+/// Only failed dispatches of proposals are replied to, with the proposal id
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    let error = match msg.result {
+        SubMsgResult::Err(error) => error,
+        // only the timelock contract is replied to on success, with when it executes the proposal
+        SubMsgResult::Ok(_) => return proposal_queued(deps, env, msg),
+    };
+    // hooks may fail without blocking the multisig, this only records it
+    if msg.id == PROPOSAL_HOOK_REPLY_ID {
+        return Ok(Response::new()
+            .add_attribute("action", "proposal_hook_failed")
+            .add_attribute("error", error));
+    }
+    // failed proposals can be executed again, cancelled, or closed once expired, but no longer
+    // voted on, even if they only passed on expiration
+    let mut prop = PROPOSALS.load(deps.storage, msg.id)?;
+    prop.status = Status::ExecutionFailed;
+    prop.execution_error = Some(error.clone());
+    PROPOSALS.save(deps.storage, msg.id, &prop)?;
+    OPEN_PROPOSALS.remove(deps.storage, msg.id);
+
+    Ok(Response::new()
+        .add_attribute("action", "execution_failed")
+        .add_attribute("proposal_id", msg.id.to_string())
+        .add_attribute("status", format!("{:?}", prop.status))
+        .add_attribute("error", error))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Threshold {} => to_binary(&query_threshold(deps)?),
+        QueryMsg::Proposal { proposal_id } => to_binary(&query_proposal(deps, env, proposal_id)?),
+        QueryMsg::ProposalTally { proposal_id } => to_binary(&query_tally(deps, env, proposal_id)?),
+        QueryMsg::Vote { proposal_id, voter } => to_binary(&query_vote(deps, proposal_id, voter)?),
+        QueryMsg::ListProposals { start_after, limit } => {
+            to_binary(&list_proposals(deps, env, start_after, limit)?)
+        }
+        QueryMsg::ReverseProposals {
+            start_before,
+            limit,
+        } => to_binary(&reverse_proposals(deps, env, start_before, limit)?),
+        QueryMsg::ListProposalsByStatus {
+            status,
+            start_after,
+            limit,
+        } => to_binary(&list_proposals_by_status(
+            deps,
+            env,
+            status,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::ScheduledExecutions { start_after, limit } => {
+            to_binary(&list_scheduled_executions(deps, env, start_after, limit)?)
+        }
+        QueryMsg::PendingVotes {
+            voter,
+            start_after,
+            limit,
+        } => to_binary(&list_pending_votes(deps, env, voter, start_after, limit)?),
+        QueryMsg::ListProposalsByProposer {
+            proposer,
+            start_after,
+            limit,
+        } => to_binary(&list_proposals_by_proposer(
+            deps,
+            env,
+            proposer,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::ListVotes {
+            proposal_id,
+            start_after,
+            limit,
+        } => to_binary(&list_votes(deps, proposal_id, start_after, limit)?),
+        QueryMsg::Voter { address } => to_binary(&query_voter(deps, address)?),
+        QueryMsg::ListVoters { start_after, limit } => {
+            to_binary(&list_voters(deps, start_after, limit)?)
+        }
+        QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::ProposalHooks {} => to_binary(&PROPOSAL_HOOKS.query_hooks(deps)?),
+        QueryMsg::VoteNonce { voter } => {
+            let voter = deps.api.addr_validate(&voter)?;
+            to_binary(&VOTE_PERMITS.query_nonce(deps, &voter)?)
+        }
+        QueryMsg::VoteCommitment { proposal_id, voter } => {
+            to_binary(&query_vote_commitment(deps, proposal_id, voter)?)
+        }
+        QueryMsg::ThresholdProfiles {} => to_binary(&query_threshold_profiles(deps)?),
+        QueryMsg::ExecutableAt { proposal_id } => {
+            to_binary(&query_executable_at(deps, env, proposal_id)?)
+        }
+    }
+}
+
+fn query_threshold(deps: Deps) -> StdResult<ThresholdResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let total_weight = cfg.total_weight(deps.storage, &deps.querier)?;
+    Ok(cfg.threshold.to_response(total_weight))
+}
+
+fn query_threshold_profiles(deps: Deps) -> StdResult<ThresholdProfilesResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let total_weight = cfg.total_weight(deps.storage, &deps.querier)?;
+    let profiles = THRESHOLD_PROFILES
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (name, threshold) = item?;
+            Ok(ThresholdProfileResponse {
+                name,
+                threshold: threshold.to_response(total_weight),
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(ThresholdProfilesResponse { profiles })
+}
+
+fn query_config(deps: Deps) -> StdResult<Config> {
+    CONFIG.load(deps.storage)
+}
+
+fn query_tally(deps: Deps, env: Env, id: u64) -> StdResult<TallyResponse> {
+    let prop = PROPOSALS.load(deps.storage, id)?;
+    let veto = CONFIG.load(deps.storage)?.veto;
+    let status = proposal_status(deps.storage, veto.as_ref(), id, &prop, &env.block)?;
+    Ok(prop.tally(status))
+}
+
+fn query_proposal(deps: Deps, env: Env, id: u64) -> StdResult<ProposalResponse> {
+    let prop = PROPOSALS.load(deps.storage, id)?;
+    let veto = CONFIG.load(deps.storage)?.veto;
+    let status = proposal_status(deps.storage, veto.as_ref(), id, &prop, &env.block)?;
+    let threshold = prop.threshold.to_response(prop.total_weight);
+    Ok(ProposalResponse {
+        id,
+        title: prop.title,
+        description: prop.description,
+        msgs: prop.msgs,
+        status,
+        expires: prop.expires,
+        proposer: prop.proposer,
+        deposit: prop.deposit,
+        deposit_status: prop.deposit_status,
+        threshold,
+        kind: prop.kind,
+        execution_error: prop.execution_error,
+        cast_threshold: prop.cast_threshold,
+        execution_window: prop.execution_window,
+        metadata: prop.metadata,
+        options: prop.options,
+        win_condition: prop.win_condition,
+        executed_msgs: prop.executed_msgs,
+        commit_deadline: prop.commit_deadline,
+    })
+}
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+fn list_proposals(
+    deps: Deps,
+    env: Env,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ProposalListResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let veto = CONFIG.load(deps.storage)?.veto;
+    let proposals = PROPOSALS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|p| map_proposal(deps.storage, veto.as_ref(), &env.block, p))
+        .collect::<StdResult<_>>()?;
+
+    Ok(ProposalListResponse { proposals })
+}
+
+fn list_proposals_by_status(
+    deps: Deps,
+    env: Env,
+    status: Status,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ProposalListResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let veto = CONFIG.load(deps.storage)?.veto;
+    // statuses change with time, so they are checked as proposals are listed
+    let proposals = PROPOSALS
+        .range(deps.storage, start, None, Order::Ascending)
+        .map(|p| map_proposal(deps.storage, veto.as_ref(), &env.block, p))
+        .filter(|p| !matches!(p, Ok(p) if p.status != status))
+        .take(limit)
+        .collect::<StdResult<_>>()?;
+
+    Ok(ProposalListResponse { proposals })
+}
+
+fn list_proposals_by_proposer(
+    deps: Deps,
+    env: Env,
+    proposer: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ProposalListResponse> {
+    let proposer = deps.api.addr_validate(&proposer)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let veto = CONFIG.load(deps.storage)?.veto;
+    let proposals = PROPOSALS_BY_PROPOSER
+        .prefix(&proposer)
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|id| {
+            let id = id?;
+            map_proposal(
+                deps.storage,
+                veto.as_ref(),
+                &env.block,
+                Ok((id, PROPOSALS.load(deps.storage, id)?)),
+            )
+        })
+        .collect::<StdResult<_>>()?;
+
+    Ok(ProposalListResponse { proposals })
+}
+
+fn reverse_proposals(
+    deps: Deps,
+    env: Env,
+    start_before: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ProposalListResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let end = start_before.map(Bound::exclusive);
+    let veto = CONFIG.load(deps.storage)?.veto;
+    let props: StdResult<Vec<_>> = PROPOSALS
+        .range(deps.storage, None, end, Order::Descending)
+        .take(limit)
+        .map(|p| map_proposal(deps.storage, veto.as_ref(), &env.block, p))
+        .collect();
+
+    Ok(ProposalListResponse { proposals: props? })
+}
+
+fn list_scheduled_executions(
+    deps: Deps,
+    env: Env,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ProposalListResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let veto = CONFIG.load(deps.storage)?.veto;
+    // only the passed ones are pending, until their window is over
+    let passed = [
+        Status::Passed,
+        Status::VetoTimelock,
+        Status::ExecutionFailed,
+    ];
+    let pending = |prop: &ProposalResponse| {
+        passed.contains(&prop.status)
+            && matches!(&prop.execution_window, Some(window) if !window.is_over(&env.block))
+    };
+    let proposals = SCHEDULED_PROPOSALS
+        .keys(deps.storage, start, None, Order::Ascending)
+        .map(|id| {
+            let id = id?;
+            let prop = PROPOSALS.load(deps.storage, id)?;
+            map_proposal(deps.storage, veto.as_ref(), &env.block, Ok((id, prop)))
+        })
+        .filter(|p| !matches!(p, Ok(p) if !pending(p)))
+        .take(limit)
+        .collect::<StdResult<_>>()?;
+
+    Ok(ProposalListResponse { proposals })
+}
+
+fn list_pending_votes(
+    deps: Deps,
+    env: Env,
+    voter: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ProposalListResponse> {
+    let voter = deps.api.addr_validate(&voter)?;
+    let cfg = CONFIG.load(deps.storage)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    // still open, as of the current block, and the voter could vote but did not yet
+    let pending = |id: u64, prop: &Proposal| -> StdResult<bool> {
+        if prop.current_status(&env.block) != Status::Open
+            || BALLOTS.has(deps.storage, (id, &voter))
+        {
+            return Ok(false);
+        }
+        Ok(cfg
+            .voting_power(&deps.querier, &voter, prop.start_height)?
+            .is_some())
+    };
+    let proposals = OPEN_PROPOSALS
+        .keys(deps.storage, start, None, Order::Ascending)
+        .map(|id| {
+            let id = id?;
+            let prop = PROPOSALS.load(deps.storage, id)?;
+            if pending(id, &prop)? {
+                map_proposal(deps.storage, cfg.veto.as_ref(), &env.block, Ok((id, prop))).map(Some)
+            } else {
+                Ok(None)
+            }
+        })
+        .filter_map(StdResult::transpose)
+        .take(limit)
+        .collect::<StdResult<_>>()?;
+
+    Ok(ProposalListResponse { proposals })
+}
+
+fn map_proposal(
+    storage: &dyn Storage,
+    veto: Option<&VetoConfig>,
+    block: &BlockInfo,
+    item: StdResult<(u64, Proposal)>,
+) -> StdResult<ProposalResponse> {
+    item.and_then(|(id, prop)| {
+        let status = proposal_status(storage, veto, id, &prop, block)?;
+        let threshold = prop.threshold.to_response(prop.total_weight);
+        Ok(ProposalResponse {
+            id,
+            title: prop.title,
+            description: prop.description,
+            msgs: prop.msgs,
+            status,
+            expires: prop.expires,
+            deposit: prop.deposit,
+            deposit_status: prop.deposit_status,
+            proposer: prop.proposer,
+            threshold,
+            kind: prop.kind,
+            execution_error: prop.execution_error,
+            cast_threshold: prop.cast_threshold,
+            execution_window: prop.execution_window,
+            metadata: prop.metadata,
+            options: prop.options,
+            win_condition: prop.win_condition,
+            executed_msgs: prop.executed_msgs,
+            commit_deadline: prop.commit_deadline,
+        })
+    })
+}
+
+fn query_vote(deps: Deps, proposal_id: u64, voter: String) -> StdResult<VoteResponse> {
+    let voter_addr = deps.api.addr_validate(&voter)?;
+    let prop = BALLOTS.may_load(deps.storage, (proposal_id, &voter_addr))?;
+    let vote = prop.map(|b| VoteInfo {
+        proposal_id,
+        voter,
+        vote: b.vote,
+        weight: b.weight,
+        split: b.split,
+        choice: b.choice,
+    });
+    Ok(VoteResponse { vote })
+}
+
+fn query_vote_commitment(
+    deps: Deps,
+    proposal_id: u64,
+    voter: String,
+) -> StdResult<VoteCommitmentResponse> {
+    let voter = deps.api.addr_validate(&voter)?;
+    let commitment = VOTE_COMMITMENTS.may_load(deps.storage, (proposal_id, &voter))?;
+    Ok(VoteCommitmentResponse {
+        commitment: commitment.map(|commitment| commitment.hash),
+    })
+}
+
+fn list_votes(
+    deps: Deps,
+    proposal_id: u64,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<VoteListResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let addr = maybe_addr(deps.api, start_after)?;
+    let start = addr.as_ref().map(Bound::exclusive);
+
+    let votes = BALLOTS
+        .prefix(proposal_id)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            item.map(|(addr, ballot)| VoteInfo {
+                proposal_id,
+                voter: addr.into(),
+                vote: ballot.vote,
+                weight: ballot.weight,
+                split: ballot.split,
+                choice: ballot.choice,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+
+    Ok(VoteListResponse { votes })
+}
+
+fn query_voter(deps: Deps, voter: String) -> StdResult<VoterResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let voter_addr = deps.api.addr_validate(&voter)?;
+    let weight = cfg.member_weight(&deps.querier, &voter_addr, None)?;
+
+    Ok(VoterResponse { weight })
+}
+
+fn list_voters(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<VoterListResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+    let voters = cfg.voters(&deps.querier, start_after, limit)?;
+    Ok(VoterListResponse { voters })
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{
+        attr, coin, coins, Addr, BankMsg, Coin, Decimal, StdError, Timestamp, Uint128, WasmMsg,
+    };
+
+    use cw2::{query_contract_info, ContractVersion};
+    use cw20::{Cw20Coin, Denom, UncheckedDenom};
+    use cw3::{DepositError, UncheckedDepositForfeit, UncheckedDepositInfo, VoterDetail};
+    use cw4::{Cw4ExecuteMsg, Member, TotalWeightDiff};
+    use cw4_group::helpers::Cw4GroupContract;
+    use cw_multi_test::{
+        next_block, App, AppBuilder, BankSudo, Contract, ContractWrapper, Executor, SudoMsg,
+    };
+    use cw_utils::{Duration, Threshold, ThresholdError};
+
+    use super::*;
+    use crate::msg::{ThresholdProfile, UncheckedEmergencyTrack, UncheckedWeightedGroup};
+    use crate::state::isqrt;
+
+    const OWNER: &str = "admin0001";
+    const VOTER1: &str = "voter0001";
+    const VOTER2: &str = "voter0002";
+    const VOTER3: &str = "voter0003";
+    const VOTER4: &str = "voter0004";
+    const VOTER5: &str = "voter0005";
+    const SOMEBODY: &str = "somebody";
+
+    fn member<T: Into<String>>(addr: T, weight: u64) -> Member {
+        Member {
+            addr: addr.into(),
+            weight,
+        }
+    }
+
+    pub fn contract_flex() -> Box<dyn Contract<Empty>> {
+        let contract = ContractWrapper::new(
+            crate::contract::execute,
+            crate::contract::instantiate,
+            crate::contract::query,
+        )
+        .with_reply(crate::contract::reply)
+        .with_migrate(crate::contract::migrate);
+        Box::new(contract)
+    }
+
+    pub fn contract_group() -> Box<dyn Contract<Empty>> {
+        let contract = ContractWrapper::new(
+            cw4_group::contract::execute,
+            cw4_group::contract::instantiate,
+            cw4_group::contract::query,
+        );
+        Box::new(contract)
+    }
+
+    fn contract_cw20() -> Box<dyn Contract<Empty>> {
+        let contract = ContractWrapper::new(
+            cw20_base::contract::execute,
+            cw20_base::contract::instantiate,
+            cw20_base::contract::query,
+        );
+        Box::new(contract)
+    }
+
+    fn mock_app(init_funds: &[Coin]) -> App {
+        AppBuilder::new().build(|router, _, storage| {
+            router
+                .bank
+                .init_balance(storage, &Addr::unchecked(OWNER), init_funds.to_vec())
+                .unwrap();
+        })
+    }
+
+    // uploads code and returns address of group contract
+    fn instantiate_group(app: &mut App, members: Vec<Member>) -> Addr {
+        let group_id = app.store_code(contract_group());
+        let msg = cw4_group::msg::InstantiateMsg {
+            admin: Some(OWNER.into()),
+            members,
+            allow_leave: false,
+            weight_decimals: 0,
+            decimal_members: vec![],
+        };
+        app.instantiate_contract(group_id, Addr::unchecked(OWNER), &msg, &[], "group", None)
+            .unwrap()
+    }
+
+    #[track_caller]
+    fn instantiate_flex(
+        app: &mut App,
+        group: Addr,
+        threshold: Threshold,
+        max_voting_period: Duration,
+        executor: Option<crate::state::Executor>,
+        proposal_deposit: Option<UncheckedDepositInfo>,
+    ) -> Addr {
+        let flex_id = app.store_code(contract_flex());
+        let msg = crate::msg::InstantiateMsg {
+            group_addr: group.to_string(),
+            threshold,
+            max_voting_period,
+            executor,
+            proposal_deposit,
+            veto: None,
+            execution_delay: None,
+            close_reward: None,
+            threshold_profiles: vec![],
+            allow_revoting: false,
+            proposers: None,
+            extra_groups: vec![],
+            emergency: None,
+            quadratic_voting: false,
+            timelock: None,
+            private_voting: None,
+        };
+        app.instantiate_contract(flex_id, Addr::unchecked(OWNER), &msg, &[], "flex", None)
+            .unwrap()
+    }
+
+    // this will set up both contracts, instantiating the group with
+    // all voters defined above, and the multisig pointing to it and given threshold criteria.
+    // Returns (multisig address, group address).
+    #[track_caller]
+    fn setup_test_case_fixed(
+        app: &mut App,
+        weight_needed: u64,
+        max_voting_period: Duration,
+        init_funds: Vec<Coin>,
+        multisig_as_group_admin: bool,
+    ) -> (Addr, Addr) {
+        setup_test_case(
+            app,
+            Threshold::AbsoluteCount {
+                weight: weight_needed,
+            },
+            max_voting_period,
+            init_funds,
+            multisig_as_group_admin,
+            None,
+            None,
+        )
+    }
+
+    #[track_caller]
+    fn setup_test_case(
+        app: &mut App,
+        threshold: Threshold,
+        max_voting_period: Duration,
+        init_funds: Vec<Coin>,
+        multisig_as_group_admin: bool,
+        executor: Option<crate::state::Executor>,
+        proposal_deposit: Option<UncheckedDepositInfo>,
+    ) -> (Addr, Addr) {
+        // 1. Instantiate group contract with members (and OWNER as admin)
+        let members = vec![
+            member(OWNER, 0),
+            member(VOTER1, 1),
+            member(VOTER2, 2),
+            member(VOTER3, 3),
+            member(VOTER4, 12), // so that he alone can pass a 50 / 52% threshold proposal
+            member(VOTER5, 5),
+        ];
+        let group_addr = instantiate_group(app, members);
+        app.update_block(next_block);
+
+        // 2. Set up Multisig backed by this group
+        let flex_addr = instantiate_flex(
+            app,
+            group_addr.clone(),
+            threshold,
+            max_voting_period,
+            executor,
+            proposal_deposit,
+        );
+        app.update_block(next_block);
+
+        // 3. (Optional) Set the multisig as the group owner
+        if multisig_as_group_admin {
+            let update_admin = Cw4ExecuteMsg::UpdateAdmin {
+                admin: Some(flex_addr.to_string()),
+            };
+            app.execute_contract(
+                Addr::unchecked(OWNER),
+                group_addr.clone(),
+                &update_admin,
+                &[],
+            )
+            .unwrap();
+            app.update_block(next_block);
+        }
+
+        // Bonus: set some funds on the multisig contract for future proposals
+        if !init_funds.is_empty() {
+            app.send_tokens(Addr::unchecked(OWNER), flex_addr.clone(), &init_funds)
+                .unwrap();
+        }
+        (flex_addr, group_addr)
+    }
+
+    fn proposal_info() -> (Vec<CosmosMsg<Empty>>, String, String) {
+        let bank_msg = BankMsg::Send {
+            to_address: SOMEBODY.into(),
+            amount: coins(1, "BTC"),
+        };
+        let msgs = vec![bank_msg.into()];
+        let title = "Pay somebody".to_string();
+        let description = "Do I pay her?".to_string();
+        (msgs, title, description)
+    }
+
+    fn pay_somebody_proposal() -> ExecuteMsg {
+        let (msgs, title, description) = proposal_info();
+        ExecuteMsg::Propose {
+            title,
+            description,
+            msgs,
+            latest: None,
+            threshold_profile: None,
+            execution_window: None,
+            metadata: None,
+        }
+    }
+
+    fn text_proposal() -> ExecuteMsg {
         let (_, title, description) = proposal_info();
         ExecuteMsg::Propose {
             title,
             description,
-            msgs: vec![],
+            msgs: vec![],
+            latest: None,
+            threshold_profile: None,
+            execution_window: None,
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn test_instantiate_works() {
+        let mut app = mock_app(&[]);
+
+        // make a simple group
+        let group_addr = instantiate_group(&mut app, vec![member(OWNER, 1)]);
+        let flex_id = app.store_code(contract_flex());
+
+        let max_voting_period = Duration::Time(1234567);
+
+        // Zero required weight fails
+        let instantiate_msg = InstantiateMsg {
+            group_addr: group_addr.to_string(),
+            threshold: Threshold::ThresholdQuorum {
+                threshold: Decimal::zero(),
+                quorum: Decimal::percent(1),
+            },
+            max_voting_period,
+            executor: None,
+            proposal_deposit: None,
+            veto: None,
+            execution_delay: None,
+            close_reward: None,
+            threshold_profiles: vec![],
+            allow_revoting: false,
+            proposers: None,
+            extra_groups: vec![],
+            emergency: None,
+            quadratic_voting: false,
+            timelock: None,
+            private_voting: None,
+        };
+        let err = app
+            .instantiate_contract(
+                flex_id,
+                Addr::unchecked(OWNER),
+                &instantiate_msg,
+                &[],
+                "zero required weight",
+                None,
+            )
+            .unwrap_err();
+        assert_eq!(
+            ContractError::Threshold(cw_utils::ThresholdError::InvalidThreshold {}),
+            err.downcast().unwrap()
+        );
+
+        // Total weight less than required weight not allowed
+        let instantiate_msg = InstantiateMsg {
+            group_addr: group_addr.to_string(),
+            threshold: Threshold::AbsoluteCount { weight: 100 },
+            max_voting_period,
+            executor: None,
+            proposal_deposit: None,
+            veto: None,
+            execution_delay: None,
+            close_reward: None,
+            threshold_profiles: vec![],
+            allow_revoting: false,
+            proposers: None,
+            extra_groups: vec![],
+            emergency: None,
+            quadratic_voting: false,
+            timelock: None,
+            private_voting: None,
+        };
+        let err = app
+            .instantiate_contract(
+                flex_id,
+                Addr::unchecked(OWNER),
+                &instantiate_msg,
+                &[],
+                "high required weight",
+                None,
+            )
+            .unwrap_err();
+        assert_eq!(
+            ContractError::Threshold(cw_utils::ThresholdError::UnreachableWeight {}),
+            err.downcast().unwrap()
+        );
+
+        // All valid
+        let instantiate_msg = InstantiateMsg {
+            group_addr: group_addr.to_string(),
+            threshold: Threshold::AbsoluteCount { weight: 1 },
+            max_voting_period,
+            executor: None,
+            proposal_deposit: None,
+            veto: None,
+            execution_delay: None,
+            close_reward: None,
+            threshold_profiles: vec![],
+            allow_revoting: false,
+            proposers: None,
+            extra_groups: vec![],
+            emergency: None,
+            quadratic_voting: false,
+            timelock: None,
+            private_voting: None,
+        };
+        let flex_addr = app
+            .instantiate_contract(
+                flex_id,
+                Addr::unchecked(OWNER),
+                &instantiate_msg,
+                &[],
+                "all good",
+                None,
+            )
+            .unwrap();
+
+        // Verify contract version set properly
+        let version = query_contract_info(&app.wrap(), flex_addr.clone()).unwrap();
+        assert_eq!(
+            ContractVersion {
+                contract: CONTRACT_NAME.to_string(),
+                version: CONTRACT_VERSION.to_string(),
+            },
+            version,
+        );
+
+        // Get voters query
+        let voters: VoterListResponse = app
+            .wrap()
+            .query_wasm_smart(
+                &flex_addr,
+                &QueryMsg::ListVoters {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            voters.voters,
+            vec![VoterDetail {
+                addr: OWNER.into(),
+                weight: 1
+            }]
+        );
+    }
+
+    #[test]
+    fn test_propose_works() {
+        let init_funds = coins(10, "BTC");
+        let mut app = mock_app(&init_funds);
+
+        let required_weight = 4;
+        let voting_period = Duration::Time(2000000);
+        let (flex_addr, _) =
+            setup_test_case_fixed(&mut app, required_weight, voting_period, init_funds, false);
+
+        let proposal = pay_somebody_proposal();
+        // Only voters can propose
+        let err = app
+            .execute_contract(Addr::unchecked(SOMEBODY), flex_addr.clone(), &proposal, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+        // Wrong expiration option fails
+        let msgs = match proposal.clone() {
+            ExecuteMsg::Propose { msgs, .. } => msgs,
+            _ => panic!("Wrong variant"),
+        };
+        let proposal_wrong_exp = ExecuteMsg::Propose {
+            title: "Rewarding somebody".to_string(),
+            description: "Do we reward her?".to_string(),
+            msgs,
+            latest: Some(Expiration::AtHeight(123456)),
+            threshold_profile: None,
+            execution_window: None,
+            metadata: None,
+        };
+        let err = app
+            .execute_contract(
+                Addr::unchecked(OWNER),
+                flex_addr.clone(),
+                &proposal_wrong_exp,
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(ContractError::WrongExpiration {}, err.downcast().unwrap());
+
+        // Proposal from voter works
+        let res = app
+            .execute_contract(Addr::unchecked(VOTER3), flex_addr.clone(), &proposal, &[])
+            .unwrap();
+        assert_eq!(
+            res.custom_attrs(1),
+            [
+                ("action", "propose"),
+                ("sender", VOTER3),
+                ("proposal_id", "1"),
+                ("status", "Open"),
+            ],
+        );
+
+        // Proposal from voter with enough vote power directly passes
+        let res = app
+            .execute_contract(Addr::unchecked(VOTER4), flex_addr, &proposal, &[])
+            .unwrap();
+        assert_eq!(
+            res.custom_attrs(1),
+            [
+                ("action", "propose"),
+                ("sender", VOTER4),
+                ("proposal_id", "2"),
+                ("status", "Passed"),
+            ],
+        );
+    }
+
+    fn get_tally(app: &App, flex_addr: &str, proposal_id: u64) -> u64 {
+        // Get all the voters on the proposal
+        let voters = QueryMsg::ListVotes {
+            proposal_id,
+            start_after: None,
+            limit: None,
+        };
+        let votes: VoteListResponse = app.wrap().query_wasm_smart(flex_addr, &voters).unwrap();
+        // Sum the weights of the Yes votes to get the tally
+        votes
+            .votes
+            .iter()
+            .filter(|&v| v.vote == Vote::Yes)
+            .map(|v| v.weight)
+            .sum()
+    }
+
+    fn expire(voting_period: Duration) -> impl Fn(&mut BlockInfo) {
+        move |block: &mut BlockInfo| {
+            match voting_period {
+                Duration::Time(duration) => block.time = block.time.plus_seconds(duration + 1),
+                Duration::Height(duration) => block.height += duration + 1,
+            };
+        }
+    }
+
+    fn unexpire(voting_period: Duration) -> impl Fn(&mut BlockInfo) {
+        move |block: &mut BlockInfo| {
+            match voting_period {
+                Duration::Time(duration) => {
+                    block.time =
+                        Timestamp::from_nanos(block.time.nanos() - (duration * 1_000_000_000));
+                }
+                Duration::Height(duration) => block.height -= duration,
+            };
+        }
+    }
+
+    #[test]
+    fn test_proposal_queries() {
+        let init_funds = coins(10, "BTC");
+        let mut app = mock_app(&init_funds);
+
+        let voting_period = Duration::Time(2000000);
+        let threshold = Threshold::ThresholdQuorum {
+            threshold: Decimal::percent(80),
+            quorum: Decimal::percent(20),
+        };
+        let (flex_addr, _) = setup_test_case(
+            &mut app,
+            threshold,
+            voting_period,
+            init_funds,
+            false,
+            None,
+            None,
+        );
+
+        // create proposal with 1 vote power
+        let proposal = pay_somebody_proposal();
+        let res = app
+            .execute_contract(Addr::unchecked(VOTER1), flex_addr.clone(), &proposal, &[])
+            .unwrap();
+        let proposal_id1: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
+
+        // another proposal immediately passes
+        app.update_block(next_block);
+        let proposal = pay_somebody_proposal();
+        let res = app
+            .execute_contract(Addr::unchecked(VOTER4), flex_addr.clone(), &proposal, &[])
+            .unwrap();
+        let proposal_id2: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
+
+        // expire them both
+        app.update_block(expire(voting_period));
+
+        // add one more open proposal, 2 votes
+        let proposal = pay_somebody_proposal();
+        let res = app
+            .execute_contract(Addr::unchecked(VOTER2), flex_addr.clone(), &proposal, &[])
+            .unwrap();
+        let proposal_id3: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
+        let proposed_at = app.block_info();
+
+        // next block, let's query them all... make sure status is properly updated (1 should be rejected in query)
+        app.update_block(next_block);
+        let list_query = QueryMsg::ListProposals {
+            start_after: None,
+            limit: None,
+        };
+        let res: ProposalListResponse = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &list_query)
+            .unwrap();
+        assert_eq!(3, res.proposals.len());
+
+        // check the id and status are properly set
+        let info: Vec<_> = res.proposals.iter().map(|p| (p.id, p.status)).collect();
+        let expected_info = vec![
+            (proposal_id1, Status::Rejected),
+            (proposal_id2, Status::Passed),
+            (proposal_id3, Status::Open),
+        ];
+        assert_eq!(expected_info, info);
+
+        // ensure the common features are set
+        let (expected_msgs, expected_title, expected_description) = proposal_info();
+        for prop in res.proposals {
+            assert_eq!(prop.title, expected_title);
+            assert_eq!(prop.description, expected_description);
+            assert_eq!(prop.msgs, expected_msgs);
+        }
+
+        // reverse query can get just proposal_id3
+        let list_query = QueryMsg::ReverseProposals {
+            start_before: None,
+            limit: Some(1),
+        };
+        let res: ProposalListResponse = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &list_query)
+            .unwrap();
+        assert_eq!(1, res.proposals.len());
+
+        let (msgs, title, description) = proposal_info();
+        let expected = ProposalResponse {
+            id: proposal_id3,
+            title,
+            description,
+            msgs,
+            expires: voting_period.after(&proposed_at),
+            status: Status::Open,
+            threshold: ThresholdResponse::ThresholdQuorum {
+                total_weight: 23,
+                threshold: Decimal::percent(80),
+                quorum: Decimal::percent(20),
+            },
+            proposer: Addr::unchecked(VOTER2),
+            deposit: None,
+            deposit_status: None,
+            kind: ProposalKind::Action,
+            execution_error: None,
+            cast_threshold: None,
+            execution_window: None,
+            metadata: None,
+            options: vec![],
+            win_condition: None,
+            executed_msgs: vec![],
+            commit_deadline: None,
+        };
+        assert_eq!(&expected, &res.proposals[0]);
+    }
+
+    #[test]
+    fn test_vote_works() {
+        let init_funds = coins(10, "BTC");
+        let mut app = mock_app(&init_funds);
+
+        let threshold = Threshold::ThresholdQuorum {
+            threshold: Decimal::percent(51),
+            quorum: Decimal::percent(1),
+        };
+        let voting_period = Duration::Time(2000000);
+        let (flex_addr, _) = setup_test_case(
+            &mut app,
+            threshold,
+            voting_period,
+            init_funds,
+            false,
+            None,
+            None,
+        );
+
+        // create proposal with 0 vote power
+        let proposal = pay_somebody_proposal();
+        let res = app
+            .execute_contract(Addr::unchecked(OWNER), flex_addr.clone(), &proposal, &[])
+            .unwrap();
+
+        // Get the proposal id from the logs
+        let proposal_id: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
+
+        // Owner with 0 voting power cannot vote
+        let yes_vote = ExecuteMsg::Vote {
+            proposal_id,
+            vote: Vote::Yes,
+        };
+        let err = app
+            .execute_contract(Addr::unchecked(OWNER), flex_addr.clone(), &yes_vote, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+        // Only voters can vote
+        let err = app
+            .execute_contract(Addr::unchecked(SOMEBODY), flex_addr.clone(), &yes_vote, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+        // But voter1 can
+        let res = app
+            .execute_contract(Addr::unchecked(VOTER1), flex_addr.clone(), &yes_vote, &[])
+            .unwrap();
+        assert_eq!(
+            res.custom_attrs(1),
+            [
+                ("action", "vote"),
+                ("sender", VOTER1),
+                ("proposal_id", proposal_id.to_string().as_str()),
+                ("status", "Open"),
+            ],
+        );
+
+        // VOTER1 cannot vote again
+        let err = app
+            .execute_contract(Addr::unchecked(VOTER1), flex_addr.clone(), &yes_vote, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::AlreadyVoted {}, err.downcast().unwrap());
+
+        // No/Veto votes have no effect on the tally
+        // Compute the current tally
+        let tally = get_tally(&app, flex_addr.as_ref(), proposal_id);
+        assert_eq!(tally, 1);
+
+        // Cast a No vote
+        let no_vote = ExecuteMsg::Vote {
+            proposal_id,
+            vote: Vote::No,
+        };
+        let _ = app
+            .execute_contract(Addr::unchecked(VOTER2), flex_addr.clone(), &no_vote, &[])
+            .unwrap();
+
+        // Cast a Veto vote
+        let veto_vote = ExecuteMsg::Vote {
+            proposal_id,
+            vote: Vote::Veto,
+        };
+        let _ = app
+            .execute_contract(Addr::unchecked(VOTER3), flex_addr.clone(), &veto_vote, &[])
+            .unwrap();
+
+        // Tally unchanged
+        assert_eq!(tally, get_tally(&app, flex_addr.as_ref(), proposal_id));
+
+        let err = app
+            .execute_contract(Addr::unchecked(VOTER3), flex_addr.clone(), &yes_vote, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::AlreadyVoted {}, err.downcast().unwrap());
+
+        // Expired proposals cannot be voted
+        app.update_block(expire(voting_period));
+        let err = app
+            .execute_contract(Addr::unchecked(VOTER4), flex_addr.clone(), &yes_vote, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::Expired {}, err.downcast().unwrap());
+        app.update_block(unexpire(voting_period));
+
+        // Powerful voter supports it, so it passes
+        let res = app
+            .execute_contract(Addr::unchecked(VOTER4), flex_addr.clone(), &yes_vote, &[])
+            .unwrap();
+        assert_eq!(
+            res.custom_attrs(1),
+            [
+                ("action", "vote"),
+                ("sender", VOTER4),
+                ("proposal_id", proposal_id.to_string().as_str()),
+                ("status", "Passed"),
+            ],
+        );
+
+        // Passed proposals can still be voted (while they are not expired or executed)
+        let res = app
+            .execute_contract(Addr::unchecked(VOTER5), flex_addr.clone(), &yes_vote, &[])
+            .unwrap();
+        // Verify
+        assert_eq!(
+            res.custom_attrs(1),
+            [
+                ("action", "vote"),
+                ("sender", VOTER5),
+                ("proposal_id", proposal_id.to_string().as_str()),
+                ("status", "Passed")
+            ]
+        );
+
+        // query individual votes
+        // initial (with 0 weight)
+        let voter = OWNER.into();
+        let vote: VoteResponse = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &QueryMsg::Vote { proposal_id, voter })
+            .unwrap();
+        assert_eq!(
+            vote.vote.unwrap(),
+            VoteInfo {
+                proposal_id,
+                voter: OWNER.into(),
+                vote: Vote::Yes,
+                weight: 0,
+                split: None,
+                choice: None,
+            }
+        );
+
+        // nay sayer
+        let voter = VOTER2.into();
+        let vote: VoteResponse = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &QueryMsg::Vote { proposal_id, voter })
+            .unwrap();
+        assert_eq!(
+            vote.vote.unwrap(),
+            VoteInfo {
+                proposal_id,
+                voter: VOTER2.into(),
+                vote: Vote::No,
+                weight: 2,
+                split: None,
+                choice: None,
+            }
+        );
+
+        // non-voter
+        let voter = SOMEBODY.into();
+        let vote: VoteResponse = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &QueryMsg::Vote { proposal_id, voter })
+            .unwrap();
+        assert!(vote.vote.is_none());
+
+        // create proposal with 0 vote power
+        let proposal = pay_somebody_proposal();
+        let res = app
+            .execute_contract(Addr::unchecked(OWNER), flex_addr.clone(), &proposal, &[])
+            .unwrap();
+
+        // Get the proposal id from the logs
+        let proposal_id: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
+
+        // Cast a No vote
+        let no_vote = ExecuteMsg::Vote {
+            proposal_id,
+            vote: Vote::No,
+        };
+        let _ = app
+            .execute_contract(Addr::unchecked(VOTER2), flex_addr.clone(), &no_vote, &[])
+            .unwrap();
+
+        // Powerful voter opposes it, so it rejects
+        let res = app
+            .execute_contract(Addr::unchecked(VOTER4), flex_addr.clone(), &no_vote, &[])
+            .unwrap();
+
+        assert_eq!(
+            res.custom_attrs(1),
+            [
+                ("action", "vote"),
+                ("sender", VOTER4),
+                ("proposal_id", proposal_id.to_string().as_str()),
+                ("status", "Rejected"),
+            ],
+        );
+
+        // Rejected proposals can still be voted (while they are not expired)
+        let yes_vote = ExecuteMsg::Vote {
+            proposal_id,
+            vote: Vote::Yes,
+        };
+        let res = app
+            .execute_contract(Addr::unchecked(VOTER5), flex_addr, &yes_vote, &[])
+            .unwrap();
+
+        assert_eq!(
+            res.custom_attrs(1),
+            [
+                ("action", "vote"),
+                ("sender", VOTER5),
+                ("proposal_id", proposal_id.to_string().as_str()),
+                ("status", "Rejected"),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_execute_works() {
+        let init_funds = coins(10, "BTC");
+        let mut app = mock_app(&init_funds);
+
+        let threshold = Threshold::ThresholdQuorum {
+            threshold: Decimal::percent(51),
+            quorum: Decimal::percent(1),
+        };
+        let voting_period = Duration::Time(2000000);
+        let (flex_addr, _) = setup_test_case(
+            &mut app,
+            threshold,
+            voting_period,
+            init_funds,
+            true,
+            None,
+            None,
+        );
+
+        // ensure we have cash to cover the proposal
+        let contract_bal = app.wrap().query_balance(&flex_addr, "BTC").unwrap();
+        assert_eq!(contract_bal, coin(10, "BTC"));
+
+        // create proposal with 0 vote power
+        let proposal = pay_somebody_proposal();
+        let res = app
+            .execute_contract(Addr::unchecked(OWNER), flex_addr.clone(), &proposal, &[])
+            .unwrap();
+
+        // Get the proposal id from the logs
+        let proposal_id: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
+
+        // Only Passed can be executed
+        let execution = ExecuteMsg::Execute {
+            proposal_id,
+            msg_indices: None,
+        };
+        let err = app
+            .execute_contract(Addr::unchecked(OWNER), flex_addr.clone(), &execution, &[])
+            .unwrap_err();
+        assert_eq!(
+            ContractError::WrongExecuteStatus {},
+            err.downcast().unwrap()
+        );
+
+        // Vote it, so it passes
+        let vote = ExecuteMsg::Vote {
+            proposal_id,
+            vote: Vote::Yes,
+        };
+        let res = app
+            .execute_contract(Addr::unchecked(VOTER4), flex_addr.clone(), &vote, &[])
+            .unwrap();
+        assert_eq!(
+            res.custom_attrs(1),
+            [
+                ("action", "vote"),
+                ("sender", VOTER4),
+                ("proposal_id", proposal_id.to_string().as_str()),
+                ("status", "Passed"),
+            ],
+        );
+
+        // In passing: Try to close Passed fails
+        let closing = ExecuteMsg::Close { proposal_id };
+        let err = app
+            .execute_contract(Addr::unchecked(OWNER), flex_addr.clone(), &closing, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::WrongCloseStatus {}, err.downcast().unwrap());
+
+        // Execute works. Anybody can execute Passed proposals
+        let res = app
+            .execute_contract(
+                Addr::unchecked(SOMEBODY),
+                flex_addr.clone(),
+                &execution,
+                &[],
+            )
+            .unwrap();
+        assert_eq!(
+            res.custom_attrs(1),
+            [
+                ("action", "execute"),
+                ("sender", SOMEBODY),
+                ("proposal_id", proposal_id.to_string().as_str()),
+            ],
+        );
+
+        // verify money was transfered
+        let some_bal = app.wrap().query_balance(SOMEBODY, "BTC").unwrap();
+        assert_eq!(some_bal, coin(1, "BTC"));
+        let contract_bal = app.wrap().query_balance(&flex_addr, "BTC").unwrap();
+        assert_eq!(contract_bal, coin(9, "BTC"));
+
+        // In passing: Try to close Executed fails
+        let err = app
+            .execute_contract(Addr::unchecked(OWNER), flex_addr.clone(), &closing, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::WrongCloseStatus {}, err.downcast().unwrap());
+
+        // Trying to execute something that was already executed fails
+        let err = app
+            .execute_contract(Addr::unchecked(SOMEBODY), flex_addr, &execution, &[])
+            .unwrap_err();
+        assert_eq!(
+            ContractError::WrongExecuteStatus {},
+            err.downcast().unwrap()
+        );
+    }
+
+    #[test]
+    fn execute_with_executor_member() {
+        let init_funds = coins(10, "BTC");
+        let mut app = mock_app(&init_funds);
+
+        let threshold = Threshold::ThresholdQuorum {
+            threshold: Decimal::percent(51),
+            quorum: Decimal::percent(1),
+        };
+        let voting_period = Duration::Time(2000000);
+        let (flex_addr, _) = setup_test_case(
+            &mut app,
+            threshold,
+            voting_period,
+            init_funds,
+            true,
+            Some(crate::state::Executor::Member), // set executor as Member of voting group
+            None,
+        );
+
+        // create proposal with 0 vote power
+        let proposal = pay_somebody_proposal();
+        let res = app
+            .execute_contract(Addr::unchecked(OWNER), flex_addr.clone(), &proposal, &[])
+            .unwrap();
+
+        // Get the proposal id from the logs
+        let proposal_id: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
+
+        // Vote it, so it passes
+        let vote = ExecuteMsg::Vote {
+            proposal_id,
+            vote: Vote::Yes,
+        };
+        app.execute_contract(Addr::unchecked(VOTER4), flex_addr.clone(), &vote, &[])
+            .unwrap();
+
+        let execution = ExecuteMsg::Execute {
+            proposal_id,
+            msg_indices: None,
+        };
+        let err = app
+            .execute_contract(
+                Addr::unchecked(Addr::unchecked("anyone")), // anyone is not allowed to execute
+                flex_addr.clone(),
+                &execution,
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+        app.execute_contract(
+            Addr::unchecked(Addr::unchecked(VOTER2)), // member of voting group is allowed to execute
+            flex_addr,
+            &execution,
+            &[],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn execute_with_executor_only() {
+        let init_funds = coins(10, "BTC");
+        let mut app = mock_app(&init_funds);
+
+        let threshold = Threshold::ThresholdQuorum {
+            threshold: Decimal::percent(51),
+            quorum: Decimal::percent(1),
+        };
+        let voting_period = Duration::Time(2000000);
+        let (flex_addr, _) = setup_test_case(
+            &mut app,
+            threshold,
+            voting_period,
+            init_funds,
+            true,
+            Some(crate::state::Executor::Only(Addr::unchecked(VOTER3))), // only VOTER3 can execute proposal
+            None,
+        );
+
+        // create proposal with 0 vote power
+        let proposal = pay_somebody_proposal();
+        let res = app
+            .execute_contract(Addr::unchecked(OWNER), flex_addr.clone(), &proposal, &[])
+            .unwrap();
+
+        // Get the proposal id from the logs
+        let proposal_id: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
+
+        // Vote it, so it passes
+        let vote = ExecuteMsg::Vote {
+            proposal_id,
+            vote: Vote::Yes,
+        };
+        app.execute_contract(Addr::unchecked(VOTER4), flex_addr.clone(), &vote, &[])
+            .unwrap();
+
+        let execution = ExecuteMsg::Execute {
+            proposal_id,
+            msg_indices: None,
+        };
+        let err = app
+            .execute_contract(
+                Addr::unchecked(Addr::unchecked("anyone")), // anyone is not allowed to execute
+                flex_addr.clone(),
+                &execution,
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+        let err = app
+            .execute_contract(
+                Addr::unchecked(Addr::unchecked(VOTER1)), // VOTER1 is not allowed to execute
+                flex_addr.clone(),
+                &execution,
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+        app.execute_contract(
+            Addr::unchecked(Addr::unchecked(VOTER3)), // VOTER3 is allowed to execute
+            flex_addr,
+            &execution,
+            &[],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn proposal_pass_on_expiration() {
+        let init_funds = coins(10, "BTC");
+        let mut app = mock_app(&init_funds);
+
+        let threshold = Threshold::ThresholdQuorum {
+            threshold: Decimal::percent(51),
+            quorum: Decimal::percent(1),
+        };
+        let voting_period = 2000000;
+        let (flex_addr, _) = setup_test_case(
+            &mut app,
+            threshold,
+            Duration::Time(voting_period),
+            init_funds,
+            true,
+            None,
+            None,
+        );
+
+        // ensure we have cash to cover the proposal
+        let contract_bal = app.wrap().query_balance(&flex_addr, "BTC").unwrap();
+        assert_eq!(contract_bal, coin(10, "BTC"));
+
+        // create proposal with 0 vote power
+        let proposal = pay_somebody_proposal();
+        let res = app
+            .execute_contract(Addr::unchecked(OWNER), flex_addr.clone(), &proposal, &[])
+            .unwrap();
+
+        // Get the proposal id from the logs
+        let proposal_id: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
+
+        // Vote it, so it passes after voting period is over
+        let vote = ExecuteMsg::Vote {
+            proposal_id,
+            vote: Vote::Yes,
+        };
+        let res = app
+            .execute_contract(Addr::unchecked(VOTER3), flex_addr.clone(), &vote, &[])
+            .unwrap();
+        assert_eq!(
+            res.custom_attrs(1),
+            [
+                ("action", "vote"),
+                ("sender", VOTER3),
+                ("proposal_id", proposal_id.to_string().as_str()),
+                ("status", "Open"),
+            ],
+        );
+
+        // Wait until the voting period is over.
+        app.update_block(|block| {
+            block.time = block.time.plus_seconds(voting_period);
+            block.height += std::cmp::max(1, voting_period / 5);
+        });
+
+        // Proposal should now be passed.
+        let prop: ProposalResponse = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &QueryMsg::Proposal { proposal_id })
+            .unwrap();
+        assert_eq!(prop.status, Status::Passed);
+
+        // Closing should NOT be possible
+        let err = app
+            .execute_contract(
+                Addr::unchecked(SOMEBODY),
+                flex_addr.clone(),
+                &ExecuteMsg::Close { proposal_id },
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(ContractError::WrongCloseStatus {}, err.downcast().unwrap());
+
+        // Execution should now be possible.
+        let res = app
+            .execute_contract(
+                Addr::unchecked(SOMEBODY),
+                flex_addr,
+                &ExecuteMsg::Execute {
+                    proposal_id,
+                    msg_indices: None,
+                },
+                &[],
+            )
+            .unwrap();
+        assert_eq!(
+            res.custom_attrs(1),
+            [
+                ("action", "execute"),
+                ("sender", SOMEBODY),
+                ("proposal_id", proposal_id.to_string().as_str()),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_close_works() {
+        let init_funds = coins(10, "BTC");
+        let mut app = mock_app(&init_funds);
+
+        let threshold = Threshold::ThresholdQuorum {
+            threshold: Decimal::percent(51),
+            quorum: Decimal::percent(1),
+        };
+        let voting_period = Duration::Height(2000000);
+        let (flex_addr, _) = setup_test_case(
+            &mut app,
+            threshold,
+            voting_period,
+            init_funds,
+            true,
+            None,
+            None,
+        );
+
+        // create proposal with 0 vote power
+        let proposal = pay_somebody_proposal();
+        let res = app
+            .execute_contract(Addr::unchecked(OWNER), flex_addr.clone(), &proposal, &[])
+            .unwrap();
+
+        // Get the proposal id from the logs
+        let proposal_id: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
+
+        // Non-expired proposals cannot be closed
+        let closing = ExecuteMsg::Close { proposal_id };
+        let err = app
+            .execute_contract(Addr::unchecked(SOMEBODY), flex_addr.clone(), &closing, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::NotExpired {}, err.downcast().unwrap());
+
+        // Expired proposals can be closed
+        app.update_block(expire(voting_period));
+        let res = app
+            .execute_contract(Addr::unchecked(SOMEBODY), flex_addr.clone(), &closing, &[])
+            .unwrap();
+        assert_eq!(
+            res.custom_attrs(1),
+            [
+                ("action", "close"),
+                ("sender", SOMEBODY),
+                ("proposal_id", proposal_id.to_string().as_str()),
+            ],
+        );
+
+        // Trying to close it again fails
+        let closing = ExecuteMsg::Close { proposal_id };
+        let err = app
+            .execute_contract(Addr::unchecked(SOMEBODY), flex_addr, &closing, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::WrongCloseStatus {}, err.downcast().unwrap());
+    }
+
+    // uses the power from the beginning of the voting period
+    #[test]
+    fn execute_group_changes_from_external() {
+        let init_funds = coins(10, "BTC");
+        let mut app = mock_app(&init_funds);
+
+        let threshold = Threshold::ThresholdQuorum {
+            threshold: Decimal::percent(51),
+            quorum: Decimal::percent(1),
+        };
+        let voting_period = Duration::Time(20000);
+        let (flex_addr, group_addr) = setup_test_case(
+            &mut app,
+            threshold,
+            voting_period,
+            init_funds,
+            false,
+            None,
+            None,
+        );
+
+        // VOTER1 starts a proposal to send some tokens (1/4 votes)
+        let proposal = pay_somebody_proposal();
+        let res = app
+            .execute_contract(Addr::unchecked(VOTER1), flex_addr.clone(), &proposal, &[])
+            .unwrap();
+        // Get the proposal id from the logs
+        let proposal_id: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
+        let prop_status = |app: &App, proposal_id: u64| -> Status {
+            let query_prop = QueryMsg::Proposal { proposal_id };
+            let prop: ProposalResponse = app
+                .wrap()
+                .query_wasm_smart(&flex_addr, &query_prop)
+                .unwrap();
+            prop.status
+        };
+
+        // 1/4 votes
+        assert_eq!(prop_status(&app, proposal_id), Status::Open);
+
+        // check current threshold (global)
+        let threshold: ThresholdResponse = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &QueryMsg::Threshold {})
+            .unwrap();
+        let expected_thresh = ThresholdResponse::ThresholdQuorum {
+            total_weight: 23,
+            threshold: Decimal::percent(51),
+            quorum: Decimal::percent(1),
+        };
+        assert_eq!(expected_thresh, threshold);
+
+        // a few blocks later...
+        app.update_block(|block| block.height += 2);
+
+        // admin changes the group
+        // updates VOTER2 power to 21 -> with snapshot, vote doesn't pass proposal
+        // adds NEWBIE with 2 power -> with snapshot, invalid vote
+        // removes VOTER3 -> with snapshot, can vote on proposal
+        let newbie: &str = "newbie";
+        let update_msg = cw4_group::msg::ExecuteMsg::UpdateMembers {
+            remove: vec![VOTER3.into()],
+            add: vec![member(VOTER2, 21), member(newbie, 2)],
+        };
+        app.execute_contract(Addr::unchecked(OWNER), group_addr, &update_msg, &[])
+            .unwrap();
+
+        // check membership queries properly updated
+        let query_voter = QueryMsg::Voter {
+            address: VOTER3.into(),
+        };
+        let power: VoterResponse = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &query_voter)
+            .unwrap();
+        assert_eq!(power.weight, None);
+
+        // proposal still open
+        assert_eq!(prop_status(&app, proposal_id), Status::Open);
+
+        // a few blocks later...
+        app.update_block(|block| block.height += 3);
+
+        // make a second proposal
+        let proposal2 = pay_somebody_proposal();
+        let res = app
+            .execute_contract(Addr::unchecked(VOTER1), flex_addr.clone(), &proposal2, &[])
+            .unwrap();
+        // Get the proposal id from the logs
+        let proposal_id2: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
+
+        // VOTER2 can pass this alone with the updated vote (newer height ignores snapshot)
+        let yes_vote = ExecuteMsg::Vote {
+            proposal_id: proposal_id2,
+            vote: Vote::Yes,
+        };
+        app.execute_contract(Addr::unchecked(VOTER2), flex_addr.clone(), &yes_vote, &[])
+            .unwrap();
+        assert_eq!(prop_status(&app, proposal_id2), Status::Passed);
+
+        // VOTER2 can only vote on first proposal with weight of 2 (not enough to pass)
+        let yes_vote = ExecuteMsg::Vote {
+            proposal_id,
+            vote: Vote::Yes,
+        };
+        app.execute_contract(Addr::unchecked(VOTER2), flex_addr.clone(), &yes_vote, &[])
+            .unwrap();
+        assert_eq!(prop_status(&app, proposal_id), Status::Open);
+
+        // newbie cannot vote
+        let err = app
+            .execute_contract(Addr::unchecked(newbie), flex_addr.clone(), &yes_vote, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+        // previously removed VOTER3 can still vote, passing the proposal
+        app.execute_contract(Addr::unchecked(VOTER3), flex_addr.clone(), &yes_vote, &[])
+            .unwrap();
+
+        // check current threshold (global) is updated
+        let threshold: ThresholdResponse = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &QueryMsg::Threshold {})
+            .unwrap();
+        let expected_thresh = ThresholdResponse::ThresholdQuorum {
+            total_weight: 41,
+            threshold: Decimal::percent(51),
+            quorum: Decimal::percent(1),
+        };
+        assert_eq!(expected_thresh, threshold);
+
+        // TODO: check proposal threshold not changed
+    }
+
+    // uses the power from the beginning of the voting period
+    // similar to above - simpler case, but shows that one proposals can
+    // trigger the action
+    #[test]
+    fn execute_group_changes_from_proposal() {
+        let init_funds = coins(10, "BTC");
+        let mut app = mock_app(&init_funds);
+
+        let required_weight = 4;
+        let voting_period = Duration::Time(20000);
+        let (flex_addr, group_addr) =
+            setup_test_case_fixed(&mut app, required_weight, voting_period, init_funds, true);
+
+        // Start a proposal to remove VOTER3 from the set
+        let update_msg = Cw4GroupContract::new(group_addr)
+            .update_members(vec![VOTER3.into()], vec![])
+            .unwrap();
+        let update_proposal = ExecuteMsg::Propose {
+            title: "Kick out VOTER3".to_string(),
+            description: "He's trying to steal our money".to_string(),
+            msgs: vec![update_msg],
             latest: None,
-        }
+            threshold_profile: None,
+            execution_window: None,
+            metadata: None,
+        };
+        let res = app
+            .execute_contract(
+                Addr::unchecked(VOTER1),
+                flex_addr.clone(),
+                &update_proposal,
+                &[],
+            )
+            .unwrap();
+        // Get the proposal id from the logs
+        let update_proposal_id: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
+
+        // next block...
+        app.update_block(|b| b.height += 1);
+
+        // VOTER1 starts a proposal to send some tokens
+        let cash_proposal = pay_somebody_proposal();
+        let res = app
+            .execute_contract(
+                Addr::unchecked(VOTER1),
+                flex_addr.clone(),
+                &cash_proposal,
+                &[],
+            )
+            .unwrap();
+        // Get the proposal id from the logs
+        let cash_proposal_id: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
+        assert_ne!(cash_proposal_id, update_proposal_id);
+
+        // query proposal state
+        let prop_status = |app: &App, proposal_id: u64| -> Status {
+            let query_prop = QueryMsg::Proposal { proposal_id };
+            let prop: ProposalResponse = app
+                .wrap()
+                .query_wasm_smart(&flex_addr, &query_prop)
+                .unwrap();
+            prop.status
+        };
+        assert_eq!(prop_status(&app, cash_proposal_id), Status::Open);
+        assert_eq!(prop_status(&app, update_proposal_id), Status::Open);
+
+        // next block...
+        app.update_block(|b| b.height += 1);
+
+        // Pass and execute first proposal
+        let yes_vote = ExecuteMsg::Vote {
+            proposal_id: update_proposal_id,
+            vote: Vote::Yes,
+        };
+        app.execute_contract(Addr::unchecked(VOTER4), flex_addr.clone(), &yes_vote, &[])
+            .unwrap();
+        let execution = ExecuteMsg::Execute {
+            proposal_id: update_proposal_id,
+            msg_indices: None,
+        };
+        app.execute_contract(Addr::unchecked(VOTER4), flex_addr.clone(), &execution, &[])
+            .unwrap();
+
+        // ensure that the update_proposal is executed, but the other unchanged
+        assert_eq!(prop_status(&app, update_proposal_id), Status::Executed);
+        assert_eq!(prop_status(&app, cash_proposal_id), Status::Open);
+
+        // next block...
+        app.update_block(|b| b.height += 1);
+
+        // VOTER3 can still pass the cash proposal
+        // voting on it fails
+        let yes_vote = ExecuteMsg::Vote {
+            proposal_id: cash_proposal_id,
+            vote: Vote::Yes,
+        };
+        app.execute_contract(Addr::unchecked(VOTER3), flex_addr.clone(), &yes_vote, &[])
+            .unwrap();
+        assert_eq!(prop_status(&app, cash_proposal_id), Status::Passed);
+
+        // but cannot open a new one
+        let cash_proposal = pay_somebody_proposal();
+        let err = app
+            .execute_contract(
+                Addr::unchecked(VOTER3),
+                flex_addr.clone(),
+                &cash_proposal,
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+        // extra: ensure no one else can call the hook
+        let hook_hack = ExecuteMsg::MemberChangedHook(MemberChangedHookMsg::one(
+            MemberDiff::new(VOTER1, Some(1), None),
+            TotalWeightDiff::new(1, 0),
+        ));
+        let err = app
+            .execute_contract(Addr::unchecked(VOTER2), flex_addr.clone(), &hook_hack, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+    }
+
+    // uses the power from the beginning of the voting period
+    #[test]
+    fn percentage_handles_group_changes() {
+        let init_funds = coins(10, "BTC");
+        let mut app = mock_app(&init_funds);
+
+        // 51% required, which is 12 of the initial 24
+        let threshold = Threshold::ThresholdQuorum {
+            threshold: Decimal::percent(51),
+            quorum: Decimal::percent(1),
+        };
+        let voting_period = Duration::Time(20000);
+        let (flex_addr, group_addr) = setup_test_case(
+            &mut app,
+            threshold,
+            voting_period,
+            init_funds,
+            false,
+            None,
+            None,
+        );
+
+        // VOTER3 starts a proposal to send some tokens (3/12 votes)
+        let proposal = pay_somebody_proposal();
+        let res = app
+            .execute_contract(Addr::unchecked(VOTER3), flex_addr.clone(), &proposal, &[])
+            .unwrap();
+        // Get the proposal id from the logs
+        let proposal_id: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
+        let prop_status = |app: &App| -> Status {
+            let query_prop = QueryMsg::Proposal { proposal_id };
+            let prop: ProposalResponse = app
+                .wrap()
+                .query_wasm_smart(&flex_addr, &query_prop)
+                .unwrap();
+            prop.status
+        };
+
+        // 3/12 votes
+        assert_eq!(prop_status(&app), Status::Open);
+
+        // a few blocks later...
+        app.update_block(|block| block.height += 2);
+
+        // admin changes the group (3 -> 0, 2 -> 9, 0 -> 29) - total = 56, require 29 to pass
+        let newbie: &str = "newbie";
+        let update_msg = cw4_group::msg::ExecuteMsg::UpdateMembers {
+            remove: vec![VOTER3.into()],
+            add: vec![member(VOTER2, 9), member(newbie, 29)],
+        };
+        app.execute_contract(Addr::unchecked(OWNER), group_addr, &update_msg, &[])
+            .unwrap();
+
+        // a few blocks later...
+        app.update_block(|block| block.height += 3);
+
+        // VOTER2 votes according to original weights: 3 + 2 = 5 / 12 => Open
+        // with updated weights, it would be 3 + 9 = 12 / 12 => Passed
+        let yes_vote = ExecuteMsg::Vote {
+            proposal_id,
+            vote: Vote::Yes,
+        };
+        app.execute_contract(Addr::unchecked(VOTER2), flex_addr.clone(), &yes_vote, &[])
+            .unwrap();
+        assert_eq!(prop_status(&app), Status::Open);
+
+        // new proposal can be passed single-handedly by newbie
+        let proposal = pay_somebody_proposal();
+        let res = app
+            .execute_contract(Addr::unchecked(newbie), flex_addr.clone(), &proposal, &[])
+            .unwrap();
+        // Get the proposal id from the logs
+        let proposal_id2: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
+
+        // check proposal2 status
+        let query_prop = QueryMsg::Proposal {
+            proposal_id: proposal_id2,
+        };
+        let prop: ProposalResponse = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &query_prop)
+            .unwrap();
+        assert_eq!(Status::Passed, prop.status);
     }
 
+    // uses the power from the beginning of the voting period
     #[test]
-    fn test_instantiate_works() {
-        let mut app = mock_app(&[]);
+    fn quorum_handles_group_changes() {
+        let init_funds = coins(10, "BTC");
+        let mut app = mock_app(&init_funds);
+
+        // 33% required for quora, which is 8 of the initial 24
+        // 50% yes required to pass early (12 of the initial 24)
+        let voting_period = Duration::Time(20000);
+        let (flex_addr, group_addr) = setup_test_case(
+            &mut app,
+            Threshold::ThresholdQuorum {
+                threshold: Decimal::percent(51),
+                quorum: Decimal::percent(33),
+            },
+            voting_period,
+            init_funds,
+            false,
+            None,
+            None,
+        );
+
+        // VOTER3 starts a proposal to send some tokens (3 votes)
+        let proposal = pay_somebody_proposal();
+        let res = app
+            .execute_contract(Addr::unchecked(VOTER3), flex_addr.clone(), &proposal, &[])
+            .unwrap();
+        // Get the proposal id from the logs
+        let proposal_id: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
+        let prop_status = |app: &App| -> Status {
+            let query_prop = QueryMsg::Proposal { proposal_id };
+            let prop: ProposalResponse = app
+                .wrap()
+                .query_wasm_smart(&flex_addr, &query_prop)
+                .unwrap();
+            prop.status
+        };
+
+        // 3/12 votes - not expired
+        assert_eq!(prop_status(&app), Status::Open);
+
+        // a few blocks later...
+        app.update_block(|block| block.height += 2);
+
+        // admin changes the group (3 -> 0, 2 -> 9, 0 -> 28) - total = 55, require 28 to pass
+        let newbie: &str = "newbie";
+        let update_msg = cw4_group::msg::ExecuteMsg::UpdateMembers {
+            remove: vec![VOTER3.into()],
+            add: vec![member(VOTER2, 9), member(newbie, 29)],
+        };
+        app.execute_contract(Addr::unchecked(OWNER), group_addr, &update_msg, &[])
+            .unwrap();
+
+        // a few blocks later...
+        app.update_block(|block| block.height += 3);
+
+        // VOTER2 votes yes, according to original weights: 3 yes, 2 no, 5 total (will fail when expired)
+        // with updated weights, it would be 3 yes, 9 yes, 11 total (will pass when expired)
+        let yes_vote = ExecuteMsg::Vote {
+            proposal_id,
+            vote: Vote::Yes,
+        };
+        app.execute_contract(Addr::unchecked(VOTER2), flex_addr.clone(), &yes_vote, &[])
+            .unwrap();
+        // not expired yet
+        assert_eq!(prop_status(&app), Status::Open);
+
+        // wait until the vote is over, and see it was rejected
+        app.update_block(expire(voting_period));
+        assert_eq!(prop_status(&app), Status::Rejected);
+    }
+
+    #[test]
+    fn quorum_enforced_even_if_absolute_threshold_met() {
+        let init_funds = coins(10, "BTC");
+        let mut app = mock_app(&init_funds);
+
+        // 33% required for quora, which is 5 of the initial 15
+        // 50% yes required to pass early (8 of the initial 15)
+        let voting_period = Duration::Time(20000);
+        let (flex_addr, _) = setup_test_case(
+            &mut app,
+            // note that 60% yes is not enough to pass without 20% no as well
+            Threshold::ThresholdQuorum {
+                threshold: Decimal::percent(60),
+                quorum: Decimal::percent(80),
+            },
+            voting_period,
+            init_funds,
+            false,
+            None,
+            None,
+        );
+
+        // create proposal
+        let proposal = pay_somebody_proposal();
+        let res = app
+            .execute_contract(Addr::unchecked(VOTER5), flex_addr.clone(), &proposal, &[])
+            .unwrap();
+        // Get the proposal id from the logs
+        let proposal_id: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
+        let prop_status = |app: &App| -> Status {
+            let query_prop = QueryMsg::Proposal { proposal_id };
+            let prop: ProposalResponse = app
+                .wrap()
+                .query_wasm_smart(&flex_addr, &query_prop)
+                .unwrap();
+            prop.status
+        };
+        assert_eq!(prop_status(&app), Status::Open);
+        app.update_block(|block| block.height += 3);
+
+        // reach 60% of yes votes, not enough to pass early (or late)
+        let yes_vote = ExecuteMsg::Vote {
+            proposal_id,
+            vote: Vote::Yes,
+        };
+        app.execute_contract(Addr::unchecked(VOTER4), flex_addr.clone(), &yes_vote, &[])
+            .unwrap();
+        // 9 of 15 is 60% absolute threshold, but less than 12 (80% quorum needed)
+        assert_eq!(prop_status(&app), Status::Open);
+
+        // add 3 weight no vote and we hit quorum and this passes
+        let no_vote = ExecuteMsg::Vote {
+            proposal_id,
+            vote: Vote::No,
+        };
+        app.execute_contract(Addr::unchecked(VOTER3), flex_addr.clone(), &no_vote, &[])
+            .unwrap();
+        assert_eq!(prop_status(&app), Status::Passed);
+    }
+
+    #[test]
+    fn test_instantiate_with_invalid_deposit() {
+        let mut app = App::default();
 
-        // make a simple group
-        let group_addr = instantiate_group(&mut app, vec![member(OWNER, 1)]);
         let flex_id = app.store_code(contract_flex());
 
-        let max_voting_period = Duration::Time(1234567);
+        let group_addr = instantiate_group(
+            &mut app,
+            vec![Member {
+                addr: OWNER.to_string(),
+                weight: 10,
+            }],
+        );
 
-        // Zero required weight fails
-        let instantiate_msg = InstantiateMsg {
+        // Instantiate with an invalid cw20 token.
+        let instantiate = InstantiateMsg {
             group_addr: group_addr.to_string(),
-            threshold: Threshold::ThresholdQuorum {
-                threshold: Decimal::zero(),
-                quorum: Decimal::percent(1),
-            },
-            max_voting_period,
+            threshold: Threshold::AbsoluteCount { weight: 10 },
+            max_voting_period: Duration::Time(10),
             executor: None,
-            proposal_deposit: None,
+            proposal_deposit: Some(UncheckedDepositInfo {
+                amount: Uint128::new(1),
+                refund_failed_proposals: true,
+                refund_passed_proposals: true,
+                forfeit: None,
+                denom: UncheckedDenom::Cw20(group_addr.to_string()),
+            }),
+            veto: None,
+            execution_delay: None,
+            close_reward: None,
+            threshold_profiles: vec![],
+            allow_revoting: false,
+            proposers: None,
+            extra_groups: vec![],
+            emergency: None,
+            quadratic_voting: false,
+            timelock: None,
+            private_voting: None,
         };
-        let err = app
+
+        let err: ContractError = app
             .instantiate_contract(
                 flex_id,
                 Addr::unchecked(OWNER),
-                &instantiate_msg,
+                &instantiate,
                 &[],
-                "zero required weight",
+                "Bad cw20",
                 None,
             )
-            .unwrap_err();
-        assert_eq!(
-            ContractError::Threshold(cw_utils::ThresholdError::InvalidThreshold {}),
-            err.downcast().unwrap()
-        );
+            .unwrap_err()
+            .downcast()
+            .unwrap();
 
-        // Total weight less than required weight not allowed
-        let instantiate_msg = InstantiateMsg {
+        assert_eq!(err, ContractError::Deposit(DepositError::InvalidCw20 {}));
+
+        // Instantiate with a zero amount.
+        let instantiate = InstantiateMsg {
             group_addr: group_addr.to_string(),
-            threshold: Threshold::AbsoluteCount { weight: 100 },
-            max_voting_period,
+            threshold: Threshold::AbsoluteCount { weight: 10 },
+            max_voting_period: Duration::Time(10),
             executor: None,
-            proposal_deposit: None,
+            proposal_deposit: Some(UncheckedDepositInfo {
+                amount: Uint128::zero(),
+                refund_failed_proposals: true,
+                refund_passed_proposals: true,
+                forfeit: None,
+                denom: UncheckedDenom::Native("native".to_string()),
+            }),
+            veto: None,
+            execution_delay: None,
+            close_reward: None,
+            threshold_profiles: vec![],
+            allow_revoting: false,
+            proposers: None,
+            extra_groups: vec![],
+            emergency: None,
+            quadratic_voting: false,
+            timelock: None,
+            private_voting: None,
         };
-        let err = app
+
+        let err: ContractError = app
             .instantiate_contract(
                 flex_id,
                 Addr::unchecked(OWNER),
-                &instantiate_msg,
+                &instantiate,
                 &[],
-                "high required weight",
+                "Bad cw20",
                 None,
             )
-            .unwrap_err();
-        assert_eq!(
-            ContractError::Threshold(cw_utils::ThresholdError::UnreachableWeight {}),
-            err.downcast().unwrap()
-        );
+            .unwrap_err()
+            .downcast()
+            .unwrap();
 
-        // All valid
-        let instantiate_msg = InstantiateMsg {
-            group_addr: group_addr.to_string(),
-            threshold: Threshold::AbsoluteCount { weight: 1 },
-            max_voting_period,
-            executor: None,
-            proposal_deposit: None,
-        };
-        let flex_addr = app
+        assert_eq!(err, ContractError::Deposit(DepositError::ZeroDeposit {}))
+    }
+
+    #[test]
+    fn test_cw20_proposal_deposit() {
+        let mut app = App::default();
+
+        let cw20_id = app.store_code(contract_cw20());
+
+        let cw20_addr = app
             .instantiate_contract(
-                flex_id,
+                cw20_id,
                 Addr::unchecked(OWNER),
-                &instantiate_msg,
+                &cw20_base::msg::InstantiateMsg {
+                    name: "Token".to_string(),
+                    symbol: "TOKEN".to_string(),
+                    decimals: 6,
+                    initial_balances: vec![
+                        Cw20Coin {
+                            address: VOTER4.to_string(),
+                            amount: Uint128::new(10),
+                        },
+                        Cw20Coin {
+                            address: OWNER.to_string(),
+                            amount: Uint128::new(10),
+                        },
+                    ],
+                    mint: None,
+                    marketing: None,
+                    snapshots: false,
+                    freezer: None,
+                    pauser: None,
+                },
                 &[],
-                "all good",
+                "Token",
                 None,
             )
             .unwrap();
 
-        // Verify contract version set properly
-        let version = query_contract_info(&app.wrap(), flex_addr.clone()).unwrap();
-        assert_eq!(
-            ContractVersion {
-                contract: CONTRACT_NAME.to_string(),
-                version: CONTRACT_VERSION.to_string(),
-            },
-            version,
+        let (flex_addr, _) = setup_test_case(
+            &mut app,
+            Threshold::AbsoluteCount { weight: 10 },
+            Duration::Height(10),
+            vec![],
+            true,
+            None,
+            Some(UncheckedDepositInfo {
+                amount: Uint128::new(10),
+                denom: UncheckedDenom::Cw20(cw20_addr.to_string()),
+                refund_failed_proposals: true,
+                refund_passed_proposals: true,
+                forfeit: None,
+            }),
         );
 
-        // Get voters query
-        let voters: VoterListResponse = app
+        app.execute_contract(
+            Addr::unchecked(VOTER4),
+            cw20_addr.clone(),
+            &cw20::Cw20ExecuteMsg::IncreaseAllowance {
+                spender: flex_addr.to_string(),
+                amount: Uint128::new(10),
+                expires: None,
+            },
+            &[],
+        )
+        .unwrap();
+
+        // Make a proposal that will pass.
+        let proposal = text_proposal();
+        app.execute_contract(Addr::unchecked(VOTER4), flex_addr.clone(), &proposal, &[])
+            .unwrap();
+
+        // Make sure the deposit was transfered.
+        let balance: cw20::BalanceResponse = app
             .wrap()
             .query_wasm_smart(
-                &flex_addr,
-                &QueryMsg::ListVoters {
-                    start_after: None,
-                    limit: None,
+                cw20_addr.clone(),
+                &cw20::Cw20QueryMsg::Balance {
+                    address: VOTER4.to_string(),
                 },
             )
             .unwrap();
-        assert_eq!(
-            voters.voters,
-            vec![VoterDetail {
-                addr: OWNER.into(),
-                weight: 1
-            }]
-        );
-    }
+        assert_eq!(balance.balance, Uint128::zero());
 
-    #[test]
-    fn test_propose_works() {
-        let init_funds = coins(10, "BTC");
-        let mut app = mock_app(&init_funds);
+        let balance: cw20::BalanceResponse = app
+            .wrap()
+            .query_wasm_smart(
+                cw20_addr.clone(),
+                &cw20::Cw20QueryMsg::Balance {
+                    address: flex_addr.to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(balance.balance, Uint128::new(10));
 
-        let required_weight = 4;
-        let voting_period = Duration::Time(2000000);
-        let (flex_addr, _) =
-            setup_test_case_fixed(&mut app, required_weight, voting_period, init_funds, false);
+        app.execute_contract(
+            Addr::unchecked(VOTER4),
+            flex_addr.clone(),
+            &ExecuteMsg::Execute {
+                proposal_id: 1,
+                msg_indices: None,
+            },
+            &[],
+        )
+        .unwrap();
 
-        let proposal = pay_somebody_proposal();
-        // Only voters can propose
-        let err = app
-            .execute_contract(Addr::unchecked(SOMEBODY), flex_addr.clone(), &proposal, &[])
-            .unwrap_err();
-        assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+        // Make sure the deposit was returned.
+        let balance: cw20::BalanceResponse = app
+            .wrap()
+            .query_wasm_smart(
+                cw20_addr.clone(),
+                &cw20::Cw20QueryMsg::Balance {
+                    address: VOTER4.to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(balance.balance, Uint128::new(10));
 
-        // Wrong expiration option fails
-        let msgs = match proposal.clone() {
-            ExecuteMsg::Propose { msgs, .. } => msgs,
-            _ => panic!("Wrong variant"),
-        };
-        let proposal_wrong_exp = ExecuteMsg::Propose {
-            title: "Rewarding somebody".to_string(),
-            description: "Do we reward her?".to_string(),
-            msgs,
-            latest: Some(Expiration::AtHeight(123456)),
-        };
-        let err = app
-            .execute_contract(
-                Addr::unchecked(OWNER),
-                flex_addr.clone(),
-                &proposal_wrong_exp,
-                &[],
+        let balance: cw20::BalanceResponse = app
+            .wrap()
+            .query_wasm_smart(
+                cw20_addr.clone(),
+                &cw20::Cw20QueryMsg::Balance {
+                    address: flex_addr.to_string(),
+                },
             )
-            .unwrap_err();
-        assert_eq!(ContractError::WrongExpiration {}, err.downcast().unwrap());
+            .unwrap();
+        assert_eq!(balance.balance, Uint128::zero());
 
-        // Proposal from voter works
-        let res = app
-            .execute_contract(Addr::unchecked(VOTER3), flex_addr.clone(), &proposal, &[])
+        app.execute_contract(
+            Addr::unchecked(OWNER),
+            cw20_addr.clone(),
+            &cw20::Cw20ExecuteMsg::IncreaseAllowance {
+                spender: flex_addr.to_string(),
+                amount: Uint128::new(10),
+                expires: None,
+            },
+            &[],
+        )
+        .unwrap();
+
+        // Make a proposal that fails.
+        let proposal = text_proposal();
+        app.execute_contract(Addr::unchecked(OWNER), flex_addr.clone(), &proposal, &[])
             .unwrap();
-        assert_eq!(
-            res.custom_attrs(1),
-            [
-                ("action", "propose"),
-                ("sender", VOTER3),
-                ("proposal_id", "1"),
-                ("status", "Open"),
-            ],
-        );
 
-        // Proposal from voter with enough vote power directly passes
-        let res = app
-            .execute_contract(Addr::unchecked(VOTER4), flex_addr, &proposal, &[])
+        // Check that the deposit was transfered.
+        let balance: cw20::BalanceResponse = app
+            .wrap()
+            .query_wasm_smart(
+                cw20_addr.clone(),
+                &cw20::Cw20QueryMsg::Balance {
+                    address: flex_addr.to_string(),
+                },
+            )
             .unwrap();
-        assert_eq!(
-            res.custom_attrs(1),
-            [
-                ("action", "propose"),
-                ("sender", VOTER4),
-                ("proposal_id", "2"),
-                ("status", "Passed"),
-            ],
-        );
-    }
+        assert_eq!(balance.balance, Uint128::new(10));
 
-    fn get_tally(app: &App, flex_addr: &str, proposal_id: u64) -> u64 {
-        // Get all the voters on the proposal
-        let voters = QueryMsg::ListVotes {
-            proposal_id,
-            start_after: None,
-            limit: None,
-        };
-        let votes: VoteListResponse = app.wrap().query_wasm_smart(flex_addr, &voters).unwrap();
-        // Sum the weights of the Yes votes to get the tally
-        votes
-            .votes
-            .iter()
-            .filter(|&v| v.vote == Vote::Yes)
-            .map(|v| v.weight)
-            .sum()
-    }
+        // Fail the proposal.
+        app.execute_contract(
+            Addr::unchecked(VOTER4),
+            flex_addr.clone(),
+            &ExecuteMsg::Vote {
+                proposal_id: 2,
+                vote: Vote::No,
+            },
+            &[],
+        )
+        .unwrap();
 
-    fn expire(voting_period: Duration) -> impl Fn(&mut BlockInfo) {
-        move |block: &mut BlockInfo| {
-            match voting_period {
-                Duration::Time(duration) => block.time = block.time.plus_seconds(duration + 1),
-                Duration::Height(duration) => block.height += duration + 1,
-            };
-        }
-    }
+        // Expire the proposal.
+        app.update_block(|b| b.height += 10);
 
-    fn unexpire(voting_period: Duration) -> impl Fn(&mut BlockInfo) {
-        move |block: &mut BlockInfo| {
-            match voting_period {
-                Duration::Time(duration) => {
-                    block.time =
-                        Timestamp::from_nanos(block.time.nanos() - (duration * 1_000_000_000));
-                }
-                Duration::Height(duration) => block.height -= duration,
-            };
-        }
+        app.execute_contract(
+            Addr::unchecked(VOTER4),
+            flex_addr,
+            &ExecuteMsg::Close { proposal_id: 2 },
+            &[],
+        )
+        .unwrap();
+
+        // Make sure the deposit was returned despite the proposal failing.
+        let balance: cw20::BalanceResponse = app
+            .wrap()
+            .query_wasm_smart(
+                cw20_addr,
+                &cw20::Cw20QueryMsg::Balance {
+                    address: VOTER4.to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(balance.balance, Uint128::new(10));
     }
 
     #[test]
-    fn test_proposal_queries() {
-        let init_funds = coins(10, "BTC");
-        let mut app = mock_app(&init_funds);
+    fn proposal_deposit_no_failed_refunds() {
+        let mut app = App::default();
 
-        let voting_period = Duration::Time(2000000);
-        let threshold = Threshold::ThresholdQuorum {
-            threshold: Decimal::percent(80),
-            quorum: Decimal::percent(20),
-        };
         let (flex_addr, _) = setup_test_case(
             &mut app,
-            threshold,
-            voting_period,
-            init_funds,
-            false,
-            None,
+            Threshold::AbsoluteCount { weight: 10 },
+            Duration::Height(10),
+            vec![],
+            true,
             None,
+            Some(UncheckedDepositInfo {
+                amount: Uint128::new(10),
+                denom: UncheckedDenom::Native("TOKEN".to_string()),
+                refund_failed_proposals: false,
+                refund_passed_proposals: true,
+                forfeit: None,
+            }),
         );
 
-        // create proposal with 1 vote power
-        let proposal = pay_somebody_proposal();
-        let res = app
-            .execute_contract(Addr::unchecked(VOTER1), flex_addr.clone(), &proposal, &[])
-            .unwrap();
-        let proposal_id1: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
+        app.sudo(SudoMsg::Bank(BankSudo::Mint {
+            to_address: OWNER.to_string(),
+            amount: vec![Coin {
+                amount: Uint128::new(10),
+                denom: "TOKEN".to_string(),
+            }],
+        }))
+        .unwrap();
 
-        // another proposal immediately passes
-        app.update_block(next_block);
-        let proposal = pay_somebody_proposal();
-        let res = app
-            .execute_contract(Addr::unchecked(VOTER4), flex_addr.clone(), &proposal, &[])
+        // Make a proposal that fails.
+        let proposal = text_proposal();
+        app.execute_contract(
+            Addr::unchecked(OWNER),
+            flex_addr.clone(),
+            &proposal,
+            &[Coin {
+                amount: Uint128::new(10),
+                denom: "TOKEN".to_string(),
+            }],
+        )
+        .unwrap();
+
+        // Check that the deposit was transfered.
+        let balance = app
+            .wrap()
+            .query_balance(OWNER, "TOKEN".to_string())
             .unwrap();
-        let proposal_id2: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
+        assert_eq!(balance.amount, Uint128::zero());
 
-        // expire them both
-        app.update_block(expire(voting_period));
+        // Fail the proposal.
+        app.execute_contract(
+            Addr::unchecked(VOTER4),
+            flex_addr.clone(),
+            &ExecuteMsg::Vote {
+                proposal_id: 1,
+                vote: Vote::No,
+            },
+            &[],
+        )
+        .unwrap();
 
-        // add one more open proposal, 2 votes
-        let proposal = pay_somebody_proposal();
-        let res = app
-            .execute_contract(Addr::unchecked(VOTER2), flex_addr.clone(), &proposal, &[])
+        // Expire the proposal.
+        app.update_block(|b| b.height += 10);
+
+        app.execute_contract(
+            Addr::unchecked(VOTER4),
+            flex_addr,
+            &ExecuteMsg::Close { proposal_id: 1 },
+            &[],
+        )
+        .unwrap();
+
+        // Check that the deposit wasn't returned.
+        let balance = app
+            .wrap()
+            .query_balance(OWNER, "TOKEN".to_string())
             .unwrap();
-        let proposal_id3: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
-        let proposed_at = app.block_info();
+        assert_eq!(balance.amount, Uint128::zero());
+    }
 
-        // next block, let's query them all... make sure status is properly updated (1 should be rejected in query)
-        app.update_block(next_block);
-        let list_query = QueryMsg::ListProposals {
-            start_after: None,
-            limit: None,
-        };
-        let res: ProposalListResponse = app
+    #[test]
+    fn test_native_proposal_deposit() {
+        let mut app = App::default();
+
+        app.sudo(SudoMsg::Bank(BankSudo::Mint {
+            to_address: VOTER4.to_string(),
+            amount: vec![Coin {
+                amount: Uint128::new(10),
+                denom: "TOKEN".to_string(),
+            }],
+        }))
+        .unwrap();
+
+        app.sudo(SudoMsg::Bank(BankSudo::Mint {
+            to_address: OWNER.to_string(),
+            amount: vec![Coin {
+                amount: Uint128::new(10),
+                denom: "TOKEN".to_string(),
+            }],
+        }))
+        .unwrap();
+
+        let (flex_addr, _) = setup_test_case(
+            &mut app,
+            Threshold::AbsoluteCount { weight: 10 },
+            Duration::Height(10),
+            vec![],
+            true,
+            None,
+            Some(UncheckedDepositInfo {
+                amount: Uint128::new(10),
+                denom: UncheckedDenom::Native("TOKEN".to_string()),
+                refund_failed_proposals: true,
+                refund_passed_proposals: true,
+                forfeit: None,
+            }),
+        );
+
+        // Make a proposal that will pass.
+        let proposal = text_proposal();
+        app.execute_contract(
+            Addr::unchecked(VOTER4),
+            flex_addr.clone(),
+            &proposal,
+            &[Coin {
+                amount: Uint128::new(10),
+                denom: "TOKEN".to_string(),
+            }],
+        )
+        .unwrap();
+
+        // Make sure the deposit was transfered.
+        let balance = app
             .wrap()
-            .query_wasm_smart(&flex_addr, &list_query)
+            .query_balance(flex_addr.clone(), "TOKEN")
             .unwrap();
-        assert_eq!(3, res.proposals.len());
+        assert_eq!(balance.amount, Uint128::new(10));
 
-        // check the id and status are properly set
-        let info: Vec<_> = res.proposals.iter().map(|p| (p.id, p.status)).collect();
-        let expected_info = vec![
-            (proposal_id1, Status::Rejected),
-            (proposal_id2, Status::Passed),
-            (proposal_id3, Status::Open),
-        ];
-        assert_eq!(expected_info, info);
+        app.execute_contract(
+            Addr::unchecked(VOTER4),
+            flex_addr.clone(),
+            &ExecuteMsg::Execute {
+                proposal_id: 1,
+                msg_indices: None,
+            },
+            &[],
+        )
+        .unwrap();
 
-        // ensure the common features are set
-        let (expected_msgs, expected_title, expected_description) = proposal_info();
-        for prop in res.proposals {
-            assert_eq!(prop.title, expected_title);
-            assert_eq!(prop.description, expected_description);
-            assert_eq!(prop.msgs, expected_msgs);
-        }
+        // Make sure the deposit was returned.
+        let balance = app.wrap().query_balance(VOTER4, "TOKEN").unwrap();
+        assert_eq!(balance.amount, Uint128::new(10));
 
-        // reverse query can get just proposal_id3
-        let list_query = QueryMsg::ReverseProposals {
-            start_before: None,
-            limit: Some(1),
-        };
-        let res: ProposalListResponse = app
+        // Make a proposal that fails.
+        let proposal = text_proposal();
+        app.execute_contract(
+            Addr::unchecked(OWNER),
+            flex_addr.clone(),
+            &proposal,
+            &[Coin {
+                amount: Uint128::new(10),
+                denom: "TOKEN".to_string(),
+            }],
+        )
+        .unwrap();
+
+        let balance = app
             .wrap()
-            .query_wasm_smart(&flex_addr, &list_query)
+            .query_balance(flex_addr.clone(), "TOKEN")
             .unwrap();
-        assert_eq!(1, res.proposals.len());
+        assert_eq!(balance.amount, Uint128::new(10));
 
-        let (msgs, title, description) = proposal_info();
-        let expected = ProposalResponse {
-            id: proposal_id3,
-            title,
-            description,
-            msgs,
-            expires: voting_period.after(&proposed_at),
-            status: Status::Open,
-            threshold: ThresholdResponse::ThresholdQuorum {
-                total_weight: 23,
-                threshold: Decimal::percent(80),
-                quorum: Decimal::percent(20),
+        // Fail the proposal.
+        app.execute_contract(
+            Addr::unchecked(VOTER4),
+            flex_addr.clone(),
+            &ExecuteMsg::Vote {
+                proposal_id: 2,
+                vote: Vote::No,
             },
-            proposer: Addr::unchecked(VOTER2),
-            deposit: None,
-        };
-        assert_eq!(&expected, &res.proposals[0]);
+            &[],
+        )
+        .unwrap();
+
+        // Expire the proposal.
+        app.update_block(|b| b.height += 10);
+
+        app.execute_contract(
+            Addr::unchecked(VOTER4),
+            flex_addr,
+            &ExecuteMsg::Close { proposal_id: 2 },
+            &[],
+        )
+        .unwrap();
+
+        // Make sure the deposit was returned despite the proposal failing.
+        let balance = app.wrap().query_balance(OWNER, "TOKEN").unwrap();
+        assert_eq!(balance.amount, Uint128::new(10));
     }
 
-    #[test]
-    fn test_vote_works() {
-        let init_funds = coins(10, "BTC");
-        let mut app = mock_app(&init_funds);
+    #[test]
+    fn proposal_deposit_forfeited_on_rejection() {
+        let mut app = App::default();
 
-        let threshold = Threshold::ThresholdQuorum {
-            threshold: Decimal::percent(51),
-            quorum: Decimal::percent(1),
-        };
-        let voting_period = Duration::Time(2000000);
         let (flex_addr, _) = setup_test_case(
             &mut app,
-            threshold,
-            voting_period,
-            init_funds,
-            false,
-            None,
+            Threshold::AbsoluteCount { weight: 10 },
+            Duration::Height(10),
+            vec![],
+            true,
             None,
+            Some(UncheckedDepositInfo {
+                amount: Uint128::new(10),
+                denom: UncheckedDenom::Native("TOKEN".to_string()),
+                refund_failed_proposals: false,
+                refund_passed_proposals: true,
+                forfeit: Some(UncheckedDepositForfeit::Send {
+                    recipient: SOMEBODY.to_string(),
+                }),
+            }),
         );
 
-        // create proposal with 0 vote power
-        let proposal = pay_somebody_proposal();
-        let res = app
-            .execute_contract(Addr::unchecked(OWNER), flex_addr.clone(), &proposal, &[])
-            .unwrap();
-
-        // Get the proposal id from the logs
-        let proposal_id: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
+        app.sudo(SudoMsg::Bank(BankSudo::Mint {
+            to_address: OWNER.to_string(),
+            amount: coins(10, "TOKEN"),
+        }))
+        .unwrap();
 
-        // Owner with 0 voting power cannot vote
-        let yes_vote = ExecuteMsg::Vote {
-            proposal_id,
-            vote: Vote::Yes,
-        };
-        let err = app
-            .execute_contract(Addr::unchecked(OWNER), flex_addr.clone(), &yes_vote, &[])
-            .unwrap_err();
-        assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+        // Make a proposal, holding the deposit.
+        app.execute_contract(
+            Addr::unchecked(OWNER),
+            flex_addr.clone(),
+            &text_proposal(),
+            &coins(10, "TOKEN"),
+        )
+        .unwrap();
+        let prop: ProposalResponse = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &QueryMsg::Proposal { proposal_id: 1 })
+            .unwrap();
+        assert_eq!(prop.deposit_status, Some(DepositStatus::Held));
 
-        // Only voters can vote
-        let err = app
-            .execute_contract(Addr::unchecked(SOMEBODY), flex_addr.clone(), &yes_vote, &[])
-            .unwrap_err();
-        assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+        // Reject it by votes, before it expires.
+        for voter in [VOTER3, VOTER4] {
+            app.execute_contract(
+                Addr::unchecked(voter),
+                flex_addr.clone(),
+                &ExecuteMsg::Vote {
+                    proposal_id: 1,
+                    vote: Vote::No,
+                },
+                &[],
+            )
+            .unwrap();
+        }
 
-        // But voter1 can
-        let res = app
-            .execute_contract(Addr::unchecked(VOTER1), flex_addr.clone(), &yes_vote, &[])
+        // Closing it settles the deposit, sending it to the forfeit recipient.
+        app.execute_contract(
+            Addr::unchecked(VOTER1),
+            flex_addr.clone(),
+            &ExecuteMsg::Close { proposal_id: 1 },
+            &[],
+        )
+        .unwrap();
+        let balance = app.wrap().query_balance(SOMEBODY, "TOKEN").unwrap();
+        assert_eq!(balance.amount, Uint128::new(10));
+        let prop: ProposalResponse = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &QueryMsg::Proposal { proposal_id: 1 })
             .unwrap();
-        assert_eq!(
-            res.custom_attrs(1),
-            [
-                ("action", "vote"),
-                ("sender", VOTER1),
-                ("proposal_id", proposal_id.to_string().as_str()),
-                ("status", "Open"),
-            ],
-        );
+        assert_eq!(prop.status, Status::Rejected);
+        assert_eq!(prop.deposit_status, Some(DepositStatus::Forfeited));
 
-        // VOTER1 cannot vote again
+        // But only once.
         let err = app
-            .execute_contract(Addr::unchecked(VOTER1), flex_addr.clone(), &yes_vote, &[])
+            .execute_contract(
+                Addr::unchecked(VOTER1),
+                flex_addr,
+                &ExecuteMsg::Close { proposal_id: 1 },
+                &[],
+            )
             .unwrap_err();
-        assert_eq!(ContractError::AlreadyVoted {}, err.downcast().unwrap());
+        assert_eq!(ContractError::WrongCloseStatus {}, err.downcast().unwrap());
+    }
 
-        // No/Veto votes have no effect on the tally
-        // Compute the current tally
-        let tally = get_tally(&app, flex_addr.as_ref(), proposal_id);
-        assert_eq!(tally, 1);
+    #[test]
+    fn vetoer_can_kill_passed_proposals() {
+        let mut app = App::default();
+        app.sudo(SudoMsg::Bank(BankSudo::Mint {
+            to_address: VOTER4.to_string(),
+            amount: coins(20, "TOKEN"),
+        }))
+        .unwrap();
 
-        // Cast a No vote
-        let no_vote = ExecuteMsg::Vote {
-            proposal_id,
-            vote: Vote::No,
+        let members = vec![member(VOTER1, 1), member(VOTER4, 12)];
+        let group_addr = instantiate_group(&mut app, members);
+        let flex_id = app.store_code(contract_flex());
+        let instantiate = |timelock_duration| InstantiateMsg {
+            group_addr: group_addr.to_string(),
+            threshold: Threshold::AbsoluteCount { weight: 10 },
+            max_voting_period: Duration::Height(10),
+            executor: None,
+            proposal_deposit: Some(UncheckedDepositInfo {
+                amount: Uint128::new(10),
+                denom: UncheckedDenom::Native("TOKEN".to_string()),
+                refund_failed_proposals: true,
+                refund_passed_proposals: true,
+                forfeit: None,
+            }),
+            veto: Some(crate::msg::UncheckedVetoConfig {
+                vetoer: SOMEBODY.to_string(),
+                timelock_duration,
+                burn_deposit: true,
+            }),
+            execution_delay: None,
+            close_reward: None,
+            threshold_profiles: vec![],
+            allow_revoting: false,
+            proposers: None,
+            extra_groups: vec![],
+            emergency: None,
+            quadratic_voting: false,
+            timelock: None,
+            private_voting: None,
         };
-        let _ = app
-            .execute_contract(Addr::unchecked(VOTER2), flex_addr.clone(), &no_vote, &[])
-            .unwrap();
 
-        // Cast a Veto vote
-        let veto_vote = ExecuteMsg::Vote {
-            proposal_id,
-            vote: Vote::Veto,
-        };
-        let _ = app
-            .execute_contract(Addr::unchecked(VOTER3), flex_addr.clone(), &veto_vote, &[])
+        // The veto window is measured like the voting period
+        let err = app
+            .instantiate_contract(
+                flex_id,
+                Addr::unchecked(OWNER),
+                &instantiate(Duration::Time(100)),
+                &[],
+                "flex",
+                None,
+            )
+            .unwrap_err();
+        assert_eq!(
+            ContractError::InvalidVetoTimelock {},
+            err.downcast().unwrap()
+        );
+        let flex_addr = app
+            .instantiate_contract(
+                flex_id,
+                Addr::unchecked(OWNER),
+                &instantiate(Duration::Height(5)),
+                &[],
+                "flex",
+                None,
+            )
             .unwrap();
 
-        // Tally unchanged
-        assert_eq!(tally, get_tally(&app, flex_addr.as_ref(), proposal_id));
+        let propose = |app: &mut App| {
+            app.execute_contract(
+                Addr::unchecked(VOTER4),
+                flex_addr.clone(),
+                &text_proposal(),
+                &coins(10, "TOKEN"),
+            )
+            .unwrap();
+        };
+        let status = |app: &App, proposal_id| {
+            let prop: ProposalResponse = app
+                .wrap()
+                .query_wasm_smart(&flex_addr, &QueryMsg::Proposal { proposal_id })
+                .unwrap();
+            prop.status
+        };
 
+        // Passed proposals can be vetoed before they are executed
+        propose(&mut app);
+        assert_eq!(status(&app, 1), Status::VetoTimelock);
         let err = app
-            .execute_contract(Addr::unchecked(VOTER3), flex_addr.clone(), &yes_vote, &[])
+            .execute_contract(
+                Addr::unchecked(VOTER4),
+                flex_addr.clone(),
+                &ExecuteMsg::Execute {
+                    proposal_id: 1,
+                    msg_indices: None,
+                },
+                &[],
+            )
             .unwrap_err();
-        assert_eq!(ContractError::AlreadyVoted {}, err.downcast().unwrap());
-
-        // Expired proposals cannot be voted
-        app.update_block(expire(voting_period));
+        assert_eq!(ContractError::InVetoTimelock {}, err.downcast().unwrap());
         let err = app
-            .execute_contract(Addr::unchecked(VOTER4), flex_addr.clone(), &yes_vote, &[])
+            .execute_contract(
+                Addr::unchecked(VOTER4),
+                flex_addr.clone(),
+                &ExecuteMsg::Veto { proposal_id: 1 },
+                &[],
+            )
             .unwrap_err();
-        assert_eq!(ContractError::Expired {}, err.downcast().unwrap());
-        app.update_block(unexpire(voting_period));
+        assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
 
-        // Powerful voter supports it, so it passes
-        let res = app
-            .execute_contract(Addr::unchecked(VOTER4), flex_addr.clone(), &yes_vote, &[])
-            .unwrap();
+        // Vetoing burns the deposit
+        app.execute_contract(
+            Addr::unchecked(SOMEBODY),
+            flex_addr.clone(),
+            &ExecuteMsg::Veto { proposal_id: 1 },
+            &[],
+        )
+        .unwrap();
+        assert_eq!(status(&app, 1), Status::Vetoed);
+        let balance = app.wrap().query_balance(&flex_addr, "TOKEN").unwrap();
+        assert_eq!(balance.amount, Uint128::zero());
+        let err = app
+            .execute_contract(
+                Addr::unchecked(VOTER4),
+                flex_addr.clone(),
+                &ExecuteMsg::Execute {
+                    proposal_id: 1,
+                    msg_indices: None,
+                },
+                &[],
+            )
+            .unwrap_err();
         assert_eq!(
-            res.custom_attrs(1),
-            [
-                ("action", "vote"),
-                ("sender", VOTER4),
-                ("proposal_id", proposal_id.to_string().as_str()),
-                ("status", "Passed"),
-            ],
+            ContractError::WrongExecuteStatus {},
+            err.downcast().unwrap()
         );
 
-        // Passed proposals can still be voted (while they are not expired or executed)
-        let res = app
-            .execute_contract(Addr::unchecked(VOTER5), flex_addr.clone(), &yes_vote, &[])
-            .unwrap();
-        // Verify
-        assert_eq!(
-            res.custom_attrs(1),
-            [
-                ("action", "vote"),
-                ("sender", VOTER5),
-                ("proposal_id", proposal_id.to_string().as_str()),
-                ("status", "Passed")
-            ]
-        );
+        // Once the window is over, they can only be executed
+        propose(&mut app);
+        app.update_block(|b| b.height += 5);
+        assert_eq!(status(&app, 2), Status::Passed);
+        let err = app
+            .execute_contract(
+                Addr::unchecked(SOMEBODY),
+                flex_addr.clone(),
+                &ExecuteMsg::Veto { proposal_id: 2 },
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(ContractError::WrongVetoStatus {}, err.downcast().unwrap());
+        app.execute_contract(
+            Addr::unchecked(VOTER4),
+            flex_addr,
+            &ExecuteMsg::Execute {
+                proposal_id: 2,
+                msg_indices: None,
+            },
+            &[],
+        )
+        .unwrap();
+        let balance = app.wrap().query_balance(VOTER4, "TOKEN").unwrap();
+        assert_eq!(balance.amount, Uint128::new(10));
+    }
 
-        // query individual votes
-        // initial (with 0 weight)
-        let voter = OWNER.into();
-        let vote: VoteResponse = app
-            .wrap()
-            .query_wasm_smart(&flex_addr, &QueryMsg::Vote { proposal_id, voter })
-            .unwrap();
-        assert_eq!(
-            vote.vote.unwrap(),
-            VoteInfo {
-                proposal_id,
-                voter: OWNER.into(),
-                vote: Vote::Yes,
-                weight: 0
-            }
-        );
+    #[test]
+    fn config_updated_by_proposals() {
+        let mut app = mock_app(&[]);
+        let (flex_addr, _) =
+            setup_test_case_fixed(&mut app, 10, Duration::Height(10), vec![], false);
+
+        let update = |threshold| ExecuteMsg::UpdateConfig {
+            threshold: Some(Threshold::AbsoluteCount { weight: threshold }),
+            max_voting_period: Some(Duration::Height(20)),
+            group_addr: None,
+        };
+        let propose_and_execute = |app: &mut App, update: ExecuteMsg| {
+            let proposal = ExecuteMsg::Propose {
+                title: "Update config".to_string(),
+                description: "Stricter rules".to_string(),
+                msgs: vec![WasmMsg::Execute {
+                    contract_addr: flex_addr.to_string(),
+                    msg: to_binary(&update).unwrap(),
+                    funds: vec![],
+                }
+                .into()],
+                latest: None,
+                threshold_profile: None,
+                execution_window: None,
+                metadata: None,
+            };
+            let res = app
+                .execute_contract(Addr::unchecked(VOTER4), flex_addr.clone(), &proposal, &[])
+                .unwrap();
+            let proposal_id: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
+            app.execute_contract(
+                Addr::unchecked(VOTER4),
+                flex_addr.clone(),
+                &ExecuteMsg::Execute {
+                    proposal_id,
+                    msg_indices: None,
+                },
+                &[],
+            )
+        };
 
-        // nay sayer
-        let voter = VOTER2.into();
-        let vote: VoteResponse = app
+        // Only the multisig itself can change its config
+        let err = app
+            .execute_contract(Addr::unchecked(OWNER), flex_addr.clone(), &update(15), &[])
+            .unwrap_err();
+        assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+        // To a threshold the group can reach, failed executions are recorded
+        let res = propose_and_execute(&mut app, update(100)).unwrap();
+        let proposal_id: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
+        let prop: ProposalResponse = app
             .wrap()
-            .query_wasm_smart(&flex_addr, &QueryMsg::Vote { proposal_id, voter })
+            .query_wasm_smart(&flex_addr, &QueryMsg::Proposal { proposal_id })
             .unwrap();
-        assert_eq!(
-            vote.vote.unwrap(),
-            VoteInfo {
-                proposal_id,
-                voter: VOTER2.into(),
-                vote: Vote::No,
-                weight: 2
-            }
-        );
-
-        // non-voter
-        let voter = SOMEBODY.into();
-        let vote: VoteResponse = app
+        assert_eq!(prop.status, Status::ExecutionFailed);
+        assert!(prop.execution_error.is_some());
+        propose_and_execute(&mut app, update(15)).unwrap();
+        let cfg: Config = app
             .wrap()
-            .query_wasm_smart(&flex_addr, &QueryMsg::Vote { proposal_id, voter })
+            .query_wasm_smart(&flex_addr, &QueryMsg::Config {})
             .unwrap();
-        assert!(vote.vote.is_none());
+        assert_eq!(cfg.threshold, Threshold::AbsoluteCount { weight: 15 });
+        assert_eq!(cfg.max_voting_period, Duration::Height(20));
 
-        // create proposal with 0 vote power
-        let proposal = pay_somebody_proposal();
+        // Which applies to proposals to come
         let res = app
-            .execute_contract(Addr::unchecked(OWNER), flex_addr.clone(), &proposal, &[])
+            .execute_contract(
+                Addr::unchecked(VOTER4),
+                flex_addr.clone(),
+                &text_proposal(),
+                &[],
+            )
             .unwrap();
+        assert_eq!(res.custom_attrs(1)[3].value, "Open");
+    }
 
-        // Get the proposal id from the logs
-        let proposal_id: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
+    #[test]
+    fn split_votes_are_tallied_fractionally() {
+        let mut app = mock_app(&[]);
+        let (flex_addr, _) =
+            setup_test_case_fixed(&mut app, 10, Duration::Height(10), vec![], false);
+        app.execute_contract(
+            Addr::unchecked(OWNER),
+            flex_addr.clone(),
+            &text_proposal(),
+            &[],
+        )
+        .unwrap();
 
-        // Cast a No vote
-        let no_vote = ExecuteMsg::Vote {
-            proposal_id,
-            vote: Vote::No,
+        let option = |vote, percent| WeightedVoteOption {
+            vote,
+            weight: Decimal::percent(percent),
+        };
+        let vote_weighted = |options| ExecuteMsg::VoteWeighted {
+            proposal_id: 1,
+            options,
         };
-        let _ = app
-            .execute_contract(Addr::unchecked(VOTER2), flex_addr.clone(), &no_vote, &[])
-            .unwrap();
 
-        // Powerful voter opposes it, so it rejects
+        // The shares must add up to one
+        let err = app
+            .execute_contract(
+                Addr::unchecked(VOTER4),
+                flex_addr.clone(),
+                &vote_weighted(vec![option(Vote::Yes, 60), option(Vote::Abstain, 30)]),
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(ContractError::InvalidSplitVote {}, err.downcast().unwrap());
+
+        // 12 split 60% yes / 40% abstain, with the rounding leftover going to yes
+        let options = vec![option(Vote::Yes, 60), option(Vote::Abstain, 40)];
         let res = app
-            .execute_contract(Addr::unchecked(VOTER4), flex_addr.clone(), &no_vote, &[])
+            .execute_contract(
+                Addr::unchecked(VOTER4),
+                flex_addr.clone(),
+                &vote_weighted(options.clone()),
+                &[],
+            )
+            .unwrap();
+        assert_eq!(res.custom_attrs(1)[3].value, "Open");
+        let res: VoteResponse = app
+            .wrap()
+            .query_wasm_smart(
+                &flex_addr,
+                &QueryMsg::Vote {
+                    proposal_id: 1,
+                    voter: VOTER4.to_string(),
+                },
+            )
             .unwrap();
-
         assert_eq!(
-            res.custom_attrs(1),
-            [
-                ("action", "vote"),
-                ("sender", VOTER4),
-                ("proposal_id", proposal_id.to_string().as_str()),
-                ("status", "Rejected"),
-            ],
+            res.vote.unwrap(),
+            VoteInfo {
+                proposal_id: 1,
+                voter: VOTER4.to_string(),
+                vote: Vote::Yes,
+                weight: 12,
+                split: Some(options),
+                choice: None,
+            }
         );
 
-        // Rejected proposals can still be voted (while they are not expired)
-        let yes_vote = ExecuteMsg::Vote {
-            proposal_id,
-            vote: Vote::Yes,
-        };
+        // 8 yes so far, so 3 more pass the proposal
         let res = app
-            .execute_contract(Addr::unchecked(VOTER5), flex_addr, &yes_vote, &[])
+            .execute_contract(
+                Addr::unchecked(VOTER3),
+                flex_addr,
+                &ExecuteMsg::Vote {
+                    proposal_id: 1,
+                    vote: Vote::Yes,
+                },
+                &[],
+            )
             .unwrap();
-
-        assert_eq!(
-            res.custom_attrs(1),
-            [
-                ("action", "vote"),
-                ("sender", VOTER5),
-                ("proposal_id", proposal_id.to_string().as_str()),
-                ("status", "Rejected"),
-            ],
-        );
+        assert_eq!(res.custom_attrs(1)[3].value, "Passed");
     }
 
     #[test]
-    fn test_execute_works() {
-        let init_funds = coins(10, "BTC");
-        let mut app = mock_app(&init_funds);
-
-        let threshold = Threshold::ThresholdQuorum {
-            threshold: Decimal::percent(51),
-            quorum: Decimal::percent(1),
+    fn passed_proposals_wait_for_execution_timelock() {
+        let mut app = mock_app(&[]);
+        let members = vec![member(VOTER1, 1), member(VOTER4, 12)];
+        let group_addr = instantiate_group(&mut app, members);
+        let flex_id = app.store_code(contract_flex());
+        let instantiate = |execution_delay| InstantiateMsg {
+            group_addr: group_addr.to_string(),
+            threshold: Threshold::AbsoluteCount { weight: 10 },
+            max_voting_period: Duration::Height(10),
+            executor: None,
+            proposal_deposit: None,
+            veto: None,
+            execution_delay: Some(execution_delay),
+            close_reward: None,
+            threshold_profiles: vec![],
+            allow_revoting: false,
+            proposers: None,
+            extra_groups: vec![],
+            emergency: None,
+            quadratic_voting: false,
+            timelock: None,
+            private_voting: None,
         };
-        let voting_period = Duration::Time(2000000);
-        let (flex_addr, _) = setup_test_case(
-            &mut app,
-            threshold,
-            voting_period,
-            init_funds,
-            true,
-            None,
-            None,
-        );
-
-        // ensure we have cash to cover the proposal
-        let contract_bal = app.wrap().query_balance(&flex_addr, "BTC").unwrap();
-        assert_eq!(contract_bal, coin(10, "BTC"));
-
-        // create proposal with 0 vote power
-        let proposal = pay_somebody_proposal();
-        let res = app
-            .execute_contract(Addr::unchecked(OWNER), flex_addr.clone(), &proposal, &[])
-            .unwrap();
-
-        // Get the proposal id from the logs
-        let proposal_id: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
 
-        // Only Passed can be executed
-        let execution = ExecuteMsg::Execute { proposal_id };
+        // The delay is measured like the voting period
         let err = app
-            .execute_contract(Addr::unchecked(OWNER), flex_addr.clone(), &execution, &[])
+            .instantiate_contract(
+                flex_id,
+                Addr::unchecked(OWNER),
+                &instantiate(Duration::Time(100)),
+                &[],
+                "flex",
+                None,
+            )
             .unwrap_err();
         assert_eq!(
-            ContractError::WrongExecuteStatus {},
+            ContractError::InvalidExecutionDelay {},
             err.downcast().unwrap()
         );
-
-        // Vote it, so it passes
-        let vote = ExecuteMsg::Vote {
-            proposal_id,
-            vote: Vote::Yes,
-        };
-        let res = app
-            .execute_contract(Addr::unchecked(VOTER4), flex_addr.clone(), &vote, &[])
+        let flex_addr = app
+            .instantiate_contract(
+                flex_id,
+                Addr::unchecked(OWNER),
+                &instantiate(Duration::Height(5)),
+                &[],
+                "flex",
+                None,
+            )
             .unwrap();
-        assert_eq!(
-            res.custom_attrs(1),
-            [
-                ("action", "vote"),
-                ("sender", VOTER4),
-                ("proposal_id", proposal_id.to_string().as_str()),
-                ("status", "Passed"),
-            ],
-        );
-
-        // In passing: Try to close Passed fails
-        let closing = ExecuteMsg::Close { proposal_id };
-        let err = app
-            .execute_contract(Addr::unchecked(OWNER), flex_addr.clone(), &closing, &[])
-            .unwrap_err();
-        assert_eq!(ContractError::WrongCloseStatus {}, err.downcast().unwrap());
 
-        // Execute works. Anybody can execute Passed proposals
+        // Passing starts the timelock
         let res = app
             .execute_contract(
-                Addr::unchecked(SOMEBODY),
+                Addr::unchecked(VOTER4),
                 flex_addr.clone(),
-                &execution,
+                &text_proposal(),
                 &[],
             )
             .unwrap();
-        assert_eq!(
-            res.custom_attrs(1),
-            [
-                ("action", "execute"),
-                ("sender", SOMEBODY),
-                ("proposal_id", proposal_id.to_string().as_str()),
-            ],
-        );
-
-        // verify money was transfered
-        let some_bal = app.wrap().query_balance(SOMEBODY, "BTC").unwrap();
-        assert_eq!(some_bal, coin(1, "BTC"));
-        let contract_bal = app.wrap().query_balance(&flex_addr, "BTC").unwrap();
-        assert_eq!(contract_bal, coin(9, "BTC"));
-
-        // In passing: Try to close Executed fails
-        let err = app
-            .execute_contract(Addr::unchecked(OWNER), flex_addr.clone(), &closing, &[])
-            .unwrap_err();
-        assert_eq!(ContractError::WrongCloseStatus {}, err.downcast().unwrap());
+        let executable_at = Expiration::AtHeight(app.block_info().height + 5);
+        assert!(res.has_event(
+            &Event::new("wasm-execution_timelock")
+                .add_attribute("proposal_id", "1")
+                .add_attribute("executable_at", executable_at.to_string())
+        ));
+        let query_executable_at = |app: &App| {
+            let res: ExecutableAtResponse = app
+                .wrap()
+                .query_wasm_smart(&flex_addr, &QueryMsg::ExecutableAt { proposal_id: 1 })
+                .unwrap();
+            res.executable_at
+        };
+        assert_eq!(query_executable_at(&app), Some(executable_at));
 
-        // Trying to execute something that was already executed fails
-        let err = app
-            .execute_contract(Addr::unchecked(SOMEBODY), flex_addr, &execution, &[])
-            .unwrap_err();
+        let execute = |app: &mut App| {
+            app.execute_contract(
+                Addr::unchecked(VOTER1),
+                flex_addr.clone(),
+                &ExecuteMsg::Execute {
+                    proposal_id: 1,
+                    msg_indices: None,
+                },
+                &[],
+            )
+        };
+        app.update_block(|b| b.height += 4);
+        let err = execute(&mut app).unwrap_err();
         assert_eq!(
-            ContractError::WrongExecuteStatus {},
+            ContractError::InExecutionTimelock {},
             err.downcast().unwrap()
         );
+
+        // Then anybody can execute it
+        app.update_block(|b| b.height += 1);
+        assert_eq!(query_executable_at(&app), None);
+        execute(&mut app).unwrap();
     }
 
     #[test]
-    fn execute_with_executor_member() {
-        let init_funds = coins(10, "BTC");
-        let mut app = mock_app(&init_funds);
-
-        let threshold = Threshold::ThresholdQuorum {
-            threshold: Decimal::percent(51),
-            quorum: Decimal::percent(1),
-        };
-        let voting_period = Duration::Time(2000000);
-        let (flex_addr, _) = setup_test_case(
-            &mut app,
-            threshold,
-            voting_period,
-            init_funds,
-            true,
-            Some(crate::state::Executor::Member), // set executor as Member of voting group
-            None,
-        );
-
-        // create proposal with 0 vote power
-        let proposal = pay_somebody_proposal();
-        let res = app
-            .execute_contract(Addr::unchecked(OWNER), flex_addr.clone(), &proposal, &[])
+    fn closers_are_rewarded_by_treasury() {
+        let mut app = App::default();
+        let group_addr = instantiate_group(&mut app, vec![member(VOTER1, 1), member(VOTER4, 12)]);
+        let flex_id = app.store_code(contract_flex());
+        let msg = InstantiateMsg {
+            group_addr: group_addr.to_string(),
+            threshold: Threshold::AbsoluteCount { weight: 10 },
+            max_voting_period: Duration::Height(10),
+            executor: None,
+            proposal_deposit: None,
+            veto: None,
+            execution_delay: None,
+            close_reward: Some(CloseReward::Treasury {
+                amount: coin(4, "REWARD"),
+            }),
+            threshold_profiles: vec![],
+            allow_revoting: false,
+            proposers: None,
+            extra_groups: vec![],
+            emergency: None,
+            quadratic_voting: false,
+            timelock: None,
+            private_voting: None,
+        };
+        let flex_addr = app
+            .instantiate_contract(flex_id, Addr::unchecked(OWNER), &msg, &[], "flex", None)
             .unwrap();
+        app.sudo(SudoMsg::Bank(BankSudo::Mint {
+            to_address: flex_addr.to_string(),
+            amount: coins(10, "REWARD"),
+        }))
+        .unwrap();
 
-        // Get the proposal id from the logs
-        let proposal_id: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
-
-        // Vote it, so it passes
-        let vote = ExecuteMsg::Vote {
-            proposal_id,
-            vote: Vote::Yes,
-        };
-        app.execute_contract(Addr::unchecked(VOTER4), flex_addr.clone(), &vote, &[])
+        for _ in 0..3 {
+            app.execute_contract(
+                Addr::unchecked(VOTER1),
+                flex_addr.clone(),
+                &text_proposal(),
+                &[],
+            )
             .unwrap();
+        }
+        app.update_block(expire(Duration::Height(10)));
+
+        let close_many = |app: &mut App, limit| {
+            let res = app
+                .execute_contract(
+                    Addr::unchecked(SOMEBODY),
+                    flex_addr.clone(),
+                    &ExecuteMsg::CloseMany {
+                        start_after: None,
+                        limit,
+                    },
+                    &[],
+                )
+                .unwrap();
+            let balance = app.wrap().query_balance(SOMEBODY, "REWARD").unwrap();
+            (res.custom_attrs(1)[2].value.clone(), balance.amount.u128())
+        };
 
-        let execution = ExecuteMsg::Execute { proposal_id };
+        // Rewarded for each closed proposal, as long as the multisig can pay
+        assert_eq!(close_many(&mut app, Some(2)), ("1,2".to_string(), 8));
+        assert_eq!(close_many(&mut app, None), ("3".to_string(), 10));
         let err = app
             .execute_contract(
-                Addr::unchecked(Addr::unchecked("anyone")), // anyone is not allowed to execute
+                Addr::unchecked(SOMEBODY),
                 flex_addr.clone(),
-                &execution,
+                &ExecuteMsg::CloseMany {
+                    start_after: None,
+                    limit: None,
+                },
                 &[],
             )
             .unwrap_err();
-        assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
-
-        app.execute_contract(
-            Addr::unchecked(Addr::unchecked(VOTER2)), // member of voting group is allowed to execute
-            flex_addr,
-            &execution,
-            &[],
-        )
-        .unwrap();
+        assert_eq!(ContractError::NothingToClose {}, err.downcast().unwrap());
+        let prop: ProposalResponse = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &QueryMsg::Proposal { proposal_id: 3 })
+            .unwrap();
+        assert_eq!(prop.status, Status::Rejected);
     }
 
     #[test]
-    fn execute_with_executor_only() {
-        let init_funds = coins(10, "BTC");
-        let mut app = mock_app(&init_funds);
-
-        let threshold = Threshold::ThresholdQuorum {
-            threshold: Decimal::percent(51),
-            quorum: Decimal::percent(1),
+    fn proposals_tallied_with_threshold_profiles() {
+        let mut app = mock_app(&[]);
+        let members = vec![member(VOTER1, 1), member(VOTER4, 12), member(VOTER5, 5)];
+        let group_addr = instantiate_group(&mut app, members);
+        app.update_block(next_block);
+        let flex_id = app.store_code(contract_flex());
+        let instantiate = |app: &mut App, profiles: Vec<(&str, u64)>| {
+            let msg = InstantiateMsg {
+                group_addr: group_addr.to_string(),
+                threshold: Threshold::AbsoluteCount { weight: 10 },
+                max_voting_period: Duration::Height(10),
+                executor: None,
+                proposal_deposit: None,
+                veto: None,
+                close_reward: None,
+                execution_delay: None,
+                threshold_profiles: profiles
+                    .into_iter()
+                    .map(|(name, weight)| ThresholdProfile {
+                        name: name.to_string(),
+                        threshold: Threshold::AbsoluteCount { weight },
+                    })
+                    .collect(),
+                allow_revoting: false,
+                proposers: None,
+                extra_groups: vec![],
+                emergency: None,
+                quadratic_voting: false,
+                timelock: None,
+                private_voting: None,
+            };
+            app.instantiate_contract(flex_id, Addr::unchecked(OWNER), &msg, &[], "flex", None)
         };
-        let voting_period = Duration::Time(2000000);
-        let (flex_addr, _) = setup_test_case(
-            &mut app,
-            threshold,
-            voting_period,
-            init_funds,
-            true,
-            Some(crate::state::Executor::Only(Addr::unchecked(VOTER3))), // only VOTER3 can execute proposal
-            None,
-        );
 
-        // create proposal with 0 vote power
-        let proposal = pay_somebody_proposal();
-        let res = app
-            .execute_contract(Addr::unchecked(OWNER), flex_addr.clone(), &proposal, &[])
+        // Profiles are validated like the default threshold
+        let err = instantiate(&mut app, vec![("upgrade", 15), ("upgrade", 16)]).unwrap_err();
+        assert_eq!(
+            ContractError::InvalidThresholdProfile {},
+            err.downcast().unwrap()
+        );
+        let err = instantiate(&mut app, vec![("upgrade", 100)]).unwrap_err();
+        assert_eq!(
+            ContractError::Threshold(ThresholdError::UnreachableWeight {}),
+            err.downcast().unwrap()
+        );
+        let flex_addr = instantiate(&mut app, vec![("upgrade", 15), ("text", 1)]).unwrap();
+        let res: ThresholdProfilesResponse = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &QueryMsg::ThresholdProfiles {})
             .unwrap();
+        let names: Vec<_> = res.profiles.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["text", "upgrade"]);
+        assert_eq!(
+            res.profiles[1].threshold,
+            ThresholdResponse::AbsoluteCount {
+                weight: 15,
+                total_weight: 18
+            }
+        );
 
-        // Get the proposal id from the logs
-        let proposal_id: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
-
-        // Vote it, so it passes
-        let vote = ExecuteMsg::Vote {
-            proposal_id,
-            vote: Vote::Yes,
+        let propose = |app: &mut App, threshold_profile: Option<&str>| {
+            let proposal = ExecuteMsg::Propose {
+                title: "Upgrade".to_string(),
+                description: "Needs more support".to_string(),
+                msgs: vec![],
+                latest: None,
+                threshold_profile: threshold_profile.map(str::to_string),
+                execution_window: None,
+                metadata: None,
+            };
+            app.execute_contract(Addr::unchecked(VOTER4), flex_addr.clone(), &proposal, &[])
         };
-        app.execute_contract(Addr::unchecked(VOTER4), flex_addr.clone(), &vote, &[])
-            .unwrap();
+        let err = propose(&mut app, Some("spend")).unwrap_err();
+        assert_eq!(
+            ContractError::UnknownThresholdProfile {
+                name: "spend".to_string()
+            },
+            err.downcast().unwrap()
+        );
 
-        let execution = ExecuteMsg::Execute { proposal_id };
-        let err = app
+        // The default threshold passes right away, the stricter one needs another voter
+        let res = propose(&mut app, None).unwrap();
+        assert_eq!(res.custom_attrs(1)[3].value, "Passed");
+        let res = propose(&mut app, Some("upgrade")).unwrap();
+        assert_eq!(res.custom_attrs(1)[3].value, "Open");
+        assert_eq!(res.custom_attrs(1)[4].value, "upgrade");
+        let res = app
             .execute_contract(
-                Addr::unchecked(Addr::unchecked("anyone")), // anyone is not allowed to execute
+                Addr::unchecked(VOTER5),
                 flex_addr.clone(),
-                &execution,
+                &ExecuteMsg::Vote {
+                    proposal_id: 2,
+                    vote: Vote::Yes,
+                },
                 &[],
             )
-            .unwrap_err();
-        assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+            .unwrap();
+        assert_eq!(res.custom_attrs(1)[3].value, "Passed");
+    }
+
+    #[test]
+    fn signaling_proposals_skip_execution() {
+        let mut app = App::default();
+        app.sudo(SudoMsg::Bank(BankSudo::Mint {
+            to_address: VOTER4.to_string(),
+            amount: coins(10, "TOKEN"),
+        }))
+        .unwrap();
+        let members = vec![member(VOTER1, 1), member(VOTER4, 12)];
+        let group_addr = instantiate_group(&mut app, members);
+        let flex_id = app.store_code(contract_flex());
+        let msg = InstantiateMsg {
+            group_addr: group_addr.to_string(),
+            threshold: Threshold::AbsoluteCount { weight: 10 },
+            max_voting_period: Duration::Height(10),
+            executor: None,
+            proposal_deposit: Some(UncheckedDepositInfo {
+                amount: Uint128::new(10),
+                denom: UncheckedDenom::Native("TOKEN".to_string()),
+                refund_failed_proposals: true,
+                refund_passed_proposals: true,
+                forfeit: None,
+            }),
+            veto: Some(crate::msg::UncheckedVetoConfig {
+                vetoer: SOMEBODY.to_string(),
+                timelock_duration: Duration::Height(5),
+                burn_deposit: true,
+            }),
+            close_reward: None,
+            execution_delay: Some(Duration::Height(5)),
+            threshold_profiles: vec![],
+            allow_revoting: false,
+            proposers: None,
+            extra_groups: vec![],
+            emergency: None,
+            quadratic_voting: false,
+            timelock: None,
+            private_voting: None,
+        };
+        let flex_addr = app
+            .instantiate_contract(flex_id, Addr::unchecked(OWNER), &msg, &[], "flex", None)
+            .unwrap();
+
+        // Passing is final, without veto window nor execution timelock
+        let proposal = ExecuteMsg::ProposeSignaling {
+            title: "Poll".to_string(),
+            description: "Shall we?".to_string(),
+            latest: None,
+            threshold_profile: None,
+            metadata: None,
+        };
+        app.execute_contract(
+            Addr::unchecked(VOTER4),
+            flex_addr.clone(),
+            &proposal,
+            &coins(10, "TOKEN"),
+        )
+        .unwrap();
+        let prop: ProposalResponse = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &QueryMsg::Proposal { proposal_id: 1 })
+            .unwrap();
+        assert_eq!(prop.kind, ProposalKind::Signaling);
+        assert_eq!(prop.status, Status::Passed);
+        assert_eq!(prop.deposit_status, Some(DepositStatus::Refunded));
+        let balance = app.wrap().query_balance(VOTER4, "TOKEN").unwrap();
+        assert_eq!(balance.amount, Uint128::new(10));
+        let res: ExecutableAtResponse = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &QueryMsg::ExecutableAt { proposal_id: 1 })
+            .unwrap();
+        assert_eq!(res.executable_at, None);
 
         let err = app
             .execute_contract(
-                Addr::unchecked(Addr::unchecked(VOTER1)), // VOTER1 is not allowed to execute
-                flex_addr.clone(),
-                &execution,
+                Addr::unchecked(VOTER4),
+                flex_addr,
+                &ExecuteMsg::Execute {
+                    proposal_id: 1,
+                    msg_indices: None,
+                },
                 &[],
             )
             .unwrap_err();
-        assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
-
-        app.execute_contract(
-            Addr::unchecked(Addr::unchecked(VOTER3)), // VOTER3 is allowed to execute
-            flex_addr,
-            &execution,
-            &[],
-        )
-        .unwrap();
+        assert_eq!(ContractError::SignalingProposal {}, err.downcast().unwrap());
     }
 
     #[test]
-    fn proposal_pass_on_expiration() {
+    fn choice_proposals_won_by_plurality() {
         let init_funds = coins(10, "BTC");
         let mut app = mock_app(&init_funds);
-
-        let threshold = Threshold::ThresholdQuorum {
-            threshold: Decimal::percent(51),
-            quorum: Decimal::percent(1),
-        };
-        let voting_period = 2000000;
+        let threshold = Threshold::AbsoluteCount { weight: 20 };
+        let voting_period = Duration::Time(2000000);
         let (flex_addr, _) = setup_test_case(
             &mut app,
             threshold,
-            Duration::Time(voting_period),
+            voting_period,
             init_funds,
-            true,
+            false,
             None,
             None,
         );
-
-        // ensure we have cash to cover the proposal
-        let contract_bal = app.wrap().query_balance(&flex_addr, "BTC").unwrap();
-        assert_eq!(contract_bal, coin(10, "BTC"));
-
-        // create proposal with 0 vote power
-        let proposal = pay_somebody_proposal();
-        let res = app
-            .execute_contract(Addr::unchecked(OWNER), flex_addr.clone(), &proposal, &[])
-            .unwrap();
-
-        // Get the proposal id from the logs
-        let proposal_id: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
-
-        // Vote it, so it passes after voting period is over
-        let vote = ExecuteMsg::Vote {
-            proposal_id,
-            vote: Vote::Yes,
+        let pay = |amount| -> CosmosMsg {
+            BankMsg::Send {
+                to_address: SOMEBODY.into(),
+                amount: coins(amount, "BTC"),
+            }
+            .into()
         };
-        let res = app
-            .execute_contract(Addr::unchecked(VOTER3), flex_addr.clone(), &vote, &[])
-            .unwrap();
-        assert_eq!(
-            res.custom_attrs(1),
-            [
-                ("action", "vote"),
-                ("sender", VOTER3),
-                ("proposal_id", proposal_id.to_string().as_str()),
-                ("status", "Open"),
+        let option = |label: &str, msgs| ProposalOption {
+            label: label.to_string(),
+            msgs,
+        };
+        let proposal = ExecuteMsg::ProposeChoice {
+            title: "Grant".to_string(),
+            description: "How much shall we pay somebody?".to_string(),
+            options: vec![
+                option("Nothing", vec![]),
+                option("Some", vec![pay(1)]),
+                option("Lots", vec![pay(5)]),
             ],
-        );
+            win_condition: WinCondition::Plurality {
+                quorum: Decimal::percent(20),
+            },
+            latest: None,
+            metadata: None,
+        };
+        app.execute_contract(Addr::unchecked(VOTER1), flex_addr.clone(), &proposal, &[])
+            .unwrap();
 
-        // Wait until the voting period is over.
-        app.update_block(|block| {
-            block.time = block.time.plus_seconds(voting_period);
-            block.height += std::cmp::max(1, voting_period / 5);
-        });
+        // Choice proposals are neither voted on with split votes nor amended
+        let split = ExecuteMsg::VoteWeighted {
+            proposal_id: 1,
+            options: vec![WeightedVoteOption {
+                vote: Vote::Yes,
+                weight: Decimal::one(),
+            }],
+        };
+        let err = app
+            .execute_contract(Addr::unchecked(VOTER2), flex_addr.clone(), &split, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::ChoiceProposal {}, err.downcast().unwrap());
+        let amend = ExecuteMsg::Amend {
+            proposal_id: 1,
+            title: None,
+            description: None,
+            msgs: Some(vec![pay(10)]),
+        };
+        let err = app
+            .execute_contract(Addr::unchecked(VOTER1), flex_addr.clone(), &amend, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::ChoiceProposal {}, err.downcast().unwrap());
 
-        // Proposal should now be passed.
+        // The leading option reached the quorum, but others may still catch up
+        for (voter, option) in [(VOTER3, 2), (VOTER5, 1)] {
+            let vote = ExecuteMsg::VoteChoice {
+                proposal_id: 1,
+                option,
+            };
+            app.execute_contract(Addr::unchecked(voter), flex_addr.clone(), &vote, &[])
+                .unwrap();
+        }
         let prop: ProposalResponse = app
             .wrap()
-            .query_wasm_smart(&flex_addr, &QueryMsg::Proposal { proposal_id })
+            .query_wasm_smart(&flex_addr, &QueryMsg::Proposal { proposal_id: 1 })
             .unwrap();
-        assert_eq!(prop.status, Status::Passed);
-
-        // Closing should NOT be possible
-        let err = app
-            .execute_contract(
-                Addr::unchecked(SOMEBODY),
-                flex_addr.clone(),
-                &ExecuteMsg::Close { proposal_id },
-                &[],
-            )
-            .unwrap_err();
-        assert_eq!(ContractError::WrongCloseStatus {}, err.downcast().unwrap());
+        assert_eq!(prop.kind, ProposalKind::Choice);
+        assert_eq!(prop.status, Status::Open);
 
-        // Execution should now be possible.
-        let res = app
-            .execute_contract(
-                Addr::unchecked(SOMEBODY),
-                flex_addr,
-                &ExecuteMsg::Execute { proposal_id },
-                &[],
-            )
+        // until the proposal expired
+        app.update_block(expire(voting_period));
+        let prop: ProposalResponse = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &QueryMsg::Proposal { proposal_id: 1 })
             .unwrap();
-        assert_eq!(
-            res.custom_attrs(1),
-            [
-                ("action", "execute"),
-                ("sender", SOMEBODY),
-                ("proposal_id", proposal_id.to_string().as_str()),
-            ],
-        );
+        assert_eq!(prop.status, Status::Passed);
+        app.execute_contract(
+            Addr::unchecked(SOMEBODY),
+            flex_addr.clone(),
+            &ExecuteMsg::Execute {
+                proposal_id: 1,
+                msg_indices: None,
+            },
+            &[],
+        )
+        .unwrap();
+        let balance = app.wrap().query_balance(SOMEBODY, "BTC").unwrap();
+        assert_eq!(balance.amount, Uint128::new(1));
     }
 
     #[test]
-    fn test_close_works() {
-        let init_funds = coins(10, "BTC");
-        let mut app = mock_app(&init_funds);
+    fn signed_ballots_relayed() {
+        use k256::ecdsa::signature::Signer;
+        use k256::ecdsa::{Signature, SigningKey};
 
-        let threshold = Threshold::ThresholdQuorum {
-            threshold: Decimal::percent(51),
-            quorum: Decimal::percent(1),
-        };
-        let voting_period = Duration::Height(2000000);
-        let (flex_addr, _) = setup_test_case(
+        let mut app = mock_app(&[]);
+        let key = SigningKey::from_bytes(&[7; 32]).unwrap();
+        let pubkey = Binary::from(key.verifying_key().to_bytes().as_slice());
+        let signer = cw_controllers::pubkey_to_address(&pubkey, "cosmos").unwrap();
+        let members = vec![member(VOTER1, 1), member(&signer, 12)];
+        let group_addr = instantiate_group(&mut app, members);
+        app.update_block(next_block);
+        let flex_addr = instantiate_flex(
             &mut app,
-            threshold,
-            voting_period,
-            init_funds,
-            true,
+            group_addr,
+            Threshold::AbsoluteCount { weight: 13 },
+            Duration::Height(10),
             None,
             None,
         );
+        app.update_block(next_block);
+        app.execute_contract(
+            Addr::unchecked(VOTER1),
+            flex_addr.clone(),
+            &pay_somebody_proposal(),
+            &[],
+        )
+        .unwrap();
 
-        // create proposal with 0 vote power
-        let proposal = pay_somebody_proposal();
-        let res = app
-            .execute_contract(Addr::unchecked(OWNER), flex_addr.clone(), &proposal, &[])
+        // Anyone can relay the vote, counted with the weight of the voter
+        let ballot = VotePermit {
+            chain_id: app.block_info().chain_id,
+            contract: flex_addr.to_string(),
+            voter: signer.clone(),
+            proposal_id: 1,
+            vote: Vote::Yes,
+            nonce: 0,
+            deadline: Expiration::AtHeight(app.block_info().height + 5),
+        };
+        let signature: Signature = key.sign(ballot.sign_bytes().unwrap().as_slice());
+        let relay = ExecuteMsg::RelayVote {
+            ballot,
+            pubkey,
+            signature: Binary::from(signature.as_ref()),
+        };
+        app.execute_contract(Addr::unchecked(SOMEBODY), flex_addr.clone(), &relay, &[])
             .unwrap();
+        let prop: ProposalResponse = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &QueryMsg::Proposal { proposal_id: 1 })
+            .unwrap();
+        assert_eq!(prop.status, Status::Passed);
+        let nonce: cw_controllers::PermitNonceResponse = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &QueryMsg::VoteNonce { voter: signer })
+            .unwrap();
+        assert_eq!(nonce.nonce, 1);
 
-        // Get the proposal id from the logs
-        let proposal_id: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
-
-        // Non-expired proposals cannot be closed
-        let closing = ExecuteMsg::Close { proposal_id };
+        // but only once
         let err = app
-            .execute_contract(Addr::unchecked(SOMEBODY), flex_addr.clone(), &closing, &[])
+            .execute_contract(Addr::unchecked(SOMEBODY), flex_addr, &relay, &[])
             .unwrap_err();
-        assert_eq!(ContractError::NotExpired {}, err.downcast().unwrap());
-
-        // Expired proposals can be closed
-        app.update_block(expire(voting_period));
-        let res = app
-            .execute_contract(Addr::unchecked(SOMEBODY), flex_addr.clone(), &closing, &[])
-            .unwrap();
         assert_eq!(
-            res.custom_attrs(1),
-            [
-                ("action", "close"),
-                ("sender", SOMEBODY),
-                ("proposal_id", proposal_id.to_string().as_str()),
-            ],
+            ContractError::Permit(cw_controllers::PermitError::InvalidNonce { expected: 1 }),
+            err.downcast().unwrap()
         );
+    }
 
-        // Trying to close it again fails
-        let closing = ExecuteMsg::Close { proposal_id };
+    #[test]
+    fn proposer_amends_before_votes() {
+        let mut app = mock_app(&[]);
+        let (flex_addr, group_addr) =
+            setup_test_case_fixed(&mut app, 10, Duration::Height(10), vec![], false);
+        app.execute_contract(
+            Addr::unchecked(VOTER3),
+            flex_addr.clone(),
+            &text_proposal(),
+            &[],
+        )
+        .unwrap();
+        assert_eq!(get_tally(&app, flex_addr.as_ref(), 1), 3);
+
+        let amend = ExecuteMsg::Amend {
+            proposal_id: 1,
+            title: Some("Amended".to_string()),
+            description: None,
+            msgs: None,
+        };
+        app.execute_contract(Addr::unchecked(VOTER3), flex_addr.clone(), &amend, &[])
+            .unwrap();
+        let prop: ProposalResponse = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &QueryMsg::Proposal { proposal_id: 1 })
+            .unwrap();
+        assert_eq!(prop.title, "Amended");
+        assert_eq!(get_tally(&app, flex_addr.as_ref(), 1), 3);
+
+        // The proposer votes again at their current weight
+        let update = cw4_group::msg::ExecuteMsg::UpdateMembers {
+            remove: vec![],
+            add: vec![member(VOTER3, 5)],
+        };
+        app.execute_contract(Addr::unchecked(OWNER), group_addr, &update, &[])
+            .unwrap();
+        app.execute_contract(Addr::unchecked(VOTER3), flex_addr.clone(), &amend, &[])
+            .unwrap();
+        assert_eq!(get_tally(&app, flex_addr.as_ref(), 1), 5);
+        let vote: VoteResponse = app
+            .wrap()
+            .query_wasm_smart(
+                &flex_addr,
+                &QueryMsg::Vote {
+                    proposal_id: 1,
+                    voter: VOTER3.to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(vote.vote.unwrap().weight, 5);
+
+        // Not once others voted
+        app.execute_contract(
+            Addr::unchecked(VOTER4),
+            flex_addr.clone(),
+            &ExecuteMsg::Vote {
+                proposal_id: 1,
+                vote: Vote::No,
+            },
+            &[],
+        )
+        .unwrap();
         let err = app
-            .execute_contract(Addr::unchecked(SOMEBODY), flex_addr, &closing, &[])
+            .execute_contract(Addr::unchecked(VOTER3), flex_addr, &amend, &[])
             .unwrap_err();
-        assert_eq!(ContractError::WrongCloseStatus {}, err.downcast().unwrap());
+        assert_eq!(ContractError::VotesCast {}, err.downcast().unwrap());
     }
 
-    // uses the power from the beginning of the voting period
     #[test]
-    fn execute_group_changes_from_external() {
-        let init_funds = coins(10, "BTC");
-        let mut app = mock_app(&init_funds);
+    fn proposals_listed_by_status_and_proposer() {
+        let mut app = mock_app(&[]);
+        let (flex_addr, _) =
+            setup_test_case_fixed(&mut app, 10, Duration::Height(10), vec![], false);
+        for proposer in [VOTER4, VOTER1] {
+            app.execute_contract(
+                Addr::unchecked(proposer),
+                flex_addr.clone(),
+                &text_proposal(),
+                &[],
+            )
+            .unwrap();
+        }
+        let ids = |msg: QueryMsg| {
+            let res: ProposalListResponse = app.wrap().query_wasm_smart(&flex_addr, &msg).unwrap();
+            res.proposals.iter().map(|p| p.id).collect::<Vec<_>>()
+        };
 
-        let threshold = Threshold::ThresholdQuorum {
-            threshold: Decimal::percent(51),
-            quorum: Decimal::percent(1),
+        let passed = QueryMsg::ListProposalsByStatus {
+            status: Status::Passed,
+            start_after: None,
+            limit: None,
         };
-        let voting_period = Duration::Time(20000);
-        let (flex_addr, group_addr) = setup_test_case(
-            &mut app,
-            threshold,
-            voting_period,
-            init_funds,
-            false,
-            None,
-            None,
-        );
+        assert_eq!(ids(passed), vec![1]);
+        let by_voter1 = QueryMsg::ListProposalsByProposer {
+            proposer: VOTER1.to_string(),
+            start_after: None,
+            limit: None,
+        };
+        assert_eq!(ids(by_voter1), vec![2]);
+    }
 
-        // VOTER1 starts a proposal to send some tokens (1/4 votes)
+    #[test]
+    fn failed_execution_can_be_retried() {
+        let mut app = mock_app(&coins(10, "BTC"));
+        // the multisig has no funds to pay with
+        let (flex_addr, _) =
+            setup_test_case_fixed(&mut app, 12, Duration::Time(2000000), vec![], true);
+
+        // Passed by VOTER4 alone
         let proposal = pay_somebody_proposal();
         let res = app
-            .execute_contract(Addr::unchecked(VOTER1), flex_addr.clone(), &proposal, &[])
+            .execute_contract(Addr::unchecked(VOTER4), flex_addr.clone(), &proposal, &[])
             .unwrap();
-        // Get the proposal id from the logs
         let proposal_id: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
-        let prop_status = |app: &App, proposal_id: u64| -> Status {
-            let query_prop = QueryMsg::Proposal { proposal_id };
-            let prop: ProposalResponse = app
-                .wrap()
-                .query_wasm_smart(&flex_addr, &query_prop)
-                .unwrap();
-            prop.status
-        };
-
-        // 1/4 votes
-        assert_eq!(prop_status(&app, proposal_id), Status::Open);
 
-        // check current threshold (global)
-        let threshold: ThresholdResponse = app
+        // Executing fails, recording the error instead of reverting
+        let execution = ExecuteMsg::Execute {
+            proposal_id,
+            msg_indices: None,
+        };
+        let res = app
+            .execute_contract(
+                Addr::unchecked(SOMEBODY),
+                flex_addr.clone(),
+                &execution,
+                &[],
+            )
+            .unwrap();
+        assert_eq!(
+            res.custom_attrs(3)[..2],
+            [
+                attr("action", "execution_failed"),
+                attr("proposal_id", proposal_id.to_string()),
+            ]
+        );
+        let prop: ProposalResponse = app
             .wrap()
-            .query_wasm_smart(&flex_addr, &QueryMsg::Threshold {})
+            .query_wasm_smart(&flex_addr, &QueryMsg::Proposal { proposal_id })
             .unwrap();
-        let expected_thresh = ThresholdResponse::ThresholdQuorum {
-            total_weight: 23,
-            threshold: Decimal::percent(51),
-            quorum: Decimal::percent(1),
-        };
-        assert_eq!(expected_thresh, threshold);
-
-        // a few blocks later...
-        app.update_block(|block| block.height += 2);
+        assert_eq!(prop.status, Status::ExecutionFailed);
+        assert!(prop.execution_error.is_some());
+        let balance = app.wrap().query_balance(SOMEBODY, "BTC").unwrap();
+        assert_eq!(balance.amount, Uint128::zero());
 
-        // admin changes the group
-        // updates VOTER2 power to 21 -> with snapshot, vote doesn't pass proposal
-        // adds NEWBIE with 2 power -> with snapshot, invalid vote
-        // removes VOTER3 -> with snapshot, can vote on proposal
-        let newbie: &str = "newbie";
-        let update_msg = cw4_group::msg::ExecuteMsg::UpdateMembers {
-            remove: vec![VOTER3.into()],
-            add: vec![member(VOTER2, 21), member(newbie, 2)],
-        };
-        app.execute_contract(Addr::unchecked(OWNER), group_addr, &update_msg, &[])
+        // Once funded, it can be executed again
+        app.send_tokens(Addr::unchecked(OWNER), flex_addr.clone(), &coins(10, "BTC"))
             .unwrap();
-
-        // check membership queries properly updated
-        let query_voter = QueryMsg::Voter {
-            address: VOTER3.into(),
-        };
-        let power: VoterResponse = app
+        app.execute_contract(
+            Addr::unchecked(SOMEBODY),
+            flex_addr.clone(),
+            &execution,
+            &[],
+        )
+        .unwrap();
+        let prop: ProposalResponse = app
             .wrap()
-            .query_wasm_smart(&flex_addr, &query_voter)
+            .query_wasm_smart(&flex_addr, &QueryMsg::Proposal { proposal_id })
             .unwrap();
-        assert_eq!(power.weight, None);
+        assert_eq!(prop.status, Status::Executed);
+        assert_eq!(prop.execution_error, None);
+        let balance = app.wrap().query_balance(SOMEBODY, "BTC").unwrap();
+        assert_eq!(balance.amount, Uint128::new(1));
 
-        // proposal still open
-        assert_eq!(prop_status(&app, proposal_id), Status::Open);
+        // But only once
+        let err = app
+            .execute_contract(Addr::unchecked(SOMEBODY), flex_addr, &execution, &[])
+            .unwrap_err();
+        assert_eq!(
+            ContractError::WrongExecuteStatus {},
+            err.downcast().unwrap()
+        );
+    }
 
-        // a few blocks later...
-        app.update_block(|block| block.height += 3);
+    #[test]
+    fn votes_changed_while_open() {
+        let mut app = mock_app(&[]);
+        let members = vec![member(VOTER1, 1), member(VOTER4, 12), member(VOTER5, 5)];
+        let group_addr = instantiate_group(&mut app, members);
+        app.update_block(next_block);
+        let flex_id = app.store_code(contract_flex());
+        let msg = InstantiateMsg {
+            group_addr: group_addr.to_string(),
+            threshold: Threshold::AbsoluteCount { weight: 13 },
+            max_voting_period: Duration::Height(10),
+            executor: None,
+            proposal_deposit: None,
+            veto: None,
+            close_reward: None,
+            execution_delay: None,
+            threshold_profiles: vec![],
+            allow_revoting: true,
+            proposers: None,
+            extra_groups: vec![],
+            emergency: None,
+            quadratic_voting: false,
+            timelock: None,
+            private_voting: None,
+        };
+        let flex_addr = app
+            .instantiate_contract(flex_id, Addr::unchecked(OWNER), &msg, &[], "flex", None)
+            .unwrap();
+        app.update_block(next_block);
 
-        // make a second proposal
-        let proposal2 = pay_somebody_proposal();
         let res = app
-            .execute_contract(Addr::unchecked(VOTER1), flex_addr.clone(), &proposal2, &[])
+            .execute_contract(
+                Addr::unchecked(VOTER1),
+                flex_addr.clone(),
+                &pay_somebody_proposal(),
+                &[],
+            )
             .unwrap();
-        // Get the proposal id from the logs
-        let proposal_id2: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
-
-        // VOTER2 can pass this alone with the updated vote (newer height ignores snapshot)
-        let yes_vote = ExecuteMsg::Vote {
-            proposal_id: proposal_id2,
-            vote: Vote::Yes,
+        let proposal_id: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
+        let tally = |app: &App| -> TallyResponse {
+            app.wrap()
+                .query_wasm_smart(&flex_addr, &QueryMsg::ProposalTally { proposal_id })
+                .unwrap()
         };
-        app.execute_contract(Addr::unchecked(VOTER2), flex_addr.clone(), &yes_vote, &[])
-            .unwrap();
-        assert_eq!(prop_status(&app, proposal_id2), Status::Passed);
 
-        // VOTER2 can only vote on first proposal with weight of 2 (not enough to pass)
-        let yes_vote = ExecuteMsg::Vote {
+        // A split vote can be changed, taking back all of its shares
+        let option = |vote, percent| WeightedVoteOption {
+            vote,
+            weight: Decimal::percent(percent),
+        };
+        let split = ExecuteMsg::VoteWeighted {
             proposal_id,
-            vote: Vote::Yes,
+            options: vec![option(Vote::No, 40), option(Vote::Abstain, 60)],
         };
-        app.execute_contract(Addr::unchecked(VOTER2), flex_addr.clone(), &yes_vote, &[])
+        app.execute_contract(Addr::unchecked(VOTER5), flex_addr.clone(), &split, &[])
+            .unwrap();
+        assert_eq!((tally(&app).votes.no, tally(&app).votes.abstain), (2, 3));
+        let vote = |vote| ExecuteMsg::Vote { proposal_id, vote };
+        let res = app
+            .execute_contract(
+                Addr::unchecked(VOTER5),
+                flex_addr.clone(),
+                &vote(Vote::Yes),
+                &[],
+            )
             .unwrap();
-        assert_eq!(prop_status(&app, proposal_id), Status::Open);
+        assert!(res.has_event(
+            &Event::new("wasm-vote_changed")
+                .add_attribute("proposal_id", proposal_id.to_string())
+                .add_attribute("voter", VOTER5)
+                .add_attribute("previous_vote", "Abstain")
+                .add_attribute("vote", "Yes")
+        ));
+        let tally = tally(&app);
+        assert_eq!(
+            tally.votes,
+            Votes {
+                yes: 6,
+                no: 0,
+                abstain: 0,
+                veto: 0
+            }
+        );
+        assert_eq!(tally.status, Status::Open);
 
-        // newbie cannot vote
+        // Once passed, votes are final
+        app.execute_contract(
+            Addr::unchecked(VOTER4),
+            flex_addr.clone(),
+            &vote(Vote::Yes),
+            &[],
+        )
+        .unwrap();
         let err = app
-            .execute_contract(Addr::unchecked(newbie), flex_addr.clone(), &yes_vote, &[])
+            .execute_contract(Addr::unchecked(VOTER4), flex_addr, &vote(Vote::No), &[])
             .unwrap_err();
-        assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+        assert_eq!(ContractError::AlreadyVoted {}, err.downcast().unwrap());
+    }
 
-        // previously removed VOTER3 can still vote, passing the proposal
-        app.execute_contract(Addr::unchecked(VOTER3), flex_addr.clone(), &yes_vote, &[])
-            .unwrap();
+    #[test]
+    fn proposals_restricted_to_proposers() {
+        let mut app = mock_app(&[]);
+        let members = vec![member(VOTER1, 1), member(VOTER4, 12), member(VOTER5, 5)];
+        let group_addr = instantiate_group(&mut app, members);
+        app.update_block(next_block);
+        let flex_id = app.store_code(contract_flex());
+        let instantiate = |app: &mut App, proposers| {
+            let msg = InstantiateMsg {
+                group_addr: group_addr.to_string(),
+                threshold: Threshold::AbsoluteCount { weight: 13 },
+                max_voting_period: Duration::Height(10),
+                executor: None,
+                proposal_deposit: None,
+                veto: None,
+                close_reward: None,
+                execution_delay: None,
+                threshold_profiles: vec![],
+                allow_revoting: false,
+                proposers,
+                extra_groups: vec![],
+                emergency: None,
+                quadratic_voting: false,
+                timelock: None,
+                private_voting: None,
+            };
+            app.instantiate_contract(flex_id, Addr::unchecked(OWNER), &msg, &[], "flex", None)
+        };
+        let propose = |app: &mut App, flex_addr: &Addr, proposer: &str| {
+            app.execute_contract(
+                Addr::unchecked(proposer),
+                flex_addr.clone(),
+                &pay_somebody_proposal(),
+                &[],
+            )
+        };
 
-        // check current threshold (global) is updated
-        let threshold: ThresholdResponse = app
+        // The allowlist must not be empty
+        let err = instantiate(&mut app, Some(UncheckedProposers::Only(vec![]))).unwrap_err();
+        assert_eq!(ContractError::NoProposers {}, err.downcast().unwrap());
+
+        // A council, which may be outside the group, proposes without voting
+        let council = vec![VOTER4.to_string(), SOMEBODY.to_string()];
+        let flex_addr = instantiate(&mut app, Some(UncheckedProposers::Only(council))).unwrap();
+        app.update_block(next_block);
+        let err = propose(&mut app, &flex_addr, VOTER5).unwrap_err();
+        assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+        let res = propose(&mut app, &flex_addr, SOMEBODY).unwrap();
+        let proposal_id: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
+        let tally: TallyResponse = app
             .wrap()
-            .query_wasm_smart(&flex_addr, &QueryMsg::Threshold {})
+            .query_wasm_smart(&flex_addr, &QueryMsg::ProposalTally { proposal_id })
             .unwrap();
-        let expected_thresh = ThresholdResponse::ThresholdQuorum {
-            total_weight: 41,
-            threshold: Decimal::percent(51),
-            quorum: Decimal::percent(1),
+        assert_eq!(tally.votes, Votes::yes(0));
+        // while the rest of the group still votes
+        let vote = ExecuteMsg::Vote {
+            proposal_id,
+            vote: Vote::Yes,
         };
-        assert_eq!(expected_thresh, threshold);
+        app.execute_contract(Addr::unchecked(VOTER5), flex_addr.clone(), &vote, &[])
+            .unwrap();
+        let res = propose(&mut app, &flex_addr, VOTER4).unwrap();
+        assert_eq!(res.custom_attrs(1)[3].value, "Open");
 
-        // TODO: check proposal threshold not changed
+        // Or only the members with enough weight
+        let flex_addr = instantiate(&mut app, Some(UncheckedProposers::MinWeight(5))).unwrap();
+        app.update_block(next_block);
+        let err = propose(&mut app, &flex_addr, VOTER1).unwrap_err();
+        assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+        let err = propose(&mut app, &flex_addr, SOMEBODY).unwrap_err();
+        assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+        propose(&mut app, &flex_addr, VOTER5).unwrap();
     }
 
-    // uses the power from the beginning of the voting period
-    // similar to above - simpler case, but shows that one proposals can
-    // trigger the action
     #[test]
-    fn execute_group_changes_from_proposal() {
+    fn scheduled_execution_within_window() {
         let init_funds = coins(10, "BTC");
         let mut app = mock_app(&init_funds);
+        let (flex_addr, _) =
+            setup_test_case_fixed(&mut app, 12, Duration::Height(10), init_funds, true);
+
+        let height = app.block_info().height;
+        let proposal = |execution_window| {
+            let (msgs, title, description) = proposal_info();
+            ExecuteMsg::Propose {
+                title,
+                description,
+                msgs,
+                latest: None,
+                threshold_profile: None,
+                execution_window: Some(execution_window),
+                metadata: None,
+            }
+        };
 
-        let required_weight = 4;
-        let voting_period = Duration::Time(20000);
-        let (flex_addr, group_addr) =
-            setup_test_case_fixed(&mut app, required_weight, voting_period, init_funds, true);
-
-        // Start a proposal to remove VOTER3 from the set
-        let update_msg = Cw4GroupContract::new(group_addr)
-            .update_members(vec![VOTER3.into()], vec![])
-            .unwrap();
-        let update_proposal = ExecuteMsg::Propose {
-            title: "Kick out VOTER3".to_string(),
-            description: "He's trying to steal our money".to_string(),
-            msgs: vec![update_msg],
-            latest: None,
+        // The window has the unit of the voting period
+        let window = ExecutionWindow {
+            start: Expiration::AtTime(app.block_info().time.plus_seconds(100)),
+            end: Expiration::Never {},
         };
-        let res = app
+        let err = app
             .execute_contract(
-                Addr::unchecked(VOTER1),
+                Addr::unchecked(VOTER4),
                 flex_addr.clone(),
-                &update_proposal,
+                &proposal(window),
                 &[],
             )
-            .unwrap();
-        // Get the proposal id from the logs
-        let update_proposal_id: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
-
-        // next block...
-        app.update_block(|b| b.height += 1);
+            .unwrap_err();
+        assert_eq!(
+            ContractError::InvalidExecutionWindow {},
+            err.downcast().unwrap()
+        );
 
-        // VOTER1 starts a proposal to send some tokens
-        let cash_proposal = pay_somebody_proposal();
+        // Passed by VOTER4 alone, it waits for its window
+        let window = ExecutionWindow {
+            start: Expiration::AtHeight(height + 5),
+            end: Expiration::AtHeight(height + 8),
+        };
         let res = app
             .execute_contract(
-                Addr::unchecked(VOTER1),
+                Addr::unchecked(VOTER4),
                 flex_addr.clone(),
-                &cash_proposal,
+                &proposal(window.clone()),
                 &[],
             )
             .unwrap();
-        // Get the proposal id from the logs
-        let cash_proposal_id: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
-        assert_ne!(cash_proposal_id, update_proposal_id);
-
-        // query proposal state
-        let prop_status = |app: &App, proposal_id: u64| -> Status {
-            let query_prop = QueryMsg::Proposal { proposal_id };
-            let prop: ProposalResponse = app
-                .wrap()
-                .query_wasm_smart(&flex_addr, &query_prop)
-                .unwrap();
-            prop.status
-        };
-        assert_eq!(prop_status(&app, cash_proposal_id), Status::Open);
-        assert_eq!(prop_status(&app, update_proposal_id), Status::Open);
-
-        // next block...
-        app.update_block(|b| b.height += 1);
-
-        // Pass and execute first proposal
-        let yes_vote = ExecuteMsg::Vote {
-            proposal_id: update_proposal_id,
-            vote: Vote::Yes,
-        };
-        app.execute_contract(Addr::unchecked(VOTER4), flex_addr.clone(), &yes_vote, &[])
+        let proposal_id: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
+        let executable_at: ExecutableAtResponse = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &QueryMsg::ExecutableAt { proposal_id })
             .unwrap();
-        let execution = ExecuteMsg::Execute {
-            proposal_id: update_proposal_id,
-        };
-        app.execute_contract(Addr::unchecked(VOTER4), flex_addr.clone(), &execution, &[])
+        assert_eq!(executable_at.executable_at, Some(window.start));
+        let scheduled: ProposalListResponse = app
+            .wrap()
+            .query_wasm_smart(
+                &flex_addr,
+                &QueryMsg::ScheduledExecutions {
+                    start_after: None,
+                    limit: None,
+                },
+            )
             .unwrap();
-
-        // ensure that the update_proposal is executed, but the other unchanged
-        assert_eq!(prop_status(&app, update_proposal_id), Status::Executed);
-        assert_eq!(prop_status(&app, cash_proposal_id), Status::Open);
-
-        // next block...
-        app.update_block(|b| b.height += 1);
-
-        // VOTER3 can still pass the cash proposal
-        // voting on it fails
-        let yes_vote = ExecuteMsg::Vote {
-            proposal_id: cash_proposal_id,
-            vote: Vote::Yes,
+        assert_eq!(scheduled.proposals[0].execution_window, Some(window));
+        let execution = ExecuteMsg::Execute {
+            proposal_id,
+            msg_indices: None,
         };
-        app.execute_contract(Addr::unchecked(VOTER3), flex_addr.clone(), &yes_vote, &[])
-            .unwrap();
-        assert_eq!(prop_status(&app, cash_proposal_id), Status::Passed);
-
-        // but cannot open a new one
-        let cash_proposal = pay_somebody_proposal();
         let err = app
             .execute_contract(
-                Addr::unchecked(VOTER3),
+                Addr::unchecked(SOMEBODY),
                 flex_addr.clone(),
-                &cash_proposal,
+                &execution,
                 &[],
             )
             .unwrap_err();
-        assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+        assert_eq!(
+            ContractError::OutsideExecutionWindow {},
+            err.downcast().unwrap()
+        );
 
-        // extra: ensure no one else can call the hook
-        let hook_hack = ExecuteMsg::MemberChangedHook(MemberChangedHookMsg {
-            diffs: vec![MemberDiff::new(VOTER1, Some(1), None)],
-        });
-        let err = app
-            .execute_contract(Addr::unchecked(VOTER2), flex_addr.clone(), &hook_hack, &[])
-            .unwrap_err();
-        assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+        // Then it can be executed
+        app.update_block(|block| block.height += 5);
+        app.execute_contract(
+            Addr::unchecked(SOMEBODY),
+            flex_addr.clone(),
+            &execution,
+            &[],
+        )
+        .unwrap();
+        let balance = app.wrap().query_balance(SOMEBODY, "BTC").unwrap();
+        assert_eq!(balance.amount, Uint128::new(1));
+        let scheduled: ProposalListResponse = app
+            .wrap()
+            .query_wasm_smart(
+                &flex_addr,
+                &QueryMsg::ScheduledExecutions {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(scheduled.proposals, vec![]);
     }
 
-    // uses the power from the beginning of the voting period
     #[test]
-    fn percentage_handles_group_changes() {
-        let init_funds = coins(10, "BTC");
-        let mut app = mock_app(&init_funds);
+    fn voting_power_summed_over_groups() {
+        let mut app = mock_app(&[]);
+        let stakers = vec![member(VOTER1, 1), member(VOTER2, 2), member(VOTER4, 4)];
+        let stakers_addr = instantiate_group(&mut app, stakers);
+        let council = vec![member(VOTER2, 1), member(VOTER5, 1)];
+        let council_addr = instantiate_group(&mut app, council);
+        app.update_block(next_block);
+        let flex_id = app.store_code(contract_flex());
+        let instantiate = |app: &mut App, extra_groups| {
+            let msg = InstantiateMsg {
+                group_addr: stakers_addr.to_string(),
+                threshold: Threshold::AbsoluteCount { weight: 9 },
+                max_voting_period: Duration::Height(10),
+                executor: None,
+                proposal_deposit: None,
+                veto: None,
+                close_reward: None,
+                execution_delay: None,
+                threshold_profiles: vec![],
+                allow_revoting: false,
+                proposers: None,
+                extra_groups,
+                emergency: None,
+                quadratic_voting: false,
+                timelock: None,
+                private_voting: None,
+            };
+            app.instantiate_contract(flex_id, Addr::unchecked(OWNER), &msg, &[], "flex", None)
+        };
+        let council_group = |multiplier| UncheckedWeightedGroup {
+            addr: council_addr.to_string(),
+            multiplier,
+        };
 
-        // 51% required, which is 12 of the initial 24
-        let threshold = Threshold::ThresholdQuorum {
-            threshold: Decimal::percent(51),
-            quorum: Decimal::percent(1),
+        // Multipliers must not be 0, and groups not be repeated
+        let err = instantiate(&mut app, vec![council_group(0)]).unwrap_err();
+        assert_eq!(
+            ContractError::InvalidGroupMultiplier {},
+            err.downcast().unwrap()
+        );
+        let stakers_group = UncheckedWeightedGroup {
+            addr: stakers_addr.to_string(),
+            multiplier: 2,
         };
-        let voting_period = Duration::Time(20000);
-        let (flex_addr, group_addr) = setup_test_case(
-            &mut app,
+        let err = instantiate(&mut app, vec![stakers_group]).unwrap_err();
+        assert_eq!(
+            ContractError::InvalidGroup {
+                addr: stakers_addr.to_string()
+            },
+            err.downcast().unwrap()
+        );
+
+        // Weights too large to be summed are rejected rather than wrapping around
+        let err = instantiate(&mut app, vec![council_group(u64::MAX)]).unwrap_err();
+        assert!(matches!(
+            err.downcast().unwrap(),
+            ContractError::Std(StdError::Overflow { .. })
+        ));
+
+        // Each council seat counts 3 times
+        let flex_addr = instantiate(&mut app, vec![council_group(3)]).unwrap();
+        app.update_block(next_block);
+        let threshold: ThresholdResponse = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &QueryMsg::Threshold {})
+            .unwrap();
+        assert_eq!(
             threshold,
-            voting_period,
-            init_funds,
-            false,
-            None,
-            None,
+            ThresholdResponse::AbsoluteCount {
+                weight: 9,
+                total_weight: 13
+            }
+        );
+        let voters: VoterListResponse = app
+            .wrap()
+            .query_wasm_smart(
+                &flex_addr,
+                &QueryMsg::ListVoters {
+                    start_after: Some(VOTER1.into()),
+                    limit: Some(3),
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            voters.voters,
+            vec![
+                VoterDetail {
+                    addr: VOTER2.into(),
+                    weight: 5
+                },
+                VoterDetail {
+                    addr: VOTER4.into(),
+                    weight: 4
+                },
+                VoterDetail {
+                    addr: VOTER5.into(),
+                    weight: 3
+                },
+            ]
         );
+        let voter: VoterResponse = app
+            .wrap()
+            .query_wasm_smart(
+                &flex_addr,
+                &QueryMsg::Voter {
+                    address: SOMEBODY.into(),
+                },
+            )
+            .unwrap();
+        assert_eq!(voter.weight, None);
+
+        // The proposer votes with both seats
+        let res = app
+            .execute_contract(
+                Addr::unchecked(VOTER2),
+                flex_addr.clone(),
+                &pay_somebody_proposal(),
+                &[],
+            )
+            .unwrap();
+        let proposal_id: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
+
+        // Members joining the council afterwards do not vote on it
+        let add = cw4_group::msg::ExecuteMsg::UpdateMembers {
+            remove: vec![],
+            add: vec![member(VOTER3, 1)],
+        };
+        app.execute_contract(Addr::unchecked(OWNER), council_addr.clone(), &add, &[])
+            .unwrap();
+        app.update_block(next_block);
+        let vote = ExecuteMsg::Vote {
+            proposal_id,
+            vote: Vote::Yes,
+        };
+        let err = app
+            .execute_contract(Addr::unchecked(VOTER3), flex_addr.clone(), &vote, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
 
-        // VOTER3 starts a proposal to send some tokens (3/12 votes)
-        let proposal = pay_somebody_proposal();
         let res = app
-            .execute_contract(Addr::unchecked(VOTER3), flex_addr.clone(), &proposal, &[])
+            .execute_contract(Addr::unchecked(VOTER4), flex_addr, &vote, &[])
             .unwrap();
-        // Get the proposal id from the logs
-        let proposal_id: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
-        let prop_status = |app: &App| -> Status {
-            let query_prop = QueryMsg::Proposal { proposal_id };
-            let prop: ProposalResponse = app
-                .wrap()
-                .query_wasm_smart(&flex_addr, &query_prop)
-                .unwrap();
-            prop.status
-        };
+        assert_eq!(res.custom_attrs(1)[3].value, "Passed");
+    }
 
-        // 3/12 votes
-        assert_eq!(prop_status(&app), Status::Open);
+    #[test]
+    fn emergency_proposals_for_council() {
+        let mut app = mock_app(&[]);
+        let members = vec![
+            member(VOTER1, 1),
+            member(VOTER2, 2),
+            member(VOTER3, 3),
+            member(VOTER4, 4),
+        ];
+        let group_addr = instantiate_group(&mut app, members);
+        app.update_block(next_block);
+        let flex_id = app.store_code(contract_flex());
+        let instantiate = |app: &mut App, voting_period| {
+            let msg = InstantiateMsg {
+                group_addr: group_addr.to_string(),
+                threshold: Threshold::AbsoluteCount { weight: 4 },
+                max_voting_period: Duration::Height(10),
+                executor: None,
+                proposal_deposit: None,
+                veto: None,
+                close_reward: None,
+                execution_delay: None,
+                threshold_profiles: vec![],
+                allow_revoting: false,
+                proposers: Some(UncheckedProposers::MinWeight(4)),
+                extra_groups: vec![],
+                emergency: Some(UncheckedEmergencyTrack {
+                    council: vec![VOTER1.to_string(), SOMEBODY.to_string()],
+                    threshold: Threshold::AbsoluteCount { weight: 8 },
+                    voting_period,
+                }),
+                quadratic_voting: false,
+                timelock: None,
+                private_voting: None,
+            };
+            app.instantiate_contract(flex_id, Addr::unchecked(OWNER), &msg, &[], "flex", None)
+        };
 
-        // a few blocks later...
-        app.update_block(|block| block.height += 2);
+        // The emergency voting period has the unit of the max voting period
+        let err = instantiate(&mut app, Duration::Time(3600)).unwrap_err();
+        assert_eq!(
+            ContractError::InvalidEmergencyTrack {},
+            err.downcast().unwrap()
+        );
 
-        // admin changes the group (3 -> 0, 2 -> 9, 0 -> 29) - total = 56, require 29 to pass
-        let newbie: &str = "newbie";
-        let update_msg = cw4_group::msg::ExecuteMsg::UpdateMembers {
-            remove: vec![VOTER3.into()],
-            add: vec![member(VOTER2, 9), member(newbie, 29)],
+        let flex_addr = instantiate(&mut app, Duration::Height(2)).unwrap();
+        app.update_block(next_block);
+        let proposal = ExecuteMsg::ProposeEmergency {
+            title: "Pause".to_string(),
+            description: "Pause the protocol".to_string(),
+            msgs: vec![],
+            metadata: None,
         };
-        app.execute_contract(Addr::unchecked(OWNER), group_addr, &update_msg, &[])
-            .unwrap();
 
-        // a few blocks later...
-        app.update_block(|block| block.height += 3);
+        // Only for the members in the council, who need not be usual proposers
+        for proposer in [OWNER, SOMEBODY] {
+            let err = app
+                .execute_contract(Addr::unchecked(proposer), flex_addr.clone(), &proposal, &[])
+                .unwrap_err();
+            assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+        }
+        let res = app
+            .execute_contract(Addr::unchecked(VOTER1), flex_addr.clone(), &proposal, &[])
+            .unwrap();
+        let proposal_id: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
+        let prop: ProposalResponse = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &QueryMsg::Proposal { proposal_id })
+            .unwrap();
+        assert_eq!(prop.kind, ProposalKind::Emergency);
+        assert_eq!(
+            prop.expires,
+            Expiration::AtHeight(app.block_info().height + 2)
+        );
 
-        // VOTER2 votes according to original weights: 3 + 2 = 5 / 12 => Open
-        // with updated weights, it would be 3 + 9 = 12 / 12 => Passed
-        let yes_vote = ExecuteMsg::Vote {
+        // The emergency threshold applies, rather than the usual one
+        let vote = ExecuteMsg::Vote {
             proposal_id,
             vote: Vote::Yes,
         };
-        app.execute_contract(Addr::unchecked(VOTER2), flex_addr.clone(), &yes_vote, &[])
+        let res = app
+            .execute_contract(Addr::unchecked(VOTER3), flex_addr.clone(), &vote, &[])
             .unwrap();
-        assert_eq!(prop_status(&app), Status::Open);
-
-        // new proposal can be passed single-handedly by newbie
-        let proposal = pay_somebody_proposal();
+        assert_eq!(res.custom_attrs(1)[3].value, "Open");
         let res = app
-            .execute_contract(Addr::unchecked(newbie), flex_addr.clone(), &proposal, &[])
+            .execute_contract(Addr::unchecked(VOTER4), flex_addr, &vote, &[])
             .unwrap();
-        // Get the proposal id from the logs
-        let proposal_id2: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
+        assert_eq!(res.custom_attrs(1)[3].value, "Passed");
+    }
 
-        // check proposal2 status
-        let query_prop = QueryMsg::Proposal {
-            proposal_id: proposal_id2,
-        };
-        let prop: ProposalResponse = app
-            .wrap()
-            .query_wasm_smart(&flex_addr, &query_prop)
-            .unwrap();
-        assert_eq!(Status::Passed, prop.status);
+    #[test]
+    fn integer_square_root() {
+        let roots = [0, 1, 1, 1, 2, 2, 2, 2, 2, 3];
+        for (n, root) in roots.iter().enumerate() {
+            assert_eq!(isqrt(n as u64), *root);
+        }
+        assert_eq!(isqrt(15), 3);
+        assert_eq!(isqrt(16), 4);
+        assert_eq!(isqrt(1_000_000), 1000);
+        assert_eq!(isqrt(999_999), 999);
+        assert_eq!(isqrt(u64::MAX), u32::MAX as u64);
     }
 
-    // uses the power from the beginning of the voting period
     #[test]
-    fn quorum_handles_group_changes() {
-        let init_funds = coins(10, "BTC");
-        let mut app = mock_app(&init_funds);
+    fn quadratic_voting_dampens_whales() {
+        let mut app = mock_app(&[]);
+        let members = vec![member(VOTER1, 1), member(VOTER2, 4), member(VOTER3, 100)];
+        let group_addr = instantiate_group(&mut app, members);
+        app.update_block(next_block);
+        let flex_id = app.store_code(contract_flex());
+        let msg = InstantiateMsg {
+            group_addr: group_addr.to_string(),
+            threshold: Threshold::AbsoluteCount { weight: 11 },
+            max_voting_period: Duration::Height(10),
+            executor: None,
+            proposal_deposit: None,
+            veto: None,
+            close_reward: None,
+            execution_delay: None,
+            threshold_profiles: vec![],
+            allow_revoting: false,
+            proposers: None,
+            extra_groups: vec![],
+            emergency: None,
+            quadratic_voting: true,
+            timelock: None,
+            private_voting: None,
+        };
+        let flex_addr = app
+            .instantiate_contract(flex_id, Addr::unchecked(OWNER), &msg, &[], "flex", None)
+            .unwrap();
+        app.update_block(next_block);
 
-        // 33% required for quora, which is 8 of the initial 24
-        // 50% yes required to pass early (12 of the initial 24)
-        let voting_period = Duration::Time(20000);
-        let (flex_addr, group_addr) = setup_test_case(
-            &mut app,
-            Threshold::ThresholdQuorum {
-                threshold: Decimal::percent(51),
-                quorum: Decimal::percent(33),
-            },
-            voting_period,
-            init_funds,
-            false,
-            None,
-            None,
+        // Voting power is the square root of the weight, and so is the total
+        let threshold: ThresholdResponse = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &QueryMsg::Threshold {})
+            .unwrap();
+        assert_eq!(
+            threshold,
+            ThresholdResponse::AbsoluteCount {
+                weight: 11,
+                total_weight: 13
+            }
         );
+        let voters: VoterListResponse = app
+            .wrap()
+            .query_wasm_smart(
+                &flex_addr,
+                &QueryMsg::ListVoters {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        let weights: Vec<_> = voters.voters.iter().map(|voter| voter.weight).collect();
+        assert_eq!(weights, vec![1, 2, 10]);
 
-        // VOTER3 starts a proposal to send some tokens (3 votes)
-        let proposal = pay_somebody_proposal();
+        // so the largest member cannot pass proposals alone
         let res = app
-            .execute_contract(Addr::unchecked(VOTER3), flex_addr.clone(), &proposal, &[])
+            .execute_contract(
+                Addr::unchecked(VOTER3),
+                flex_addr.clone(),
+                &pay_somebody_proposal(),
+                &[],
+            )
             .unwrap();
-        // Get the proposal id from the logs
+        assert_eq!(res.custom_attrs(1)[3].value, "Open");
         let proposal_id: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
-        let prop_status = |app: &App| -> Status {
-            let query_prop = QueryMsg::Proposal { proposal_id };
-            let prop: ProposalResponse = app
-                .wrap()
-                .query_wasm_smart(&flex_addr, &query_prop)
-                .unwrap();
-            prop.status
+        let vote = ExecuteMsg::Vote {
+            proposal_id,
+            vote: Vote::Yes,
         };
+        let res = app
+            .execute_contract(Addr::unchecked(VOTER1), flex_addr.clone(), &vote, &[])
+            .unwrap();
+        assert_eq!(res.custom_attrs(1)[3].value, "Passed");
 
-        // 3/12 votes - not expired
-        assert_eq!(prop_status(&app), Status::Open);
-
-        // a few blocks later...
-        app.update_block(|block| block.height += 2);
-
-        // admin changes the group (3 -> 0, 2 -> 9, 0 -> 28) - total = 55, require 28 to pass
-        let newbie: &str = "newbie";
-        let update_msg = cw4_group::msg::ExecuteMsg::UpdateMembers {
-            remove: vec![VOTER3.into()],
-            add: vec![member(VOTER2, 9), member(newbie, 29)],
+        // The total is kept up to date by the membership hook, without listing the members
+        let add_hook = cw4_group::msg::ExecuteMsg::AddHook {
+            addr: flex_addr.to_string(),
         };
-        app.execute_contract(Addr::unchecked(OWNER), group_addr, &update_msg, &[])
+        app.execute_contract(Addr::unchecked(OWNER), group_addr.clone(), &add_hook, &[])
             .unwrap();
-
-        // a few blocks later...
-        app.update_block(|block| block.height += 3);
-
-        // VOTER2 votes yes, according to original weights: 3 yes, 2 no, 5 total (will fail when expired)
-        // with updated weights, it would be 3 yes, 9 yes, 11 total (will pass when expired)
-        let yes_vote = ExecuteMsg::Vote {
-            proposal_id,
-            vote: Vote::Yes,
+        let update = cw4_group::msg::ExecuteMsg::UpdateMembers {
+            remove: vec![VOTER1.into()],
+            add: vec![member(VOTER3, 81), member(VOTER5, 9)],
         };
-        app.execute_contract(Addr::unchecked(VOTER2), flex_addr.clone(), &yes_vote, &[])
+        app.execute_contract(Addr::unchecked(OWNER), group_addr, &update, &[])
             .unwrap();
-        // not expired yet
-        assert_eq!(prop_status(&app), Status::Open);
+        let threshold: ThresholdResponse = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &QueryMsg::Threshold {})
+            .unwrap();
+        assert_eq!(
+            threshold,
+            ThresholdResponse::AbsoluteCount {
+                weight: 11,
+                total_weight: 14
+            }
+        );
 
-        // wait until the vote is over, and see it was rejected
-        app.update_block(expire(voting_period));
-        assert_eq!(prop_status(&app), Status::Rejected);
+        // Only the groups can call the hook
+        let hook = ExecuteMsg::MemberChangedHook(MemberChangedHookMsg::one(
+            MemberDiff::new(VOTER1, None, Some(100)),
+            TotalWeightDiff::new(90, 190),
+        ));
+        let err = app
+            .execute_contract(Addr::unchecked(VOTER1), flex_addr, &hook, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
     }
 
     #[test]
-    fn quorum_enforced_even_if_absolute_threshold_met() {
-        let init_funds = coins(10, "BTC");
-        let mut app = mock_app(&init_funds);
-
-        // 33% required for quora, which is 5 of the initial 15
-        // 50% yes required to pass early (8 of the initial 15)
-        let voting_period = Duration::Time(20000);
+    fn cw20_deposit_sent_with_proposal() {
+        let mut app = App::default();
+        let cw20_id = app.store_code(contract_cw20());
+        let cw20_addr = app
+            .instantiate_contract(
+                cw20_id,
+                Addr::unchecked(OWNER),
+                &cw20_base::msg::InstantiateMsg {
+                    name: "Token".to_string(),
+                    symbol: "TOKEN".to_string(),
+                    decimals: 6,
+                    initial_balances: vec![Cw20Coin {
+                        address: OWNER.to_string(),
+                        amount: Uint128::new(10),
+                    }],
+                    mint: None,
+                    marketing: None,
+                    snapshots: false,
+                    freezer: None,
+                    pauser: None,
+                },
+                &[],
+                "Token",
+                None,
+            )
+            .unwrap();
         let (flex_addr, _) = setup_test_case(
             &mut app,
-            // note that 60% yes is not enough to pass without 20% no as well
-            Threshold::ThresholdQuorum {
-                threshold: Decimal::percent(60),
-                quorum: Decimal::percent(80),
-            },
-            voting_period,
-            init_funds,
-            false,
-            None,
+            Threshold::AbsoluteCount { weight: 10 },
+            Duration::Height(10),
+            vec![],
+            true,
             None,
+            Some(UncheckedDepositInfo {
+                amount: Uint128::new(10),
+                denom: UncheckedDenom::Cw20(cw20_addr.to_string()),
+                refund_failed_proposals: false,
+                refund_passed_proposals: true,
+                forfeit: Some(UncheckedDepositForfeit::Burn {}),
+            }),
+        );
+        let send = |amount, msg: &ExecuteMsg| cw20::Cw20ExecuteMsg::Send {
+            contract: flex_addr.to_string(),
+            amount: Uint128::new(amount),
+            msg: to_binary(msg).unwrap(),
+        };
+        let balance = |app: &App, address: &Addr| {
+            let balance: cw20::BalanceResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    &cw20_addr,
+                    &cw20::Cw20QueryMsg::Balance {
+                        address: address.to_string(),
+                    },
+                )
+                .unwrap();
+            balance.balance.u128()
+        };
+
+        // The whole deposit must be sent, along with a proposal
+        let err = app
+            .execute_contract(
+                Addr::unchecked(OWNER),
+                cw20_addr.clone(),
+                &send(5, &text_proposal()),
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            ContractError::Deposit(DepositError::InvalidDeposit {}).to_string()
+        );
+        let vote = ExecuteMsg::Vote {
+            proposal_id: 1,
+            vote: Vote::Yes,
+        };
+        let err = app
+            .execute_contract(
+                Addr::unchecked(OWNER),
+                cw20_addr.clone(),
+                &send(10, &vote),
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            ContractError::NotProposal {}.to_string()
         );
 
-        // create proposal
-        let proposal = pay_somebody_proposal();
-        let res = app
-            .execute_contract(Addr::unchecked(VOTER5), flex_addr.clone(), &proposal, &[])
+        // The sender of the tokens proposes, without any allowance
+        app.execute_contract(
+            Addr::unchecked(OWNER),
+            cw20_addr.clone(),
+            &send(10, &text_proposal()),
+            &[],
+        )
+        .unwrap();
+        let prop: ProposalResponse = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &QueryMsg::Proposal { proposal_id: 1 })
+            .unwrap();
+        assert_eq!(prop.proposer, Addr::unchecked(OWNER));
+        assert_eq!(prop.deposit.unwrap().denom, Denom::Cw20(cw20_addr.clone()));
+        assert_eq!(prop.deposit_status, Some(DepositStatus::Held));
+        assert_eq!(balance(&app, &flex_addr), 10);
+
+        // and the deposit is burned as for allowances, once rejected
+        app.update_block(|b| b.height += 10);
+        app.execute_contract(
+            Addr::unchecked(VOTER1),
+            flex_addr.clone(),
+            &ExecuteMsg::Close { proposal_id: 1 },
+            &[],
+        )
+        .unwrap();
+        assert_eq!(balance(&app, &flex_addr), 0);
+        assert_eq!(balance(&app, &Addr::unchecked(OWNER)), 0);
+        let info: cw20::TokenInfoResponse = app
+            .wrap()
+            .query_wasm_smart(&cw20_addr, &cw20::Cw20QueryMsg::TokenInfo {})
             .unwrap();
-        // Get the proposal id from the logs
-        let proposal_id: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
-        let prop_status = |app: &App| -> Status {
-            let query_prop = QueryMsg::Proposal { proposal_id };
-            let prop: ProposalResponse = app
+        assert_eq!(info.total_supply, Uint128::zero());
+    }
+
+    #[test]
+    fn pending_votes_listed() {
+        let mut app = mock_app(&[]);
+        let members = vec![member(VOTER1, 1), member(VOTER2, 2), member(VOTER3, 3)];
+        let group_addr = instantiate_group(&mut app, members);
+        app.update_block(next_block);
+        let flex_id = app.store_code(contract_flex());
+        let msg = InstantiateMsg {
+            group_addr: group_addr.to_string(),
+            threshold: Threshold::AbsoluteCount { weight: 5 },
+            max_voting_period: Duration::Height(10),
+            executor: None,
+            proposal_deposit: None,
+            veto: None,
+            close_reward: None,
+            execution_delay: None,
+            threshold_profiles: vec![],
+            allow_revoting: false,
+            proposers: None,
+            extra_groups: vec![],
+            emergency: None,
+            quadratic_voting: false,
+            timelock: None,
+            private_voting: None,
+        };
+        let flex_addr = app
+            .instantiate_contract(flex_id, Addr::unchecked(OWNER), &msg, &[], "flex", None)
+            .unwrap();
+        app.update_block(next_block);
+        app.execute_contract(
+            Addr::unchecked(VOTER1),
+            flex_addr.clone(),
+            &pay_somebody_proposal(),
+            &[],
+        )
+        .unwrap();
+        let pending = |app: &App, voter: &str| {
+            let res: ProposalListResponse = app
                 .wrap()
-                .query_wasm_smart(&flex_addr, &query_prop)
+                .query_wasm_smart(
+                    &flex_addr,
+                    &QueryMsg::PendingVotes {
+                        voter: voter.to_string(),
+                        start_after: None,
+                        limit: None,
+                    },
+                )
                 .unwrap();
-            prop.status
+            res.proposals.iter().map(|p| p.id).collect::<Vec<u64>>()
         };
-        assert_eq!(prop_status(&app), Status::Open);
-        app.update_block(|block| block.height += 3);
 
-        // reach 60% of yes votes, not enough to pass early (or late)
-        let yes_vote = ExecuteMsg::Vote {
-            proposal_id,
-            vote: Vote::Yes,
+        // Members who joined after the proposal was created cannot vote on it
+        let add = cw4_group::msg::ExecuteMsg::UpdateMembers {
+            remove: vec![],
+            add: vec![member(VOTER4, 4)],
         };
-        app.execute_contract(Addr::unchecked(VOTER4), flex_addr.clone(), &yes_vote, &[])
+        app.execute_contract(Addr::unchecked(OWNER), group_addr, &add, &[])
             .unwrap();
-        // 9 of 15 is 60% absolute threshold, but less than 12 (80% quorum needed)
-        assert_eq!(prop_status(&app), Status::Open);
+        app.update_block(next_block);
+        assert_eq!(pending(&app, VOTER1), Vec::<u64>::new());
+        assert_eq!(pending(&app, VOTER2), vec![1]);
+        assert_eq!(pending(&app, VOTER3), vec![1]);
+        assert_eq!(pending(&app, VOTER4), Vec::<u64>::new());
 
-        // add 3 weight no vote and we hit quorum and this passes
-        let no_vote = ExecuteMsg::Vote {
-            proposal_id,
+        // Voting, or the proposal expiring, clears the pending votes
+        let vote = ExecuteMsg::Vote {
+            proposal_id: 1,
             vote: Vote::No,
         };
-        app.execute_contract(Addr::unchecked(VOTER3), flex_addr.clone(), &no_vote, &[])
+        app.execute_contract(Addr::unchecked(VOTER2), flex_addr.clone(), &vote, &[])
             .unwrap();
-        assert_eq!(prop_status(&app), Status::Passed);
+        assert_eq!(pending(&app, VOTER2), Vec::<u64>::new());
+        app.update_block(|b| b.height += 10);
+        assert_eq!(pending(&app, VOTER3), Vec::<u64>::new());
+    }
+
+    #[cosmwasm_schema::cw_serde]
+    enum HookExecuteMsg {
+        ProposalHook(ProposalHookMsg),
+    }
+
+    const HOOK_MSGS: cw_storage_plus::Item<Vec<ProposalHookMsg>> =
+        cw_storage_plus::Item::new("hook_msgs");
+
+    // records the proposal hook messages it is sent, returning them to any query
+    fn contract_hook_recorder() -> Box<dyn Contract<Empty>> {
+        fn instantiate(deps: DepsMut, _: Env, _: MessageInfo, _: Empty) -> StdResult<Response> {
+            HOOK_MSGS.save(deps.storage, &vec![])?;
+            Ok(Response::new())
+        }
+        fn execute(
+            deps: DepsMut,
+            _: Env,
+            _: MessageInfo,
+            msg: HookExecuteMsg,
+        ) -> StdResult<Response> {
+            let HookExecuteMsg::ProposalHook(msg) = msg;
+            HOOK_MSGS.update(deps.storage, |mut msgs| -> StdResult<_> {
+                msgs.push(msg);
+                Ok(msgs)
+            })?;
+            Ok(Response::new())
+        }
+        fn query(deps: Deps, _: Env, _: Empty) -> StdResult<Binary> {
+            to_binary(&HOOK_MSGS.load(deps.storage)?)
+        }
+        Box::new(ContractWrapper::new(execute, instantiate, query))
     }
 
     #[test]
-    fn test_instantiate_with_invalid_deposit() {
+    fn proposal_hooks_told_of_vetoes() {
         let mut app = App::default();
 
+        let members = vec![member(VOTER1, 1), member(VOTER4, 12)];
+        let group_addr = instantiate_group(&mut app, members);
         let flex_id = app.store_code(contract_flex());
-
-        let group_addr = instantiate_group(
-            &mut app,
-            vec![Member {
-                addr: OWNER.to_string(),
-                weight: 10,
-            }],
-        );
-
-        // Instantiate with an invalid cw20 token.
         let instantiate = InstantiateMsg {
             group_addr: group_addr.to_string(),
             threshold: Threshold::AbsoluteCount { weight: 10 },
-            max_voting_period: Duration::Time(10),
+            max_voting_period: Duration::Height(10),
             executor: None,
-            proposal_deposit: Some(UncheckedDepositInfo {
-                amount: Uint128::new(1),
-                refund_failed_proposals: true,
-                denom: UncheckedDenom::Cw20(group_addr.to_string()),
+            proposal_deposit: None,
+            veto: Some(crate::msg::UncheckedVetoConfig {
+                vetoer: SOMEBODY.to_string(),
+                timelock_duration: Duration::Height(5),
+                burn_deposit: false,
             }),
+            execution_delay: None,
+            close_reward: None,
+            threshold_profiles: vec![],
+            allow_revoting: false,
+            proposers: None,
+            extra_groups: vec![],
+            emergency: None,
+            quadratic_voting: false,
+            timelock: None,
+            private_voting: None,
         };
-
-        let err: ContractError = app
+        let flex_addr = app
             .instantiate_contract(
                 flex_id,
                 Addr::unchecked(OWNER),
                 &instantiate,
                 &[],
-                "Bad cw20",
+                "flex",
+                None,
+            )
+            .unwrap();
+        let recorder_id = app.store_code(contract_hook_recorder());
+        let recorder_addr = app
+            .instantiate_contract(
+                recorder_id,
+                Addr::unchecked(OWNER),
+                &Empty {},
+                &[],
+                "recorder",
                 None,
             )
-            .unwrap_err()
-            .downcast()
             .unwrap();
 
-        assert_eq!(err, ContractError::Deposit(DepositError::InvalidCw20 {}));
+        // Only the multisig itself registers hooks, once
+        let add_hook = ExecuteMsg::AddProposalHook {
+            addr: recorder_addr.to_string(),
+        };
+        let err = app
+            .execute_contract(Addr::unchecked(VOTER4), flex_addr.clone(), &add_hook, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+        app.execute_contract(flex_addr.clone(), flex_addr.clone(), &add_hook, &[])
+            .unwrap();
+        let err = app
+            .execute_contract(flex_addr.clone(), flex_addr.clone(), &add_hook, &[])
+            .unwrap_err();
+        assert_eq!(
+            ContractError::Hook(cw_controllers::HookError::HookAlreadyRegistered {}),
+            err.downcast().unwrap()
+        );
 
-        // Instantiate with a zero amount.
+        // Passing right away, then vetoed
+        app.execute_contract(
+            Addr::unchecked(VOTER4),
+            flex_addr.clone(),
+            &text_proposal(),
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            Addr::unchecked(SOMEBODY),
+            flex_addr.clone(),
+            &ExecuteMsg::Veto { proposal_id: 1 },
+            &[],
+        )
+        .unwrap();
+        let recorded: Vec<ProposalHookMsg> = app
+            .wrap()
+            .query_wasm_smart(&recorder_addr, &Empty {})
+            .unwrap();
+        assert_eq!(
+            recorded,
+            vec![
+                ProposalHookMsg::Created {
+                    proposal_id: 1,
+                    proposer: VOTER4.to_string(),
+                },
+                ProposalHookMsg::Passed { proposal_id: 1 },
+                ProposalHookMsg::Vetoed { proposal_id: 1 },
+            ]
+        );
+
+        // Unregistered hooks are no longer told
+        let remove_hook = ExecuteMsg::RemoveProposalHook {
+            addr: recorder_addr.to_string(),
+        };
+        app.execute_contract(flex_addr.clone(), flex_addr.clone(), &remove_hook, &[])
+            .unwrap();
+        let hooks: cw_controllers::HooksResponse = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &QueryMsg::ProposalHooks {})
+            .unwrap();
+        assert!(hooks.hooks.is_empty());
+        app.execute_contract(Addr::unchecked(VOTER4), flex_addr, &text_proposal(), &[])
+            .unwrap();
+        let recorded: Vec<ProposalHookMsg> = app
+            .wrap()
+            .query_wasm_smart(&recorder_addr, &Empty {})
+            .unwrap();
+        assert_eq!(recorded.len(), 3);
+    }
+
+    #[cosmwasm_schema::cw_serde]
+    enum MockTimelockMsg {
+        Schedule {
+            proposal_id: u64,
+            msgs: Vec<CosmosMsg>,
+        },
+        /// Anyone runs the messages of a queued proposal once its eta is reached
+        Execute { proposal_id: u64 },
+        /// Only sent by the timelock to itself, so that the messages succeed or fail together
+        Run { proposal_id: u64 },
+    }
+
+    // the multisig, messages and eta of each queued proposal
+    const QUEUED: cw_storage_plus::Map<u64, (Addr, Vec<CosmosMsg>, Expiration)> =
+        cw_storage_plus::Map::new("queued");
+
+    // queues proposals to run 5 blocks later, reporting the execution to the multisig
+    fn contract_timelock() -> Box<dyn Contract<Empty>> {
+        fn instantiate(_: DepsMut, _: Env, _: MessageInfo, _: Empty) -> StdResult<Response> {
+            Ok(Response::new())
+        }
+        fn execute(
+            deps: DepsMut,
+            env: Env,
+            info: MessageInfo,
+            msg: MockTimelockMsg,
+        ) -> StdResult<Response> {
+            match msg {
+                MockTimelockMsg::Schedule { proposal_id, msgs } => {
+                    let eta = Expiration::AtHeight(env.block.height + 5);
+                    QUEUED.save(deps.storage, proposal_id, &(info.sender, msgs, eta))?;
+                    Ok(Response::new().set_data(to_binary(&ScheduleResponse { eta })?))
+                }
+                MockTimelockMsg::Execute { proposal_id } => {
+                    let (_, _, eta) = QUEUED.load(deps.storage, proposal_id)?;
+                    if !eta.is_expired(&env.block) {
+                        return Err(StdError::generic_err("not ready"));
+                    }
+                    let run = WasmMsg::Execute {
+                        contract_addr: env.contract.address.to_string(),
+                        msg: to_binary(&MockTimelockMsg::Run { proposal_id })?,
+                        funds: vec![],
+                    };
+                    Ok(Response::new().add_submessage(SubMsg::reply_always(run, proposal_id)))
+                }
+                MockTimelockMsg::Run { proposal_id } => {
+                    if info.sender != env.contract.address {
+                        return Err(StdError::generic_err("unauthorized"));
+                    }
+                    let (_, msgs, _) = QUEUED.load(deps.storage, proposal_id)?;
+                    Ok(Response::new().add_messages(msgs))
+                }
+            }
+        }
+        fn reply(deps: DepsMut, _: Env, msg: Reply) -> StdResult<Response> {
+            let proposal_id = msg.id;
+            let (multisig, _, _) = QUEUED.load(deps.storage, proposal_id)?;
+            // failed proposals are queued again by the multisig
+            let error = match msg.result {
+                SubMsgResult::Ok(_) => {
+                    QUEUED.remove(deps.storage, proposal_id);
+                    None
+                }
+                SubMsgResult::Err(error) => Some(error),
+            };
+            let executed = WasmMsg::Execute {
+                contract_addr: multisig.to_string(),
+                msg: to_binary(&ExecuteMsg::TimelockExecuted { proposal_id, error })?,
+                funds: vec![],
+            };
+            Ok(Response::new().add_message(executed))
+        }
+        fn query(_: Deps, _: Env, _: Empty) -> StdResult<Binary> {
+            to_binary(&Empty {})
+        }
+        Box::new(ContractWrapper::new(execute, instantiate, query).with_reply(reply))
+    }
+
+    #[test]
+    fn passed_proposals_queued_in_timelock() {
+        let mut app = App::default();
+
+        let members = vec![member(VOTER1, 1), member(VOTER4, 12)];
+        let group_addr = instantiate_group(&mut app, members);
+        let timelock_id = app.store_code(contract_timelock());
+        let timelock_addr = app
+            .instantiate_contract(
+                timelock_id,
+                Addr::unchecked(OWNER),
+                &Empty {},
+                &[],
+                "timelock",
+                None,
+            )
+            .unwrap();
+        let flex_id = app.store_code(contract_flex());
         let instantiate = InstantiateMsg {
             group_addr: group_addr.to_string(),
             threshold: Threshold::AbsoluteCount { weight: 10 },
-            max_voting_period: Duration::Time(10),
+            max_voting_period: Duration::Height(10),
             executor: None,
-            proposal_deposit: Some(UncheckedDepositInfo {
-                amount: Uint128::zero(),
-                refund_failed_proposals: true,
-                denom: UncheckedDenom::Native("native".to_string()),
-            }),
+            proposal_deposit: None,
+            veto: None,
+            execution_delay: None,
+            close_reward: None,
+            threshold_profiles: vec![],
+            allow_revoting: false,
+            proposers: None,
+            extra_groups: vec![],
+            emergency: None,
+            quadratic_voting: false,
+            timelock: Some(timelock_addr.to_string()),
+            private_voting: None,
         };
-
-        let err: ContractError = app
+        let flex_addr = app
             .instantiate_contract(
                 flex_id,
                 Addr::unchecked(OWNER),
                 &instantiate,
                 &[],
-                "Bad cw20",
+                "flex",
                 None,
             )
-            .unwrap_err()
-            .downcast()
             .unwrap();
+        let proposal = |app: &App| {
+            let prop: ProposalResponse = app
+                .wrap()
+                .query_wasm_smart(&flex_addr, &QueryMsg::Proposal { proposal_id: 1 })
+                .unwrap();
+            prop
+        };
+        let executable_at = |app: &App| {
+            let res: ExecutableAtResponse = app
+                .wrap()
+                .query_wasm_smart(&flex_addr, &QueryMsg::ExecutableAt { proposal_id: 1 })
+                .unwrap();
+            res.executable_at
+        };
+        let execute = |app: &mut App| {
+            app.execute_contract(
+                Addr::unchecked(VOTER1),
+                flex_addr.clone(),
+                &ExecuteMsg::Execute {
+                    proposal_id: 1,
+                    msg_indices: None,
+                },
+                &[],
+            )
+            .unwrap()
+        };
+        let run_queued = |app: &mut App| {
+            app.execute_contract(
+                Addr::unchecked(SOMEBODY),
+                timelock_addr.clone(),
+                &MockTimelockMsg::Execute { proposal_id: 1 },
+                &[],
+            )
+        };
+
+        // Passed proposals are queued in the timelock, rather than executed
+        let (msgs, title, description) = proposal_info();
+        let propose = ExecuteMsg::Propose {
+            title,
+            description,
+            msgs,
+            latest: None,
+            threshold_profile: None,
+            execution_window: None,
+            metadata: None,
+        };
+        app.execute_contract(Addr::unchecked(VOTER4), flex_addr.clone(), &propose, &[])
+            .unwrap();
+        let res = execute(&mut app);
+        assert!(res.has_event(&Event::new("wasm").add_attribute("action", "queue_proposal")));
+        assert_eq!(proposal(&app).status, Status::Queued);
+        let eta = Expiration::AtHeight(app.block_info().height + 5);
+        assert_eq!(executable_at(&app), Some(eta));
+        let err = app
+            .execute_contract(
+                Addr::unchecked(VOTER1),
+                flex_addr.clone(),
+                &ExecuteMsg::Execute {
+                    proposal_id: 1,
+                    msg_indices: None,
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            ContractError::WrongExecuteStatus {},
+            err.downcast().unwrap()
+        );
 
-        assert_eq!(err, ContractError::Deposit(DepositError::ZeroDeposit {}))
+        // Only the timelock reports how the execution went
+        let err = app
+            .execute_contract(
+                Addr::unchecked(VOTER4),
+                flex_addr.clone(),
+                &ExecuteMsg::TimelockExecuted {
+                    proposal_id: 1,
+                    error: None,
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+        // The timelock runs the messages once the eta is reached, failing without funds
+        run_queued(&mut app).unwrap_err();
+        app.update_block(|block| block.height += 5);
+        run_queued(&mut app).unwrap();
+        let prop = proposal(&app);
+        assert_eq!(prop.status, Status::ExecutionFailed);
+        assert!(prop.execution_error.is_some());
+
+        // Failed proposals are queued again, until executed
+        app.sudo(SudoMsg::Bank(BankSudo::Mint {
+            to_address: timelock_addr.to_string(),
+            amount: coins(1, "BTC"),
+        }))
+        .unwrap();
+        execute(&mut app);
+        assert_eq!(proposal(&app).status, Status::Queued);
+        app.update_block(|block| block.height += 5);
+        run_queued(&mut app).unwrap();
+        let prop = proposal(&app);
+        assert_eq!(prop.status, Status::Executed);
+        assert_eq!(prop.execution_error, None);
+        assert_eq!(executable_at(&app), None);
+        let balance = app.wrap().query_balance(SOMEBODY, "BTC").unwrap();
+        assert_eq!(balance.amount, Uint128::new(1));
     }
 
     #[test]
-    fn test_cw20_proposal_deposit() {
+    fn vetoed_proposals_pruned() {
         let mut app = App::default();
 
-        let cw20_id = app.store_code(contract_cw20());
-
-        let cw20_addr = app
-            .instantiate_contract(
-                cw20_id,
-                Addr::unchecked(OWNER),
-                &cw20_base::msg::InstantiateMsg {
-                    name: "Token".to_string(),
-                    symbol: "TOKEN".to_string(),
-                    decimals: 6,
-                    initial_balances: vec![
-                        Cw20Coin {
-                            address: VOTER4.to_string(),
-                            amount: Uint128::new(10),
-                        },
-                        Cw20Coin {
-                            address: OWNER.to_string(),
-                            amount: Uint128::new(10),
-                        },
-                    ],
-                    mint: None,
-                    marketing: None,
-                },
+        let members = vec![member(VOTER1, 1), member(VOTER4, 12)];
+        let group_addr = instantiate_group(&mut app, members);
+        let flex_id = app.store_code(contract_flex());
+        let instantiate = InstantiateMsg {
+            group_addr: group_addr.to_string(),
+            threshold: Threshold::AbsoluteCount { weight: 10 },
+            max_voting_period: Duration::Height(10),
+            executor: None,
+            proposal_deposit: None,
+            veto: Some(crate::msg::UncheckedVetoConfig {
+                vetoer: SOMEBODY.to_string(),
+                timelock_duration: Duration::Height(5),
+                burn_deposit: false,
+            }),
+            execution_delay: None,
+            close_reward: None,
+            threshold_profiles: vec![],
+            allow_revoting: false,
+            proposers: None,
+            extra_groups: vec![],
+            emergency: None,
+            quadratic_voting: false,
+            timelock: None,
+            private_voting: None,
+        };
+        let flex_addr = app
+            .instantiate_contract(
+                flex_id,
+                Addr::unchecked(OWNER),
+                &instantiate,
                 &[],
-                "Token",
+                "flex",
                 None,
             )
             .unwrap();
-
-        let (flex_addr, _) = setup_test_case(
-            &mut app,
-            Threshold::AbsoluteCount { weight: 10 },
-            Duration::Height(10),
-            vec![],
-            true,
-            None,
-            Some(UncheckedDepositInfo {
-                amount: Uint128::new(10),
-                denom: UncheckedDenom::Cw20(cw20_addr.to_string()),
-                refund_failed_proposals: true,
-            }),
-        );
-
+        for _ in 0..2 {
+            app.execute_contract(
+                Addr::unchecked(VOTER4),
+                flex_addr.clone(),
+                &text_proposal(),
+                &[],
+            )
+            .unwrap();
+        }
         app.execute_contract(
-            Addr::unchecked(VOTER4),
-            cw20_addr.clone(),
-            &cw20::Cw20ExecuteMsg::IncreaseAllowance {
-                spender: flex_addr.to_string(),
-                amount: Uint128::new(10),
-                expires: None,
-            },
+            Addr::unchecked(SOMEBODY),
+            flex_addr.clone(),
+            &ExecuteMsg::Veto { proposal_id: 1 },
             &[],
         )
         .unwrap();
 
-        // Make a proposal that will pass.
-        let proposal = text_proposal();
-        app.execute_contract(Addr::unchecked(VOTER4), flex_addr.clone(), &proposal, &[])
-            .unwrap();
+        let prune = |app: &mut App| {
+            app.execute_contract(
+                Addr::unchecked(SOMEBODY),
+                flex_addr.clone(),
+                &ExecuteMsg::PruneProposals {
+                    older_than: Duration::Height(5),
+                    start_after: None,
+                    limit: None,
+                },
+                &[],
+            )
+        };
+        app.update_block(|block| block.height += 12);
+        let err = prune(&mut app).unwrap_err();
+        assert_eq!(ContractError::NothingToPrune {}, err.downcast().unwrap());
 
-        // Make sure the deposit was transfered.
-        let balance: cw20::BalanceResponse = app
+        // Only the vetoed one, as the other one can still be executed
+        app.update_block(|block| block.height += 3);
+        prune(&mut app).unwrap();
+        let proposals: ProposalListResponse = app
             .wrap()
             .query_wasm_smart(
-                cw20_addr.clone(),
-                &cw20::Cw20QueryMsg::Balance {
-                    address: VOTER4.to_string(),
+                &flex_addr,
+                &QueryMsg::ListProposals {
+                    start_after: None,
+                    limit: None,
                 },
             )
             .unwrap();
-        assert_eq!(balance.balance, Uint128::zero());
-
-        let balance: cw20::BalanceResponse = app
+        let ids: Vec<_> = proposals.proposals.iter().map(|prop| prop.id).collect();
+        assert_eq!(ids, vec![2]);
+        let votes: VoteListResponse = app
             .wrap()
             .query_wasm_smart(
-                cw20_addr.clone(),
-                &cw20::Cw20QueryMsg::Balance {
-                    address: flex_addr.to_string(),
+                &flex_addr,
+                &QueryMsg::ListVotes {
+                    proposal_id: 1,
+                    start_after: None,
+                    limit: None,
                 },
             )
             .unwrap();
-        assert_eq!(balance.balance, Uint128::new(10));
+        assert!(votes.votes.is_empty());
+    }
 
-        app.execute_contract(
-            Addr::unchecked(VOTER4),
-            flex_addr.clone(),
-            &ExecuteMsg::Execute { proposal_id: 1 },
-            &[],
+    fn contract_fixed() -> Box<dyn Contract<Empty>> {
+        let contract = ContractWrapper::new(
+            cw3_fixed_multisig::contract::execute,
+            cw3_fixed_multisig::contract::instantiate,
+            cw3_fixed_multisig::contract::query,
         )
-        .unwrap();
+        .with_reply(cw3_fixed_multisig::contract::reply);
+        Box::new(contract)
+    }
 
-        // Make sure the deposit was returned.
-        let balance: cw20::BalanceResponse = app
-            .wrap()
-            .query_wasm_smart(
-                cw20_addr.clone(),
-                &cw20::Cw20QueryMsg::Balance {
-                    address: VOTER4.to_string(),
-                },
+    #[test]
+    fn fixed_multisig_migrated_in_place() {
+        let mut app = mock_app(&coins(10, "BTC"));
+        for voter in [VOTER1, VOTER3] {
+            app.sudo(SudoMsg::Bank(BankSudo::Mint {
+                to_address: voter.to_string(),
+                amount: coins(20, "TOKEN"),
+            }))
+            .unwrap();
+        }
+        let fixed_id = app.store_code(contract_fixed());
+        let flex_id = app.store_code(contract_flex());
+        let voter = |addr: &str, weight| cw3_fixed_multisig::msg::Voter {
+            addr: addr.to_string(),
+            weight,
+        };
+        let msg = cw3_fixed_multisig::msg::InstantiateMsg {
+            voters: vec![voter(VOTER1, 1), voter(VOTER2, 2), voter(VOTER3, 3)],
+            threshold: Threshold::AbsoluteCount { weight: 3 },
+            max_voting_period: Duration::Height(10),
+            proposal_deposit: Some(UncheckedDepositInfo {
+                amount: Uint128::new(10),
+                denom: UncheckedDenom::Native("TOKEN".to_string()),
+                refund_failed_proposals: false,
+                refund_passed_proposals: true,
+                forfeit: None,
+            }),
+            close_reward: None,
+            allow_revoting: false,
+            cast_threshold: None,
+            emergency: None,
+            private_voting: None,
+        };
+        let multisig = app
+            .instantiate_contract(
+                fixed_id,
+                Addr::unchecked(OWNER),
+                &msg,
+                &[],
+                "fixed",
+                Some(OWNER.to_string()),
             )
             .unwrap();
-        assert_eq!(balance.balance, Uint128::new(10));
+        app.send_tokens(Addr::unchecked(OWNER), multisig.clone(), &coins(10, "BTC"))
+            .unwrap();
 
-        let balance: cw20::BalanceResponse = app
-            .wrap()
-            .query_wasm_smart(
-                cw20_addr.clone(),
-                &cw20::Cw20QueryMsg::Balance {
-                    address: flex_addr.to_string(),
-                },
+        // One proposal passed, the other is still open
+        let (msgs, title, description) = proposal_info();
+        let proposal = cw3_fixed_multisig::msg::ExecuteMsg::Propose {
+            title,
+            description,
+            msgs,
+            latest: None,
+            execution_window: None,
+            metadata: None,
+        };
+        for _ in 0..2 {
+            app.execute_contract(
+                Addr::unchecked(VOTER1),
+                multisig.clone(),
+                &proposal,
+                &coins(10, "TOKEN"),
             )
             .unwrap();
-        assert_eq!(balance.balance, Uint128::zero());
-
-        app.execute_contract(
-            Addr::unchecked(OWNER),
-            cw20_addr.clone(),
-            &cw20::Cw20ExecuteMsg::IncreaseAllowance {
-                spender: flex_addr.to_string(),
-                amount: Uint128::new(10),
-                expires: None,
-            },
-            &[],
-        )
-        .unwrap();
-
-        // Make a proposal that fails.
-        let proposal = text_proposal();
-        app.execute_contract(Addr::unchecked(OWNER), flex_addr.clone(), &proposal, &[])
+        }
+        let vote = cw3_fixed_multisig::msg::ExecuteMsg::Vote {
+            proposal_id: 1,
+            vote: Vote::Yes,
+        };
+        app.execute_contract(Addr::unchecked(VOTER2), multisig.clone(), &vote, &[])
             .unwrap();
 
-        // Check that the deposit was transfered.
-        let balance: cw20::BalanceResponse = app
+        // The voters are exported to the group replacing them
+        let export: cw3_fixed_multisig::msg::ExportResponse = app
             .wrap()
-            .query_wasm_smart(
-                cw20_addr.clone(),
-                &cw20::Cw20QueryMsg::Balance {
-                    address: flex_addr.to_string(),
-                },
-            )
+            .query_wasm_smart(&multisig, &cw3_fixed_multisig::msg::QueryMsg::Export {})
             .unwrap();
-        assert_eq!(balance.balance, Uint128::new(10));
+        assert_eq!(export.threshold, Threshold::AbsoluteCount { weight: 3 });
+        let members = export
+            .voters
+            .into_iter()
+            .map(|voter| member(voter.addr, voter.weight))
+            .collect();
+        let group_addr = instantiate_group(&mut app, members);
 
-        // Fail the proposal.
-        app.execute_contract(
-            Addr::unchecked(VOTER4),
-            flex_addr.clone(),
-            &ExecuteMsg::Vote {
-                proposal_id: 2,
-                vote: Vote::No,
-            },
-            &[],
+        let err = app
+            .migrate_contract(
+                Addr::unchecked(OWNER),
+                multisig.clone(),
+                &MigrateMsg { group_addr: None },
+                flex_id,
+            )
+            .unwrap_err();
+        assert_eq!(ContractError::GroupRequired {}, err.downcast().unwrap());
+        let migration = MigrateMsg {
+            group_addr: Some(group_addr.to_string()),
+        };
+        app.migrate_contract(
+            Addr::unchecked(OWNER),
+            multisig.clone(),
+            &migration,
+            flex_id,
         )
         .unwrap();
+        let version = query_contract_info(&app.wrap(), multisig.clone()).unwrap();
+        assert_eq!(version.contract, CONTRACT_NAME);
 
-        // Expire the proposal.
-        app.update_block(|b| b.height += 10);
+        // The open proposal was closed, refunding its deposit
+        let prop: ProposalResponse = app
+            .wrap()
+            .query_wasm_smart(&multisig, &QueryMsg::Proposal { proposal_id: 2 })
+            .unwrap();
+        assert_eq!(prop.status, Status::Rejected);
+        assert_eq!(prop.deposit_status, Some(DepositStatus::Refunded));
+        let balance = app.wrap().query_balance(VOTER1, "TOKEN").unwrap();
+        assert_eq!(balance.amount, Uint128::new(10));
 
+        // while the passed one can still be executed
         app.execute_contract(
-            Addr::unchecked(VOTER4),
-            flex_addr,
-            &ExecuteMsg::Close { proposal_id: 2 },
+            Addr::unchecked(SOMEBODY),
+            multisig.clone(),
+            &ExecuteMsg::Execute {
+                proposal_id: 1,
+                msg_indices: None,
+            },
             &[],
         )
         .unwrap();
+        let balance = app.wrap().query_balance(SOMEBODY, "BTC").unwrap();
+        assert_eq!(balance.amount, Uint128::new(1));
 
-        // Make sure the deposit was returned despite the proposal failing.
-        let balance: cw20::BalanceResponse = app
-            .wrap()
-            .query_wasm_smart(
-                cw20_addr,
-                &cw20::Cw20QueryMsg::Balance {
-                    address: VOTER4.to_string(),
-                },
+        // and members of the group propose from now on
+        let res = app
+            .execute_contract(
+                Addr::unchecked(VOTER3),
+                multisig.clone(),
+                &pay_somebody_proposal(),
+                &coins(10, "TOKEN"),
             )
             .unwrap();
-        assert_eq!(balance.balance, Uint128::new(10));
+        assert_eq!(res.custom_attrs(1)[2], attr("proposal_id", "3"));
+        let prop: ProposalResponse = app
+            .wrap()
+            .query_wasm_smart(&multisig, &QueryMsg::Proposal { proposal_id: 3 })
+            .unwrap();
+        assert_eq!(prop.status, Status::Passed);
     }
 
     #[test]
-    fn proposal_deposit_no_failed_refunds() {
-        let mut app = App::default();
-
-        let (flex_addr, _) = setup_test_case(
-            &mut app,
-            Threshold::AbsoluteCount { weight: 10 },
-            Duration::Height(10),
-            vec![],
-            true,
-            None,
-            Some(UncheckedDepositInfo {
-                amount: Uint128::new(10),
-                denom: UncheckedDenom::Native("TOKEN".to_string()),
-                refund_failed_proposals: false,
-            }),
-        );
-
-        app.sudo(SudoMsg::Bank(BankSudo::Mint {
-            to_address: OWNER.to_string(),
-            amount: vec![Coin {
-                amount: Uint128::new(10),
-                denom: "TOKEN".to_string(),
-            }],
-        }))
-        .unwrap();
-
-        // Make a proposal that fails.
-        let proposal = text_proposal();
-        app.execute_contract(
-            Addr::unchecked(OWNER),
-            flex_addr.clone(),
-            &proposal,
-            &[Coin {
-                amount: Uint128::new(10),
-                denom: "TOKEN".to_string(),
-            }],
-        )
-        .unwrap();
+    fn failed_execution_cleaned_up() {
+        let init_funds = coins(10, "BTC");
+        let mut app = mock_app(&init_funds);
+        let voting_period = Duration::Time(2000000);
+        let (flex_addr, _) = setup_test_case_fixed(&mut app, 20, voting_period, init_funds, false);
+
+        // A choice proposal only passes on expiration, while still indexed as open
+        let proposal = ExecuteMsg::ProposeChoice {
+            title: "Grant".to_string(),
+            description: "More than we have".to_string(),
+            options: vec![
+                ProposalOption {
+                    label: "Nothing".to_string(),
+                    msgs: vec![],
+                },
+                ProposalOption {
+                    label: "Lots".to_string(),
+                    msgs: vec![BankMsg::Send {
+                        to_address: SOMEBODY.into(),
+                        amount: coins(100, "BTC"),
+                    }
+                    .into()],
+                },
+            ],
+            win_condition: WinCondition::Plurality {
+                quorum: Decimal::percent(20),
+            },
+            latest: None,
+            metadata: None,
+        };
+        app.execute_contract(Addr::unchecked(VOTER1), flex_addr.clone(), &proposal, &[])
+            .unwrap();
+        for voter in [VOTER3, VOTER5] {
+            let vote = ExecuteMsg::VoteChoice {
+                proposal_id: 1,
+                option: 1,
+            };
+            app.execute_contract(Addr::unchecked(voter), flex_addr.clone(), &vote, &[])
+                .unwrap();
+        }
+        app.update_block(expire(voting_period));
+        let is_open = |app: &App| {
+            app.wrap()
+                .query_wasm_raw(&flex_addr, OPEN_PROPOSALS.key(1).to_vec())
+                .unwrap()
+                .is_some()
+        };
+        assert!(is_open(&app));
 
-        // Check that the deposit was transfered.
-        let balance = app
+        // Its failing message sets it to failed, no longer open
+        let execution = ExecuteMsg::Execute {
+            proposal_id: 1,
+            msg_indices: None,
+        };
+        let res = app
+            .execute_contract(
+                Addr::unchecked(SOMEBODY),
+                flex_addr.clone(),
+                &execution,
+                &[],
+            )
+            .unwrap();
+        assert!(res.has_event(
+            &Event::new("wasm")
+                .add_attribute("action", "execution_failed")
+                .add_attribute("status", "ExecutionFailed")
+        ));
+        let prop: ProposalResponse = app
             .wrap()
-            .query_balance(OWNER, "TOKEN".to_string())
+            .query_wasm_smart(&flex_addr, &QueryMsg::Proposal { proposal_id: 1 })
             .unwrap();
-        assert_eq!(balance.amount, Uint128::zero());
+        assert_eq!(prop.status, Status::ExecutionFailed);
+        assert!(prop.execution_error.is_some());
+        assert!(!is_open(&app));
 
-        // Fail the proposal.
+        // so that it can be closed
         app.execute_contract(
-            Addr::unchecked(VOTER4),
+            Addr::unchecked(SOMEBODY),
             flex_addr.clone(),
-            &ExecuteMsg::Vote {
-                proposal_id: 1,
-                vote: Vote::No,
-            },
-            &[],
-        )
-        .unwrap();
-
-        // Expire the proposal.
-        app.update_block(|b| b.height += 10);
-
-        app.execute_contract(
-            Addr::unchecked(VOTER4),
-            flex_addr,
             &ExecuteMsg::Close { proposal_id: 1 },
             &[],
         )
         .unwrap();
-
-        // Check that the deposit wasn't returned.
-        let balance = app
+        let prop: ProposalResponse = app
             .wrap()
-            .query_balance(OWNER, "TOKEN".to_string())
+            .query_wasm_smart(&flex_addr, &QueryMsg::Proposal { proposal_id: 1 })
             .unwrap();
-        assert_eq!(balance.amount, Uint128::zero());
+        assert_eq!(prop.status, Status::Rejected);
+        let err = app
+            .execute_contract(Addr::unchecked(SOMEBODY), flex_addr, &execution, &[])
+            .unwrap_err();
+        assert_eq!(
+            ContractError::WrongExecuteStatus {},
+            err.downcast().unwrap()
+        );
     }
 
     #[test]
-    fn test_native_proposal_deposit() {
-        let mut app = App::default();
+    fn failing_message_skipped_in_ranges() {
+        let init_funds = coins(10, "BTC");
+        let mut app = mock_app(&init_funds);
+        let voting_period = Duration::Time(2000000);
+        let (flex_addr, _) = setup_test_case_fixed(&mut app, 4, voting_period, init_funds, false);
 
-        app.sudo(SudoMsg::Bank(BankSudo::Mint {
-            to_address: VOTER4.to_string(),
-            amount: vec![Coin {
-                amount: Uint128::new(10),
-                denom: "TOKEN".to_string(),
-            }],
-        }))
-        .unwrap();
+        // Passes right away, by the proposer vote, though its second message cannot succeed
+        let pay = |amount| -> CosmosMsg {
+            BankMsg::Send {
+                to_address: SOMEBODY.into(),
+                amount: coins(amount, "BTC"),
+            }
+            .into()
+        };
+        let proposal = ExecuteMsg::Propose {
+            title: "Pay".to_string(),
+            description: "Pay somebody in three parts".to_string(),
+            msgs: vec![pay(1), pay(100), pay(2)],
+            latest: None,
+            threshold_profile: None,
+            execution_window: None,
+            metadata: None,
+        };
+        app.execute_contract(Addr::unchecked(VOTER4), flex_addr.clone(), &proposal, &[])
+            .unwrap();
+        let execute_range = |app: &mut App, msg_indices: Option<Vec<u32>>| {
+            let execution = ExecuteMsg::Execute {
+                proposal_id: 1,
+                msg_indices,
+            };
+            app.execute_contract(
+                Addr::unchecked(SOMEBODY),
+                flex_addr.clone(),
+                &execution,
+                &[],
+            )
+            .unwrap();
+            app.wrap()
+                .query_wasm_smart::<ProposalResponse>(
+                    &flex_addr,
+                    &QueryMsg::Proposal { proposal_id: 1 },
+                )
+                .unwrap()
+        };
+
+        // Executing them all fails, but they can be executed around the failing one
+        let prop = execute_range(&mut app, None);
+        assert_eq!(prop.status, Status::ExecutionFailed);
+        assert_eq!(prop.executed_msgs, Vec::<u32>::new());
+        let prop = execute_range(&mut app, Some(vec![0]));
+        assert_eq!(prop.status, Status::Passed);
+        assert_eq!(prop.executed_msgs, vec![0]);
+        let prop = execute_range(&mut app, Some(vec![2]));
+        assert_eq!(prop.status, Status::Passed);
+        assert_eq!(prop.executed_msgs, vec![0, 2]);
+        let balance = app.wrap().query_balance(SOMEBODY, "BTC").unwrap();
+        assert_eq!(balance.amount, Uint128::new(3));
+
+        // Executed messages are not executed again
+        let execution = ExecuteMsg::Execute {
+            proposal_id: 1,
+            msg_indices: Some(vec![0, 1]),
+        };
+        let err = app
+            .execute_contract(
+                Addr::unchecked(SOMEBODY),
+                flex_addr.clone(),
+                &execution,
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(ContractError::InvalidMsgIndices {}, err.downcast().unwrap());
+        let prop = execute_range(&mut app, None);
+        assert_eq!(prop.status, Status::ExecutionFailed);
+        assert_eq!(prop.executed_msgs, vec![0, 2]);
+    }
 
+    #[test]
+    fn proposals_cancelled_by_proposer_or_vetoer() {
+        let mut app = App::default();
         app.sudo(SudoMsg::Bank(BankSudo::Mint {
-            to_address: OWNER.to_string(),
-            amount: vec![Coin {
-                amount: Uint128::new(10),
-                denom: "TOKEN".to_string(),
-            }],
+            to_address: VOTER1.to_string(),
+            amount: coins(40, "TOKEN"),
         }))
         .unwrap();
 
-        let (flex_addr, _) = setup_test_case(
-            &mut app,
-            Threshold::AbsoluteCount { weight: 10 },
-            Duration::Height(10),
-            vec![],
-            true,
-            None,
-            Some(UncheckedDepositInfo {
+        let members = vec![member(VOTER1, 1), member(VOTER2, 1), member(VOTER4, 12)];
+        let group_addr = instantiate_group(&mut app, members);
+        app.update_block(next_block);
+        let flex_id = app.store_code(contract_flex());
+        let instantiate = InstantiateMsg {
+            group_addr: group_addr.to_string(),
+            threshold: Threshold::AbsoluteCount { weight: 10 },
+            max_voting_period: Duration::Height(10),
+            executor: None,
+            proposal_deposit: Some(UncheckedDepositInfo {
                 amount: Uint128::new(10),
                 denom: UncheckedDenom::Native("TOKEN".to_string()),
                 refund_failed_proposals: true,
+                refund_passed_proposals: true,
+                forfeit: None,
             }),
-        );
+            veto: Some(crate::msg::UncheckedVetoConfig {
+                vetoer: SOMEBODY.to_string(),
+                timelock_duration: Duration::Height(5),
+                burn_deposit: true,
+            }),
+            execution_delay: None,
+            close_reward: None,
+            threshold_profiles: vec![],
+            allow_revoting: false,
+            proposers: None,
+            extra_groups: vec![],
+            emergency: None,
+            quadratic_voting: false,
+            timelock: None,
+            private_voting: None,
+        };
+        let flex_addr = app
+            .instantiate_contract(
+                flex_id,
+                Addr::unchecked(OWNER),
+                &instantiate,
+                &[],
+                "flex",
+                None,
+            )
+            .unwrap();
 
-        // Make a proposal that will pass.
-        let proposal = text_proposal();
-        app.execute_contract(
-            Addr::unchecked(VOTER4),
-            flex_addr.clone(),
-            &proposal,
-            &[Coin {
-                amount: Uint128::new(10),
-                denom: "TOKEN".to_string(),
-            }],
-        )
-        .unwrap();
+        let propose = |app: &mut App| {
+            app.execute_contract(
+                Addr::unchecked(VOTER1),
+                flex_addr.clone(),
+                &text_proposal(),
+                &coins(10, "TOKEN"),
+            )
+            .unwrap();
+        };
+        let vote = |app: &mut App, voter: &str, proposal_id, vote| {
+            let vote = ExecuteMsg::Vote { proposal_id, vote };
+            app.execute_contract(Addr::unchecked(voter), flex_addr.clone(), &vote, &[])
+                .unwrap();
+        };
+        let cancel = |app: &mut App, sender: &str, proposal_id| {
+            app.execute_contract(
+                Addr::unchecked(sender),
+                flex_addr.clone(),
+                &ExecuteMsg::Cancel { proposal_id },
+                &[],
+            )
+            .map_err(|err| err.downcast::<ContractError>().unwrap())
+        };
+        let status = |app: &App, proposal_id| {
+            let prop: ProposalResponse = app
+                .wrap()
+                .query_wasm_smart(&flex_addr, &QueryMsg::Proposal { proposal_id })
+                .unwrap();
+            prop.status
+        };
+        let balance = |app: &App| {
+            let balance = app.wrap().query_balance(VOTER1, "TOKEN").unwrap();
+            balance.amount.u128()
+        };
 
-        // Make sure the deposit was transfered.
-        let balance = app
-            .wrap()
-            .query_balance(flex_addr.clone(), "TOKEN")
+        // The proposer withdraws their proposal, refunded as for rejected ones
+        propose(&mut app);
+        let err = cancel(&mut app, VOTER2, 1).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        cancel(&mut app, VOTER1, 1).unwrap();
+        assert_eq!(status(&app, 1), Status::Cancelled);
+        assert_eq!(balance(&app), 40);
+
+        // Until anybody votes against it
+        propose(&mut app);
+        vote(&mut app, VOTER2, 2, Vote::No);
+        let err = cancel(&mut app, VOTER1, 2).unwrap_err();
+        assert_eq!(err, ContractError::OpposedProposal {});
+
+        // While the vetoer can cancel it anyway, burning the deposit
+        cancel(&mut app, SOMEBODY, 2).unwrap();
+        assert_eq!(status(&app, 2), Status::Cancelled);
+        assert_eq!(balance(&app), 30);
+        let contract = app.wrap().query_balance(&flex_addr, "TOKEN").unwrap();
+        assert_eq!(contract.amount, Uint128::zero());
+
+        // And passed ones too, as long as they were not executed
+        propose(&mut app);
+        vote(&mut app, VOTER4, 3, Vote::Yes);
+        assert_eq!(status(&app, 3), Status::VetoTimelock);
+        cancel(&mut app, SOMEBODY, 3).unwrap();
+        assert_eq!(status(&app, 3), Status::Cancelled);
+        let err = cancel(&mut app, SOMEBODY, 3).unwrap_err();
+        assert_eq!(err, ContractError::WrongCancelStatus {});
+
+        propose(&mut app);
+        vote(&mut app, VOTER4, 4, Vote::Yes);
+        app.update_block(|b| b.height += 5);
+        let execution = ExecuteMsg::Execute {
+            proposal_id: 4,
+            msg_indices: None,
+        };
+        app.execute_contract(Addr::unchecked(VOTER1), flex_addr.clone(), &execution, &[])
             .unwrap();
-        assert_eq!(balance.amount, Uint128::new(10));
+        let err = cancel(&mut app, SOMEBODY, 4).unwrap_err();
+        assert_eq!(err, ContractError::WrongCancelStatus {});
+    }
+
+    #[test]
+    fn unrevealed_votes_forfeit_bond() {
+        let mut app = App::default();
+        for voter in [VOTER1, VOTER2, VOTER4] {
+            app.sudo(SudoMsg::Bank(BankSudo::Mint {
+                to_address: voter.to_string(),
+                amount: coins(10, "TOKEN"),
+            }))
+            .unwrap();
+        }
 
+        let members = vec![member(VOTER1, 1), member(VOTER2, 1), member(VOTER4, 12)];
+        let group_addr = instantiate_group(&mut app, members);
+        app.update_block(next_block);
+        let flex_id = app.store_code(contract_flex());
+        let instantiate = InstantiateMsg {
+            group_addr: group_addr.to_string(),
+            threshold: Threshold::AbsoluteCount { weight: 2 },
+            max_voting_period: Duration::Height(10),
+            executor: None,
+            proposal_deposit: None,
+            veto: None,
+            execution_delay: None,
+            close_reward: None,
+            threshold_profiles: vec![],
+            allow_revoting: false,
+            proposers: None,
+            extra_groups: vec![],
+            emergency: None,
+            quadratic_voting: false,
+            timelock: None,
+            private_voting: Some(cw3::PrivateVoting {
+                reveal_period: Duration::Height(5),
+                bond: Some(coin(5, "TOKEN")),
+            }),
+        };
+        let flex_addr = app
+            .instantiate_contract(
+                flex_id,
+                Addr::unchecked(OWNER),
+                &instantiate,
+                &[],
+                "flex",
+                None,
+            )
+            .unwrap();
         app.execute_contract(
-            Addr::unchecked(VOTER4),
+            Addr::unchecked(VOTER1),
             flex_addr.clone(),
-            &ExecuteMsg::Execute { proposal_id: 1 },
+            &text_proposal(),
             &[],
         )
         .unwrap();
 
-        // Make sure the deposit was returned.
-        let balance = app.wrap().query_balance(VOTER4, "TOKEN").unwrap();
-        assert_eq!(balance.amount, Uint128::new(10));
-
-        // Make a proposal that fails.
-        let proposal = text_proposal();
-        app.execute_contract(
-            Addr::unchecked(OWNER),
-            flex_addr.clone(),
-            &proposal,
-            &[Coin {
-                amount: Uint128::new(10),
-                denom: "TOKEN".to_string(),
+        // Votes are committed first, after which the proposal cannot be amended
+        let salt = Binary::from(b"salt");
+        for (voter, vote) in [(VOTER1, Vote::Yes), (VOTER2, Vote::Yes), (VOTER4, Vote::No)] {
+            let commit = ExecuteMsg::CommitVote {
+                proposal_id: 1,
+                commitment: vote_commitment(1, voter, vote, &salt).unwrap(),
+            };
+            app.execute_contract(
+                Addr::unchecked(voter),
+                flex_addr.clone(),
+                &commit,
+                &coins(5, "TOKEN"),
+            )
+            .unwrap();
+        }
+        let amend = ExecuteMsg::Amend {
+            proposal_id: 1,
+            title: None,
+            description: Some("Changed my mind".to_string()),
+            msgs: None,
+        };
+        let err = app
+            .execute_contract(Addr::unchecked(VOTER1), flex_addr.clone(), &amend, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::VotesCast {}, err.downcast().unwrap());
+        let split = ExecuteMsg::VoteWeighted {
+            proposal_id: 1,
+            options: vec![WeightedVoteOption {
+                vote: Vote::Yes,
+                weight: Decimal::one(),
             }],
-        )
-        .unwrap();
+        };
+        let err = app
+            .execute_contract(Addr::unchecked(VOTER2), flex_addr.clone(), &split, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::PrivateProposal {}, err.downcast().unwrap());
 
-        let balance = app
+        // Revealed votes are counted, and their bond refunded
+        app.update_block(|b| b.height += 10);
+        for voter in [VOTER1, VOTER2] {
+            let reveal = ExecuteMsg::RevealVote {
+                proposal_id: 1,
+                vote: Vote::Yes,
+                salt: salt.clone(),
+            };
+            app.execute_contract(Addr::unchecked(voter), flex_addr.clone(), &reveal, &[])
+                .unwrap();
+            let balance = app.wrap().query_balance(voter, "TOKEN").unwrap();
+            assert_eq!(balance.amount, Uint128::new(10));
+        }
+        let prop: ProposalResponse = app
             .wrap()
-            .query_balance(flex_addr.clone(), "TOKEN")
+            .query_wasm_smart(&flex_addr, &QueryMsg::Proposal { proposal_id: 1 })
             .unwrap();
-        assert_eq!(balance.amount, Uint128::new(10));
-
-        // Fail the proposal.
-        app.execute_contract(
-            Addr::unchecked(VOTER4),
-            flex_addr.clone(),
-            &ExecuteMsg::Vote {
-                proposal_id: 2,
-                vote: Vote::No,
-            },
-            &[],
-        )
-        .unwrap();
-
-        // Expire the proposal.
-        app.update_block(|b| b.height += 10);
-
-        app.execute_contract(
-            Addr::unchecked(VOTER4),
-            flex_addr,
-            &ExecuteMsg::Close { proposal_id: 2 },
-            &[],
-        )
-        .unwrap();
+        assert_eq!(prop.status, Status::Passed);
 
-        // Make sure the deposit was returned despite the proposal failing.
-        let balance = app.wrap().query_balance(OWNER, "TOKEN").unwrap();
-        assert_eq!(balance.amount, Uint128::new(10));
+        // While the bond of those not revealed in time is forfeited
+        app.update_block(|b| b.height += 5);
+        let reveal = ExecuteMsg::RevealVote {
+            proposal_id: 1,
+            vote: Vote::No,
+            salt,
+        };
+        let err = app
+            .execute_contract(Addr::unchecked(VOTER4), flex_addr.clone(), &reveal, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::Expired {}, err.downcast().unwrap());
+        let commitment: VoteCommitmentResponse = app
+            .wrap()
+            .query_wasm_smart(
+                &flex_addr,
+                &QueryMsg::VoteCommitment {
+                    proposal_id: 1,
+                    voter: VOTER4.to_string(),
+                },
+            )
+            .unwrap();
+        assert!(commitment.commitment.is_some());
+        let balance = app.wrap().query_balance(&flex_addr, "TOKEN").unwrap();
+        assert_eq!(balance.amount, Uint128::new(5));
     }
 }
@@ -1,6 +1,7 @@
 use cosmwasm_std::StdError;
 use cw3::DepositError;
-use cw_utils::{PaymentError, ThresholdError};
+use cw_controllers::{HookError, PermitError};
+use cw_utils::{ParseReplyError, PaymentError, ThresholdError};
 
 use thiserror::Error;
 
@@ -9,12 +10,21 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("{0}")]
+    Hook(#[from] HookError),
+
+    #[error("{0}")]
+    Permit(#[from] PermitError),
+
     #[error("{0}")]
     Threshold(#[from] ThresholdError),
 
     #[error("Group contract invalid address '{addr}'")]
     InvalidGroup { addr: String },
 
+    #[error("Migrating a cw3-fixed-multisig requires the group replacing its voters")]
+    GroupRequired {},
+
     #[error("Unauthorized")]
     Unauthorized {},
 
@@ -33,15 +43,132 @@ pub enum ContractError {
     #[error("Already voted on this proposal")]
     AlreadyVoted {},
 
+    #[error("Proposal cannot be amended once others voted on it")]
+    VotesCast {},
+
+    #[error("Split vote shares must be non-zero, for distinct options, and add up to one")]
+    InvalidSplitVote {},
+
     #[error("Proposal must have passed and not yet been executed")]
     WrongExecuteStatus {},
 
+    #[error("Signaling proposals cannot be executed")]
+    SignalingProposal {},
+
+    #[error("Choice proposals are voted on and amended by option")]
+    ChoiceProposal {},
+
+    #[error("Only choice proposals are voted on by option")]
+    NotChoiceProposal {},
+
+    #[error("Choice proposals need 2 to 16 options with distinct labels of up to 64 bytes, and a quorum of at most 1")]
+    InvalidOptions {},
+
+    #[error("Proposal has no option {option}")]
+    UnknownOption { option: u32 },
+
+    #[error("Message indices must be ascending, and of messages not executed yet")]
+    InvalidMsgIndices {},
+
+    #[error("Proposals queued in a timelock contract are executed in full")]
+    PartialQueue {},
+
     #[error("Cannot close completed or passed proposals")]
     WrongCloseStatus {},
 
+    #[error("Only proposals not executed nor concluded yet can be cancelled")]
+    WrongCancelStatus {},
+
+    #[error("Proposals voted against cannot be cancelled by their proposer")]
+    OpposedProposal {},
+
+    #[error("No proposal can be closed")]
+    NothingToClose {},
+
+    #[error("No proposal can be pruned")]
+    NothingToPrune {},
+
+    #[error("Threshold profile names must be unique and not empty")]
+    InvalidThresholdProfile {},
+
+    #[error("Unknown threshold profile: {name}")]
+    UnknownThresholdProfile { name: String },
+
+    #[error("Proposal can still be vetoed, until its veto timelock expires")]
+    InVetoTimelock {},
+
+    #[error("Only passed proposals can be vetoed, until their veto timelock expires")]
+    WrongVetoStatus {},
+
+    #[error("Veto timelock duration must have the same unit as the max voting period")]
+    InvalidVetoTimelock {},
+
+    #[error("Proposal cannot be executed until its execution timelock expires")]
+    InExecutionTimelock {},
+
+    #[error("Execution delay must have the same unit as the max voting period")]
+    InvalidExecutionDelay {},
+
+    #[error("Execution window must start before it ends, with the unit of the max voting period, and not be over already")]
+    InvalidExecutionWindow {},
+
+    #[error("Proposal can only be executed within its execution window")]
+    OutsideExecutionWindow {},
+
+    #[error("The proposer allowlist must not be empty")]
+    NoProposers {},
+
+    #[error("Group multipliers must not be 0")]
+    InvalidGroupMultiplier {},
+
+    #[error("The emergency council must not be empty, and its voting period not be longer than the max voting period, with the same unit")]
+    InvalidEmergencyTrack {},
+
+    #[error("Emergency proposals are not enabled")]
+    NoEmergencyTrack {},
+
+    #[error("Proposal metadata must have non-empty fields within their bounds, a link without whitespace, an alphanumeric IPFS hash and distinct tags")]
+    InvalidMetadata {},
+
+    #[error("Only proposals can be created with a cw20 deposit")]
+    NotProposal {},
+
     #[error("{0}")]
     Payment(#[from] PaymentError),
 
+    #[error("{0}")]
+    ParseReply(#[from] ParseReplyError),
+
+    #[error("Proposal is not queued in the timelock")]
+    NotQueued {},
+
+    #[error("Timelock did not tell when it executes the proposal")]
+    NoTimelockEta {},
+
+    #[error("Private voting must have a reveal period with the unit of the max voting period, and a non-zero bond")]
+    InvalidPrivateVoting {},
+
+    #[error("Votes on private proposals are committed, then revealed")]
+    PrivateProposal {},
+
+    #[error("Only votes on private proposals are committed")]
+    NotPrivateProposal {},
+
+    #[error("Commitments to votes on this proposal are closed")]
+    CommitmentsClosed {},
+
+    #[error("Votes are revealed once commitments are closed")]
+    CommitmentsOpen {},
+
+    #[error("No commitment to reveal")]
+    NoCommitment {},
+
+    #[error("Vote and salt do not match the commitment")]
+    InvalidReveal {},
+
+    #[error("Commitments must be sent along with exactly the bond, if any")]
+    WrongBond {},
+
     #[error("{0}")]
     Deposit(#[from] DepositError),
 }
@@ -1,8 +1,12 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{CosmosMsg, Empty};
-use cw3::{UncheckedDepositInfo, Vote};
+use cosmwasm_std::{Binary, CosmosMsg, Empty};
+use cw20::Cw20ReceiveMsg;
+use cw3::{
+    CloseReward, ExecutionWindow, PrivateVoting, ProposalMetadata, ProposalOption,
+    UncheckedDepositInfo, Vote, VotePermit, WeightedVoteOption, WinCondition,
+};
 use cw4::MemberChangedHookMsg;
-use cw_utils::{Duration, Expiration, Threshold};
+use cw_utils::{Duration, Expiration, Threshold, ThresholdResponse};
 
 use crate::state::Executor;
 
@@ -17,6 +21,86 @@ pub struct InstantiateMsg {
     pub executor: Option<Executor>,
     /// The cost of creating a proposal (if any).
     pub proposal_deposit: Option<UncheckedDepositInfo>,
+    /// Who can veto passed proposals, and for how long (if anyone).
+    #[serde(default)]
+    pub veto: Option<UncheckedVetoConfig>,
+    /// The reward for closing a rejected proposal (if any).
+    #[serde(default)]
+    pub close_reward: Option<CloseReward>,
+    /// How long passed proposals wait before they can be executed (if at all).
+    /// Must have the same unit as `max_voting_period`.
+    #[serde(default)]
+    pub execution_delay: Option<Duration>,
+    /// Named thresholds proposers can pick instead of `threshold`, e.g. a stricter one
+    /// for upgrades than for spending.
+    #[serde(default)]
+    pub threshold_profiles: Vec<ThresholdProfile>,
+    /// Whether voters can change their vote while the proposal is open.
+    #[serde(default)]
+    pub allow_revoting: bool,
+    /// Who can create proposals (if not every member of the group), eg. only a council.
+    #[serde(default)]
+    pub proposers: Option<UncheckedProposers>,
+    /// Other groups whose members also vote, eg. a council along with stakers.
+    #[serde(default)]
+    pub extra_groups: Vec<UncheckedWeightedGroup>,
+    /// Lets a council of members propose with a shorter voting period and its own threshold,
+    /// for incident response (if any).
+    #[serde(default)]
+    pub emergency: Option<UncheckedEmergencyTrack>,
+    /// Whether members vote with the square root of their weight, to dampen the largest ones.
+    #[serde(default)]
+    pub quadratic_voting: bool,
+    /// Timelock contract the messages of passed proposals are queued in, rather than executed
+    /// by the multisig (if any).
+    #[serde(default)]
+    pub timelock: Option<String>,
+    /// Has voters commit to their vote during the voting period, and reveal it after, on
+    /// proposals other than emergency and choice ones (if any).
+    #[serde(default)]
+    pub private_voting: Option<PrivateVoting>,
+}
+
+#[cw_serde]
+pub struct ThresholdProfile {
+    pub name: String,
+    pub threshold: Threshold,
+}
+
+/// Validated into `VetoConfig` on instantiation
+#[cw_serde]
+pub struct UncheckedVetoConfig {
+    pub vetoer: String,
+    /// Must have the same unit as `max_voting_period`.
+    pub timelock_duration: Duration,
+    pub burn_deposit: bool,
+}
+
+/// Validated into `Proposers` on instantiation
+#[cw_serde]
+pub enum UncheckedProposers {
+    /// Must not be empty
+    Only(Vec<String>),
+    MinWeight(u64),
+}
+
+/// Validated into `WeightedGroup` on instantiation
+#[cw_serde]
+pub struct UncheckedWeightedGroup {
+    pub addr: String,
+    /// How many times the weight of the members in this group counts, relative to
+    /// `group_addr`. Must not be 0
+    pub multiplier: u64,
+}
+
+/// Validated into `EmergencyTrack` on instantiation
+#[cw_serde]
+pub struct UncheckedEmergencyTrack {
+    /// Must not be empty. Only those of them who are members of the group can propose
+    pub council: Vec<String>,
+    pub threshold: Threshold,
+    /// Must have the unit of the max voting period, and not be longer
+    pub voting_period: Duration,
 }
 
 // TODO: add some T variants? Maybe good enough as fixed Empty for now
@@ -28,19 +112,180 @@ pub enum ExecuteMsg {
         msgs: Vec<CosmosMsg<Empty>>,
         // note: we ignore API-spec'd earliest if passed, always opens immediately
         latest: Option<Expiration>,
+        /// The threshold profile tallying the proposal, instead of the default threshold
+        #[serde(default)]
+        threshold_profile: Option<String>,
+        /// When the proposal can be executed once passed, eg. exactly at an epoch boundary.
+        /// Must have the unit of `max_voting_period`.
+        #[serde(default)]
+        execution_window: Option<ExecutionWindow>,
+        /// Structured data for governance UIs, eg. a link to the discussion
+        #[serde(default)]
+        metadata: Option<ProposalMetadata>,
+    },
+    /// Proposes to record a decision, with nothing to execute once it passed
+    ProposeSignaling {
+        title: String,
+        description: String,
+        latest: Option<Expiration>,
+        #[serde(default)]
+        threshold_profile: Option<String>,
+        /// Structured data for governance UIs, eg. a link to the discussion
+        #[serde(default)]
+        metadata: Option<ProposalMetadata>,
+    },
+    /// Proposes an urgent action, voted on with the emergency voting period and threshold.
+    /// Only for members in the emergency council
+    ProposeEmergency {
+        title: String,
+        description: String,
+        msgs: Vec<CosmosMsg<Empty>>,
+        /// Structured data for governance UIs, eg. a link to the discussion
+        #[serde(default)]
+        metadata: Option<ProposalMetadata>,
+    },
+    /// Proposes several options, the one winning as per `win_condition` having its messages
+    /// executed. Voted on with `VoteChoice`, tallied with the default threshold
+    ProposeChoice {
+        title: String,
+        description: String,
+        options: Vec<ProposalOption>,
+        win_condition: WinCondition,
+        // note: we ignore API-spec'd earliest if passed, always opens immediately
+        latest: Option<Expiration>,
+        /// Structured data for governance UIs, eg. a link to the discussion
+        #[serde(default)]
+        metadata: Option<ProposalMetadata>,
+    },
+    /// Creates the proposal of the message, one of the above, paying its cw20 deposit with the
+    /// tokens sent
+    Receive(Cw20ReceiveMsg),
+    /// Updates an open proposal nobody else voted on yet. Only for the proposer, whose own
+    /// vote is withdrawn
+    Amend {
+        proposal_id: u64,
+        title: Option<String>,
+        description: Option<String>,
+        msgs: Option<Vec<CosmosMsg<Empty>>>,
     },
     Vote {
         proposal_id: u64,
         vote: Vote,
     },
+    /// Votes with the weight of the sender split across options (eg. 60% yes, 40% abstain).
+    /// The shares must be non-zero, for distinct options, and add up to one
+    VoteWeighted {
+        proposal_id: u64,
+        options: Vec<WeightedVoteOption>,
+    },
+    /// Votes for one of the options of a choice proposal, by its index
+    VoteChoice {
+        proposal_id: u64,
+        option: u32,
+    },
+    /// Casts the vote of a voter who signed the ballot off-chain, so that voters without gas
+    /// tokens can vote. Anyone may submit it before the deadline, and nonce must be the voter's
+    /// current one, which is then incremented
+    RelayVote {
+        ballot: VotePermit,
+        /// The voter's compressed secp256k1 public key, which must derive to the voter address
+        pubkey: Binary,
+        signature: Binary,
+    },
+    /// Commits to a vote on a private proposal, with the `vote_commitment` of the vote and a
+    /// secret salt, along with the bond if any. Only while commitments are open
+    CommitVote {
+        proposal_id: u64,
+        commitment: Binary,
+    },
+    /// Casts the vote committed to, once commitments are closed and until the proposal expires
+    RevealVote {
+        proposal_id: u64,
+        vote: Vote,
+        salt: Binary,
+    },
     Execute {
         proposal_id: u64,
+        /// The ascending indices of the messages to execute, all those not executed yet if
+        /// unset. The proposal is `Executed` once all its messages are. Not supported with a
+        /// timelock contract, which queues all of them.
+        #[serde(default)]
+        msg_indices: Option<Vec<u32>>,
+    },
+    /// Dispatches the messages of a proposal being executed. Only for the multisig itself, as
+    /// a submessage of `Execute`, so that failing messages leave the proposal `ExecutionFailed`
+    DispatchProposal {
+        proposal_id: u64,
+        #[serde(default)]
+        msg_indices: Option<Vec<u32>>,
     },
     Close {
         proposal_id: u64,
     },
+    /// Withdraws a proposal not executed yet. For its proposer, until anybody votes against it,
+    /// and for the vetoer at any time
+    Cancel {
+        proposal_id: u64,
+    },
+    /// Closes the proposals that can be closed among the next `limit` ones after `start_after`.
+    /// The last one read is returned in the `last_proposal_id` attribute, to continue from there
+    CloseMany {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Removes the executed, rejected, vetoed or cancelled proposals, along with their votes, once
+    /// `older_than` passed since they expired, among the next `limit` ones after `start_after`.
+    /// The last one read is returned in the `last_proposal_id` attribute, to continue from there
+    PruneProposals {
+        older_than: Duration,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Kills a passed proposal during its veto window. Only for the vetoer
+    Veto {
+        proposal_id: u64,
+    },
+    /// Changes the rules for proposals to come. Only for the multisig itself, executing a passed
+    /// proposal. Fields left to None are unchanged
+    UpdateConfig {
+        threshold: Option<Threshold>,
+        max_voting_period: Option<Duration>,
+        group_addr: Option<String>,
+    },
     /// Handles update hook messages from the group contract
     MemberChangedHook(MemberChangedHookMsg),
+    /// Registers a contract to be told of the lifecycle of proposals. Only for the multisig
+    /// itself, executing a passed proposal
+    AddProposalHook {
+        addr: String,
+    },
+    /// Unregisters a proposal hook. Only for the multisig itself, executing a passed proposal
+    RemoveProposalHook {
+        addr: String,
+    },
+    /// Records how the messages of a queued proposal were executed, failing if `error` is set.
+    /// Only for the timelock contract
+    TimelockExecuted {
+        proposal_id: u64,
+        error: Option<String>,
+    },
+}
+
+/// Messages the multisig sends to its timelock contract
+#[cw_serde]
+pub enum TimelockExecuteMsg {
+    /// Queues the messages of a passed proposal, returning a `ScheduleResponse` as data. The
+    /// timelock sends `TimelockExecuted` back to the multisig once it executed them
+    Schedule {
+        proposal_id: u64,
+        msgs: Vec<CosmosMsg>,
+    },
+}
+
+#[cw_serde]
+pub struct ScheduleResponse {
+    /// When the timelock will execute the messages
+    pub eta: Expiration,
 }
 
 // We can also add this as a cw3 extension
@@ -51,6 +296,8 @@ pub enum QueryMsg {
     Threshold {},
     #[returns(cw3::ProposalResponse)]
     Proposal { proposal_id: u64 },
+    #[returns(cw3::TallyResponse)]
+    ProposalTally { proposal_id: u64 },
     #[returns(cw3::ProposalListResponse)]
     ListProposals {
         start_after: Option<u64>,
@@ -61,6 +308,32 @@ pub enum QueryMsg {
         start_before: Option<u64>,
         limit: Option<u32>,
     },
+    /// Lists the proposals with the given current status, skipping the others.
+    #[returns(cw3::ProposalListResponse)]
+    ListProposalsByStatus {
+        status: cw3::Status,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    #[returns(cw3::ProposalListResponse)]
+    ListProposalsByProposer {
+        proposer: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Passed proposals waiting for their execution window, or within it
+    #[returns(cw3::ProposalListResponse)]
+    ScheduledExecutions {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Open proposals the voter can vote on, but did not yet
+    #[returns(cw3::ProposalListResponse)]
+    PendingVotes {
+        voter: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
     #[returns(cw3::VoteResponse)]
     Vote { proposal_id: u64, voter: String },
     #[returns(cw3::VoteListResponse)]
@@ -79,4 +352,46 @@ pub enum QueryMsg {
     /// Gets the current configuration.
     #[returns(crate::state::Config)]
     Config {},
+    /// When a passed proposal can be executed, once its veto window and execution timelock
+    /// are over.
+    #[returns(ExecutableAtResponse)]
+    ExecutableAt { proposal_id: u64 },
+    /// Lists the threshold profiles proposers can pick.
+    #[returns(ThresholdProfilesResponse)]
+    ThresholdProfiles {},
+    /// Contracts told of the lifecycle of proposals
+    #[returns(cw_controllers::HooksResponse)]
+    ProposalHooks {},
+    /// The nonce the next ballot signed by the voter must use
+    #[returns(cw_controllers::PermitNonceResponse)]
+    VoteNonce { voter: String },
+    /// The commitment of the voter to a vote on a private proposal, until it is revealed
+    #[returns(cw3::VoteCommitmentResponse)]
+    VoteCommitment { proposal_id: u64, voter: String },
+}
+
+#[cw_serde]
+pub struct ExecutableAtResponse {
+    /// None if the proposal did not pass, was executed (or vetoed), or can be executed now
+    pub executable_at: Option<Expiration>,
+}
+
+#[cw_serde]
+pub struct ThresholdProfilesResponse {
+    pub profiles: Vec<ThresholdProfileResponse>,
+}
+
+#[cw_serde]
+pub struct ThresholdProfileResponse {
+    pub name: String,
+    pub threshold: ThresholdResponse,
+}
+
+#[cw_serde]
+pub struct MigrateMsg {
+    /// Required to migrate a cw3-fixed-multisig in place: the group whose members vote from now
+    /// on, usually a cw4-group instantiated with the voters of its `Export {}` query. Ignored
+    /// when upgrading a cw3-flex-multisig
+    #[serde(default)]
+    pub group_addr: Option<String>,
 }
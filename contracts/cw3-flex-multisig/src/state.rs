@@ -1,9 +1,11 @@
+use std::collections::BTreeMap;
+
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, QuerierWrapper};
-use cw3::DepositInfo;
+use cosmwasm_std::{Addr, QuerierWrapper, StdResult, Storage, Uint64};
+use cw3::{CloseReward, DepositInfo, PrivateVoting, VoterDetail};
 use cw4::Cw4Contract;
-use cw_storage_plus::Item;
-use cw_utils::{Duration, Threshold};
+use cw_storage_plus::{Item, Map};
+use cw_utils::{Duration, Expiration, Threshold};
 
 use crate::error::ContractError;
 
@@ -16,6 +18,15 @@ pub enum Executor {
     Only(Addr),
 }
 
+/// Restricts who is able to create proposals, instead of any member of the voting group
+#[cw_serde]
+pub enum Proposers {
+    /// Only the given addresses, members of the group or not
+    Only(Vec<Addr>),
+    /// Only members of the group with at least this weight
+    MinWeight(u64),
+}
+
 #[cw_serde]
 pub struct Config {
     pub threshold: Threshold,
@@ -27,6 +38,77 @@ pub struct Config {
     pub executor: Option<Executor>,
     /// The price, if any, of creating a new proposal.
     pub proposal_deposit: Option<DepositInfo>,
+    /// Who can veto passed proposals, and for how long, if anyone.
+    #[serde(default)]
+    pub veto: Option<VetoConfig>,
+    /// The reward, if any, for closing a rejected proposal.
+    #[serde(default)]
+    pub close_reward: Option<CloseReward>,
+    /// How long passed proposals wait before they can be executed, if at all.
+    /// Has the same unit as `max_voting_period`.
+    #[serde(default)]
+    pub execution_delay: Option<Duration>,
+    /// Whether voters can change their vote while the proposal is open.
+    #[serde(default)]
+    pub allow_revoting: bool,
+    /// Who can create proposals, if not every member of the group.
+    #[serde(default)]
+    pub proposers: Option<Proposers>,
+    /// Groups whose members vote along with those of `group_addr`, with their weight multiplied.
+    #[serde(default)]
+    pub extra_groups: Vec<WeightedGroup>,
+    /// Whether voting power is the square root of the weight, rather than the weight itself.
+    #[serde(default)]
+    pub quadratic_voting: bool,
+    /// How emergency proposals are created and voted on, if they can be.
+    #[serde(default)]
+    pub emergency: Option<EmergencyTrack>,
+    /// Timelock contract executing the messages of passed proposals, if not the multisig itself.
+    #[serde(default)]
+    pub timelock: Option<Addr>,
+    /// Whether voters commit to their vote before revealing it, on proposals other than
+    /// emergency and choice ones.
+    #[serde(default)]
+    pub private_voting: Option<PrivateVoting>,
+}
+
+#[cw_serde]
+pub struct EmergencyTrack {
+    /// The members among them can create emergency proposals
+    pub council: Vec<Addr>,
+    /// Applied instead of the threshold of the config
+    pub threshold: Threshold,
+    /// Applied instead of the max voting period, with the same unit
+    pub voting_period: Duration,
+}
+
+impl EmergencyTrack {
+    /// Whether the voting period is no longer than `max_voting_period`, with the same unit
+    pub fn is_shorter(&self, max_voting_period: Duration) -> bool {
+        match (self.voting_period, max_voting_period) {
+            (Duration::Height(period), Duration::Height(max)) => period <= max,
+            (Duration::Time(period), Duration::Time(max)) => period <= max,
+            _ => false,
+        }
+    }
+}
+
+/// Another group whose members vote, the weight in it counting `multiplier` times
+#[cw_serde]
+pub struct WeightedGroup {
+    pub group: Cw4Contract,
+    pub multiplier: u64,
+}
+
+/// Allows an address to veto passed proposals during a window after they passed
+#[cw_serde]
+pub struct VetoConfig {
+    pub vetoer: Addr,
+    /// How long passed proposals can be vetoed before they can be executed.
+    /// Has the same unit as `max_voting_period`.
+    pub timelock_duration: Duration,
+    /// Whether vetoing a proposal burns its deposit, rather than handling it as for rejected ones
+    pub burn_deposit: bool,
 }
 
 impl Config {
@@ -38,8 +120,7 @@ impl Config {
         if let Some(executor) = &self.executor {
             match executor {
                 Executor::Member => {
-                    self.group_addr
-                        .is_member(querier, sender, None)?
+                    self.member_weight(querier, sender, None)?
                         .ok_or(ContractError::Unauthorized {})?;
                 }
                 Executor::Only(addr) => {
@@ -51,7 +132,180 @@ impl Config {
         }
         Ok(())
     }
+
+    /// Checks the sender can create proposals, returning the weight of their implied vote.
+    /// Non-voting members may propose, as may allowed proposers outside the group, with 0 weight.
+    pub fn proposer_power(
+        &self,
+        querier: &QuerierWrapper,
+        sender: &Addr,
+    ) -> Result<u64, ContractError> {
+        let power = self.member_weight(querier, sender, None)?;
+        match &self.proposers {
+            None => power.ok_or(ContractError::Unauthorized {}),
+            Some(Proposers::Only(addrs)) if addrs.contains(sender) => Ok(power.unwrap_or_default()),
+            Some(Proposers::Only(_)) => Err(ContractError::Unauthorized {}),
+            Some(Proposers::MinWeight(min)) => match power {
+                Some(power) if power >= *min => Ok(power),
+                _ => Err(ContractError::Unauthorized {}),
+            },
+        }
+    }
+
+    /// All the voting groups with their multiplier, starting with `group_addr`
+    pub fn groups(&self) -> impl Iterator<Item = (&Cw4Contract, u64)> {
+        std::iter::once((&self.group_addr, 1)).chain(
+            self.extra_groups
+                .iter()
+                .map(|group| (&group.group, group.multiplier)),
+        )
+    }
+
+    /// Sum of the total weight of each group, multiplied. With quadratic voting, rather the sum
+    /// of the voting power of every member, as stored in `QUADRATIC_TOTAL`
+    pub fn total_weight(&self, storage: &dyn Storage, querier: &QuerierWrapper) -> StdResult<u64> {
+        if self.quadratic_voting {
+            return QUADRATIC_TOTAL.load(storage);
+        }
+        self.groups().try_fold(0, |total, (group, multiplier)| {
+            add_weight(total, group.total_weight(querier)?, multiplier)
+        })
+    }
+
+    /// Sum of the voting power of every member, listing them all. Only called when the groups
+    /// are set, as the membership hook keeps `QUADRATIC_TOTAL` up to date afterwards.
+    pub fn count_voting_power(&self, querier: &QuerierWrapper) -> StdResult<u64> {
+        let mut total = 0;
+        let mut start_after = None;
+        loop {
+            let voters = self.voters(querier, start_after, VOTERS_PAGE)?;
+            total = voters
+                .iter()
+                .try_fold(total, |total, voter| add_weight(total, voter.weight, 1))?;
+            match voters.last() {
+                Some(last) => start_after = Some(last.addr.clone()),
+                None => return Ok(total),
+            }
+        }
+    }
+
+    /// The voting power of the members of any group after `start_after`, up to `limit` of them
+    pub fn voters(
+        &self,
+        querier: &QuerierWrapper,
+        start_after: Option<String>,
+        limit: u32,
+    ) -> StdResult<Vec<VoterDetail>> {
+        // the first members after start_after of each group, merged, are the first voters
+        let mut weights = BTreeMap::<String, u64>::new();
+        for (group, multiplier) in self.groups() {
+            for member in group.list_members(querier, start_after.clone(), Some(limit))? {
+                let weight = weights.entry(member.addr).or_default();
+                *weight = add_weight(*weight, member.weight, multiplier)?;
+            }
+        }
+        Ok(weights
+            .into_iter()
+            .take(limit as usize)
+            .map(|(addr, weight)| VoterDetail {
+                addr,
+                weight: self.scale(weight),
+            })
+            .collect())
+    }
+
+    /// The voting power for the given weight: its square root with quadratic voting
+    pub fn scale(&self, weight: u64) -> u64 {
+        if self.quadratic_voting {
+            isqrt(weight)
+        } else {
+            weight
+        }
+    }
+
+    /// Voting power of the address (its weight in each group, multiplied, and summed), at the
+    /// given height if any. None if it is a member of none of the groups.
+    pub fn member_weight(
+        &self,
+        querier: &QuerierWrapper,
+        addr: &Addr,
+        height: Option<u64>,
+    ) -> StdResult<Option<u64>> {
+        let weight = self.summed_weight(querier, addr, height)?;
+        Ok(weight.map(|weight| self.scale(weight)))
+    }
+
+    /// Weight of the address in each group, multiplied, and summed, at the given height if any.
+    /// None if it is a member of none of the groups.
+    pub fn summed_weight(
+        &self,
+        querier: &QuerierWrapper,
+        addr: &Addr,
+        height: Option<u64>,
+    ) -> StdResult<Option<u64>> {
+        self.groups()
+            .try_fold(None, |total: Option<u64>, (group, multiplier)| {
+                StdResult::Ok(match group.is_member(querier, addr, height)? {
+                    Some(weight) => {
+                        Some(add_weight(total.unwrap_or_default(), weight, multiplier)?)
+                    }
+                    None => total,
+                })
+            })
+    }
+
+    /// Like `member_weight` at the given height, but None for a total weight of 0
+    pub fn voting_power(
+        &self,
+        querier: &QuerierWrapper,
+        addr: &Addr,
+        height: u64,
+    ) -> StdResult<Option<u64>> {
+        Ok(self
+            .member_weight(querier, addr, Some(height))?
+            .filter(|power| *power >= 1))
+    }
+}
+
+// how many voters are listed at once when adding up quadratic voting power
+const VOTERS_PAGE: u32 = 30;
+
+/// `total` plus `weight` counted `multiplier` times, erroring on overflow
+pub fn add_weight(total: u64, weight: u64, multiplier: u64) -> StdResult<u64> {
+    let weight = Uint64::from(weight).checked_mul(multiplier.into())?;
+    Ok(Uint64::from(total).checked_add(weight)?.u64())
+}
+
+/// Floor of the square root, with Newton's method on integers
+pub fn isqrt(n: u64) -> u64 {
+    if n < 2 {
+        return n;
+    }
+    // u128 so that x + n / x cannot overflow
+    let n = n as u128;
+    let mut x = n;
+    let mut y = x / 2 + x % 2;
+    while y < x {
+        x = y;
+        y = (x + n / x) / 2;
+    }
+    x as u64
 }
 
 // unique items
 pub const CONFIG: Item<Config> = Item::new("config");
+
+// end of the veto window of proposals that passed by votes
+pub const VETO_TIMELOCKS: Map<u64, Expiration> = Map::new("veto_timelocks");
+
+// end of the execution timelock of proposals that passed by votes
+pub const EXECUTION_TIMELOCKS: Map<u64, Expiration> = Map::new("execution_timelocks");
+
+// when the timelock contract executes the messages of queued proposals
+pub const TIMELOCK_ETAS: Map<u64, Expiration> = Map::new("timelock_etas");
+
+// sum of the voting power of every member, with quadratic voting
+pub const QUADRATIC_TOTAL: Item<u64> = Item::new("quadratic_total");
+
+// thresholds proposers can pick by name instead of the default one
+pub const THRESHOLD_PROFILES: Map<&str, Threshold> = Map::new("threshold_profiles");
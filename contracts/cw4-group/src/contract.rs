@@ -2,12 +2,12 @@
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
     attr, to_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Order, Response, StdResult,
-    SubMsg, Uint64,
+    Storage, SubMsg, Uint64,
 };
 use cw2::set_contract_version;
 use cw4::{
-    Member, MemberChangedHookMsg, MemberDiff, MemberListResponse, MemberResponse,
-    TotalWeightResponse,
+    DecimalMember, Member, MemberChangedHookMsg, MemberDiff, MemberListResponse, MemberResponse,
+    TotalWeightDiff, TotalWeightResponse, WeightDecimalsResponse, MAX_WEIGHT_DECIMALS,
 };
 use cw_storage_plus::Bound;
 use cw_utils::maybe_addr;
@@ -15,7 +15,7 @@ use cw_utils::maybe_addr;
 use crate::error::ContractError;
 use crate::helpers::validate_unique_members;
 use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::state::{ADMIN, HOOKS, MEMBERS, TOTAL};
+use crate::state::{ADMIN, ALLOW_LEAVE, HOOKS, MEMBERS, TOTAL, WEIGHT_DECIMALS};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:cw4-group";
@@ -25,13 +25,22 @@ const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 // make use of the custom errors
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     _info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
-    create(deps, msg.admin, msg.members, env.block.height)?;
+    if msg.weight_decimals > MAX_WEIGHT_DECIMALS {
+        return Err(ContractError::InvalidWeightDecimals {
+            max: MAX_WEIGHT_DECIMALS,
+        });
+    }
+    let mut members = msg.members;
+    members.extend(to_members(&msg.decimal_members, msg.weight_decimals)?);
+    create(deps.branch(), msg.admin, members, env.block.height)?;
+    ALLOW_LEAVE.save(deps.storage, &msg.allow_leave)?;
+    WEIGHT_DECIMALS.save(deps.storage, &msg.weight_decimals)?;
     Ok(Response::default())
 }
 
@@ -81,6 +90,12 @@ pub fn execute(
         ExecuteMsg::UpdateMembers { add, remove } => {
             execute_update_members(deps, env, info, add, remove)
         }
+        ExecuteMsg::UpdateDecimalMembers { add, remove } => {
+            let decimals = load_weight_decimals(deps.storage)?;
+            let add = to_members(&add, decimals)?;
+            execute_update_members(deps, env, info, add, remove)
+        }
+        ExecuteMsg::Leave {} => execute_leave(deps, env, info),
         ExecuteMsg::AddHook { addr } => {
             Ok(HOOKS.execute_add_hook(&ADMIN, deps, info, api.addr_validate(&addr)?)?)
         }
@@ -115,6 +130,38 @@ pub fn execute_update_members(
         .add_attributes(attributes))
 }
 
+pub fn execute_leave(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    if !ALLOW_LEAVE.may_load(deps.storage)?.unwrap_or_default() {
+        return Err(ContractError::LeaveDisabled {});
+    }
+
+    let height = env.block.height;
+    let weight = MEMBERS
+        .may_load(deps.storage, &info.sender)?
+        .ok_or(ContractError::NotMember {})?;
+    MEMBERS.remove(deps.storage, &info.sender, height)?;
+    let old_total = TOTAL.load(deps.storage)?;
+    let new_total = Uint64::from(old_total).checked_sub(Uint64::from(weight))?;
+    TOTAL.save(deps.storage, &new_total.u64(), height)?;
+
+    // call all registered hooks
+    let diff = MemberChangedHookMsg::one(
+        MemberDiff::new(&info.sender, Some(weight), None),
+        TotalWeightDiff::new(old_total, new_total.u64()),
+    );
+    let messages = HOOKS.prepare_hooks(deps.storage, |h| {
+        diff.clone().into_cosmos_msg(h).map(SubMsg::new)
+    })?;
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_attribute("action", "leave")
+        .add_attribute("sender", info.sender))
+}
+
 // the logic from execute_update_members extracted for easier import
 pub fn update_members(
     deps: DepsMut,
@@ -128,7 +175,8 @@ pub fn update_members(
 
     ADMIN.assert_admin(deps.as_ref(), &sender)?;
 
-    let mut total = Uint64::from(TOTAL.load(deps.storage)?);
+    let old_total = TOTAL.load(deps.storage)?;
+    let mut total = Uint64::from(old_total);
     let mut diffs: Vec<MemberDiff> = vec![];
 
     // add all new members and update total
@@ -154,7 +202,10 @@ pub fn update_members(
     }
 
     TOTAL.save(deps.storage, &total.u64(), height)?;
-    Ok(MemberChangedHookMsg { diffs })
+    Ok(MemberChangedHookMsg::new(
+        diffs,
+        TotalWeightDiff::new(old_total, total.u64()),
+    ))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -172,9 +223,21 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
         }
         QueryMsg::Admin {} => to_binary(&ADMIN.query_admin(deps)?),
         QueryMsg::Hooks {} => to_binary(&HOOKS.query_hooks(deps)?),
+        QueryMsg::WeightDecimals {} => to_binary(&WeightDecimalsResponse {
+            decimals: load_weight_decimals(deps.storage)?,
+        }),
     }
 }
 
+/// Groups created before decimal weights were supported have integer weights
+fn load_weight_decimals(storage: &dyn Storage) -> StdResult<u32> {
+    Ok(WEIGHT_DECIMALS.may_load(storage)?.unwrap_or_default())
+}
+
+fn to_members(members: &[DecimalMember], decimals: u32) -> StdResult<Vec<Member>> {
+    members.iter().map(|m| m.to_member(decimals)).collect()
+}
+
 pub fn query_total_weight(deps: Deps, height: Option<u64>) -> StdResult<TotalWeightResponse> {
     let weight = match height {
         Some(h) => TOTAL.may_load_at_height(deps.storage, h),
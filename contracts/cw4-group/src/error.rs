@@ -20,6 +20,15 @@ pub enum ContractError {
     #[error("Unauthorized")]
     Unauthorized {},
 
+    #[error("Members are not allowed to leave this group")]
+    LeaveDisabled {},
+
+    #[error("Sender is not a member of this group")]
+    NotMember {},
+
+    #[error("Weight decimals cannot exceed {max}")]
+    InvalidWeightDecimals { max: u32 },
+
     #[error("Message contained duplicate member: {member}")]
     DuplicateMember { member: String },
 }
@@ -1,5 +1,5 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cw4::Member;
+use cw4::{DecimalMember, Member};
 
 #[cw_serde]
 pub struct InstantiateMsg {
@@ -7,6 +7,16 @@ pub struct InstantiateMsg {
     /// Omit it to make the group immutable.
     pub admin: Option<String>,
     pub members: Vec<Member>,
+    /// If set, members may remove themselves from the group with `Leave {}`
+    #[serde(default)]
+    pub allow_leave: bool,
+    /// Number of decimal places of the weights, see the cw4 "decimal weights" extension.
+    /// Defaults to 0, that is integer weights.
+    #[serde(default)]
+    pub weight_decimals: u32,
+    /// Members with fractional weights, added along with `members`
+    #[serde(default)]
+    pub decimal_members: Vec<DecimalMember>,
 }
 
 #[cw_serde]
@@ -19,6 +29,14 @@ pub enum ExecuteMsg {
         remove: Vec<String>,
         add: Vec<Member>,
     },
+    /// Same as UpdateMembers, with fractional weights. They may not have more
+    /// decimal places than `weight_decimals`
+    UpdateDecimalMembers {
+        remove: Vec<String>,
+        add: Vec<DecimalMember>,
+    },
+    /// Removes the sender from the group. Only allowed if `allow_leave` was set on instantiation
+    Leave {},
     /// Add a new hook to be informed of all membership changes. Must be called by Admin
     AddHook { addr: String },
     /// Remove a hook. Must be called by Admin
@@ -45,4 +63,7 @@ pub enum QueryMsg {
     /// Shows all registered hooks.
     #[returns(cw_controllers::HooksResponse)]
     Hooks {},
+    /// Number of decimal places of the weights
+    #[returns(cw4::WeightDecimalsResponse)]
+    WeightDecimals {},
 }
@@ -4,10 +4,14 @@ use cw4::{
     TOTAL_KEY_CHECKPOINTS,
 };
 use cw_controllers::{Admin, Hooks};
-use cw_storage_plus::{SnapshotItem, SnapshotMap, Strategy};
+use cw_storage_plus::{Item, SnapshotItem, SnapshotMap, Strategy};
 
 pub const ADMIN: Admin = Admin::new("admin");
 pub const HOOKS: Hooks = Hooks::new("cw4-hooks");
+/// Whether members may remove themselves with `Leave {}`
+pub const ALLOW_LEAVE: Item<bool> = Item::new("allow_leave");
+/// Number of decimal places of the weights, unset for groups created with integer weights
+pub const WEIGHT_DECIMALS: Item<u32> = Item::new("weight_decimals");
 
 pub const TOTAL: SnapshotItem<u64> = SnapshotItem::new(
     TOTAL_KEY,
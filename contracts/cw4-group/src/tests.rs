@@ -1,12 +1,20 @@
+use std::str::FromStr;
+
 use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-use cosmwasm_std::{from_slice, Addr, Api, DepsMut, OwnedDeps, Querier, Storage, SubMsg};
-use cw4::{member_key, Member, MemberChangedHookMsg, MemberDiff, TOTAL_KEY};
+use cosmwasm_std::{
+    from_binary, from_slice, Addr, Api, Decimal, DepsMut, OwnedDeps, Querier, Storage, SubMsg,
+};
+use cw4::{
+    member_key, DecimalMember, Member, MemberChangedHookMsg, MemberDiff, TotalWeightDiff,
+    WeightDecimalsResponse, TOTAL_KEY,
+};
 use cw_controllers::{AdminError, HookError};
 
 use crate::contract::{
-    execute, instantiate, query_list_members, query_member, query_total_weight, update_members,
+    execute, instantiate, query, query_list_members, query_member, query_total_weight,
+    update_members,
 };
-use crate::msg::{ExecuteMsg, InstantiateMsg};
+use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
 use crate::state::{ADMIN, HOOKS};
 use crate::ContractError;
 
@@ -28,6 +36,9 @@ fn set_up(deps: DepsMut) {
                 weight: 6,
             },
         ],
+        allow_leave: false,
+        weight_decimals: 0,
+        decimal_members: vec![],
     };
     let info = mock_info("creator", &[]);
     instantiate(deps, mock_env(), info, msg).unwrap();
@@ -85,6 +96,9 @@ fn duplicate_members_instantiation() {
                 weight: 6,
             },
         ],
+        allow_leave: false,
+        weight_decimals: 0,
+        decimal_members: vec![],
     };
     let info = mock_info("creator", &[]);
     let err = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap_err();
@@ -390,7 +404,7 @@ fn hooks_fire() {
         MemberDiff::new(USER1, Some(11), Some(20)),
         MemberDiff::new(USER2, Some(6), None),
     ];
-    let hook_msg = MemberChangedHookMsg { diffs };
+    let hook_msg = MemberChangedHookMsg::new(diffs, TotalWeightDiff::new(17, 25));
     let msg1 = SubMsg::new(hook_msg.clone().into_cosmos_msg(contract1).unwrap());
     let msg2 = SubMsg::new(hook_msg.into_cosmos_msg(contract2).unwrap());
     dbg!(&res.messages);
@@ -399,6 +413,156 @@ fn hooks_fire() {
     assert_eq!(res.messages, vec![msg1, msg2]);
 }
 
+#[test]
+fn member_can_leave() {
+    let mut deps = mock_dependencies();
+    set_up(deps.as_mut());
+
+    // leaving is disabled by default
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER1, &[]),
+        ExecuteMsg::Leave {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::LeaveDisabled {});
+
+    // re-create the group with leaving enabled
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        admin: Some(INIT_ADMIN.into()),
+        members: vec![
+            Member {
+                addr: USER1.into(),
+                weight: 11,
+            },
+            Member {
+                addr: USER2.into(),
+                weight: 6,
+            },
+        ],
+        allow_leave: true,
+        weight_decimals: 0,
+        decimal_members: vec![],
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    let hook = String::from("hook1");
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(INIT_ADMIN, &[]),
+        ExecuteMsg::AddHook { addr: hook.clone() },
+    )
+    .unwrap();
+
+    // non-members cannot leave
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER3, &[]),
+        ExecuteMsg::Leave {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NotMember {});
+
+    // member leaves, total is updated and the hook is informed
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER1, &[]),
+        ExecuteMsg::Leave {},
+    )
+    .unwrap();
+    assert_users(&deps, None, Some(6), None, None);
+    let hook_msg = MemberChangedHookMsg::one(
+        MemberDiff::new(USER1, Some(11), None),
+        TotalWeightDiff::new(17, 6),
+    );
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(hook_msg.into_cosmos_msg(hook).unwrap())]
+    );
+
+    // cannot leave twice
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER1, &[]),
+        ExecuteMsg::Leave {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NotMember {});
+}
+
+#[test]
+fn decimal_weights() {
+    let mut deps = mock_dependencies();
+
+    // cannot be more precise than Decimal
+    let msg = InstantiateMsg {
+        admin: Some(INIT_ADMIN.into()),
+        members: vec![],
+        allow_leave: false,
+        weight_decimals: 19,
+        decimal_members: vec![],
+    };
+    let info = mock_info("creator", &[]);
+    let err = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert_eq!(err, ContractError::InvalidWeightDecimals { max: 18 });
+
+    // integer and fractional members can be mixed
+    let msg = InstantiateMsg {
+        admin: Some(INIT_ADMIN.into()),
+        members: vec![Member {
+            addr: USER1.into(),
+            weight: 250,
+        }],
+        allow_leave: false,
+        weight_decimals: 2,
+        decimal_members: vec![DecimalMember {
+            addr: USER2.into(),
+            weight: Decimal::from_str("0.75").unwrap(),
+        }],
+    };
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_users(&deps, Some(250), Some(75), None, None);
+
+    let res: WeightDecimalsResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::WeightDecimals {}).unwrap())
+            .unwrap();
+    assert_eq!(res.decimals, 2);
+
+    // weights cannot be rounded
+    let msg = ExecuteMsg::UpdateDecimalMembers {
+        remove: vec![],
+        add: vec![DecimalMember {
+            addr: USER3.into(),
+            weight: Decimal::from_str("0.125").unwrap(),
+        }],
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap_err();
+
+    let msg = ExecuteMsg::UpdateDecimalMembers {
+        remove: vec![USER1.into()],
+        add: vec![DecimalMember {
+            addr: USER3.into(),
+            weight: Decimal::from_str("1.25").unwrap(),
+        }],
+    };
+    // only the admin may update members
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER1, &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, AdminError::NotAdmin {}.into());
+    execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+    assert_users(&deps, None, Some(75), Some(125), None);
+}
+
 #[test]
 fn raw_queries_work() {
     // add will over-write and remove have no effect
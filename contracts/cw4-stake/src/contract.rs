@@ -1,22 +1,24 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    coins, from_slice, to_binary, Addr, BankMsg, Binary, Deps, DepsMut, Env, MessageInfo, Order,
-    Response, StdResult, Storage, SubMsg, Uint128, WasmMsg,
+    coins, from_slice, to_binary, Addr, BankMsg, Binary, BlockInfo, Decimal, Deps, DepsMut, Env,
+    MessageInfo, Order, Response, StdError, StdResult, Storage, SubMsg, Uint128, WasmMsg,
 };
 
 use cw2::set_contract_version;
 use cw20::{Balance, Cw20CoinVerified, Cw20ExecuteMsg, Cw20ReceiveMsg, Denom};
 use cw4::{
-    Member, MemberChangedHookMsg, MemberDiff, MemberListResponse, MemberResponse,
+    Member, MemberChangedHookMsg, MemberDiff, MemberListResponse, MemberResponse, TotalWeightDiff,
     TotalWeightResponse,
 };
 use cw_storage_plus::Bound;
 use cw_utils::{maybe_addr, NativeBalance};
 
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, ReceiveMsg, StakedResponse};
-use crate::state::{Config, ADMIN, CLAIMS, CONFIG, HOOKS, MEMBERS, STAKE, TOTAL};
+use crate::msg::{
+    EarlyClaimResponse, ExecuteMsg, InstantiateMsg, QueryMsg, ReceiveMsg, StakedResponse,
+};
+use crate::state::{Config, EarlyExit, ADMIN, CLAIMS, CONFIG, HOOKS, MEMBERS, STAKE, TOTAL};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:cw4-stake";
@@ -38,11 +40,25 @@ pub fn instantiate(
     // min_bond is at least 1, so 0 stake -> non-membership
     let min_bond = std::cmp::max(msg.min_bond, Uint128::new(1));
 
+    let early_exit = msg
+        .early_exit
+        .map(|early_exit| -> Result<_, ContractError> {
+            if early_exit.penalty > Decimal::one() {
+                return Err(ContractError::InvalidPenalty {});
+            }
+            Ok(EarlyExit {
+                penalty: early_exit.penalty,
+                treasury: api.addr_validate(&early_exit.treasury)?,
+            })
+        })
+        .transpose()?;
+
     let config = Config {
         denom: msg.denom,
         tokens_per_weight: msg.tokens_per_weight,
         min_bond,
         unbonding_period: msg.unbonding_period,
+        early_exit,
     };
     CONFIG.save(deps.storage, &config)?;
     TOTAL.save(deps.storage, &0)?;
@@ -72,6 +88,7 @@ pub fn execute(
         ExecuteMsg::Bond {} => execute_bond(deps, env, Balance::from(info.funds), info.sender),
         ExecuteMsg::Unbond { tokens: amount } => execute_unbond(deps, env, info, amount),
         ExecuteMsg::Claim {} => execute_claim(deps, env, info),
+        ExecuteMsg::ClaimEarly {} => execute_claim_early(deps, env, info),
         ExecuteMsg::Receive(msg) => execute_receive(deps, env, info, msg),
     }
 }
@@ -217,14 +234,15 @@ fn update_membership(
     }?;
 
     // update total
-    TOTAL.update(storage, |total| -> StdResult<_> {
-        Ok(total + new.unwrap_or_default() - old.unwrap_or_default())
-    })?;
+    let old_total = TOTAL.load(storage)?;
+    let new_total = old_total + new.unwrap_or_default() - old.unwrap_or_default();
+    TOTAL.save(storage, &new_total)?;
 
     // alert the hooks
     let diff = MemberDiff::new(sender, old, new);
+    let total = TotalWeightDiff::new(old_total, new_total);
     HOOKS.prepare_hooks(storage, |h| {
-        MemberChangedHookMsg::one(diff.clone())
+        MemberChangedHookMsg::one(diff.clone(), total.clone())
             .into_cosmos_msg(h)
             .map(SubMsg::new)
     })
@@ -250,45 +268,81 @@ pub fn execute_claim(
     }
 
     let config = CONFIG.load(deps.storage)?;
-    let (amount_str, message) = match &config.denom {
-        Denom::Native(denom) => {
-            let amount_str = coin_to_string(release, denom.as_str());
-            let amount = coins(release.u128(), denom);
-            let message = SubMsg::new(BankMsg::Send {
-                to_address: info.sender.to_string(),
-                amount,
-            });
-            (amount_str, message)
-        }
+    let message = send_tokens(&config.denom, &info.sender, release)?;
+
+    Ok(Response::new()
+        .add_submessage(message)
+        .add_attribute("action", "claim")
+        .add_attribute("tokens", coin_to_string(release, &config.denom))
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_claim_early(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let early_exit = config
+        .early_exit
+        .ok_or(ContractError::EarlyExitDisabled {})?;
+
+    // mature claims are paid in full, only the ones still unbonding are slashed
+    let mature = CLAIMS.claim_tokens(deps.storage, &info.sender, &env.block, None)?;
+    let pending = CLAIMS.release_all(deps.storage, &info.sender)?;
+    if mature.is_zero() && pending.is_zero() {
+        return Err(ContractError::NothingToClaim {});
+    }
+    let penalty = pending * early_exit.penalty;
+    let release = mature + pending - penalty;
+
+    let mut messages = vec![];
+    if !release.is_zero() {
+        messages.push(send_tokens(&config.denom, &info.sender, release)?);
+    }
+    if !penalty.is_zero() {
+        messages.push(send_tokens(&config.denom, &early_exit.treasury, penalty)?);
+    }
+
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_attribute("action", "claim_early")
+        .add_attribute("tokens", coin_to_string(release, &config.denom))
+        .add_attribute("penalty", coin_to_string(penalty, &config.denom))
+        .add_attribute("sender", info.sender))
+}
+
+fn send_tokens(denom: &Denom, to: &Addr, amount: Uint128) -> StdResult<SubMsg> {
+    let message = match denom {
+        Denom::Native(denom) => SubMsg::new(BankMsg::Send {
+            to_address: to.to_string(),
+            amount: coins(amount.u128(), denom),
+        }),
         Denom::Cw20(addr) => {
-            let amount_str = coin_to_string(release, addr.as_str());
             let transfer = Cw20ExecuteMsg::Transfer {
-                recipient: info.sender.clone().into(),
-                amount: release,
+                recipient: to.into(),
+                amount,
             };
-            let message = SubMsg::new(WasmMsg::Execute {
+            SubMsg::new(WasmMsg::Execute {
                 contract_addr: addr.into(),
                 msg: to_binary(&transfer)?,
                 funds: vec![],
-            });
-            (amount_str, message)
+            })
         }
     };
-
-    Ok(Response::new()
-        .add_submessage(message)
-        .add_attribute("action", "claim")
-        .add_attribute("tokens", amount_str)
-        .add_attribute("sender", info.sender))
+    Ok(message)
 }
 
 #[inline]
-fn coin_to_string(amount: Uint128, denom: &str) -> String {
-    format!("{} {}", amount, denom)
+fn coin_to_string(amount: Uint128, denom: &Denom) -> String {
+    match denom {
+        Denom::Native(denom) => format!("{} {}", amount, denom),
+        Denom::Cw20(addr) => format!("{} {}", amount, addr),
+    }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Member {
             addr,
@@ -301,6 +355,9 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::Claims { address } => {
             to_binary(&CLAIMS.query_claims(deps, &deps.api.addr_validate(&address)?)?)
         }
+        QueryMsg::EarlyClaim { address } => {
+            to_binary(&query_early_claim(deps, &env.block, address)?)
+        }
         QueryMsg::Staked { address } => to_binary(&query_staked(deps, address)?),
         QueryMsg::Admin {} => to_binary(&ADMIN.query_admin(deps)?),
         QueryMsg::Hooks {} => to_binary(&HOOKS.query_hooks(deps)?),
@@ -319,6 +376,35 @@ pub fn query_staked(deps: Deps, addr: String) -> StdResult<StakedResponse> {
     Ok(StakedResponse { stake, denom })
 }
 
+pub fn query_early_claim(
+    deps: Deps,
+    block: &BlockInfo,
+    addr: String,
+) -> StdResult<EarlyClaimResponse> {
+    let addr = deps.api.addr_validate(&addr)?;
+    let config = CONFIG.load(deps.storage)?;
+    let early_exit = config
+        .early_exit
+        .ok_or_else(|| StdError::generic_err("Early exit is not enabled"))?;
+
+    let (mature, pending) = CLAIMS.query_claims(deps, &addr)?.claims.into_iter().fold(
+        (Uint128::zero(), Uint128::zero()),
+        |(mature, pending), claim| {
+            if claim.release_at.is_expired(block) {
+                (mature + claim.amount, pending)
+            } else {
+                (mature, pending + claim.amount)
+            }
+        },
+    );
+    let penalty = pending * early_exit.penalty;
+    Ok(EarlyClaimResponse {
+        release: mature + pending - penalty,
+        penalty,
+        denom: config.denom,
+    })
+}
+
 fn query_member(deps: Deps, addr: String, height: Option<u64>) -> StdResult<MemberResponse> {
     let addr = deps.api.addr_validate(&addr)?;
     let weight = match height {
@@ -367,6 +453,7 @@ mod tests {
     use cw_utils::Duration;
 
     use crate::error::ContractError;
+    use crate::msg::EarlyExitMsg;
 
     use super::*;
 
@@ -401,6 +488,7 @@ mod tests {
             min_bond,
             unbonding_period,
             admin: Some(INIT_ADMIN.into()),
+            early_exit: None,
         };
         let info = mock_info("creator", &[]);
         instantiate(deps, mock_env(), info, msg).unwrap();
@@ -413,6 +501,7 @@ mod tests {
             min_bond: MIN_BOND,
             unbonding_period,
             admin: Some(INIT_ADMIN.into()),
+            early_exit: None,
         };
         let info = mock_info("creator", &[]);
         instantiate(deps, mock_env(), info, msg).unwrap();
@@ -843,6 +932,101 @@ mod tests {
         assert_eq!(get_claims(deps.as_ref(), &Addr::unchecked(USER2)), vec![]);
     }
 
+    #[test]
+    fn claim_early_workflow() {
+        let mut deps = mock_dependencies();
+        default_instantiate(deps.as_mut());
+        bond(deps.as_mut(), 12_000, 0, 0, 1);
+        unbond(deps.as_mut(), 4_000, 0, 0, 2);
+
+        // disabled by default
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(USER1, &[]),
+            ExecuteMsg::ClaimEarly {},
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::EarlyExitDisabled {});
+
+        // penalty cannot exceed 100%
+        let mut deps = mock_dependencies();
+        let mut msg = InstantiateMsg {
+            denom: Denom::Native(DENOM.to_string()),
+            tokens_per_weight: TOKENS_PER_WEIGHT,
+            min_bond: MIN_BOND,
+            unbonding_period: Duration::Height(UNBONDING_BLOCKS),
+            admin: Some(INIT_ADMIN.into()),
+            early_exit: Some(EarlyExitMsg {
+                penalty: Decimal::percent(101),
+                treasury: "treasury".to_string(),
+            }),
+        };
+        let err = instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidPenalty {});
+
+        // 10% penalty
+        msg.early_exit = Some(EarlyExitMsg {
+            penalty: Decimal::percent(10),
+            treasury: "treasury".to_string(),
+        });
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        bond(deps.as_mut(), 12_000, 0, 0, 1);
+        unbond(deps.as_mut(), 4_000, 0, 0, 2);
+        unbond(deps.as_mut(), 1_000, 0, 0, 50);
+
+        // first claim is mature, second one is still unbonding
+        let mut env = mock_env();
+        env.block.height += 2 + UNBONDING_BLOCKS;
+        let preview = query_early_claim(deps.as_ref(), &env.block, USER1.into()).unwrap();
+        assert_eq!(
+            preview,
+            EarlyClaimResponse {
+                release: Uint128::new(4_900),
+                penalty: Uint128::new(100),
+                denom: Denom::Native(DENOM.to_string()),
+            }
+        );
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(USER1, &[]),
+            ExecuteMsg::ClaimEarly {},
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::new(BankMsg::Send {
+                    to_address: USER1.into(),
+                    amount: coins(4_900, DENOM),
+                }),
+                SubMsg::new(BankMsg::Send {
+                    to_address: "treasury".into(),
+                    amount: coins(100, DENOM),
+                }),
+            ]
+        );
+        assert_eq!(get_claims(deps.as_ref(), &Addr::unchecked(USER1)), vec![]);
+
+        // nothing left to claim
+        let err = execute(
+            deps.as_mut(),
+            env,
+            mock_info(USER1, &[]),
+            ExecuteMsg::ClaimEarly {},
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::NothingToClaim {});
+    }
+
     #[test]
     fn add_remove_hooks() {
         // add will over-write and remove have no effect
@@ -944,7 +1128,7 @@ mod tests {
         // ensure messages for each of the 2 hooks
         assert_eq!(res.messages.len(), 2);
         let diff = MemberDiff::new(USER1, None, Some(13));
-        let hook_msg = MemberChangedHookMsg::one(diff);
+        let hook_msg = MemberChangedHookMsg::one(diff, TotalWeightDiff::new(0, 13));
         let msg1 = SubMsg::new(hook_msg.clone().into_cosmos_msg(contract1.clone()).unwrap());
         let msg2 = SubMsg::new(hook_msg.into_cosmos_msg(contract2.clone()).unwrap());
         assert_eq!(res.messages, vec![msg1, msg2]);
@@ -960,7 +1144,7 @@ mod tests {
         // ensure messages for each of the 2 hooks
         assert_eq!(res.messages.len(), 2);
         let diff = MemberDiff::new(USER1, Some(13), Some(6));
-        let hook_msg = MemberChangedHookMsg::one(diff);
+        let hook_msg = MemberChangedHookMsg::one(diff, TotalWeightDiff::new(13, 6));
         let msg1 = SubMsg::new(hook_msg.clone().into_cosmos_msg(contract1).unwrap());
         let msg2 = SubMsg::new(hook_msg.into_cosmos_msg(contract2).unwrap());
         assert_eq!(res.messages, vec![msg1, msg2]);
@@ -37,4 +37,10 @@ pub enum ContractError {
 
     #[error("No data in ReceiveMsg")]
     NoData {},
+
+    #[error("Early exit is not enabled")]
+    EarlyExitDisabled {},
+
+    #[error("Early exit penalty must not exceed 100%")]
+    InvalidPenalty {},
 }
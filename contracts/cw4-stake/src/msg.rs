@@ -1,5 +1,5 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::Uint128;
+use cosmwasm_std::{Decimal, Uint128};
 
 use cw20::{Cw20ReceiveMsg, Denom};
 pub use cw_controllers::ClaimsResponse;
@@ -15,6 +15,16 @@ pub struct InstantiateMsg {
 
     // admin can only add/remove hooks, not change other parameters
     pub admin: Option<String>,
+    /// If set, allows withdrawing unbonding tokens early for a penalty
+    pub early_exit: Option<EarlyExitMsg>,
+}
+
+#[cw_serde]
+pub struct EarlyExitMsg {
+    /// Portion of the unbonding tokens that is slashed on an early exit, must not exceed 1
+    pub penalty: Decimal,
+    /// Receives the slashed tokens
+    pub treasury: String,
 }
 
 #[cw_serde]
@@ -28,6 +38,10 @@ pub enum ExecuteMsg {
     /// Claim is used to claim your native tokens that you previously "unbonded"
     /// after the contract-defined waiting period (eg. 1 week)
     Claim {},
+    /// ClaimEarly releases all your claims, including the ones still unbonding.
+    /// The tokens that are still unbonding are slashed by the configured penalty,
+    /// which is sent to the treasury. Only allowed if early exit is enabled.
+    ClaimEarly {},
 
     /// Change the admin
     UpdateAdmin { admin: Option<String> },
@@ -52,6 +66,9 @@ pub enum QueryMsg {
     /// Claims shows the tokens in process of unbonding for this address
     #[returns(cw_controllers::ClaimsResponse)]
     Claims { address: String },
+    /// Shows how many tokens would be released and slashed by `ClaimEarly` for this address
+    #[returns(EarlyClaimResponse)]
+    EarlyClaim { address: String },
     // Show the number of tokens currently staked by this address.
     #[returns(StakedResponse)]
     Staked { address: String },
@@ -80,3 +97,12 @@ pub struct StakedResponse {
     pub stake: Uint128,
     pub denom: Denom,
 }
+
+#[cw_serde]
+pub struct EarlyClaimResponse {
+    /// Tokens sent to the claimer
+    pub release: Uint128,
+    /// Tokens sent to the treasury
+    pub penalty: Uint128,
+    pub denom: Denom,
+}
@@ -1,5 +1,5 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Uint128};
+use cosmwasm_std::{Addr, Decimal, Uint128};
 use cw20::Denom;
 use cw4::TOTAL_KEY;
 use cw_controllers::{Admin, Claims, Hooks};
@@ -15,6 +15,16 @@ pub struct Config {
     pub tokens_per_weight: Uint128,
     pub min_bond: Uint128,
     pub unbonding_period: Duration,
+    /// If set, unbonding tokens may be withdrawn before the end of the unbonding period
+    pub early_exit: Option<EarlyExit>,
+}
+
+#[cw_serde]
+pub struct EarlyExit {
+    /// Portion of the unbonding tokens that is slashed on an early exit
+    pub penalty: Decimal,
+    /// Receives the slashed tokens
+    pub treasury: Addr,
 }
 
 pub const ADMIN: Admin = Admin::new("admin");
@@ -84,6 +84,15 @@ impl<'a> Claims<'a> {
         Ok(to_send)
     }
 
+    /// This removes all claims for the address, regardless of their maturity,
+    /// and returns the total amount of tokens they were worth.
+    /// It is meant for early exits, where the contract applies its own penalty.
+    pub fn release_all(&self, storage: &mut dyn Storage, addr: &Addr) -> StdResult<Uint128> {
+        let claims = self.0.may_load(storage, addr)?.unwrap_or_default();
+        self.0.remove(storage, addr);
+        Ok(claims.into_iter().map(|c| c.amount).sum())
+    }
+
     pub fn query_claims<Q: CustomQuery>(
         &self,
         deps: Deps<Q>,
@@ -584,4 +593,38 @@ mod test {
 
         assert_eq!(queried_claims.claims.len(), 0);
     }
+
+    #[test]
+    fn release_all_removes_mature_and_pending_claims() {
+        let mut deps = mock_dependencies();
+        let claims = Claims::new("claims");
+        let addr = Addr::unchecked("addr");
+
+        claims
+            .create_claim(
+                deps.as_mut().storage,
+                &addr,
+                TEST_AMOUNT.into(),
+                Expiration::AtHeight(5),
+            )
+            .unwrap();
+        claims
+            .create_claim(
+                deps.as_mut().storage,
+                &addr,
+                (TEST_AMOUNT + 100).into(),
+                Expiration::AtHeight(mock_env().block.height + 100),
+            )
+            .unwrap();
+
+        let amount = claims.release_all(deps.as_mut().storage, &addr).unwrap();
+        assert_eq!(amount, Uint128::from(TEST_AMOUNT * 2 + 100));
+
+        let queried_claims = claims.query_claims(deps.as_ref(), &addr).unwrap();
+        assert_eq!(queried_claims.claims.len(), 0);
+
+        // nothing left to release
+        let amount = claims.release_all(deps.as_mut().storage, &addr).unwrap();
+        assert_eq!(amount, Uint128::zero());
+    }
 }
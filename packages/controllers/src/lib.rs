@@ -13,11 +13,17 @@ goes for smaller scale units.
 Supported controllers:
 
 * Admin (`UpdateAdmin` handler, `Admin` querier, set_admin and is_admin methods)
+* Pausable (`Pause` and `Unpause` handlers guarded by an Admin, `Paused` querier, assert_not_paused method)
+* Permits (use_permit method verifying payloads signed off-chain, `Nonce` querier)
 */
 mod admin;
 mod claim;
 mod hooks;
+mod pause;
+mod permits;
 
 pub use admin::{Admin, AdminError, AdminResponse};
 pub use claim::{Claim, Claims, ClaimsResponse};
 pub use hooks::{HookError, Hooks, HooksResponse};
+pub use pause::{Pausable, PauseError, PausedResponse};
+pub use permits::{pubkey_to_address, PermitError, PermitNonceResponse, Permits};
@@ -0,0 +1,232 @@
+use schemars::JsonSchema;
+use std::fmt;
+use thiserror::Error;
+
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    attr, BlockInfo, CustomQuery, Deps, DepsMut, MessageInfo, Response, StdError, StdResult,
+    Storage,
+};
+use cw_storage_plus::Item;
+use cw_utils::Expiration;
+
+use crate::admin::{Admin, AdminError};
+
+/// Returned from Pausable.query_paused()
+#[cw_serde]
+pub struct PausedResponse {
+    pub paused: bool,
+    /// When the pause ends by itself, `Expiration::Never` if it lasts until unpaused.
+    /// None if not paused
+    pub until: Option<Expiration>,
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum PauseError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    Admin(#[from] AdminError),
+
+    #[error("Contract is paused")]
+    Paused {},
+
+    #[error("Pause expiration is already expired")]
+    AlreadyExpired {},
+}
+
+// stores until when the contract is paused, nothing if it is not
+pub struct Pausable<'a>(Item<'a, Expiration>);
+
+impl<'a> Pausable<'a> {
+    pub const fn new(namespace: &'a str) -> Self {
+        Pausable(Item::new(namespace))
+    }
+
+    /// Pauses until the given expiration, `Expiration::Never` to pause until `unpause` is called.
+    /// Overwrites any running pause.
+    pub fn pause(
+        &self,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        until: Expiration,
+    ) -> Result<(), PauseError> {
+        if until.is_expired(block) {
+            return Err(PauseError::AlreadyExpired {});
+        }
+        Ok(self.0.save(storage, &until)?)
+    }
+
+    pub fn unpause(&self, storage: &mut dyn Storage) {
+        self.0.remove(storage)
+    }
+
+    /// Returns until when the contract is paused, None if it is not (anymore)
+    pub fn paused_until(
+        &self,
+        storage: &dyn Storage,
+        block: &BlockInfo,
+    ) -> StdResult<Option<Expiration>> {
+        Ok(self
+            .0
+            .may_load(storage)?
+            .filter(|until| !until.is_expired(block)))
+    }
+
+    pub fn is_paused(&self, storage: &dyn Storage, block: &BlockInfo) -> StdResult<bool> {
+        Ok(self.paused_until(storage, block)?.is_some())
+    }
+
+    /// Returns PauseError::Paused if paused.
+    /// Helper for a nice one-line check at the top of the handlers to halt.
+    pub fn assert_not_paused(
+        &self,
+        storage: &dyn Storage,
+        block: &BlockInfo,
+    ) -> Result<(), PauseError> {
+        if self.is_paused(storage, block)? {
+            Err(PauseError::Paused {})
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn execute_pause<C, Q: CustomQuery>(
+        &self,
+        admin: &Admin,
+        deps: DepsMut<Q>,
+        block: &BlockInfo,
+        info: MessageInfo,
+        until: Expiration,
+    ) -> Result<Response<C>, PauseError>
+    where
+        C: Clone + fmt::Debug + PartialEq + JsonSchema,
+    {
+        admin.assert_admin(deps.as_ref(), &info.sender)?;
+        self.pause(deps.storage, block, until)?;
+
+        let attributes = vec![
+            attr("action", "pause"),
+            attr("until", until.to_string()),
+            attr("sender", info.sender),
+        ];
+        Ok(Response::new().add_attributes(attributes))
+    }
+
+    pub fn execute_unpause<C, Q: CustomQuery>(
+        &self,
+        admin: &Admin,
+        deps: DepsMut<Q>,
+        info: MessageInfo,
+    ) -> Result<Response<C>, PauseError>
+    where
+        C: Clone + fmt::Debug + PartialEq + JsonSchema,
+    {
+        admin.assert_admin(deps.as_ref(), &info.sender)?;
+        self.unpause(deps.storage);
+
+        let attributes = vec![attr("action", "unpause"), attr("sender", info.sender)];
+        Ok(Response::new().add_attributes(attributes))
+    }
+
+    pub fn query_paused<Q: CustomQuery>(
+        &self,
+        deps: Deps<Q>,
+        block: &BlockInfo,
+    ) -> StdResult<PausedResponse> {
+        let until = self.paused_until(deps.storage, block)?;
+        Ok(PausedResponse {
+            paused: until.is_some(),
+            until,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{Addr, Empty};
+
+    #[test]
+    fn pause_and_expire() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let control = Pausable::new("paused");
+        let admin = Admin::new("pauser");
+        admin
+            .set(deps.as_mut(), Some(Addr::unchecked("pauser")))
+            .unwrap();
+
+        // not paused at first
+        control
+            .assert_not_paused(&deps.storage, &env.block)
+            .unwrap();
+        assert_eq!(
+            control.query_paused(deps.as_ref(), &env.block).unwrap(),
+            PausedResponse {
+                paused: false,
+                until: None
+            }
+        );
+
+        // only the admin can pause, and not in the past
+        let until = Expiration::AtHeight(env.block.height + 10);
+        let err = control
+            .execute_pause::<Empty, Empty>(
+                &admin,
+                deps.as_mut(),
+                &env.block,
+                mock_info("imposter", &[]),
+                until,
+            )
+            .unwrap_err();
+        assert_eq!(err, PauseError::Admin(AdminError::NotAdmin {}));
+        let err = control
+            .execute_pause::<Empty, Empty>(
+                &admin,
+                deps.as_mut(),
+                &env.block,
+                mock_info("pauser", &[]),
+                Expiration::AtHeight(env.block.height),
+            )
+            .unwrap_err();
+        assert_eq!(err, PauseError::AlreadyExpired {});
+        control
+            .execute_pause::<Empty, Empty>(
+                &admin,
+                deps.as_mut(),
+                &env.block,
+                mock_info("pauser", &[]),
+                until,
+            )
+            .unwrap();
+        let err = control
+            .assert_not_paused(&deps.storage, &env.block)
+            .unwrap_err();
+        assert_eq!(err, PauseError::Paused {});
+        assert_eq!(
+            control.query_paused(deps.as_ref(), &env.block).unwrap(),
+            PausedResponse {
+                paused: true,
+                until: Some(until)
+            }
+        );
+
+        // the pause ends by itself
+        env.block.height += 10;
+        assert!(!control.is_paused(&deps.storage, &env.block).unwrap());
+
+        // or when unpaused
+        control
+            .pause(&mut deps.storage, &env.block, Expiration::Never {})
+            .unwrap();
+        assert!(control.is_paused(&deps.storage, &env.block).unwrap());
+        control
+            .execute_unpause::<Empty, Empty>(&admin, deps.as_mut(), mock_info("pauser", &[]))
+            .unwrap();
+        assert!(!control.is_paused(&deps.storage, &env.block).unwrap());
+    }
+}
@@ -0,0 +1,175 @@
+use bech32::{ToBase32, Variant};
+use ripemd::Ripemd160;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Api, BlockInfo, CustomQuery, Deps, StdError, StdResult, Storage};
+use cw_storage_plus::Map;
+use cw_utils::Expiration;
+
+/// Returned from Permits.query_nonce()
+#[cw_serde]
+pub struct PermitNonceResponse {
+    /// The nonce the next permit signed by the address must use
+    pub nonce: u64,
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum PermitError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Permit deadline has passed")]
+    Expired {},
+
+    #[error("Invalid permit nonce, expected {expected}")]
+    InvalidNonce { expected: u64 },
+
+    #[error("Public key does not match the permit signer")]
+    InvalidSigner {},
+
+    #[error("Invalid permit signature")]
+    InvalidSignature {},
+}
+
+/// Verifies payloads signed off-chain by accounts, so that anyone can submit them on their
+/// behalf. Stores the nonce the next permit of each signer must use, so that none is replayed.
+pub struct Permits<'a>(Map<'a, &'a Addr, u64>);
+
+impl<'a> Permits<'a> {
+    pub const fn new(namespace: &'a str) -> Self {
+        Permits(Map::new(namespace))
+    }
+
+    pub fn nonce(&self, storage: &dyn Storage, signer: &Addr) -> StdResult<u64> {
+        Ok(self.0.may_load(storage, signer)?.unwrap_or_default())
+    }
+
+    /// Checks that `signer` signed `sign_bytes` for their current nonce with the key behind
+    /// their address, and that the deadline has not passed. The nonce is then consumed.
+    ///
+    /// `pubkey` is the compressed secp256k1 public key of the signer, and `signature` is over
+    /// the sha256 hash of `sign_bytes`. The payload should include the chain id and contract
+    /// address, so that it cannot be replayed elsewhere.
+    #[allow(clippy::too_many_arguments)]
+    pub fn use_permit(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        signer: &Addr,
+        nonce: u64,
+        deadline: &Expiration,
+        sign_bytes: &[u8],
+        pubkey: &[u8],
+        signature: &[u8],
+    ) -> Result<(), PermitError> {
+        if deadline.is_expired(block) {
+            return Err(PermitError::Expired {});
+        }
+
+        let expected = self.nonce(storage, signer)?;
+        if nonce != expected {
+            return Err(PermitError::InvalidNonce { expected });
+        }
+
+        let (prefix, _, _) =
+            bech32::decode(signer.as_str()).map_err(|_| PermitError::InvalidSigner {})?;
+        if pubkey_to_address(pubkey, &prefix)? != signer.as_str() {
+            return Err(PermitError::InvalidSigner {});
+        }
+        let hash = Sha256::digest(sign_bytes);
+        // malformed signatures or keys are simply invalid
+        let valid = api
+            .secp256k1_verify(&hash, signature, pubkey)
+            .unwrap_or(false);
+        if !valid {
+            return Err(PermitError::InvalidSignature {});
+        }
+
+        Ok(self.0.save(storage, signer, &(nonce + 1))?)
+    }
+
+    pub fn query_nonce<Q: CustomQuery>(
+        &self,
+        deps: Deps<Q>,
+        signer: &Addr,
+    ) -> StdResult<PermitNonceResponse> {
+        let nonce = self.nonce(deps.storage, signer)?;
+        Ok(PermitNonceResponse { nonce })
+    }
+}
+
+/// The bech32 address with the given prefix of the account behind a secp256k1 public key
+pub fn pubkey_to_address(pubkey: &[u8], prefix: &str) -> Result<String, PermitError> {
+    let hash = Ripemd160::digest(Sha256::digest(pubkey));
+    bech32::encode(prefix, hash.to_base32(), Variant::Bech32)
+        .map_err(|_| PermitError::InvalidSigner {})
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::testing::{mock_dependencies, mock_env};
+    use k256::ecdsa::signature::Signer;
+    use k256::ecdsa::{Signature, SigningKey};
+
+    #[test]
+    fn permits_verified_once() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let control = Permits::new("permits");
+        let key = SigningKey::from_bytes(&[7; 32]).unwrap();
+        let pubkey = key.verifying_key().to_bytes();
+        let signer = Addr::unchecked(pubkey_to_address(&pubkey, "cosmos").unwrap());
+        let other = SigningKey::from_bytes(&[8; 32]).unwrap();
+        let deadline = Expiration::AtHeight(env.block.height + 10);
+        let payload = b"vote yes on 1";
+        let signature: Signature = key.sign(payload);
+
+        let use_permit = |deps: &mut cosmwasm_std::OwnedDeps<_, _, _>,
+                          block: &BlockInfo,
+                          nonce,
+                          pubkey: &[u8],
+                          signature: &Signature| {
+            control.use_permit(
+                &deps.api,
+                &mut deps.storage,
+                block,
+                &signer,
+                nonce,
+                &deadline,
+                payload,
+                pubkey,
+                signature.as_ref(),
+            )
+        };
+
+        // the nonce must be the current one, and the key that of the signer
+        assert_eq!(control.nonce(&deps.storage, &signer).unwrap(), 0);
+        let err = use_permit(&mut deps, &env.block, 1, &pubkey, &signature).unwrap_err();
+        assert_eq!(err, PermitError::InvalidNonce { expected: 0 });
+        let other_pubkey = other.verifying_key().to_bytes();
+        let err = use_permit(&mut deps, &env.block, 0, &other_pubkey, &signature).unwrap_err();
+        assert_eq!(err, PermitError::InvalidSigner {});
+        let forged: Signature = other.sign(payload);
+        let err = use_permit(&mut deps, &env.block, 0, &pubkey, &forged).unwrap_err();
+        assert_eq!(err, PermitError::InvalidSignature {});
+
+        // a valid permit consumes the nonce, so it cannot be replayed
+        use_permit(&mut deps, &env.block, 0, &pubkey, &signature).unwrap();
+        assert_eq!(
+            control.query_nonce(deps.as_ref(), &signer).unwrap(),
+            PermitNonceResponse { nonce: 1 }
+        );
+        let err = use_permit(&mut deps, &env.block, 0, &pubkey, &signature).unwrap_err();
+        assert_eq!(err, PermitError::InvalidNonce { expected: 1 });
+
+        // nor used after the deadline
+        env.block.height += 10;
+        let err = use_permit(&mut deps, &env.block, 1, &pubkey, &signature).unwrap_err();
+        assert_eq!(err, PermitError::Expired {});
+    }
+}
@@ -1,7 +1,7 @@
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{to_binary, Addr, CosmosMsg, StdResult, WasmMsg};
 
-use crate::msg::Cw1ExecuteMsg;
+use crate::msg::{Cw1ExecuteMsg, ExecuteMode};
 
 /// Cw1Contract is a wrapper around Addr that provides a lot of helpers
 /// for working with this.
@@ -16,7 +16,10 @@ impl Cw1Contract {
     }
 
     pub fn execute<T: Into<Vec<CosmosMsg>>>(&self, msgs: T) -> StdResult<CosmosMsg> {
-        let msg = Cw1ExecuteMsg::Execute { msgs: msgs.into() };
+        let msg = Cw1ExecuteMsg::Execute {
+            msgs: msgs.into(),
+            mode: ExecuteMode::Atomic,
+        };
         Ok(WasmMsg::Execute {
             contract_addr: self.addr().into(),
             msg: to_binary(&msg)?,
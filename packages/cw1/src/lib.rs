@@ -26,5 +26,5 @@ pub mod msg;
 pub mod query;
 
 pub use crate::helpers::Cw1Contract;
-pub use crate::msg::Cw1ExecuteMsg;
+pub use crate::msg::{Cw1ExecuteMsg, ExecuteMode};
 pub use crate::query::{CanExecuteResponse, Cw1QueryMsg};
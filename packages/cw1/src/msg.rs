@@ -12,5 +12,21 @@ where
     /// Execute requests the contract to re-dispatch all these messages with the
     /// contract's address as sender. Every implementation has it's own logic to
     /// determine in
-    Execute { msgs: Vec<CosmosMsg<T>> },
+    Execute {
+        msgs: Vec<CosmosMsg<T>>,
+        #[serde(default)]
+        mode: ExecuteMode,
+    },
+}
+
+/// How the messages of an `Execute` are dispatched
+#[cw_serde]
+#[derive(Default)]
+pub enum ExecuteMode {
+    /// Either all messages succeed, or the whole execution fails
+    #[default]
+    Atomic,
+    /// Every message is dispatched as a separate submessage, so a failing one does not abort the
+    /// others. The outcome of each message is reported in an `execute_result` event.
+    BestEffort,
 }
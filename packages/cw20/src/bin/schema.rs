@@ -5,8 +5,8 @@ use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
 use cw20::{
     AllAccountsResponse, AllAllowancesResponse, AllowanceResponse, BalanceResponse, Cw20ExecuteMsg,
-    Cw20QueryMsg, Cw20ReceiveMsg, DownloadLogoResponse, MarketingInfoResponse, MinterResponse,
-    TokenInfoResponse,
+    Cw20Permit, Cw20QueryMsg, Cw20ReceiveMsg, Cw20TransferPermit, DownloadLogoResponse,
+    MarketingInfoResponse, MinterResponse, NonceResponse, TokenInfoResponse,
 };
 
 fn main() {
@@ -18,10 +18,13 @@ fn main() {
     export_schema(&schema_for!(Cw20ExecuteMsg), &out_dir);
     export_schema(&schema_for!(Cw20QueryMsg), &out_dir);
     export_schema(&schema_for!(Cw20ReceiveMsg), &out_dir);
+    export_schema(&schema_for!(Cw20Permit), &out_dir);
+    export_schema(&schema_for!(Cw20TransferPermit), &out_dir);
     export_schema(&schema_for!(AllowanceResponse), &out_dir);
     export_schema(&schema_for!(BalanceResponse), &out_dir);
     export_schema(&schema_for!(TokenInfoResponse), &out_dir);
     export_schema(&schema_for!(MinterResponse), &out_dir);
+    export_schema(&schema_for!(NonceResponse), &out_dir);
     export_schema(&schema_for!(DownloadLogoResponse), &out_dir);
     export_schema(&schema_for!(MarketingInfoResponse), &out_dir);
     export_schema(&schema_for!(AllAllowancesResponse), &out_dir);
@@ -3,10 +3,11 @@ use cosmwasm_std::{
     to_binary, Addr, CosmosMsg, CustomQuery, QuerierWrapper, QueryRequest, StdResult, Uint128,
     WasmMsg, WasmQuery,
 };
+use serde::Serialize;
 
 use crate::{
-    AllowanceResponse, BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg, MinterResponse,
-    TokenInfoResponse,
+    AllSpenderAllowancesResponse, AllowanceResponse, BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg,
+    Expiration, MinterResponse, TokenInfoResponse,
 };
 
 /// Cw20Contract is a wrapper around Addr that provides a lot of helpers
@@ -31,6 +32,103 @@ impl Cw20Contract {
         .into())
     }
 
+    /// Transfers `amount` tokens of this contract to `recipient`
+    pub fn transfer<T: Into<String>>(&self, recipient: T, amount: Uint128) -> StdResult<CosmosMsg> {
+        self.call(Cw20ExecuteMsg::Transfer {
+            recipient: recipient.into(),
+            amount,
+        })
+    }
+
+    /// Sends `amount` tokens to `contract`, which is called with `msg` wrapped in a Cw20ReceiveMsg
+    pub fn send<T, M>(&self, contract: T, amount: Uint128, msg: &M) -> StdResult<CosmosMsg>
+    where
+        T: Into<String>,
+        M: Serialize,
+    {
+        self.call(Cw20ExecuteMsg::Send {
+            contract: contract.into(),
+            amount,
+            msg: to_binary(msg)?,
+        })
+    }
+
+    /// Allows `spender` to use an additional `amount` tokens of the sender
+    pub fn increase_allowance<T: Into<String>>(
+        &self,
+        spender: T,
+        amount: Uint128,
+        expires: Option<Expiration>,
+    ) -> StdResult<CosmosMsg> {
+        self.call(Cw20ExecuteMsg::IncreaseAllowance {
+            spender: spender.into(),
+            amount,
+            expires,
+        })
+    }
+
+    /// Lowers the allowance of `spender` on the tokens of the sender by `amount`
+    pub fn decrease_allowance<T: Into<String>>(
+        &self,
+        spender: T,
+        amount: Uint128,
+        expires: Option<Expiration>,
+    ) -> StdResult<CosmosMsg> {
+        self.call(Cw20ExecuteMsg::DecreaseAllowance {
+            spender: spender.into(),
+            amount,
+            expires,
+        })
+    }
+
+    /// Transfers `amount` tokens from `owner` to `recipient`, using the allowance of the sender
+    pub fn transfer_from<T, U>(
+        &self,
+        owner: T,
+        recipient: U,
+        amount: Uint128,
+    ) -> StdResult<CosmosMsg>
+    where
+        T: Into<String>,
+        U: Into<String>,
+    {
+        self.call(Cw20ExecuteMsg::TransferFrom {
+            owner: owner.into(),
+            recipient: recipient.into(),
+            amount,
+        })
+    }
+
+    /// Sends `amount` tokens from `owner` to `contract`, using the allowance of the sender.
+    /// `contract` is called with `msg` wrapped in a Cw20ReceiveMsg
+    pub fn send_from<T, U, M>(
+        &self,
+        owner: T,
+        contract: U,
+        amount: Uint128,
+        msg: &M,
+    ) -> StdResult<CosmosMsg>
+    where
+        T: Into<String>,
+        U: Into<String>,
+        M: Serialize,
+    {
+        self.call(Cw20ExecuteMsg::SendFrom {
+            owner: owner.into(),
+            contract: contract.into(),
+            amount,
+            msg: to_binary(msg)?,
+        })
+    }
+
+    /// Burns `amount` tokens of `owner`, using the allowance of the sender
+    pub fn burn_from<T: Into<String>>(&self, owner: T, amount: Uint128) -> StdResult<CosmosMsg> {
+        self.call(Cw20ExecuteMsg::BurnFrom {
+            owner: owner.into(),
+            amount,
+        })
+    }
+
     fn encode_smart_query<CQ: CustomQuery>(
         &self,
         msg: Cw20QueryMsg,
@@ -55,6 +153,24 @@ impl Cw20Contract {
         Ok(res.balance)
     }
 
+    /// Get token balances for many addresses at once, in the same order.
+    /// Fails if any of the queries fails
+    pub fn balances<I, T, CQ>(
+        &self,
+        querier: &QuerierWrapper<CQ>,
+        addresses: I,
+    ) -> StdResult<Vec<Uint128>>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+        CQ: CustomQuery,
+    {
+        addresses
+            .into_iter()
+            .map(|address| self.balance(querier, address))
+            .collect()
+    }
+
     /// Get metadata from the contract. This is a good check that the address
     /// is a valid Cw20 contract.
     pub fn meta<CQ: CustomQuery>(
@@ -84,6 +200,26 @@ impl Cw20Contract {
         querier.query(&query)
     }
 
+    /// Get a page of the allowances granted to spender, by owner. Requires the "enumerable" extension
+    pub fn spender_allowances<T, CQ>(
+        &self,
+        querier: &QuerierWrapper<CQ>,
+        spender: T,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<AllSpenderAllowancesResponse>
+    where
+        T: Into<String>,
+        CQ: CustomQuery,
+    {
+        let query = self.encode_smart_query(Cw20QueryMsg::AllSpenderAllowances {
+            spender: spender.into(),
+            start_after,
+            limit,
+        })?;
+        querier.query(&query)
+    }
+
     /// Find info on who can mint, and how much
     pub fn minter<CQ: CustomQuery>(
         &self,
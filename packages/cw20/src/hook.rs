@@ -0,0 +1,98 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{to_binary, Binary, CosmosMsg, StdResult, Uint128, WasmMsg};
+
+use crate::AllowanceResponse;
+
+/// Cw20AllowanceSpentMsg should be de/serialized under `AllowanceSpent()` variant in a ExecuteMsg.
+/// It is sent to owners that are contracts whenever a spender consumes their allowance.
+#[cw_serde]
+pub struct Cw20AllowanceSpentMsg {
+    pub spender: String,
+    pub amount: Uint128,
+    /// The allowance left after this spend
+    pub remaining: AllowanceResponse,
+}
+
+impl Cw20AllowanceSpentMsg {
+    /// serializes the message
+    pub fn into_binary(self) -> StdResult<Binary> {
+        let msg = HookExecuteMsg::AllowanceSpent(self);
+        to_binary(&msg)
+    }
+
+    /// creates a cosmos_msg sending this struct to the named contract
+    pub fn into_cosmos_msg<T: Into<String>>(self, contract_addr: T) -> StdResult<CosmosMsg> {
+        let msg = self.into_binary()?;
+        let execute = WasmMsg::Execute {
+            contract_addr: contract_addr.into(),
+            msg,
+            funds: vec![],
+        };
+        Ok(execute.into())
+    }
+}
+
+/// Cw20TransferHookMsg should be de/serialized under `TransferHook()` variant in a ExecuteMsg.
+/// It is sent to registered hook contracts whenever tokens are transferred, sent or burnt.
+#[cw_serde]
+pub struct Cw20TransferHookMsg {
+    /// The account the tokens were taken from
+    pub sender: String,
+    /// The account receiving the tokens, None if they were burnt
+    pub recipient: Option<String>,
+    pub amount: Uint128,
+}
+
+impl Cw20TransferHookMsg {
+    /// serializes the message
+    pub fn into_binary(self) -> StdResult<Binary> {
+        let msg = HookExecuteMsg::TransferHook(self);
+        to_binary(&msg)
+    }
+
+    /// creates a cosmos_msg sending this struct to the named contract
+    pub fn into_cosmos_msg<T: Into<String>>(self, contract_addr: T) -> StdResult<CosmosMsg> {
+        let msg = self.into_binary()?;
+        let execute = WasmMsg::Execute {
+            contract_addr: contract_addr.into(),
+            msg,
+            funds: vec![],
+        };
+        Ok(execute.into())
+    }
+}
+
+/// Cw20MintCallbackMsg should be de/serialized under `MintCallback()` variant in a ExecuteMsg.
+/// It is sent to the minter after every mint, if it requested so.
+#[cw_serde]
+pub struct Cw20MintCallbackMsg {
+    pub recipient: String,
+    pub amount: Uint128,
+}
+
+impl Cw20MintCallbackMsg {
+    /// serializes the message
+    pub fn into_binary(self) -> StdResult<Binary> {
+        let msg = HookExecuteMsg::MintCallback(self);
+        to_binary(&msg)
+    }
+
+    /// creates a cosmos_msg sending this struct to the named contract
+    pub fn into_cosmos_msg<T: Into<String>>(self, contract_addr: T) -> StdResult<CosmosMsg> {
+        let msg = self.into_binary()?;
+        let execute = WasmMsg::Execute {
+            contract_addr: contract_addr.into(),
+            msg,
+            funds: vec![],
+        };
+        Ok(execute.into())
+    }
+}
+
+// This is just a helper to properly serialize the above messages
+#[cw_serde]
+enum HookExecuteMsg {
+    AllowanceSpent(Cw20AllowanceSpentMsg),
+    TransferHook(Cw20TransferHookMsg),
+    MintCallback(Cw20MintCallbackMsg),
+}
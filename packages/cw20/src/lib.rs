@@ -15,21 +15,25 @@ pub use crate::balance::Balance;
 pub use crate::coin::{Cw20Coin, Cw20CoinVerified};
 pub use crate::denom::{Denom, UncheckedDenom};
 pub use crate::helpers::Cw20Contract;
+pub use crate::hook::{Cw20AllowanceSpentMsg, Cw20MintCallbackMsg, Cw20TransferHookMsg};
 pub use crate::logo::{EmbeddedLogo, Logo, LogoInfo};
 pub use crate::msg::Cw20ExecuteMsg;
+pub use crate::permit::{Cw20Permit, Cw20TransferPermit};
 pub use crate::query::{
     AllAccountsResponse, AllAllowancesResponse, AllSpenderAllowancesResponse, AllowanceInfo,
     AllowanceResponse, BalanceResponse, Cw20QueryMsg, DownloadLogoResponse, MarketingInfoResponse,
-    MinterResponse, SpenderAllowanceInfo, TokenInfoResponse,
+    MinterResponse, NonceResponse, SpenderAllowanceInfo, TokenInfoResponse,
 };
-pub use crate::receiver::Cw20ReceiveMsg;
+pub use crate::receiver::{Cw20ReceiveAck, Cw20ReceiveMsg};
 
 mod balance;
 mod coin;
 mod denom;
 mod helpers;
+mod hook;
 mod logo;
 mod msg;
+mod permit;
 mod query;
 mod receiver;
 
@@ -17,6 +17,13 @@ pub enum Cw20ExecuteMsg {
         amount: Uint128,
         msg: Binary,
     },
+    /// Only with "acked receive" extension. Like Send, but the receiving contract must accept
+    /// the tokens by returning a `Cw20ReceiveAck` as its response data, or the send is reverted.
+    SendAcked {
+        contract: String,
+        amount: Uint128,
+        msg: Binary,
+    },
     /// Only with "approval" extension. Allows spender to access an additional amount tokens
     /// from the owner's (env.sender) account. If expires is Some(), overwrites current allowance
     /// expiration with this one.
@@ -50,6 +57,34 @@ pub enum Cw20ExecuteMsg {
     },
     /// Only with "approval" extension. Destroys tokens forever
     BurnFrom { owner: String, amount: Uint128 },
+    /// Only with "permit" extension. Sets the allowance of spender on the owner's account to
+    /// amount (with no expiration), authorized by the owner's signature of the matching
+    /// `Cw20Permit` instead of `env.sender`. Anyone may submit it before the deadline, and
+    /// nonce must be the owner's current one, which is then incremented.
+    Permit {
+        owner: String,
+        spender: String,
+        amount: Uint128,
+        nonce: u64,
+        deadline: Expiration,
+        /// The owner's compressed secp256k1 public key, which must derive to the owner address
+        pubkey: Binary,
+        signature: Binary,
+    },
+    /// Only with "permit" extension. Transfers amount tokens from owner -> recipient,
+    /// authorized by the owner's signature of the matching `Cw20TransferPermit` instead of
+    /// an allowance. Anyone may submit it before the deadline, and nonce must be the owner's
+    /// current one, which is then incremented.
+    PermitTransferFrom {
+        owner: String,
+        recipient: String,
+        amount: Uint128,
+        nonce: u64,
+        deadline: Expiration,
+        /// The owner's compressed secp256k1 public key, which must derive to the owner address
+        pubkey: Binary,
+        signature: Binary,
+    },
     /// Only with the "mintable" extension. If authorized, creates amount new tokens
     /// and adds to the recipient balance.
     Mint { recipient: String, amount: Uint128 },
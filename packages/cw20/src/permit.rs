@@ -0,0 +1,46 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{to_binary, Binary, StdResult, Uint128};
+use cw_utils::Expiration;
+
+/// Cw20Permit is the payload an owner signs off-chain to grant an allowance via
+/// `Cw20ExecuteMsg::Permit`. It includes the chain id and token contract, so a signed
+/// permit cannot be replayed on another chain or token.
+#[cw_serde]
+pub struct Cw20Permit {
+    pub chain_id: String,
+    pub contract: String,
+    pub owner: String,
+    pub spender: String,
+    pub amount: Uint128,
+    pub nonce: u64,
+    pub deadline: Expiration,
+}
+
+impl Cw20Permit {
+    /// serializes the permit into the bytes to be signed. The signature is a secp256k1
+    /// signature over the sha256 hash of these bytes.
+    pub fn sign_bytes(&self) -> StdResult<Binary> {
+        to_binary(self)
+    }
+}
+
+/// Cw20TransferPermit is the payload an owner signs off-chain to move tokens directly via
+/// `Cw20ExecuteMsg::PermitTransferFrom`, without setting an allowance first. It shares the
+/// owner's nonce with `Cw20Permit`.
+#[cw_serde]
+pub struct Cw20TransferPermit {
+    pub chain_id: String,
+    pub contract: String,
+    pub owner: String,
+    pub recipient: String,
+    pub amount: Uint128,
+    pub nonce: u64,
+    pub deadline: Expiration,
+}
+
+impl Cw20TransferPermit {
+    /// serializes the permit into the bytes to be signed, like `Cw20Permit::sign_bytes`
+    pub fn sign_bytes(&self) -> StdResult<Binary> {
+        to_binary(self)
+    }
+}
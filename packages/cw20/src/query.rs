@@ -20,6 +20,10 @@ pub enum Cw20QueryMsg {
     /// Returns how much spender can use from owner account, 0 if unset.
     /// Return type: AllowanceResponse.
     Allowance { owner: String, spender: String },
+    /// Only with "permit" extension.
+    /// Returns the nonce the next permit signed by owner must use.
+    /// Return type: NonceResponse.
+    Nonce { owner: String },
     /// Only with "mintable" extension.
     /// Returns who can mint and the hard cap on maximum tokens after minting.
     /// Return type: MinterResponse.
@@ -42,6 +46,14 @@ pub enum Cw20QueryMsg {
         start_after: Option<String>,
         limit: Option<u32>,
     },
+    /// Only with "enumerable" extension (and "allowances")
+    /// Returns all allowances this spender has been granted. Supports pagination.
+    /// Return type: AllSpenderAllowancesResponse.
+    AllSpenderAllowances {
+        spender: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
     /// Only with "enumerable" extension
     /// Returns all accounts that have balances. Supports pagination.
     /// Return type: AllAccountsResponse.
@@ -70,6 +82,11 @@ pub struct AllowanceResponse {
     pub expires: Expiration,
 }
 
+#[cw_serde]
+pub struct NonceResponse {
+    pub nonce: u64,
+}
+
 #[cw_serde]
 pub struct MinterResponse {
     pub minter: String,
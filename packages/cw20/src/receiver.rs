@@ -29,6 +29,33 @@ impl Cw20ReceiveMsg {
     }
 }
 
+/// Returned as the response data of `Receive` by contracts supporting the "acked receive"
+/// extension. Tokens sent with `SendAcked` only move if the receiver accepts them.
+#[cw_serde]
+pub enum Cw20ReceiveAck {
+    /// The tokens are accepted. The payload, if any, becomes the response data of `SendAcked`
+    Accepted { payload: Option<Binary> },
+    /// The tokens are refused, reverting the whole send
+    Rejected { reason: String },
+}
+
+impl Cw20ReceiveAck {
+    pub fn accept(payload: Option<Binary>) -> Self {
+        Cw20ReceiveAck::Accepted { payload }
+    }
+
+    pub fn reject<T: Into<String>>(reason: T) -> Self {
+        Cw20ReceiveAck::Rejected {
+            reason: reason.into(),
+        }
+    }
+
+    /// serializes the ack, to be set as the data of the `Receive` response
+    pub fn into_binary(self) -> StdResult<Binary> {
+        to_binary(&self)
+    }
+}
+
 // This is just a helper to properly serialize the above message
 #[cw_serde]
 
@@ -0,0 +1,89 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{to_vec, Binary, Coin, StdResult};
+use cw_utils::Duration;
+use sha2::{Digest, Sha256};
+
+use crate::msg::Vote;
+
+/// PrivateVoting lets voters commit to their vote while proposals are open, and only reveal it
+/// once commitments are closed, so that nobody votes knowing how others did.
+#[cw_serde]
+pub struct PrivateVoting {
+    /// How long votes can be revealed once commitments are closed, with the same unit as the
+    /// voting period. Unrevealed votes are not counted.
+    pub reveal_period: Duration,
+    /// The native tokens, if any, to send along with commitments. They are refunded when the
+    /// vote is revealed, and forfeited to the contract otherwise.
+    pub bond: Option<Coin>,
+}
+
+impl PrivateVoting {
+    /// Whether the reveal period has the unit of `voting_period`, and the bond is not zero
+    pub fn is_valid(&self, voting_period: Duration) -> bool {
+        let same_unit = matches!(
+            (self.reveal_period, voting_period),
+            (Duration::Height(_), Duration::Height(_)) | (Duration::Time(_), Duration::Time(_))
+        );
+        same_unit && self.bond.iter().all(|bond| !bond.amount.is_zero())
+    }
+}
+
+/// The commitment a voter submits for `vote`: the sha256 hash of the proposal id, voter
+/// address, vote and a secret salt. It is revealed by sending the vote and salt.
+pub fn vote_commitment(
+    proposal_id: u64,
+    voter: &str,
+    vote: Vote,
+    salt: &[u8],
+) -> StdResult<Binary> {
+    let mut hasher = Sha256::new();
+    hasher.update(proposal_id.to_be_bytes());
+    hasher.update(voter.as_bytes());
+    hasher.update(to_vec(&vote)?);
+    hasher.update(salt);
+    Ok(Binary::from(hasher.finalize().as_slice()))
+}
+
+#[cw_serde]
+pub struct VoteCommitmentResponse {
+    /// The commitment of the voter, until it is revealed
+    pub commitment: Option<Binary>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use cosmwasm_std::coin;
+
+    #[test]
+    fn commitments_bind_vote() {
+        let commitment = vote_commitment(1, "voter", Vote::Yes, b"salt").unwrap();
+        assert_eq!(commitment.len(), 32);
+        assert_eq!(
+            commitment,
+            vote_commitment(1, "voter", Vote::Yes, b"salt").unwrap()
+        );
+        for other in [
+            vote_commitment(2, "voter", Vote::Yes, b"salt"),
+            vote_commitment(1, "other", Vote::Yes, b"salt"),
+            vote_commitment(1, "voter", Vote::No, b"salt"),
+            vote_commitment(1, "voter", Vote::Yes, b"pepper"),
+        ] {
+            assert_ne!(commitment, other.unwrap());
+        }
+    }
+
+    #[test]
+    fn private_voting_validated() {
+        let private = |reveal_period, bond| PrivateVoting {
+            reveal_period,
+            bond,
+        };
+        let period = Duration::Height(10);
+        assert!(private(Duration::Height(5), None).is_valid(period));
+        assert!(private(Duration::Height(5), Some(coin(1, "TOKEN"))).is_valid(period));
+        assert!(!private(Duration::Time(5), None).is_valid(period));
+        assert!(!private(Duration::Height(5), Some(coin(0, "TOKEN"))).is_valid(period));
+    }
+}
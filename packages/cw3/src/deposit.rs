@@ -3,10 +3,15 @@ use cw_utils::{must_pay, PaymentError};
 use thiserror::Error;
 
 use cosmwasm_std::{
-    to_binary, Addr, BankMsg, Coin, CosmosMsg, Deps, MessageInfo, StdResult, Uint128, WasmMsg,
+    to_binary, Addr, BankMsg, Coin, CosmosMsg, Deps, MessageInfo, QuerierWrapper, StdResult,
+    Uint128, WasmMsg,
 };
 use cw20::{Denom, UncheckedDenom};
 
+fn default_true() -> bool {
+    true
+}
+
 /// Information about the deposit required to create a proposal.
 #[cw_serde]
 pub struct DepositInfo {
@@ -16,6 +21,33 @@ pub struct DepositInfo {
     pub denom: Denom,
     /// Should failed proposals have their deposits refunded?
     pub refund_failed_proposals: bool,
+    /// Should passed proposals have their deposits refunded? Defaults to true.
+    #[serde(default = "default_true")]
+    pub refund_passed_proposals: bool,
+    /// What happens to deposits that are not refunded. If None, they are kept by the contract.
+    #[serde(default)]
+    pub forfeit: Option<DepositForfeit>,
+}
+
+/// Where a deposit that is not refunded goes
+#[cw_serde]
+pub enum DepositForfeit {
+    /// Burned, with a bank burn for native tokens or a cw20 burn
+    Burn {},
+    /// Sent to the given address, e.g. a community pool
+    Send { recipient: Addr },
+}
+
+/// Whether the deposit of a proposal is still held by the contract
+#[cw_serde]
+#[derive(Copy)]
+pub enum DepositStatus {
+    /// Paid with the proposal, waiting for it to be executed or closed
+    Held,
+    /// Returned to the proposer
+    Refunded,
+    /// Not refunded, as set by `DepositInfo::forfeit`
+    Forfeited,
 }
 
 /// Information about the deposit required to create a proposal. For
@@ -29,6 +61,99 @@ pub struct UncheckedDepositInfo {
     pub denom: UncheckedDenom,
     /// Should failed proposals have their deposits refunded?
     pub refund_failed_proposals: bool,
+    /// Should passed proposals have their deposits refunded? Defaults to true.
+    #[serde(default = "default_true")]
+    pub refund_passed_proposals: bool,
+    /// What happens to deposits that are not refunded. If None, they are kept by the contract.
+    #[serde(default)]
+    pub forfeit: Option<UncheckedDepositForfeit>,
+}
+
+/// Where a deposit that is not refunded goes. For use in messages, checked along with
+/// `UncheckedDepositInfo`.
+#[cw_serde]
+pub enum UncheckedDepositForfeit {
+    Burn {},
+    Send { recipient: String },
+}
+
+/// Reward paid to whoever closes a rejected proposal, so that they do not pile up
+#[cw_serde]
+pub enum CloseReward {
+    /// Taken out of the deposit of the proposal (in its denom) before it is refunded or
+    /// forfeited, up to the whole deposit
+    Deposit { amount: Uint128 },
+    /// Paid by the multisig out of its own funds, as long as it holds enough of them
+    Treasury { amount: Coin },
+}
+
+impl CloseReward {
+    /// Checks the reward is not zero, and is taken out of deposits only if they are required
+    pub fn validate(&self, deposit: Option<&DepositInfo>) -> Result<(), DepositError> {
+        let valid = match self {
+            CloseReward::Deposit { amount } => !amount.is_zero() && deposit.is_some(),
+            CloseReward::Treasury { amount } => !amount.amount.is_zero(),
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(DepositError::InvalidCloseReward {})
+        }
+    }
+
+    /// The share of the deposit of each closed proposal paid as reward
+    pub fn deposit_share(&self) -> Uint128 {
+        match self {
+            CloseReward::Deposit { amount } => *amount,
+            CloseReward::Treasury { .. } => Uint128::zero(),
+        }
+    }
+
+    /// Returns the message paying the treasury reward for closing `closed` proposals to
+    /// `closer`, capped by the funds `contract` has left once the `outgoing` messages
+    /// (settling deposits) are executed. None if there is nothing to pay.
+    pub fn get_treasury_reward_message(
+        &self,
+        querier: &QuerierWrapper,
+        contract: &Addr,
+        closer: &Addr,
+        closed: u128,
+        outgoing: &[CosmosMsg],
+    ) -> StdResult<Option<CosmosMsg>> {
+        let reward = match self {
+            CloseReward::Treasury { amount } => amount,
+            CloseReward::Deposit { .. } => return Ok(None),
+        };
+        let spent: Uint128 = outgoing
+            .iter()
+            .flat_map(|msg| match msg {
+                CosmosMsg::Bank(BankMsg::Send { amount, .. } | BankMsg::Burn { amount }) => {
+                    amount.as_slice()
+                }
+                _ => &[],
+            })
+            .filter(|coin| coin.denom == reward.denom)
+            .map(|coin| coin.amount)
+            .sum();
+        let balance = querier.query_balance(contract, &reward.denom)?.amount;
+        let amount = reward
+            .amount
+            .saturating_mul(Uint128::from(closed))
+            .min(balance.saturating_sub(spent));
+        if amount.is_zero() {
+            return Ok(None);
+        }
+        Ok(Some(
+            BankMsg::Send {
+                to_address: closer.to_string(),
+                amount: vec![Coin {
+                    amount,
+                    denom: reward.denom.clone(),
+                }],
+            }
+            .into(),
+        ))
+    }
 }
 
 #[derive(Error, Debug, PartialEq, Eq)]
@@ -44,6 +169,12 @@ pub enum DepositError {
 
     #[error("Invalid native deposit amount")]
     InvalidDeposit {},
+
+    #[error("Invalid deposit forfeit recipient")]
+    InvalidRecipient {},
+
+    #[error("Invalid close reward: it must be non-zero, and can only come from required deposits")]
+    InvalidCloseReward {},
 }
 
 impl UncheckedDepositInfo {
@@ -59,6 +190,19 @@ impl UncheckedDepositInfo {
                     .into_checked(deps)
                     .map_err(|_| DepositError::InvalidCw20 {})?,
                 refund_failed_proposals: self.refund_failed_proposals,
+                refund_passed_proposals: self.refund_passed_proposals,
+                forfeit: match self.forfeit {
+                    Some(UncheckedDepositForfeit::Burn {}) => Some(DepositForfeit::Burn {}),
+                    Some(UncheckedDepositForfeit::Send { recipient }) => {
+                        Some(DepositForfeit::Send {
+                            recipient: deps
+                                .api
+                                .addr_validate(&recipient)
+                                .map_err(|_| DepositError::InvalidRecipient {})?,
+                        })
+                    }
+                    None => None,
+                },
             })
         }
     }
@@ -83,6 +227,24 @@ impl DepositInfo {
         }
     }
 
+    /// Checks the cw20 tokens received along with a proposal, by `Send`, are the deposit
+    pub fn check_cw20_deposit_paid(
+        &self,
+        token: &Addr,
+        amount: Uint128,
+    ) -> Result<(), DepositError> {
+        match &self.denom {
+            Denom::Cw20(address) if address == token => {
+                if amount != self.amount {
+                    Err(DepositError::InvalidDeposit {})
+                } else {
+                    Ok(())
+                }
+            }
+            _ => Err(DepositError::InvalidCw20 {}),
+        }
+    }
+
     pub fn get_take_deposit_messages(
         &self,
         depositor: &Addr,
@@ -138,4 +300,55 @@ impl DepositInfo {
         };
         Ok(message)
     }
+
+    /// Returns the messages burning or sending away a deposit that is not refunded,
+    /// none if the contract keeps it.
+    pub fn get_forfeit_deposit_messages(&self) -> StdResult<Vec<CosmosMsg>> {
+        let message = match (&self.forfeit, &self.denom) {
+            (None, _) => return Ok(vec![]),
+            (Some(DepositForfeit::Send { recipient }), _) => {
+                self.get_return_deposit_message(recipient)?
+            }
+            (Some(DepositForfeit::Burn {}), Denom::Native(denom)) => BankMsg::Burn {
+                amount: vec![Coin {
+                    amount: self.amount,
+                    denom: denom.to_string(),
+                }],
+            }
+            .into(),
+            (Some(DepositForfeit::Burn {}), Denom::Cw20(address)) => WasmMsg::Execute {
+                contract_addr: address.to_string(),
+                msg: to_binary(&cw20::Cw20ExecuteMsg::Burn {
+                    amount: self.amount,
+                })?,
+                funds: vec![],
+            }
+            .into(),
+        };
+        Ok(vec![message])
+    }
+
+    /// Returns whether the deposit of a proposal that passed (or not) is refunded,
+    /// along with the messages refunding it to `depositor` or forfeiting it.
+    pub fn get_settle_deposit_messages(
+        &self,
+        depositor: &Addr,
+        passed: bool,
+    ) -> StdResult<(DepositStatus, Vec<CosmosMsg>)> {
+        let refund = match passed {
+            true => self.refund_passed_proposals,
+            false => self.refund_failed_proposals,
+        };
+        if refund {
+            Ok((
+                DepositStatus::Refunded,
+                vec![self.get_return_deposit_message(depositor)?],
+            ))
+        } else {
+            Ok((
+                DepositStatus::Forfeited,
+                self.get_forfeit_deposit_messages()?,
+            ))
+        }
+    }
 }
@@ -0,0 +1,59 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{to_binary, Binary, CosmosMsg, StdResult, WasmMsg};
+
+use crate::query::Status;
+
+/// ProposalHookMsg should be de/serialized under `ProposalHook()` variant in a ExecuteMsg.
+/// It tells the hook contracts of a step in the lifecycle of a proposal.
+#[cw_serde]
+pub enum ProposalHookMsg {
+    /// A new proposal was created
+    Created { proposal_id: u64, proposer: String },
+    /// The proposal passed (it may still have to wait out a veto window before execution)
+    Passed { proposal_id: u64 },
+    /// The proposal was rejected, by votes or once it expired
+    Rejected { proposal_id: u64 },
+    /// The messages of the proposal were executed
+    Executed { proposal_id: u64 },
+    /// The proposal passed, but was vetoed
+    Vetoed { proposal_id: u64 },
+    /// The proposal was cancelled before it was executed
+    Cancelled { proposal_id: u64 },
+}
+
+impl ProposalHookMsg {
+    /// The message for a proposal whose status changed to `status`, if hooks are told of it
+    pub fn status_changed(proposal_id: u64, status: Status) -> Option<Self> {
+        match status {
+            Status::Passed | Status::VetoTimelock => Some(ProposalHookMsg::Passed { proposal_id }),
+            Status::Rejected => Some(ProposalHookMsg::Rejected { proposal_id }),
+            Status::Executed => Some(ProposalHookMsg::Executed { proposal_id }),
+            Status::Vetoed => Some(ProposalHookMsg::Vetoed { proposal_id }),
+            Status::Cancelled => Some(ProposalHookMsg::Cancelled { proposal_id }),
+            Status::Pending | Status::Open | Status::ExecutionFailed | Status::Queued => None,
+        }
+    }
+
+    /// serializes the message
+    pub fn into_binary(self) -> StdResult<Binary> {
+        let msg = ProposalHookExecuteMsg::ProposalHook(self);
+        to_binary(&msg)
+    }
+
+    /// creates a cosmos_msg sending this struct to the named contract
+    pub fn into_cosmos_msg<T: Into<String>>(self, contract_addr: T) -> StdResult<CosmosMsg> {
+        let msg = self.into_binary()?;
+        let execute = WasmMsg::Execute {
+            contract_addr: contract_addr.into(),
+            msg,
+            funds: vec![],
+        };
+        Ok(execute.into())
+    }
+}
+
+// This is just a helper to properly serialize the above message
+#[cw_serde]
+enum ProposalHookExecuteMsg {
+    ProposalHook(ProposalHookMsg),
+}
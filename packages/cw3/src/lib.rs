@@ -10,17 +10,27 @@ For more information on this specification, please check out the
 */
 
 // mod helpers;
+mod commitment;
 mod deposit;
 mod helpers;
+mod hook;
 mod msg;
+mod permit;
 mod proposal;
 mod query;
 
-pub use crate::deposit::{DepositError, DepositInfo, UncheckedDepositInfo};
+pub use crate::commitment::{vote_commitment, PrivateVoting, VoteCommitmentResponse};
+pub use crate::deposit::{
+    CloseReward, DepositError, DepositForfeit, DepositInfo, DepositStatus, UncheckedDepositForfeit,
+    UncheckedDepositInfo,
+};
 pub use crate::helpers::Cw3Contract;
-pub use crate::msg::{Cw3ExecuteMsg, Vote};
-pub use crate::proposal::{Ballot, Proposal, Votes};
+pub use crate::hook::ProposalHookMsg;
+pub use crate::msg::{is_valid_split_vote, Cw3ExecuteMsg, Vote, WeightedVoteOption};
+pub use crate::permit::VotePermit;
+pub use crate::proposal::{largest_option, Ballot, Proposal, Votes};
 pub use crate::query::{
-    Cw3QueryMsg, ProposalListResponse, ProposalResponse, Status, VoteInfo, VoteListResponse,
-    VoteResponse, VoterDetail, VoterListResponse, VoterResponse,
+    is_valid_options, Cw3QueryMsg, ExecutionWindow, ProposalKind, ProposalListResponse,
+    ProposalMetadata, ProposalOption, ProposalResponse, Status, TallyResponse, VoteInfo,
+    VoteListResponse, VoteResponse, VoterDetail, VoterListResponse, VoterResponse, WinCondition,
 };
@@ -3,7 +3,7 @@ use serde::{Deserialize, Serialize};
 use std::fmt;
 
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{CosmosMsg, Empty};
+use cosmwasm_std::{CosmosMsg, Decimal, Empty};
 use cw_utils::Expiration;
 
 #[cw_serde]
@@ -29,6 +29,10 @@ where
     Close {
         proposal_id: u64,
     },
+    CloseMany {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema, Debug)]
@@ -45,6 +49,28 @@ pub enum Vote {
     Veto,
 }
 
+/// The share of their weight a voter casts for one option, when splitting their vote
+#[cw_serde]
+pub struct WeightedVoteOption {
+    pub vote: Vote,
+    pub weight: Decimal,
+}
+
+/// Whether the options of a split vote are distinct and have non-zero shares adding up to one
+pub fn is_valid_split_vote(options: &[WeightedVoteOption]) -> bool {
+    let mut total = Decimal::zero();
+    for (i, option) in options.iter().enumerate() {
+        if option.weight.is_zero() || options[..i].iter().any(|o| o.vote == option.vote) {
+            return false;
+        }
+        total = match total.checked_add(option.weight) {
+            Ok(total) => total,
+            Err(_) => return false,
+        };
+    }
+    total == Decimal::one()
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -58,6 +84,32 @@ mod test {
         assert_eq!(r#""yes""#, json.as_str());
     }
 
+    #[test]
+    fn split_vote_validation() {
+        let option = |vote, percent| WeightedVoteOption {
+            vote,
+            weight: Decimal::percent(percent),
+        };
+        assert!(is_valid_split_vote(&[option(Vote::Yes, 100)]));
+        assert!(is_valid_split_vote(&[
+            option(Vote::Yes, 60),
+            option(Vote::Abstain, 40)
+        ]));
+        assert!(!is_valid_split_vote(&[]));
+        assert!(!is_valid_split_vote(&[
+            option(Vote::Yes, 60),
+            option(Vote::Abstain, 30)
+        ]));
+        assert!(!is_valid_split_vote(&[
+            option(Vote::Yes, 50),
+            option(Vote::Yes, 50)
+        ]));
+        assert!(!is_valid_split_vote(&[
+            option(Vote::Yes, 100),
+            option(Vote::No, 0)
+        ]));
+    }
+
     #[test]
     fn vote_encoding_embedded() {
         let msg = Cw3ExecuteMsg::Vote::<Empty> {
@@ -0,0 +1,27 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{to_binary, Binary, StdResult};
+use cw_utils::Expiration;
+
+use crate::msg::Vote;
+
+/// VotePermit is the ballot a voter signs off-chain, so that anyone can cast their vote (and pay
+/// the gas for it). It includes the chain id and voting contract, so a signed ballot cannot be
+/// replayed on another chain or contract.
+#[cw_serde]
+pub struct VotePermit {
+    pub chain_id: String,
+    pub contract: String,
+    pub voter: String,
+    pub proposal_id: u64,
+    pub vote: Vote,
+    pub nonce: u64,
+    pub deadline: Expiration,
+}
+
+impl VotePermit {
+    /// serializes the ballot into the bytes to be signed. The signature is a secp256k1
+    /// signature over the sha256 hash of these bytes.
+    pub fn sign_bytes(&self) -> StdResult<Binary> {
+        to_binary(self)
+    }
+}
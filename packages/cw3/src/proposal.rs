@@ -1,8 +1,11 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, BlockInfo, CosmosMsg, Decimal, Empty, Uint128};
+use cosmwasm_std::{Addr, BlockInfo, CosmosMsg, Decimal, Empty, StdResult, Uint128};
 use cw_utils::{Expiration, Threshold};
 
-use crate::{DepositInfo, Status, Vote};
+use crate::{
+    DepositForfeit, DepositInfo, DepositStatus, ExecutionWindow, ProposalKind, ProposalMetadata,
+    ProposalOption, Status, TallyResponse, Vote, WeightedVoteOption, WinCondition,
+};
 
 // we multiply by this when calculating needed_votes in order to round up properly
 // Note: `10u128.pow(9)` fails as "u128::pow` is not yet stable as a const fn"
@@ -27,6 +30,40 @@ pub struct Proposal {
     /// The deposit that was paid along with this proposal. This may
     /// be refunded upon proposal completion.
     pub deposit: Option<DepositInfo>,
+    /// Whether the deposit is still held, set when it is paid.
+    #[serde(default)]
+    pub deposit_status: Option<DepositStatus>,
+    /// Signaling proposals have nothing to execute.
+    #[serde(default)]
+    pub kind: ProposalKind,
+    /// Why dispatching the messages failed, if the last execution did.
+    #[serde(default)]
+    pub execution_error: Option<String>,
+    /// The share of the votes cast (but abstain) that must be yes, on top of the threshold.
+    #[serde(default)]
+    pub cast_threshold: Option<Decimal>,
+    /// When the proposal can be executed once passed, if scheduled.
+    #[serde(default)]
+    pub execution_window: Option<ExecutionWindow>,
+    /// Structured data for governance UIs, if any.
+    #[serde(default)]
+    pub metadata: Option<ProposalMetadata>,
+    /// The options of choice proposals, rather than `msgs`.
+    #[serde(default)]
+    pub options: Vec<ProposalOption>,
+    /// How the winning option of choice proposals is decided.
+    #[serde(default)]
+    pub win_condition: Option<WinCondition>,
+    /// The weight cast for each option of choice proposals.
+    #[serde(default)]
+    pub choice_votes: Vec<u64>,
+    /// The indices of the messages already executed, when executed in several parts.
+    #[serde(default)]
+    pub executed_msgs: Vec<u32>,
+    /// Until when voters commit to their vote, if voting is private. Votes are then revealed
+    /// until the proposal expires.
+    #[serde(default)]
+    pub commit_deadline: Option<Expiration>,
 }
 
 impl Proposal {
@@ -52,53 +89,267 @@ impl Proposal {
         self.status = self.current_status(block);
     }
 
+    /// Refunds or forfeits the deposit still held for this proposal, depending on whether
+    /// it passed, and returns the messages doing so. Does nothing if the deposit was settled.
+    pub fn settle_deposit(&mut self, passed: bool) -> StdResult<Vec<CosmosMsg>> {
+        match self.held_deposit() {
+            Some(deposit) => {
+                let (status, msgs) = deposit.get_settle_deposit_messages(&self.proposer, passed)?;
+                self.deposit_status = Some(status);
+                Ok(msgs)
+            }
+            None => Ok(vec![]),
+        }
+    }
+
+    /// Forfeits the deposit still held for this proposal as given, whatever the rules of the
+    /// deposit (eg. burning the deposit of a vetoed proposal), and returns the messages doing so.
+    pub fn forfeit_deposit(&mut self, forfeit: DepositForfeit) -> StdResult<Vec<CosmosMsg>> {
+        match self.held_deposit() {
+            Some(deposit) => {
+                let deposit = DepositInfo {
+                    forfeit: Some(forfeit),
+                    ..deposit.clone()
+                };
+                self.deposit_status = Some(DepositStatus::Forfeited);
+                deposit.get_forfeit_deposit_messages()
+            }
+            None => Ok(vec![]),
+        }
+    }
+
+    /// Settles the deposit still held for this rejected proposal like `settle_deposit`, after
+    /// paying up to `reward` out of it to `closer`, and returns the messages doing so.
+    pub fn settle_deposit_with_reward(
+        &mut self,
+        closer: &Addr,
+        reward: Uint128,
+    ) -> StdResult<Vec<CosmosMsg>> {
+        let deposit = match self.held_deposit() {
+            Some(deposit) if !reward.is_zero() => deposit.clone(),
+            _ => return self.settle_deposit(false),
+        };
+        let reward = reward.min(deposit.amount);
+        let rest = DepositInfo {
+            amount: deposit.amount - reward,
+            ..deposit.clone()
+        };
+        let reward = DepositInfo {
+            amount: reward,
+            ..deposit
+        };
+        let mut msgs = vec![reward.get_return_deposit_message(closer)?];
+        if rest.amount.is_zero() {
+            self.deposit_status = Some(DepositStatus::Forfeited);
+        } else {
+            let (status, rest_msgs) = rest.get_settle_deposit_messages(&self.proposer, false)?;
+            self.deposit_status = Some(status);
+            msgs.extend(rest_msgs);
+        }
+        Ok(msgs)
+    }
+
+    /// Refunds or forfeits the deposit of a signaling proposal as soon as it passed, since it
+    /// will not be executed, and returns the messages doing so. Does nothing otherwise.
+    pub fn conclude_signaling(&mut self, block: &BlockInfo) -> StdResult<Vec<CosmosMsg>> {
+        if self.kind == ProposalKind::Signaling && self.current_status(block) == Status::Passed {
+            self.status = Status::Passed;
+            self.settle_deposit(true)
+        } else {
+            Ok(vec![])
+        }
+    }
+
+    /// Whether voters can still commit to their vote, rather than reveal it
+    pub fn is_committing(&self, block: &BlockInfo) -> bool {
+        matches!(&self.commit_deadline, Some(deadline) if !deadline.is_expired(block))
+    }
+
+    /// Whether anybody voted against the proposal, or for any option of a choice proposal
+    pub fn has_opposing_votes(&self) -> bool {
+        match self.kind {
+            ProposalKind::Choice => self.choice_votes.iter().any(|weight| *weight > 0),
+            _ => self.votes.no > 0 || self.votes.veto > 0,
+        }
+    }
+
+    /// The messages to execute once passed: those of the winning option for choice proposals
+    pub fn msgs_to_execute(&self) -> Vec<CosmosMsg> {
+        match self.kind {
+            ProposalKind::Choice => self
+                .winning_option()
+                .map(|winner| self.options[winner].msgs.clone())
+                .unwrap_or_default(),
+            _ => self.msgs.clone(),
+        }
+    }
+
+    /// The indices of the messages to execute next: those given if they are ascending, and of
+    /// messages not executed yet, or else all the messages not executed yet. None if invalid.
+    pub fn msg_indices_to_execute(&self, msg_indices: Option<Vec<u32>>) -> Option<Vec<u32>> {
+        let count = self.msgs_to_execute().len() as u32;
+        match msg_indices {
+            None => Some(
+                (0..count)
+                    .filter(|i| !self.executed_msgs.contains(i))
+                    .collect(),
+            ),
+            Some(indices) => {
+                let valid = !indices.is_empty()
+                    && indices.windows(2).all(|pair| pair[0] < pair[1])
+                    && indices
+                        .iter()
+                        .all(|i| *i < count && !self.executed_msgs.contains(i));
+                valid.then_some(indices)
+            }
+        }
+    }
+
+    /// Whether all the messages to execute were, possibly in several parts
+    pub fn all_msgs_executed(&self) -> bool {
+        self.executed_msgs.len() == self.msgs_to_execute().len()
+    }
+
+    /// The option of a choice proposal with more weight than any other, if any
+    pub fn winning_option(&self) -> Option<usize> {
+        let most = *self.choice_votes.iter().max()?;
+        let mut leaders = self
+            .choice_votes
+            .iter()
+            .enumerate()
+            .filter(|(_, weight)| **weight == most);
+        match (leaders.next(), leaders.next()) {
+            (Some((winner, _)), None) if most > 0 => Some(winner),
+            _ => None,
+        }
+    }
+
+    /// Weight cast on the proposal, by option for choice proposals
+    fn cast(&self) -> u64 {
+        self.votes.total() + self.choice_votes.iter().sum::<u64>()
+    }
+
+    /// The votes for an option of a choice proposal, counting the other options as "no"
+    fn option_votes(&self, option: usize) -> Votes {
+        let yes = self.choice_votes[option];
+        Votes {
+            yes,
+            no: self.cast() - yes,
+            abstain: 0,
+            veto: 0,
+        }
+    }
+
+    /// The tally of the votes, with the given current status of the proposal
+    pub fn tally(&self, status: Status) -> TallyResponse {
+        let turnout = if self.total_weight == 0 {
+            Decimal::zero()
+        } else {
+            Decimal::from_ratio(self.cast(), self.total_weight)
+        };
+        TallyResponse {
+            votes: self.votes.clone(),
+            turnout,
+            threshold: self.threshold.to_response(self.total_weight),
+            cast_threshold: self.cast_threshold,
+            status,
+            choice_votes: self.choice_votes.clone(),
+        }
+    }
+
+    fn held_deposit(&self) -> Option<&DepositInfo> {
+        match (&self.deposit, self.deposit_status) {
+            (Some(deposit), None | Some(DepositStatus::Held)) => Some(deposit),
+            _ => None,
+        }
+    }
+
     /// Returns true if this proposal is sure to pass (even before expiration, if no future
     /// sequence of possible votes could cause it to fail).
     pub fn is_passed(&self, block: &BlockInfo) -> bool {
-        match self.threshold {
+        let (condition, winner) = match (&self.win_condition, self.winning_option()) {
+            (None, _) => return self.votes_pass(&self.votes, block),
+            (Some(condition), Some(winner)) => (condition, winner),
+            (Some(_), None) => return false,
+        };
+        match condition {
+            WinCondition::Threshold {} => self.votes_pass(&self.option_votes(winner), block),
+            WinCondition::Plurality { quorum } => {
+                let cast = self.cast();
+                if cast < votes_needed(self.total_weight, *quorum) {
+                    return false;
+                }
+                // until expiration, the weight that did not vote yet may go to the runner-up
+                let runner_up = (0..self.choice_votes.len())
+                    .filter(|option| *option != winner)
+                    .map(|option| self.choice_votes[option])
+                    .max()
+                    .unwrap_or_default();
+                self.expires.is_expired(block)
+                    || self.choice_votes[winner] - runner_up
+                        > self.total_weight.saturating_sub(cast)
+            }
+        }
+    }
+
+    fn votes_pass(&self, votes: &Votes, block: &BlockInfo) -> bool {
+        let passed = match self.threshold {
             Threshold::AbsoluteCount {
                 weight: weight_needed,
-            } => self.votes.yes >= weight_needed,
+            } => votes.yes >= weight_needed,
             Threshold::AbsolutePercentage {
                 percentage: percentage_needed,
-            } => {
-                self.votes.yes
-                    >= votes_needed(self.total_weight - self.votes.abstain, percentage_needed)
-            }
+            } => votes.yes >= votes_needed(self.total_weight - votes.abstain, percentage_needed),
             Threshold::ThresholdQuorum { threshold, quorum } => {
                 // we always require the quorum
-                if self.votes.total() < votes_needed(self.total_weight, quorum) {
+                if votes.total() < votes_needed(self.total_weight, quorum) {
                     return false;
                 }
                 if self.expires.is_expired(block) {
                     // If expired, we compare vote_count against the total number of votes (minus abstain).
-                    let opinions = self.votes.total() - self.votes.abstain;
-                    self.votes.yes >= votes_needed(opinions, threshold)
+                    let opinions = votes.total() - votes.abstain;
+                    votes.yes >= votes_needed(opinions, threshold)
                 } else {
                     // If not expired, we must assume all non-votes will be cast against
-                    let possible_opinions = self.total_weight - self.votes.abstain;
-                    self.votes.yes >= votes_needed(possible_opinions, threshold)
+                    let possible_opinions = self.total_weight - votes.abstain;
+                    votes.yes >= votes_needed(possible_opinions, threshold)
                 }
             }
+        };
+        match self.cast_threshold {
+            Some(threshold) => {
+                passed && votes.yes >= votes_needed(self.opinions(votes, block), threshold)
+            }
+            None => passed,
         }
     }
 
     /// Returns true if this proposal is sure to be rejected (even before expiration, if
     /// no future sequence of possible votes could cause it to pass).
     pub fn is_rejected(&self, block: &BlockInfo) -> bool {
-        match self.threshold {
+        match &self.win_condition {
+            None => self.votes_reject(&self.votes, block),
+            Some(WinCondition::Threshold {}) => (0..self.options.len())
+                .all(|option| self.votes_reject(&self.option_votes(option), block)),
+            // only once expired without a winner
+            Some(WinCondition::Plurality { .. }) => false,
+        }
+    }
+
+    fn votes_reject(&self, votes: &Votes, block: &BlockInfo) -> bool {
+        let rejected = match self.threshold {
             Threshold::AbsoluteCount {
                 weight: weight_needed,
             } => {
                 let weight = self.total_weight - weight_needed;
-                self.votes.no > weight
+                votes.no > weight
             }
             Threshold::AbsolutePercentage {
                 percentage: percentage_needed,
             } => {
-                self.votes.no
+                votes.no
                     > votes_needed(
-                        self.total_weight - self.votes.abstain,
+                        self.total_weight - votes.abstain,
                         Decimal::one() - percentage_needed,
                     )
             }
@@ -108,20 +359,39 @@ impl Proposal {
             } => {
                 if self.expires.is_expired(block) {
                     // If expired, we compare vote_count against the total number of votes (minus abstain).
-                    let opinions = self.votes.total() - self.votes.abstain;
-                    self.votes.no > votes_needed(opinions, Decimal::one() - threshold)
+                    let opinions = votes.total() - votes.abstain;
+                    votes.no > votes_needed(opinions, Decimal::one() - threshold)
                 } else {
                     // If not expired, we must assume all non-votes will be cast for
-                    let possible_opinions = self.total_weight - self.votes.abstain;
-                    self.votes.no > votes_needed(possible_opinions, Decimal::one() - threshold)
+                    let possible_opinions = self.total_weight - votes.abstain;
+                    votes.no > votes_needed(possible_opinions, Decimal::one() - threshold)
                 }
             }
+        };
+        match self.cast_threshold {
+            Some(threshold) => {
+                rejected
+                    || votes.no
+                        > votes_needed(self.opinions(votes, block), Decimal::one() - threshold)
+            }
+            None => rejected,
+        }
+    }
+
+    /// Weight of the votes (minus abstain) the cast threshold applies to. Until expiration, all
+    /// the weight that did not vote yet may still be cast.
+    fn opinions(&self, votes: &Votes, block: &BlockInfo) -> u64 {
+        if self.expires.is_expired(block) {
+            votes.total() - votes.abstain
+        } else {
+            self.total_weight - votes.abstain
         }
     }
 }
 
 // weight of votes for each option
 #[cw_serde]
+#[derive(Default)]
 pub struct Votes {
     pub yes: u64,
     pub no: u64,
@@ -153,6 +423,45 @@ impl Votes {
             Vote::Veto => self.veto += weight,
         }
     }
+
+    /// Adds the weight of a voter split across options, as given by their shares (expected to be
+    /// valid, see `is_valid_split_vote`). What is lost when rounding down goes to the largest
+    /// share, so the whole weight is counted.
+    pub fn add_split_vote(&mut self, options: &[WeightedVoteOption], weight: u64) {
+        let mut left = weight;
+        for option in options {
+            let share = (option.weight * Uint128::from(weight)).u128() as u64;
+            self.add_vote(option.vote, share);
+            left -= share;
+        }
+        if let Some(largest) = largest_option(options) {
+            self.add_vote(largest, left);
+        }
+    }
+
+    /// Takes back what a ballot added to the tally, eg. when the voter changes their vote
+    pub fn remove_ballot(&mut self, ballot: &Ballot) {
+        let mut cast = Votes::default();
+        match &ballot.split {
+            Some(options) => cast.add_split_vote(options, ballot.weight),
+            None => cast.add_vote(ballot.vote, ballot.weight),
+        }
+        self.yes -= cast.yes;
+        self.no -= cast.no;
+        self.abstain -= cast.abstain;
+        self.veto -= cast.veto;
+    }
+}
+
+/// The option with the largest share of a split vote (the first of them, if several)
+pub fn largest_option(options: &[WeightedVoteOption]) -> Option<Vote> {
+    options
+        .iter()
+        .reduce(|largest, option| match option.weight > largest.weight {
+            true => option,
+            false => largest,
+        })
+        .map(|option| option.vote)
 }
 
 // this is a helper function so Decimal works with u64 rather than Uint128
@@ -168,13 +477,21 @@ fn votes_needed(weight: u64, percentage: Decimal) -> u64 {
 #[cw_serde]
 pub struct Ballot {
     pub weight: u64,
+    /// The vote, or the option with the largest share of a split vote
     pub vote: Vote,
+    /// The shares of the weight cast for each option, for split votes
+    #[serde(default)]
+    pub split: Option<Vec<WeightedVoteOption>>,
+    /// The option picked, for choice proposals
+    #[serde(default)]
+    pub choice: Option<u32>,
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
     use cosmwasm_std::testing::mock_env;
+    use cosmwasm_std::BankMsg;
 
     #[test]
     fn count_votes() {
@@ -191,6 +508,61 @@ mod test {
         assert_eq!(votes.abstain, 40);
     }
 
+    #[test]
+    fn count_split_votes() {
+        let option = |vote, percent| WeightedVoteOption {
+            vote,
+            weight: Decimal::percent(percent),
+        };
+        let mut votes = Votes::yes(0);
+        votes.add_split_vote(&[option(Vote::Yes, 60), option(Vote::Abstain, 40)], 10);
+        assert_eq!((votes.yes, votes.abstain), (6, 4));
+
+        // leftovers of rounding go to the largest share
+        let options = [
+            option(Vote::No, 30),
+            option(Vote::Veto, 30),
+            option(Vote::Abstain, 40),
+        ];
+        votes.add_split_vote(&options, 7);
+        assert_eq!(votes.total(), 17);
+        assert_eq!((votes.no, votes.veto, votes.abstain), (2, 2, 7));
+        assert_eq!(largest_option(&options), Some(Vote::Abstain));
+    }
+
+    #[test]
+    fn remove_ballots() {
+        let mut votes = Votes::yes(5);
+        votes.add_vote(Vote::No, 10);
+        let split = vec![
+            WeightedVoteOption {
+                vote: Vote::No,
+                weight: Decimal::percent(30),
+            },
+            WeightedVoteOption {
+                vote: Vote::Veto,
+                weight: Decimal::percent(70),
+            },
+        ];
+        votes.add_split_vote(&split, 7);
+
+        // removing a split ballot takes back the same rounding
+        votes.remove_ballot(&Ballot {
+            weight: 7,
+            vote: Vote::Veto,
+            split: Some(split),
+            choice: None,
+        });
+        assert_eq!((votes.yes, votes.no, votes.veto), (5, 10, 0));
+        votes.remove_ballot(&Ballot {
+            weight: 10,
+            vote: Vote::No,
+            split: None,
+            choice: None,
+        });
+        assert_eq!(votes, Votes::yes(5));
+    }
+
     #[test]
     // we ensure this rounds up (as it calculates needed votes)
     fn votes_needed_rounds_properly() {
@@ -225,9 +597,20 @@ mod test {
             status: Status::Open,
             proposer: Addr::unchecked("Proposer"),
             deposit: None,
+            deposit_status: None,
+            kind: ProposalKind::Action,
+            execution_error: None,
+            cast_threshold: None,
+            execution_window: None,
             threshold,
             total_weight,
             votes,
+            metadata: None,
+            options: vec![],
+            win_condition: None,
+            choice_votes: vec![],
+            executed_msgs: vec![],
+            commit_deadline: None,
         };
 
         (prop, block)
@@ -267,6 +650,30 @@ mod test {
         assert!(check_is_passed(fixed, votes, 30, true));
     }
 
+    #[test]
+    fn proposal_with_cast_threshold() {
+        // at least 10 yes, and 60% of the votes cast
+        let fixed = Threshold::AbsoluteCount { weight: 10 };
+        let check = |votes: &Votes, is_expired| {
+            let (mut prop, block) = setup_prop(fixed.clone(), votes.clone(), 30, is_expired);
+            prop.cast_threshold = Some(Decimal::percent(60));
+            (prop.is_passed(&block), prop.is_rejected(&block))
+        };
+        let mut votes = Votes::yes(12);
+        votes.add_vote(Vote::No, 10);
+        votes.add_vote(Vote::Abstain, 5);
+        // enough yes weight, but only 12 of the 22 opinions
+        assert_eq!(check(&votes, true), (false, true));
+        // until expiration, 15 of the possible 25 opinions would do
+        assert_eq!(check(&votes, false), (false, false));
+        votes.add_vote(Vote::Yes, 3);
+        assert_eq!(check(&votes, false), (true, false));
+
+        // the share of the votes cast is not enough on its own
+        let votes = Votes::yes(8);
+        assert_eq!(check(&votes, true), (false, false));
+    }
+
     #[test]
     fn proposal_rejected_absolute_count() {
         let fixed = Threshold::AbsoluteCount { weight: 10 };
@@ -574,4 +981,120 @@ mod test {
         ));
         assert!(check_is_passed(quorum, passes_early, 15, true));
     }
+
+    fn setup_choice_prop(
+        win_condition: WinCondition,
+        choice_votes: Vec<u64>,
+        is_expired: bool,
+    ) -> (Proposal, BlockInfo) {
+        let threshold = Threshold::AbsolutePercentage {
+            percentage: Decimal::percent(50),
+        };
+        let (mut prop, block) = setup_prop(threshold, Votes::default(), 20, is_expired);
+        prop.kind = ProposalKind::Choice;
+        prop.options = (0..choice_votes.len())
+            .map(|i| ProposalOption {
+                label: format!("option {}", i),
+                msgs: vec![],
+            })
+            .collect();
+        prop.win_condition = Some(win_condition);
+        prop.choice_votes = choice_votes;
+        (prop, block)
+    }
+
+    #[test]
+    fn choice_threshold() {
+        let check = |choice_votes: Vec<u64>, is_expired| {
+            let (prop, block) =
+                setup_choice_prop(WinCondition::Threshold {}, choice_votes, is_expired);
+            (
+                prop.is_passed(&block),
+                prop.is_rejected(&block),
+                prop.winning_option(),
+            )
+        };
+
+        // the winner needs half the total weight
+        assert_eq!(check(vec![9, 6, 0], true), (false, false, Some(0)));
+        assert_eq!(check(vec![4, 10, 6], false), (true, false, Some(1)));
+        // no option can reach it anymore
+        assert_eq!(check(vec![9, 9, 2], false), (false, true, None));
+        // ties never pass
+        assert_eq!(check(vec![0, 0, 0], true), (false, false, None));
+    }
+
+    #[test]
+    fn choice_plurality() {
+        let plurality = WinCondition::Plurality {
+            quorum: Decimal::percent(40),
+        };
+        let check = |choice_votes: Vec<u64>, is_expired| {
+            let (prop, block) = setup_choice_prop(plurality.clone(), choice_votes, is_expired);
+            prop.is_passed(&block)
+        };
+
+        // below quorum
+        assert!(!check(vec![7, 0, 0], true));
+        // the runner-up may still catch up until expiration
+        assert!(!check(vec![5, 3, 0], false));
+        assert!(check(vec![5, 3, 0], true));
+        // unless the lead is larger than the weight still to vote
+        assert!(check(vec![12, 2, 1], false));
+        // ties never pass
+        assert!(!check(vec![4, 4, 1], true));
+
+        // never rejected before expiration, only closed without a winner
+        let (prop, block) = setup_choice_prop(plurality, vec![4, 4, 1], true);
+        assert!(!prop.is_rejected(&block));
+        assert_eq!(prop.current_status(&block), Status::Rejected);
+    }
+
+    #[test]
+    fn choice_msgs_to_execute() {
+        let (mut prop, _) = setup_choice_prop(WinCondition::Threshold {}, vec![2, 11], false);
+        let msg: CosmosMsg = BankMsg::Burn {
+            amount: cosmwasm_std::coins(1, "ucosm"),
+        }
+        .into();
+        prop.msgs = vec![msg.clone(), msg.clone()];
+        prop.options[1].msgs = vec![msg.clone()];
+        assert_eq!(prop.msgs_to_execute(), vec![msg]);
+
+        prop.choice_votes = vec![3, 3];
+        assert_eq!(prop.msgs_to_execute(), vec![]);
+    }
+
+    #[test]
+    fn msg_indices_to_execute() {
+        let (mut prop, _) = setup_prop(
+            Threshold::AbsoluteCount { weight: 1 },
+            Votes::yes(1),
+            1,
+            false,
+        );
+        let msg: CosmosMsg = BankMsg::Burn {
+            amount: cosmwasm_std::coins(1, "ucosm"),
+        }
+        .into();
+        prop.msgs = vec![msg; 3];
+        assert_eq!(prop.msg_indices_to_execute(None), Some(vec![0, 1, 2]));
+        assert_eq!(
+            prop.msg_indices_to_execute(Some(vec![0, 2])),
+            Some(vec![0, 2])
+        );
+        assert_eq!(prop.msg_indices_to_execute(Some(vec![])), None);
+        assert_eq!(prop.msg_indices_to_execute(Some(vec![2, 0])), None);
+        assert_eq!(prop.msg_indices_to_execute(Some(vec![1, 1])), None);
+        assert_eq!(prop.msg_indices_to_execute(Some(vec![3])), None);
+        assert!(!prop.all_msgs_executed());
+
+        prop.executed_msgs = vec![1];
+        assert_eq!(prop.msg_indices_to_execute(None), Some(vec![0, 2]));
+        assert_eq!(prop.msg_indices_to_execute(Some(vec![0, 1])), None);
+
+        prop.executed_msgs = vec![0, 1, 2];
+        assert_eq!(prop.msg_indices_to_execute(None), Some(vec![]));
+        assert!(prop.all_msgs_executed());
+    }
 }
@@ -1,8 +1,10 @@
+use std::cmp::Ordering;
+
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, CosmosMsg, Empty};
+use cosmwasm_std::{Addr, BlockInfo, CosmosMsg, Decimal, Empty};
 use cw_utils::{Expiration, ThresholdResponse};
 
-use crate::{msg::Vote, DepositInfo};
+use crate::{msg::Vote, msg::WeightedVoteOption, DepositInfo, DepositStatus, Votes};
 
 #[cw_serde]
 pub enum Cw3QueryMsg {
@@ -24,6 +26,9 @@ pub enum Cw3QueryMsg {
         start_before: Option<u64>,
         limit: Option<u32>,
     },
+    /// Returns the weight cast for each option on `proposal_id`, along with the turnout and
+    /// the threshold applied to it. Returns TallyResponse
+    ProposalTally { proposal_id: u64 },
     /// Query the vote made by the given voter on `proposal_id`. This should
     /// return an error if there is no such proposal. It will return a None value
     /// if the proposal exists but the voter did not vote. Returns VoteResponse
@@ -64,6 +69,171 @@ pub struct ProposalResponse<T = Empty> {
     pub threshold: ThresholdResponse,
     pub proposer: Addr,
     pub deposit: Option<DepositInfo>,
+    /// Whether the deposit is still held, or was refunded or forfeited.
+    #[serde(default)]
+    pub deposit_status: Option<DepositStatus>,
+    #[serde(default)]
+    pub kind: ProposalKind,
+    /// The error returned by the proposal messages, if their last execution failed.
+    #[serde(default)]
+    pub execution_error: Option<String>,
+    /// The share of the votes cast (but abstain) that must be yes, on top of the threshold.
+    #[serde(default)]
+    pub cast_threshold: Option<Decimal>,
+    /// When the proposal can be executed once passed, if scheduled.
+    #[serde(default)]
+    pub execution_window: Option<ExecutionWindow>,
+    /// Structured data for governance UIs, if any.
+    #[serde(default)]
+    pub metadata: Option<ProposalMetadata>,
+    /// The options of choice proposals, rather than `msgs`.
+    #[serde(default)]
+    pub options: Vec<ProposalOption>,
+    /// How the winning option of choice proposals is decided.
+    #[serde(default)]
+    pub win_condition: Option<WinCondition>,
+    /// The indices of the messages already executed, when executed in several parts.
+    #[serde(default)]
+    pub executed_msgs: Vec<u32>,
+    /// Until when voters commit to their vote, if voting is private. Votes are then revealed
+    /// until the proposal expires.
+    #[serde(default)]
+    pub commit_deadline: Option<Expiration>,
+}
+
+/// How a proposal is faring, so that clients do not have to apply the threshold themselves
+#[cw_serde]
+pub struct TallyResponse {
+    /// The weight cast for each option
+    pub votes: Votes,
+    /// The share of `total_weight` that voted
+    pub turnout: Decimal,
+    /// The threshold applied to the proposal, with the total weight when it was created
+    pub threshold: ThresholdResponse,
+    /// The share of the votes cast (but abstain) that must be yes, on top of the threshold
+    #[serde(default)]
+    pub cast_threshold: Option<Decimal>,
+    pub status: Status,
+    /// The weight cast for each option of choice proposals
+    #[serde(default)]
+    pub choice_votes: Vec<u64>,
+}
+
+/// When a passed proposal can be executed: once `start` expired, until `end` does
+#[cw_serde]
+pub struct ExecutionWindow {
+    pub start: Expiration,
+    pub end: Expiration,
+}
+
+impl ExecutionWindow {
+    /// The window must start before it ends, both at heights or both at times (or never ending)
+    pub fn is_valid(&self) -> bool {
+        self.start.partial_cmp(&self.end) == Some(Ordering::Less)
+    }
+
+    pub fn is_open(&self, block: &BlockInfo) -> bool {
+        self.start.is_expired(block) && !self.end.is_expired(block)
+    }
+
+    /// Whether the proposal can no longer be executed
+    pub fn is_over(&self, block: &BlockInfo) -> bool {
+        self.end.is_expired(block)
+    }
+}
+
+pub const MAX_OPTIONS: usize = 16;
+pub const MAX_OPTION_LABEL_LENGTH: usize = 64;
+
+/// One of the outcomes of a choice proposal, whose messages are executed if it wins
+#[cw_serde]
+pub struct ProposalOption<T = Empty> {
+    pub label: String,
+    pub msgs: Vec<CosmosMsg<T>>,
+}
+
+/// Whether there are 2 to `MAX_OPTIONS` options, with distinct labels neither empty nor too long
+pub fn is_valid_options<T>(options: &[ProposalOption<T>]) -> bool {
+    (2..=MAX_OPTIONS).contains(&options.len())
+        && options.iter().enumerate().all(|(i, option)| {
+            !option.label.is_empty()
+                && option.label.len() <= MAX_OPTION_LABEL_LENGTH
+                && options[..i].iter().all(|o| o.label != option.label)
+        })
+}
+
+/// How the winning option of a choice proposal is decided. Either way, it must have more weight
+/// than any other option
+#[cw_serde]
+pub enum WinCondition {
+    /// It wins once its weight passes the threshold of the proposal, the weight cast for the
+    /// other options counting as "no"
+    Threshold {},
+    /// It wins once no other option can catch up with it (or at expiration), as long as the
+    /// weight cast reaches the `quorum` share of the total weight
+    Plurality { quorum: Decimal },
+}
+
+impl WinCondition {
+    pub fn is_valid(&self) -> bool {
+        match self {
+            WinCondition::Threshold {} => true,
+            WinCondition::Plurality { quorum } => *quorum <= Decimal::one(),
+        }
+    }
+}
+
+pub const MAX_LINK_LENGTH: usize = 256;
+pub const MAX_IPFS_HASH_LENGTH: usize = 128;
+pub const MAX_TAGS: usize = 8;
+pub const MAX_TAG_LENGTH: usize = 32;
+
+/// Structured data about a proposal, rather than crammed into its description
+#[cw_serde]
+#[derive(Default)]
+pub struct ProposalMetadata {
+    /// Where the proposal is discussed, eg. a forum thread
+    pub link: Option<String>,
+    /// Of a document detailing the proposal
+    pub ipfs_hash: Option<String>,
+    pub tags: Vec<String>,
+}
+
+impl ProposalMetadata {
+    /// Fields must not be empty nor too long, the link have no whitespace, the IPFS hash be
+    /// alphanumeric, and tags be distinct and not too many
+    pub fn is_valid(&self) -> bool {
+        let valid_link = self.link.iter().all(|link| {
+            !link.is_empty()
+                && link.len() <= MAX_LINK_LENGTH
+                && !link.chars().any(char::is_whitespace)
+        });
+        let valid_hash = self.ipfs_hash.iter().all(|hash| {
+            !hash.is_empty()
+                && hash.len() <= MAX_IPFS_HASH_LENGTH
+                && hash.chars().all(|c| c.is_ascii_alphanumeric())
+        });
+        let valid_tags = self.tags.len() <= MAX_TAGS
+            && self.tags.iter().enumerate().all(|(i, tag)| {
+                !tag.is_empty() && tag.len() <= MAX_TAG_LENGTH && !self.tags[..i].contains(tag)
+            });
+        valid_link && valid_hash && valid_tags
+    }
+}
+
+#[cw_serde]
+#[derive(Copy, Default)]
+pub enum ProposalKind {
+    /// executes its messages once passed
+    #[default]
+    Action,
+    /// only records a decision, it has no messages and passing is final
+    Signaling,
+    /// executes its messages once passed, proposed by an emergency council for a shorter
+    /// voting period and usually a higher threshold
+    Emergency,
+    /// voted on by option, executing the messages of the option that won once passed
+    Choice,
 }
 
 #[cw_serde]
@@ -80,6 +250,16 @@ pub enum Status {
     Passed = 4,
     /// voting is over it passed, and the proposal was executed
     Executed = 5,
+    /// voting is over and it did pass, but it can still be vetoed before it is executed
+    VetoTimelock = 6,
+    /// the proposal passed, but was vetoed before it was executed
+    Vetoed = 7,
+    /// the proposal passed, but its messages failed when executed; it can be executed again or closed
+    ExecutionFailed = 8,
+    /// the proposal passed, and its messages were queued in a timelock contract executing them
+    Queued = 9,
+    /// the proposal was cancelled, by its proposer or an admin, before it was executed
+    Cancelled = 10,
 }
 
 #[cw_serde]
@@ -98,8 +278,15 @@ pub struct VoteListResponse {
 pub struct VoteInfo {
     pub proposal_id: u64,
     pub voter: String,
+    /// The vote, or the option with the largest share of a split vote
     pub vote: Vote,
     pub weight: u64,
+    /// The shares of the weight cast for each option, for split votes
+    #[serde(default)]
+    pub split: Option<Vec<WeightedVoteOption>>,
+    /// The option picked, for choice proposals
+    #[serde(default)]
+    pub choice: Option<u32>,
 }
 
 #[cw_serde]
@@ -5,7 +5,7 @@ use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
 pub use cw4::{
     AdminResponse, Cw4ExecuteMsg, Cw4QueryMsg, Member, MemberChangedHookMsg, MemberListResponse,
-    MemberResponse, TotalWeightResponse,
+    MemberResponse, TotalWeightResponse, WeightDecimalsResponse,
 };
 
 fn main() {
@@ -20,5 +20,6 @@ fn main() {
     export_schema(&schema_for!(MemberListResponse), &out_dir);
     export_schema(&schema_for!(MemberResponse), &out_dir);
     export_schema(&schema_for!(TotalWeightResponse), &out_dir);
+    export_schema(&schema_for!(WeightDecimalsResponse), &out_dir);
     export_schema(&schema_for!(MemberChangedHookMsg), &out_dir);
 }
@@ -0,0 +1,96 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Decimal, StdError, StdResult};
+
+use crate::Member;
+
+/// Weights are stored as integers, a group with the "decimal weights" extension
+/// interprets them as fixed-point numbers with a given number of decimal places.
+/// A weight of 1500 with 3 decimals is 1.5. This may never exceed 18, the precision of `Decimal`.
+pub const MAX_WEIGHT_DECIMALS: u32 = 18;
+
+/// A group member with a fractional weight, for groups with the "decimal weights" extension
+#[cw_serde]
+pub struct DecimalMember {
+    pub addr: String,
+    pub weight: Decimal,
+}
+
+impl DecimalMember {
+    /// Converts into a member with the integer weight stored by a group with `decimals` decimals.
+    /// Fails if the weight is more precise than that, rather than rounding it.
+    pub fn to_member(&self, decimals: u32) -> StdResult<Member> {
+        Ok(Member {
+            addr: self.addr.clone(),
+            weight: decimal_to_weight(self.weight, decimals)?,
+        })
+    }
+}
+
+impl Member {
+    /// Converts the integer weight of a group with `decimals` decimals into a fractional one
+    pub fn to_decimal(&self, decimals: u32) -> StdResult<DecimalMember> {
+        Ok(DecimalMember {
+            addr: self.addr.clone(),
+            weight: weight_to_decimal(self.weight, decimals)?,
+        })
+    }
+}
+
+/// Interprets an integer weight as a fixed-point number with `decimals` decimals
+pub fn weight_to_decimal(weight: u64, decimals: u32) -> StdResult<Decimal> {
+    Decimal::from_atomics(weight, decimals).map_err(|e| StdError::generic_err(e.to_string()))
+}
+
+/// Converts a fractional weight into an integer weight with `decimals` decimals.
+/// Fails if it doesn't fit or has more decimal places, so tallies never drift from rounding.
+pub fn decimal_to_weight(weight: Decimal, decimals: u32) -> StdResult<u64> {
+    if decimals > MAX_WEIGHT_DECIMALS {
+        return Err(StdError::generic_err(format!(
+            "Weight decimals cannot exceed {}",
+            MAX_WEIGHT_DECIMALS
+        )));
+    }
+    let scale = 10u128.pow(MAX_WEIGHT_DECIMALS - decimals);
+    let atomics = weight.atomics().u128();
+    let (whole, fraction) = (atomics / scale, atomics % scale);
+    if fraction != 0 {
+        return Err(StdError::generic_err(format!(
+            "Weight {} has more than {} decimal places",
+            weight, decimals
+        )));
+    }
+    u64::try_from(whole)
+        .map_err(|_| StdError::generic_err(format!("Weight {} is too large", weight)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::str::FromStr;
+
+    #[test]
+    fn conversions_are_exact() {
+        let weight = Decimal::from_str("1.25").unwrap();
+        assert_eq!(decimal_to_weight(weight, 2).unwrap(), 125);
+        assert_eq!(decimal_to_weight(weight, 4).unwrap(), 12500);
+        assert_eq!(weight_to_decimal(125, 2).unwrap(), weight);
+
+        // integer compatibility mode
+        assert_eq!(decimal_to_weight(Decimal::percent(700), 0).unwrap(), 7);
+        assert_eq!(weight_to_decimal(7, 0).unwrap(), Decimal::percent(700));
+
+        // no rounding
+        decimal_to_weight(weight, 1).unwrap_err();
+        decimal_to_weight(weight, MAX_WEIGHT_DECIMALS + 1).unwrap_err();
+        decimal_to_weight(Decimal::MAX, 0).unwrap_err();
+
+        let member = DecimalMember {
+            addr: "bob".to_string(),
+            weight,
+        };
+        let raw = member.to_member(3).unwrap();
+        assert_eq!(raw.weight, 1250);
+        assert_eq!(raw.to_decimal(3).unwrap(), member);
+    }
+}
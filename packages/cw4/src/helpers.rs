@@ -5,7 +5,7 @@ use cosmwasm_std::{
 };
 
 use crate::msg::Cw4ExecuteMsg;
-use crate::query::HooksResponse;
+use crate::query::{HooksResponse, WeightDecimalsResponse};
 use crate::{
     AdminResponse, Cw4QueryMsg, Member, MemberListResponse, MemberResponse, MEMBERS_KEY, TOTAL_KEY,
 };
@@ -68,6 +68,13 @@ impl Cw4Contract {
         Ok(res.hooks)
     }
 
+    /// Number of decimal places of the weights, only with the "decimal weights" extension
+    pub fn weight_decimals(&self, querier: &QuerierWrapper) -> StdResult<u32> {
+        let query = self.encode_smart_query(Cw4QueryMsg::WeightDecimals {})?;
+        let res: WeightDecimalsResponse = querier.query(&query)?;
+        Ok(res.decimals)
+    }
+
     /// Read the total weight
     pub fn total_weight(&self, querier: &QuerierWrapper) -> StdResult<u64> {
         Item::new(TOTAL_KEY).query(querier, self.addr())
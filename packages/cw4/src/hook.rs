@@ -23,20 +23,42 @@ impl MemberDiff {
     }
 }
 
+/// TotalWeightDiff shows the total weight of the group before and after
+/// all diffs of a transaction were applied.
+#[cw_serde]
+pub struct TotalWeightDiff {
+    pub old: u64,
+    pub new: u64,
+}
+
+impl TotalWeightDiff {
+    pub fn new(old_total: u64, new_total: u64) -> Self {
+        TotalWeightDiff {
+            old: old_total,
+            new: new_total,
+        }
+    }
+}
+
 /// MemberChangedHookMsg should be de/serialized under `MemberChangedHook()` variant in a ExecuteMsg.
-/// This contains a list of all diffs on the given transaction.
+/// This contains a list of all diffs on the given transaction, along with the change
+/// of the total weight, so receivers don't need to query the group for it.
 #[cw_serde]
 pub struct MemberChangedHookMsg {
     pub diffs: Vec<MemberDiff>,
+    pub total: TotalWeightDiff,
 }
 
 impl MemberChangedHookMsg {
-    pub fn one(diff: MemberDiff) -> Self {
-        MemberChangedHookMsg { diffs: vec![diff] }
+    pub fn one(diff: MemberDiff, total: TotalWeightDiff) -> Self {
+        MemberChangedHookMsg {
+            diffs: vec![diff],
+            total,
+        }
     }
 
-    pub fn new(diffs: Vec<MemberDiff>) -> Self {
-        MemberChangedHookMsg { diffs }
+    pub fn new(diffs: Vec<MemberDiff>, total: TotalWeightDiff) -> Self {
+        MemberChangedHookMsg { diffs, total }
     }
 
     /// serializes the message
@@ -19,16 +19,20 @@ For more information on this specification, please check out the
 [README](https://github.com/CosmWasm/cw-plus/blob/main/packages/cw4/README.md).
 */
 
+mod decimal;
 mod helpers;
 mod hook;
 mod msg;
 mod query;
 
+pub use crate::decimal::{
+    decimal_to_weight, weight_to_decimal, DecimalMember, MAX_WEIGHT_DECIMALS,
+};
 pub use crate::helpers::Cw4Contract;
-pub use crate::hook::{MemberChangedHookMsg, MemberDiff};
+pub use crate::hook::{MemberChangedHookMsg, MemberDiff, TotalWeightDiff};
 pub use crate::msg::Cw4ExecuteMsg;
 pub use crate::query::{
     member_key, AdminResponse, Cw4QueryMsg, HooksResponse, Member, MemberListResponse,
-    MemberResponse, TotalWeightResponse, MEMBERS_CHANGELOG, MEMBERS_CHECKPOINTS, MEMBERS_KEY,
-    TOTAL_KEY, TOTAL_KEY_CHANGELOG, TOTAL_KEY_CHECKPOINTS,
+    MemberResponse, TotalWeightResponse, WeightDecimalsResponse, MEMBERS_CHANGELOG,
+    MEMBERS_CHECKPOINTS, MEMBERS_KEY, TOTAL_KEY, TOTAL_KEY_CHANGELOG, TOTAL_KEY_CHECKPOINTS,
 };
@@ -19,6 +19,8 @@ pub enum Cw4QueryMsg {
     },
     /// Shows all registered hooks. Returns HooksResponse.
     Hooks {},
+    /// Only with "decimal weights" extension. Returns WeightDecimalsResponse
+    WeightDecimals {},
 }
 
 #[cw_serde]
@@ -50,6 +52,13 @@ pub struct TotalWeightResponse {
     pub weight: u64,
 }
 
+/// Number of decimal places of the weights. Groups without the
+/// "decimal weights" extension have integer weights, that is 0.
+#[cw_serde]
+pub struct WeightDecimalsResponse {
+    pub decimals: u32,
+}
+
 #[cw_serde]
 pub struct HooksResponse {
     pub hooks: Vec<String>,
@@ -22,7 +22,7 @@ ALL_PACKAGES="controllers cw1 cw3 cw4 cw20"
 CW20_BASE="cw20-base"
 # these are imported by other contracts
 BASE_CONTRACTS="cw1-whitelist cw4-group cw3-fixed-multisig "
-ALL_CONTRACTS="cw1-subkeys cw3-flex-multisig cw4-stake cw20-ics20"
+ALL_CONTRACTS="cw1-subkeys cw3-flex-multisig cw4-stake cw20-ics20 cw20-merkle-airdrop cw20-vault cw20-wrap"
 
 SLEEP_TIME=30
 